@@ -25,11 +25,10 @@ fn main() -> anyhow::Result<()> {
         let name = args.next().ok_or_else(|| {
             anyhow!("Second argument is the name of the branch from which to start iteration, like 'main' or 'master'")
         })?;
-        let packed = repo.refs.packed()?;
         let commit_id = repo
             .refs
-            .find_existing(&name, packed.as_ref())?
-            .peel_to_id_in_place(&repo.refs, packed.as_ref())?
+            .find_existing(&name)?
+            .peel_to_id_in_place(&repo.refs)?
             .to_owned();
         (repo, commit_id)
     };
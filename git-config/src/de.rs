@@ -0,0 +1,575 @@
+//! Deserialization of Rust types from a [`GitConfig`] with [`serde`].
+//!
+//! The fields of the deserialized struct (or the keys of a map) are section
+//! names, and the fields of each section are its keys, both matched
+//! case-insensitively. A section deserialized into a map has its
+//! subsection names as keys if all sections of that name have a
+//! subsection, like `[remote "origin"]`, or its keys otherwise.
+//!
+//! Values are interpreted like `git` does, so booleans may be spelled
+//! `yes` or `off` and integers may have a `k`, `m` or `g` suffix. If a key
+//! has multiple values, sequences receive all of them in order, while all
+//! other types receive the last one.
+
+use crate::{
+    error::{Error, Result},
+    file::GitConfig,
+    parser::parse_from_bytes_owned,
+    typed::{boolean, integer},
+};
+use serde::de::{self, value::SeqDeserializer, DeserializeOwned, IntoDeserializer, MapAccess, Visitor};
+use std::convert::TryFrom;
+
+/// Deserializes an instance of `T` from the sections of `config`.
+///
+/// # Examples
+///
+/// ```
+/// # use git_config::file::GitConfig;
+/// # use std::collections::BTreeMap;
+/// # use std::convert::TryFrom;
+/// # use serde_derive::Deserialize;
+/// #[derive(Deserialize)]
+/// # #[serde(crate = "serde_crate")]
+/// struct Remote {
+///     url: String,
+///     #[serde(default)]
+///     fetch: Vec<String>,
+/// }
+///
+/// #[derive(Deserialize)]
+/// # #[serde(crate = "serde_crate")]
+/// struct Config {
+///     remote: BTreeMap<String, Remote>,
+/// }
+///
+/// let config = GitConfig::try_from(
+///     "[remote \"origin\"]\n  url = https://example.com\n  fetch = a\n  fetch = b",
+/// )
+/// .unwrap();
+/// let config: Config = git_config::from_config(&config).unwrap();
+/// assert_eq!(config.remote["origin"].url, "https://example.com");
+/// assert_eq!(config.remote["origin"].fetch, vec!["a", "b"]);
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if a value couldn't be interpreted as the type it is
+/// deserialized into, or if `T` rejects the configuration.
+pub fn from_config<T: DeserializeOwned>(config: &GitConfig<'_>) -> Result<T> {
+    T::deserialize(&Deserializer::from_config(config))
+}
+
+/// Parses `input` as `git-config` file and deserializes an instance of `T`
+/// from it, like [`from_config`].
+///
+/// # Errors
+///
+/// Returns an error if `input` couldn't be parsed or if deserialization
+/// failed.
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T> {
+    let config = GitConfig::from(parse_from_bytes_owned(input.as_bytes()).map_err(Error::Parse)?);
+    from_config(&config)
+}
+
+/// A deserializer for the sections of a [`GitConfig`].
+///
+/// Values are normalized and unescaped when the deserializer is created, so
+/// it doesn't borrow from the config it was created from.
+pub struct Deserializer {
+    sections: Vec<Section>,
+}
+
+/// A section with its values normalized and unescaped, and multiple values of
+/// the same key grouped together. Values of keys without `=` are `None`.
+struct Section {
+    name: String,
+    subsection: Option<String>,
+    entries: Vec<(String, Vec<Option<Vec<u8>>>)>,
+}
+
+impl Deserializer {
+    /// Creates a deserializer for the sections of `config`.
+    #[must_use]
+    pub fn from_config(config: &GitConfig<'_>) -> Self {
+        let sections = config
+            .sections()
+            .map(|(header, body)| {
+                let mut keys = Vec::new();
+                for key in body.keys() {
+                    if !keys.contains(&key) {
+                        keys.push(key);
+                    }
+                }
+                Section {
+                    name: header.name.0.to_string(),
+                    subsection: header.subsection_name.as_ref().map(ToString::to_string),
+                    entries: keys
+                        .into_iter()
+                        .map(|key| {
+                            let values = body
+                                .values(key)
+                                .iter()
+                                .zip(body.implicit_values(key))
+                                .map(|(value, is_implicit)| (!is_implicit).then(|| unescape(value)))
+                                .collect();
+                            (key.0.to_string(), values)
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        Self { sections }
+    }
+
+    fn sections_by_name<'a>(&'a self, fields: &[&'static str]) -> Vec<(String, SectionsDeserializer<'a>)> {
+        let mut by_name: Vec<(String, SectionsDeserializer<'a>)> = Vec::new();
+        for section in &self.sections {
+            match by_name
+                .iter_mut()
+                .find(|(_, sections)| sections.name.eq_ignore_ascii_case(&section.name))
+            {
+                Some((_, sections)) => sections.sections.push(section),
+                None => by_name.push((
+                    field_name(fields, &section.name),
+                    SectionsDeserializer {
+                        name: &section.name,
+                        sections: vec![section],
+                    },
+                )),
+            }
+        }
+        by_name
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &Deserializer {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(Entries::new(self.sections_by_name(&[])))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_map(Entries::new(self.sections_by_name(fields)))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct enum identifier ignored_any
+    }
+}
+
+/// All sections of the same name.
+struct SectionsDeserializer<'a> {
+    name: &'a str,
+    sections: Vec<&'a Section>,
+}
+
+impl<'a> SectionsDeserializer<'a> {
+    /// The merged body of all sections without subsection.
+    fn body(&self) -> BodyDeserializer<'a> {
+        BodyDeserializer::new(
+            self.name.to_owned(),
+            self.sections
+                .iter()
+                .copied()
+                .filter(|section| section.subsection.is_none()),
+        )
+    }
+
+    fn subsections(&self) -> Vec<(String, BodyDeserializer<'a>)> {
+        let mut subsections: Vec<(String, Vec<&'a Section>)> = Vec::new();
+        for section in &self.sections {
+            let subsection = section.subsection.as_deref().unwrap_or_default();
+            match subsections.iter_mut().find(|(name, _)| name == subsection) {
+                Some((_, sections)) => sections.push(section),
+                None => subsections.push((subsection.to_owned(), vec![section])),
+            }
+        }
+        subsections
+            .into_iter()
+            .map(|(subsection, sections)| {
+                let prefix = format!("{}.{}", self.name, subsection);
+                (subsection, BodyDeserializer::new(prefix, sections.into_iter()))
+            })
+            .collect()
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for SectionsDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        if self.sections.iter().all(|section| section.subsection.is_some()) {
+            visitor.visit_map(Entries::new(self.subsections()))
+        } else {
+            self.body().deserialize_map(visitor)
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.body().deserialize_struct(name, fields, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct enum identifier ignored_any
+    }
+}
+
+/// The merged keys of one or more sections with the same name and subsection.
+struct BodyDeserializer<'a> {
+    prefix: String,
+    entries: Vec<(&'a str, Vec<Option<&'a [u8]>>)>,
+}
+
+impl<'a> BodyDeserializer<'a> {
+    fn new(prefix: String, sections: impl Iterator<Item = &'a Section>) -> Self {
+        let mut entries: Vec<(&'a str, Vec<Option<&'a [u8]>>)> = Vec::new();
+        for (key, values) in sections.flat_map(|section| section.entries.iter()) {
+            let values = values.iter().map(Option::as_deref);
+            match entries
+                .iter_mut()
+                .find(|(existing, _)| existing.eq_ignore_ascii_case(key))
+            {
+                Some((_, existing)) => existing.extend(values),
+                None => entries.push((key, values.collect())),
+            }
+        }
+        Self { prefix, entries }
+    }
+
+    fn values(
+        self,
+        fields: &'static [&'static str],
+    ) -> Entries<impl Iterator<Item = (String, ValueDeserializer<'a>)>, ValueDeserializer<'a>> {
+        let prefix = self.prefix;
+        Entries::new(self.entries.into_iter().map(move |(key, values)| {
+            let value = ValueDeserializer {
+                key: format!("{}.{}", prefix, key),
+                values,
+            };
+            (field_name(fields, key), value)
+        }))
+    }
+}
+
+impl<'de, 'a> de::Deserializer<'de> for BodyDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_map(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_map(self.values(&[]))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_map(self.values(fields))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct enum identifier ignored_any
+    }
+}
+
+/// All values of a key, of which scalars use the last one, with `None` for
+/// keys without `=`.
+struct ValueDeserializer<'a> {
+    key: String,
+    values: Vec<Option<&'a [u8]>>,
+}
+
+impl<'a> ValueDeserializer<'a> {
+    fn last(&self) -> &'a [u8] {
+        self.values.last().copied().flatten().unwrap_or_default()
+    }
+
+    /// Returns true if the last value has no `=`, which makes it `true`
+    /// instead of empty.
+    fn is_implicit(&self) -> bool {
+        matches!(self.values.last(), Some(None))
+    }
+
+    fn string(&self) -> Result<String> {
+        std::str::from_utf8(self.last())
+            .map(ToOwned::to_owned)
+            .map_err(|_| self.invalid("valid UTF-8"))
+    }
+
+    fn invalid(&self, expected: &'static str) -> Error {
+        Error::InvalidValue {
+            key: self.key.clone(),
+            value: String::from_utf8_lossy(self.last()).into_owned(),
+            expected,
+        }
+    }
+}
+
+impl<'de, 'a> IntoDeserializer<'de, Error> for ValueDeserializer<'a> {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($($method:ident => $visit:ident($ty:ty)),*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+                let value = integer(self.last())
+                    .and_then(|value| <$ty>::try_from(value).ok())
+                    .ok_or_else(|| self.invalid(concat!("an integer that fits into ", stringify!($ty))))?;
+                visitor.$visit(value)
+            }
+        )*
+    };
+}
+
+impl<'de, 'a> de::Deserializer<'de> for ValueDeserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match std::str::from_utf8(self.last()) {
+            Ok(value) => visitor.visit_string(value.to_owned()),
+            Err(_) => visitor.visit_byte_buf(self.last().to_vec()),
+        }
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let value = if self.is_implicit() {
+            true
+        } else {
+            boolean(self.last()).ok_or_else(|| self.invalid("a boolean"))?
+        };
+        visitor.visit_bool(value)
+    }
+
+    deserialize_integer! {
+        deserialize_i8 => visit_i8(i8),
+        deserialize_i16 => visit_i16(i16),
+        deserialize_i32 => visit_i32(i32),
+        deserialize_i64 => visit_i64(i64),
+        deserialize_u8 => visit_u8(u8),
+        deserialize_u16 => visit_u16(u16),
+        deserialize_u32 => visit_u32(u32),
+        deserialize_u64 => visit_u64(u64)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_f64(visitor)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let value = std::str::from_utf8(self.last())
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| self.invalid("a floating point number"))?;
+        visitor.visit_f64(value)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let value = self.string()?;
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(self.invalid("a single character")),
+        }
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_string(self.string()?)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_str(visitor)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_byte_buf(self.last().to_vec())
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        let key = self.key;
+        visitor.visit_seq(SeqDeserializer::new(self.values.into_iter().map(|value| {
+            ValueDeserializer {
+                key: key.clone(),
+                values: vec![value],
+            }
+        })))
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value> {
+        self.deserialize_seq(visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_enum(self.string()?.into_deserializer())
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 map struct identifier
+    }
+}
+
+/// Named values, like sections by name or values by key.
+struct Entries<I, V> {
+    iter: I,
+    value: Option<V>,
+}
+
+impl<I, V> Entries<I, V> {
+    fn new(iter: impl IntoIterator<IntoIter = I, Item = (String, V)>) -> Self {
+        Self {
+            iter: iter.into_iter(),
+            value: None,
+        }
+    }
+}
+
+impl<'de, I, V> MapAccess<'de> for Entries<I, V>
+where
+    I: Iterator<Item = (String, V)>,
+    V: de::Deserializer<'de, Error = Error>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K: de::DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<S: de::DeserializeSeed<'de>>(&mut self, seed: S) -> Result<S::Value> {
+        seed.deserialize(
+            self.value
+                .take()
+                .expect("next_key_seed() is called before next_value_seed()"),
+        )
+    }
+}
+
+/// Returns the field of `fields` that matches `name` case-insensitively, or
+/// `name` if there is none.
+fn field_name(fields: &[&'static str], name: &str) -> String {
+    fields
+        .iter()
+        .find(|field| field.eq_ignore_ascii_case(name))
+        .map_or_else(|| name.to_owned(), |field| (*field).to_owned())
+}
+
+/// Resolves the escape sequences `\\`, `\n`, `\t` and `\b` in a normalized
+/// value, which already had its quotes removed and `\"` unescaped.
+fn unescape(value: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(value.len());
+    let mut bytes = value.iter().copied();
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            out.push(byte);
+            continue;
+        }
+        match bytes.next() {
+            Some(b'n') => out.push(b'\n'),
+            Some(b't') => out.push(b'\t'),
+            Some(b'b') => {
+                out.pop();
+            }
+            Some(other) => out.push(other),
+            None => out.push(b'\\'),
+        }
+    }
+    out
+}
@@ -0,0 +1,63 @@
+//! The error type of the [`serde`] integration.
+
+use std::fmt::Display;
+
+/// A specialized [`Result`](std::result::Result) for (de)serializing
+/// configuration with [`serde`].
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The error returned when (de)serializing configuration with [`serde`].
+#[derive(Debug)]
+pub enum Error {
+    /// The input could not be parsed as `git-config` file.
+    Parse(crate::parser::Error<'static>),
+    /// The value of the given key couldn't be interpreted as the expected type.
+    InvalidValue {
+        /// The key of the value, like `core.bare`.
+        key: String,
+        /// The value as it appeared in the configuration, after normalization.
+        value: String,
+        /// A description of what was expected instead.
+        expected: &'static str,
+    },
+    /// A type that has no representation in `git-config` files was
+    /// (de)serialized.
+    Unsupported(String),
+    /// A custom error raised by an implementation of `Serialize` or
+    /// `Deserialize`.
+    Message(String),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Parse(err) => write!(f, "{}", err),
+            Self::InvalidValue { key, value, expected } => {
+                write!(f, "Invalid value '{}' of '{}': expected {}.", value, key, expected)
+            }
+            Self::Unsupported(what) => write!(f, "{}", what),
+            Self::Message(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl serde::de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::Message(msg.to_string())
+    }
+}
+
+impl serde::ser::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self::Message(msg.to_string())
+    }
+}
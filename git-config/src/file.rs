@@ -9,6 +9,10 @@ use std::collections::{HashMap, VecDeque};
 use std::convert::TryFrom;
 use std::fmt::Display;
 use std::ops::{Deref, DerefMut, Range};
+use std::path::{Path, PathBuf};
+
+pub mod include;
+pub mod persist;
 
 /// All possible error types that may occur from interacting with [`GitConfig`].
 #[derive(PartialEq, Eq, Hash, Clone, PartialOrd, Ord, Debug)]
@@ -332,6 +336,28 @@ impl<'event> SectionBody<'event> {
         values
     }
 
+    /// Returns, for each value of the provided key name in order, whether it
+    /// was defined without `=`, which makes it an implicit boolean `true` as
+    /// opposed to an empty value.
+    pub(crate) fn implicit_values(&self, key: &Key) -> Vec<bool> {
+        let mut implicit = Vec::new();
+        let mut found_key = false;
+        for event in &self.0 {
+            match event {
+                Event::Key(event_key) if event_key == key => {
+                    found_key = true;
+                    implicit.push(true);
+                }
+                Event::KeyValueSeparator if found_key => {
+                    *implicit.last_mut().expect("pushed with key") = false;
+                }
+                Event::Value(_) | Event::ValueNotDone(_) if found_key => found_key = false,
+                _ => (),
+            }
+        }
+        implicit
+    }
+
     /// Retrieves all values that have the provided key name. This may return
     /// an empty vec, which implies there was values with the provided key.
     ///
@@ -512,6 +538,21 @@ pub struct GitConfig<'event> {
     section_id_counter: usize,
     /// Section order for output ordering.
     section_order: VecDeque<SectionId>,
+    /// Files included by `include` and `includeIf` sections, in the order
+    /// they were included.
+    includes: Vec<Include<'event>>,
+    /// The content of each file as it was read from disk, to detect changes
+    /// made by others before writing it back.
+    contents: persist::Contents,
+}
+
+/// A file included into a [`GitConfig`], along with the sections it
+/// contributed.
+#[derive(PartialEq, Eq, Clone, Debug)]
+struct Include<'event> {
+    path: PathBuf,
+    frontmatter_events: SectionBody<'event>,
+    sections: Vec<SectionId>,
 }
 
 impl<'event> GitConfig<'event> {
@@ -722,6 +763,81 @@ impl<'event> GitConfig<'event> {
             .collect()
     }
 
+    /// Gets all sections that match the provided name along with their
+    /// headers, in the order they appear in the config. Unlike
+    /// [`Self::sections_by_name`], this makes the subsection name of each
+    /// section available.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use git_config::file::GitConfig;
+    /// # use std::convert::TryFrom;
+    /// let config = r#"
+    ///     [submodule "a"]
+    ///         path = a
+    ///     [core]
+    ///         bare = false
+    ///     [submodule "b"]
+    ///         path = b
+    /// "#;
+    /// let git_config = GitConfig::try_from(config).unwrap();
+    /// let names: Vec<_> = git_config
+    ///     .sections_by_name_with_header("submodule")
+    ///     .into_iter()
+    ///     .map(|(header, _body)| header.subsection_name.as_deref())
+    ///     .collect();
+    /// assert_eq!(names, vec![Some("a"), Some("b")]);
+    /// ```
+    #[must_use]
+    pub fn sections_by_name_with_header<'lookup>(
+        &self,
+        section_name: &'lookup str,
+    ) -> Vec<(&ParsedSectionHeader<'event>, &SectionBody<'event>)> {
+        let section_name = SectionHeaderName::from(section_name);
+        self.section_order
+            .iter()
+            .filter_map(|id| {
+                let header = self
+                    .section_headers
+                    .get(id)
+                    .expect("section doesn't have a header for its id");
+                (header.name == section_name).then(|| {
+                    (
+                        header,
+                        self.sections.get(id).expect("section doesn't have id from from lookup"),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Returns an iterator over all sections along with their headers, in the
+    /// order they appear in the config.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use git_config::file::GitConfig;
+    /// # use std::convert::TryFrom;
+    /// let git_config = GitConfig::try_from("[core]\n[remote \"origin\"]").unwrap();
+    /// let names: Vec<_> = git_config
+    ///     .sections()
+    ///     .map(|(header, _body)| (header.name.0.as_ref(), header.subsection_name.as_deref()))
+    ///     .collect();
+    /// assert_eq!(names, vec![("core", None), ("remote", Some("origin"))]);
+    /// ```
+    pub fn sections(&self) -> impl Iterator<Item = (&ParsedSectionHeader<'event>, &SectionBody<'event>)> + '_ {
+        self.section_order.iter().map(move |id| {
+            (
+                self.section_headers
+                    .get(id)
+                    .expect("section doesn't have a header for its id"),
+                self.sections.get(id).expect("section doesn't have id from from lookup"),
+            )
+        })
+    }
+
     /// Adds a new section to config. If a subsection name was provided, then
     /// the generated header will use the modern subsection syntax. Returns a
     /// reference to the new section for immediate editing.
@@ -856,20 +972,123 @@ impl<'event> GitConfig<'event> {
         new_subsection_name: impl Into<Option<Cow<'event, str>>>,
     ) -> Result<(), GitConfigError<'lookup>> {
         let id = self.get_section_ids_by_name_and_subname(section_name, subsection_name.into())?;
-        let id = id
+        let id = *id
             .last()
             .expect("list of sections were empty, which violates invariant");
         let header = self
             .section_headers
-            .get_mut(id)
-            .expect("sections does not have section id from section ids");
-        header.name = new_section_name.into();
-        header.subsection_name = new_subsection_name.into();
+            .get(&id)
+            .expect("sections does not have section id from section ids")
+            .clone();
+        self.remove_from_lookup_tree(&header, id);
+
+        let subsection_name = new_subsection_name.into();
+        let header = ParsedSectionHeader {
+            name: new_section_name.into(),
+            separator: subsection_name.is_some().then(|| " ".into()),
+            subsection_name,
+        };
+        self.insert_into_lookup_tree(&header, id);
+        self.section_headers.insert(id, header);
 
         Ok(())
     }
 }
 
+/// # Included files
+///
+/// Files included with [`GitConfig::from_path_with_includes`] are part of the
+/// same `GitConfig`, but remember the file each of their sections came from.
+impl<'event> GitConfig<'event> {
+    /// Returns the path of the included file the section to modify when
+    /// changing values of the given section and subsection comes from, or
+    /// `None` if it's from the including file itself.
+    ///
+    /// As later sections override earlier ones, this is the file of the last
+    /// matching section.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if the section and subsection do
+    /// not exist.
+    pub fn section_path<'lookup>(
+        &self,
+        section_name: &'lookup str,
+        subsection_name: Option<&'lookup str>,
+    ) -> Result<Option<&Path>, GitConfigError<'lookup>> {
+        let section_ids = self.get_section_ids_by_name_and_subname(section_name, subsection_name)?;
+        let id = section_ids
+            .last()
+            .expect("list of sections were empty, which violates invariant");
+        Ok(self
+            .includes
+            .iter()
+            .find(|include| include.sections.contains(id))
+            .map(|include| include.path.as_path()))
+    }
+
+    /// Returns the paths of all included files, in the order they were first
+    /// included.
+    #[must_use]
+    pub fn include_paths(&self) -> Vec<&Path> {
+        let mut out: Vec<&Path> = Vec::new();
+        for include in &self.includes {
+            if !out.contains(&include.path.as_path()) {
+                out.push(&include.path);
+            }
+        }
+        out
+    }
+
+    /// Serializes only the parts of this config which belong to the file at
+    /// `path`, or to the including file itself if `path` is `None`, so that
+    /// changes can be written back to the files they belong to.
+    ///
+    /// If a file was included multiple times, the sections of its first
+    /// inclusion are used. Sections added after loading belong to the
+    /// including file. Converting the whole `GitConfig` into bytes serializes
+    /// all files as one instead.
+    #[must_use]
+    pub fn to_bytes_of(&self, path: Option<&Path>) -> Vec<u8> {
+        let (frontmatter, sections): (_, Vec<SectionId>) = match path {
+            Some(path) => match self.includes.iter().find(|include| include.path == path) {
+                Some(include) => (&include.frontmatter_events, include.sections.clone()),
+                None => return Vec::new(),
+            },
+            None => (
+                &self.frontmatter_events,
+                self.section_order
+                    .iter()
+                    .filter(|id| !self.includes.iter().any(|include| include.sections.contains(id)))
+                    .copied()
+                    .collect(),
+            ),
+        };
+
+        let mut value = Vec::new();
+        for event in &frontmatter.0 {
+            value.extend(event.to_vec());
+        }
+        for section_id in sections.iter().filter(|id| self.section_order.contains(id)) {
+            value.extend(
+                self.section_headers
+                    .get(section_id)
+                    .expect("section_header does not contain section id from section_order")
+                    .to_vec(),
+            );
+            for event in &self
+                .sections
+                .get(section_id)
+                .expect("sections does not contain section id from section_order")
+                .0
+            {
+                value.extend(event.to_vec());
+            }
+        }
+        value
+    }
+}
+
 /// An intermediate representation of a mutable value obtained from
 /// [`GitConfig`].
 ///
@@ -1291,6 +1510,26 @@ impl<'event> GitConfig<'event> {
         Err(GitConfigError::KeyDoesNotExist)
     }
 
+    /// Returns true if the value returned by [`Self::get_raw_value`] was
+    /// defined without `=`, which makes it an implicit boolean `true` instead
+    /// of an empty value.
+    pub(crate) fn is_implicit_value(&self, section_name: &str, subsection_name: Option<&str>, key: &str) -> bool {
+        let key = Key(key.into());
+        self.get_section_ids_by_name_and_subname(section_name, subsection_name)
+            .ok()
+            .and_then(|ids| {
+                ids.iter().rev().find_map(|section_id| {
+                    self.sections
+                        .get(section_id)
+                        .expect("sections does not have section id from section ids")
+                        .implicit_values(&key)
+                        .last()
+                        .copied()
+                })
+            })
+            .unwrap_or(false)
+    }
+
     /// Returns a mutable reference to an uninterpreted value given a section,
     /// an optional subsection and key.
     ///
@@ -1692,41 +1931,77 @@ impl<'event> GitConfig<'event> {
         let new_section_id = SectionId(self.section_id_counter);
         self.section_headers.insert(new_section_id, header.clone());
         self.sections.insert(new_section_id, section);
-        let lookup = self.section_lookup_tree.entry(header.name).or_default();
-
-        let mut found_node = false;
-        if let Some(subsection_name) = header.subsection_name {
-            for node in lookup.iter_mut() {
-                if let LookupTreeNode::NonTerminal(subsection) = node {
-                    found_node = true;
-                    subsection
-                        // Clones the cow, not the inner borrowed str.
-                        .entry(subsection_name.clone())
-                        .or_default()
-                        .push(new_section_id);
-                    break;
+        self.insert_into_lookup_tree(&header, new_section_id);
+        self.section_order.push_back(new_section_id);
+        self.section_id_counter += 1;
+        self.sections.get_mut(&new_section_id).map(MutableSection::new).unwrap()
+    }
+
+    /// Adds `id` to the lookup tree under the name and subsection name of
+    /// `header`, keeping the ids of each lookup in the order of the sections.
+    fn insert_into_lookup_tree(&mut self, header: &ParsedSectionHeader<'event>, id: SectionId) {
+        let lookup = self.section_lookup_tree.entry(header.name.clone()).or_default();
+        let ids = match &header.subsection_name {
+            Some(subsection_name) => {
+                let position = lookup
+                    .iter()
+                    .position(|node| matches!(node, LookupTreeNode::NonTerminal(_)))
+                    .unwrap_or_else(|| {
+                        lookup.push(LookupTreeNode::NonTerminal(HashMap::new()));
+                        lookup.len() - 1
+                    });
+                match &mut lookup[position] {
+                    // Clones the cow, not the inner borrowed str.
+                    LookupTreeNode::NonTerminal(subsections) => subsections.entry(subsection_name.clone()).or_default(),
+                    LookupTreeNode::Terminal(_) => unreachable!("position points to a non-terminal node"),
                 }
             }
-            if !found_node {
-                let mut map = HashMap::new();
-                map.insert(subsection_name, vec![new_section_id]);
-                lookup.push(LookupTreeNode::NonTerminal(map));
-            }
-        } else {
-            for node in lookup.iter_mut() {
-                if let LookupTreeNode::Terminal(vec) = node {
-                    found_node = true;
-                    vec.push(new_section_id);
-                    break;
+            None => {
+                let position = lookup
+                    .iter()
+                    .position(|node| matches!(node, LookupTreeNode::Terminal(_)))
+                    .unwrap_or_else(|| {
+                        lookup.push(LookupTreeNode::Terminal(Vec::new()));
+                        lookup.len() - 1
+                    });
+                match &mut lookup[position] {
+                    LookupTreeNode::Terminal(ids) => ids,
+                    LookupTreeNode::NonTerminal(_) => unreachable!("position points to a terminal node"),
                 }
             }
-            if !found_node {
-                lookup.push(LookupTreeNode::Terminal(vec![new_section_id]))
+        };
+        let index = ids.binary_search(&id).unwrap_or_else(|index| index);
+        ids.insert(index, id);
+    }
+
+    /// Removes `id` from the lookup tree under the name and subsection name
+    /// of `header`, along with lookups that become empty.
+    fn remove_from_lookup_tree(&mut self, header: &ParsedSectionHeader<'event>, id: SectionId) {
+        let lookup = match self.section_lookup_tree.get_mut(&header.name) {
+            Some(lookup) => lookup,
+            None => return,
+        };
+        for node in lookup.iter_mut() {
+            match (node, &header.subsection_name) {
+                (LookupTreeNode::Terminal(ids), None) => ids.retain(|existing| *existing != id),
+                (LookupTreeNode::NonTerminal(subsections), Some(subsection_name)) => {
+                    if let Some(ids) = subsections.get_mut(subsection_name) {
+                        ids.retain(|existing| *existing != id);
+                        if ids.is_empty() {
+                            subsections.remove(subsection_name);
+                        }
+                    }
+                }
+                _ => {}
             }
         }
-        self.section_order.push_back(new_section_id);
-        self.section_id_counter += 1;
-        self.sections.get_mut(&new_section_id).map(MutableSection::new).unwrap()
+        lookup.retain(|node| match node {
+            LookupTreeNode::Terminal(ids) => !ids.is_empty(),
+            LookupTreeNode::NonTerminal(subsections) => !subsections.is_empty(),
+        });
+        if lookup.is_empty() {
+            self.section_lookup_tree.remove(&header.name);
+        }
     }
 
     /// Returns the mapping between section and subsection name to section ids.
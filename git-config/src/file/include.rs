@@ -0,0 +1,334 @@
+//! Resolution of `include` and `includeIf` sections, which pull the sections
+//! of other files into a [`GitConfig`].
+//!
+//! Included files are [interpolated][crate::values::Path::interpolate()]
+//! relative to the directory of the including file. The supported
+//! conditions of `includeIf` sections are `gitdir:`, `gitdir/i:`,
+//! `onbranch:` and `hasconfig:remote.*.url:`.
+
+use super::{GitConfig, Include, SectionBody, SectionId};
+use crate::parser::{parse_from_bytes_owned, Key, ParsedSectionHeader};
+use crate::values::normalize_cow;
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+
+/// The maximum depth of nested includes, which is the same as in `git`.
+pub const MAX_DEPTH: usize = 10;
+
+/// The information needed to evaluate the conditions of `includeIf` sections.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Options {
+    /// The git directory of the repository the configuration is read for,
+    /// used by `gitdir:` conditions, which never match if it's `None`.
+    pub git_dir: Option<PathBuf>,
+    /// The name of the branch checked out in the repository, with or without
+    /// `refs/heads/` prefix, used by `onbranch:` conditions, which never
+    /// match if it's `None`.
+    pub branch_name: Option<String>,
+    /// The directory `~/` refers to in paths and conditions, or `None` to use
+    /// the home directory of the current user.
+    pub home_dir: Option<PathBuf>,
+    /// The installation directory of `git`, which `%(prefix)/` refers to in
+    /// paths. Paths using it are ignored if it's `None`.
+    pub git_install_dir: Option<PathBuf>,
+    /// The maximum depth of nested includes, with the including file being
+    /// at depth zero.
+    pub max_depth: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            git_dir: None,
+            branch_name: None,
+            home_dir: None,
+            git_install_dir: None,
+            max_depth: MAX_DEPTH,
+        }
+    }
+}
+
+/// The error returned by [`GitConfig::from_path_with_includes`].
+#[derive(Debug)]
+pub enum Error {
+    /// The file at the given path could not be read.
+    Io(PathBuf, std::io::Error),
+    /// The file at the given path could not be parsed.
+    Parse(PathBuf, crate::parser::Error<'static>),
+    /// The file at the given path would be included deeper than the maximum
+    /// depth.
+    DepthExceeded(PathBuf, usize),
+    /// The file at the given path includes itself, directly or indirectly.
+    Cycle(PathBuf),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(path, _) => write!(f, "Could not read '{}'.", path.display()),
+            Self::Parse(path, err) => write!(f, "Could not parse '{}': {}", path.display(), err),
+            Self::DepthExceeded(path, max) => write!(
+                f,
+                "Including '{}' exceeds the maximum include depth of {}.",
+                path.display(),
+                max
+            ),
+            Self::Cycle(path) => write!(f, "'{}' includes itself.", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(_, err) => Some(err),
+            Self::Parse(_, err) => Some(err),
+            Self::DepthExceeded(..) | Self::Cycle(_) => None,
+        }
+    }
+}
+
+impl GitConfig<'static> {
+    /// Reads the file at `path` along with all files it includes through
+    /// `include.path` and the `path` of `includeIf` sections whose condition
+    /// matches, recursively.
+    ///
+    /// The sections of included files follow the section including them, so
+    /// they override the values preceding it like in `git`. Included files
+    /// that don't exist are ignored. Use [`section_path`] to learn which file
+    /// a section came from.
+    ///
+    /// `hasconfig:remote.*.url:` conditions are evaluated against the remote
+    /// urls of the resulting configuration, excluding the files included
+    /// through these conditions. Use [`fs::Config::push_file`] to evaluate
+    /// them against the remote urls of all levels instead, like `git` does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any file couldn't be read or parsed, or if includes
+    /// are nested too deeply or form a cycle.
+    ///
+    /// [`section_path`]: Self::section_path
+    /// [`fs::Config::push_file`]: crate::fs::Config::push_file
+    pub fn from_path_with_includes(path: impl AsRef<Path>, options: &Options) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let config = Self::from_path_with_remote_urls(path, options, None)?;
+        if !config.has_hasconfig_conditions() {
+            return Ok(config);
+        }
+        let remote_urls = config.remote_urls();
+        Self::from_path_with_remote_urls(path, options, Some(remote_urls))
+    }
+
+    /// Like [`from_path_with_includes`][Self::from_path_with_includes()], but
+    /// evaluates `hasconfig:remote.*.url:` conditions against `remote_urls`,
+    /// or lets them never match if `None`.
+    pub(crate) fn from_path_with_remote_urls(
+        path: &Path,
+        options: &Options,
+        remote_urls: Option<Vec<Vec<u8>>>,
+    ) -> Result<Self, Error> {
+        Resolver::new(options, remote_urls).load(path)
+    }
+
+    pub(crate) fn has_hasconfig_conditions(&self) -> bool {
+        self.sections_by_name_with_header("includeIf")
+            .iter()
+            .any(|(header, _)| {
+                header
+                    .subsection_name
+                    .as_deref()
+                    .map_or(false, |condition| condition.starts_with("hasconfig:"))
+            })
+    }
+
+    pub(crate) fn remote_urls(&self) -> Vec<Vec<u8>> {
+        self.sections_by_name_with_header("remote")
+            .into_iter()
+            .filter(|(header, _)| header.subsection_name.is_some())
+            .flat_map(|(_, body)| body.values(&Key("url".into())))
+            .map(|url| normalize_cow(url).into_owned())
+            .collect()
+    }
+}
+
+struct Resolver<'a> {
+    options: &'a Options,
+    /// The remote urls to evaluate `hasconfig:` conditions against, or `None`
+    /// if these conditions never match.
+    remote_urls: Option<Vec<Vec<u8>>>,
+    /// The canonical paths of the files currently being read, to detect cycles.
+    stack: Vec<PathBuf>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(options: &'a Options, remote_urls: Option<Vec<Vec<u8>>>) -> Self {
+        Resolver {
+            options,
+            remote_urls,
+            stack: Vec::new(),
+        }
+    }
+
+    fn load(&mut self, path: &Path) -> Result<GitConfig<'static>, Error> {
+        let mut config = GitConfig::new();
+        self.stack.push(canonicalize(path));
+        self.read_into(&mut config, path, None)?;
+        Ok(config)
+    }
+
+    /// Read the file at `path` into `config`, as file included by the section
+    /// preceding its sections if `include` is set.
+    fn read_into(&mut self, config: &mut GitConfig<'static>, path: &Path, include: Option<usize>) -> Result<(), Error> {
+        let bytes = std::fs::read(path).map_err(|err| Error::Io(path.to_owned(), err))?;
+        config.contents.insert(path, &bytes);
+        let mut parser = parse_from_bytes_owned(&bytes).map_err(|err| Error::Parse(path.to_owned(), err))?;
+        let frontmatter = SectionBody(parser.take_frontmatter());
+        match include {
+            Some(index) => config.includes[index].frontmatter_events = frontmatter,
+            None => config.frontmatter_events = frontmatter,
+        }
+
+        for section in parser.take_sections() {
+            let paths = self.included_paths(&section.section_header, &section.events, path);
+            config.push_section_internal(section.section_header, SectionBody(section.events));
+            if let Some(index) = include {
+                config.includes[index]
+                    .sections
+                    .push(SectionId(config.section_id_counter - 1));
+            }
+
+            for included_path in paths {
+                if !included_path.is_file() {
+                    continue;
+                }
+                let canonical = canonicalize(&included_path);
+                if self.stack.contains(&canonical) {
+                    return Err(Error::Cycle(included_path));
+                }
+                if self.stack.len() > self.options.max_depth {
+                    return Err(Error::DepthExceeded(included_path, self.options.max_depth));
+                }
+                config.includes.push(Include {
+                    path: included_path.clone(),
+                    frontmatter_events: SectionBody::new(),
+                    sections: Vec::new(),
+                });
+                let index = config.includes.len() - 1;
+                self.stack.push(canonical);
+                self.read_into(config, &included_path, Some(index))?;
+                self.stack.pop();
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the paths of the files included by the section with `header` and
+    /// `events`, which was read from the file at `path`.
+    fn included_paths(
+        &self,
+        header: &ParsedSectionHeader<'_>,
+        events: &[crate::parser::Event<'static>],
+        path: &Path,
+    ) -> Vec<PathBuf> {
+        let is_included = match (
+            header.name.0.to_ascii_lowercase().as_str(),
+            header.subsection_name.as_deref(),
+        ) {
+            ("include", None) => true,
+            ("includeif", Some(condition)) => self.matches(condition, path),
+            _ => false,
+        };
+        if !is_included {
+            return Vec::new();
+        }
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        SectionBody(events.to_vec())
+            .values(&Key("path".into()))
+            .into_iter()
+            .map(normalize_cow)
+            .filter(|value| !value.is_empty())
+            .filter_map(|value| self.resolve(&value, base))
+            .collect()
+    }
+
+    fn matches(&self, condition: &str, path: &Path) -> bool {
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        if let Some(pattern) = condition.strip_prefix("gitdir:") {
+            self.matches_git_dir(pattern, base, git_glob::wildmatch::Mode::empty())
+        } else if let Some(pattern) = condition.strip_prefix("gitdir/i:") {
+            self.matches_git_dir(pattern, base, git_glob::wildmatch::Mode::IGNORE_CASE)
+        } else if let Some(pattern) = condition.strip_prefix("onbranch:") {
+            let branch = match &self.options.branch_name {
+                Some(branch) => branch.strip_prefix("refs/heads/").unwrap_or(branch),
+                None => return false,
+            };
+            let mut pattern = pattern.to_owned();
+            if pattern.ends_with('/') {
+                pattern.push_str("**");
+            }
+            wildmatch(&pattern, branch.as_bytes(), git_glob::wildmatch::Mode::empty())
+        } else if let Some(pattern) = condition.strip_prefix("hasconfig:remote.*.url:") {
+            self.remote_urls.as_ref().map_or(false, |urls| {
+                urls.iter()
+                    .any(|url| wildmatch(pattern, url, git_glob::wildmatch::Mode::empty()))
+            })
+        } else {
+            false
+        }
+    }
+
+    fn matches_git_dir(&self, pattern: &str, base: &Path, mode: git_glob::wildmatch::Mode) -> bool {
+        let git_dir = match &self.options.git_dir {
+            Some(git_dir) => git_dir,
+            None => return false,
+        };
+        let mut pattern = if let Some(relative) = pattern.strip_prefix("./") {
+            base.join(relative).to_string_lossy().into_owned()
+        } else if pattern.starts_with("~/") {
+            match self.resolve(pattern.as_bytes(), base) {
+                Some(path) => path.to_string_lossy().into_owned(),
+                None => return false,
+            }
+        } else if pattern.starts_with('/') || pattern.starts_with("**/") {
+            pattern.to_owned()
+        } else {
+            format!("**/{}", pattern)
+        };
+        if pattern.ends_with('/') {
+            pattern.push_str("**");
+        }
+        let canonical = canonicalize(git_dir);
+        [git_dir.as_path(), canonical.as_path()]
+            .iter()
+            .any(|git_dir| wildmatch(&pattern, git_dir.to_string_lossy().as_bytes(), mode))
+    }
+
+    /// Resolve `value` relative to `base`, expanding a leading `~/` to the
+    /// home directory.
+    fn resolve(&self, value: &[u8], base: &Path) -> Option<PathBuf> {
+        if let (Some(home_dir), Some(relative)) = (&self.options.home_dir, value.strip_prefix(b"~/")) {
+            return std::str::from_utf8(relative)
+                .ok()
+                .map(|relative| home_dir.join(relative));
+        }
+        crate::values::Path::from(Cow::Borrowed(value))
+            .interpolate(self.options.git_install_dir.as_deref(), Some(base))
+            .ok()
+            .map(Cow::into_owned)
+    }
+}
+
+fn wildmatch(pattern: &str, value: &[u8], mode: git_glob::wildmatch::Mode) -> bool {
+    use bstr::ByteSlice;
+    git_glob::wildmatch(
+        pattern.as_bytes().as_bstr(),
+        value.as_bstr(),
+        mode | git_glob::wildmatch::Mode::NO_MATCH_SLASH_LITERAL,
+    )
+}
+
+fn canonicalize(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_owned())
+}
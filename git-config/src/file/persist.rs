@@ -0,0 +1,166 @@
+//! Reading a [`GitConfig`] from disk and writing it back to the files it was
+//! read from.
+//!
+//! Files are written like `git` does, by writing their new content into a
+//! `.lock` file next to them, which atomically replaces the original file
+//! once all content was written. As the event stream of a [`GitConfig`] is
+//! serialized losslessly, comments and whitespace are preserved.
+
+use super::GitConfig;
+use crate::parser::parse_from_bytes_owned;
+use std::fmt::Display;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub use git_lock::acquire::Fail;
+
+/// The error returned by [`GitConfig::open`] and [`GitConfig::write_to_path`].
+#[derive(Debug)]
+pub enum Error {
+    /// The file at the given path could not be read.
+    Read(PathBuf, std::io::Error),
+    /// The file at the given path could not be parsed.
+    Parse(PathBuf, crate::parser::Error<'static>),
+    /// The lock for a file could not be obtained.
+    Lock(git_lock::acquire::Error),
+    /// The file at the given path could not be written.
+    Write(PathBuf, std::io::Error),
+    /// The file at the given path was changed by someone else since it was read.
+    Changed(PathBuf),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Read(path, _) => write!(f, "Could not read '{}'.", path.display()),
+            Self::Parse(path, err) => write!(f, "Could not parse '{}': {}", path.display(), err),
+            Self::Lock(err) => write!(f, "{}", err),
+            Self::Write(path, _) => write!(f, "Could not write '{}'.", path.display()),
+            Self::Changed(path) => write!(f, "'{}' was changed since it was read.", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Read(_, err) | Self::Write(_, err) => Some(err),
+            Self::Parse(_, err) => Some(err),
+            Self::Lock(err) => Some(err),
+            Self::Changed(_) => None,
+        }
+    }
+}
+
+impl GitConfig<'static> {
+    /// Reads and parses the file at `path`, without resolving the files it
+    /// includes. Use [`from_path_with_includes`] to resolve them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file couldn't be read or parsed.
+    ///
+    /// [`from_path_with_includes`]: Self::from_path_with_includes
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|err| Error::Read(path.to_owned(), err))?;
+        let mut config = parse_from_bytes_owned(&bytes)
+            .map(Self::from)
+            .map_err(|err| Error::Parse(path.to_owned(), err))?;
+        config.contents.insert(path, &bytes);
+        Ok(config)
+    }
+}
+
+impl GitConfig<'_> {
+    /// Writes this config to the file at `path`, which is typically the one
+    /// it was read from, and the sections of each included file back to the
+    /// file they were read from, as serialized by [`to_bytes_of`]. Files
+    /// whose content wouldn't change aren't written.
+    ///
+    /// All files are locked before any of them is read or changed, with
+    /// `lock_mode` controlling how long to wait for locks held by other
+    /// processes. If writing fails, files that weren't replaced yet remain
+    /// unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a lock couldn't be obtained or a file couldn't be
+    /// written, or if a file was changed on disk since it was read into this
+    /// config, in which case no file is written.
+    ///
+    /// [`to_bytes_of`]: Self::to_bytes_of
+    pub fn write_to_path(&mut self, path: impl AsRef<Path>, lock_mode: Fail) -> Result<(), Error> {
+        let mut files = vec![(path.as_ref().to_owned(), self.to_bytes_of(None))];
+        files.extend(
+            self.include_paths()
+                .into_iter()
+                .map(|include| (include.to_owned(), self.to_bytes_of(Some(include)))),
+        );
+
+        let mut locks = Vec::with_capacity(files.len());
+        for (path, _) in &files {
+            locks.push(git_lock::File::acquire_to_update_resource(path, lock_mode, None).map_err(Error::Lock)?);
+        }
+
+        let mut changes = Vec::new();
+        for ((path, bytes), lock) in files.into_iter().zip(locks) {
+            let current = match std::fs::read(&path) {
+                Ok(current) => Some(current),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                Err(err) => return Err(Error::Read(path, err)),
+            };
+            if let Some(original) = self.contents.get(&path) {
+                if current.as_deref() != Some(original) {
+                    return Err(Error::Changed(path));
+                }
+            }
+            if current.as_ref() != Some(&bytes) {
+                changes.push((path, bytes, lock));
+            }
+        }
+
+        for (path, bytes, lock) in &mut changes {
+            lock.with_mut(|file| file.write_all(bytes))
+                .map_err(|err| Error::Write(path.clone(), err))?;
+        }
+        for (path, bytes, lock) in changes {
+            lock.commit().map_err(|err| Error::Write(path.clone(), err.error))?;
+            self.contents.insert(&path, &bytes);
+        }
+        Ok(())
+    }
+}
+
+/// The content of the files a [`GitConfig`] was read from, as it was when
+/// they were read.
+///
+/// It merely serves to detect changes made by others, which is why all
+/// instances compare equal to not affect the equality of configs.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Contents(Vec<(PathBuf, Vec<u8>)>);
+
+impl Contents {
+    /// Remember `bytes` as the content of the file at `path`.
+    pub(crate) fn insert(&mut self, path: &Path, bytes: &[u8]) {
+        match self.0.iter_mut().find(|(existing, _)| existing == path) {
+            Some((_, content)) => bytes.clone_into(content),
+            None => self.0.push((path.to_owned(), bytes.to_owned())),
+        }
+    }
+
+    fn get(&self, path: &Path) -> Option<&[u8]> {
+        self.0
+            .iter()
+            .find(|(existing, _)| existing == path)
+            .map(|(_, content)| content.as_slice())
+    }
+}
+
+impl PartialEq for Contents {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Contents {}
@@ -0,0 +1,399 @@
+//! This module provides a cascading view over multiple `git-config` files, as
+//! read by `git` from the system, global, repository and worktree levels as
+//! well as from the command-line.
+
+use crate::file::{include, GitConfig, GitConfigError};
+use crate::parser::Key;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// The level a `git-config` file was loaded from, in order of increasing
+/// precedence.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, PartialOrd, Ord, Debug)]
+pub enum Source {
+    /// The system-wide configuration, typically at `/etc/gitconfig`.
+    System,
+    /// The configuration of the current user, at `$XDG_CONFIG_HOME/git/config`
+    /// or `~/.gitconfig`.
+    Global,
+    /// The configuration of a repository, at `$GIT_COMMON_DIR/config`.
+    Repository,
+    /// The configuration of a worktree, at `$GIT_DIR/config.worktree`, which
+    /// is only used if `extensions.worktreeConfig` is enabled.
+    Worktree,
+    /// Values passed on the command-line, like with `git -c section.key=value`.
+    Cli,
+}
+
+impl Source {
+    /// Returns the paths `git` reads the configuration of this level from by
+    /// default, in the order they are read.
+    ///
+    /// Only the [`System`] and [`Global`] levels have default paths, as the
+    /// others depend on the repository. The [`Global`] paths are derived from
+    /// `XDG_CONFIG_HOME` and `HOME`, and are empty if neither is set.
+    ///
+    /// [`System`]: Self::System
+    /// [`Global`]: Self::Global
+    #[must_use]
+    pub fn default_paths(self) -> Vec<PathBuf> {
+        match self {
+            Self::System => vec![PathBuf::from("/etc/gitconfig")],
+            Self::Global => {
+                let home = std::env::var_os("HOME").map(PathBuf::from);
+                let xdg = std::env::var_os("XDG_CONFIG_HOME")
+                    .map(PathBuf::from)
+                    .or_else(|| home.as_ref().map(|home| home.join(".config")))
+                    .map(|config| config.join("git").join("config"));
+                xdg.into_iter()
+                    .chain(home.map(|home| home.join(".gitconfig")))
+                    .collect()
+            }
+            Self::Repository | Self::Worktree | Self::Cli => Vec::new(),
+        }
+    }
+}
+
+/// Where the values of a `git-config` file in a [`Config`] came from.
+#[derive(PartialEq, Eq, Hash, Clone, PartialOrd, Ord, Debug)]
+pub struct Origin {
+    /// The level the file was loaded from.
+    pub source: Source,
+    /// The path of the file, or `None` if it wasn't read from disk, like
+    /// values passed on the command-line.
+    pub path: Option<PathBuf>,
+}
+
+/// The error returned by [`Config::push_cli_values`] if a value isn't of the
+/// form `section[.subsection].key[=value]`.
+#[derive(PartialEq, Eq, Hash, Clone, PartialOrd, Ord, Debug)]
+pub struct InvalidCliValue(pub String);
+
+impl Display for InvalidCliValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "'{}' is not of the form 'section[.subsection].key[=value]'.", self.0)
+    }
+}
+
+impl std::error::Error for InvalidCliValue {}
+
+/// A cascading view over multiple [`GitConfig`] files, each of which is
+/// annotated with its [`Origin`].
+///
+/// Files are kept in order of their [`Source`], and files of the same source
+/// in the order they were added. Like in `git`, values of later files
+/// override the ones of earlier files, while multivars contain the values of
+/// all files.
+///
+/// # Examples
+///
+/// ```
+/// # use git_config::fs::{Config, Source};
+/// # use std::borrow::Cow;
+/// let mut config = Config::new();
+/// config.push_cli_values(Some("core.abbrev=12"))?;
+/// assert_eq!(config.get_raw_value("core", None, "abbrev")?, Cow::<[u8]>::Borrowed(b"12"));
+/// assert_eq!(config.get_raw_value_with_origin("core", None, "abbrev")?.1.source, Source::Cli);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct Config {
+    files: Vec<File>,
+}
+
+#[derive(PartialEq, Eq, Clone, Debug)]
+struct File {
+    origin: Origin,
+    config: GitConfig<'static>,
+    /// The options the file was read with, if it was read from disk.
+    include_options: Option<include::Options>,
+    /// The file before the `hasconfig:` conditions it contains were evaluated,
+    /// which is only set once they were.
+    without_hasconfig: Option<GitConfig<'static>>,
+}
+
+impl File {
+    fn without_hasconfig(&self) -> &GitConfig<'static> {
+        self.without_hasconfig.as_ref().unwrap_or(&self.config)
+    }
+}
+
+impl Config {
+    /// Constructs a configuration without any files.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `file` with the given `origin`, after all files of the same or a
+    /// lower level and before all files of higher levels.
+    pub fn push(&mut self, origin: Origin, file: GitConfig<'static>) {
+        self.insert(File {
+            origin,
+            config: file,
+            include_options: None,
+            without_hasconfig: None,
+        });
+    }
+
+    fn insert(&mut self, file: File) {
+        let index = self
+            .files
+            .iter()
+            .position(|existing| existing.origin.source > file.origin.source)
+            .unwrap_or(self.files.len());
+        self.files.insert(index, file);
+    }
+
+    /// Reads the file at `path` along with the files it includes, and adds
+    /// it as a file of the given `source`, as [`push`] does. Files that don't
+    /// exist are ignored, like `git` does.
+    ///
+    /// The `hasconfig:remote.*.url:` conditions of all files read so far are
+    /// evaluated against the remote urls of all files, as [`resolve_hasconfig_includes`]
+    /// does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file or any of the files it includes exists
+    /// but couldn't be read or parsed.
+    ///
+    /// [`push`]: Self::push
+    /// [`resolve_hasconfig_includes`]: Self::resolve_hasconfig_includes
+    pub fn push_file(
+        &mut self,
+        source: Source,
+        path: impl Into<PathBuf>,
+        options: &include::Options,
+    ) -> Result<(), include::Error> {
+        let path = path.into();
+        let file = match GitConfig::from_path_with_remote_urls(&path, options, None) {
+            Ok(file) => file,
+            Err(include::Error::Io(io_path, err)) if io_path == path && err.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(())
+            }
+            Err(err) => return Err(err),
+        };
+        self.insert(File {
+            origin: Origin {
+                source,
+                path: Some(path),
+            },
+            config: file,
+            include_options: Some(options.clone()),
+            without_hasconfig: None,
+        });
+        self.resolve_hasconfig_includes()
+    }
+
+    /// Evaluates the `hasconfig:remote.*.url:` conditions of the files read
+    /// with [`push_file`] against the remote urls of all files, and re-reads
+    /// the files containing them accordingly.
+    ///
+    /// Like in `git`, files included through these conditions don't
+    /// contribute remote urls. This is done by [`push_file`] already, but has
+    /// to be repeated if remote urls are added otherwise, like with
+    /// [`push_cli_values`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file couldn't be read again.
+    ///
+    /// [`push_file`]: Self::push_file
+    /// [`push_cli_values`]: Self::push_cli_values
+    pub fn resolve_hasconfig_includes(&mut self) -> Result<(), include::Error> {
+        if !self
+            .files
+            .iter()
+            .any(|file| file.without_hasconfig().has_hasconfig_conditions())
+        {
+            return Ok(());
+        }
+        let remote_urls: Vec<_> = self
+            .files
+            .iter()
+            .flat_map(|file| file.without_hasconfig().remote_urls())
+            .collect();
+        for file in &mut self.files {
+            let config = match (&file.origin.path, &file.include_options) {
+                (Some(path), Some(options)) if file.without_hasconfig().has_hasconfig_conditions() => {
+                    GitConfig::from_path_with_remote_urls(path, options, Some(remote_urls.clone()))?
+                }
+                _ => continue,
+            };
+            let without_hasconfig = std::mem::replace(&mut file.config, config);
+            file.without_hasconfig.get_or_insert(without_hasconfig);
+        }
+        Ok(())
+    }
+
+    /// Adds all `values` of the form `section[.subsection].key[=value]` as a
+    /// single file of the [`Cli`] level, like `git -c` does.
+    ///
+    /// Section names and keys are case-insensitive, while subsection names
+    /// are case-sensitive. Keys without `=` are set to `true`. Remote urls
+    /// among the values are only seen by `hasconfig:` conditions after calling
+    /// [`resolve_hasconfig_includes`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value lacks a section name or key, in which case
+    /// no value is added.
+    ///
+    /// [`Cli`]: Source::Cli
+    /// [`resolve_hasconfig_includes`]: Self::resolve_hasconfig_includes
+    pub fn push_cli_values<'a>(&mut self, values: impl IntoIterator<Item = &'a str>) -> Result<(), InvalidCliValue> {
+        let mut file = GitConfig::new();
+        for input in values {
+            let (name, value) = match input.find('=') {
+                Some(pos) => (&input[..pos], &input[pos + 1..]),
+                None => (input, "true"),
+            };
+            let (section, subsection, key) = match (name.find('.'), name.rfind('.')) {
+                (Some(first), Some(last)) if first > 0 && last + 1 < name.len() => (
+                    &name[..first],
+                    (first != last).then(|| &name[first + 1..last]),
+                    &name[last + 1..],
+                ),
+                _ => return Err(InvalidCliValue(input.to_owned())),
+            };
+            file.new_section(section.to_owned(), subsection.map(|s| Cow::Owned(s.to_owned())))
+                .push(Key(key.to_owned().into()), Cow::Owned(value.as_bytes().to_owned()));
+        }
+        self.push(
+            Origin {
+                source: Source::Cli,
+                path: None,
+            },
+            file,
+        );
+        Ok(())
+    }
+
+    /// Returns all files along with their origin, from the lowest to the
+    /// highest precedence.
+    pub fn files(&self) -> impl Iterator<Item = (&Origin, &GitConfig<'static>)> {
+        self.files.iter().map(|file| (&file.origin, &file.config))
+    }
+
+    /// Returns an uninterpreted value given a section, an optional subsection
+    /// and key, taken from the file with the highest precedence that has it.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GitConfigError::KeyDoesNotExist`] if no file has the value.
+    pub fn get_raw_value<'lookup>(
+        &self,
+        section_name: &'lookup str,
+        subsection_name: Option<&'lookup str>,
+        key: &'lookup str,
+    ) -> Result<Cow<'_, [u8]>, GitConfigError<'lookup>> {
+        self.get_raw_value_with_origin(section_name, subsection_name, key)
+            .map(|(value, _)| value)
+    }
+
+    /// Like [`get_raw_value`], but also returns the origin of the file the
+    /// value was taken from.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GitConfigError::KeyDoesNotExist`] if no file has the value.
+    ///
+    /// [`get_raw_value`]: Self::get_raw_value
+    pub fn get_raw_value_with_origin<'lookup>(
+        &self,
+        section_name: &'lookup str,
+        subsection_name: Option<&'lookup str>,
+        key: &'lookup str,
+    ) -> Result<(Cow<'_, [u8]>, &Origin), GitConfigError<'lookup>> {
+        self.files
+            .iter()
+            .rev()
+            .find_map(|file| {
+                file.config
+                    .get_raw_value(section_name, subsection_name, key)
+                    .ok()
+                    .map(|value| (value, &file.origin))
+            })
+            .ok_or(GitConfigError::KeyDoesNotExist)
+    }
+
+    /// Returns true if the value returned by [`get_raw_value`] was defined
+    /// without `=`, which makes it an implicit boolean `true` instead of an
+    /// empty value.
+    ///
+    /// [`get_raw_value`]: Self::get_raw_value
+    pub(crate) fn is_implicit_value(&self, section_name: &str, subsection_name: Option<&str>, key: &str) -> bool {
+        self.files
+            .iter()
+            .rev()
+            .find(|file| file.config.get_raw_value(section_name, subsection_name, key).is_ok())
+            .map_or(false, |file| {
+                file.config.is_implicit_value(section_name, subsection_name, key)
+            })
+    }
+
+    /// Returns all uninterpreted values of a multivar given a section, an
+    /// optional subsection and key, from the lowest to the highest precedence.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`GitConfigError::KeyDoesNotExist`] if no file has the value.
+    pub fn get_raw_multi_value<'lookup>(
+        &self,
+        section_name: &'lookup str,
+        subsection_name: Option<&'lookup str>,
+        key: &'lookup str,
+    ) -> Result<Vec<Cow<'_, [u8]>>, GitConfigError<'lookup>> {
+        let values: Vec<_> = self
+            .files
+            .iter()
+            .filter_map(|file| file.config.get_raw_multi_value(section_name, subsection_name, key).ok())
+            .flatten()
+            .collect();
+        if values.is_empty() {
+            return Err(GitConfigError::KeyDoesNotExist);
+        }
+        Ok(values)
+    }
+
+    /// Returns an interpreted value given a section, an optional subsection
+    /// and key, like [`GitConfig::value`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no file has the value or if it couldn't be
+    /// converted into the requested type.
+    pub fn value<'a, 'lookup, T: TryFrom<Cow<'a, [u8]>>>(
+        &'a self,
+        section_name: &'lookup str,
+        subsection_name: Option<&'lookup str>,
+        key: &'lookup str,
+    ) -> Result<T, GitConfigError<'lookup>> {
+        T::try_from(self.get_raw_value(section_name, subsection_name, key)?)
+            .map_err(|_| GitConfigError::FailedConversion)
+    }
+
+    /// Returns all interpreted values of a multivar given a section, an
+    /// optional subsection and key, like [`GitConfig::multi_value`] does.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no file has the value or if any value couldn't be
+    /// converted into the requested type.
+    pub fn multi_value<'a, 'lookup, T: TryFrom<Cow<'a, [u8]>>>(
+        &'a self,
+        section_name: &'lookup str,
+        subsection_name: Option<&'lookup str>,
+        key: &'lookup str,
+    ) -> Result<Vec<T>, GitConfigError<'lookup>> {
+        self.get_raw_multi_value(section_name, subsection_name, key)?
+            .into_iter()
+            .map(T::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| GitConfigError::FailedConversion)
+    }
+}
@@ -52,19 +52,27 @@
 // specify the actual serde crate when you define a feature called serde. We
 // instead call the serde crate as serde_crate and then rename the crate to
 // serde, to get around this in an intuitive manner.
-#[cfg(feature = "serde")]
+#[cfg(feature = "serde1")]
 extern crate serde_crate as serde;
 
 pub mod file;
+pub mod fs;
 pub mod parser;
+pub mod typed;
 pub mod values;
 
-// mod de;
-// mod ser;
-// mod error;
-// pub use de::{from_str, Deserializer};
-// pub use error::{Error, Result};
-// pub use ser::{to_string, Serializer};
+#[cfg(feature = "serde1")]
+pub mod de;
+#[cfg(feature = "serde1")]
+mod error;
+#[cfg(feature = "serde1")]
+pub mod ser;
+#[cfg(feature = "serde1")]
+pub use de::{from_config, from_str, Deserializer};
+#[cfg(feature = "serde1")]
+pub use error::{Error, Result};
+#[cfg(feature = "serde1")]
+pub use ser::{to_config, to_string};
 
 #[cfg(test)]
 pub mod test_util;
@@ -1239,7 +1239,8 @@ fn config_value<'a, 'b>(i: &'a [u8], events: &'b mut Vec<Event<'a>>) -> IResult<
 /// Returns an error if an invalid escape was used, if there was an unfinished
 /// quote, or there was an escape but there is nothing left to escape.
 fn value_impl<'a, 'b>(i: &'a [u8], events: &'b mut Vec<Event<'a>>) -> IResult<&'a [u8], ()> {
-    let mut parsed_index: usize = 0;
+    // The end of the value, which may be at the very beginning if the value is empty.
+    let mut parsed_index: Option<usize> = None;
     let mut offset: usize = 0;
 
     let mut was_prev_char_escape_char = false;
@@ -1261,7 +1262,6 @@ fn value_impl<'a, 'b>(i: &'a [u8], events: &'b mut Vec<Event<'a>>) -> IResult<&'
                         std::str::from_utf8(&i[index..=index]).unwrap(),
                     )));
                     offset = index + 1;
-                    parsed_index = 0;
                 }
                 b't' | b'\\' | b'n' | b'"' => (),
                 _ => {
@@ -1274,11 +1274,11 @@ fn value_impl<'a, 'b>(i: &'a [u8], events: &'b mut Vec<Event<'a>>) -> IResult<&'
         } else {
             match c {
                 b'\n' => {
-                    parsed_index = index;
+                    parsed_index = Some(index);
                     break;
                 }
                 b';' | b'#' if !is_in_quotes => {
-                    parsed_index = index;
+                    parsed_index = Some(index);
                     break;
                 }
                 b'\\' => was_prev_char_escape_char = true,
@@ -1288,9 +1288,7 @@ fn value_impl<'a, 'b>(i: &'a [u8], events: &'b mut Vec<Event<'a>>) -> IResult<&'
         }
     }
 
-    if parsed_index == 0 {
-        parsed_index = i.len();
-    }
+    let parsed_index = parsed_index.unwrap_or(i.len());
 
     // Handle incomplete escape
     if was_prev_char_escape_char {
@@ -0,0 +1,887 @@
+//! Serialization of Rust types into a [`GitConfig`] with [`serde`].
+//!
+//! This is the inverse of the [`de`](crate::de) module: the fields of the
+//! serialized struct (or the keys of a map) become sections, maps of structs
+//! or maps become subsections and all other values become keys of their
+//! section. Sequences are written as multiple values of the same key and
+//! `None` values are omitted.
+
+use crate::{
+    error::{Error, Result},
+    file::{GitConfig, MutableSection},
+    parser::Key,
+};
+use serde::ser::{self, Impossible, Serialize};
+use std::borrow::Cow;
+
+/// Serializes `value` into a new [`GitConfig`].
+///
+/// # Examples
+///
+/// ```
+/// # use std::collections::BTreeMap;
+/// # use serde_derive::Serialize;
+/// #[derive(Serialize)]
+/// # #[serde(crate = "serde_crate")]
+/// struct Remote {
+///     url: String,
+///     fetch: Vec<String>,
+/// }
+///
+/// #[derive(Serialize)]
+/// # #[serde(crate = "serde_crate")]
+/// struct Config {
+///     remote: BTreeMap<String, Remote>,
+/// }
+///
+/// let mut remote = BTreeMap::new();
+/// remote.insert(
+///     "origin".to_string(),
+///     Remote {
+///         url: "https://example.com".into(),
+///         fetch: vec!["a".into(), "b".into()],
+///     },
+/// );
+/// let config = git_config::to_config(&Config { remote }).unwrap();
+/// assert_eq!(
+///     config.to_string(),
+///     "[remote \"origin\"]\n  url=https://example.com\n  fetch=a\n  fetch=b\n"
+/// );
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if `value` or one of its fields has no representation in
+/// a `git-config` file, like a sequence of sections or an invalid key name.
+pub fn to_config<T: Serialize + ?Sized>(value: &T) -> Result<GitConfig<'static>> {
+    let mut config = GitConfig::new();
+    value.serialize(ConfigSerializer { config: &mut config })?;
+    Ok(config)
+}
+
+/// Serializes `value` into a `git-config` file, like [`to_config`].
+///
+/// # Errors
+///
+/// Returns an error if `value` couldn't be serialized.
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    to_config(value).map(|config| config.to_string())
+}
+
+fn unsupported(what: &str) -> Error {
+    Error::Unsupported(format!("{} can't be represented in a git-config file", what))
+}
+
+/// Implements the methods for scalar values by serializing them with
+/// [`ValueSerializer`] and passing the resulting bytes to `self.$finish()`.
+macro_rules! serialize_scalars {
+    ($finish:ident) => {
+        serialize_scalars!($finish:
+            serialize_bool(bool), serialize_i8(i8), serialize_i16(i16), serialize_i32(i32), serialize_i64(i64),
+            serialize_u8(u8), serialize_u16(u16), serialize_u32(u32), serialize_u64(u64),
+            serialize_f32(f32), serialize_f64(f64), serialize_char(char), serialize_str(&str),
+            serialize_bytes(&[u8])
+        );
+
+        fn serialize_unit_variant(self, name: &'static str, index: u32, variant: &'static str) -> Result<Self::Ok> {
+            let value = ValueSerializer.serialize_unit_variant(name, index, variant)?;
+            self.$finish(value)
+        }
+    };
+    ($finish:ident: $($method:ident($ty:ty)),*) => {
+        $(
+            fn $method(self, value: $ty) -> Result<Self::Ok> {
+                let value = ValueSerializer.$method(value)?;
+                self.$finish(value)
+            }
+        )*
+    };
+}
+
+/// Implements the methods for scalar values by failing with an error that
+/// mentions `$what`.
+macro_rules! unsupported_scalars {
+    ($what:literal) => {
+        unsupported_scalars!($what:
+            serialize_bool(bool), serialize_i8(i8), serialize_i16(i16), serialize_i32(i32), serialize_i64(i64),
+            serialize_u8(u8), serialize_u16(u16), serialize_u32(u32), serialize_u64(u64),
+            serialize_f32(f32), serialize_f64(f64), serialize_char(char), serialize_str(&str),
+            serialize_bytes(&[u8])
+        );
+
+        fn serialize_unit_variant(self, _name: &'static str, _index: u32, _variant: &'static str) -> Result<Self::Ok> {
+            Err(unsupported($what))
+        }
+    };
+    ($what:literal: $($method:ident($ty:ty)),*) => {
+        $(
+            fn $method(self, _value: $ty) -> Result<Self::Ok> {
+                Err(unsupported($what))
+            }
+        )*
+    };
+}
+
+/// Implements the methods for enum variants with data, which can't be
+/// represented.
+macro_rules! unsupported_variants {
+    () => {
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            _value: &T,
+        ) -> Result<Self::Ok> {
+            Err(unsupported(&format!("The enum variant '{}'", variant)))
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleVariant> {
+            Err(unsupported(&format!("The enum variant '{}'", variant)))
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStructVariant> {
+            Err(unsupported(&format!("The enum variant '{}'", variant)))
+        }
+    };
+}
+
+/// Serializes the top-level struct or map, whose fields are sections.
+struct ConfigSerializer<'a> {
+    config: &'a mut GitConfig<'static>,
+}
+
+impl<'a> ser::Serializer for ConfigSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = SectionsSerializer<'a>;
+    type SerializeStruct = SectionsSerializer<'a>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    unsupported_scalars!("A value outside of a section");
+    unsupported_variants!();
+
+    fn serialize_none(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(unsupported("A sequence of sections"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(unsupported("A sequence of sections"))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        Err(unsupported("A sequence of sections"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(SectionsSerializer {
+            config: self.config,
+            name: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(SectionsSerializer {
+            config: self.config,
+            name: None,
+        })
+    }
+}
+
+/// Serializes the fields of the top-level struct or map as sections.
+struct SectionsSerializer<'a> {
+    config: &'a mut GitConfig<'static>,
+    name: Option<String>,
+}
+
+impl ser::SerializeStruct for SectionsSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        value.serialize(SectionSerializer {
+            config: self.config,
+            name: key.to_owned(),
+        })
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for SectionsSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.name = Some(key.serialize(NameSerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.serialize(SectionSerializer {
+            config: self.config,
+            name: self
+                .name
+                .take()
+                .expect("serialize_key() is called before serialize_value()"),
+        })
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes a section, which is a struct with its keys or a map with its
+/// subsections or keys.
+struct SectionSerializer<'a> {
+    config: &'a mut GitConfig<'static>,
+    name: String,
+}
+
+impl<'a> ser::Serializer for SectionSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Impossible<(), Error>;
+    type SerializeTuple = Impossible<(), Error>;
+    type SerializeTupleStruct = Impossible<(), Error>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = SubsectionsSerializer<'a>;
+    type SerializeStruct = BodySerializer<'a>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    unsupported_scalars!("A value outside of a section");
+    unsupported_variants!();
+
+    fn serialize_none(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(unsupported("A sequence of sections"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(unsupported("A sequence of sections"))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        Err(unsupported("A sequence of sections"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(SubsectionsSerializer {
+            config: self.config,
+            name: self.name,
+            key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Ok(BodySerializer {
+            section: self.config.new_section(self.name, None),
+        })
+    }
+}
+
+/// Serializes the entries of a map in a section as subsections or keys.
+struct SubsectionsSerializer<'a> {
+    config: &'a mut GitConfig<'static>,
+    name: String,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for SubsectionsSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+        self.key = Some(key.serialize(NameSerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        value.serialize(SubsectionSerializer {
+            config: self.config,
+            name: self.name.clone(),
+            key: self
+                .key
+                .take()
+                .expect("serialize_key() is called before serialize_value()"),
+        })
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes an entry of a map in a section, which is a subsection if it is
+/// a struct or map, or a key of the section otherwise.
+struct SubsectionSerializer<'a> {
+    config: &'a mut GitConfig<'static>,
+    name: String,
+    key: String,
+}
+
+impl SubsectionSerializer<'_> {
+    fn push(self, value: Vec<u8>) -> Result<()> {
+        self.push_all(vec![value])
+    }
+
+    fn push_all(self, values: Vec<Vec<u8>>) -> Result<()> {
+        let key = key(self.key)?;
+        if self.config.section(&self.name, None).is_err() {
+            self.config.new_section(self.name.clone(), None);
+        }
+        let mut section = self
+            .config
+            .section_mut(&self.name, None)
+            .expect("the section exists or was just created");
+        for value in values {
+            section.push(key.clone(), Cow::Owned(escape(&value)));
+        }
+        Ok(())
+    }
+}
+
+impl<'a> ser::Serializer for SubsectionSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = PendingValues<'a>;
+    type SerializeTuple = PendingValues<'a>;
+    type SerializeTupleStruct = PendingValues<'a>;
+    type SerializeTupleVariant = Impossible<(), Error>;
+    type SerializeMap = BodySerializer<'a>;
+    type SerializeStruct = BodySerializer<'a>;
+    type SerializeStructVariant = Impossible<(), Error>;
+
+    serialize_scalars!(push);
+    unsupported_variants!();
+
+    fn serialize_none(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        Ok(())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(PendingValues {
+            target: self,
+            values: Values::default(),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(BodySerializer {
+            section: self.config.new_section(self.name, Some(Cow::Owned(self.key))),
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        self.serialize_map(None)
+    }
+}
+
+/// The values of a sequence that become a multi-valued key once complete.
+struct PendingValues<'a> {
+    target: SubsectionSerializer<'a>,
+    values: Values,
+}
+
+impl ser::SerializeSeq for PendingValues<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(&mut self.values, value)
+    }
+
+    fn end(self) -> Result<()> {
+        self.target.push_all(self.values.0)
+    }
+}
+
+impl ser::SerializeTuple for PendingValues<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<()> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for PendingValues<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<()> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+/// Serializes the fields of a struct or entries of a map as keys of a
+/// section.
+struct BodySerializer<'a> {
+    section: MutableSection<'a, 'static>,
+}
+
+impl BodySerializer<'_> {
+    fn push<T: Serialize + ?Sized>(&mut self, key_name: String, value: &T) -> Result<()> {
+        let key = key(key_name)?;
+        for value in value.serialize(ValuesSerializer)? {
+            self.section.push(key.clone(), Cow::Owned(escape(&value)));
+        }
+        Ok(())
+    }
+}
+
+impl ser::SerializeStruct for BodySerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+        self.push(key.to_owned(), value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeMap for BodySerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, _key: &T) -> Result<()> {
+        unreachable!("serialize_entry() is implemented")
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, _value: &T) -> Result<()> {
+        unreachable!("serialize_entry() is implemented")
+    }
+
+    fn serialize_entry<K: Serialize + ?Sized, V: Serialize + ?Sized>(&mut self, key: &K, value: &V) -> Result<()> {
+        let key = key.serialize(NameSerializer)?;
+        self.push(key, value)
+    }
+
+    fn end(self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Serializes the value of a key into all of its values, which may be none
+/// or multiple.
+struct ValuesSerializer;
+
+impl ValuesSerializer {
+    #[allow(clippy::unnecessary_wraps, clippy::unused_self)]
+    fn single(self, value: Vec<u8>) -> Result<Vec<Vec<u8>>> {
+        Ok(vec![value])
+    }
+}
+
+impl ser::Serializer for ValuesSerializer {
+    type Ok = Vec<Vec<u8>>;
+    type Error = Error;
+    type SerializeSeq = Values;
+    type SerializeTuple = Values;
+    type SerializeTupleStruct = Values;
+    type SerializeTupleVariant = Impossible<Self::Ok, Error>;
+    type SerializeMap = Impossible<Self::Ok, Error>;
+    type SerializeStruct = Impossible<Self::Ok, Error>;
+    type SerializeStructVariant = Impossible<Self::Ok, Error>;
+
+    serialize_scalars!(single);
+    unsupported_variants!();
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(Vec::new())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(Vec::new())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        Ok(Vec::new())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Self::Ok> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(Values::default())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Ok(Values::default())
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        Ok(Values::default())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(unsupported("A map within a section"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(unsupported("A struct within a section"))
+    }
+}
+
+/// The values of a multi-valued key.
+#[derive(Default)]
+struct Values(Vec<Vec<u8>>);
+
+impl ser::SerializeSeq for Values {
+    type Ok = Vec<Vec<u8>>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        self.0.push(value.serialize(ValueSerializer)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.0)
+    }
+}
+
+impl ser::SerializeTuple for Values {
+    type Ok = Vec<Vec<u8>>;
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.0)
+    }
+}
+
+impl ser::SerializeTupleStruct for Values {
+    type Ok = Vec<Vec<u8>>;
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(self.0)
+    }
+}
+
+/// Serializes a single scalar value into its unescaped bytes.
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Vec<u8>;
+    type Error = Error;
+    type SerializeSeq = Impossible<Vec<u8>, Error>;
+    type SerializeTuple = Impossible<Vec<u8>, Error>;
+    type SerializeTupleStruct = Impossible<Vec<u8>, Error>;
+    type SerializeTupleVariant = Impossible<Vec<u8>, Error>;
+    type SerializeMap = Impossible<Vec<u8>, Error>;
+    type SerializeStruct = Impossible<Vec<u8>, Error>;
+    type SerializeStructVariant = Impossible<Vec<u8>, Error>;
+
+    unsupported_variants!();
+
+    fn serialize_bool(self, value: bool) -> Result<Vec<u8>> {
+        Ok(if value { b"true".to_vec() } else { b"false".to_vec() })
+    }
+
+    fn serialize_i8(self, value: i8) -> Result<Vec<u8>> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i16(self, value: i16) -> Result<Vec<u8>> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i32(self, value: i32) -> Result<Vec<u8>> {
+        self.serialize_i64(value.into())
+    }
+
+    fn serialize_i64(self, value: i64) -> Result<Vec<u8>> {
+        Ok(value.to_string().into_bytes())
+    }
+
+    fn serialize_u8(self, value: u8) -> Result<Vec<u8>> {
+        self.serialize_u64(value.into())
+    }
+
+    fn serialize_u16(self, value: u16) -> Result<Vec<u8>> {
+        self.serialize_u64(value.into())
+    }
+
+    fn serialize_u32(self, value: u32) -> Result<Vec<u8>> {
+        self.serialize_u64(value.into())
+    }
+
+    fn serialize_u64(self, value: u64) -> Result<Vec<u8>> {
+        Ok(value.to_string().into_bytes())
+    }
+
+    fn serialize_f32(self, value: f32) -> Result<Vec<u8>> {
+        Ok(value.to_string().into_bytes())
+    }
+
+    fn serialize_f64(self, value: f64) -> Result<Vec<u8>> {
+        Ok(value.to_string().into_bytes())
+    }
+
+    fn serialize_char(self, value: char) -> Result<Vec<u8>> {
+        Ok(value.to_string().into_bytes())
+    }
+
+    fn serialize_str(self, value: &str) -> Result<Vec<u8>> {
+        Ok(value.as_bytes().to_vec())
+    }
+
+    fn serialize_bytes(self, value: &[u8]) -> Result<Vec<u8>> {
+        Ok(value.to_vec())
+    }
+
+    fn serialize_none(self) -> Result<Vec<u8>> {
+        Err(unsupported("A missing value within a sequence"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Vec<u8>> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Vec<u8>> {
+        Err(unsupported("A unit value within a sequence"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<Vec<u8>> {
+        Err(unsupported(&format!("The unit struct '{}'", name)))
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Vec<u8>> {
+        Ok(variant.as_bytes().to_vec())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Vec<u8>> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(unsupported("A nested sequence"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(unsupported("A nested sequence"))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        Err(unsupported("A nested sequence"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(unsupported("A map within a sequence"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(unsupported("A struct within a sequence"))
+    }
+}
+
+/// Serializes the key of a map, which is the name of a section or key or a
+/// subsection name.
+struct NameSerializer;
+
+impl NameSerializer {
+    #[allow(clippy::unused_self)]
+    fn utf8(self, value: Vec<u8>) -> Result<String> {
+        String::from_utf8(value).map_err(|_| unsupported("A name that isn't valid UTF-8"))
+    }
+}
+
+impl ser::Serializer for NameSerializer {
+    type Ok = String;
+    type Error = Error;
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    serialize_scalars!(utf8);
+    unsupported_variants!();
+
+    fn serialize_none(self) -> Result<String> {
+        Err(unsupported("A missing name"))
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(unsupported("A missing name"))
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> Result<String> {
+        Err(unsupported(&format!("The unit struct '{}' as name", name)))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<String> {
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(unsupported("A sequence as name"))
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(unsupported("A sequence as name"))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+        Err(unsupported("A sequence as name"))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(unsupported("A map as name"))
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(unsupported("A struct as name"))
+    }
+}
+
+/// Validates `name` as key name, which must start with a letter and may only
+/// contain letters, digits and `-`.
+fn key(name: String) -> Result<Key<'static>> {
+    let valid = name.chars().next().map_or(false, |c| c.is_ascii_alphabetic())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if valid {
+        Ok(Key(Cow::Owned(name)))
+    } else {
+        Err(Error::Unsupported(format!("'{}' is not a valid key name", name)))
+    }
+}
+
+/// Escapes backslashes, quotes, newlines and tabs in `value`, and quotes it
+/// if it would otherwise lose whitespace or be cut off by a comment.
+fn escape(value: &[u8]) -> Vec<u8> {
+    let needs_quotes = value.is_empty()
+        || value.first().map_or(false, u8::is_ascii_whitespace)
+        || value.last().map_or(false, u8::is_ascii_whitespace)
+        || value.iter().any(|b| *b == b';' || *b == b'#');
+    let mut out = Vec::with_capacity(value.len() + 2);
+    if needs_quotes {
+        out.push(b'"');
+    }
+    for byte in value {
+        match byte {
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            b'"' => out.extend_from_slice(b"\\\""),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            _ => out.push(*byte),
+        }
+    }
+    if needs_quotes {
+        out.push(b'"');
+    }
+    out
+}
@@ -0,0 +1,733 @@
+//! Typed access to the well-known keys `git` and our tools read most often,
+//! with the same defaults `git` uses if a key isn't set.
+//!
+//! Each section is read from a cascading [`Config`] into a plain struct, like
+//! [`Core::from_config`]. Values that can't be interpreted as the type of
+//! their key result in an [`Error`] naming the key and the file the value was
+//! read from.
+//!
+//! `url.<base>.insteadOf` and `url.<base>.pushInsteadOf` are supported
+//! through [`UrlRewrite`], which [`Remote::from_config`] applies to the urls
+//! of a remote.
+
+use crate::fs::{Config, Origin, Source};
+use crate::parser::Key;
+use crate::values::{normalize_cow, Boolean, Integer, Path};
+use bstr::BString;
+use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::fmt::Display;
+use std::path::PathBuf;
+
+/// The error returned if a value can't be interpreted as the type of its key.
+#[derive(Debug)]
+pub struct Error {
+    /// The full name of the key, like `core.abbrev` or `remote.origin.url`.
+    pub key: String,
+    /// The value that couldn't be interpreted, with quotes removed.
+    pub value: BString,
+    /// The origin of the file the value was read from.
+    pub origin: Origin,
+    /// A description of the values the key accepts.
+    pub expected: &'static str,
+    /// The error that occurred when interpreting the value, if any.
+    pub source: Option<Box<dyn std::error::Error + Send + Sync + 'static>>,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid value '{}' of '{}' ", self.value, self.key)?;
+        match (&self.origin.path, self.origin.source) {
+            (Some(path), _) => write!(f, "in '{}'", path.display())?,
+            (None, Source::Cli) => write!(f, "on the command-line")?,
+            (None, source) => write!(f, "at the {:?} level", source)?,
+        }
+        write!(f, ": expected {}.", self.expected)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|err| err.as_ref() as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// The values of the `core` section.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Core {
+    /// `core.bare`, or `None` if the repository layout decides.
+    pub bare: Option<bool>,
+    /// `core.abbrev`, the amount of hex characters to abbreviate object ids
+    /// to, which is `None` for `auto` or if unset.
+    pub abbrev: Option<usize>,
+    /// `core.fileMode`, `true` by default.
+    pub file_mode: bool,
+    /// `core.ignoreCase`, `false` by default.
+    pub ignore_case: bool,
+    /// `core.symlinks`, `true` by default.
+    pub symlinks: bool,
+    /// `core.logAllRefUpdates`, or `None` if the repository layout decides.
+    /// `always` is interpreted as `true`.
+    pub log_all_ref_updates: Option<bool>,
+    /// `core.autocrlf`, [`AutoCrlf::False`] by default.
+    pub auto_crlf: AutoCrlf,
+    /// `core.excludesFile`, with `~/` and `~user/` expanded.
+    pub excludes_file: Option<PathBuf>,
+    /// `core.hooksPath`, with `~/` and `~user/` expanded.
+    pub hooks_path: Option<PathBuf>,
+    /// `core.compression`, `-1` for the default of zlib by default.
+    pub compression: i64,
+    /// `core.repositoryFormatVersion`, `0` by default.
+    pub repository_format_version: i64,
+}
+
+impl Default for Core {
+    fn default() -> Self {
+        Self {
+            bare: None,
+            abbrev: None,
+            file_mode: true,
+            ignore_case: false,
+            symlinks: true,
+            log_all_ref_updates: None,
+            auto_crlf: AutoCrlf::False,
+            excludes_file: None,
+            hooks_path: None,
+            compression: -1,
+            repository_format_version: 0,
+        }
+    }
+}
+
+/// The values of `core.autocrlf`.
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum AutoCrlf {
+    /// Line endings are never converted.
+    False,
+    /// Line endings are converted to `LF` when adding files and to `CRLF`
+    /// when checking them out.
+    True,
+    /// Line endings are converted to `LF` when adding files only.
+    Input,
+}
+
+impl Core {
+    /// Reads the `core` section of `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value can't be interpreted.
+    pub fn from_config(config: &Config) -> Result<Self, Error> {
+        let section = Section::new(config, "core", None);
+        let default = Self::default();
+        Ok(Self {
+            bare: section.boolean("bare")?,
+            abbrev: section.abbrev()?,
+            file_mode: section.boolean("fileMode")?.unwrap_or(default.file_mode),
+            ignore_case: section.boolean("ignoreCase")?.unwrap_or(default.ignore_case),
+            symlinks: section.boolean("symlinks")?.unwrap_or(default.symlinks),
+            log_all_ref_updates: section.parse("logAllRefUpdates", "a boolean or 'always'", |value| {
+                if value.eq_ignore_ascii_case(b"always") {
+                    Some(true)
+                } else {
+                    boolean(value)
+                }
+            })?,
+            auto_crlf: section
+                .parse("autocrlf", "a boolean or 'input'", |value| {
+                    if value.eq_ignore_ascii_case(b"input") {
+                        Some(AutoCrlf::Input)
+                    } else {
+                        boolean(value).map(|value| if value { AutoCrlf::True } else { AutoCrlf::False })
+                    }
+                })?
+                .unwrap_or(default.auto_crlf),
+            excludes_file: section.path("excludesFile")?,
+            hooks_path: section.path("hooksPath")?,
+            compression: section.integer_in("compression", -1, 9)?.unwrap_or(default.compression),
+            repository_format_version: section
+                .integer("repositoryFormatVersion")?
+                .unwrap_or(default.repository_format_version),
+        })
+    }
+
+    /// Returns the amount of hex characters to abbreviate object ids of
+    /// `hex_len` characters to, which is 7 unless `core.abbrev` is set to a
+    /// number or to disable abbreviation.
+    #[must_use]
+    pub fn abbrev_len(&self, hex_len: usize) -> usize {
+        self.abbrev.unwrap_or(7).min(hex_len)
+    }
+}
+
+/// The values of the `user` section.
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct User {
+    /// `user.name`.
+    pub name: Option<BString>,
+    /// `user.email`.
+    pub email: Option<BString>,
+}
+
+impl User {
+    /// Reads the `user` section of `config`.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let section = Section::new(config, "user", None);
+        Self {
+            name: section.string("name"),
+            email: section.string("email"),
+        }
+    }
+}
+
+/// The values of a `remote.<name>` section.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Remote {
+    /// The name of the remote.
+    pub name: String,
+    /// `remote.<name>.url`, rewritten by `url.<base>.insteadOf`.
+    pub url: Option<git_url::Url>,
+    /// The url to push to, which is `remote.<name>.pushurl` rewritten by
+    /// `url.<base>.insteadOf`, or if unset, `remote.<name>.url` rewritten by
+    /// `url.<base>.pushInsteadOf`. `None` if pushes use [`url`][Self::url].
+    pub push_url: Option<git_url::Url>,
+    /// All `remote.<name>.fetch` refspecs.
+    pub fetch: Vec<BString>,
+    /// All `remote.<name>.push` refspecs.
+    pub push: Vec<BString>,
+    /// `remote.<name>.prune`, or `None` to use `fetch.prune`.
+    pub prune: Option<bool>,
+    /// `remote.<name>.mirror`, `false` by default.
+    pub mirror: bool,
+}
+
+impl Remote {
+    /// Reads the `remote.<name>` section of `config`, or returns `None` if
+    /// there is no such section.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value can't be interpreted.
+    pub fn from_config(config: &Config, name: &str) -> Result<Option<Self>, Error> {
+        let section = Section::new(config, "remote", Some(name));
+        if !section.exists() {
+            return Ok(None);
+        }
+        let rewrite = UrlRewrite::from_config(config);
+        let url = section.url("url", |url| rewrite.rewrite(url))?;
+        let push_url = if section.raw("pushurl").is_some() {
+            section.url("pushurl", |url| rewrite.rewrite(url))?
+        } else {
+            match section.raw("url") {
+                Some((url, _)) if rewrite.rewrite_push(&url).is_some() => {
+                    section.url("url", |url| rewrite.rewrite_push(url))?
+                }
+                _ => None,
+            }
+        };
+        Ok(Some(Self {
+            name: name.to_owned(),
+            url,
+            push_url,
+            fetch: section.strings("fetch"),
+            push: section.strings("push"),
+            prune: section.boolean("prune")?,
+            mirror: section.boolean("mirror")?.unwrap_or(false),
+        }))
+    }
+
+    /// Returns the names of all remotes in `config`, in the order they were
+    /// first defined.
+    #[must_use]
+    pub fn names(config: &Config) -> Vec<String> {
+        let mut names = Vec::new();
+        for (_, file) in config.files() {
+            for (header, _) in file.sections_by_name_with_header("remote") {
+                if let Some(name) = &header.subsection_name {
+                    if !names.iter().any(|existing: &String| existing == name) {
+                        names.push(name.to_string());
+                    }
+                }
+            }
+        }
+        names
+    }
+}
+
+/// The values of a `branch.<name>` section.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Branch {
+    /// The name of the branch, without `refs/heads/` prefix.
+    pub name: String,
+    /// `branch.<name>.remote`, the remote to fetch from.
+    pub remote: Option<BString>,
+    /// `branch.<name>.pushRemote`, the remote to push to.
+    pub push_remote: Option<BString>,
+    /// `branch.<name>.merge`, the upstream branch on the remote.
+    pub merge: Option<BString>,
+    /// `branch.<name>.rebase`, or `None` to use `pull.rebase`. Values other
+    /// than booleans, like `merges`, are interpreted as `true`.
+    pub rebase: Option<bool>,
+}
+
+impl Branch {
+    /// Reads the `branch.<name>` section of `config`, or returns `None` if
+    /// there is no such section.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value can't be interpreted.
+    pub fn from_config(config: &Config, name: &str) -> Result<Option<Self>, Error> {
+        let name = name.strip_prefix("refs/heads/").unwrap_or(name);
+        let section = Section::new(config, "branch", Some(name));
+        if !section.exists() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            name: name.to_owned(),
+            remote: section.string("remote"),
+            push_remote: section.string("pushRemote"),
+            merge: section.string("merge"),
+            rebase: section.parse("rebase", "a boolean, 'merges' or 'interactive'", |value| {
+                boolean(value).or_else(|| {
+                    [&b"merges"[..], b"interactive", b"i", b"m"]
+                        .iter()
+                        .any(|variant| value.eq_ignore_ascii_case(variant))
+                        .then(|| true)
+                })
+            })?,
+        }))
+    }
+}
+
+/// The values of the `pack` section.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Pack {
+    /// `pack.window`, `10` by default.
+    pub window: u64,
+    /// `pack.depth`, `50` by default.
+    pub depth: u64,
+    /// `pack.windowMemory` in bytes, with `0` meaning unlimited.
+    pub window_memory: u64,
+    /// `pack.threads`, or `None` to use as many threads as there are cores.
+    pub threads: Option<usize>,
+    /// `pack.compression`, or `None` to use `core.compression`.
+    pub compression: Option<i64>,
+}
+
+impl Default for Pack {
+    fn default() -> Self {
+        Self {
+            window: 10,
+            depth: 50,
+            window_memory: 0,
+            threads: None,
+            compression: None,
+        }
+    }
+}
+
+impl Pack {
+    /// Reads the `pack` section of `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value can't be interpreted.
+    pub fn from_config(config: &Config) -> Result<Self, Error> {
+        let section = Section::new(config, "pack", None);
+        let default = Self::default();
+        Ok(Self {
+            window: section.unsigned("window")?.unwrap_or(default.window),
+            depth: section.unsigned("depth")?.unwrap_or(default.depth),
+            window_memory: section.unsigned("windowMemory")?.unwrap_or(default.window_memory),
+            #[allow(clippy::cast_possible_truncation)]
+            threads: section
+                .unsigned("threads")?
+                .and_then(|threads| (threads != 0).then(|| threads as usize)),
+            compression: section.integer_in("compression", -1, 9)?,
+        })
+    }
+}
+
+/// The values of the `gc` section.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Gc {
+    /// `gc.auto`, the amount of loose objects triggering a collection, with
+    /// `0` disabling automatic collections. `6700` by default.
+    pub auto: u64,
+    /// `gc.autoPackLimit`, the amount of packs triggering their consolidation,
+    /// with `0` disabling it. `50` by default.
+    pub auto_pack_limit: u64,
+    /// `gc.pruneExpire`, the approxidate after which unreachable loose objects
+    /// are pruned. `2.weeks.ago` by default.
+    pub prune_expire: BString,
+    /// `gc.reflogExpire`, `90.days.ago` by default.
+    pub reflog_expire: BString,
+    /// `gc.reflogExpireUnreachable`, `30.days.ago` by default.
+    pub reflog_expire_unreachable: BString,
+}
+
+impl Default for Gc {
+    fn default() -> Self {
+        Self {
+            auto: 6700,
+            auto_pack_limit: 50,
+            prune_expire: "2.weeks.ago".into(),
+            reflog_expire: "90.days.ago".into(),
+            reflog_expire_unreachable: "30.days.ago".into(),
+        }
+    }
+}
+
+impl Gc {
+    /// Reads the `gc` section of `config`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value can't be interpreted.
+    pub fn from_config(config: &Config) -> Result<Self, Error> {
+        let section = Section::new(config, "gc", None);
+        let default = Self::default();
+        Ok(Self {
+            auto: section.unsigned("auto")?.unwrap_or(default.auto),
+            auto_pack_limit: section.unsigned("autoPackLimit")?.unwrap_or(default.auto_pack_limit),
+            prune_expire: section.string("pruneExpire").unwrap_or(default.prune_expire),
+            reflog_expire: section.string("reflogExpire").unwrap_or(default.reflog_expire),
+            reflog_expire_unreachable: section
+                .string("reflogExpireUnreachable")
+                .unwrap_or(default.reflog_expire_unreachable),
+        })
+    }
+}
+
+/// The values of the `http` section.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct Http {
+    /// `http.proxy`.
+    pub proxy: Option<BString>,
+    /// `http.sslVerify`, `true` by default.
+    pub ssl_verify: bool,
+    /// `http.userAgent`.
+    pub user_agent: Option<BString>,
+    /// `http.lowSpeedLimit` in bytes per second, with `0` disabling it.
+    pub low_speed_limit: u64,
+    /// `http.lowSpeedTime` in seconds, with `0` disabling it.
+    pub low_speed_time: u64,
+    /// `http.postBuffer` in bytes, `1MiB` by default.
+    pub post_buffer: u64,
+    /// All `http.extraHeader` values.
+    pub extra_headers: Vec<BString>,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            ssl_verify: true,
+            user_agent: None,
+            low_speed_limit: 0,
+            low_speed_time: 0,
+            post_buffer: 1024 * 1024,
+            extra_headers: Vec::new(),
+        }
+    }
+}
+
+impl Http {
+    /// Reads the `http` section of `config`. Url-specific `http.<url>.*`
+    /// sections are ignored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value can't be interpreted.
+    pub fn from_config(config: &Config) -> Result<Self, Error> {
+        let section = Section::new(config, "http", None);
+        let default = Self::default();
+        Ok(Self {
+            proxy: section.string("proxy"),
+            ssl_verify: section.boolean("sslVerify")?.unwrap_or(default.ssl_verify),
+            user_agent: section.string("userAgent"),
+            low_speed_limit: section.unsigned("lowSpeedLimit")?.unwrap_or(default.low_speed_limit),
+            low_speed_time: section.unsigned("lowSpeedTime")?.unwrap_or(default.low_speed_time),
+            post_buffer: section.unsigned("postBuffer")?.unwrap_or(default.post_buffer),
+            extra_headers: section.strings("extraHeader"),
+        })
+    }
+}
+
+/// The url rewrites of all `url.<base>.insteadOf` and
+/// `url.<base>.pushInsteadOf` values.
+///
+/// Like in `git`, a url starting with one of the values is rewritten by
+/// replacing the value with its `<base>`, using the longest matching value
+/// if more than one match.
+///
+/// # Examples
+///
+/// ```
+/// # use git_config::{fs::Config, typed::UrlRewrite};
+/// let mut config = Config::new();
+/// config.push_cli_values(Some("url.https://github.com/.insteadOf=gh:"))?;
+/// let rewrite = UrlRewrite::from_config(&config);
+/// assert_eq!(rewrite.rewrite(b"gh:byron/gitoxide").unwrap(), "https://github.com/byron/gitoxide");
+/// assert_eq!(rewrite.rewrite(b"https://example.com/repo"), None);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(PartialEq, Eq, Clone, Debug, Default)]
+pub struct UrlRewrite {
+    /// Pairs of the value to replace and its replacement.
+    instead_of: Vec<(BString, BString)>,
+    push_instead_of: Vec<(BString, BString)>,
+}
+
+impl UrlRewrite {
+    /// Collects the url rewrites of all `url.<base>` sections in `config`.
+    #[must_use]
+    pub fn from_config(config: &Config) -> Self {
+        let mut rewrite = Self::default();
+        for (_, file) in config.files() {
+            for (header, body) in file.sections_by_name_with_header("url") {
+                let base = match &header.subsection_name {
+                    Some(base) => BString::from(base.as_bytes()),
+                    None => continue,
+                };
+                for (key, list) in [
+                    ("insteadOf", &mut rewrite.instead_of),
+                    ("pushInsteadOf", &mut rewrite.push_instead_of),
+                ] {
+                    list.extend(
+                        body.values(&Key(key.into()))
+                            .into_iter()
+                            .map(|value| (normalize_cow(value).into_owned().into(), base.clone())),
+                    );
+                }
+            }
+        }
+        rewrite
+    }
+
+    /// Returns `url` rewritten by `url.<base>.insteadOf`, or `None` if no
+    /// value matches.
+    #[must_use]
+    pub fn rewrite(&self, url: &[u8]) -> Option<BString> {
+        Self::apply(&self.instead_of, url)
+    }
+
+    /// Returns `url` rewritten by `url.<base>.pushInsteadOf` for pushing to
+    /// it, or `None` if no value matches.
+    #[must_use]
+    pub fn rewrite_push(&self, url: &[u8]) -> Option<BString> {
+        Self::apply(&self.push_instead_of, url)
+    }
+
+    /// Returns `url` rewritten by `url.<base>.insteadOf`, or `None` if no
+    /// value matches the string representation of `url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the rewritten url can't be parsed.
+    pub fn rewrite_url(&self, url: &git_url::Url) -> Result<Option<git_url::Url>, git_url::parse::Error> {
+        let url = match url_to_bytes(url) {
+            Some(url) => url,
+            None => return Ok(None),
+        };
+        self.rewrite(&url)
+            .map(|rewritten| git_url::Url::from_bytes(&rewritten))
+            .transpose()
+    }
+
+    fn apply(list: &[(BString, BString)], url: &[u8]) -> Option<BString> {
+        list.iter()
+            .filter(|(instead_of, _)| url.starts_with(instead_of))
+            .max_by_key(|(instead_of, _)| instead_of.len())
+            .map(|(instead_of, base)| {
+                let mut rewritten = base.clone();
+                rewritten.extend_from_slice(&url[instead_of.len()..]);
+                rewritten
+            })
+    }
+}
+
+fn url_to_bytes(url: &git_url::Url) -> Option<Vec<u8>> {
+    use std::fmt::Write;
+    let mut buf = String::new();
+    write!(buf, "{}", url).ok()?;
+    Some(buf.into_bytes())
+}
+
+/// Interprets `value` as boolean like `git` does, accepting integers as well.
+///
+/// An empty value is `false`, as only keys without `=` are implicitly `true`,
+/// which callers have to check for themselves.
+pub(crate) fn boolean(value: &[u8]) -> Option<bool> {
+    if value.is_empty() {
+        return Some(false);
+    }
+    Boolean::try_from(value)
+        .ok()
+        .map(Into::into)
+        .or_else(|| integer(value).map(|value| value != 0))
+}
+
+/// Interprets `value` as integer with an optional `k`, `m` or `g` suffix.
+pub(crate) fn integer(value: &[u8]) -> Option<i64> {
+    let int = Integer::try_from(value).ok()?;
+    match int.suffix {
+        Some(suffix) => int.value.checked_mul(1 << suffix.bitwise_offset()),
+        None => Some(int.value),
+    }
+}
+
+/// A section in a cascading [`Config`], to read values of the file with the
+/// highest precedence from.
+struct Section<'a> {
+    config: &'a Config,
+    name: &'a str,
+    subsection: Option<&'a str>,
+}
+
+impl<'a> Section<'a> {
+    const fn new(config: &'a Config, name: &'a str, subsection: Option<&'a str>) -> Self {
+        Self {
+            config,
+            name,
+            subsection,
+        }
+    }
+
+    fn exists(&self) -> bool {
+        self.config.files().any(|(_, file)| {
+            file.sections_by_name_with_header(self.name)
+                .iter()
+                .any(|(header, _)| header.subsection_name.as_deref() == self.subsection)
+        })
+    }
+
+    fn key_name(&self, key: &str) -> String {
+        match self.subsection {
+            Some(subsection) => format!("{}.{}.{}", self.name, subsection, key),
+            None => format!("{}.{}", self.name, key),
+        }
+    }
+
+    fn raw(&self, key: &str) -> Option<(Cow<'a, [u8]>, &'a Origin)> {
+        self.config
+            .get_raw_value_with_origin(self.name, self.subsection, key)
+            .ok()
+            .map(|(value, origin)| (normalize_cow(value), origin))
+    }
+
+    /// Returns true if `key` is defined without `=`, which makes it `true`.
+    fn is_implicit(&self, key: &str) -> bool {
+        self.config.is_implicit_value(self.name, self.subsection, key)
+    }
+
+    fn error(&self, key: &str, value: &[u8], origin: &Origin, expected: &'static str) -> Error {
+        Error {
+            key: self.key_name(key),
+            value: value.into(),
+            origin: origin.clone(),
+            expected,
+            source: None,
+        }
+    }
+
+    /// Interprets the value of `key` with `parse`, which returns `None` if
+    /// the value isn't valid. Keys without `=` are passed as `true`.
+    fn parse<T>(
+        &self,
+        key: &str,
+        expected: &'static str,
+        parse: impl FnOnce(&[u8]) -> Option<T>,
+    ) -> Result<Option<T>, Error> {
+        match self.raw(key) {
+            Some((value, origin)) => parse(if self.is_implicit(key) { b"true" } else { &value })
+                .map(Some)
+                .ok_or_else(|| self.error(key, &value, origin, expected)),
+            None => Ok(None),
+        }
+    }
+
+    fn string(&self, key: &str) -> Option<BString> {
+        self.raw(key).map(|(value, _)| value.into_owned().into())
+    }
+
+    fn strings(&self, key: &str) -> Vec<BString> {
+        self.config
+            .get_raw_multi_value(self.name, self.subsection, key)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|value| normalize_cow(value).into_owned().into())
+            .collect()
+    }
+
+    fn boolean(&self, key: &str) -> Result<Option<bool>, Error> {
+        self.parse(key, "a boolean", boolean)
+    }
+
+    fn integer(&self, key: &str) -> Result<Option<i64>, Error> {
+        self.parse(key, "an integer", integer)
+    }
+
+    fn integer_in(&self, key: &str, min: i64, max: i64) -> Result<Option<i64>, Error> {
+        self.parse(key, "an integer in the valid range", |value| {
+            integer(value).filter(|value| (min..=max).contains(value))
+        })
+    }
+
+    fn unsigned(&self, key: &str) -> Result<Option<u64>, Error> {
+        self.parse(key, "a non-negative integer", |value| {
+            integer(value).and_then(|value| u64::try_from(value).ok())
+        })
+    }
+
+    fn path(&self, key: &str) -> Result<Option<PathBuf>, Error> {
+        match self.raw(key) {
+            Some((value, origin)) => Path::from(value.clone())
+                .interpolate(None, None)
+                .map(|path| Some(path.into_owned()))
+                .map_err(|err| Error {
+                    source: Some(Box::new(err)),
+                    ..self.error(key, &value, origin, "a path")
+                }),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads the url of `key`, after applying `rewrite` to it.
+    fn url(&self, key: &str, rewrite: impl FnOnce(&[u8]) -> Option<BString>) -> Result<Option<git_url::Url>, Error> {
+        match self.raw(key) {
+            Some((value, origin)) => {
+                let rewritten = rewrite(&value);
+                git_url::Url::from_bytes(rewritten.as_deref().map_or(&value, |url| url.as_ref()))
+                    .map(Some)
+                    .map_err(|err| Error {
+                        source: Some(Box::new(err)),
+                        ..self.error(key, &value, origin, "a valid url")
+                    })
+            }
+            None => Ok(None),
+        }
+    }
+
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+    fn abbrev(&self) -> Result<Option<usize>, Error> {
+        const MIN: i64 = 4;
+        const MAX: i64 = 40;
+        self.parse("abbrev", "'auto', a boolean or an integer from 4 to 40", |value| {
+            if value.eq_ignore_ascii_case(b"auto") {
+                return Some(None);
+            }
+            if value.is_empty() || matches!(Boolean::try_from(value), Ok(Boolean::False(_))) {
+                return Some(Some(MAX as usize));
+            }
+            integer(value)
+                .filter(|value| (MIN..=MAX).contains(value))
+                .map(|value| Some(value as usize))
+        })
+        .map(Option::flatten)
+    }
+}
@@ -1,6 +1,6 @@
 //! Rust containers for valid `git-config` types.
 
-#[cfg(feature = "serde")]
+#[cfg(feature = "serde1")]
 use serde::{Serialize, Serializer};
 use std::borrow::Cow;
 use std::convert::TryFrom;
@@ -260,7 +260,7 @@ impl Display for Value<'_> {
     }
 }
 
-#[cfg(feature = "serde")]
+#[cfg(feature = "serde1")]
 impl Serialize for Value<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -417,7 +417,7 @@ impl From<&Boolean<'_>> for Vec<u8> {
     }
 }
 
-#[cfg(feature = "serde")]
+#[cfg(feature = "serde1")]
 impl Serialize for Boolean<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -515,7 +515,7 @@ impl<'a, 'b: 'a> From<&'b TrueVariant<'a>> for &'a [u8] {
     }
 }
 
-#[cfg(feature = "serde")]
+#[cfg(feature = "serde1")]
 impl Serialize for TrueVariant<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -565,7 +565,7 @@ impl Display for Integer {
     }
 }
 
-#[cfg(feature = "serde")]
+#[cfg(feature = "serde1")]
 impl Serialize for Integer {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -684,7 +684,7 @@ impl Display for IntegerSuffix {
     }
 }
 
-#[cfg(feature = "serde")]
+#[cfg(feature = "serde1")]
 impl Serialize for IntegerSuffix {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -776,7 +776,7 @@ impl Display for Color {
     }
 }
 
-#[cfg(feature = "serde")]
+#[cfg(feature = "serde1")]
 impl Serialize for Color {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -937,7 +937,7 @@ impl Display for ColorValue {
     }
 }
 
-#[cfg(feature = "serde")]
+#[cfg(feature = "serde1")]
 impl Serialize for ColorValue {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -1057,7 +1057,7 @@ impl Display for ColorAttribute {
     }
 }
 
-#[cfg(feature = "serde")]
+#[cfg(feature = "serde1")]
 impl Serialize for ColorAttribute {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -1126,6 +1126,120 @@ impl TryFrom<&[u8]> for ColorAttribute {
     }
 }
 
+/// Any value that can be interpreted as a file path.
+///
+/// Paths are only interpolated on request through [`interpolate`], which
+/// expands a leading `~/` or `~user/` to the home directory of the current or
+/// the given user, a leading `%(prefix)/` to the installation directory of
+/// `git`, and resolves relative paths against the directory of the file the
+/// value was defined in.
+///
+/// [`interpolate`]: Path::interpolate
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Path<'a> {
+    /// The uninterpolated path, with quotes removed.
+    pub value: Cow<'a, [u8]>,
+}
+
+/// The error returned by [`Path::interpolate`].
+#[derive(Debug)]
+pub enum PathError {
+    /// The path contains `%(prefix)/`, but no installation directory of
+    /// `git` was provided.
+    MissingInstallDir,
+    /// The home directory of a user couldn't be obtained, or the path isn't
+    /// valid UTF-8 on a system that requires it.
+    UserInterpolation(git_url::expand_path::Error),
+    /// The path isn't valid UTF-8 on a system that requires it.
+    Utf8(bstr::Utf8Error),
+}
+
+impl Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingInstallDir => write!(f, "%(prefix) is used, but the git installation directory is unknown."),
+            Self::UserInterpolation(err) => write!(f, "Could not expand the home directory: {}", err),
+            Self::Utf8(err) => write!(f, "The path is not valid UTF-8: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for PathError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::MissingInstallDir => None,
+            Self::UserInterpolation(err) => Some(err),
+            Self::Utf8(err) => Some(err),
+        }
+    }
+}
+
+impl<'a> Path<'a> {
+    /// Interpolates this path, with `git_install_dir` being the directory
+    /// `%(prefix)/` refers to, and `base` the directory relative paths are
+    /// resolved against, typically the one containing the file the value was
+    /// read from. Relative paths are returned unchanged if `base` is `None`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use git_config::values::Path;
+    /// # use std::borrow::Cow;
+    /// let path = Path::from(Cow::Borrowed(&b"%(prefix)/share/git"[..]));
+    /// assert_eq!(path.interpolate(Some("/usr".as_ref()), None)?, std::path::Path::new("/usr/share/git"));
+    ///
+    /// let path = Path::from(Cow::Borrowed(&b"hooks"[..]));
+    /// assert_eq!(path.interpolate(None, Some("/repo/.git".as_ref()))?, std::path::Path::new("/repo/.git/hooks"));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `%(prefix)/` is used without `git_install_dir`, if
+    /// the home directory of a user couldn't be obtained, or if the path isn't
+    /// valid UTF-8 on systems requiring it.
+    pub fn interpolate(
+        self,
+        git_install_dir: Option<&std::path::Path>,
+        base: Option<&std::path::Path>,
+    ) -> Result<Cow<'a, std::path::Path>, PathError> {
+        use bstr::ByteSlice;
+        const PREFIX: &[u8] = b"%(prefix)/";
+        const USER_HOME: &[u8] = b"~";
+
+        if self.value.starts_with(PREFIX) {
+            let install_dir = git_install_dir.ok_or(PathError::MissingInstallDir)?;
+            let relative = self.value[PREFIX.len()..].to_path().map_err(PathError::Utf8)?;
+            return Ok(install_dir.join(relative).into());
+        }
+        if self.value.starts_with(USER_HOME) {
+            let mut path = b"/".to_vec();
+            path.extend_from_slice(&self.value);
+            let (user, path) = git_url::expand_path::parse(path.as_bstr()).map_err(PathError::UserInterpolation)?;
+            return git_url::expand_path(user.as_ref(), path.as_bstr())
+                .map(Cow::Owned)
+                .map_err(PathError::UserInterpolation);
+        }
+        let path: Cow<'a, std::path::Path> = match self.value {
+            Cow::Borrowed(value) => value.to_path().map_err(PathError::Utf8)?.into(),
+            Cow::Owned(value) => value.to_path().map_err(PathError::Utf8)?.to_owned().into(),
+        };
+        Ok(match base {
+            Some(base) if path.is_relative() => base.join(path).into(),
+            _ => path,
+        })
+    }
+}
+
+impl<'a> From<Cow<'a, [u8]>> for Path<'a> {
+    #[inline]
+    fn from(value: Cow<'a, [u8]>) -> Self {
+        Self {
+            value: normalize_cow(value),
+        }
+    }
+}
+
 #[cfg(test)]
 mod normalize {
     use super::normalize_str;
@@ -125,3 +125,88 @@ fn value_names_are_case_insensitive() -> Result<(), Box<dyn std::error::Error>>
 
     Ok(())
 }
+
+#[test]
+fn get_value_for_paths() -> Result<(), Box<dyn std::error::Error>> {
+    let config = r#"
+        [core]
+            quoted = "/absolute/path"
+            relative = hooks
+            prefixed = %(prefix)/share/git
+            home = ~/.gitignore
+    "#;
+    let file = GitConfig::try_from(config)?;
+    let path = |key| file.value::<Path>("core", None, key);
+
+    assert_eq!(path("quoted")?.value, Cow::<[u8]>::Borrowed(b"/absolute/path"));
+    assert_eq!(
+        path("quoted")?.interpolate(None, Some("/base".as_ref()))?,
+        std::path::Path::new("/absolute/path")
+    );
+    assert_eq!(
+        path("relative")?.interpolate(None, Some("/base".as_ref()))?,
+        std::path::Path::new("/base/hooks")
+    );
+    assert_eq!(
+        path("relative")?.interpolate(None, None)?,
+        std::path::Path::new("hooks")
+    );
+    assert_eq!(
+        path("prefixed")?.interpolate(Some("/usr".as_ref()), None)?,
+        std::path::Path::new("/usr/share/git")
+    );
+    assert!(matches!(
+        path("prefixed")?.interpolate(None, None),
+        Err(PathError::MissingInstallDir)
+    ));
+    let home = std::path::PathBuf::from(std::env::var_os("HOME").expect("HOME is set"));
+    assert_eq!(path("home")?.interpolate(None, None)?, home.join(".gitignore"));
+    Ok(())
+}
+
+#[test]
+fn renamed_sections_can_be_looked_up_by_their_new_name() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = GitConfig::try_from(
+        "[remote \"origin\"]\n\turl = a\n[core]\n\tbare = true\n[remote \"upstream\"]\n\turl = b\n",
+    )?;
+    config.rename_section("remote", Some("origin"), "remote", Some(Cow::Borrowed("upstream")))?;
+    assert_eq!(
+        config.get_raw_value("remote", Some("upstream"), "url")?,
+        Cow::<[u8]>::Borrowed(b"b"),
+        "the renamed section comes first, so the later one still wins"
+    );
+    assert!(config.get_raw_value("remote", Some("origin"), "url").is_err());
+
+    config.rename_section("core", None, "remote", Some(Cow::Borrowed("core")))?;
+    config.rename_section("remote", Some("upstream"), "other", None)?;
+    assert_eq!(config.get_raw_value("other", None, "url")?, Cow::<[u8]>::Borrowed(b"b"));
+    assert!(config.get_raw_value("core", None, "bare").is_err());
+    assert_eq!(
+        config.to_string(),
+        "[remote \"upstream\"]\n\turl = a\n[remote \"core\"]\n\tbare = true\n[other]\n\turl = b\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn sections_by_name_with_header() {
+    let config = r#"
+    [submodule "a"]
+        path = a
+    [Submodule "b"]
+        path = b
+    [submodule]
+        path = c
+    [core]
+        bare = false
+    "#;
+
+    let config = GitConfig::try_from(config).unwrap();
+    let sections = config.sections_by_name_with_header("submodule");
+    let names: Vec<_> = sections
+        .iter()
+        .map(|(header, _)| header.subsection_name.as_deref())
+        .collect();
+    assert_eq!(names, vec![Some("a"), Some("b"), None], "names are case-insensitive");
+    assert_eq!(sections[1].1.value(&"path".into()), Some(Cow::Borrowed(&b"b"[..])));
+}
@@ -0,0 +1,119 @@
+use git_config::file::{GitConfig, GitConfigError};
+use git_config::fs::{Config, InvalidCliValue, Origin, Source};
+use std::borrow::Cow;
+use std::convert::TryFrom;
+
+fn origin(source: Source) -> Origin {
+    Origin { source, path: None }
+}
+
+#[test]
+fn files_are_ordered_by_source_and_later_values_win() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = Config::new();
+    config.push(
+        origin(Source::Repository),
+        GitConfig::try_from("[core]\na = repo\nm = 2")?,
+    );
+    config.push(
+        origin(Source::System),
+        GitConfig::try_from("[core]\na = system\nb = system\nm = 1")?,
+    );
+    config.push_cli_values(vec!["core.m=3", "remote.origin.url=https://example.com"])?;
+
+    assert_eq!(
+        config.files().map(|(origin, _)| origin.source).collect::<Vec<_>>(),
+        vec![Source::System, Source::Repository, Source::Cli]
+    );
+    assert_eq!(config.get_raw_value("core", None, "a")?, Cow::<[u8]>::Borrowed(b"repo"));
+    let (value, origin) = config.get_raw_value_with_origin("core", None, "b")?;
+    assert_eq!(value, Cow::<[u8]>::Borrowed(b"system"));
+    assert_eq!(origin.source, Source::System);
+    assert_eq!(
+        config.get_raw_multi_value("core", None, "m")?,
+        vec![Cow::<[u8]>::Borrowed(b"1"), Cow::Borrowed(b"2"), Cow::Borrowed(b"3")]
+    );
+    assert_eq!(
+        config.get_raw_value("remote", Some("origin"), "url")?,
+        Cow::<[u8]>::Borrowed(b"https://example.com")
+    );
+    assert_eq!(
+        config.get_raw_value("core", None, "missing"),
+        Err(GitConfigError::KeyDoesNotExist)
+    );
+    Ok(())
+}
+
+#[test]
+fn cli_values_need_a_section_and_a_key() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = Config::new();
+    for invalid in &["key", ".key", "section.", "=value"] {
+        assert_eq!(
+            config.push_cli_values(Some(*invalid)),
+            Err(InvalidCliValue((*invalid).to_owned()))
+        );
+    }
+    config.push_cli_values(Some("core.implicit"))?;
+    assert!(bool::from(
+        config.value::<git_config::values::Boolean>("core", None, "implicit")?
+    ));
+    Ok(())
+}
+
+#[test]
+fn missing_files_are_ignored() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = Config::new();
+    config.push_file(Source::Global, "this/file/does/not/exist", &Default::default())?;
+    assert_eq!(config.files().count(), 0);
+    Ok(())
+}
+
+#[test]
+fn hasconfig_conditions_see_the_remote_urls_of_all_files() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let write = |name: &str, content: &str| std::fs::write(dir.path().join(name), content);
+    write(
+        "global",
+        "[includeIf \"hasconfig:remote.*.url:https://example.com/**\"]\n  path = example\n\
+         [includeIf \"hasconfig:remote.*.url:https://other.com/**\"]\n  path = other\n\
+         [includeIf \"hasconfig:remote.*.url:https://cli.com/**\"]\n  path = cli\n",
+    )?;
+    write(
+        "example",
+        "[core]\n  example = true\n[remote \"included\"]\n  url = https://other.com/repo\n",
+    )?;
+    write("other", "[core]\n  other = true\n")?;
+    write("cli", "[core]\n  cli = true\n")?;
+    write("repo", "[remote \"origin\"]\n  url = https://example.com/repo\n")?;
+
+    let mut config = Config::new();
+    config.push_file(Source::Global, dir.path().join("global"), &Default::default())?;
+    assert!(config.get_raw_value("core", None, "example").is_err());
+
+    config.push_file(Source::Repository, dir.path().join("repo"), &Default::default())?;
+    assert_eq!(
+        config.get_raw_value("core", None, "example")?,
+        Cow::<[u8]>::Borrowed(b"true"),
+        "the remote url of a later file is seen by the conditions of an earlier one"
+    );
+    assert!(
+        config.get_raw_value("core", None, "other").is_err(),
+        "remote urls of files included by hasconfig conditions don't count"
+    );
+    assert_eq!(
+        config.files().map(|(origin, _)| origin.source).collect::<Vec<_>>(),
+        vec![Source::Global, Source::Repository]
+    );
+
+    config.push_cli_values(Some("remote.cli.url=https://cli.com/repo"))?;
+    assert!(config.get_raw_value("core", None, "cli").is_err());
+    config.resolve_hasconfig_includes()?;
+    assert_eq!(
+        config.get_raw_value("core", None, "cli")?,
+        Cow::<[u8]>::Borrowed(b"true")
+    );
+    assert_eq!(
+        config.get_raw_value("core", None, "example")?,
+        Cow::<[u8]>::Borrowed(b"true")
+    );
+    Ok(())
+}
@@ -0,0 +1,165 @@
+use git_config::file::{include, GitConfig};
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+fn write(dir: &Path, name: &str, content: &str) -> std::io::Result<()> {
+    let path = dir.join(name);
+    fs::create_dir_all(path.parent().expect("parent"))?;
+    fs::write(path, content)
+}
+
+fn value(config: &GitConfig<'_>, key: &str) -> Option<Vec<u8>> {
+    config.get_raw_value("core", None, key).ok().map(Cow::into_owned)
+}
+
+#[test]
+fn includes_are_resolved_relative_to_the_including_file_and_remember_their_origin() -> Result {
+    let dir = tempfile::tempdir()?;
+    write(
+        dir.path(),
+        "config",
+        "# root\n[core]\n  a = root\n  b = root\n[include]\n  path = sub/first\n  path = missing\n[core]\n  b = after\n",
+    )?;
+    write(
+        dir.path(),
+        "sub/first",
+        "# first\n[core]\n  a = first\n  b = first\n[include]\n  path = second\n",
+    )?;
+    write(dir.path(), "sub/second", "[other]\n  c = second\n")?;
+
+    let config = GitConfig::from_path_with_includes(dir.path().join("config"), &Default::default())?;
+    assert_eq!(
+        value(&config, "a").as_deref(),
+        Some(&b"first"[..]),
+        "included values override preceding ones"
+    );
+    assert_eq!(
+        value(&config, "b").as_deref(),
+        Some(&b"after"[..]),
+        "values after the include win"
+    );
+    assert_eq!(
+        config.get_raw_value("other", None, "c")?,
+        Cow::<[u8]>::Borrowed(b"second")
+    );
+
+    let first = dir.path().join("sub/first");
+    let second = dir.path().join("sub/second");
+    assert_eq!(config.include_paths(), vec![first.as_path(), second.as_path()]);
+    assert_eq!(config.section_path("other", None)?, Some(second.as_path()));
+    assert_eq!(
+        config.section_path("core", None)?,
+        None,
+        "the last core section is in the root file"
+    );
+
+    assert_eq!(
+        config.to_bytes_of(None),
+        fs::read(dir.path().join("config"))?,
+        "each file can be serialized on its own"
+    );
+    assert_eq!(config.to_bytes_of(Some(&first)), fs::read(&first)?);
+    assert_eq!(config.to_bytes_of(Some(&second)), fs::read(&second)?);
+    Ok(())
+}
+
+#[test]
+fn conditional_includes() -> Result {
+    let dir = tempfile::tempdir()?;
+    let repo = dir.path().join("work/Repo/.git");
+    fs::create_dir_all(&repo)?;
+    write(
+        dir.path(),
+        "config",
+        r#"[remote "origin"]
+  url = https://example.com/org/repo
+[includeIf "gitdir:work/"]
+  path = gitdir
+[includeIf "gitdir:work/repo/"]
+  path = gitdir-case-sensitive
+[includeIf "gitdir/i:work/repo/"]
+  path = gitdir-case-insensitive
+[includeIf "gitdir:./work/Repo/.git"]
+  path = gitdir-relative
+[includeIf "onbranch:feature/"]
+  path = onbranch
+[includeIf "hasconfig:remote.*.url:https://example.com/**"]
+  path = hasconfig
+[includeIf "hasconfig:remote.*.url:https://other.com/**"]
+  path = hasconfig-other
+[includeIf "unknown:condition"]
+  path = unknown
+"#,
+    )?;
+    for name in &[
+        "gitdir",
+        "gitdir-case-sensitive",
+        "gitdir-case-insensitive",
+        "gitdir-relative",
+        "onbranch",
+        "hasconfig",
+        "hasconfig-other",
+        "unknown",
+    ] {
+        write(dir.path(), name, &format!("[core]\n  {} = true\n", name))?;
+    }
+
+    let options = include::Options {
+        git_dir: Some(repo),
+        branch_name: Some("refs/heads/feature/topic".into()),
+        ..Default::default()
+    };
+    let config = GitConfig::from_path_with_includes(dir.path().join("config"), &options)?;
+    for (name, expected) in &[
+        ("gitdir", true),
+        ("gitdir-case-sensitive", false),
+        ("gitdir-case-insensitive", true),
+        ("gitdir-relative", true),
+        ("onbranch", true),
+        ("hasconfig", true),
+        ("hasconfig-other", false),
+        ("unknown", false),
+    ] {
+        assert_eq!(value(&config, name).is_some(), *expected, "{}", name);
+    }
+
+    let config = GitConfig::from_path_with_includes(dir.path().join("config"), &Default::default())?;
+    assert_eq!(
+        config.include_paths(),
+        vec![dir.path().join("hasconfig").as_path()],
+        "without repository information only the hasconfig condition can match"
+    );
+    Ok(())
+}
+
+#[test]
+fn cycles_and_deep_nesting_are_errors() -> Result {
+    let dir = tempfile::tempdir()?;
+    write(dir.path(), "a", "[include]\n  path = b\n")?;
+    write(dir.path(), "b", "[include]\n  path = a\n")?;
+    assert!(matches!(
+        GitConfig::from_path_with_includes(dir.path().join("a"), &Default::default()),
+        Err(include::Error::Cycle(path)) if path == dir.path().join("a")
+    ));
+
+    write(dir.path(), "0", "[include]\n  path = 1\n")?;
+    write(dir.path(), "1", "[include]\n  path = 2\n")?;
+    write(dir.path(), "2", "[core]\n  deep = true\n")?;
+    let options = include::Options {
+        max_depth: 1,
+        ..Default::default()
+    };
+    assert!(matches!(
+        GitConfig::from_path_with_includes(dir.path().join("0"), &options),
+        Err(include::Error::DepthExceeded(_, 1))
+    ));
+    let options = include::Options {
+        max_depth: 2,
+        ..Default::default()
+    };
+    assert!(GitConfig::from_path_with_includes(dir.path().join("0"), &options).is_ok());
+    Ok(())
+}
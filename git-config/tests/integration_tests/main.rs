@@ -5,4 +5,11 @@
 // less build artifacts.
 
 mod file_integeration_test;
+mod fs_integration_test;
+mod include_integration_test;
 mod parser_integration_tests;
+mod persist_integration_test;
+#[cfg(feature = "serde1")]
+mod serde_integration_test;
+mod typed_integration_test;
+mod values_integration_test;
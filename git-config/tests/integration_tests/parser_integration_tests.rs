@@ -207,6 +207,32 @@ fn newline_events_are_merged() {
     );
 }
 
+#[test]
+fn empty_values_end_at_the_newline_or_comment() {
+    assert_eq!(
+        parse_from_str("[core]\na =\nb=;c\nc").unwrap().into_vec(),
+        vec![
+            section_header_event("core", None),
+            newline(),
+            name("a"),
+            whitespace(" "),
+            separator(),
+            value(""),
+            newline(),
+            name("b"),
+            separator(),
+            value(""),
+            Event::Comment(git_config::parser::ParsedComment {
+                comment_tag: ';',
+                comment: Cow::Borrowed(b"c"),
+            }),
+            newline(),
+            name("c"),
+            value(""),
+        ]
+    );
+}
+
 #[test]
 fn error() {
     let input = "[core] a=b\n 4a=3";
@@ -0,0 +1,114 @@
+use git_config::file::{persist, GitConfig};
+use std::fs;
+
+type Result = std::result::Result<(), Box<dyn std::error::Error>>;
+
+#[test]
+fn edits_are_written_back_preserving_comments_and_whitespace() -> Result {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("config");
+    fs::write(
+        &path,
+        "# a comment\n[core]\n\tbare = false ; trailing\n\n[remote \"origin\"]\n\turl = old\n",
+    )?;
+
+    let mut config = GitConfig::open(&path)?;
+    config.set_raw_value("remote", Some("origin"), "url", b"new".to_vec())?;
+    config
+        .new_section("user", None)
+        .push("name".into(), b"me".to_vec().into());
+    config.write_to_path(&path, persist::Fail::Immediately)?;
+
+    assert_eq!(
+        fs::read_to_string(&path)?,
+        "# a comment\n[core]\n\tbare = false ; trailing\n\n[remote \"origin\"]\n\turl=new\n[user]\n  name=me\n"
+    );
+    assert!(!dir.path().join("config.lock").exists(), "the lock is gone");
+    Ok(())
+}
+
+#[test]
+fn included_files_are_written_back_separately() -> Result {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("config");
+    let included = dir.path().join("included");
+    fs::write(&path, "[include]\n\tpath = included\n[core]\n\ta = root\n")?;
+    fs::write(&included, "# included\n[other]\n\tb = old\n")?;
+
+    let mut config = GitConfig::from_path_with_includes(&path, &Default::default())?;
+    config.set_raw_value("other", None, "b", b"new".to_vec())?;
+    config.write_to_path(&path, persist::Fail::Immediately)?;
+
+    assert_eq!(
+        fs::read_to_string(&path)?,
+        "[include]\n\tpath = included\n[core]\n\ta = root\n"
+    );
+    assert_eq!(fs::read_to_string(&included)?, "# included\n[other]\n\tb=new\n");
+
+    config.set_raw_value("other", None, "b", b"newer".to_vec())?;
+    config.write_to_path(&path, persist::Fail::Immediately)?;
+    assert_eq!(
+        fs::read_to_string(&included)?,
+        "# included\n[other]\n\tb=newer\n",
+        "writing again compares with what was written last"
+    );
+
+    fs::write(dir.path().join("config.lock"), "")?;
+    config.set_raw_value("other", None, "b", b"newest".to_vec())?;
+    assert!(
+        matches!(
+            config.write_to_path(&path, persist::Fail::Immediately),
+            Err(persist::Error::Lock(_))
+        ),
+        "all files are locked, even the unchanged ones"
+    );
+    assert_eq!(fs::read_to_string(&included)?, "# included\n[other]\n\tb=newer\n");
+    Ok(())
+}
+
+#[test]
+fn files_changed_since_they_were_read_are_not_overwritten() -> Result {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("config");
+    let included = dir.path().join("included");
+    fs::write(&path, "[include]\n\tpath = included\n[core]\n\ta = b\n")?;
+    fs::write(&included, "[other]\n\tb = old\n")?;
+
+    let mut config = GitConfig::from_path_with_includes(&path, &Default::default())?;
+    config.set_raw_value("core", None, "a", b"c".to_vec())?;
+    fs::write(&included, "[other]\n\tb = concurrent\n")?;
+    assert!(matches!(
+        config.write_to_path(&path, persist::Fail::Immediately),
+        Err(persist::Error::Changed(changed)) if changed == included
+    ));
+    assert_eq!(
+        fs::read_to_string(&path)?,
+        "[include]\n\tpath = included\n[core]\n\ta = b\n",
+        "no file is written if one of them changed"
+    );
+    assert_eq!(fs::read_to_string(&included)?, "[other]\n\tb = concurrent\n");
+    assert!(!dir.path().join("config.lock").exists(), "locks are released");
+    Ok(())
+}
+
+#[test]
+fn locked_files_are_not_written() -> Result {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join("config");
+    fs::write(&path, "[core]\n\ta = b\n")?;
+    fs::write(dir.path().join("config.lock"), "")?;
+
+    let mut config = GitConfig::open(&path)?;
+    config.set_raw_value("core", None, "a", b"c".to_vec())?;
+    assert!(matches!(
+        config.write_to_path(&path, persist::Fail::Immediately),
+        Err(persist::Error::Lock(_))
+    ));
+    assert_eq!(fs::read_to_string(&path)?, "[core]\n\ta = b\n");
+
+    assert!(matches!(
+        GitConfig::open(dir.path().join("missing")),
+        Err(persist::Error::Read(..))
+    ));
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use serde_derive::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "serde_crate", rename_all = "lowercase")]
+enum Mode {
+    Fast,
+    Thorough,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "serde_crate")]
+struct Tool {
+    enabled: bool,
+    #[serde(rename = "cacheSize")]
+    cache_size: u64,
+    mode: Mode,
+    #[serde(default)]
+    args: Vec<String>,
+    description: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(crate = "serde_crate")]
+struct Settings {
+    user: Option<BTreeMap<String, String>>,
+    tool: BTreeMap<String, Tool>,
+}
+
+#[test]
+fn sections_subsections_and_multi_valued_keys_are_deserialized() -> Result<(), Box<dyn std::error::Error>> {
+    let settings: Settings = git_config::from_str(
+        "[User]\n  name = me\n  name = later\n\
+         [tool \"lint\"]\n  enabled = yes\n  CACHESIZE = 2k\n  mode = fast\n  args = -v\n  args = \"--fix \"\n\
+         [tool \"fmt\"]\n  enabled\n  cacheSize = 0\n  mode = thorough\n  description = \"tab\\tand \\\"quotes\\\" ; \\\\\"\n\
+         [tool \"lint\"]\n  args = --all",
+    )?;
+
+    let mut user = BTreeMap::new();
+    user.insert("name".to_owned(), "later".to_owned());
+    assert_eq!(settings.user, Some(user), "the last value wins for scalars");
+
+    let lint = &settings.tool["lint"];
+    assert!(lint.enabled);
+    assert_eq!(lint.cache_size, 2048, "keys are case-insensitive and suffixes apply");
+    assert_eq!(lint.mode, Mode::Fast);
+    assert_eq!(
+        lint.args,
+        vec!["-v", "--fix ", "--all"],
+        "values of sections with the same name are merged"
+    );
+    assert_eq!(lint.description, None);
+
+    let fmt = &settings.tool["fmt"];
+    assert!(fmt.enabled, "implicit values are true");
+    assert!(fmt.args.is_empty());
+    assert_eq!(fmt.description.as_deref(), Some("tab\tand \"quotes\" ; \\"));
+    Ok(())
+}
+
+#[test]
+fn empty_values_are_false_unlike_keys_without_equals_sign() -> Result<(), Box<dyn std::error::Error>> {
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct Flags {
+        empty: bool,
+        quoted: bool,
+        implicit: bool,
+        overridden: bool,
+        all: Vec<String>,
+    }
+    let flags: BTreeMap<String, Flags> = git_config::from_str(
+        "[flags]\n  empty =\n  quoted = \"\"\n  implicit\n  overridden\n  overridden =\n  all\n  all = x\n",
+    )?;
+    assert_eq!(
+        flags["flags"],
+        Flags {
+            empty: false,
+            quoted: false,
+            implicit: true,
+            overridden: false,
+            all: vec!["".into(), "x".into()],
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_values_name_their_key() {
+    let err = git_config::from_str::<Settings>("[tool \"lint\"]\n  enabled = maybe\n  cacheSize = 1\n  mode = fast")
+        .expect_err("not a boolean");
+    assert_eq!(
+        err.to_string(),
+        "Invalid value 'maybe' of 'tool.lint.enabled': expected a boolean."
+    );
+
+    let err = git_config::from_str::<Settings>("[tool \"lint\"]\n  enabled\n  cacheSize = -1\n  mode = fast")
+        .expect_err("negative");
+    assert!(matches!(err, git_config::Error::InvalidValue { key, .. } if key == "tool.lint.cacheSize"));
+}
+
+#[test]
+fn serialized_values_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let mut tool = BTreeMap::new();
+    tool.insert(
+        "lint".to_owned(),
+        Tool {
+            enabled: true,
+            cache_size: 4096,
+            mode: Mode::Thorough,
+            args: vec!["-v".into(), " padded ".into()],
+            description: Some("a \"quoted\"\tvalue # with\\ comment chars;\n".into()),
+        },
+    );
+    tool.insert(
+        "fmt".to_owned(),
+        Tool {
+            enabled: false,
+            cache_size: 0,
+            mode: Mode::Fast,
+            args: Vec::new(),
+            description: None,
+        },
+    );
+    let mut user = BTreeMap::new();
+    user.insert("email".to_owned(), "me@example.com".to_owned());
+    let settings = Settings { user: Some(user), tool };
+
+    let serialized = git_config::to_string(&settings)?;
+    assert_eq!(
+        serialized,
+        "[user]\n  email=me@example.com\n\
+         [tool \"fmt\"]\n  enabled=false\n  cacheSize=0\n  mode=fast\n\
+         [tool \"lint\"]\n  enabled=true\n  cacheSize=4096\n  mode=thorough\n  args=-v\n  args=\" padded \"\n  \
+         description=\"a \\\"quoted\\\"\\tvalue # with\\\\ comment chars;\\n\"\n"
+    );
+    assert_eq!(git_config::from_str::<Settings>(&serialized)?, settings);
+    Ok(())
+}
+
+#[test]
+fn unrepresentable_values_are_rejected() {
+    #[derive(Serialize)]
+    #[serde(crate = "serde_crate")]
+    struct InvalidKey {
+        section: BTreeMap<&'static str, u8>,
+    }
+    let mut section = BTreeMap::new();
+    section.insert("snake_case", 1);
+    assert_eq!(
+        git_config::to_string(&InvalidKey { section })
+            .expect_err("invalid key")
+            .to_string(),
+        "'snake_case' is not a valid key name"
+    );
+
+    assert!(git_config::to_string(&vec![1, 2]).is_err(), "sections must be named");
+}
@@ -0,0 +1,172 @@
+use git_config::file::GitConfig;
+use git_config::fs::{Config, Origin, Source};
+use git_config::typed::{AutoCrlf, Branch, Core, Gc, Http, Pack, Remote, User};
+use std::convert::TryFrom;
+use std::path::PathBuf;
+
+fn config(repository: &'static str) -> Result<Config, Box<dyn std::error::Error>> {
+    let mut config = Config::new();
+    config.push(
+        Origin {
+            source: Source::Repository,
+            path: Some(PathBuf::from("/repo/.git/config")),
+        },
+        GitConfig::try_from(repository)?,
+    );
+    Ok(config)
+}
+
+#[test]
+fn unset_keys_have_git_defaults() -> Result<(), Box<dyn std::error::Error>> {
+    let config = config("")?;
+    assert_eq!(Core::from_config(&config)?, Core::default());
+    assert_eq!(Core::default().abbrev_len(40), 7);
+    assert_eq!(User::from_config(&config), User::default());
+    assert_eq!(Pack::from_config(&config)?.window, 10);
+    assert_eq!(Pack::from_config(&config)?.depth, 50);
+    assert_eq!(Gc::from_config(&config)?.auto, 6700);
+    assert_eq!(Gc::from_config(&config)?.prune_expire, "2.weeks.ago");
+    let http = Http::from_config(&config)?;
+    assert!(http.ssl_verify);
+    assert_eq!(http.post_buffer, 1024 * 1024);
+    assert_eq!(Remote::from_config(&config, "origin")?, None);
+    assert_eq!(Branch::from_config(&config, "main")?, None);
+    Ok(())
+}
+
+#[test]
+fn values_are_interpreted_with_later_files_overriding_earlier_ones() -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = config(
+        "[core]\n  bare = false\n  abbrev = 12\n  autocrlf = input\n  fileMode = 0\n  logAllRefUpdates = always\n\
+         [pack]\n  threads = 0\n  windowMemory = 1m\n\
+         [branch \"main\"]\n  remote = origin\n  merge = refs/heads/main\n  rebase = merges\n\
+         [http]\n  extraHeader = a: 1\n  extraHeader = b: 2",
+    )?;
+    config.push_cli_values(vec!["core.abbrev=no", "user.name=cli", "pack.threads=4"])?;
+
+    let core = Core::from_config(&config)?;
+    assert_eq!(core.bare, Some(false));
+    assert_eq!(core.abbrev, Some(40), "'no' disables abbreviation");
+    assert_eq!(core.auto_crlf, AutoCrlf::Input);
+    assert!(!core.file_mode, "integers are booleans too");
+    assert_eq!(core.log_all_ref_updates, Some(true));
+    assert_eq!(
+        User::from_config(&config).name.as_ref().map(|n| n.as_slice()),
+        Some(&b"cli"[..])
+    );
+
+    let pack = Pack::from_config(&config)?;
+    assert_eq!(pack.threads, Some(4));
+    assert_eq!(pack.window_memory, 1024 * 1024);
+
+    let branch = Branch::from_config(&config, "refs/heads/main")?.expect("present");
+    assert_eq!(branch.name, "main");
+    assert_eq!(branch.remote.expect("set"), "origin");
+    assert_eq!(branch.merge.expect("set"), "refs/heads/main");
+    assert_eq!(branch.rebase, Some(true));
+    assert_eq!(Http::from_config(&config)?.extra_headers, vec!["a: 1", "b: 2"]);
+    Ok(())
+}
+
+#[test]
+fn only_keys_without_equals_sign_are_implicitly_true() -> Result<(), Box<dyn std::error::Error>> {
+    let config = config(
+        "[core]\n  bare =\n  fileMode = \"\"\n  symlinks\n  abbrev =\n\
+         [remote \"origin\"]\n  url = https://example.com\n  prune\n  mirror =",
+    )?;
+    let core = Core::from_config(&config)?;
+    assert_eq!(core.bare, Some(false), "an empty value is false");
+    assert!(!core.file_mode, "an empty quoted value is false as well");
+    assert!(core.symlinks, "a key without value is true");
+    assert_eq!(core.abbrev, Some(40), "empty values disable abbreviation like 'false'");
+
+    let remote = Remote::from_config(&config, "origin")?.expect("present");
+    assert_eq!(remote.prune, Some(true));
+    assert!(!remote.mirror);
+
+    let last_is_implicit = self::config("[core]\n  symlinks =\n[core]\n  symlinks")?;
+    assert!(Core::from_config(&last_is_implicit)?.symlinks, "the last value counts");
+    let last_is_empty = self::config("[core]\n  symlinks\n  symlinks = \n")?;
+    assert!(!Core::from_config(&last_is_empty)?.symlinks);
+    Ok(())
+}
+
+#[test]
+fn invalid_values_name_key_and_file() -> Result<(), Box<dyn std::error::Error>> {
+    let config = config("[core]\n  abbrev = 3\n[remote \"origin\"]\n  prune = maybe")?;
+    let err = Core::from_config(&config).expect_err("out of range");
+    assert_eq!(err.key, "core.abbrev");
+    assert_eq!(err.value, "3");
+    assert_eq!(
+        err.origin.path.as_deref(),
+        Some(PathBuf::from("/repo/.git/config").as_path())
+    );
+    assert_eq!(
+        err.to_string(),
+        "Invalid value '3' of 'core.abbrev' in '/repo/.git/config': expected 'auto', a boolean or an integer from 4 to 40."
+    );
+
+    let err = Remote::from_config(&config, "origin").expect_err("not a boolean");
+    assert_eq!(err.key, "remote.origin.prune");
+
+    let mut config = Config::new();
+    config.push_cli_values(Some("gc.auto=-1"))?;
+    assert_eq!(
+        Gc::from_config(&config).expect_err("negative").to_string(),
+        "Invalid value '-1' of 'gc.auto' on the command-line: expected a non-negative integer."
+    );
+    Ok(())
+}
+
+#[test]
+fn remote_urls_are_rewritten() -> Result<(), Box<dyn std::error::Error>> {
+    let config = config(
+        "[url \"https://github.com/\"]\n  insteadOf = gh:\n  insteadOf = https://gh.example.com/\n\
+         [url \"ssh://git@github.com/\"]\n  pushInsteadOf = https://github.com/\n  insteadOf = gh:private/\n\
+         [remote \"origin\"]\n  url = gh:byron/gitoxide\n  fetch = +refs/heads/*:refs/remotes/origin/*\n\
+         [remote \"private\"]\n  url = gh:private/repo\n  pushurl = https://gh.example.com/other\n\
+         [remote \"direct\"]\n  url = https://github.com/byron/gitoxide\n\
+         [remote \"plain\"]\n  url = https://example.com/repo",
+    )?;
+    assert_eq!(Remote::names(&config), vec!["origin", "private", "direct", "plain"]);
+
+    let origin = Remote::from_config(&config, "origin")?.expect("present");
+    assert_eq!(
+        origin.url,
+        Some(git_url::Url::from_bytes(b"https://github.com/byron/gitoxide")?)
+    );
+    assert_eq!(
+        origin.push_url, None,
+        "pushInsteadOf applies to the original url, which doesn't match"
+    );
+    assert_eq!(origin.fetch, vec!["+refs/heads/*:refs/remotes/origin/*"]);
+
+    let private = Remote::from_config(&config, "private")?.expect("present");
+    assert_eq!(
+        private.url,
+        Some(git_url::Url::from_bytes(b"ssh://git@github.com/repo")?),
+        "the longest match wins"
+    );
+    assert_eq!(
+        private.push_url,
+        Some(git_url::Url::from_bytes(b"https://github.com/other")?),
+        "pushurl is rewritten by insteadOf"
+    );
+
+    let direct = Remote::from_config(&config, "direct")?.expect("present");
+    assert_eq!(
+        direct.push_url,
+        Some(git_url::Url::from_bytes(b"ssh://git@github.com/byron/gitoxide")?)
+    );
+
+    let plain = Remote::from_config(&config, "plain")?.expect("present");
+    assert_eq!(plain.push_url, None);
+
+    let rewrite = git_config::typed::UrlRewrite::from_config(&config);
+    assert_eq!(
+        rewrite.rewrite_url(&git_url::Url::from_bytes(b"https://gh.example.com/a/b")?)?,
+        Some(git_url::Url::from_bytes(b"https://github.com/a/b")?)
+    );
+    assert_eq!(rewrite.rewrite_url(&plain.url.expect("set"))?, None);
+    Ok(())
+}
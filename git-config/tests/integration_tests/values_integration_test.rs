@@ -0,0 +1,43 @@
+mod path {
+    use git_config::values::{Path, PathError};
+    use std::borrow::Cow;
+
+    fn interpolate(value: &str, base: Option<&str>) -> Result<std::path::PathBuf, PathError> {
+        Path::from(Cow::Borrowed(value.as_bytes()))
+            .interpolate(Some("/prefix".as_ref()), base.map(AsRef::as_ref))
+            .map(Cow::into_owned)
+    }
+
+    #[test]
+    fn quotes_are_removed() {
+        assert_eq!(
+            Path::from(Cow::Borrowed(&b"\"a b\""[..])).value,
+            Cow::<[u8]>::Borrowed(b"a b")
+        );
+    }
+
+    #[test]
+    fn prefix_is_substituted() {
+        assert_eq!(
+            interpolate("%(prefix)/etc", None).unwrap(),
+            std::path::Path::new("/prefix/etc")
+        );
+        assert_eq!(
+            interpolate("%(prefix)", Some("/base")).unwrap(),
+            std::path::Path::new("/base/%(prefix)"),
+            "only a prefix followed by a slash is substituted"
+        );
+    }
+
+    #[test]
+    fn relative_paths_are_resolved_against_the_base() {
+        assert_eq!(
+            interpolate("a/b", Some("/base")).unwrap(),
+            std::path::Path::new("/base/a/b")
+        );
+        assert_eq!(
+            interpolate("/a/b", Some("/base")).unwrap(),
+            std::path::Path::new("/a/b")
+        );
+    }
+}
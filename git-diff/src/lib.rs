@@ -4,3 +4,6 @@
 
 ///
 pub mod tree;
+
+/// Diff sequences of lines, as a basis for patches and merges of blobs.
+pub mod lines;
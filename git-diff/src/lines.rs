@@ -0,0 +1,317 @@
+use std::ops::Range;
+
+/// A change between two sequences of lines, expressed as the range of lines in `before` which are replaced by the range of lines in `after`.
+///
+/// Empty ranges denote pure additions or deletions, with their `start` marking the position at which the change applies.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Change {
+    /// The lines in the original sequence that are removed.
+    pub before: Range<usize>,
+    /// The lines in the new sequence which take their place.
+    pub after: Range<usize>,
+}
+
+/// Split `data` into lines, each of which retains its terminating `\n` if present.
+///
+/// Only the last line may lack the newline, and an empty `data` buffer yields no lines at all.
+pub fn split(data: &[u8]) -> Vec<&[u8]> {
+    let mut lines: Vec<_> = data.split_inclusive(|b| *b == b'\n').collect();
+    if data.is_empty() {
+        lines.clear();
+    }
+    lines
+}
+
+/// Compute the [changes][Change] needed to turn `before` into `after`, ordered by their position.
+///
+/// The diff is computed with the linear-space variant of [Myers' algorithm][myers], and ambiguous changes are shifted similar to
+/// how git does it by sliding them down as far as possible unless they can be aligned with a change on the other side.
+/// Note that the result isn't guaranteed to be identical to the one of `git diff` as heuristics differ.
+///
+/// [myers]: http://www.xmailserver.org/diff2.pdf
+pub fn changes<T: PartialEq>(before: &[T], after: &[T]) -> Vec<Change> {
+    let mut removed = Changed::new(before.len());
+    let mut added = Changed::new(after.len());
+    let max_d = (before.len() + after.len() + 1) / 2 + 1;
+    let mut state = State {
+        forward: vec![0; 2 * max_d + 2],
+        backward: vec![0; 2 * max_d + 2],
+    };
+    state.compare(before, 0..before.len(), after, 0..after.len(), &mut removed, &mut added);
+
+    compact(before, &mut removed, &added);
+    compact(after, &mut added, &removed);
+
+    let mut out = Vec::new();
+    let (mut i1, mut i2) = (0, 0);
+    while i1 < before.len() || i2 < after.len() {
+        if removed.get(i1) || added.get(i2) {
+            let (start1, start2) = (i1, i2);
+            while removed.get(i1) {
+                i1 += 1;
+            }
+            while added.get(i2) {
+                i2 += 1;
+            }
+            out.push(Change {
+                before: start1..i1,
+                after: start2..i2,
+            });
+        } else {
+            i1 += 1;
+            i2 += 1;
+        }
+    }
+    out
+}
+
+/// Flags for each line of a sequence with a sentinel on either end, to know whether it is part of a change.
+struct Changed(Vec<bool>);
+
+impl Changed {
+    fn new(len: usize) -> Self {
+        Changed(vec![false; len + 2])
+    }
+    fn len(&self) -> usize {
+        self.0.len() - 2
+    }
+    fn get(&self, idx: usize) -> bool {
+        self.0.get(idx + 1).copied().unwrap_or(false)
+    }
+    fn get_before(&self, idx: usize) -> bool {
+        self.0[idx]
+    }
+    fn set(&mut self, idx: usize, value: bool) {
+        self.0[idx + 1] = value;
+    }
+}
+
+struct State {
+    forward: Vec<usize>,
+    backward: Vec<usize>,
+}
+
+impl State {
+    fn compare<T: PartialEq>(
+        &mut self,
+        a: &[T],
+        mut ra: Range<usize>,
+        b: &[T],
+        mut rb: Range<usize>,
+        removed: &mut Changed,
+        added: &mut Changed,
+    ) {
+        while !ra.is_empty() && !rb.is_empty() && a[ra.start] == b[rb.start] {
+            ra.start += 1;
+            rb.start += 1;
+        }
+        while !ra.is_empty() && !rb.is_empty() && a[ra.end - 1] == b[rb.end - 1] {
+            ra.end -= 1;
+            rb.end -= 1;
+        }
+        if ra.is_empty() {
+            rb.for_each(|idx| added.set(idx, true));
+            return;
+        }
+        if rb.is_empty() {
+            ra.for_each(|idx| removed.set(idx, true));
+            return;
+        }
+        match self.middle_snake(a, ra.clone(), b, rb.clone()) {
+            Some((x, y)) => {
+                self.compare(a, ra.start..x, b, rb.start..y, removed, added);
+                self.compare(a, x..ra.end, b, y..rb.end, removed, added);
+            }
+            None => {
+                ra.for_each(|idx| removed.set(idx, true));
+                rb.for_each(|idx| added.set(idx, true));
+            }
+        }
+    }
+
+    /// Find the point at which the shortest edit script can be split in two, as absolute positions in `a` and `b`.
+    fn middle_snake<T: PartialEq>(
+        &mut self,
+        a: &[T],
+        ra: Range<usize>,
+        b: &[T],
+        rb: Range<usize>,
+    ) -> Option<(usize, usize)> {
+        let (n, m) = (ra.len() as isize, rb.len() as isize);
+        let delta = n - m;
+        let odd = delta & 1 == 1;
+        let max_d = (n + m + 1) / 2 + 1;
+        let offset = max_d;
+        let idx = |k: isize| (k + offset) as usize;
+        self.forward[idx(1)] = 0;
+        self.backward[idx(1)] = 0;
+
+        for d in 0..max_d {
+            let mut k = d;
+            while k >= -d {
+                let mut x = if k == -d || (k != d && self.forward[idx(k - 1)] < self.forward[idx(k + 1)]) {
+                    self.forward[idx(k + 1)]
+                } else {
+                    self.forward[idx(k - 1)] + 1
+                } as isize;
+                let mut y = x - k;
+                let (x0, y0) = (x, y);
+                while x < n && y < m && y >= 0 && a[ra.start + x as usize] == b[rb.start + y as usize] {
+                    x += 1;
+                    y += 1;
+                }
+                self.forward[idx(k)] = x as usize;
+                if odd && (k - delta).abs() < d && x + self.backward[idx(delta - k)] as isize >= n {
+                    return Some((ra.start + x0 as usize, rb.start + y0 as usize));
+                }
+                k -= 2;
+            }
+
+            let mut k = d;
+            while k >= -d {
+                let mut x = if k == -d || (k != d && self.backward[idx(k - 1)] < self.backward[idx(k + 1)]) {
+                    self.backward[idx(k + 1)]
+                } else {
+                    self.backward[idx(k - 1)] + 1
+                } as isize;
+                let mut y = x - k;
+                while x < n
+                    && y < m
+                    && y >= 0
+                    && a[ra.start + (n - x - 1) as usize] == b[rb.start + (m - y - 1) as usize]
+                {
+                    x += 1;
+                    y += 1;
+                }
+                self.backward[idx(k)] = x as usize;
+                if !odd && (k - delta).abs() <= d && x + self.forward[idx(delta - k)] as isize >= n {
+                    return Some((ra.start + (n - x) as usize, rb.start + (m - y) as usize));
+                }
+                k -= 2;
+            }
+        }
+        None
+    }
+}
+
+/// A group of changed lines, possibly empty, as `start..end`.
+struct Group {
+    start: usize,
+    end: usize,
+}
+
+impl Group {
+    fn init(changed: &Changed) -> Self {
+        let mut end = 0;
+        while changed.get(end) {
+            end += 1;
+        }
+        Group { start: 0, end }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    fn next(&mut self, changed: &Changed) -> bool {
+        if self.end == changed.len() {
+            return false;
+        }
+        self.start = self.end + 1;
+        self.end = self.start;
+        while changed.get(self.end) {
+            self.end += 1;
+        }
+        true
+    }
+
+    fn previous(&mut self, changed: &Changed) -> bool {
+        if self.start == 0 {
+            return false;
+        }
+        self.end = self.start - 1;
+        self.start = self.end;
+        while changed.get_before(self.start) {
+            self.start -= 1;
+        }
+        true
+    }
+
+    fn slide_down<T: PartialEq>(&mut self, lines: &[T], changed: &mut Changed) -> bool {
+        if self.end < lines.len() && lines[self.start] == lines[self.end] {
+            changed.set(self.start, false);
+            changed.set(self.end, true);
+            self.start += 1;
+            self.end += 1;
+            while changed.get(self.end) {
+                self.end += 1;
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    fn slide_up<T: PartialEq>(&mut self, lines: &[T], changed: &mut Changed) -> bool {
+        if self.start > 0 && lines[self.start - 1] == lines[self.end - 1] {
+            self.start -= 1;
+            self.end -= 1;
+            changed.set(self.start, true);
+            changed.set(self.end, false);
+            while changed.get_before(self.start) {
+                self.start -= 1;
+            }
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Shift groups of changes in `lines` down as far as possible, or to where they align with a group of changes in `other`,
+/// similar to what `xdl_change_compact()` does in git.
+fn compact<T: PartialEq>(lines: &[T], changed: &mut Changed, other: &Changed) {
+    let mut g = Group::init(changed);
+    let mut go = Group::init(other);
+
+    loop {
+        if !g.is_empty() {
+            let mut earliest_end;
+            let mut end_matching_other;
+            loop {
+                let group_size = g.end - g.start;
+                end_matching_other = None;
+
+                while g.slide_up(lines, changed) {
+                    go.previous(other);
+                }
+                earliest_end = g.end;
+                if !go.is_empty() {
+                    end_matching_other = Some(g.end);
+                }
+
+                while g.slide_down(lines, changed) {
+                    go.next(other);
+                    if !go.is_empty() {
+                        end_matching_other = Some(g.end);
+                    }
+                }
+                if group_size == g.end - g.start {
+                    break;
+                }
+            }
+
+            if g.end != earliest_end && end_matching_other.is_some() {
+                while go.is_empty() {
+                    g.slide_up(lines, changed);
+                    go.previous(other);
+                }
+            }
+        }
+
+        if !g.next(changed) {
+            break;
+        }
+        go.next(other);
+    }
+}
@@ -313,7 +313,7 @@ fn handle_lhs_and_rhs_with_equal_filenames<R: tree::Visit>(
         (lhs_non_tree, rhs_non_tree) => {
             delegate.push_path_component(lhs.filename);
             debug_assert!(lhs_non_tree.is_no_tree() && rhs_non_tree.is_no_tree());
-            if lhs.oid != rhs.oid
+            if (lhs.oid != rhs.oid || lhs.mode != rhs.mode)
                 && delegate
                     .visit(Change::Modification {
                         previous_entry_mode: lhs.mode,
@@ -0,0 +1,21 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q
+git config commit.gpgsign false
+git config core.autocrlf false
+git config core.filemode true
+
+git checkout -q -b main
+
+mkdir d
+echo content > f
+echo content > d/g
+touch d/h
+git add .
+git commit -qm 'f, d/g and d/h added'
+
+chmod +x f d/g
+git commit -qam 'f and d/g made executable'
+
+git diff-tree -r -t HEAD~1 HEAD > diff-tree.expected
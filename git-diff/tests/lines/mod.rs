@@ -0,0 +1,57 @@
+use git_diff::lines::{self, Change};
+
+fn changes(before: &str, after: &str) -> Vec<Change> {
+    lines::changes(&lines::split(before.as_bytes()), &lines::split(after.as_bytes()))
+}
+
+fn change(before: std::ops::Range<usize>, after: std::ops::Range<usize>) -> Change {
+    Change { before, after }
+}
+
+#[test]
+fn split_keeps_newlines_and_handles_missing_trailing_newline() {
+    assert!(lines::split(b"").is_empty());
+    assert_eq!(lines::split(b"a\nb"), vec![&b"a\n"[..], b"b"]);
+    assert_eq!(lines::split(b"a\n\n"), vec![&b"a\n"[..], b"\n"]);
+}
+
+#[test]
+fn identical_inputs_have_no_changes() {
+    assert!(changes("", "").is_empty());
+    assert!(changes("a\nb\n", "a\nb\n").is_empty());
+}
+
+#[test]
+fn additions_deletions_and_modifications() {
+    assert_eq!(changes("", "a\nb\n"), vec![change(0..0, 0..2)]);
+    assert_eq!(changes("a\nb\n", ""), vec![change(0..2, 0..0)]);
+    assert_eq!(changes("a\nb\nc\n", "a\nB\nc\n"), vec![change(1..2, 1..2)]);
+    assert_eq!(
+        changes("a\nb\nc\nd\ne\n", "x\na\nc\nd\ny\ne\n"),
+        vec![change(0..0, 0..1), change(1..2, 2..2), change(4..4, 4..5)]
+    );
+}
+
+#[test]
+fn a_missing_trailing_newline_is_a_change() {
+    assert_eq!(changes("a\nb\n", "a\nb"), vec![change(1..2, 1..2)]);
+}
+
+#[test]
+fn ambiguous_changes_are_slid_down_like_git_does() {
+    assert_eq!(
+        changes("a\nb\n}\n\nc\n", "a\nb\n}\n\nx\n}\n\nc\n"),
+        vec![change(4..4, 4..7)],
+        "the insertion could be placed at 2..2 as well, but git moves it down as far as possible"
+    );
+    assert_eq!(changes("a\na\na\n", "a\na\n"), vec![change(2..3, 2..2)]);
+}
+
+#[test]
+fn larger_inputs_produce_minimal_diffs() {
+    let before: Vec<_> = (0..200).map(|n| n % 7).collect();
+    let after: Vec<_> = (0..200).map(|n| if n % 13 == 0 { 100 } else { n % 7 }).collect();
+    let changes = lines::changes(&before, &after);
+    assert_eq!(changes.len(), 16, "each line divisible by 13 was replaced");
+    assert!(changes.iter().all(|c| c.before.len() == 1 && c.after.len() == 1));
+}
@@ -606,5 +606,43 @@ mod changes {
             );
             Ok(())
         }
+
+        #[test]
+        fn mode_only_changes_are_modifications() -> crate::Result {
+            use std::convert::TryFrom;
+
+            let dir = git_testtools::scripted_fixture_repo_read_only("make_diff_for_mode_changes_repo.sh")?;
+            let db = linked::Store::at(dir.join(".git").join("objects"))?;
+            let all_commits = all_commits(&db);
+            let mode = |mode: &[u8]| EntryMode::try_from(mode.trim_start_with(|c| c == '0')).expect("valid mode");
+            let expected: Changes = std::fs::read(dir.join("diff-tree.expected"))?
+                .lines()
+                .map(|line| {
+                    let mut tokens = line.splitn_str(2, "\t");
+                    let (info, path) = (tokens.next().expect("info"), tokens.next().expect("path"));
+                    let fields: Vec<_> = info[1..].split_str(" ").collect();
+                    assert_eq!(fields[4], b"M", "only modifications are expected");
+                    Modification {
+                        previous_entry_mode: mode(fields[0]),
+                        previous_oid: ObjectId::from_hex(fields[2]).expect("valid hex"),
+                        entry_mode: mode(fields[1]),
+                        oid: ObjectId::from_hex(fields[3]).expect("valid hex"),
+                        path: path.into(),
+                    }
+                })
+                .collect();
+            assert_eq!(expected.len(), 3, "d, d/g and f changed");
+
+            let mut actual = diff_with_previous_commit_from(&db, &all_commits[1])?;
+            actual.sort_by(|a, b| match (a, b) {
+                (Modification { path: a, .. }, Modification { path: b, .. }) => a.cmp(b),
+                _ => unreachable!("only modifications are expected"),
+            });
+            assert_eq!(
+                actual, expected,
+                "like `git diff-tree -r -t`, the unchanged blobs are reported as modified due to their mode change"
+            );
+            Ok(())
+        }
     }
 }
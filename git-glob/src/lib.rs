@@ -0,0 +1,14 @@
+//! Match paths against glob patterns with the semantics of git, as used in `.gitignore` and `.gitattributes` files.
+//!
+//! Use [`wildmatch()`] to match a single glob against a text, or [parse][Pattern::from_bytes()] a [`Pattern`] to match
+//! it against paths relative to the directory containing the file it was read from.
+#![forbid(unsafe_code, rust_2018_idioms)]
+#![deny(missing_docs)]
+
+///
+pub mod wildmatch;
+pub use wildmatch::function::wildmatch;
+
+///
+pub mod pattern;
+pub use pattern::Pattern;
@@ -0,0 +1,148 @@
+use bitflags::bitflags;
+use bstr::{BStr, BString, ByteSlice};
+
+use crate::{wildmatch, wildmatch::function::is_glob_special};
+
+bitflags! {
+    /// Information about a [`Pattern`] obtained while parsing it.
+    pub struct Mode: u32 {
+        /// The pattern does not contain a sub-directory and thus matches the file name of paths only.
+        const NO_SUB_DIR = 1 << 0;
+        /// A pattern that is `*literal`, meaning that it ends with the literal following the asterisk.
+        const ENDS_WITH = 1 << 1;
+        /// The pattern must match a directory, and not a file.
+        const MUST_BE_DIR = 1 << 2;
+        /// The pattern was prefixed with `!`, negating its meaning.
+        const NEGATIVE = 1 << 3;
+        /// The pattern started with a slash, which was removed, to make it match from the beginning of a path only.
+        const ABSOLUTE = 1 << 4;
+    }
+}
+
+/// Describes how case is treated when matching.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Case {
+    /// The case of characters must match exactly.
+    Sensitive,
+    /// Ascii characters match regardless of their case.
+    Fold,
+}
+
+impl Default for Case {
+    fn default() -> Self {
+        Case::Sensitive
+    }
+}
+
+/// A glob pattern as found in `.gitignore` or `.gitattributes` files, optimized for quick matching.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Pattern {
+    /// The glob itself, without the prefixes and suffixes indicated by [`mode`][Pattern::mode].
+    pub text: BString,
+    /// Additional information about the pattern.
+    pub mode: Mode,
+    /// The position of the first character with special meaning in `text`, or `None` if it's a literal.
+    pub first_wildcard_pos: Option<usize>,
+}
+
+impl Pattern {
+    /// Parse a single `pattern` as found on a line of a `.gitignore` file, or return `None` if it is empty.
+    ///
+    /// Leading `!` negate the pattern, a leading `/` anchors it to the directory containing the file it was read from
+    /// and a trailing `/` makes it only match directories.
+    /// Note that comments and trailing whitespace have to be removed by the caller.
+    pub fn from_bytes(mut pattern: &[u8]) -> Option<Self> {
+        if pattern.is_empty() {
+            return None;
+        }
+        let mut mode = Mode::empty();
+        if pattern[0] == b'!' {
+            mode |= Mode::NEGATIVE;
+            pattern = &pattern[1..];
+        }
+        if pattern.last() == Some(&b'/') {
+            mode |= Mode::MUST_BE_DIR;
+            pattern = &pattern[..pattern.len() - 1];
+        }
+        if !pattern.contains(&b'/') {
+            mode |= Mode::NO_SUB_DIR;
+        }
+        if pattern.first() == Some(&b'/') {
+            mode |= Mode::ABSOLUTE;
+            pattern = &pattern[1..];
+        }
+        if pattern.is_empty() {
+            return None;
+        }
+        let first_wildcard_pos = pattern.iter().position(|b| is_glob_special(*b));
+        if pattern[0] == b'*' && !pattern[1..].iter().copied().any(is_glob_special) {
+            mode |= Mode::ENDS_WITH;
+        }
+        Some(Pattern {
+            text: pattern.into(),
+            mode,
+            first_wildcard_pos,
+        })
+    }
+
+    /// Return true if this pattern is negated, which is when it was prefixed with `!`.
+    pub fn is_negative(&self) -> bool {
+        self.mode.contains(Mode::NEGATIVE)
+    }
+
+    /// Match this pattern against `path`, which is relative to the directory containing the file the pattern was read from.
+    ///
+    /// `basename_start_pos` is the position right after the last slash in `path`, or `None` if there is no slash, and `is_dir`
+    /// is true if `path` is a directory.
+    /// Note that negation is not taken into account, so negative patterns match like positive ones.
+    pub fn matches_repo_relative_path(
+        &self,
+        path: &BStr,
+        basename_start_pos: Option<usize>,
+        is_dir: bool,
+        case: Case,
+    ) -> bool {
+        if self.mode.contains(Mode::MUST_BE_DIR) && !is_dir {
+            return false;
+        }
+        let flags = match case {
+            Case::Fold => wildmatch::Mode::IGNORE_CASE,
+            Case::Sensitive => wildmatch::Mode::empty(),
+        };
+        if self.mode.contains(Mode::NO_SUB_DIR) {
+            let basename = &path[basename_start_pos.unwrap_or(0)..];
+            if self.mode.contains(Mode::ENDS_WITH) {
+                let suffix = &self.text[1..];
+                return basename.len() >= suffix.len() && eq(&basename[basename.len() - suffix.len()..], suffix, case);
+            }
+            self.matches(basename.as_bstr(), flags)
+        } else {
+            self.matches(path, flags | wildmatch::Mode::NO_MATCH_SLASH_LITERAL)
+        }
+    }
+
+    /// Match this pattern literally against `value` with the given `mode`, without taking any of its [flags][Pattern::mode]
+    /// into account.
+    pub fn matches(&self, value: &BStr, mode: wildmatch::Mode) -> bool {
+        let case = if mode.contains(wildmatch::Mode::IGNORE_CASE) {
+            Case::Fold
+        } else {
+            Case::Sensitive
+        };
+        match self.first_wildcard_pos {
+            None => eq(value, &self.text, case),
+            Some(pos) => {
+                value.len() >= pos
+                    && eq(&value[..pos], &self.text[..pos], case)
+                    && wildmatch(self.text[pos..].as_bstr(), value[pos..].as_bstr(), mode)
+            }
+        }
+    }
+}
+
+fn eq(lhs: &[u8], rhs: &[u8], case: Case) -> bool {
+    match case {
+        Case::Sensitive => lhs == rhs,
+        Case::Fold => lhs.eq_ignore_ascii_case(rhs),
+    }
+}
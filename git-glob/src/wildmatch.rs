@@ -0,0 +1,276 @@
+use bitflags::bitflags;
+
+bitflags! {
+    /// The match mode employed in [`wildmatch()`][crate::wildmatch()].
+    pub struct Mode: u8 {
+        /// Let globs like `*` and `?` not match the slash `/` literal, which is useful when matching paths.
+        const NO_MATCH_SLASH_LITERAL = 1 << 0;
+        /// Match case insensitively for ascii characters only.
+        const IGNORE_CASE = 1 << 1;
+    }
+}
+
+pub(crate) mod function {
+    use bstr::BStr;
+
+    use crate::wildmatch::Mode;
+
+    #[derive(Eq, PartialEq)]
+    enum Result {
+        Match,
+        NoMatch,
+        AbortAll,
+        AbortToStarStar,
+    }
+
+    const STAR: u8 = b'*';
+    const BACKSLASH: u8 = b'\\';
+    const SLASH: u8 = b'/';
+    const BRACKET_OPEN: u8 = b'[';
+    const BRACKET_CLOSE: u8 = b']';
+    const COLON: u8 = b':';
+
+    /// Return the byte at `pos` in `s`, or `0` if it is past the end, which emulates the null-terminated strings
+    /// the original algorithm is written for.
+    fn at(s: &[u8], pos: usize) -> u8 {
+        s.get(pos).copied().unwrap_or(0)
+    }
+
+    pub(crate) fn is_glob_special(b: u8) -> bool {
+        matches!(b, b'*' | b'?' | b'[' | b'\\')
+    }
+
+    fn fold(b: u8, mode: Mode) -> u8 {
+        if mode.contains(Mode::IGNORE_CASE) {
+            b.to_ascii_lowercase()
+        } else {
+            b
+        }
+    }
+
+    fn match_recursive(pattern: &[u8], text: &[u8], mode: Mode) -> Result {
+        use self::Result::*;
+        let (mut p, mut t) = (0, 0);
+        while p < pattern.len() {
+            let mut p_ch = fold(pattern[p], mode);
+            let mut t_ch = fold(at(text, t), mode);
+            if t_ch == 0 && p_ch != STAR {
+                return AbortAll;
+            }
+            match p_ch {
+                b'?' => {
+                    if mode.contains(Mode::NO_MATCH_SLASH_LITERAL) && t_ch == SLASH {
+                        return NoMatch;
+                    }
+                }
+                STAR => {
+                    let match_slash;
+                    p += 1;
+                    if mode.contains(Mode::NO_MATCH_SLASH_LITERAL) && at(pattern, p) == STAR {
+                        let prev_p = p.checked_sub(2);
+                        while at(pattern, p) == STAR {
+                            p += 1;
+                        }
+                        let next = at(pattern, p);
+                        if prev_p.map_or(true, |prev_p| pattern[prev_p] == SLASH)
+                            && (next == 0 || next == SLASH || (next == BACKSLASH && at(pattern, p + 1) == SLASH))
+                        {
+                            // Assuming we already match 'foo/' and are at '**/', assume it matches nothing and try to match
+                            // the rest of the pattern with the remaining text, which makes 'foo/**/bar' match 'foo/bar'.
+                            if next == SLASH && match_recursive(&pattern[p + 1..], &text[t..], mode) == Match {
+                                return Match;
+                            }
+                            match_slash = true;
+                        } else {
+                            match_slash = false;
+                        }
+                    } else {
+                        // Without matching paths, '*' is the same as '**'.
+                        match_slash = !mode.contains(Mode::NO_MATCH_SLASH_LITERAL);
+                    }
+
+                    if p == pattern.len() {
+                        // Trailing '**' matches everything, a trailing '*' matches only if there are no more slashes.
+                        if !match_slash && text[t..].contains(&SLASH) {
+                            return NoMatch;
+                        }
+                        return Match;
+                    } else if !match_slash && pattern[p] == SLASH {
+                        // A single asterisk followed by a slash matches the next directory.
+                        match text[t..].iter().position(|b| *b == SLASH) {
+                            Some(pos) => {
+                                // The slash itself is consumed along with the one in the pattern.
+                                t += pos + 1;
+                                p += 1;
+                                continue;
+                            }
+                            None => return NoMatch,
+                        }
+                    }
+
+                    loop {
+                        if t_ch == 0 {
+                            break;
+                        }
+                        // Advance faster if the asterisk is followed by a literal, which must be preceded by what
+                        // the asterisk matches. Without matching slashes, don't look past the first one.
+                        if !is_glob_special(pattern[p]) {
+                            p_ch = fold(pattern[p], mode);
+                            loop {
+                                t_ch = fold(at(text, t), mode);
+                                if t_ch == 0 || (!match_slash && t_ch == SLASH) || t_ch == p_ch {
+                                    break;
+                                }
+                                t += 1;
+                            }
+                            if t_ch != p_ch {
+                                return NoMatch;
+                            }
+                        }
+                        let matched = match_recursive(&pattern[p..], &text[t..], mode);
+                        if matched != NoMatch {
+                            if !match_slash || matched != AbortToStarStar {
+                                return matched;
+                            }
+                        } else if !match_slash && t_ch == SLASH {
+                            return AbortToStarStar;
+                        }
+                        t += 1;
+                        t_ch = fold(at(text, t), mode);
+                    }
+                    return AbortAll;
+                }
+                BRACKET_OPEN => {
+                    p += 1;
+                    p_ch = at(pattern, p);
+                    if p_ch == b'^' {
+                        p_ch = b'!';
+                    }
+                    let negated = p_ch == b'!';
+                    if negated {
+                        p += 1;
+                        p_ch = at(pattern, p);
+                    }
+                    let mut prev_ch = 0;
+                    let mut matched = false;
+                    loop {
+                        if p_ch == 0 {
+                            return AbortAll;
+                        }
+                        if p_ch == BACKSLASH {
+                            p += 1;
+                            p_ch = at(pattern, p);
+                            if p_ch == 0 {
+                                return AbortAll;
+                            }
+                            if t_ch == p_ch {
+                                matched = true;
+                            }
+                        } else if p_ch == b'-'
+                            && prev_ch != 0
+                            && at(pattern, p + 1) != 0
+                            && at(pattern, p + 1) != BRACKET_CLOSE
+                        {
+                            p += 1;
+                            p_ch = at(pattern, p);
+                            if p_ch == BACKSLASH {
+                                p += 1;
+                                p_ch = at(pattern, p);
+                                if p_ch == 0 {
+                                    return AbortAll;
+                                }
+                            }
+                            if t_ch <= p_ch && t_ch >= prev_ch {
+                                matched = true;
+                            } else if mode.contains(Mode::IGNORE_CASE) && t_ch.is_ascii_lowercase() {
+                                let t_ch_upper = t_ch.to_ascii_uppercase();
+                                if t_ch_upper <= p_ch && t_ch_upper >= prev_ch {
+                                    matched = true;
+                                }
+                            }
+                            p_ch = 0;
+                        } else if p_ch == BRACKET_OPEN && at(pattern, p + 1) == COLON {
+                            p += 2;
+                            let class_start = p;
+                            while at(pattern, p) != 0 && at(pattern, p) != BRACKET_CLOSE {
+                                p += 1;
+                            }
+                            if at(pattern, p) == 0 {
+                                return AbortAll;
+                            }
+                            if p == class_start || pattern[p - 1] != COLON {
+                                // Didn't find ':]', so treat it like a normal set.
+                                p = class_start - 2;
+                                p_ch = BRACKET_OPEN;
+                                if t_ch == p_ch {
+                                    matched = true;
+                                }
+                            } else {
+                                let class = &pattern[class_start..p - 1];
+                                matched |= match class {
+                                    b"alnum" => t_ch.is_ascii_alphanumeric(),
+                                    b"alpha" => t_ch.is_ascii_alphabetic(),
+                                    b"blank" => t_ch == b' ' || t_ch == b'\t',
+                                    b"cntrl" => t_ch.is_ascii_control(),
+                                    b"digit" => t_ch.is_ascii_digit(),
+                                    b"graph" => t_ch.is_ascii_graphic(),
+                                    b"lower" => t_ch.is_ascii_lowercase(),
+                                    b"print" => t_ch.is_ascii_graphic() || t_ch == b' ',
+                                    b"punct" => t_ch.is_ascii_punctuation(),
+                                    b"space" => t_ch.is_ascii_whitespace() || t_ch == b'\x0b',
+                                    b"upper" => {
+                                        t_ch.is_ascii_uppercase()
+                                            || (mode.contains(Mode::IGNORE_CASE) && t_ch.is_ascii_lowercase())
+                                    }
+                                    b"xdigit" => t_ch.is_ascii_hexdigit(),
+                                    _ => return AbortAll,
+                                };
+                                p_ch = 0;
+                            }
+                        } else if t_ch == p_ch {
+                            matched = true;
+                        }
+
+                        prev_ch = p_ch;
+                        p += 1;
+                        p_ch = at(pattern, p);
+                        if p_ch == BRACKET_CLOSE {
+                            break;
+                        }
+                    }
+                    if matched == negated || (mode.contains(Mode::NO_MATCH_SLASH_LITERAL) && t_ch == SLASH) {
+                        return NoMatch;
+                    }
+                }
+                BACKSLASH => {
+                    // A literal match with the following character, or no match if the pattern ends here.
+                    p += 1;
+                    p_ch = at(pattern, p);
+                    if t_ch != p_ch {
+                        return NoMatch;
+                    }
+                }
+                _ => {
+                    if t_ch != p_ch {
+                        return NoMatch;
+                    }
+                }
+            }
+            p += 1;
+            t += 1;
+        }
+        if t < text.len() {
+            NoMatch
+        } else {
+            Match
+        }
+    }
+
+    /// Employ pattern matching to see if `value` matches `pattern`.
+    ///
+    /// `mode` can be used to adjust the way the matching is performed.
+    /// This is a port of the `wildmatch()` function of git, with the same results.
+    pub fn wildmatch(pattern: &BStr, value: &BStr, mode: Mode) -> bool {
+        match_recursive(pattern, value, mode) == Result::Match
+    }
+}
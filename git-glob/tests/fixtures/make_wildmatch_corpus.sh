@@ -0,0 +1,37 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q repo && cd repo
+
+paths=(
+  abc aBc ABC a/b/c a/bc a/x/y/c ab/c abd/e/f
+  foo/bar foobar foo/baz/bar foo/baz/qux/bar deep/foo/bar fooXbar
+  x.txt dir/x.TXT dir/sub/y.txt dir/z.txt
+  ']x' -a a-b b1 c_d 'sp ace' Tab
+)
+for path in "${paths[@]}"; do
+  mkdir -p "$(dirname -- "$path")"
+  echo "$path" > "./$path"
+done
+git add .
+printf '%s\n' "${paths[@]}" > ../paths
+
+patterns=(
+  '*' '*.txt' '*.TXT' '**/bar' 'foo/**' 'foo/**/bar' 'foo*bar' 'foo**bar' '**/foo/**'
+  'a?c' 'a[bB]c' 'a[!b]c' 'a[^b]c' '[a-c]*' '[[:upper:]]*' '*[[:digit:]]' '[[:alpha:]][[:punct:]]*'
+  'a/**/c' '*/c' '**' '\a*' 'a\*' '[]]x' '[!]]*' 'a[-]b' '*[[:space:]]*' '[[:xdigit:]][[:lower:]]*'
+  'dir/*.txt' 'dir/**/*.txt' '*/*/*' '***/bar' 'foo/***' '*/**/bar' 'a/*/c' 'a**c' '?' '??' '[a-]*'
+  '[A-Z]*' '[[:alnum:]_]*' 'abd/**/' 'x[.]txt' '[[:digit:]' '[[:nope:]]*' '*[' 'foo/*' '**/*.TXT'
+)
+for pattern in "${patterns[@]}"; do
+  for magic in literal-star glob icase glob,icase; do
+    if [ "$magic" = literal-star ]; then
+      spec="$pattern"
+    else
+      spec=":($magic)$pattern"
+    fi
+    git ls-files -- "$spec" | while read -r path; do
+      printf '%s\t%s\t%s\n' "$magic" "$pattern" "$path"
+    done
+  done
+done > ../wildmatch.expected
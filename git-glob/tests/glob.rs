@@ -0,0 +1,4 @@
+pub type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+mod pattern;
+mod wildmatch;
@@ -0,0 +1,67 @@
+use bstr::ByteSlice;
+use git_glob::{pattern, pattern::Mode, Pattern};
+
+fn pat(text: &str) -> Pattern {
+    Pattern::from_bytes(text.as_bytes()).expect("valid pattern")
+}
+
+fn matches(pattern: &str, path: &str, is_dir: bool) -> bool {
+    let path = path.as_bytes().as_bstr();
+    let basename_start_pos = path.rfind_byte(b'/').map(|pos| pos + 1);
+    pat(pattern).matches_repo_relative_path(path, basename_start_pos, is_dir, pattern::Case::Sensitive)
+}
+
+#[test]
+fn parsing_strips_prefixes_and_suffixes_into_the_mode() {
+    assert_eq!(Pattern::from_bytes(b""), None);
+    assert_eq!(Pattern::from_bytes(b"!"), None);
+    assert_eq!(Pattern::from_bytes(b"/"), None);
+
+    let p = pat("!/a/b*/");
+    assert_eq!(p.text, "a/b*");
+    assert_eq!(p.mode, Mode::NEGATIVE | Mode::ABSOLUTE | Mode::MUST_BE_DIR);
+    assert_eq!(p.first_wildcard_pos, Some(3));
+    assert!(p.is_negative());
+
+    let p = pat("*.o");
+    assert_eq!(p.mode, Mode::NO_SUB_DIR | Mode::ENDS_WITH);
+    assert_eq!(p.first_wildcard_pos, Some(0));
+
+    let p = pat("dir/");
+    assert_eq!(p.mode, Mode::NO_SUB_DIR | Mode::MUST_BE_DIR);
+    assert_eq!(p.first_wildcard_pos, None);
+
+    assert_eq!(
+        pat(r"\!important").text,
+        r"\!important",
+        "escapes are kept for matching"
+    );
+}
+
+#[test]
+fn patterns_without_slash_match_the_file_name() {
+    assert!(matches("*.o", "a/b/c.o", false));
+    assert!(matches("c.o", "a/b/c.o", false));
+    assert!(!matches("b", "a/b/c.o", false));
+    assert!(matches("b", "a/b", true));
+    assert!(!matches("b/", "a/b", false), "directory patterns don't match files");
+}
+
+#[test]
+fn patterns_with_slash_match_the_whole_path() {
+    assert!(matches("a/*.o", "a/c.o", false));
+    assert!(!matches("a/*.o", "a/b/c.o", false));
+    assert!(!matches("a/*.o", "x/a/c.o", false));
+    assert!(matches("/c.o", "c.o", false));
+    assert!(!matches("/c.o", "a/c.o", false));
+    assert!(matches("a/**/c.o", "a/c.o", false));
+}
+
+#[test]
+fn case_can_be_ignored() {
+    let p = pat("*.TXT");
+    assert!(p.matches_repo_relative_path("a.txt".as_bytes().as_bstr(), None, false, pattern::Case::Fold));
+    assert!(!p.matches_repo_relative_path("a.txt".as_bytes().as_bstr(), None, false, pattern::Case::Sensitive));
+    let p = pat("Dir/File");
+    assert!(p.matches_repo_relative_path("dir/file".as_bytes().as_bstr(), Some(4), false, pattern::Case::Fold));
+}
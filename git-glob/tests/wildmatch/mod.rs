@@ -0,0 +1,92 @@
+use std::collections::BTreeSet;
+
+use bstr::ByteSlice;
+use git_glob::{pattern, wildmatch, Pattern};
+
+fn corpus() -> crate::Result<(Vec<String>, BTreeSet<(String, String, String)>)> {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_wildmatch_corpus.sh")?;
+    let paths = std::fs::read_to_string(root.join("paths"))?
+        .lines()
+        .map(ToOwned::to_owned)
+        .collect();
+    let expected = std::fs::read_to_string(root.join("wildmatch.expected"))?
+        .lines()
+        .map(|line| {
+            let mut tokens = line.splitn(3, '\t').map(ToOwned::to_owned);
+            (
+                tokens.next().expect("magic"),
+                tokens.next().expect("pattern"),
+                tokens.next().expect("path"),
+            )
+        })
+        .collect();
+    Ok((paths, expected))
+}
+
+/// A pattern which is matched like git matches pathspecs, without interpreting any special prefix or suffix.
+fn pathspec(text: &str) -> Pattern {
+    Pattern {
+        text: text.into(),
+        mode: pattern::Mode::empty(),
+        first_wildcard_pos: text.find(|c| matches!(c, '*' | '?' | '[' | '\\')),
+    }
+}
+
+#[test]
+fn matches_like_git_pathspecs() -> crate::Result {
+    let (paths, expected) = corpus()?;
+    let patterns: BTreeSet<_> = expected.iter().map(|(_, pattern, _)| pattern.clone()).collect();
+    assert!(patterns.len() > 40, "most patterns match at least one path");
+
+    let mut actual = BTreeSet::new();
+    for (magic, mode) in &[
+        ("literal-star", wildmatch::Mode::empty()),
+        ("glob", wildmatch::Mode::NO_MATCH_SLASH_LITERAL),
+        ("icase", wildmatch::Mode::IGNORE_CASE),
+        (
+            "glob,icase",
+            wildmatch::Mode::NO_MATCH_SLASH_LITERAL | wildmatch::Mode::IGNORE_CASE,
+        ),
+    ] {
+        for text in &patterns {
+            let pattern = pathspec(text);
+            for path in &paths {
+                if pattern.matches(path.as_bytes().as_bstr(), *mode) {
+                    actual.insert((magic.to_string(), text.clone(), path.clone()));
+                }
+            }
+        }
+    }
+    let missing: Vec<_> = expected.difference(&actual).collect();
+    let unexpected: Vec<_> = actual.difference(&expected).collect();
+    assert_eq!((missing, unexpected), (vec![], vec![]));
+    Ok(())
+}
+
+fn matches(pattern: &str, text: &str, mode: wildmatch::Mode) -> bool {
+    git_glob::wildmatch(pattern.as_bytes().as_bstr(), text.as_bytes().as_bstr(), mode)
+}
+
+#[test]
+fn double_stars_match_any_amount_of_directories_with_paths() {
+    let mode = wildmatch::Mode::NO_MATCH_SLASH_LITERAL;
+    assert!(matches("foo/**/bar", "foo/bar", mode));
+    assert!(matches("foo/**/bar", "foo/a/b/bar", mode));
+    assert!(matches("**/bar", "bar", mode));
+    assert!(matches("foo/**", "foo/a/b", mode));
+    assert!(!matches("foo/**", "foo", mode));
+    assert!(!matches("foo*bar", "foo/bar", mode));
+    assert!(matches("foo*bar", "foo/bar", wildmatch::Mode::empty()));
+}
+
+#[test]
+fn character_classes() {
+    let mode = wildmatch::Mode::empty();
+    assert!(matches("[[:digit:][:upper:]]", "A", mode));
+    assert!(matches("[a-c]", "b", mode));
+    assert!(!matches("[!a-c]", "b", mode));
+    assert!(matches("[]-]", "-", mode));
+    assert!(matches("[[:upper:]]", "a", wildmatch::Mode::IGNORE_CASE));
+    assert!(!matches("[[:bogus:]]", "a", mode), "malformed classes never match");
+    assert!(!matches("[abc", "a", mode), "unterminated brackets never match");
+}
@@ -0,0 +1,221 @@
+use git_hash::ObjectId;
+use git_object::bstr::{BString, ByteSlice};
+
+use crate::{
+    entry::{self, Entry},
+    extension::{self, Signature},
+    util::{entry_mode_from_u32, read_u16, read_u32, split_at_byte_exclusive, split_at_pos, var_int},
+    State, Version,
+};
+
+mod error {
+    use git_hash::ObjectId;
+    use git_object::bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`State::from_bytes()`][crate::State::from_bytes()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Corrupt(message: &'static str) {
+                display("{}", message)
+            }
+            UnsupportedVersion(version: u32) {
+                display("Index version {} is unsupported", version)
+            }
+            Entry(index: u32) {
+                display("Could not decode index entry at position {}", index)
+            }
+            Extension(signature: BString) {
+                display("Could not decode extension '{}'", signature)
+            }
+            UnsupportedMandatoryExtension(signature: BString) {
+                display("Mandatory extension '{}' is unsupported", signature)
+            }
+            ChecksumMismatch { expected: ObjectId, actual: ObjectId } {
+                display("The index checksum {} didn't match the computed checksum {}", expected, actual)
+            }
+        }
+    }
+}
+pub use error::Error;
+
+const SIGNATURE: &[u8] = b"DIRC";
+const HEADER_LEN: usize = 12;
+const CHECKSUM_LEN: usize = 20;
+const EXTENSION_HEADER_LEN: usize = 8;
+/// The smallest possible entry consists of its fixed-size fields, followed by a path with its null terminator or,
+/// in V4, the single byte prefix length and a null-terminated empty path.
+const MIN_ENTRY_LEN: usize = 62 + 2;
+
+/// Decoding
+impl State {
+    /// Decode an index from `data`, the complete content of an index file, and return it along with the checksum it ended with.
+    ///
+    /// The checksum is verified unless it is null, which happens if git was configured to skip computing it.
+    /// Note that entries of split indices will only contain the changes compared to their shared index, and the
+    /// returned state still needs to be merged with it. This is done automatically when reading a [`File`][crate::File].
+    pub fn from_bytes(data: &[u8]) -> Result<(Self, ObjectId), Error> {
+        if data.len() < HEADER_LEN + CHECKSUM_LEN {
+            return Err(Error::Corrupt("File is too small to contain an index"));
+        }
+        let (content, checksum) = data.split_at(data.len() - CHECKSUM_LEN);
+        let checksum = ObjectId::from_20_bytes(checksum);
+        if !checksum.is_null() {
+            let mut hasher = git_features::hash::Sha1::default();
+            hasher.update(content);
+            let actual = ObjectId::from(hasher.digest());
+            if actual != checksum {
+                return Err(Error::ChecksumMismatch {
+                    expected: checksum,
+                    actual,
+                });
+            }
+        }
+
+        let (signature, rest) = content.split_at(SIGNATURE.len());
+        if signature != SIGNATURE {
+            return Err(Error::Corrupt("Index signature 'DIRC' not found"));
+        }
+        let (version, rest) = read_u32(rest).expect("header fits");
+        let version = match version {
+            2 => Version::V2,
+            3 => Version::V3,
+            4 => Version::V4,
+            unknown => return Err(Error::UnsupportedVersion(unknown)),
+        };
+        let (num_entries, mut rest) = read_u32(rest).expect("header fits");
+
+        let mut entries = Vec::with_capacity((num_entries as usize).min(rest.len() / MIN_ENTRY_LEN));
+        let mut previous_path = BString::default();
+        for index in 0..num_entries {
+            let (entry, remaining) = decode_entry(rest, version, &previous_path).ok_or(Error::Entry(index))?;
+            rest = remaining;
+            if version == Version::V4 {
+                previous_path.clone_from(&entry.path);
+            }
+            entries.push(entry);
+        }
+
+        let mut state = State {
+            version,
+            entries,
+            tree: None,
+            resolve_undo: None,
+            untracked: None,
+            fs_monitor: None,
+            link: None,
+            is_sparse: false,
+        };
+
+        let extensions_offset = content.len() - rest.len();
+        let mut signatures_and_sizes = Vec::new();
+        while !rest.is_empty() {
+            let (signature, data) = split_at_pos(rest, 4).ok_or(Error::Corrupt("Truncated extension header"))?;
+            let (size, data) = read_u32(data).ok_or(Error::Corrupt("Truncated extension header"))?;
+            let (ext_data, data) =
+                split_at_pos(data, size as usize).ok_or(Error::Extension(signature.as_bstr().to_owned()))?;
+            let signature: Signature = [signature[0], signature[1], signature[2], signature[3]];
+            let err = || Error::Extension(signature.as_bstr().to_owned());
+            match signature {
+                extension::tree::SIGNATURE => state.tree = Some(extension::tree::decode(ext_data).ok_or_else(err)?),
+                extension::resolve_undo::SIGNATURE => {
+                    state.resolve_undo = Some(extension::resolve_undo::decode(ext_data).ok_or_else(err)?)
+                }
+                extension::untracked_cache::SIGNATURE => {
+                    state.untracked = Some(extension::untracked_cache::decode(ext_data).ok_or_else(err)?)
+                }
+                extension::fs_monitor::SIGNATURE => {
+                    state.fs_monitor = Some(extension::fs_monitor::decode(ext_data).ok_or_else(err)?)
+                }
+                extension::link::SIGNATURE => state.link = Some(extension::link::decode(ext_data).ok_or_else(err)?),
+                extension::sparse::SIGNATURE => state.is_sparse = true,
+                extension::end_of_index_entry::SIGNATURE => {
+                    extension::end_of_index_entry::verify(ext_data, extensions_offset, &signatures_and_sizes)
+                        .ok_or_else(err)?
+                }
+                extension::index_entry_offset_table::SIGNATURE => {
+                    extension::index_entry_offset_table::verify(ext_data, num_entries, HEADER_LEN..extensions_offset)
+                        .ok_or_else(err)?
+                }
+                _ if signature[0].is_ascii_uppercase() => {}
+                _ => return Err(Error::UnsupportedMandatoryExtension(signature.as_bstr().to_owned())),
+            }
+            signatures_and_sizes.extend_from_slice(&rest[..EXTENSION_HEADER_LEN]);
+            rest = data;
+        }
+
+        Ok((state, checksum))
+    }
+}
+
+fn decode_entry<'a>(bytes: &'a [u8], version: Version, previous_path: &BString) -> Option<(Entry, &'a [u8])> {
+    const PATH_LEN_MASK: u16 = 0x0fff;
+    let mut fields = [0u32; 10];
+    let mut data = bytes;
+    for field in fields.iter_mut() {
+        let (value, rest) = read_u32(data)?;
+        *field = value;
+        data = rest;
+    }
+    let [ctime_secs, ctime_nsecs, mtime_secs, mtime_nsecs, dev, ino, mode, uid, gid, size] = fields;
+    let (hash, data) = split_at_pos(data, 20)?;
+    let (raw_flags, mut data) = read_u16(data)?;
+    let mut flags = (raw_flags & 0xb000) as u32;
+    if raw_flags & entry::Flags::EXTENDED.bits() as u16 != 0 {
+        if version == Version::V2 {
+            return None;
+        }
+        let (extended, rest) = read_u16(data)?;
+        data = rest;
+        flags |= (extended as u32) << 16;
+    }
+
+    let (path, data) = match version {
+        Version::V4 => {
+            let (strip, data) = var_int(data)?;
+            let (suffix, data) = split_at_byte_exclusive(data, 0)?;
+            let keep = previous_path.len().checked_sub(strip as usize)?;
+            let mut path = BString::from(&previous_path[..keep]);
+            path.extend_from_slice(suffix);
+            (path, data)
+        }
+        Version::V2 | Version::V3 => {
+            let path_len = raw_flags & PATH_LEN_MASK;
+            let path = if path_len == PATH_LEN_MASK {
+                split_at_byte_exclusive(data, 0)?.0
+            } else {
+                split_at_pos(data, path_len as usize)?.0
+            };
+            let fixed_len = bytes.len() - data.len();
+            let padded_len = (fixed_len + path.len() + 8) & !7;
+            (path.into(), split_at_pos(bytes, padded_len)?.1)
+        }
+    };
+
+    Some((
+        Entry {
+            stat: entry::Stat {
+                ctime: entry::Time {
+                    secs: ctime_secs,
+                    nsecs: ctime_nsecs,
+                },
+                mtime: entry::Time {
+                    secs: mtime_secs,
+                    nsecs: mtime_nsecs,
+                },
+                dev,
+                ino,
+                uid,
+                gid,
+                size,
+            },
+            id: ObjectId::from_20_bytes(hash),
+            flags: entry::Flags::from_bits(flags)?,
+            mode: entry_mode_from_u32(mode)?,
+            path,
+        },
+        data,
+    ))
+}
@@ -0,0 +1,98 @@
+use git_hash::ObjectId;
+use git_object::{bstr::BString, tree::EntryMode};
+
+/// An entry in the index, identifying a non-tree item by its path and stage, along with information about the file in the worktree.
+///
+/// In sparse indices, entries may also be directories which stand in for all of their files outside of the sparse checkout.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Entry {
+    /// Information about the file in the worktree at the time it was last seen.
+    pub stat: Stat,
+    /// The id of the object in the object database.
+    pub id: ObjectId,
+    /// Additional flags, including the stage of the entry.
+    pub flags: Flags,
+    /// The kind of item.
+    pub mode: EntryMode,
+    /// The path relative to the root of the worktree, using slashes as separator.
+    ///
+    /// Directories in sparse indices have a trailing slash.
+    pub path: BString,
+}
+
+impl Entry {
+    /// Return the stage of the entry, which is `0` for entries without conflict, and `1` to `3` for the merge base,
+    /// our and their side of a conflict respectively.
+    pub fn stage(&self) -> u8 {
+        self.flags.stage()
+    }
+}
+
+/// A timestamp with nanosecond precision, as stored in the index.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Time {
+    /// The seconds since the unix epoch.
+    pub secs: u32,
+    /// The nanoseconds within the second.
+    pub nsecs: u32,
+}
+
+/// Information about a file in the worktree as obtained with `stat`, used to learn if it changed since it was seen last.
+///
+/// All values are truncated to 32 bits.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Stat {
+    /// The time at which the metadata of the file last changed.
+    pub ctime: Time,
+    /// The time at which the data of the file last changed.
+    pub mtime: Time,
+    /// The device the file resides on.
+    pub dev: u32,
+    /// The inode of the file.
+    pub ino: u32,
+    /// The user id of the owner of the file.
+    pub uid: u32,
+    /// The group id of the owner of the file.
+    pub gid: u32,
+    /// The size of the file in bytes.
+    pub size: u32,
+}
+
+bitflags::bitflags! {
+    /// In-memory flags of an [`Entry`], combining the flags stored with each entry and the extended flags of version 3.
+    pub struct Flags: u32 {
+        /// The mask to obtain the stage of an entry.
+        const STAGE_MASK = 0x3000;
+        /// If set, the entry needs more than two bytes of flags on disk, which requires index version 3 or higher.
+        const EXTENDED = 0x4000;
+        /// If set, the file in the worktree is assumed unchanged and won't be checked for changes.
+        const ASSUME_VALID = 0x8000;
+        /// If set, the entry was added with `git add --intent-to-add`, making the path known without staging its content yet.
+        const INTENT_TO_ADD = 1 << 29;
+        /// If set, the file isn't checked out into the worktree, for instance as it's outside of a sparse checkout.
+        const SKIP_WORKTREE = 1 << 30;
+    }
+}
+
+impl Flags {
+    /// Return the stage encoded in these flags.
+    pub fn stage(&self) -> u8 {
+        ((*self & Flags::STAGE_MASK).bits >> 12) as u8
+    }
+
+    /// Return a copy of these flags with `stage`, which must be in the range of `0..=3`.
+    pub fn with_stage(self, stage: u8) -> Self {
+        (self - Flags::STAGE_MASK) | Flags::from_bits_truncate((stage as u32 & 0x3) << 12)
+    }
+
+    /// Return the extended flags that need to be stored in the index as version 3 or higher.
+    pub fn extended(&self) -> Flags {
+        *self & (Flags::INTENT_TO_ADD | Flags::SKIP_WORKTREE)
+    }
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Flags::empty()
+    }
+}
@@ -0,0 +1,250 @@
+//! Bitmaps compressed with the EWAH scheme, as used by git to mark entries in index extensions.
+use crate::util::{read_u32, read_u64, split_at_pos};
+
+///
+pub mod decode {
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`decode()`][super::decode()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Corrupt(message: &'static str) {
+                display("{}", message)
+            }
+        }
+    }
+}
+
+const LARGEST_RUNNING_COUNT: u64 = 0xffff_ffff;
+const LARGEST_LITERAL_COUNT: u64 = 0x7fff_ffff;
+
+/// A bitmap compressed with the EWAH scheme, which alternates runs of words with all bits set or unset with uncompressed words.
+///
+/// Each run is described by a run length word, which is followed by the uncompressed words.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Vec {
+    num_bits: u32,
+    words: std::vec::Vec<u64>,
+    /// The index of the last run length word in `words`.
+    rlw: usize,
+}
+
+impl Default for Vec {
+    fn default() -> Self {
+        Vec {
+            num_bits: 0,
+            words: vec![0],
+            rlw: 0,
+        }
+    }
+}
+
+/// Decode a bitmap from `data`, returning it along with the remaining bytes.
+pub fn decode(data: &[u8]) -> Result<(Vec, &[u8]), decode::Error> {
+    let (num_bits, data) = read_u32(data).ok_or(decode::Error::Corrupt("eof reading amount of bits"))?;
+    let (len, data) = read_u32(data).ok_or(decode::Error::Corrupt("eof reading amount of words"))?;
+    let (mut bits, data) =
+        split_at_pos(data, len as usize * 8).ok_or(decode::Error::Corrupt("eof while reading words"))?;
+    let mut words = std::vec::Vec::with_capacity(len as usize);
+    while let Some((word, rest)) = read_u64(bits) {
+        words.push(word);
+        bits = rest;
+    }
+    let (rlw, data) = read_u32(data).ok_or(decode::Error::Corrupt("eof reading last run length word"))?;
+    if rlw as usize >= words.len() {
+        return Err(decode::Error::Corrupt("last run length word is out of bounds"));
+    }
+    Ok((
+        Vec {
+            num_bits,
+            words,
+            rlw: rlw as usize,
+        },
+        data,
+    ))
+}
+
+impl Vec {
+    /// Return the amount of bits in the bitmap, set or not.
+    pub fn num_bits(&self) -> usize {
+        self.num_bits as usize
+    }
+
+    /// Call `f(index)` for each set bit in order, stopping early if it returns `None`.
+    ///
+    /// Returns `None` if `f` stopped the iteration or if the bitmap turned out to be corrupt.
+    pub fn for_each_set_bit(&self, mut f: impl FnMut(usize) -> Option<()>) -> Option<()> {
+        let mut index = 0usize;
+        let mut words = self.words.iter();
+        while let Some(rlw) = words.next() {
+            let running_len = running_len(*rlw) as usize * 64;
+            let literal_words = literal_words(*rlw) as usize;
+            if running_bit(*rlw) {
+                for bit in index..index + running_len {
+                    if bit >= self.num_bits() {
+                        return Some(());
+                    }
+                    f(bit)?;
+                }
+            }
+            index += running_len;
+            for _ in 0..literal_words {
+                let word = words.next()?;
+                for bit in 0..64 {
+                    if word & (1 << bit) != 0 {
+                        if index + bit >= self.num_bits() {
+                            return Some(());
+                        }
+                        f(index + bit)?;
+                    }
+                }
+                index += 64;
+            }
+        }
+        Some(())
+    }
+
+    /// Return the indices of all set bits in order.
+    pub fn set_bits(&self) -> std::vec::Vec<usize> {
+        let mut out = std::vec::Vec::new();
+        self.for_each_set_bit(|idx| {
+            out.push(idx);
+            Some(())
+        });
+        out
+    }
+}
+
+/// Mutation
+impl Vec {
+    /// Set the bit at `index`, which must be past all bits set so far, like `ewah_set()` does in git.
+    ///
+    /// # Panics
+    ///
+    /// If `index` is smaller than [`num_bits()`][Vec::num_bits()].
+    pub fn set(&mut self, index: usize) {
+        assert!(index >= self.num_bits(), "bits must be set in ascending order");
+        let words_needed = |bits: usize| (bits + 63) / 64;
+        let distance = words_needed(index + 1) - words_needed(self.num_bits());
+        self.num_bits = (index + 1) as u32;
+        let bit = 1u64 << (index % 64);
+
+        if distance > 0 {
+            if distance > 1 {
+                self.add_empty_words(false, distance as u64 - 1);
+            }
+            self.add_literal(bit);
+            return;
+        }
+        if literal_words(self.words[self.rlw]) == 0 {
+            let running_len = running_len(self.words[self.rlw]);
+            set_running_len(&mut self.words[self.rlw], running_len - 1);
+            self.add_literal(bit);
+            return;
+        }
+        let last = self.words.last_mut().expect("at least one literal word");
+        *last |= bit;
+        if *last == u64::MAX {
+            self.words.pop();
+            let literals = literal_words(self.words[self.rlw]);
+            set_literal_words(&mut self.words[self.rlw], literals - 1);
+            self.add_empty_word(true);
+        }
+    }
+
+    fn push_rlw(&mut self) {
+        self.words.push(0);
+        self.rlw = self.words.len() - 1;
+    }
+
+    fn add_empty_word(&mut self, bit: bool) {
+        let rlw = self.words[self.rlw];
+        let no_literal = literal_words(rlw) == 0;
+        let run_len = running_len(rlw);
+        if no_literal && run_len == 0 {
+            set_running_bit(&mut self.words[self.rlw], bit);
+        }
+        if no_literal && running_bit(self.words[self.rlw]) == bit && run_len < LARGEST_RUNNING_COUNT {
+            set_running_len(&mut self.words[self.rlw], run_len + 1);
+        } else {
+            self.push_rlw();
+            set_running_bit(&mut self.words[self.rlw], bit);
+            set_running_len(&mut self.words[self.rlw], 1);
+        }
+    }
+
+    fn add_empty_words(&mut self, bit: bool, mut count: u64) {
+        let rlw = self.words[self.rlw];
+        if running_bit(rlw) != bit && running_len(rlw) + literal_words(rlw) == 0 {
+            set_running_bit(&mut self.words[self.rlw], bit);
+        }
+        if count == 0 {
+            return;
+        }
+        if running_bit(self.words[self.rlw]) != bit || literal_words(self.words[self.rlw]) > 0 {
+            self.push_rlw();
+            set_running_bit(&mut self.words[self.rlw], bit);
+        }
+        let run_len = running_len(self.words[self.rlw]);
+        let can_add = count.min(LARGEST_RUNNING_COUNT - run_len);
+        set_running_len(&mut self.words[self.rlw], run_len + can_add);
+        count -= can_add;
+        while count > 0 {
+            let run_len = count.min(LARGEST_RUNNING_COUNT);
+            self.push_rlw();
+            set_running_bit(&mut self.words[self.rlw], bit);
+            set_running_len(&mut self.words[self.rlw], run_len);
+            count -= run_len;
+        }
+    }
+
+    fn add_literal(&mut self, word: u64) {
+        let literals = literal_words(self.words[self.rlw]);
+        if literals >= LARGEST_LITERAL_COUNT {
+            self.push_rlw();
+            set_literal_words(&mut self.words[self.rlw], 1);
+        } else {
+            set_literal_words(&mut self.words[self.rlw], literals + 1);
+        }
+        self.words.push(word);
+    }
+}
+
+/// Serialization
+impl Vec {
+    /// Write this bitmap to `out` in the format understood by [`decode()`].
+    pub fn write_to(&self, mut out: impl std::io::Write) -> std::io::Result<()> {
+        out.write_all(&self.num_bits.to_be_bytes())?;
+        out.write_all(&(self.words.len() as u32).to_be_bytes())?;
+        for word in &self.words {
+            out.write_all(&word.to_be_bytes())?;
+        }
+        out.write_all(&(self.rlw as u32).to_be_bytes())
+    }
+}
+
+fn running_bit(rlw: u64) -> bool {
+    rlw & 1 == 1
+}
+
+fn running_len(rlw: u64) -> u64 {
+    (rlw >> 1) & LARGEST_RUNNING_COUNT
+}
+
+fn literal_words(rlw: u64) -> u64 {
+    rlw >> 33
+}
+
+fn set_running_bit(rlw: &mut u64, bit: bool) {
+    *rlw = (*rlw & !1) | bit as u64;
+}
+
+fn set_running_len(rlw: &mut u64, len: u64) {
+    *rlw = (*rlw & !(LARGEST_RUNNING_COUNT << 1)) | (len << 1);
+}
+
+fn set_literal_words(rlw: &mut u64, count: u64) {
+    *rlw = (*rlw & !(LARGEST_LITERAL_COUNT << 33)) | (count << 33);
+}
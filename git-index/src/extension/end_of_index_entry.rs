@@ -0,0 +1,23 @@
+use crate::{
+    extension::Signature,
+    util::{read_u32, split_at_pos},
+};
+
+pub const SIGNATURE: Signature = *b"EOIE";
+
+/// Verify that the end of index entry extension in `data` points to `expected_offset`, the offset of the first extension,
+/// and that its hash matches the one over the `signatures_and_sizes` of all extensions.
+pub fn verify(data: &[u8], expected_offset: usize, signatures_and_sizes: &[u8]) -> Option<()> {
+    let (offset, data) = read_u32(data)?;
+    let (hash, data) = split_at_pos(data, 20)?;
+    if !data.is_empty() || offset as usize != expected_offset {
+        return None;
+    }
+    let mut hasher = git_features::hash::Sha1::default();
+    hasher.update(signatures_and_sizes);
+    if hasher.digest() == hash {
+        Some(())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,68 @@
+use git_object::bstr::BString;
+
+use crate::{
+    ewah,
+    extension::{FsMonitor, Signature},
+    util::{read_u32, read_u64, split_at_byte_exclusive, split_at_pos},
+};
+
+pub(crate) const SIGNATURE: Signature = *b"FSMN";
+
+/// The token to pass to the file system monitor, which depends on the version of its protocol.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub enum Token {
+    /// The token of version 1 of the protocol.
+    V1 {
+        /// The time at which the index was last checked against the file system monitor.
+        nanos_since_1970: u64,
+    },
+    /// The token of version 2 of the protocol, an opaque string defined by the file system monitor.
+    V2 {
+        /// The opaque token.
+        token: BString,
+    },
+}
+
+pub(crate) fn decode(data: &[u8]) -> Option<FsMonitor> {
+    let (version, data) = read_u32(data)?;
+    let (token, data) = match version {
+        1 => {
+            let (nanos_since_1970, data) = read_u64(data)?;
+            (Token::V1 { nanos_since_1970 }, data)
+        }
+        2 => {
+            let (token, data) = split_at_byte_exclusive(data, 0)?;
+            (Token::V2 { token: token.into() }, data)
+        }
+        _ => return None,
+    };
+    let (ewah_size, data) = read_u32(data)?;
+    let (ewah_data, data) = split_at_pos(data, ewah_size as usize)?;
+    let (entry_dirty, rest) = ewah::decode(ewah_data).ok()?;
+    if rest.is_empty() && data.is_empty() {
+        Some(FsMonitor { token, entry_dirty })
+    } else {
+        None
+    }
+}
+
+pub(crate) fn encode(fs_monitor: &FsMonitor, out: &mut Vec<u8>) {
+    match &fs_monitor.token {
+        Token::V1 { nanos_since_1970 } => {
+            out.extend_from_slice(&1u32.to_be_bytes());
+            out.extend_from_slice(&nanos_since_1970.to_be_bytes());
+        }
+        Token::V2 { token } => {
+            out.extend_from_slice(&2u32.to_be_bytes());
+            out.extend_from_slice(token);
+            out.push(0);
+        }
+    }
+    let mut bitmap = Vec::new();
+    fs_monitor
+        .entry_dirty
+        .write_to(&mut bitmap)
+        .expect("writing to a vec never fails");
+    out.extend_from_slice(&(bitmap.len() as u32).to_be_bytes());
+    out.extend_from_slice(&bitmap);
+}
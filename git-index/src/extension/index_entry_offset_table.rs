@@ -0,0 +1,26 @@
+use crate::{extension::Signature, util::read_u32};
+
+pub const SIGNATURE: Signature = *b"IEOT";
+
+/// Verify that the index entry offset table in `data` covers `num_entries` entries in blocks which start within `entries_range`.
+pub fn verify(data: &[u8], num_entries: u32, entries_range: std::ops::Range<usize>) -> Option<()> {
+    let (version, mut data) = read_u32(data)?;
+    if version != 1 || data.len() % 8 != 0 {
+        return None;
+    }
+    let mut total_entries = 0u32;
+    while !data.is_empty() {
+        let (offset, rest) = read_u32(data)?;
+        let (count, rest) = read_u32(rest)?;
+        data = rest;
+        if !entries_range.contains(&(offset as usize)) {
+            return None;
+        }
+        total_entries = total_entries.checked_add(count)?;
+    }
+    if total_entries == num_entries {
+        Some(())
+    } else {
+        None
+    }
+}
@@ -0,0 +1,77 @@
+use git_hash::ObjectId;
+
+use crate::{
+    ewah,
+    extension::{Link, Signature},
+    util::split_at_pos,
+    Entry,
+};
+
+pub(crate) const SIGNATURE: Signature = *b"link";
+
+/// The bitmaps of a [split index][Link] which mark entries of the shared index.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Bitmaps {
+    /// A set bit marks an entry of the shared index which was deleted.
+    pub delete: ewah::Vec,
+    /// A set bit marks an entry of the shared index which was replaced by an entry of the split index, in order.
+    pub replace: ewah::Vec,
+}
+
+pub(crate) fn decode(data: &[u8]) -> Option<Link> {
+    let (hash, data) = split_at_pos(data, 20)?;
+    let bitmaps = if data.is_empty() {
+        None
+    } else {
+        let (delete, data) = ewah::decode(data).ok()?;
+        let (replace, data) = ewah::decode(data).ok()?;
+        if !data.is_empty() {
+            return None;
+        }
+        Some(Bitmaps { delete, replace })
+    };
+    Some(Link {
+        shared_index_checksum: ObjectId::from_20_bytes(hash),
+        bitmaps,
+    })
+}
+
+impl Link {
+    /// Merge the `split` entries of the split index into the `shared` entries of the shared index, like git does when
+    /// reading a split index.
+    ///
+    /// Returns `None` if the bitmaps don't match the entries.
+    pub(crate) fn merge_entries(&self, split: Vec<Entry>, mut shared: Vec<Entry>) -> Option<Vec<Entry>> {
+        let mut split = split.into_iter();
+        let mut deleted = vec![false; shared.len()];
+        if let Some(bitmaps) = &self.bitmaps {
+            bitmaps.replace.for_each_set_bit(|idx| {
+                let mut replacement = split.next()?;
+                let entry = shared.get_mut(idx)?;
+                if !replacement.path.is_empty() {
+                    return None;
+                }
+                std::mem::swap(&mut replacement.path, &mut entry.path);
+                *entry = replacement;
+                Some(())
+            })?;
+            bitmaps.delete.for_each_set_bit(|idx| {
+                *deleted.get_mut(idx)? = true;
+                Some(())
+            })?;
+        }
+        let mut deleted = deleted.into_iter();
+        shared.retain(|_| !deleted.next().expect("one flag per entry"));
+
+        for entry in split {
+            if entry.path.is_empty() {
+                return None;
+            }
+            match shared.binary_search_by(|e| e.path.cmp(&entry.path).then(e.stage().cmp(&entry.stage()))) {
+                Ok(idx) => shared[idx] = entry,
+                Err(idx) => shared.insert(idx, entry),
+            }
+        }
+        Some(shared)
+    }
+}
@@ -0,0 +1,126 @@
+use git_hash::ObjectId;
+use git_object::bstr::BString;
+
+use crate::{entry, ewah};
+
+/// The four bytes identifying an extension.
+pub type Signature = [u8; 4];
+
+/// The cached tree, which holds the ids of trees as they would be written from the index, allowing to skip computing them.
+///
+/// Trees whose content changed since they were written are invalidated and don't carry an id.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Tree {
+    /// The name of the tree relative to its parent, or empty for the root tree.
+    pub name: BString,
+    /// The id of the tree as written from the index, or `None` if it was invalidated.
+    pub id: Option<ObjectId>,
+    /// The amount of index entries this tree covers, including all of its subtrees, or `0` if it was invalidated.
+    pub num_entries: u32,
+    /// The trees contained in this tree.
+    pub children: Vec<Tree>,
+}
+
+/// The link to a shared index file, making this a split index which only contains the differences to the shared index.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Link {
+    /// The checksum of the shared index, which also is part of its file name `sharedindex.<checksum>`.
+    pub shared_index_checksum: ObjectId,
+    /// Bitmaps identifying the entries of the shared index which were deleted or replaced, if present.
+    pub bitmaps: Option<link::Bitmaps>,
+}
+
+/// A cache of untracked files in the worktree along with information to learn if it is still accurate.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct UntrackedCache {
+    /// A description of the environment in which the cache is valid, typically including the path to the worktree.
+    pub identifier: BString,
+    /// Information about `$GIT_DIR/info/exclude`.
+    pub info_exclude: untracked_cache::OidStat,
+    /// Information about the file configured in `core.excludesFile`.
+    pub excludes_file: untracked_cache::OidStat,
+    /// The name of the per-directory exclude file, usually `.gitignore`.
+    pub exclude_filename_per_directory: BString,
+    /// Flags which affect how the untracked files of directories are listed.
+    pub dir_flags: u32,
+    /// All directories in depth-first order, starting with the root directory if there is one.
+    pub directories: Vec<untracked_cache::Directory>,
+}
+
+/// The state of the file system monitor, used to learn which entries need to be checked for changes.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct FsMonitor {
+    /// The token to pass to the file system monitor to learn about all changes since it was obtained.
+    pub token: fs_monitor::Token,
+    /// A bitmap with a set bit for each entry which has to be checked as its file may have changed.
+    pub entry_dirty: ewah::Vec,
+}
+
+///
+pub mod tree;
+
+///
+pub mod resolve_undo;
+
+///
+pub mod untracked_cache;
+
+///
+pub mod link;
+
+///
+pub mod fs_monitor;
+
+pub(crate) mod end_of_index_entry;
+pub(crate) mod index_entry_offset_table;
+
+pub(crate) mod sparse {
+    pub const SIGNATURE: super::Signature = *b"sdir";
+}
+
+pub(crate) fn read_stat(data: &[u8]) -> Option<(entry::Stat, &[u8])> {
+    use crate::util::read_u32;
+    let (ctime_secs, data) = read_u32(data)?;
+    let (ctime_nsecs, data) = read_u32(data)?;
+    let (mtime_secs, data) = read_u32(data)?;
+    let (mtime_nsecs, data) = read_u32(data)?;
+    let (dev, data) = read_u32(data)?;
+    let (ino, data) = read_u32(data)?;
+    let (uid, data) = read_u32(data)?;
+    let (gid, data) = read_u32(data)?;
+    let (size, data) = read_u32(data)?;
+    Some((
+        entry::Stat {
+            ctime: entry::Time {
+                secs: ctime_secs,
+                nsecs: ctime_nsecs,
+            },
+            mtime: entry::Time {
+                secs: mtime_secs,
+                nsecs: mtime_nsecs,
+            },
+            dev,
+            ino,
+            uid,
+            gid,
+            size,
+        },
+        data,
+    ))
+}
+
+pub(crate) fn write_stat(stat: &entry::Stat, out: &mut Vec<u8>) {
+    for field in &[
+        stat.ctime.secs,
+        stat.ctime.nsecs,
+        stat.mtime.secs,
+        stat.mtime.nsecs,
+        stat.dev,
+        stat.ino,
+        stat.uid,
+        stat.gid,
+        stat.size,
+    ] {
+        out.extend_from_slice(&field.to_be_bytes());
+    }
+}
@@ -0,0 +1,78 @@
+use git_hash::ObjectId;
+use git_object::{bstr::BString, tree::EntryMode};
+
+use crate::{
+    extension::Signature,
+    util::{entry_mode_from_u32, split_at_byte_exclusive, split_at_pos},
+};
+
+pub(crate) const SIGNATURE: Signature = *b"REUC";
+
+/// All paths with resolved conflicts, sorted by name.
+pub type Paths = Vec<ResolvePath>;
+
+/// The stages of a conflict at `name` before it was resolved, allowing to recreate the conflict.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct ResolvePath {
+    /// The path of the entry relative to the root of the worktree.
+    pub name: BString,
+    /// The merge base, our and their version of the entry, for the stages `1` to `3` respectively, or `None` if a side didn't have it.
+    pub stages: [Option<Stage>; 3],
+}
+
+/// A single stage of a [resolved conflict][ResolvePath].
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub struct Stage {
+    /// The kind of item.
+    pub mode: EntryMode,
+    /// The id of the object.
+    pub id: ObjectId,
+}
+
+pub(crate) fn decode(mut data: &[u8]) -> Option<Paths> {
+    let mut out = Vec::new();
+    while !data.is_empty() {
+        let (name, rest) = split_at_byte_exclusive(data, 0)?;
+        data = rest;
+
+        let mut modes = [0u32; 3];
+        for mode in modes.iter_mut() {
+            let (octal, rest) = split_at_byte_exclusive(data, 0)?;
+            data = rest;
+            *mode = u32::from_str_radix(std::str::from_utf8(octal).ok()?, 8).ok()?;
+        }
+
+        let mut stages = [None, None, None];
+        for (mode, stage) in modes.iter().zip(stages.iter_mut()) {
+            if *mode == 0 {
+                continue;
+            }
+            let (hash, rest) = split_at_pos(data, 20)?;
+            data = rest;
+            *stage = Some(Stage {
+                mode: entry_mode_from_u32(*mode)?,
+                id: ObjectId::from_20_bytes(hash),
+            });
+        }
+        out.push(ResolvePath {
+            name: name.into(),
+            stages,
+        });
+    }
+    Some(out)
+}
+
+pub(crate) fn encode(paths: &[ResolvePath], out: &mut Vec<u8>) {
+    for path in paths {
+        out.extend_from_slice(&path.name);
+        out.push(0);
+        for stage in &path.stages {
+            let mode = stage.map_or(0, |stage| stage.mode as u32);
+            out.extend_from_slice(format!("{:o}", mode).as_bytes());
+            out.push(0);
+        }
+        for stage in path.stages.iter().flatten() {
+            out.extend_from_slice(stage.id.as_slice());
+        }
+    }
+}
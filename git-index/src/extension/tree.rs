@@ -0,0 +1,92 @@
+use git_hash::ObjectId;
+
+use crate::{
+    extension::{Signature, Tree},
+    util::{split_at_byte_exclusive, split_at_pos},
+};
+
+pub(crate) const SIGNATURE: Signature = *b"TREE";
+
+/// Decode the root tree from `data`, returning `None` if it is corrupt.
+pub(crate) fn decode(data: &[u8]) -> Option<Tree> {
+    let (tree, data) = one_recursive(data)?;
+    if data.is_empty() {
+        Some(tree)
+    } else {
+        None
+    }
+}
+
+fn one_recursive(data: &[u8]) -> Option<(Tree, &[u8])> {
+    let (name, data) = split_at_byte_exclusive(data, 0)?;
+    let (num_entries, data) = split_at_byte_exclusive(data, b' ')?;
+    let num_entries: i32 = btoi::btoi(num_entries).ok()?;
+    let (num_children, mut data) = split_at_byte_exclusive(data, b'\n')?;
+    let num_children: usize = btoi::btou(num_children).ok()?;
+
+    let id = if num_entries >= 0 {
+        let (hash, rest) = split_at_pos(data, 20)?;
+        data = rest;
+        Some(ObjectId::from_20_bytes(hash))
+    } else {
+        None
+    };
+
+    let mut children = Vec::with_capacity(num_children);
+    for _ in 0..num_children {
+        let (child, rest) = one_recursive(data)?;
+        children.push(child);
+        data = rest;
+    }
+
+    Some((
+        Tree {
+            name: name.into(),
+            id,
+            num_entries: num_entries.max(0) as u32,
+            children,
+        },
+        data,
+    ))
+}
+
+/// Encode `tree` and all of its children into `out`.
+pub(crate) fn encode(tree: &Tree, out: &mut Vec<u8>) {
+    out.extend_from_slice(&tree.name);
+    out.push(0);
+    let num_entries = match tree.id {
+        Some(_) => tree.num_entries as i64,
+        None => -1,
+    };
+    out.extend_from_slice(format!("{} {}\n", num_entries, tree.children.len()).as_bytes());
+    if let Some(id) = tree.id {
+        out.extend_from_slice(id.as_slice());
+    }
+    for child in &tree.children {
+        encode(child, out);
+    }
+}
+
+impl Tree {
+    /// Invalidate this tree and all trees leading to the item at `path`, as its content is about to change.
+    ///
+    /// A tree named like the last component of `path` is removed, as it was replaced by a file.
+    pub(crate) fn invalidate_path(&mut self, path: &[u8]) {
+        self.id = None;
+        self.num_entries = 0;
+        match path.iter().position(|b| *b == b'/') {
+            Some(pos) => {
+                let (name, rest) = (&path[..pos], &path[pos + 1..]);
+                if let Some(child) = self.children.iter_mut().find(|c| c.name == name) {
+                    child.invalidate_path(rest);
+                }
+            }
+            None => self.children.retain(|c| c.name != path),
+        }
+    }
+}
+
+/// Sort `children` like git does, by the length of their name first and by name second.
+pub(crate) fn sort_children(children: &mut [Tree]) {
+    children.sort_by(|a, b| a.name.len().cmp(&b.name.len()).then_with(|| a.name.cmp(&b.name)));
+}
@@ -0,0 +1,230 @@
+use git_hash::ObjectId;
+use git_object::bstr::BString;
+
+use crate::{
+    entry, ewah,
+    extension::{read_stat, write_stat, Signature, UntrackedCache},
+    util::{read_u32, split_at_byte_exclusive, split_at_pos, var_int, write_var_int},
+};
+
+pub(crate) const SIGNATURE: Signature = *b"UNTR";
+
+/// The stat information and object id of a file, describing its state at the time the cache was written.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub struct OidStat {
+    /// The stat information of the file.
+    pub stat: entry::Stat,
+    /// The id of the blob with the content of the file, or the null id if the file didn't exist.
+    pub id: ObjectId,
+}
+
+/// A directory in the [untracked cache][UntrackedCache].
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct Directory {
+    /// The name of the directory relative to its parent, or empty for the root directory.
+    pub name: BString,
+    /// The names of all untracked files and directories within this directory, with directories having a trailing slash.
+    pub untracked_entries: Vec<BString>,
+    /// The indices of the sub-directories in [`UntrackedCache::directories`].
+    pub sub_directories: Vec<usize>,
+    /// The stat information of the directory at the time its untracked entries were listed, or `None` if the entries
+    /// have to be listed again.
+    pub stat: Option<entry::Stat>,
+    /// The id of the per-directory exclude file in this directory, if it was read.
+    pub exclude_file_oid: Option<ObjectId>,
+    /// If true, only the presence of untracked files was checked, without listing all of them.
+    pub check_only: bool,
+}
+
+pub(crate) fn decode(data: &[u8]) -> Option<UntrackedCache> {
+    let (last, data) = data.split_last()?;
+    if *last != 0 {
+        return None;
+    }
+    let (identifier_len, data) = var_int(data)?;
+    let (identifier, data) = split_at_pos(data, identifier_len as usize)?;
+
+    let (info_exclude_stat, data) = read_stat(data)?;
+    let (excludes_file_stat, data) = read_stat(data)?;
+    let (dir_flags, data) = read_u32(data)?;
+    let (info_exclude_id, data) = split_at_pos(data, 20)?;
+    let (excludes_file_id, data) = split_at_pos(data, 20)?;
+    let (exclude_filename_per_directory, data) = split_at_byte_exclusive(data, 0)?;
+
+    let mut out = UntrackedCache {
+        identifier: identifier.into(),
+        info_exclude: OidStat {
+            stat: info_exclude_stat,
+            id: ObjectId::from_20_bytes(info_exclude_id),
+        },
+        excludes_file: OidStat {
+            stat: excludes_file_stat,
+            id: ObjectId::from_20_bytes(excludes_file_id),
+        },
+        exclude_filename_per_directory: exclude_filename_per_directory.into(),
+        dir_flags,
+        directories: Vec::new(),
+    };
+    if data.is_empty() {
+        return Some(out);
+    }
+
+    let (num_directories, data) = var_int(data)?;
+    if num_directories == 0 {
+        return None;
+    }
+    out.directories.reserve(num_directories as usize);
+    let data = decode_directory_recursive(data, &mut out.directories)?;
+    if out.directories.len() != num_directories as usize {
+        return None;
+    }
+
+    let (valid, data) = ewah::decode(data).ok()?;
+    let (check_only, data) = ewah::decode(data).ok()?;
+    let (hash_valid, mut data) = ewah::decode(data).ok()?;
+    let directories = &mut out.directories;
+
+    check_only.for_each_set_bit(|idx| {
+        directories.get_mut(idx)?.check_only = true;
+        Some(())
+    })?;
+    valid.for_each_set_bit(|idx| {
+        let (stat, rest) = read_stat(data)?;
+        data = rest;
+        directories.get_mut(idx)?.stat = Some(stat);
+        Some(())
+    })?;
+    hash_valid.for_each_set_bit(|idx| {
+        let (hash, rest) = split_at_pos(data, 20)?;
+        data = rest;
+        directories.get_mut(idx)?.exclude_file_oid = Some(ObjectId::from_20_bytes(hash));
+        Some(())
+    })?;
+
+    if data.is_empty() {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+fn decode_directory_recursive<'a>(data: &'a [u8], directories: &mut Vec<Directory>) -> Option<&'a [u8]> {
+    let (num_untracked, data) = var_int(data)?;
+    let (num_dirs, data) = var_int(data)?;
+    let (name, mut data) = split_at_byte_exclusive(data, 0)?;
+    let mut untracked_entries = Vec::with_capacity(num_untracked as usize);
+    for _ in 0..num_untracked {
+        let (entry, rest) = split_at_byte_exclusive(data, 0)?;
+        data = rest;
+        untracked_entries.push(entry.into());
+    }
+
+    let index = directories.len();
+    directories.push(Directory {
+        name: name.into(),
+        untracked_entries,
+        ..Default::default()
+    });
+
+    let mut sub_directories = Vec::with_capacity(num_dirs as usize);
+    for _ in 0..num_dirs {
+        sub_directories.push(directories.len());
+        data = decode_directory_recursive(data, directories)?;
+    }
+    directories[index].sub_directories = sub_directories;
+    Some(data)
+}
+
+/// Encode `cache` into `out` just like git does, which drops the untracked entries of directories without stat information.
+pub(crate) fn encode(cache: &UntrackedCache, out: &mut Vec<u8>) {
+    write_var_int(out, cache.identifier.len() as u64);
+    out.extend_from_slice(&cache.identifier);
+    write_stat(&cache.info_exclude.stat, out);
+    write_stat(&cache.excludes_file.stat, out);
+    out.extend_from_slice(&cache.dir_flags.to_be_bytes());
+    out.extend_from_slice(cache.info_exclude.id.as_slice());
+    out.extend_from_slice(cache.excludes_file.id.as_slice());
+    out.extend_from_slice(&cache.exclude_filename_per_directory);
+    out.push(0);
+
+    if cache.directories.is_empty() {
+        write_var_int(out, 0);
+        return;
+    }
+
+    let mut valid = ewah::Vec::default();
+    let mut check_only = ewah::Vec::default();
+    let mut hash_valid = ewah::Vec::default();
+    let mut stats = Vec::new();
+    let mut hashes = Vec::new();
+    let mut order = Vec::with_capacity(cache.directories.len());
+    let mut directories = Vec::new();
+    encode_directory_recursive(&cache.directories, 0, &mut directories, &mut order);
+    write_var_int(out, order.len() as u64);
+    out.extend_from_slice(&directories);
+    for (idx, dir) in order.into_iter().map(|dir_idx| &cache.directories[dir_idx]).enumerate() {
+        if let Some(stat) = dir.stat {
+            if dir.check_only {
+                check_only.set(idx);
+            }
+            valid.set(idx);
+            write_stat(&stat, &mut stats);
+        }
+        if let Some(id) = dir.exclude_file_oid.filter(|id| !id.is_null()) {
+            hash_valid.set(idx);
+            hashes.extend_from_slice(id.as_slice());
+        }
+    }
+    for bitmap in &[valid, check_only, hash_valid] {
+        bitmap.write_to(&mut *out).expect("writing to a vec never fails");
+    }
+    out.extend_from_slice(&stats);
+    out.extend_from_slice(&hashes);
+    out.push(0);
+}
+
+fn encode_directory_recursive(directories: &[Directory], index: usize, out: &mut Vec<u8>, order: &mut Vec<usize>) {
+    let dir = &directories[index];
+    order.push(index);
+    let untracked_entries = if dir.stat.is_some() {
+        dir.untracked_entries.as_slice()
+    } else {
+        &[]
+    };
+    write_var_int(out, untracked_entries.len() as u64);
+    write_var_int(out, dir.sub_directories.len() as u64);
+    out.extend_from_slice(&dir.name);
+    out.push(0);
+    for entry in untracked_entries {
+        out.extend_from_slice(entry);
+        out.push(0);
+    }
+    for sub_directory in &dir.sub_directories {
+        encode_directory_recursive(directories, *sub_directory, out, order);
+    }
+}
+
+impl UntrackedCache {
+    /// Invalidate all directories leading to the item at `path`, causing their untracked entries to be listed again.
+    pub(crate) fn invalidate_path(&mut self, path: &[u8]) {
+        let mut index = 0;
+        let mut components = path.split(|b| *b == b'/');
+        while let Some(dir) = self.directories.get_mut(index) {
+            dir.stat = None;
+            dir.untracked_entries.clear();
+            dir.check_only = false;
+            let name = match components.next() {
+                Some(name) => name,
+                None => break,
+            };
+            let sub_directories = dir.sub_directories.clone();
+            index = match sub_directories
+                .into_iter()
+                .find(|idx| self.directories.get(*idx).map_or(false, |d| d.name == name))
+            {
+                Some(idx) => idx,
+                None => break,
+            };
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::{io::Write, path::PathBuf};
+
+use git_hash::ObjectId;
+
+use crate::{File, State};
+
+///
+pub mod init {
+    use quick_error::quick_error;
+    use std::path::PathBuf;
+
+    quick_error! {
+        /// The error returned by [`File::at()`][crate::File::at()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io { source: std::io::Error, path: PathBuf } {
+                display("Could not read index file at '{}'", path.display())
+                source(source)
+            }
+            Decode { source: crate::decode::Error, path: PathBuf } {
+                display("Could not decode index file at '{}'", path.display())
+                source(source)
+            }
+            SharedIndex { source: Box<Error>, path: PathBuf } {
+                display("Could not read shared index of split index at '{}'", path.display())
+                source(&**source)
+            }
+            Link { path: PathBuf } {
+                display("The split index at '{}' doesn't match its shared index", path.display())
+            }
+        }
+    }
+}
+
+///
+pub mod write {
+    use quick_error::quick_error;
+    use std::path::PathBuf;
+
+    quick_error! {
+        /// The error returned by [`File::write()`][crate::File::write()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io { source: std::io::Error, path: PathBuf } {
+                display("Could not write index file at '{}'", path.display())
+                source(source)
+            }
+            AcquireLock(err: git_lock::acquire::Error) {
+                display("Could not obtain the lock for the index file")
+                from()
+                source(err)
+            }
+            Commit { source: std::io::Error, path: PathBuf } {
+                display("Could not move the lock file into place at '{}'", path.display())
+                source(source)
+            }
+        }
+    }
+}
+
+/// Initialization
+impl File {
+    /// Open the index file at `path` and decode it.
+    ///
+    /// If it's a split index, its shared index is read from the same directory and merged into it.
+    pub fn at(path: impl Into<PathBuf>) -> Result<Self, init::Error> {
+        let path = path.into();
+        let data = std::fs::read(&path).map_err(|err| init::Error::Io {
+            source: err,
+            path: path.clone(),
+        })?;
+        let (mut state, checksum) = State::from_bytes(&data).map_err(|err| init::Error::Decode {
+            source: err,
+            path: path.clone(),
+        })?;
+
+        if let Some(link) = state.link.as_ref().filter(|link| !link.shared_index_checksum.is_null()) {
+            let shared_path = path
+                .parent()
+                .map(ToOwned::to_owned)
+                .unwrap_or_default()
+                .join(format!("sharedindex.{}", link.shared_index_checksum));
+            let shared = File::at(&shared_path).map_err(|err| init::Error::SharedIndex {
+                source: Box::new(err),
+                path: path.clone(),
+            })?;
+            if shared.checksum != link.shared_index_checksum {
+                return Err(init::Error::Link { path });
+            }
+            let split_entries = std::mem::take(&mut state.entries);
+            state.entries = link
+                .merge_entries(split_entries, shared.state.entries)
+                .ok_or_else(|| init::Error::Link { path: path.clone() })?;
+        }
+
+        Ok(File { state, path, checksum })
+    }
+
+    /// Create a new index file at `path` with the given `state`, without writing it yet.
+    ///
+    /// Its checksum is null until it is [written][File::write()].
+    pub fn from_state(state: State, path: impl Into<PathBuf>) -> Self {
+        File {
+            state,
+            path: path.into(),
+            checksum: ObjectId::null_sha1(),
+        }
+    }
+}
+
+/// Writing
+impl File {
+    /// Write the index back to [its path][File::path()] through a lock file, failing according to `lock_mode` if
+    /// the index is locked already.
+    ///
+    /// Split indices are written as a single index file, and the version is adjusted to the one that was written.
+    pub fn write(&mut self, lock_mode: git_lock::acquire::Fail) -> Result<(), write::Error> {
+        let mut lock = git_lock::File::acquire_to_update_resource(&self.path, lock_mode, None)?;
+        let state = &self.state;
+        let checksum = lock
+            .with_mut(|out| {
+                let mut out = std::io::BufWriter::new(out);
+                let checksum = state.write_to(&mut out)?;
+                out.flush()?;
+                Ok(checksum)
+            })
+            .map_err(|err| write::Error::Io {
+                source: err,
+                path: self.path.clone(),
+            })?;
+        lock.close()
+            .map_err(|err| write::Error::Io {
+                source: err,
+                path: self.path.clone(),
+            })?
+            .commit()
+            .map_err(|err| write::Error::Commit {
+                source: err.error,
+                path: self.path.clone(),
+            })?;
+
+        self.state.version = self.state.version_for_writing();
+        self.state.link = None;
+        self.checksum = checksum;
+        Ok(())
+    }
+}
@@ -1 +1,165 @@
-#![forbid(unsafe_code, rust_2018_idioms)]
+//! A crate for reading the git index file, also known as the staging area, which describes the tree of the next commit
+//! along with information about the files in the worktree.
+//!
+//! The index is represented by a [`State`] which holds all [entries][Entry] sorted by path and stage, along with the
+//! content of all supported [extensions][extension].
+//! A [`File`] is a `State` read from a file on disk, like `.git/index`, which can be [modified][State::add_entry()]
+//! and [written][File::write()] back atomically.
+//!
+//! An index can be [created from a tree][State::from_tree()] and [turned into trees][State::write_tree()] again.
+//!
+//! ## Supported extensions
+//!
+//! * **TREE** - the [cached tree][extension::Tree]
+//! * **REUC** - [resolve undo][extension::resolve_undo] information of resolved conflicts
+//! * **UNTR** - the [untracked cache][extension::UntrackedCache]
+//! * **link** - [split index][extension::Link] whose shared index is merged in transparently
+//! * **FSMN** - [file system monitor][extension::FsMonitor] state
+//! * **EOIE** and **IEOT** - the end of index entries and index entry offset table, which are validated when present
+//! * **sdir** - the marker for [sparse indices][State::is_sparse()]
+#![forbid(unsafe_code)]
+#![deny(missing_docs, rust_2018_idioms)]
+
+use std::path::PathBuf;
+
+use git_hash::ObjectId;
+
+///
+pub mod entry;
+pub use entry::Entry;
+
+///
+pub mod extension;
+
+///
+pub mod decode;
+
+///
+pub mod file;
+
+///
+pub mod ewah;
+
+///
+pub mod read_tree;
+
+///
+pub mod write_tree;
+
+mod mutate;
+mod util;
+mod write;
+
+/// The version of an index file, which determines how entries are stored.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy)]
+pub enum Version {
+    /// The initial supported version.
+    V2 = 2,
+    /// Like V2, but adds support for extended flags of entries, like `skip-worktree` and `intent-to-add`.
+    V3 = 3,
+    /// Like V3, but compresses paths of entries by storing only the part which differs from the path of the previous entry.
+    V4 = 4,
+}
+
+/// An in-memory representation of the index, with all entries sorted by path and stage along with all extensions.
+///
+/// If the index was a split index, it will already contain all entries of its shared index.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct State {
+    version: Version,
+    entries: Vec<Entry>,
+    tree: Option<extension::Tree>,
+    resolve_undo: Option<extension::resolve_undo::Paths>,
+    untracked: Option<extension::UntrackedCache>,
+    fs_monitor: Option<extension::FsMonitor>,
+    link: Option<extension::Link>,
+    is_sparse: bool,
+}
+
+/// An index file on disk along with its decoded [`State`], which is accessible through dereferencing.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct File {
+    state: State,
+    path: PathBuf,
+    checksum: ObjectId,
+}
+
+/// Access
+impl State {
+    /// Return the version of the index format.
+    pub fn version(&self) -> Version {
+        self.version
+    }
+    /// Return all entries, sorted by path and stage.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+    /// Return the cached tree, if present.
+    pub fn tree(&self) -> Option<&extension::Tree> {
+        self.tree.as_ref()
+    }
+    /// Return the resolve undo information of previously resolved conflicts, if present.
+    pub fn resolve_undo(&self) -> Option<&extension::resolve_undo::Paths> {
+        self.resolve_undo.as_ref()
+    }
+    /// Return the untracked cache, if present.
+    pub fn untracked(&self) -> Option<&extension::UntrackedCache> {
+        self.untracked.as_ref()
+    }
+    /// Return the state of the file system monitor, if present.
+    pub fn fs_monitor(&self) -> Option<&extension::FsMonitor> {
+        self.fs_monitor.as_ref()
+    }
+    /// Return the link to the shared index if this index was read from a split index.
+    pub fn link(&self) -> Option<&extension::Link> {
+        self.link.as_ref()
+    }
+    /// Return true if this is a sparse index, which may contain entries for directories outside of the sparse checkout
+    /// that stand in for all of their files.
+    pub fn is_sparse(&self) -> bool {
+        self.is_sparse
+    }
+
+    /// Return the index of the entry at `path` in `stage`, or `None` if there is no such entry.
+    pub fn entry_index_by_path_and_stage(&self, path: &git_object::bstr::BStr, stage: u8) -> Option<usize> {
+        self.position_by_path_and_stage(path, stage).ok()
+    }
+
+    /// Return the entry at `path` in `stage`, or `None` if there is no such entry.
+    pub fn entry_by_path_and_stage(&self, path: &git_object::bstr::BStr, stage: u8) -> Option<&Entry> {
+        self.entry_index_by_path_and_stage(path, stage)
+            .map(|idx| &self.entries[idx])
+    }
+}
+
+/// Access
+impl File {
+    /// Return the path from which the index was read.
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+    /// Return the checksum over the whole content of the index file as found at its end.
+    ///
+    /// It's the null hash if the index was written without computing it, as done if `index.skipHash` is set.
+    pub fn checksum(&self) -> ObjectId {
+        self.checksum
+    }
+    /// Return the decoded state, consuming this instance.
+    pub fn into_state(self) -> State {
+        self.state
+    }
+}
+
+impl std::ops::Deref for File {
+    type Target = State;
+
+    fn deref(&self) -> &Self::Target {
+        &self.state
+    }
+}
+
+impl std::ops::DerefMut for File {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.state
+    }
+}
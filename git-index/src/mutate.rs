@@ -0,0 +1,128 @@
+use git_object::bstr::BStr;
+
+use crate::{extension::resolve_undo, Entry, State, Version};
+
+/// Initialization
+impl State {
+    /// Create a new empty index which will be written as `version`.
+    pub fn new(version: Version) -> Self {
+        State {
+            version,
+            entries: Vec::new(),
+            tree: None,
+            resolve_undo: None,
+            untracked: None,
+            fs_monitor: None,
+            link: None,
+            is_sparse: false,
+        }
+    }
+}
+
+/// Mutation
+impl State {
+    /// Set the `version` to use when writing the index.
+    ///
+    /// Note that version 2 and 3 are chosen automatically depending on the flags of all entries.
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
+
+    /// Return all entries for updating their stat information or flags.
+    ///
+    /// Neither the path nor the stage must be changed as entries have to stay sorted, and entries with a changed
+    /// `id` or `mode` should be [added][State::add_entry()] instead to invalidate the cached tree.
+    pub fn entries_mut(&mut self) -> &mut [Entry] {
+        &mut self.entries
+    }
+
+    /// Add `entry` at its path and stage, returning the entry it replaced if there was one.
+    ///
+    /// Like in git, adding an entry in stage `0` resolves a conflict by removing all entries in higher stages at its path,
+    /// which are kept as [resolve undo][State::resolve_undo()] information, while adding an entry in a higher stage
+    /// removes the entry in stage `0`.
+    pub fn add_entry(&mut self, entry: Entry) -> Option<Entry> {
+        self.invalidate_path(entry.path.as_slice());
+        let path = entry.path.clone();
+        let stage = entry.stage();
+        if stage == 0 {
+            for conflict_stage in 1..=3 {
+                self.remove_at_path_and_stage(path.as_ref(), conflict_stage);
+            }
+        } else {
+            self.remove_at_path_and_stage(path.as_ref(), 0);
+        }
+
+        match self.position_by_path_and_stage(path.as_ref(), stage) {
+            Ok(idx) => Some(std::mem::replace(&mut self.entries[idx], entry)),
+            Err(idx) => {
+                self.entries.insert(idx, entry);
+                None
+            }
+        }
+    }
+
+    /// Remove the entry at `path` in `stage` and return it, or `None` if there was no such entry.
+    ///
+    /// Removing an entry in a stage higher than `0` records it as [resolve undo][State::resolve_undo()] information.
+    pub fn remove_entry(&mut self, path: &BStr, stage: u8) -> Option<Entry> {
+        self.invalidate_path(path.as_ref());
+        self.remove_at_path_and_stage(path, stage)
+    }
+
+    /// Remove all entries at `path` regardless of their stage and return them.
+    pub fn remove_path(&mut self, path: &BStr) -> Vec<Entry> {
+        self.invalidate_path(path.as_ref());
+        (0..=3)
+            .filter_map(|stage| self.remove_at_path_and_stage(path, stage))
+            .collect()
+    }
+
+    pub(crate) fn position_by_path_and_stage(&self, path: &BStr, stage: u8) -> Result<usize, usize> {
+        self.entries
+            .binary_search_by(|e| e.path.as_slice().cmp(path.as_ref()).then(e.stage().cmp(&stage)))
+    }
+
+    fn remove_at_path_and_stage(&mut self, path: &BStr, stage: u8) -> Option<Entry> {
+        let idx = self.position_by_path_and_stage(path, stage).ok()?;
+        let entry = self.entries.remove(idx);
+        if stage != 0 {
+            self.record_resolve_undo(&entry);
+        }
+        Some(entry)
+    }
+
+    fn record_resolve_undo(&mut self, entry: &Entry) {
+        let paths = self.resolve_undo.get_or_insert_with(Vec::new);
+        let idx = match paths.binary_search_by(|p| p.name.cmp(&entry.path)) {
+            Ok(idx) => idx,
+            Err(idx) => {
+                paths.insert(
+                    idx,
+                    resolve_undo::ResolvePath {
+                        name: entry.path.clone(),
+                        stages: [None, None, None],
+                    },
+                );
+                idx
+            }
+        };
+        paths[idx].stages[entry.stage() as usize - 1] = Some(resolve_undo::Stage {
+            mode: entry.mode,
+            id: entry.id,
+        });
+    }
+
+    /// Invalidate all information cached in extensions which is affected by a change to the entry at `path`.
+    ///
+    /// The file system monitor state is dropped entirely as its bitmap refers to entries by position.
+    fn invalidate_path(&mut self, path: &[u8]) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.invalidate_path(path);
+        }
+        if let Some(cache) = self.untracked.as_mut() {
+            cache.invalidate_path(path);
+        }
+        self.fs_monitor = None;
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+
+use git_hash::{oid, ObjectId};
+use git_object::{
+    bstr::{BString, ByteSlice},
+    immutable,
+};
+
+use crate::{entry, extension, Entry, State, Version};
+
+mod error {
+    use git_hash::ObjectId;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`State::from_tree()`][crate::State::from_tree()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            NotFound { oid: ObjectId } {
+                display("The tree {} could not be found", oid)
+            }
+            Traverse(err: git_traverse::tree::breadthfirst::Error) {
+                display("Could not traverse the tree")
+                from()
+                source(err)
+            }
+        }
+    }
+}
+pub use error::Error;
+
+/// Initialization
+impl State {
+    /// Create an index with an entry for each blob and submodule in the `tree` and all of its subtrees, like `git read-tree` does.
+    ///
+    /// `find` is used to look up trees and return an iterator over their entries, with data written into the provided buffer.
+    /// Entries have no stat information, and the [cached tree][State::tree()] is filled with the ids of all traversed trees so that
+    /// [writing the tree][State::write_tree()] won't have to write any tree unless entries are changed.
+    pub fn from_tree<Find>(tree: &oid, mut find: Find) -> Result<Self, Error>
+    where
+        Find: for<'a> FnMut(&oid, &'a mut Vec<u8>) -> Option<immutable::TreeIter<'a>>,
+    {
+        let mut buf = Vec::new();
+        let root = find(tree, &mut buf).ok_or_else(|| Error::NotFound { oid: tree.to_owned() })?;
+        let mut recorder = git_traverse::tree::Recorder::default();
+        git_traverse::tree::breadthfirst(
+            root,
+            git_traverse::tree::breadthfirst::State::default(),
+            |oid, buf| find(oid, buf),
+            &mut recorder,
+        )?;
+
+        let mut tree_ids = HashMap::new();
+        let mut entries = Vec::new();
+        for record in recorder.records {
+            if record.mode.is_tree() {
+                tree_ids.insert(record.filepath, record.oid);
+            } else {
+                entries.push(Entry {
+                    stat: entry::Stat::default(),
+                    id: record.oid,
+                    flags: entry::Flags::empty(),
+                    mode: record.mode,
+                    path: record.filepath,
+                });
+            }
+        }
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut state = State::new(Version::V2);
+        state.tree = Some(cached_tree(BString::default(), tree.to_owned(), &entries, 0, &tree_ids));
+        state.entries = entries;
+        Ok(state)
+    }
+}
+
+/// Build the cached tree named `name` with `id` for all `entries` below the directory ending at `prefix_len`.
+fn cached_tree(
+    name: BString,
+    id: ObjectId,
+    entries: &[Entry],
+    prefix_len: usize,
+    tree_ids: &HashMap<BString, ObjectId>,
+) -> extension::Tree {
+    let mut children = Vec::new();
+    let mut idx = 0;
+    while idx < entries.len() {
+        let path = &entries[idx].path;
+        match path[prefix_len..].find_byte(b'/') {
+            Some(pos) => {
+                let dir = &path[..prefix_len + pos + 1];
+                let end = idx + entries[idx..].iter().take_while(|e| e.path.starts_with(dir)).count();
+                let dir_path = &dir[..dir.len() - 1];
+                let id = tree_ids[dir_path.as_bstr()];
+                let name = path[prefix_len..prefix_len + pos].into();
+                children.push(cached_tree(name, id, &entries[idx..end], dir.len(), tree_ids));
+                idx = end;
+            }
+            None => idx += 1,
+        }
+    }
+    extension::tree::sort_children(&mut children);
+    extension::Tree {
+        name,
+        id: Some(id),
+        num_entries: entries.len() as u32,
+        children,
+    }
+}
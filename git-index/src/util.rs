@@ -0,0 +1,68 @@
+use std::convert::TryInto;
+
+use git_object::tree::EntryMode;
+
+pub fn read_u32(data: &[u8]) -> Option<(u32, &[u8])> {
+    split_at_pos(data, 4).map(|(num, rest)| (u32::from_be_bytes(num.try_into().expect("4 bytes")), rest))
+}
+
+pub fn read_u64(data: &[u8]) -> Option<(u64, &[u8])> {
+    split_at_pos(data, 8).map(|(num, rest)| (u64::from_be_bytes(num.try_into().expect("8 bytes")), rest))
+}
+
+pub fn read_u16(data: &[u8]) -> Option<(u16, &[u8])> {
+    split_at_pos(data, 2).map(|(num, rest)| (u16::from_be_bytes(num.try_into().expect("2 bytes")), rest))
+}
+
+pub fn split_at_pos(data: &[u8], pos: usize) -> Option<(&[u8], &[u8])> {
+    if data.len() < pos {
+        return None;
+    }
+    Some(data.split_at(pos))
+}
+
+/// Split `data` at the next null byte, returning the bytes before it and the bytes after it.
+pub fn split_at_byte_exclusive(data: &[u8], byte: u8) -> Option<(&[u8], &[u8])> {
+    let pos = data.iter().position(|b| *b == byte)?;
+    Some((&data[..pos], &data[pos + 1..]))
+}
+
+/// Decode the variable-width integer encoding used by git for offsets, where each continuation adds one before shifting.
+pub fn var_int(data: &[u8]) -> Option<(u64, &[u8])> {
+    let mut bytes = data.iter();
+    let mut byte = *bytes.next()?;
+    let mut value = (byte & 0x7f) as u64;
+    let mut consumed = 1;
+    while byte & 0x80 != 0 {
+        byte = *bytes.next()?;
+        consumed += 1;
+        value = value.checked_add(1)?.checked_mul(128)? | (byte & 0x7f) as u64;
+    }
+    Some((value, &data[consumed..]))
+}
+
+pub fn entry_mode_from_u32(mode: u32) -> Option<EntryMode> {
+    Some(match mode {
+        0o040000 => EntryMode::Tree,
+        0o100644 => EntryMode::Blob,
+        0o100755 => EntryMode::BlobExecutable,
+        0o120000 => EntryMode::Link,
+        0o160000 => EntryMode::Commit,
+        _ => return None,
+    })
+}
+
+/// Encode `value` with the variable-width integer encoding used by git for offsets, the inverse of [`var_int()`].
+pub fn write_var_int(out: &mut Vec<u8>, mut value: u64) {
+    let mut buf = [0u8; 16];
+    let mut pos = buf.len() - 1;
+    buf[pos] = (value & 0x7f) as u8;
+    value >>= 7;
+    while value != 0 {
+        value -= 1;
+        pos -= 1;
+        buf[pos] = 0x80 | (value & 0x7f) as u8;
+        value >>= 7;
+    }
+    out.extend_from_slice(&buf[pos..]);
+}
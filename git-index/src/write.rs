@@ -0,0 +1,131 @@
+use std::io::Write;
+
+use git_hash::ObjectId;
+
+use crate::{
+    extension::{self, Signature},
+    util::write_var_int,
+    Entry, State, Version,
+};
+
+/// Encoding
+impl State {
+    /// Serialize this index into `out` and return the checksum written at its end.
+    ///
+    /// Like git, version 2 and 3 are chosen depending on whether entries have extended flags, while version 4 is kept.
+    /// All extensions are written except for the **link** to a shared index, as all entries are written,
+    /// and the **EOIE** and **IEOT** extensions which only serve to speed up reading.
+    pub fn write_to(&self, out: impl std::io::Write) -> std::io::Result<ObjectId> {
+        let version = self.version_for_writing();
+        let mut out = git_features::hash::Write::new(out, git_hash::Kind::Sha1);
+        out.write_all(b"DIRC")?;
+        out.write_all(&(version as u32).to_be_bytes())?;
+        out.write_all(&(self.entries.len() as u32).to_be_bytes())?;
+
+        let mut buf = Vec::new();
+        let mut previous_path: &[u8] = &[];
+        for entry in &self.entries {
+            buf.clear();
+            encode_entry(entry, version, previous_path, &mut buf);
+            out.write_all(&buf)?;
+            previous_path = &entry.path;
+        }
+
+        if let Some(tree) = &self.tree {
+            buf.clear();
+            extension::tree::encode(tree, &mut buf);
+            write_extension(&mut out, extension::tree::SIGNATURE, &buf)?;
+        }
+        if let Some(paths) = &self.resolve_undo {
+            buf.clear();
+            extension::resolve_undo::encode(paths, &mut buf);
+            write_extension(&mut out, extension::resolve_undo::SIGNATURE, &buf)?;
+        }
+        if let Some(cache) = &self.untracked {
+            buf.clear();
+            extension::untracked_cache::encode(cache, &mut buf);
+            write_extension(&mut out, extension::untracked_cache::SIGNATURE, &buf)?;
+        }
+        if let Some(fs_monitor) = &self.fs_monitor {
+            buf.clear();
+            extension::fs_monitor::encode(fs_monitor, &mut buf);
+            write_extension(&mut out, extension::fs_monitor::SIGNATURE, &buf)?;
+        }
+        if self.is_sparse {
+            write_extension(&mut out, extension::sparse::SIGNATURE, &[])?;
+        }
+
+        let checksum = ObjectId::from(out.hash.digest());
+        out.inner.write_all(checksum.as_slice())?;
+        Ok(checksum)
+    }
+
+    /// Return the version the index will have once [written][State::write_to()].
+    pub(crate) fn version_for_writing(&self) -> Version {
+        match self.version {
+            Version::V2 | Version::V3 => {
+                if self.entries.iter().any(|e| !e.flags.extended().is_empty()) {
+                    Version::V3
+                } else {
+                    Version::V2
+                }
+            }
+            Version::V4 => Version::V4,
+        }
+    }
+}
+
+fn write_extension(mut out: impl std::io::Write, signature: Signature, data: &[u8]) -> std::io::Result<()> {
+    out.write_all(&signature)?;
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(data)
+}
+
+fn encode_entry(entry: &Entry, version: Version, previous_path: &[u8], out: &mut Vec<u8>) {
+    const PATH_LEN_MASK: usize = 0x0fff;
+    let start = out.len();
+    let stat = &entry.stat;
+    for field in &[
+        stat.ctime.secs,
+        stat.ctime.nsecs,
+        stat.mtime.secs,
+        stat.mtime.nsecs,
+        stat.dev,
+        stat.ino,
+        entry.mode as u32,
+        stat.uid,
+        stat.gid,
+        stat.size,
+    ] {
+        out.extend_from_slice(&field.to_be_bytes());
+    }
+    out.extend_from_slice(entry.id.as_slice());
+
+    let extended = entry.flags.extended();
+    let mut flags = (entry.flags.bits() & 0xb000) as u16 | entry.path.len().min(PATH_LEN_MASK) as u16;
+    if !extended.is_empty() {
+        flags |= crate::entry::Flags::EXTENDED.bits() as u16;
+    }
+    out.extend_from_slice(&flags.to_be_bytes());
+    if !extended.is_empty() {
+        out.extend_from_slice(&((extended.bits() >> 16) as u16).to_be_bytes());
+    }
+
+    match version {
+        Version::V4 => {
+            let common = previous_path
+                .iter()
+                .zip(entry.path.iter())
+                .take_while(|(a, b)| a == b)
+                .count();
+            write_var_int(out, (previous_path.len() - common) as u64);
+            out.extend_from_slice(&entry.path[common..]);
+            out.push(0);
+        }
+        Version::V2 | Version::V3 => {
+            out.extend_from_slice(&entry.path);
+            let padded_len = (out.len() - start + 8) & !7;
+            out.resize(start + padded_len, 0);
+        }
+    }
+}
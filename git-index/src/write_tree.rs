@@ -0,0 +1,137 @@
+use git_hash::ObjectId;
+use git_object::{
+    bstr::{BString, ByteSlice},
+    mutable,
+    tree::EntryMode,
+};
+
+use crate::{entry, extension, Entry, State};
+
+/// The error returned by [`State::write_tree()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error<E: std::error::Error + 'static> {
+    #[error("Cannot write a tree as the entry at '{}' is unmerged", .path)]
+    Unmerged { path: BString },
+    #[error(transparent)]
+    Write(E),
+}
+
+/// Tree Creation
+impl State {
+    /// Write all entries as nested trees with ids of the given `hash_kind` into `db` and return the id of the root tree,
+    /// like `git write-tree` does.
+    ///
+    /// Subtrees whose id is known from the [cached tree][State::tree()] are reused without writing them again, and the
+    /// cached tree is updated with the ids of all written trees.
+    /// Entries added with the intent to add them are skipped, and keep the trees containing them invalid in the cached tree.
+    /// Subtrees which are empty as they only contain such entries are skipped as well.
+    pub fn write_tree<Db>(&mut self, db: &Db, hash_kind: git_hash::Kind) -> Result<ObjectId, Error<Db::Error>>
+    where
+        Db: git_odb::Write,
+    {
+        if let Some(entry) = self.entries.iter().find(|e| e.stage() != 0) {
+            return Err(Error::Unmerged {
+                path: entry.path.clone(),
+            });
+        }
+        let cached = self.tree.take();
+        let (id, tree) = write_recursive(db, hash_kind, &self.entries, 0, BString::default(), cached)?;
+        self.tree = Some(tree);
+        Ok(id.expect("the root tree is always written"))
+    }
+}
+
+/// Write the tree named `name` for all `entries` below the directory ending at `prefix_len`, unless `cached` already knows it.
+///
+/// The returned id is `None` if the tree isn't the root tree and would be empty, as all of its entries are to be added.
+fn write_recursive<Db>(
+    db: &Db,
+    hash_kind: git_hash::Kind,
+    entries: &[Entry],
+    prefix_len: usize,
+    name: BString,
+    cached: Option<extension::Tree>,
+) -> Result<(Option<ObjectId>, extension::Tree), Error<Db::Error>>
+where
+    Db: git_odb::Write,
+{
+    let mut cached_children = match cached {
+        Some(cached) => match cached.id {
+            Some(id) if cached.num_entries as usize == entries.len() => return Ok((Some(id), cached)),
+            _ => cached.children,
+        },
+        None => Vec::new(),
+    };
+
+    let mut tree_entries = Vec::new();
+    let mut children = Vec::new();
+    let mut is_valid = true;
+    let mut idx = 0;
+    while idx < entries.len() {
+        let entry = &entries[idx];
+        let name = &entry.path[prefix_len..];
+        match name.find_byte(b'/').filter(|pos| pos + 1 < name.len()) {
+            Some(pos) => {
+                let dir = &entry.path[..prefix_len + pos + 1];
+                let end = idx + entries[idx..].iter().take_while(|e| e.path.starts_with(dir)).count();
+                let dir_name = &name[..pos];
+                let cached_child = cached_children
+                    .iter()
+                    .position(|c| c.name == dir_name)
+                    .map(|pos| cached_children.remove(pos));
+                let (id, child) = write_recursive(
+                    db,
+                    hash_kind,
+                    &entries[idx..end],
+                    dir.len(),
+                    dir_name.into(),
+                    cached_child,
+                )?;
+                is_valid &= child.id.is_some();
+                if let Some(id) = id {
+                    tree_entries.push(mutable::tree::Entry {
+                        mode: EntryMode::Tree,
+                        filename: dir_name.into(),
+                        oid: id,
+                    });
+                }
+                children.push(child);
+                idx = end;
+            }
+            None => {
+                if entry.flags.contains(entry::Flags::INTENT_TO_ADD) {
+                    is_valid = false;
+                } else {
+                    // Directories of sparse indices end with a slash.
+                    let filename = if entry.mode.is_tree() {
+                        name.strip_suffix(b"/").unwrap_or(name)
+                    } else {
+                        name
+                    };
+                    tree_entries.push(mutable::tree::Entry {
+                        mode: entry.mode,
+                        filename: filename.into(),
+                        oid: entry.id,
+                    });
+                }
+                idx += 1;
+            }
+        }
+    }
+
+    let id = if tree_entries.is_empty() && prefix_len != 0 {
+        None
+    } else {
+        let tree = mutable::Object::Tree(mutable::Tree { entries: tree_entries });
+        Some(db.write(&tree, hash_kind).map_err(Error::Write)?)
+    };
+    extension::tree::sort_children(&mut children);
+    let tree = extension::Tree {
+        name,
+        id: id.filter(|_| is_valid),
+        num_entries: if is_valid { entries.len() as u32 } else { 0 },
+        children,
+    };
+    Ok((id, tree))
+}
@@ -0,0 +1,30 @@
+use git_index::ewah;
+
+#[test]
+fn set_bits_survive_a_round_trip() -> crate::Result {
+    let mut bits: Vec<usize> = vec![0, 3, 63, 64, 1000];
+    bits.extend(1024..1024 + 64 * 3);
+    bits.push(100_000);
+
+    let mut bitmap = ewah::Vec::default();
+    for bit in &bits {
+        bitmap.set(*bit);
+    }
+    assert_eq!(bitmap.set_bits(), bits);
+    assert_eq!(bitmap.num_bits(), 100_001);
+
+    let mut buf = Vec::new();
+    bitmap.write_to(&mut buf)?;
+    let (decoded, rest) = ewah::decode(&buf)?;
+    assert!(rest.is_empty());
+    assert_eq!(decoded, bitmap);
+    Ok(())
+}
+
+#[test]
+fn empty_bitmaps_are_serialized_like_git_does() -> crate::Result {
+    let mut buf = Vec::new();
+    ewah::Vec::default().write_to(&mut buf)?;
+    assert_eq!(buf, [0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    Ok(())
+}
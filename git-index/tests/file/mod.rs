@@ -0,0 +1,205 @@
+use git_index::{entry, extension, File, Version};
+use git_object::{bstr::ByteSlice, tree::EntryMode};
+
+fn fixture(name: &str) -> crate::Result<(File, String)> {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_index.sh")?;
+    let git_dir = root.join(name).join(".git");
+    let file = File::at(git_dir.join("index"))?;
+    let expected = std::fs::read_to_string(git_dir.join("ls-files.expected"))?;
+    Ok((file, expected))
+}
+
+/// Render entries like `git ls-files --stage` does.
+pub fn ls_files(state: &git_index::State) -> String {
+    state
+        .entries()
+        .iter()
+        .map(|e| format!("{:06o} {} {}\t{}\n", e.mode as u16, e.id, e.stage(), e.path))
+        .collect()
+}
+
+fn entry<'a>(file: &'a File, path: &str) -> &'a git_index::Entry {
+    file.entry_by_path_and_stage(path.into(), 0).expect("entry present")
+}
+
+#[test]
+fn all_fixtures_match_the_entries_listed_by_git() -> crate::Result {
+    for name in &[
+        "v2",
+        "v3",
+        "v4",
+        "conflict",
+        "resolve-undo",
+        "untracked-cache",
+        "fsmonitor",
+        "split-index",
+        "offset-table",
+        "sparse",
+    ] {
+        let (file, expected) = fixture(name)?;
+        assert_eq!(ls_files(&file), expected, "{}", name);
+    }
+    Ok(())
+}
+
+#[test]
+fn v2_with_cached_tree() -> crate::Result {
+    let (file, _) = fixture("v2")?;
+    assert_eq!(file.version(), Version::V2);
+    assert!(!file.checksum().is_null());
+
+    let x = entry(&file, "x");
+    assert_eq!(x.mode, EntryMode::BlobExecutable);
+    assert_eq!(x.stat.size, 2, "stat information is available");
+    assert_eq!(x.flags, entry::Flags::empty());
+    assert_eq!(entry(&file, "link").mode, EntryMode::Link);
+
+    let tree = file.tree().expect("tree extension written by commit");
+    assert_eq!(tree.name, "", "the root tree has no name");
+    assert!(tree.id.is_some());
+    assert_eq!(tree.num_entries, 5);
+    assert_eq!(tree.children.len(), 1);
+    let d = &tree.children[0];
+    assert_eq!((d.name.as_bstr(), d.num_entries), ("d".into(), 2));
+    assert_eq!(d.children[0].name, "e");
+    Ok(())
+}
+
+#[test]
+fn v3_with_extended_flags() -> crate::Result {
+    let (file, _) = fixture("v3")?;
+    assert_eq!(file.version(), Version::V3);
+    assert_eq!(entry(&file, "new").flags, entry::Flags::INTENT_TO_ADD);
+    assert_eq!(entry(&file, "a").flags, entry::Flags::SKIP_WORKTREE);
+    assert_eq!(entry(&file, "x").flags, entry::Flags::empty());
+    let tree = file.tree().expect("present");
+    assert!(tree.id.is_none(), "adding a file invalidated the root tree");
+    assert!(
+        tree.children[0].id.is_some(),
+        "but not the one of an unchanged directory"
+    );
+    Ok(())
+}
+
+#[test]
+fn v4_with_path_compression_has_the_same_entries_as_v2() -> crate::Result {
+    let (v4, _) = fixture("v4")?;
+    let (v2, _) = fixture("v2")?;
+    assert_eq!(v4.version(), Version::V4);
+    let ids_and_paths = |f: &File| f.entries().iter().map(|e| (e.id, e.path.clone())).collect::<Vec<_>>();
+    assert_eq!(ids_and_paths(&v4), ids_and_paths(&v2));
+    Ok(())
+}
+
+#[test]
+fn conflicts_are_represented_by_stages() -> crate::Result {
+    let (file, _) = fixture("conflict")?;
+    for path in &["a", "d/b"] {
+        assert!(file.entry_by_path_and_stage((*path).into(), 0).is_none());
+        for stage in 1..=3 {
+            let entry = file
+                .entry_by_path_and_stage((*path).into(), stage)
+                .expect("all stages present");
+            assert_eq!(entry.stage(), stage);
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn resolve_undo() -> crate::Result {
+    let (file, _) = fixture("resolve-undo")?;
+    let paths = file.resolve_undo().expect("present");
+    assert_eq!(paths.len(), 1);
+    assert_eq!(paths[0].name, "a");
+    assert!(paths[0]
+        .stages
+        .iter()
+        .all(|stage| stage.map(|stage| stage.mode == EntryMode::Blob).unwrap_or(false)));
+    assert_eq!(entry(&file, "a").stage(), 0, "the conflict is resolved");
+    Ok(())
+}
+
+#[test]
+fn untracked_cache() -> crate::Result {
+    let (file, _) = fixture("untracked-cache")?;
+    let cache = file.untracked().expect("present");
+    assert_eq!(cache.exclude_filename_per_directory, ".gitignore");
+    let root = &cache.directories[0];
+    assert_eq!(root.name, "");
+    assert_eq!(root.untracked_entries, vec!["untracked"]);
+    assert!(root.stat.is_some(), "the root was listed");
+    let names: Vec<_> = cache.directories.iter().map(|d| d.name.to_string()).collect();
+    assert_eq!(names, vec!["", "d", "e"], "directories are listed depth-first");
+    assert_eq!(root.sub_directories, vec![1]);
+    assert_eq!(cache.directories[2].untracked_entries, vec!["untracked"]);
+    Ok(())
+}
+
+#[test]
+fn fs_monitor() -> crate::Result {
+    let (file, _) = fixture("fsmonitor")?;
+    let fs_monitor = file.fs_monitor().expect("present");
+    assert_eq!(
+        fs_monitor.token,
+        extension::fs_monitor::Token::V2 { token: "token".into() }
+    );
+    assert_eq!(
+        fs_monitor.entry_dirty.set_bits(),
+        Vec::<usize>::new(),
+        "the entry reported by the hook was refreshed by git"
+    );
+    Ok(())
+}
+
+#[test]
+fn split_index_is_merged_with_its_shared_index() -> crate::Result {
+    let (file, _) = fixture("split-index")?;
+    let link = file.link().expect("present");
+    assert!(link.bitmaps.is_some());
+    assert_eq!(file.entries().len(), 5);
+    assert!(file.entry_by_path_and_stage("x".into(), 0).is_none(), "deleted");
+    assert!(file.entry_by_path_and_stage("new".into(), 0).is_some(), "added");
+    Ok(())
+}
+
+#[test]
+fn offset_table_and_end_of_index_entries_are_verified() -> crate::Result {
+    let (file, _) = fixture("offset-table")?;
+    assert_eq!(file.entries().len(), 1200);
+    Ok(())
+}
+
+#[test]
+fn sparse_index_with_directory_entries() -> crate::Result {
+    let (file, _) = fixture("sparse")?;
+    assert!(file.is_sparse());
+    let dir = entry(&file, "outside/");
+    assert_eq!(dir.mode, EntryMode::Tree);
+    assert_eq!(dir.flags, entry::Flags::SKIP_WORKTREE);
+    Ok(())
+}
+
+#[test]
+fn checksum_mismatches_are_detected() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_index.sh")?;
+    let mut data = std::fs::read(root.join("v2/.git/index"))?;
+    data[20] ^= 0xff;
+    assert!(matches!(
+        git_index::State::from_bytes(&data),
+        Err(git_index::decode::Error::ChecksumMismatch { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn entry_counts_larger_than_the_data_fail_without_allocating_for_them() {
+    let mut data = b"DIRC\0\0\0\x02\xff\xff\xff\xff".to_vec();
+    data.extend_from_slice(&[0; 20]);
+    assert!(matches!(
+        git_index::State::from_bytes(&data),
+        Err(git_index::decode::Error::Entry(0))
+    ));
+}
+
+mod write;
@@ -0,0 +1,142 @@
+use std::process::Command;
+
+use git_index::{entry, File, Version};
+use git_object::{bstr::ByteSlice, tree::EntryMode};
+
+use super::ls_files;
+
+fn git(repo: &std::path::Path, args: &[&str]) -> crate::Result<String> {
+    let out = Command::new("git").args(args).current_dir(repo).output()?;
+    assert!(out.status.success(), "{:?} failed: {}", args, out.stderr.as_bstr());
+    Ok(String::from_utf8(out.stdout)?)
+}
+
+#[test]
+fn writing_reproduces_index_files_written_by_git_exactly() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_index.sh")?;
+    for name in &[
+        "v2",
+        "v3",
+        "v4",
+        "conflict",
+        "resolve-undo",
+        "untracked-cache",
+        "fsmonitor",
+        "sparse",
+    ] {
+        let path = root.join(name).join(".git/index");
+        let file = File::at(&path)?;
+        let mut buf = Vec::new();
+        let checksum = file.write_to(&mut buf)?;
+        assert_eq!(checksum, file.checksum(), "{}", name);
+        assert_eq!(buf, std::fs::read(&path)?, "{}", name);
+    }
+    Ok(())
+}
+
+#[test]
+fn split_indices_and_offset_tables_are_written_as_plain_index() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_index.sh")?;
+    for name in &["split-index", "offset-table"] {
+        let file = File::at(root.join(name).join(".git/index"))?;
+        let mut buf = Vec::new();
+        file.write_to(&mut buf)?;
+        let (state, _) = git_index::State::from_bytes(&buf)?;
+        assert!(state.link().is_none(), "{}", name);
+        assert_eq!(state.entries(), file.entries(), "{}", name);
+    }
+    Ok(())
+}
+
+#[test]
+fn versions_2_and_3_are_chosen_by_extended_flags() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_index.sh")?;
+    let mut state = File::at(root.join("v3/.git/index"))?.into_state();
+    let mut buf = Vec::new();
+    state.write_to(&mut buf)?;
+    assert_eq!(git_index::State::from_bytes(&buf)?.0.version(), Version::V3);
+
+    for entry in state.entries_mut() {
+        entry
+            .flags
+            .remove(entry::Flags::INTENT_TO_ADD | entry::Flags::SKIP_WORKTREE);
+    }
+    buf.clear();
+    state.write_to(&mut buf)?;
+    assert_eq!(
+        git_index::State::from_bytes(&buf)?.0.version(),
+        Version::V2,
+        "like git, version 3 is only used if needed"
+    );
+
+    state.set_version(Version::V4);
+    buf.clear();
+    state.write_to(&mut buf)?;
+    let (v4, _) = git_index::State::from_bytes(&buf)?;
+    assert_eq!(v4.version(), Version::V4);
+    assert_eq!(v4.entries(), state.entries());
+    Ok(())
+}
+
+#[test]
+fn modifications_are_written_atomically_and_understood_by_git() -> crate::Result {
+    let tmp = git_testtools::scripted_fixture_repo_writable("make_index.sh")?;
+    let repo = tmp.path().join("v2");
+    let mut file = File::at(repo.join(".git/index"))?;
+    let a = file.entry_by_path_and_stage("a".into(), 0).expect("present").clone();
+    let b = file.entry_by_path_and_stage("d/b".into(), 0).expect("present").clone();
+
+    assert_eq!(file.remove_path("x".into()).len(), 1);
+    assert!(file.remove_entry("x".into(), 0).is_none(), "it's gone already");
+    let previous = file.add_entry(git_index::Entry { id: b.id, ..a.clone() });
+    assert_eq!(previous.expect("replaced").id, a.id);
+    for stage in 1..=3 {
+        let conflicted = git_index::Entry {
+            flags: entry::Flags::empty().with_stage(stage),
+            path: "d/conflict".into(),
+            ..a.clone()
+        };
+        assert!(file.add_entry(conflicted).is_none());
+    }
+    let tree = file.tree().expect("still present");
+    assert!(tree.id.is_none(), "the root tree was invalidated");
+    assert!(tree.children[0].id.is_none(), "and so was 'd'");
+    assert!(tree.children[0].children[0].id.is_some(), "but not 'd/e'");
+
+    let lock = git_lock::File::acquire_to_update_resource(file.path(), git_lock::acquire::Fail::Immediately, None)?;
+    assert!(
+        matches!(
+            file.write(git_lock::acquire::Fail::Immediately),
+            Err(git_index::file::write::Error::AcquireLock(_))
+        ),
+        "the index is locked"
+    );
+    drop(lock);
+
+    file.write(git_lock::acquire::Fail::Immediately)?;
+    assert!(!repo.join(".git/index.lock").exists());
+    assert_eq!(file.checksum(), File::at(file.path())?.checksum());
+    assert_eq!(git(&repo, &["ls-files", "--stage"])?, ls_files(&file));
+
+    file.add_entry(git_index::Entry {
+        path: "d/conflict".into(),
+        ..a
+    });
+    let paths = file.resolve_undo().expect("resolving a conflict records it").clone();
+    assert_eq!(paths[0].name, "d/conflict");
+    assert!(paths[0]
+        .stages
+        .iter()
+        .all(|s| s.map_or(false, |s| s.mode == EntryMode::Blob)));
+    file.write(git_lock::acquire::Fail::Immediately)?;
+    assert_eq!(git(&repo, &["ls-files", "--stage"])?, ls_files(&file));
+    assert_eq!(
+        git(&repo, &["ls-files", "--resolve-undo"])?,
+        format!(
+            "{mode:06o} {id} 1\td/conflict\n{mode:06o} {id} 2\td/conflict\n{mode:06o} {id} 3\td/conflict\n",
+            mode = EntryMode::Blob as u16,
+            id = paths[0].stages[0].expect("present").id
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,122 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function files() {
+  mkdir -p d/e
+  echo a > a
+  echo b > d/b
+  echo c > d/e/c
+  echo x > x && chmod +x x
+  ln -s a link
+}
+
+function baseline() {
+  git ls-files --stage "$@" > .git/ls-files.expected
+}
+
+(git init -q v2 && cd v2
+  files
+  git add . && git commit -qm "init"
+  baseline
+)
+
+(git init -q v3 && cd v3
+  files
+  git add . && git commit -qm "init"
+  echo new > new && git add --intent-to-add new
+  mkdir -p ita/nested && echo new > ita/nested/new && git add --intent-to-add ita
+  git update-index --skip-worktree a
+  baseline
+)
+
+(git init -q v4 && cd v4
+  files
+  git add . && git commit -qm "init"
+  git update-index --index-version 4
+  baseline
+)
+
+(git init -q conflict && cd conflict
+  files
+  git add . && git commit -qm "init"
+  git checkout -q -b other
+  echo other > a && echo other > d/b && git commit -qam "other"
+  git checkout -q main 2>/dev/null || git checkout -q master
+  echo ours > a && echo ours > d/b && git commit -qam "ours"
+  git merge other >/dev/null || :
+  baseline
+)
+
+(git init -q resolve-undo && cd resolve-undo
+  files
+  git add . && git commit -qm "init"
+  git checkout -q -b other
+  echo other > a && git commit -qam "other"
+  git checkout -q main 2>/dev/null || git checkout -q master
+  echo ours > a && git commit -qam "ours"
+  git merge other >/dev/null || :
+  echo resolved > a && git add a
+  baseline
+)
+
+(git init -q untracked-cache && cd untracked-cache
+  files
+  git add . && git commit -qm "init"
+  echo untracked > untracked && echo untracked > d/e/untracked
+  git update-index --untracked-cache
+  git -c core.untrackedCache=true status --porcelain >/dev/null
+  baseline
+)
+
+(git init -q fsmonitor && cd fsmonitor
+  files
+  git add . && git commit -qm "init"
+  cat > .git/fsmonitor-hook <<'HOOK'
+#!/bin/sh
+printf 'token\0'
+printf 'a\0'
+HOOK
+  chmod +x .git/fsmonitor-hook
+  git config core.fsmonitor .git/fsmonitor-hook
+  git config core.fsmonitorHookVersion 2
+  git update-index --fsmonitor
+  git status --porcelain >/dev/null
+  baseline
+)
+
+(git init -q split-index && cd split-index
+  files
+  git add . && git commit -qm "init"
+  git update-index --split-index
+  echo changed > a && echo new > new && git add a new && git rm -q --cached x
+  baseline
+)
+
+(git init -q offset-table && cd offset-table
+  for i in $(seq 1 1200); do
+    mkdir -p d$((i % 7)) && echo $i > d$((i % 7))/f$i
+  done
+  git add . && git commit -qm "init"
+  git -c index.threads=4 -c index.recordEndOfIndexEntries=true -c index.recordOffsetTable=true update-index --force-write-index
+  baseline
+)
+
+(git init -q sparse && cd sparse
+  files
+  mkdir outside && echo o > outside/o
+  git add . && git commit -qm "init"
+  git sparse-checkout init --cone --sparse-index
+  git sparse-checkout set d/e
+  baseline --sparse
+)
+
+(git init -q read-tree && cd read-tree
+  files
+  echo d > d.txt && echo d > d-x && mkdir -p f/g && echo h > f/g/h && echo i > f/i
+  git add .
+  git update-index --add --cacheinfo 160000,e69de29bb2d1d6434b8b29ae775ad8c2e48c5391,sub
+  git commit -qm "init"
+  git read-tree HEAD
+  git rev-parse HEAD^{tree} > .git/tree.expected
+  baseline
+)
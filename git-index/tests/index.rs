@@ -0,0 +1,5 @@
+pub type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+mod ewah;
+mod file;
+mod tree;
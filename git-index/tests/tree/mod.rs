@@ -0,0 +1,148 @@
+use std::{cell::Cell, process::Command};
+
+use git_hash::ObjectId;
+use git_index::{entry, write_tree, File, State};
+use git_object::bstr::ByteSlice;
+use git_odb::{linked, pack, FindExt};
+
+use crate::file::ls_files;
+
+/// An object database which counts the objects written into it.
+struct Counting<'a> {
+    db: &'a linked::Store,
+    writes: Cell<usize>,
+}
+
+impl<'a> git_odb::Write for Counting<'a> {
+    type Error = <linked::Store as git_odb::Write>::Error;
+
+    fn write_stream(
+        &self,
+        kind: git_object::Kind,
+        size: u64,
+        from: impl std::io::Read,
+        hash: git_hash::Kind,
+    ) -> Result<ObjectId, Self::Error> {
+        self.writes.set(self.writes.get() + 1);
+        self.db.write_stream(kind, size, from, hash)
+    }
+}
+
+fn expected_tree(repo: &std::path::Path) -> crate::Result<ObjectId> {
+    Ok(ObjectId::from_hex(
+        std::fs::read(repo.join(".git/tree.expected"))?.trim(),
+    )?)
+}
+
+#[test]
+fn from_tree_matches_git_read_tree() -> crate::Result {
+    let repo = git_testtools::scripted_fixture_repo_read_only("make_index.sh")?.join("read-tree");
+    let db = linked::Store::at(repo.join(".git/objects"))?;
+    let state = State::from_tree(&expected_tree(&repo)?, |oid, buf| {
+        db.find_existing_tree_iter(oid, buf, &mut pack::cache::Never).ok()
+    })?;
+
+    assert_eq!(
+        ls_files(&state),
+        std::fs::read_to_string(repo.join(".git/ls-files.expected"))?
+    );
+    let file = File::at(repo.join(".git/index"))?;
+    assert_eq!(
+        state.tree(),
+        file.tree(),
+        "the cached tree is primed just like git does it"
+    );
+
+    assert!(matches!(
+        State::from_tree(&ObjectId::null_sha1(), |oid, buf| {
+            db.find_existing_tree_iter(oid, buf, &mut pack::cache::Never).ok()
+        }),
+        Err(git_index::read_tree::Error::NotFound { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn write_tree_without_cached_tree_writes_all_trees() -> crate::Result {
+    let repo = git_testtools::scripted_fixture_repo_read_only("make_index.sh")?.join("read-tree");
+    let file = File::at(repo.join(".git/index"))?;
+    let mut state = State::new(file.version());
+    for entry in file.entries() {
+        state.add_entry(entry.clone());
+    }
+    assert!(state.tree().is_none());
+
+    let id = state.write_tree(&git_odb::sink(), git_hash::Kind::Sha1)?;
+    assert_eq!(id, expected_tree(&repo)?);
+    assert_eq!(state.tree(), file.tree(), "the cached tree is created as well");
+    Ok(())
+}
+
+#[test]
+fn write_tree_reuses_unchanged_trees_of_the_cached_tree() -> crate::Result {
+    let tmp = git_testtools::scripted_fixture_repo_writable("make_index.sh")?;
+    let repo = tmp.path().join("read-tree");
+    let db = linked::Store::at(repo.join(".git/objects"))?;
+    let db = Counting {
+        db: &db,
+        writes: Cell::new(0),
+    };
+    let mut file = File::at(repo.join(".git/index"))?;
+
+    assert_eq!(file.write_tree(&db, git_hash::Kind::Sha1)?, expected_tree(&repo)?);
+    assert_eq!(db.writes.get(), 0, "all trees are known already");
+
+    let a = file.entry_by_path_and_stage("a".into(), 0).expect("present").clone();
+    let mut h = file
+        .entry_by_path_and_stage("f/g/h".into(), 0)
+        .expect("present")
+        .clone();
+    h.id = a.id;
+    file.add_entry(h);
+    let id = file.write_tree(&db, git_hash::Kind::Sha1)?;
+    assert_eq!(db.writes.get(), 3, "only 'f/g', 'f' and the root tree are written");
+
+    file.write(git_lock::acquire::Fail::Immediately)?;
+    let out = Command::new("git").arg("write-tree").current_dir(&repo).output()?;
+    assert!(out.status.success());
+    assert_eq!(id, ObjectId::from_hex(out.stdout.trim())?);
+    assert_eq!(
+        File::at(file.path())?.tree(),
+        file.tree(),
+        "the cached tree is updated like git does it"
+    );
+    Ok(())
+}
+
+#[test]
+fn write_tree_skips_intent_to_add_entries_and_keeps_their_trees_invalid() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_index.sh")?;
+    let mut file = File::at(root.join("v3/.git/index"))?;
+    let new = file.entry_by_path_and_stage("new".into(), 0).expect("present");
+    assert!(new.flags.contains(entry::Flags::INTENT_TO_ADD));
+
+    let id = file.write_tree(&git_odb::sink(), git_hash::Kind::Sha1)?;
+    let head_tree = File::at(root.join("v2/.git/index"))?
+        .tree()
+        .and_then(|t| t.id)
+        .expect("valid");
+    assert_eq!(
+        id, head_tree,
+        "the entries aren't part of the tree, nor are the trees that would be empty without them"
+    );
+    let tree = file.tree().expect("present");
+    assert!(tree.id.is_none());
+    assert!(tree.children[0].id.is_some());
+    Ok(())
+}
+
+#[test]
+fn write_tree_fails_on_unmerged_entries() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_index.sh")?;
+    let mut file = File::at(root.join("conflict/.git/index"))?;
+    match file.write_tree(&git_odb::sink(), git_hash::Kind::Sha1) {
+        Err(write_tree::Error::Unmerged { path }) => assert_eq!(path, "a"),
+        res => panic!("unexpected result {:?}", res),
+    }
+    Ok(())
+}
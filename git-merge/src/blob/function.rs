@@ -0,0 +1,338 @@
+use crate::blob::{ConflictStyle, Favor, Hunk, Labels, Options, Resolution};
+use git_diff::lines;
+use git_object::bstr::BStr;
+
+/// Perform a three-way merge of the `ours` and `theirs` blobs which both derive from `base`, writing the merged result into `out`
+/// and returning the amount of conflicts that were encountered.
+///
+/// Conflicting hunks are written with conflict markers annotated with `labels` in the style configured in `options`,
+/// unless they are resolved automatically as configured in [`Options::favor`]. The output follows the format of `git merge-file`,
+/// but hunks and conflicts may differ from it as the underlying diff isn't identical to the one of `git`.
+///
+/// `out` is cleared before writing the merged result.
+pub fn merge(
+    base: &[u8],
+    ours: &[u8],
+    theirs: &[u8],
+    labels: Labels<'_>,
+    options: Options,
+    out: &mut Vec<u8>,
+) -> usize {
+    out.clear();
+    let (base, ours, theirs) = (lines::split(base), lines::split(ours), lines::split(theirs));
+    let hunks = hunks(&base, &ours, &theirs, options);
+
+    let mut conflicts = 0;
+    let mut ours_pos = 0;
+    for hunk in hunks {
+        copy(out, &ours[ours_pos..hunk.ours.start], false);
+        match hunk.resolution {
+            Resolution::Ours => copy(out, &ours[hunk.ours.clone()], false),
+            Resolution::Theirs => copy(out, &theirs[hunk.theirs.clone()], false),
+            Resolution::Union => {
+                copy(out, &ours[hunk.ours.clone()], true);
+                copy(out, &theirs[hunk.theirs.clone()], false);
+            }
+            Resolution::Conflict => {
+                conflicts += 1;
+                marker(out, b'<', labels.current, options.marker_size);
+                copy(out, &ours[hunk.ours.clone()], true);
+                if matches!(options.style, ConflictStyle::Diff3 | ConflictStyle::ZealousDiff3) {
+                    marker(out, b'|', labels.ancestor, options.marker_size);
+                    copy(out, &base[hunk.base.clone()], true);
+                }
+                marker(out, b'=', None, options.marker_size);
+                copy(out, &theirs[hunk.theirs.clone()], true);
+                marker(out, b'>', labels.other, options.marker_size);
+            }
+        }
+        ours_pos = hunk.ours.end;
+    }
+    copy(out, &ours[ours_pos..], false);
+    conflicts
+}
+
+/// Compute the hunks needed to merge the lines of `ours` and `theirs` which both derive from the lines in `base`, in order of appearance.
+///
+/// Lines can be obtained with [`git_diff::lines::split()`], and the [`style`][Options::style] and [`favor`][Options::favor] fields
+/// of `options` affect how conflicts are refined and resolved.
+/// Note that if one side didn't change at all, the hunks will describe taking the changes of the other side.
+pub fn hunks(base: &[&[u8]], ours: &[&[u8]], theirs: &[&[u8]], options: Options) -> Vec<Hunk> {
+    let ours_changes = lines::changes(base, ours);
+    let theirs_changes = lines::changes(base, theirs);
+
+    let mut merges = Vec::<Merge>::new();
+    let (mut c1, mut c2) = (ours_changes.iter().peekable(), theirs_changes.iter().peekable());
+    loop {
+        match (c1.peek(), c2.peek()) {
+            (Some(x1), Some(x2)) => {
+                if x1.before.end < x2.before.start {
+                    let i2 = x2.after.start as isize - x2.before.start as isize + x1.before.start as isize;
+                    append(
+                        &mut merges,
+                        Merge::new(Mode::Ours, &x1.before, &x1.after, i2, x1.before.len()),
+                    );
+                    c1.next();
+                    continue;
+                }
+                if x2.before.end < x1.before.start {
+                    let i1 = x1.after.start as isize - x1.before.start as isize + x2.before.start as isize;
+                    append(
+                        &mut merges,
+                        Merge {
+                            mode: Mode::Theirs,
+                            i0: x2.before.start as isize,
+                            chg0: x2.before.len() as isize,
+                            i1,
+                            chg1: x2.before.len() as isize,
+                            i2: x2.after.start as isize,
+                            chg2: x2.after.len() as isize,
+                        },
+                    );
+                    c2.next();
+                    continue;
+                }
+                if x1.before != x2.before || ours[x1.after.clone()] != theirs[x2.after.clone()] {
+                    let off = x1.before.start as isize - x2.before.start as isize;
+                    let ffo = off + x1.before.len() as isize - x2.before.len() as isize;
+                    let (mut i0, mut i1, mut i2) = (
+                        x1.before.start as isize,
+                        x1.after.start as isize,
+                        x2.after.start as isize,
+                    );
+                    if off > 0 {
+                        i0 -= off;
+                        i1 -= off;
+                    } else {
+                        i2 += off;
+                    }
+                    let mut chg0 = x1.before.end as isize - i0;
+                    let mut chg1 = x1.after.end as isize - i1;
+                    let mut chg2 = x2.after.end as isize - i2;
+                    if ffo < 0 {
+                        chg0 -= ffo;
+                        chg1 -= ffo;
+                    } else {
+                        chg2 += ffo;
+                    }
+                    append(
+                        &mut merges,
+                        Merge {
+                            mode: Mode::Conflict,
+                            i0,
+                            chg0,
+                            i1,
+                            chg1,
+                            i2,
+                            chg2,
+                        },
+                    );
+                }
+                let (end1, end2) = (x1.before.end, x2.before.end);
+                if end1 >= end2 {
+                    c2.next();
+                }
+                if end2 >= end1 {
+                    c1.next();
+                }
+            }
+            (Some(x1), None) => {
+                let i2 = x1.before.start as isize + theirs.len() as isize - base.len() as isize;
+                append(
+                    &mut merges,
+                    Merge::new(Mode::Ours, &x1.before, &x1.after, i2, x1.before.len()),
+                );
+                c1.next();
+            }
+            (None, Some(x2)) => {
+                let i1 = x2.before.start as isize + ours.len() as isize - base.len() as isize;
+                append(
+                    &mut merges,
+                    Merge {
+                        mode: Mode::Theirs,
+                        i0: x2.before.start as isize,
+                        chg0: x2.before.len() as isize,
+                        i1,
+                        chg1: x2.before.len() as isize,
+                        i2: x2.after.start as isize,
+                        chg2: x2.after.len() as isize,
+                    },
+                );
+                c2.next();
+            }
+            (None, None) => break,
+        }
+    }
+
+    match options.style {
+        ConflictStyle::ZealousDiff3 => refine_zealous_diff3_conflicts(&mut merges, ours, theirs),
+        ConflictStyle::Merge => {
+            merges = refine_conflicts(merges, ours, theirs);
+            simplify_non_conflicts(&mut merges, ours);
+        }
+        ConflictStyle::Diff3 => {}
+    }
+
+    merges
+        .into_iter()
+        .filter_map(|m| {
+            let resolution = match (m.mode, options.favor) {
+                (Mode::Ours, _) | (Mode::Conflict, Some(Favor::Ours)) => Resolution::Ours,
+                (Mode::Theirs, _) | (Mode::Conflict, Some(Favor::Theirs)) => Resolution::Theirs,
+                (Mode::Conflict, Some(Favor::Union)) => Resolution::Union,
+                (Mode::Conflict, None) => Resolution::Conflict,
+                (Mode::Identical, _) => return None,
+            };
+            Some(Hunk {
+                resolution,
+                base: range(m.i0, m.chg0),
+                ours: range(m.i1, m.chg1),
+                theirs: range(m.i2, m.chg2),
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Conflict,
+    Ours,
+    Theirs,
+    /// A conflict turned out to have the same changes on both sides after refinement.
+    Identical,
+}
+
+/// A hunk with the start and length of its lines in the base (`0`), our side (`1`) and their side (`2`), similar to `xdmerge_t` in git.
+#[derive(Debug, Clone, Copy)]
+struct Merge {
+    mode: Mode,
+    i0: isize,
+    chg0: isize,
+    i1: isize,
+    chg1: isize,
+    i2: isize,
+    chg2: isize,
+}
+
+impl Merge {
+    fn new(
+        mode: Mode,
+        base: &std::ops::Range<usize>,
+        side: &std::ops::Range<usize>,
+        other_start: isize,
+        other_len: usize,
+    ) -> Self {
+        Merge {
+            mode,
+            i0: base.start as isize,
+            chg0: base.len() as isize,
+            i1: side.start as isize,
+            chg1: side.len() as isize,
+            i2: other_start,
+            chg2: other_len as isize,
+        }
+    }
+}
+
+fn range(start: isize, len: isize) -> std::ops::Range<usize> {
+    start as usize..(start + len) as usize
+}
+
+/// Append `m` to `merges`, or merge it into the previous hunk if they overlap or touch, making it a conflict if their modes differ.
+fn append(merges: &mut Vec<Merge>, m: Merge) {
+    match merges.last_mut() {
+        Some(prev) if m.i1 <= prev.i1 + prev.chg1 || m.i2 <= prev.i2 + prev.chg2 => {
+            if prev.mode != m.mode {
+                prev.mode = Mode::Conflict;
+            }
+            prev.chg0 = m.i0 + m.chg0 - prev.i0;
+            prev.chg1 = m.i1 + m.chg1 - prev.i1;
+            prev.chg2 = m.i2 + m.chg2 - prev.i2;
+        }
+        _ => merges.push(m),
+    }
+}
+
+/// Diff our and their side of each conflict to split it into smaller conflicts around the lines both sides have in common.
+fn refine_conflicts(merges: Vec<Merge>, ours: &[&[u8]], theirs: &[&[u8]]) -> Vec<Merge> {
+    let mut out = Vec::with_capacity(merges.len());
+    for mut m in merges {
+        if m.mode != Mode::Conflict || m.chg1 == 0 || m.chg2 == 0 {
+            out.push(m);
+            continue;
+        }
+        let changes = lines::changes(&ours[range(m.i1, m.chg1)], &theirs[range(m.i2, m.chg2)]);
+        if changes.is_empty() {
+            m.mode = Mode::Identical;
+            out.push(m);
+            continue;
+        }
+        out.extend(changes.into_iter().map(|c| Merge {
+            i1: m.i1 + c.before.start as isize,
+            chg1: c.before.len() as isize,
+            i2: m.i2 + c.after.start as isize,
+            chg2: c.after.len() as isize,
+            ..m
+        }));
+    }
+    out
+}
+
+/// Move lines both sides have in common at the beginning and the end of each conflict out of it.
+fn refine_zealous_diff3_conflicts(merges: &mut [Merge], ours: &[&[u8]], theirs: &[&[u8]]) {
+    for m in merges.iter_mut().filter(|m| m.mode == Mode::Conflict) {
+        while m.chg1 > 0 && m.chg2 > 0 && ours[m.i1 as usize] == theirs[m.i2 as usize] {
+            m.chg1 -= 1;
+            m.chg2 -= 1;
+            m.i1 += 1;
+            m.i2 += 1;
+        }
+        while m.chg1 > 0 && m.chg2 > 0 && ours[(m.i1 + m.chg1 - 1) as usize] == theirs[(m.i2 + m.chg2 - 1) as usize] {
+            m.chg1 -= 1;
+            m.chg2 -= 1;
+        }
+    }
+}
+
+/// Merge adjacent conflicts if there are no more than 3 lines between them, or if these lines contain no alphanumeric characters,
+/// as it's simpler to look at one conflict than at many small ones.
+fn simplify_non_conflicts(merges: &mut Vec<Merge>, ours: &[&[u8]]) {
+    let mut idx = 0;
+    while idx + 1 < merges.len() {
+        let (m, next) = (merges[idx], merges[idx + 1]);
+        let (begin, end) = (m.i1 + m.chg1, next.i1);
+        if m.mode != Mode::Conflict
+            || next.mode != Mode::Conflict
+            || (end - begin > 3
+                && ours[range(begin, end - begin)]
+                    .iter()
+                    .any(|line| line.iter().any(u8::is_ascii_alphanumeric)))
+        {
+            idx += 1;
+        } else {
+            let m = &mut merges[idx];
+            m.chg1 = next.i1 + next.chg1 - m.i1;
+            m.chg2 = next.i2 + next.chg2 - m.i2;
+            merges.remove(idx + 1);
+        }
+    }
+}
+
+/// Copy `lines` into `out`, and if `ensure_newline` is set, assure the output ends with a newline in case lines were copied.
+fn copy(out: &mut Vec<u8>, lines: &[&[u8]], ensure_newline: bool) {
+    for line in lines {
+        out.extend_from_slice(line);
+    }
+    if ensure_newline && !lines.is_empty() && out.last() != Some(&b'\n') {
+        out.push(b'\n');
+    }
+}
+
+fn marker(out: &mut Vec<u8>, marker: u8, label: Option<&BStr>, size: usize) {
+    out.extend(std::iter::repeat(marker).take(size));
+    if let Some(label) = label {
+        out.push(b' ');
+        out.extend_from_slice(label);
+    }
+    out.push(b'\n');
+}
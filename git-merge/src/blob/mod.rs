@@ -0,0 +1,95 @@
+use git_object::bstr::BStr;
+use std::ops::Range;
+
+/// Determines how conflicting hunks are written into the merged output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConflictStyle {
+    /// Only show the zealously minified conflicting lines of our and their side, like `git merge-file` does by default.
+    Merge,
+    /// Show our and their side along with the lines of the merge base, like `git merge-file --diff3`.
+    Diff3,
+    /// Like [`Diff3`][ConflictStyle::Diff3], but lines common to both sides at the beginning and end of the conflict are moved out of it,
+    /// like `git merge-file --zdiff3`.
+    ZealousDiff3,
+}
+
+impl Default for ConflictStyle {
+    fn default() -> Self {
+        ConflictStyle::Merge
+    }
+}
+
+/// Resolve conflicting hunks automatically by picking one or both sides, similar to `git merge-file --ours|--theirs|--union`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Favor {
+    /// Take our side of conflicting hunks.
+    Ours,
+    /// Take their side of conflicting hunks.
+    Theirs,
+    /// Take our side followed by their side of conflicting hunks.
+    Union,
+}
+
+/// Options to control how the three-way merge of blobs is performed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Options {
+    /// How conflicts should be represented in the output.
+    pub style: ConflictStyle,
+    /// If set, conflicts will be resolved by choosing the given side instead of writing conflict markers.
+    pub favor: Option<Favor>,
+    /// The amount of characters to use for each conflict marker, which is 7 by default.
+    pub marker_size: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            style: Default::default(),
+            favor: None,
+            marker_size: 7,
+        }
+    }
+}
+
+/// The names to show next to the conflict markers, like the file names or refs that were merged.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Labels<'a> {
+    /// The name of the merge base, shown next to the `|||||||` marker if the style is [`Diff3`][ConflictStyle::Diff3]
+    /// or [`ZealousDiff3`][ConflictStyle::ZealousDiff3].
+    pub ancestor: Option<&'a BStr>,
+    /// The name of our side, shown next to the `<<<<<<<` marker.
+    pub current: Option<&'a BStr>,
+    /// The name of their side, shown next to the `>>>>>>>` marker.
+    pub other: Option<&'a BStr>,
+}
+
+/// Describes how a hunk of lines is to be taken into the merged result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Resolution {
+    /// Only our side changed the hunk, or the conflict was resolved in favor of our side.
+    Ours,
+    /// Only their side changed the hunk, or the conflict was resolved in favor of their side.
+    Theirs,
+    /// Our side followed by their side, as a conflict was resolved with [`Favor::Union`].
+    Union,
+    /// Both sides changed the hunk in different ways.
+    Conflict,
+}
+
+/// A hunk in a three-way merge, identifying lines by their index in the respective side.
+///
+/// All lines in between hunks are unchanged and taken from our side.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Hunk {
+    /// How to take the hunk into the merged output.
+    pub resolution: Resolution,
+    /// The lines in the merge base affected by the hunk.
+    pub base: Range<usize>,
+    /// The lines on our side affected by the hunk.
+    pub ours: Range<usize>,
+    /// The lines on their side affected by the hunk.
+    pub theirs: Range<usize>,
+}
+
+mod function;
+pub use function::{hunks, merge};
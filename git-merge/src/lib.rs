@@ -0,0 +1,10 @@
+//! Three-way merges of blobs and trees, producing merged results along with conflicts in the way git does.
+#![forbid(unsafe_code, rust_2018_idioms)]
+#![deny(missing_docs)]
+
+///
+pub mod blob;
+
+///
+pub mod tree;
+pub use tree::merge_trees;
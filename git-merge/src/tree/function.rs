@@ -0,0 +1,576 @@
+use crate::{
+    blob,
+    tree::{Conflict, Error, Options, Outcome, Side},
+};
+use git_diff::tree::recorder;
+use git_hash::{oid, ObjectId};
+use git_object::{
+    bstr::{BStr, BString, ByteSlice, ByteVec},
+    immutable, mutable,
+    tree::EntryMode,
+};
+use git_odb::{pack, Find, Write};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+type Result<T, Db> = std::result::Result<T, Error<<Db as Find>::Error, <Db as Write>::Error>>;
+
+/// Perform a three-way merge of the `ours` and `theirs` trees which both derive from the `base` tree, writing all new blobs and trees
+/// into `db` and returning the id of the merged tree along with all conflicts that were encountered.
+///
+/// The changes of each side are obtained by diffing them against `base` with [`git_diff::tree::Changes`], and the changes of
+/// their side are applied to our tree, so only trees along changed paths have to be rewritten.
+/// Files changed on both sides are merged with [`blob::merge()`] using `labels` for the conflict markers and [`options.blob`][Options::blob]
+/// to control the conflict style. Conflicts are recorded, but do not prevent a merged tree from being written.
+///
+/// # Notes
+///
+/// * Renames are only detected if they are exact, i.e. the content of the file didn't change during the move.
+/// * Files and directories competing for the same path are resolved by moving the file aside to `<path>~<label>`, with `label`
+///   being the label of the respective side in `labels` or `ours` and `theirs` respectively.
+pub fn merge_trees<Db>(
+    base: &oid,
+    ours: &oid,
+    theirs: &oid,
+    db: &Db,
+    labels: blob::Labels<'_>,
+    options: Options,
+) -> Result<Outcome, Db>
+where
+    Db: Find + Write,
+{
+    let ours_changes = leaf_changes(db, base, ours)?;
+    let theirs_changes = leaf_changes(db, base, theirs)?;
+    let (ours_renames, theirs_renames) = if options.track_renames {
+        (
+            renames(&ours_changes, base.kind()),
+            renames(&theirs_changes, base.kind()),
+        )
+    } else {
+        Default::default()
+    };
+
+    let mut state = State {
+        db,
+        hash_kind: base.kind(),
+        labels,
+        options,
+        edits: BTreeMap::new(),
+        conflicts: Vec::new(),
+        bufs: Default::default(),
+    };
+    let mut handled = HashSet::<&BString>::new();
+
+    let ours_renamed_to: HashMap<_, _> = ours_renames.iter().map(|r| (&r.from, &r.to)).collect();
+    for rename in &theirs_renames {
+        handled.insert(&rename.from);
+        handled.insert(&rename.to);
+        let base_leaf = theirs_changes[&rename.from]
+            .previous
+            .expect("rename source exists in base");
+        let theirs_leaf = theirs_changes[&rename.to].current.expect("rename destination exists");
+        if let Some(ours_to) = ours_renamed_to.get(&rename.from) {
+            if *ours_to != &rename.to {
+                state.conflicts.push(Conflict::RenameRename {
+                    base_path: rename.from.clone(),
+                    ours_path: (*ours_to).clone(),
+                    theirs_path: rename.to.clone(),
+                });
+                state.edit(&rename.to, Some(theirs_leaf));
+            }
+            continue;
+        }
+        match ours_changes.get(&rename.from) {
+            None => {
+                state.edit(&rename.from, None);
+                let leaf = match ours_changes.get(&rename.to).and_then(|c| c.current) {
+                    Some(ours_leaf) if ours_leaf != theirs_leaf => {
+                        state.merge_content(&rename.to, None, ours_leaf, theirs_leaf)?
+                    }
+                    _ => theirs_leaf,
+                };
+                state.edit(&rename.to, Some(leaf));
+            }
+            Some(Change {
+                current: Some(ours_leaf),
+                ..
+            }) => {
+                state.edit(&rename.from, None);
+                let leaf = state.merge_content(&rename.to, Some(base_leaf), *ours_leaf, theirs_leaf)?;
+                state.edit(&rename.to, Some(leaf));
+            }
+            Some(Change { current: None, .. }) => {
+                state.conflicts.push(Conflict::RenameDelete {
+                    base_path: rename.from.clone(),
+                    renamed_path: rename.to.clone(),
+                    renamed_by: Side::Theirs,
+                });
+                state.edit(&rename.to, Some(theirs_leaf));
+            }
+        }
+    }
+
+    for rename in ours_renames
+        .iter()
+        .filter(|r| !theirs_renames.iter().any(|theirs| theirs.from == r.from))
+    {
+        match theirs_changes.get(&rename.from) {
+            None => {}
+            Some(Change {
+                previous,
+                current: Some(theirs_leaf),
+            }) => {
+                handled.insert(&rename.from);
+                let ours_leaf = ours_changes[&rename.to].current.expect("rename destination exists");
+                let leaf = state.merge_content(&rename.to, *previous, ours_leaf, *theirs_leaf)?;
+                state.edit(&rename.to, Some(leaf));
+            }
+            Some(Change { current: None, .. }) => {
+                handled.insert(&rename.from);
+                state.conflicts.push(Conflict::RenameDelete {
+                    base_path: rename.from.clone(),
+                    renamed_path: rename.to.clone(),
+                    renamed_by: Side::Ours,
+                });
+            }
+        }
+    }
+
+    for (path, theirs_change) in theirs_changes.iter().filter(|(path, _)| !handled.contains(path)) {
+        let ours_change = match ours_changes.get(path) {
+            None => {
+                state.edit(path, theirs_change.current);
+                continue;
+            }
+            Some(change) if change.current == theirs_change.current => continue,
+            Some(change) => change,
+        };
+        match (ours_change.current, theirs_change.current) {
+            (Some(ours_leaf), Some(theirs_leaf)) => {
+                let leaf = state.merge_content(path, theirs_change.previous, ours_leaf, theirs_leaf)?;
+                state.edit(path, Some(leaf));
+            }
+            (None, Some(theirs_leaf)) => {
+                state.conflicts.push(Conflict::ModifyDelete {
+                    path: path.clone(),
+                    deleted_by: Side::Ours,
+                    modified: theirs_leaf.id,
+                });
+                state.edit(path, Some(theirs_leaf));
+            }
+            (Some(ours_leaf), None) => state.conflicts.push(Conflict::ModifyDelete {
+                path: path.clone(),
+                deleted_by: Side::Theirs,
+                modified: ours_leaf.id,
+            }),
+            (None, None) => unreachable!("both deletions are equal and skipped"),
+        }
+    }
+
+    let edits: Vec<_> = std::mem::take(&mut state.edits).into_iter().collect();
+    let tree = if edits.is_empty() {
+        ours.to_owned()
+    } else {
+        match state.apply(Some(ours.to_owned()), "".into(), edits)? {
+            Some(tree) => tree,
+            None => state.write_tree(Vec::new())?,
+        }
+    };
+    Ok(Outcome {
+        tree,
+        conflicts: state.conflicts,
+    })
+}
+
+/// A non-tree entry, like a file, a symbolic link or a submodule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Leaf {
+    mode: EntryMode,
+    id: ObjectId,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Change {
+    previous: Option<Leaf>,
+    current: Option<Leaf>,
+}
+
+struct Rename {
+    from: BString,
+    to: BString,
+}
+
+struct State<'a, Db> {
+    db: &'a Db,
+    hash_kind: git_hash::Kind,
+    labels: blob::Labels<'a>,
+    options: Options,
+    /// Paths of non-tree entries to set or remove in our tree.
+    edits: BTreeMap<BString, Option<Leaf>>,
+    conflicts: Vec<Conflict>,
+    bufs: [Vec<u8>; 4],
+}
+
+impl<'a, Db> State<'a, Db>
+where
+    Db: Find + Write,
+{
+    fn edit(&mut self, path: &BString, leaf: Option<Leaf>) {
+        self.edits.insert(path.clone(), leaf);
+    }
+
+    /// Merge the content of the `ours` and `theirs` versions of the file at `path`, returning the entry to use in the merged tree.
+    fn merge_content(&mut self, path: &BString, base: Option<Leaf>, ours: Leaf, theirs: Leaf) -> Result<Leaf, Db> {
+        let base = base.filter(|base| is_file(base.mode));
+        let mode = merge_mode(base.map(|b| b.mode), ours.mode, theirs.mode);
+        let conflict = |conflicts: &mut Vec<Conflict>| {
+            conflicts.push(match base {
+                Some(base) => Conflict::Content {
+                    path: path.clone(),
+                    base: base.id,
+                    ours: ours.id,
+                    theirs: theirs.id,
+                },
+                None => Conflict::AddAdd {
+                    path: path.clone(),
+                    ours: ours.id,
+                    theirs: theirs.id,
+                },
+            })
+        };
+        if ours.id == theirs.id || base.map_or(false, |base| base.id == theirs.id) {
+            return Ok(Leaf { mode, id: ours.id });
+        }
+        if base.map_or(false, |base| base.id == ours.id) {
+            return Ok(Leaf { mode, id: theirs.id });
+        }
+        if !is_file(ours.mode) || !is_file(theirs.mode) {
+            conflict(&mut self.conflicts);
+            return Ok(ours);
+        }
+
+        let [base_buf, ours_buf, theirs_buf, out] = &mut self.bufs;
+        let base_data = match base {
+            Some(base) => find_blob(self.db, &base.id, base_buf)?,
+            None => &[],
+        };
+        let ours_data = find_blob(self.db, &ours.id, ours_buf)?;
+        let theirs_data = find_blob(self.db, &theirs.id, theirs_buf)?;
+        if [base_data, ours_data, theirs_data].iter().any(|data| is_binary(data)) {
+            conflict(&mut self.conflicts);
+            return Ok(Leaf { mode, id: ours.id });
+        }
+        let num_conflicts = blob::merge(base_data, ours_data, theirs_data, self.labels, self.options.blob, out);
+        let id = self
+            .db
+            .write_buf(git_object::Kind::Blob, out, self.hash_kind)
+            .map_err(Error::Write)?;
+        if num_conflicts != 0 {
+            conflict(&mut self.conflicts);
+        }
+        Ok(Leaf { mode, id })
+    }
+
+    /// Apply `edits` with paths relative to `tree` at `prefix` and write all changed trees, returning the id of the new tree
+    /// or `None` if it is empty.
+    fn apply(
+        &mut self,
+        tree: Option<ObjectId>,
+        prefix: &BStr,
+        edits: Vec<(BString, Option<Leaf>)>,
+    ) -> Result<Option<ObjectId>, Db> {
+        let mut entries: Vec<mutable::tree::Entry> = match tree {
+            Some(id) => {
+                let mut buf = Vec::new();
+                find_tree(self.db, &id, &mut buf)?
+                    .map(|entry| {
+                        entry.map(|entry| mutable::tree::Entry {
+                            mode: entry.mode,
+                            filename: entry.filename.to_owned(),
+                            oid: entry.oid.to_owned(),
+                        })
+                    })
+                    .collect::<std::result::Result<_, _>>()?
+            }
+            None => Vec::new(),
+        };
+
+        let mut by_name = BTreeMap::<BString, (Option<Option<Leaf>>, Vec<(BString, Option<Leaf>)>)>::new();
+        for (path, leaf) in edits {
+            match path.find_byte(b'/') {
+                Some(pos) => by_name
+                    .entry(path[..pos].into())
+                    .or_default()
+                    .1
+                    .push((path[pos + 1..].into(), leaf)),
+                None => by_name.entry(path).or_default().0 = Some(leaf),
+            }
+        }
+
+        for (name, (leaf_edit, nested)) in by_name {
+            let existing = entries
+                .iter()
+                .position(|e| e.filename == name)
+                .map(|pos| entries.remove(pos));
+            let (mut file, mut tree) = match existing {
+                Some(e) if e.mode.is_tree() => (None, Some(e.oid)),
+                Some(e) => (
+                    Some((
+                        Leaf {
+                            mode: e.mode,
+                            id: e.oid,
+                        },
+                        Side::Ours,
+                    )),
+                    None,
+                ),
+                None => (None, None),
+            };
+            match leaf_edit {
+                Some(Some(leaf)) => {
+                    let side = if tree.is_some() { Side::Theirs } else { Side::Ours };
+                    file = Some((leaf, side));
+                }
+                Some(None) => file = None,
+                None => {}
+            }
+            let path = join(prefix, name.as_ref());
+            if !nested.is_empty() {
+                tree = self.apply(tree, path.as_ref(), nested)?;
+            }
+
+            match (file, tree) {
+                (Some((leaf, side)), Some(tree)) => {
+                    let renamed = self.unique_name(&entries, name.as_ref(), side);
+                    self.conflicts.push(Conflict::DirectoryFile {
+                        renamed_to: join(prefix, renamed.as_ref()),
+                        path,
+                        file_side: side,
+                    });
+                    entries.push(mutable::tree::Entry {
+                        mode: leaf.mode,
+                        filename: renamed,
+                        oid: leaf.id,
+                    });
+                    entries.push(mutable::tree::Entry {
+                        mode: EntryMode::Tree,
+                        filename: name,
+                        oid: tree,
+                    });
+                }
+                (Some((leaf, _)), None) => entries.push(mutable::tree::Entry {
+                    mode: leaf.mode,
+                    filename: name,
+                    oid: leaf.id,
+                }),
+                (None, Some(tree)) => entries.push(mutable::tree::Entry {
+                    mode: EntryMode::Tree,
+                    filename: name,
+                    oid: tree,
+                }),
+                (None, None) => {}
+            }
+        }
+
+        if entries.is_empty() {
+            return Ok(None);
+        }
+        self.write_tree(entries).map(Some)
+    }
+
+    fn write_tree(&mut self, mut entries: Vec<mutable::tree::Entry>) -> Result<ObjectId, Db> {
+        entries.sort_by(|a, b| {
+            let name = |e: &mutable::tree::Entry| {
+                let suffix = if e.mode.is_tree() { Some(b'/') } else { None };
+                e.filename.to_vec().into_iter().chain(suffix)
+            };
+            name(a).cmp(name(b))
+        });
+        self.db
+            .write(&mutable::Object::Tree(mutable::Tree { entries }), self.hash_kind)
+            .map_err(Error::Write)
+    }
+
+    /// Find a name for a file named `name` which has to make room for a directory, that isn't used by any of `entries`.
+    fn unique_name(&self, entries: &[mutable::tree::Entry], name: &BStr, side: Side) -> BString {
+        let label = match side {
+            Side::Ours => self.labels.current.unwrap_or_else(|| "ours".into()),
+            Side::Theirs => self.labels.other.unwrap_or_else(|| "theirs".into()),
+        };
+        let mut candidate: BString = name.to_owned();
+        candidate.push_byte(b'~');
+        candidate.push_str(label.replace("/", "_"));
+        let base_len = candidate.len();
+        let mut count = 0;
+        while entries.iter().any(|e| e.filename == candidate) {
+            count += 1;
+            candidate.truncate(base_len);
+            candidate.push_str(format!("_{}", count));
+        }
+        candidate
+    }
+}
+
+/// Obtain all changes to non-tree entries needed to turn the tree `lhs` into `rhs`, keyed by their path.
+fn leaf_changes<Db>(db: &Db, lhs: &oid, rhs: &oid) -> Result<BTreeMap<BString, Change>, Db>
+where
+    Db: Find + Write,
+{
+    let (mut lhs_buf, mut rhs_buf) = (Vec::new(), Vec::new());
+    let lhs_tree = find_tree(db, lhs, &mut lhs_buf)?;
+    let rhs_tree = find_tree(db, rhs, &mut rhs_buf)?;
+    let mut recorder = git_diff::tree::Recorder::default();
+    git_diff::tree::Changes::from(lhs_tree).needed_to_obtain(
+        rhs_tree,
+        git_diff::tree::State::default(),
+        |id, buf| {
+            db.find(id, buf, &mut pack::cache::Never)
+                .ok()
+                .flatten()
+                .and_then(|obj| obj.into_tree_iter())
+        },
+        &mut recorder,
+    )?;
+
+    let mut out = BTreeMap::new();
+    for change in recorder.records {
+        use recorder::Change::*;
+        let (path, change) = match change {
+            Addition { entry_mode, oid, path } if entry_mode.is_no_tree() => (
+                path,
+                Change {
+                    previous: None,
+                    current: Some(Leaf {
+                        mode: entry_mode,
+                        id: oid,
+                    }),
+                },
+            ),
+            Deletion { entry_mode, oid, path } if entry_mode.is_no_tree() => (
+                path,
+                Change {
+                    previous: Some(Leaf {
+                        mode: entry_mode,
+                        id: oid,
+                    }),
+                    current: None,
+                },
+            ),
+            Modification {
+                previous_entry_mode,
+                previous_oid,
+                entry_mode,
+                oid,
+                path,
+            } if previous_entry_mode.is_no_tree() && entry_mode.is_no_tree() => (
+                path,
+                Change {
+                    previous: Some(Leaf {
+                        mode: previous_entry_mode,
+                        id: previous_oid,
+                    }),
+                    current: Some(Leaf {
+                        mode: entry_mode,
+                        id: oid,
+                    }),
+                },
+            ),
+            _trees => continue,
+        };
+        out.insert(path, change);
+    }
+    Ok(out)
+}
+
+/// Pair deletions with additions of entries of the same kind and content, in order of their paths, ignoring empty blobs
+/// with ids of the given `hash_kind`.
+fn renames(changes: &BTreeMap<BString, Change>, hash_kind: git_hash::Kind) -> Vec<Rename> {
+    let empty_blob = git_odb::sink()
+        .write_buf(git_object::Kind::Blob, &[], hash_kind)
+        .expect("hashing into a sink doesn't fail");
+    let mut additions = HashMap::<ObjectId, VecDeque<(&BString, EntryMode)>>::new();
+    for (path, change) in changes {
+        if let (None, Some(current)) = (change.previous, change.current) {
+            if current.id != empty_blob {
+                additions.entry(current.id).or_default().push_back((path, current.mode));
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    for (path, change) in changes {
+        if let (Some(previous), None) = (change.previous, change.current) {
+            let destinations = match additions.get_mut(&previous.id) {
+                Some(destinations) => destinations,
+                None => continue,
+            };
+            if let Some(pos) = destinations.iter().position(|(_, mode)| {
+                is_file(*mode) == is_file(previous.mode) && (*mode == previous.mode || is_file(*mode))
+            }) {
+                let (to, _) = destinations.remove(pos).expect("position is valid");
+                out.push(Rename {
+                    from: path.clone(),
+                    to: to.clone(),
+                });
+            }
+        }
+    }
+    out
+}
+
+fn merge_mode(base: Option<EntryMode>, ours: EntryMode, theirs: EntryMode) -> EntryMode {
+    if base == Some(ours) {
+        theirs
+    } else {
+        ours
+    }
+}
+
+fn is_file(mode: EntryMode) -> bool {
+    matches!(mode, EntryMode::Blob | EntryMode::BlobExecutable)
+}
+
+/// Like git, consider data binary if it contains a null-byte within the first 8000 bytes.
+fn is_binary(data: &[u8]) -> bool {
+    data[..data.len().min(8000)].contains(&0)
+}
+
+fn join(prefix: &BStr, name: &BStr) -> BString {
+    let mut path = prefix.to_owned();
+    if !path.is_empty() {
+        path.push_byte(b'/');
+    }
+    path.push_str(name);
+    path
+}
+
+fn find_tree<'a, Db>(db: &Db, id: &oid, buf: &'a mut Vec<u8>) -> Result<immutable::TreeIter<'a>, Db>
+where
+    Db: Find + Write,
+{
+    find(db, id, buf, git_object::Kind::Tree).map(immutable::TreeIter::from_bytes)
+}
+
+fn find_blob<'a, Db>(db: &Db, id: &oid, buf: &'a mut Vec<u8>) -> Result<&'a [u8], Db>
+where
+    Db: Find + Write,
+{
+    find(db, id, buf, git_object::Kind::Blob)
+}
+
+fn find<'a, Db>(db: &Db, id: &oid, buf: &'a mut Vec<u8>, expected: git_object::Kind) -> Result<&'a [u8], Db>
+where
+    Db: Find + Write,
+{
+    let obj = db
+        .find(id, buf, &mut pack::cache::Never)
+        .map_err(Error::Find)?
+        .ok_or_else(|| Error::NotFound { oid: id.to_owned() })?;
+    if obj.kind != expected {
+        return Err(Error::ObjectKind {
+            oid: id.to_owned(),
+            expected,
+            actual: obj.kind,
+        });
+    }
+    Ok(obj.data)
+}
@@ -0,0 +1,152 @@
+use git_hash::ObjectId;
+use git_object::{
+    bstr::{BStr, BString},
+    immutable,
+};
+
+/// Options to control how [`merge_trees()`] operates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Options {
+    /// The options to use when merging the content of files that were changed on both sides.
+    pub blob: crate::blob::Options,
+    /// If true, files that were moved without changing their content are tracked on both sides, so that changes
+    /// of the other side can follow them to their new location.
+    pub track_renames: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            blob: Default::default(),
+            track_renames: true,
+        }
+    }
+}
+
+/// Identifies the side of the merge that caused a [`Conflict`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Side {
+    /// Our side, whose tree is used as basis for the merged tree.
+    Ours,
+    /// Their side, whose changes are merged into our tree.
+    Theirs,
+}
+
+/// A conflict encountered during [`merge_trees()`], along with a description of how it was represented in the merged tree.
+///
+/// Paths are relative to the root of the merged tree.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Conflict {
+    /// Both sides modified the file at `path` in ways that could not be merged automatically.
+    ///
+    /// The merged tree contains the file with conflict markers, or our version if the file isn't mergeable line by line,
+    /// like binary files, symbolic links and submodules.
+    Content {
+        /// The path of the file in the merged tree.
+        path: BString,
+        /// The version of the file in the merge base.
+        base: ObjectId,
+        /// Our version of the file.
+        ours: ObjectId,
+        /// Their version of the file.
+        theirs: ObjectId,
+    },
+    /// Both sides added a file at `path` with different content.
+    ///
+    /// The merged tree contains the file with conflict markers just like with [`Content`][Conflict::Content] conflicts.
+    AddAdd {
+        /// The path of the file in the merged tree.
+        path: BString,
+        /// Our version of the file.
+        ours: ObjectId,
+        /// Their version of the file.
+        theirs: ObjectId,
+    },
+    /// One side deleted the file at `path` while the other side modified it, and the modified version is kept in the merged tree.
+    ModifyDelete {
+        /// The path of the file in the merged tree.
+        path: BString,
+        /// The side which deleted the file.
+        deleted_by: Side,
+        /// The modified version of the file as found in the merged tree.
+        modified: ObjectId,
+    },
+    /// A file and a directory were placed at `path` by different sides, and the file was moved to `renamed_to` to make room
+    /// for the directory.
+    DirectoryFile {
+        /// The path at which the file and the directory were placed.
+        path: BString,
+        /// The side which placed the file at `path`.
+        file_side: Side,
+        /// The path the file was placed at in the merged tree.
+        renamed_to: BString,
+    },
+    /// Both sides renamed the file at `base_path` to different locations, and both of these are present in the merged tree.
+    RenameRename {
+        /// The path of the file in the merge base.
+        base_path: BString,
+        /// The path to which our side moved the file.
+        ours_path: BString,
+        /// The path to which their side moved the file.
+        theirs_path: BString,
+    },
+    /// One side renamed the file at `base_path` which the other side deleted, and the renamed file is kept in the merged tree.
+    RenameDelete {
+        /// The path of the file in the merge base.
+        base_path: BString,
+        /// The path to which the file was moved.
+        renamed_path: BString,
+        /// The side which renamed the file.
+        renamed_by: Side,
+    },
+}
+
+impl Conflict {
+    /// Return the path in the merged tree which is affected by the conflict.
+    pub fn path(&self) -> &BStr {
+        match self {
+            Conflict::Content { path, .. }
+            | Conflict::AddAdd { path, .. }
+            | Conflict::ModifyDelete { path, .. }
+            | Conflict::DirectoryFile { path, .. } => path.as_ref(),
+            Conflict::RenameRename { ours_path, .. } => ours_path.as_ref(),
+            Conflict::RenameDelete { renamed_path, .. } => renamed_path.as_ref(),
+        }
+    }
+}
+
+/// The result of [`merge_trees()`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Outcome {
+    /// The id of the merged tree which was written into the object database.
+    ///
+    /// It's a valid tree even if there are conflicts, with conflicting files represented as described by the respective [`Conflict`].
+    pub tree: ObjectId,
+    /// All conflicts that were encountered, in order of their discovery. The merge was clean if this is empty.
+    pub conflicts: Vec<Conflict>,
+}
+
+/// The error returned by [`merge_trees()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error<F: std::error::Error + 'static, W: std::error::Error + 'static> {
+    #[error(transparent)]
+    Find(F),
+    #[error("An object with id {} could not be found", .oid)]
+    NotFound { oid: ObjectId },
+    #[error("Expected object {} to be a {}, but it was a {}", .oid, .expected, .actual)]
+    ObjectKind {
+        oid: ObjectId,
+        expected: git_object::Kind,
+        actual: git_object::Kind,
+    },
+    #[error(transparent)]
+    Diff(#[from] git_diff::tree::changes::Error),
+    #[error(transparent)]
+    Decode(#[from] immutable::object::decode::Error),
+    #[error(transparent)]
+    Write(W),
+}
+
+mod function;
+pub use function::merge_trees;
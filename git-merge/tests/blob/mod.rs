@@ -0,0 +1,123 @@
+use git_merge::blob::{self, ConflictStyle, Favor, Labels, Options, Resolution};
+use git_object::bstr::ByteSlice;
+
+fn labels() -> Labels<'static> {
+    Labels {
+        ancestor: Some("base".into()),
+        current: Some("ours".into()),
+        other: Some("theirs".into()),
+    }
+}
+
+#[test]
+fn merges_match_git_merge_file() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_blob_merges.sh")?;
+    let mut cases = 0;
+    let mut out = Vec::new();
+    for case in std::fs::read_dir(&root)? {
+        let case = case?.path();
+        let read = |name: &str| std::fs::read(case.join(name));
+        let (base, ours, theirs) = (read("base")?, read("ours")?, read("theirs")?);
+        for (name, options) in &[
+            ("merge", Options::default()),
+            (
+                "diff3",
+                Options {
+                    style: ConflictStyle::Diff3,
+                    ..Default::default()
+                },
+            ),
+            (
+                "zdiff3",
+                Options {
+                    style: ConflictStyle::ZealousDiff3,
+                    ..Default::default()
+                },
+            ),
+            (
+                "ours",
+                Options {
+                    favor: Some(Favor::Ours),
+                    ..Default::default()
+                },
+            ),
+            (
+                "theirs",
+                Options {
+                    favor: Some(Favor::Theirs),
+                    ..Default::default()
+                },
+            ),
+            (
+                "union",
+                Options {
+                    favor: Some(Favor::Union),
+                    ..Default::default()
+                },
+            ),
+        ] {
+            let expected = read(&format!("{}.expected", name))?;
+            let conflicts = blob::merge(&base, &ours, &theirs, labels(), *options, &mut out);
+            assert_eq!(
+                out.as_bstr(),
+                expected.as_bstr(),
+                "{:?}: {} merge didn't match git",
+                case.file_name().expect("dir name"),
+                name
+            );
+            assert_eq!(
+                conflicts > 0,
+                expected.find(b"<<<<<<< ours").is_some(),
+                "conflicts are counted if there are markers"
+            );
+        }
+        cases += 1;
+    }
+    assert_eq!(cases, 12, "all cases have been tested");
+    Ok(())
+}
+
+#[test]
+fn hunks_can_be_obtained_without_rendering() {
+    let base = ["a\n", "b\n", "c\n"];
+    let ours = ["A\n", "b\n", "c\n"];
+    let theirs = ["a\n", "b\n", "C\n"];
+    let lines = |v: &[&'static str; 3]| v.iter().map(|l| l.as_bytes()).collect::<Vec<_>>();
+    let hunks = blob::hunks(&lines(&base), &lines(&ours), &lines(&theirs), Options::default());
+    assert_eq!(
+        hunks,
+        vec![
+            blob::Hunk {
+                resolution: Resolution::Ours,
+                base: 0..1,
+                ours: 0..1,
+                theirs: 0..1
+            },
+            blob::Hunk {
+                resolution: Resolution::Theirs,
+                base: 2..3,
+                ours: 2..3,
+                theirs: 2..3
+            }
+        ]
+    );
+}
+
+#[test]
+fn marker_size_and_missing_labels() {
+    let mut out = Vec::new();
+    let conflicts = blob::merge(
+        b"a\n",
+        b"b\n",
+        b"c\n",
+        Labels::default(),
+        Options {
+            marker_size: 3,
+            style: ConflictStyle::Diff3,
+            ..Default::default()
+        },
+        &mut out,
+    );
+    assert_eq!(conflicts, 1);
+    assert_eq!(out.as_bstr(), "<<<\nb\n|||\na\n===\nc\n>>>\n");
+}
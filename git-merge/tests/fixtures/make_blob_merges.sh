@@ -0,0 +1,111 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function baseline () {
+  local name=${1:?need case name}
+  (cd "$name"
+    git merge-file --stdout ours base theirs > merge.expected || true
+    for style in diff3 zdiff3; do
+      git merge-file --stdout --$style ours base theirs > $style.expected || true
+    done
+    for favor in ours theirs union; do
+      git merge-file --stdout --$favor ours base theirs > $favor.expected || true
+    done
+  )
+}
+
+mkdir clean
+(cd clean
+  printf 'a\nb\nc\nd\ne\nf\ng\n' > base
+  printf 'A\nb\nc\nd\ne\nf\ng\n' > ours
+  printf 'a\nb\nc\nd\ne\nf\nG\n' > theirs
+)
+baseline clean
+
+mkdir one-sided
+(cd one-sided
+  printf 'a\nb\nc\n' > base
+  printf 'a\nb\nc\n' > ours
+  printf 'a\nB\nc\nd\n' > theirs
+)
+baseline one-sided
+
+mkdir same-change
+(cd same-change
+  printf 'a\nb\nc\nd\n' > base
+  printf 'a\nB\nc\nd\n' > ours
+  printf 'a\nB\nc\nD\n' > theirs
+)
+baseline same-change
+
+mkdir single-line-conflict
+(cd single-line-conflict
+  printf 'a\nb\nc\nd\ne\n' > base
+  printf 'a\nb\nours\nd\ne\n' > ours
+  printf 'a\nb\ntheirs\nd\ne\n' > theirs
+)
+baseline single-line-conflict
+
+mkdir adjacent-changes
+(cd adjacent-changes
+  printf 'a\nb\nc\nd\ne\n' > base
+  printf 'a\nB\nc\nd\ne\n' > ours
+  printf 'a\nb\nC\nd\ne\n' > theirs
+)
+baseline adjacent-changes
+
+mkdir refinable-conflict
+(cd refinable-conflict
+  printf '1\n2\n3\n4\n5\n6\n7\n8\n9\n' > base
+  printf '1\n2\nx\ncommon\nshared\nalso\nmore\nlines\ny\n9\n' > ours
+  printf '1\n2\nz\ncommon\nshared\nalso\nmore\nlines\nw\n9\n' > theirs
+)
+baseline refinable-conflict
+
+mkdir conflicts-with-few-lines-between
+(cd conflicts-with-few-lines-between
+  printf '1\n2\n3\n4\n5\n' > base
+  printf '1\nx\n}\n\ny\n5\n' > ours
+  printf '1\nz\n}\n\nw\n5\n' > theirs
+)
+baseline conflicts-with-few-lines-between
+
+mkdir zdiff3-common-edges
+(cd zdiff3-common-edges
+  printf 'a\nb\nc\nd\ne\n' > base
+  printf 'a\nstart\nours\nend\ne\n' > ours
+  printf 'a\nstart\ntheirs\nend\ne\n' > theirs
+)
+baseline zdiff3-common-edges
+
+mkdir deletion-vs-modification
+(cd deletion-vs-modification
+  printf 'a\nb\nc\nd\n' > base
+  printf 'a\nd\n' > ours
+  printf 'a\nb\nC\nd\n' > theirs
+)
+baseline deletion-vs-modification
+
+mkdir additions-at-end
+(cd additions-at-end
+  printf 'a\nb\n' > base
+  printf 'a\nb\nours\n' > ours
+  printf 'a\nb\ntheirs\nmore\n' > theirs
+)
+baseline additions-at-end
+
+mkdir missing-newline-at-end
+(cd missing-newline-at-end
+  printf 'a\nb' > base
+  printf 'a\nours' > ours
+  printf 'a\ntheirs' > theirs
+)
+baseline missing-newline-at-end
+
+mkdir empty-base
+(cd empty-base
+  touch base
+  printf 'ours\n' > ours
+  printf 'theirs\n' > theirs
+)
+baseline empty-base
@@ -0,0 +1,149 @@
+#!/bin/bash
+set -eu -o pipefail
+
+mkdir repo expected
+cd repo
+git init -q
+git config commit.gpgsign false
+git config merge.conflictStyle merge
+
+function scenario () {
+  local name=${1:?need scenario name}
+  git checkout -q --orphan $name-base
+  git rm -rfq --ignore-unmatch .
+}
+
+function ours () {
+  git add -A && git commit -qm base
+  git checkout -q -b $1-ours
+}
+
+function theirs () {
+  git add -A && git commit -qm ours
+  git checkout -q $1-base
+  git checkout -q -b $1-theirs
+}
+
+function finish () {
+  git add -A && git commit -qm theirs
+  git merge-tree --write-tree $1-ours $1-theirs | head -n 1 > ../expected/$1 || true
+}
+
+scenario clean
+mkdir dir
+printf 'a\n' > a
+printf 'b\n' > b
+printf 'c\n' > dir/c
+ours clean
+printf 'a ours\n' > a
+theirs clean
+printf 'b theirs\n' > b
+printf 'd\n' > dir/d
+finish clean
+
+scenario content-merge
+printf '1\n2\n3\n4\n5\n6\n7\n8\n' > f
+ours content-merge
+printf 'one\n2\n3\n4\n5\n6\n7\n8\n' > f
+theirs content-merge
+printf '1\n2\n3\n4\n5\n6\n7\neight\n' > f
+finish content-merge
+
+scenario content-conflict
+printf '1\n2\n3\n' > f
+ours content-conflict
+printf '1\nours\n3\n' > f
+theirs content-conflict
+printf '1\ntheirs\n3\n' > f
+finish content-conflict
+
+scenario add-add
+printf 'unchanged\n' > unchanged
+ours add-add
+printf 'same\nours\n' > new
+theirs add-add
+printf 'same\ntheirs\n' > new
+finish add-add
+
+scenario delete-modify
+printf 'f\n' > f
+printf 'g\n' > g
+ours delete-modify
+rm f
+theirs delete-modify
+printf 'f theirs\n' > f
+finish delete-modify
+
+scenario modify-delete
+printf 'f\n' > f
+printf 'g\n' > g
+ours modify-delete
+printf 'f ours\n' > f
+theirs modify-delete
+rm f
+finish modify-delete
+
+scenario rename-modify
+printf '1\n2\n3\n4\n5\n' > a
+printf 'unrelated\n' > u
+ours rename-modify
+mkdir moved
+git mv a moved/b
+theirs rename-modify
+printf '1\n2\n3\n4\nfive\n' > a
+finish rename-modify
+
+scenario modify-rename
+printf '1\n2\n3\n4\n5\n' > a
+printf 'unrelated\n' > u
+ours modify-rename
+printf 'one\n2\n3\n4\n5\n' > a
+theirs modify-rename
+git mv a b
+finish modify-rename
+
+scenario directory-file
+printf 'u\n' > u
+ours directory-file
+printf 'x\n' > x
+theirs directory-file
+mkdir x
+printf 'y\n' > x/y
+finish directory-file
+
+scenario rename-delete
+printf 'a\n' > a
+printf 'u\n' > u
+ours rename-delete
+git mv a b
+theirs rename-delete
+git rm -q a
+finish rename-delete
+
+scenario rename-rename
+printf 'a\n' > a
+printf 'u\n' > u
+ours rename-rename
+git mv a b
+theirs rename-rename
+git mv a c
+finish rename-rename
+
+scenario mode-and-content
+printf '1\n2\n3\n' > f
+ours mode-and-content
+chmod +x f
+theirs mode-and-content
+printf '1\n2\nthree\n' > f
+finish mode-and-content
+
+scenario deleted-directory
+mkdir -p d/e
+printf 'a\n' > d/a
+printf 'b\n' > d/e/b
+printf 'u\n' > u
+ours deleted-directory
+printf 'u ours\n' > u
+theirs deleted-directory
+git rm -rq d
+finish deleted-directory
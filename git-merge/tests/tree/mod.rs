@@ -0,0 +1,164 @@
+use git_hash::ObjectId;
+use git_merge::{
+    blob::Labels,
+    tree::{Conflict, Options, Outcome, Side},
+};
+use git_object::bstr::ByteSlice;
+use git_odb::{linked, pack, Find};
+use std::path::Path;
+
+fn tree_of(db: &linked::Store, repo: &Path, branch: &str) -> crate::Result<ObjectId> {
+    let commit_id = ObjectId::from_hex(std::fs::read(repo.join(".git/refs/heads").join(branch))?.trim())?;
+    let mut buf = Vec::new();
+    let tree = db
+        .find(commit_id, &mut buf, &mut pack::cache::Never)?
+        .expect("commit present")
+        .decode()?
+        .into_commit()
+        .expect("a commit")
+        .tree();
+    Ok(tree)
+}
+
+/// Merge the branches of scenario `name` like `git merge-tree --write-tree` would, and return the outcome along with git's tree.
+fn merge(root: &Path, name: &str) -> crate::Result<(Outcome, ObjectId)> {
+    let repo = root.join("repo");
+    let db = linked::Store::at(repo.join(".git").join("objects"))?;
+    let (ours, theirs) = (format!("{}-ours", name), format!("{}-theirs", name));
+    let outcome = git_merge::merge_trees(
+        &tree_of(&db, &repo, &format!("{}-base", name))?,
+        &tree_of(&db, &repo, &ours)?,
+        &tree_of(&db, &repo, &theirs)?,
+        &db,
+        Labels {
+            ancestor: None,
+            current: Some(ours.as_str().into()),
+            other: Some(theirs.as_str().into()),
+        },
+        Options::default(),
+    )?;
+    let expected = ObjectId::from_hex(std::fs::read(root.join("expected").join(name))?.trim())?;
+    Ok((outcome, expected))
+}
+
+#[test]
+fn clean_merges_match_git() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_writable("make_tree_merges.sh")?;
+    for name in &[
+        "clean",
+        "content-merge",
+        "rename-modify",
+        "modify-rename",
+        "mode-and-content",
+        "deleted-directory",
+    ] {
+        let (outcome, expected) = merge(root.path(), name)?;
+        assert_eq!(outcome.conflicts, Vec::new(), "{} merges cleanly", name);
+        assert_eq!(
+            outcome.tree, expected,
+            "{}: the merged tree is the one git produces",
+            name
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn conflicting_merges_match_git_and_report_conflicts() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_writable("make_tree_merges.sh")?;
+    for name in &[
+        "content-conflict",
+        "add-add",
+        "modify-delete",
+        "delete-modify",
+        "directory-file",
+        "rename-delete",
+        "rename-rename",
+    ] {
+        let (outcome, expected) = merge(root.path(), name)?;
+        assert_eq!(
+            outcome.tree, expected,
+            "{}: the merged tree is the one git produces",
+            name
+        );
+        assert_eq!(outcome.conflicts.len(), 1, "{}: exactly one conflict", name);
+        let conflict = &outcome.conflicts[0];
+        match (*name, conflict) {
+            ("content-conflict", Conflict::Content { path, .. }) => assert_eq!(path, "f"),
+            ("add-add", Conflict::AddAdd { path, .. }) => assert_eq!(path, "new"),
+            (
+                "modify-delete",
+                Conflict::ModifyDelete {
+                    path,
+                    deleted_by: Side::Theirs,
+                    ..
+                },
+            )
+            | (
+                "delete-modify",
+                Conflict::ModifyDelete {
+                    path,
+                    deleted_by: Side::Ours,
+                    ..
+                },
+            ) => assert_eq!(path, "f"),
+            (
+                "directory-file",
+                Conflict::DirectoryFile {
+                    path,
+                    file_side: Side::Ours,
+                    renamed_to,
+                },
+            ) => {
+                assert_eq!(path, "x");
+                assert_eq!(renamed_to, "x~directory-file-ours");
+            }
+            (
+                "rename-delete",
+                Conflict::RenameDelete {
+                    base_path,
+                    renamed_path,
+                    renamed_by: Side::Ours,
+                },
+            ) => {
+                assert_eq!(base_path, "a");
+                assert_eq!(renamed_path, "b");
+            }
+            (
+                "rename-rename",
+                Conflict::RenameRename {
+                    base_path,
+                    ours_path,
+                    theirs_path,
+                },
+            ) => {
+                assert_eq!(base_path, "a");
+                assert_eq!(ours_path, "b");
+                assert_eq!(theirs_path, "c");
+            }
+            (name, conflict) => panic!("{}: unexpected conflict {:?}", name, conflict),
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn merging_an_unchanged_side_yields_the_other_tree() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_writable("make_tree_merges.sh")?;
+    let repo = root.path().join("repo");
+    let db = linked::Store::at(repo.join(".git").join("objects"))?;
+    let (base, ours) = (tree_of(&db, &repo, "clean-base")?, tree_of(&db, &repo, "clean-ours")?);
+    for (ours_side, theirs_side) in &[(ours, base), (base, ours)] {
+        let outcome = git_merge::merge_trees(
+            &base,
+            ours_side,
+            theirs_side,
+            &db,
+            Labels::default(),
+            Options::default(),
+        )?;
+        assert_eq!(outcome.tree, ours, "the changed side is taken as is");
+        assert!(outcome.conflicts.is_empty());
+    }
+    Ok(())
+}
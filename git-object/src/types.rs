@@ -78,5 +78,10 @@ pub mod tree {
         pub fn is_no_tree(&self) -> bool {
             *self != EntryMode::Tree
         }
+
+        /// Return true if this entry mode represents a commit of a submodule, also known as gitlink
+        pub fn is_commit(&self) -> bool {
+            *self == EntryMode::Commit
+        }
     }
 }
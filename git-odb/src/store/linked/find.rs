@@ -4,7 +4,7 @@ use std::convert::TryInto;
 use crate::{
     pack,
     pack::bundle::Location,
-    store::{compound, linked},
+    store::{compound, linked, loose},
 };
 use git_pack::{data::Object, find::Entry};
 
@@ -19,6 +19,23 @@ impl linked::Store {
         }
         false
     }
+
+    /// Return a stream to read the data of the object identified by `id` if it is stored as loose object in any of our databases.
+    ///
+    /// Returns `None` if the object doesn't exist or is stored in a pack, in which case [`find()`][crate::Find::find()] must
+    /// be used to obtain its data.
+    pub fn stream(&self, id: impl AsRef<oid>) -> Result<Option<loose::Stream>, loose::find::Error> {
+        let id = id.as_ref();
+        for db in self.dbs.iter() {
+            if db.internal_find_packed(id).is_some() {
+                return Ok(None);
+            }
+            if let Some(stream) = db.loose.stream(id)? {
+                return Ok(Some(stream));
+            }
+        }
+        Ok(None)
+    }
 }
 
 impl crate::Find for linked::Store {
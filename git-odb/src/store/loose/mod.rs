@@ -40,4 +40,8 @@ pub mod iter;
 #[doc(inline)]
 pub use iter::Iter;
 ///
+pub mod stream;
+///
 pub mod write;
+#[doc(inline)]
+pub use stream::Stream;
@@ -0,0 +1,71 @@
+use std::{
+    fs,
+    io::{self, BufReader, Read},
+};
+
+use git_features::zlib::{stream::inflate::ReadBoxed, Decompress};
+use git_pack::loose::object::header;
+
+use crate::store::loose::{find::Error, sha1_path, Store};
+
+/// A reader over the decompressed data of a loose object, as returned by [`Store::stream()`].
+pub struct Stream {
+    /// The kind of the object.
+    pub kind: git_object::Kind,
+    /// The size of the object's data in bytes, which is the amount of bytes that can be read from this stream.
+    pub size: u64,
+    inner: io::Take<ReadBoxed<BufReader<fs::File>>>,
+}
+
+impl Read for Stream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+/// Object streaming
+impl Store {
+    /// Return a [`Stream`] to read the data of the object identified by `id` without holding it in memory in its entirety,
+    /// or `None` if there is no such object.
+    ///
+    /// This is most useful for large objects, which would otherwise have to be decompressed into a buffer
+    /// by [`find()`][Store::find()].
+    pub fn stream(&self, id: impl AsRef<git_hash::oid>) -> Result<Option<Stream>, Error> {
+        let path = sha1_path(id.as_ref(), self.path.clone());
+        let file = match fs::File::open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => {
+                return Err(Error::Io {
+                    source: err,
+                    action: "open",
+                    path,
+                })
+            }
+        };
+        let mut inner = ReadBoxed {
+            inner: BufReader::new(file),
+            decompressor: Box::new(Decompress::new(true)),
+        };
+
+        let mut header_buf = Vec::with_capacity(32);
+        let mut byte = [0u8; 1];
+        while header_buf.last() != Some(&0) {
+            let num_read = inner.read(&mut byte).map_err(|err| Error::Io {
+                source: err,
+                action: "deflate",
+                path: path.clone(),
+            })?;
+            if num_read == 0 || header_buf.len() > 64 {
+                return Err(header::Error::InvalidHeader("Did not find 0 byte in header").into());
+            }
+            header_buf.push(byte[0]);
+        }
+        let (kind, size, _header_size) = header::decode(&header_buf)?;
+        Ok(Some(Stream {
+            kind,
+            size,
+            inner: inner.take(size),
+        }))
+    }
+}
@@ -57,6 +57,23 @@ mod locate {
     }
 }
 
+mod stream {
+    use crate::{hex_to_id, odb::store::linked::db};
+
+    #[test]
+    fn only_loose_objects_can_be_streamed() -> crate::Result {
+        let db = db();
+        let stream = db.stream(hex_to_id("37d4e6c5c48ba0d245164c4e10d5f41140cab980"))?;
+        assert_eq!(stream.map(|s| s.kind), Some(git_object::Kind::Blob));
+        assert!(
+            db.stream(hex_to_id("501b297447a8255d3533c6858bb692575cdefaa0"))?
+                .is_none(),
+            "packed objects can't be streamed"
+        );
+        Ok(())
+    }
+}
+
 mod init {
     use git_odb::linked;
     use std::convert::TryFrom;
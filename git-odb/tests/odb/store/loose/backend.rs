@@ -30,6 +30,35 @@ pub fn locate_oid(id: git_hash::ObjectId, buf: &mut Vec<u8>) -> git_pack::data::
     ldb().find(id, buf).expect("read success").expect("id present")
 }
 
+mod stream {
+    use std::io::Read;
+
+    use crate::store::loose::backend::{ldb, locate_oid, object_ids};
+
+    #[test]
+    fn all_objects_match_their_found_data() -> crate::Result {
+        let mut buf = Vec::new();
+        for id in object_ids() {
+            let mut stream = ldb().stream(id)?.expect("object exists");
+            let mut streamed = Vec::new();
+            stream.read_to_end(&mut streamed)?;
+            let object = locate_oid(id, &mut buf);
+            assert_eq!(stream.kind, object.kind);
+            assert_eq!(stream.size, object.data.len() as u64);
+            assert_eq!(streamed, object.data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn missing_objects_yield_none() -> crate::Result {
+        assert!(ldb()
+            .stream(crate::hex_to_id("37d4e6c5c48ba0d245164c4e10d5f41140cab989"))?
+            .is_none());
+        Ok(())
+    }
+}
+
 mod write {
     use git_odb::{loose, Write};
 
@@ -22,7 +22,7 @@ use bstr::BStr;
 use git_hash::oid;
 
 mod store;
-pub use store::{file, packed};
+pub use store::{file, packed, reftable};
 ///
 pub mod mutable;
 ///
@@ -0,0 +1,129 @@
+use std::{
+    cmp::Ordering,
+    hash::{Hash, Hasher},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+/// A value loaded from a file along with the information needed to determine whether it is still up to date.
+struct Entry<T> {
+    value: Arc<T>,
+    modified: SystemTime,
+    len: u64,
+    inode: u64,
+}
+
+#[cfg(unix)]
+fn inode(metadata: &std::fs::Metadata) -> u64 {
+    use std::os::unix::fs::MetadataExt;
+    metadata.ino()
+}
+
+#[cfg(not(unix))]
+fn inode(_metadata: &std::fs::Metadata) -> u64 {
+    0
+}
+
+/// A cache for a value loaded from a single file which is shared among the clones of the store it belongs to.
+///
+/// As the cache merely avoids reloading the same file, all instances compare equal to not affect the identity of a store.
+pub(crate) struct Shared<T>(Arc<Mutex<Option<Entry<T>>>>);
+
+impl<T> Shared<T> {
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Entry<T>>> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Return the value loaded from the file at `path`, calling `load` only if there is no cached value yet or if
+    /// the modification time, size or inode of the file changed since it was last loaded.
+    ///
+    /// Returns `None` if the file doesn't exist or if `load` didn't produce a value.
+    pub(crate) fn get_or_load<E>(
+        &self,
+        path: &Path,
+        load: impl FnOnce() -> Result<Option<T>, E>,
+    ) -> Result<Option<Arc<T>>, E>
+    where
+        E: From<std::io::Error>,
+    {
+        let mut cache = self.lock();
+        let metadata = match std::fs::metadata(path) {
+            Ok(metadata) => metadata,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                *cache = None;
+                return Ok(None);
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let modified = metadata.modified()?;
+        if let Some(cached) = cache.as_ref() {
+            if cached.modified == modified && cached.len == metadata.len() && cached.inode == inode(&metadata) {
+                return Ok(Some(Arc::clone(&cached.value)));
+            }
+        }
+        Ok(match load()? {
+            Some(value) => {
+                let value = Arc::new(value);
+                *cache = Some(Entry {
+                    value: Arc::clone(&value),
+                    modified,
+                    len: metadata.len(),
+                    inode: inode(&metadata),
+                });
+                Some(value)
+            }
+            None => {
+                *cache = None;
+                None
+            }
+        })
+    }
+
+    /// Forget the cached value, forcing it to be reloaded the next time it's needed.
+    pub(crate) fn clear(&self) {
+        *self.lock() = None;
+    }
+}
+
+impl<T> Default for Shared<T> {
+    fn default() -> Self {
+        Shared(Default::default())
+    }
+}
+
+impl<T> Clone for Shared<T> {
+    fn clone(&self) -> Self {
+        Shared(Arc::clone(&self.0))
+    }
+}
+
+impl<T> std::fmt::Debug for Shared<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Shared")
+    }
+}
+
+impl<T> PartialEq for Shared<T> {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl<T> Eq for Shared<T> {}
+
+impl<T> PartialOrd for Shared<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Shared<T> {
+    fn cmp(&self, _other: &Self) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+impl<T> Hash for Shared<T> {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
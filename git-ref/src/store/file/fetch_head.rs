@@ -0,0 +1,128 @@
+use std::{
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use bstr::{BString, ByteSlice};
+use git_hash::ObjectId;
+
+use crate::store::file::{self, find};
+
+/// A single line of the `FETCH_HEAD` file, describing one of the references obtained by `git fetch`.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Entry {
+    /// The id of the object the fetched reference pointed to.
+    pub id: ObjectId,
+    /// If true, the entry was marked as `not-for-merge` and is ignored by `git pull` when merging.
+    pub not_for_merge: bool,
+    /// A human-readable description of where the object was fetched from, like `branch 'main' of https://example.com/repo`.
+    pub description: BString,
+}
+
+/// The contents of the `FETCH_HEAD` file as written by `git fetch`, with one entry per fetched reference.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Default)]
+pub struct FetchHead {
+    /// All entries in the order they appear in the file.
+    pub entries: Vec<Entry>,
+}
+
+const NOT_FOR_MERGE: &[u8] = b"not-for-merge";
+
+impl FetchHead {
+    /// Parse the contents of a `FETCH_HEAD` file from `data`.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, decode::Error> {
+        let mut entries = Vec::new();
+        for (line_number, line) in data.lines().enumerate() {
+            let mut tokens = line.splitn(3, |b| *b == b'\t');
+            let id = tokens.next().and_then(|hex| ObjectId::from_hex(hex).ok());
+            let not_for_merge = match tokens.next() {
+                Some(b"") => Some(false),
+                Some(NOT_FOR_MERGE) => Some(true),
+                _ => None,
+            };
+            match (id, not_for_merge, tokens.next()) {
+                (Some(id), Some(not_for_merge), Some(description)) => entries.push(Entry {
+                    id,
+                    not_for_merge,
+                    description: description.into(),
+                }),
+                _ => {
+                    return Err(decode::Error::Parse {
+                        line_number: line_number + 1,
+                        line: line.into(),
+                    })
+                }
+            }
+        }
+        Ok(FetchHead { entries })
+    }
+
+    /// Return all entries that `git pull` would merge, i.e. those not marked as `not-for-merge`.
+    pub fn for_merge(&self) -> impl Iterator<Item = &Entry> {
+        self.entries.iter().filter(|e| !e.not_for_merge)
+    }
+
+    /// Serialize all entries to `out` in the format used by `git`.
+    pub fn write_to(&self, mut out: impl Write) -> io::Result<()> {
+        for entry in &self.entries {
+            write!(out, "{}\t", entry.id)?;
+            if entry.not_for_merge {
+                out.write_all(NOT_FOR_MERGE)?;
+            }
+            out.write_all(b"\t")?;
+            out.write_all(&entry.description)?;
+            out.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+impl file::Store {
+    /// Read and parse the `FETCH_HEAD` file of this worktree, or return `None` if it doesn't exist.
+    pub fn fetch_head(&self) -> Result<Option<FetchHead>, find::Error> {
+        Ok(match self.ref_contents(Path::new("FETCH_HEAD"))? {
+            Some(contents) => Some(FetchHead::from_bytes(&contents)?),
+            None => None,
+        })
+    }
+
+    /// Replace the `FETCH_HEAD` file of this worktree with `fetch_head`, using `lock_mode` to deal with existing locks.
+    pub fn write_fetch_head(&self, fetch_head: &FetchHead, lock_mode: git_lock::acquire::Fail) -> io::Result<()> {
+        self.write_special_ref(Path::new("FETCH_HEAD"), lock_mode, |out| {
+            let mut out = BufWriter::new(out);
+            fetch_head.write_to(&mut out)?;
+            out.flush()
+        })
+    }
+
+    /// Write the file of the special reference at `relative_path` through a lock, with `write` producing its contents.
+    pub(in crate::store::file) fn write_special_ref(
+        &self,
+        relative_path: &Path,
+        lock_mode: git_lock::acquire::Fail,
+        write: impl FnOnce(&mut std::fs::File) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let mut lock = git_lock::File::acquire_to_update_resource(self.reference_path(relative_path), lock_mode, None)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        lock.with_mut(write)?;
+        lock.commit().map_err(|err| err.error)?;
+        Ok(())
+    }
+}
+
+///
+pub mod decode {
+    use bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`FetchHead::from_bytes()`][super::FetchHead::from_bytes()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Parse { line_number: usize, line: BString } {
+                display("FETCH_HEAD line {} could not be parsed: {:?}", line_number, line)
+            }
+        }
+    }
+}
@@ -5,12 +5,13 @@ use std::{
 };
 
 use bstr::ByteSlice;
+use git_hash::ObjectId;
 
 pub use error::Error;
 
 use crate::{
     file,
-    mutable::FullName,
+    mutable::{FullName, Target},
     store::{
         file::{loose, path_to_name},
         packed,
@@ -26,8 +27,8 @@ enum Transform {
 impl file::Store {
     /// Find a single reference by the given `path` which is required to be a valid reference name.
     ///
-    /// If `packed` is provided, the reference search will extend to the packed buffer created with [`packed()`][file::Store::packed()].
-    /// Note that the caller is responsible for its freshness, i.e. assuring it wasn't modified since it was read.
+    /// The reference search extends to the packed buffer as returned by [`packed_buffer()`][file::Store::packed_buffer()],
+    /// which is reloaded if it changed on disk.
     ///
     /// Returns `Ok(None)` if no such ref exists.
     ///
@@ -36,40 +37,40 @@ impl file::Store {
     /// The lookup algorithm follows the one in [the git documentation][git-lookup-docs].
     ///
     /// [git-lookup-docs]: https://github.com/git/git/blob/5d5b1473453400224ebb126bf3947e0a3276bdf5/Documentation/revisions.txt#L34-L46
-    pub fn find<'a, 'p, 's, Name, E>(
-        &'s self,
-        partial: Name,
-        packed: Option<&'p packed::Buffer>,
-    ) -> Result<Option<file::Reference<'p>>, Error>
+    pub fn find<'a, Name, E>(&self, partial: Name) -> Result<Option<file::Reference>, Error>
     where
         Name: TryInto<PartialName<'a>, Error = E>,
         Error: From<E>,
     {
         let path = partial.try_into()?;
-        self.find_one_with_verified_input(path.to_partial_path().as_ref(), packed)
+        let packed = self.packed_buffer()?;
+        self.find_one_with_verified_input(path.to_partial_path().as_ref(), packed.as_deref())
     }
 
-    /// Similar to [`file::Store::find()`] but a non-existing ref is treated as error.
+    /// Similar to [`file::Store::find()`] but won't handle packed-refs.
     pub fn loose_find<'a, Name, E>(&self, partial: Name) -> Result<Option<loose::Reference>, Error>
     where
         Name: TryInto<PartialName<'a>, Error = E>,
         Error: From<E>,
     {
-        self.find(partial, None)
+        let path = partial.try_into()?;
+        self.find_one_with_verified_input(path.to_partial_path().as_ref(), None)
             .map(|r| r.map(|r| r.try_into().expect("only loose refs are found without pack")))
     }
 
-    pub(in crate::store::file) fn find_one_with_verified_input<'p>(
+    pub(in crate::store::file) fn find_one_with_verified_input(
         &self,
         relative_path: &Path,
-        packed: Option<&'p packed::Buffer>,
-    ) -> Result<Option<file::Reference<'p>>, Error> {
+        packed: Option<&packed::Buffer>,
+    ) -> Result<Option<file::Reference>, Error> {
         let is_all_uppercase = relative_path
             .to_string_lossy()
             .as_ref()
             .chars()
-            .all(|c| c.is_ascii_uppercase());
-        if relative_path.components().count() == 1 && is_all_uppercase {
+            .all(|c| c.is_ascii_uppercase() || c == '_');
+        let is_other_worktree_ref =
+            relative_path.starts_with("main-worktree") || relative_path.starts_with("worktrees");
+        if (relative_path.components().count() == 1 && is_all_uppercase) || is_other_worktree_ref {
             if let Some(r) = self.find_inner("", &relative_path, None, Transform::None)? {
                 return Ok(Some(r));
             }
@@ -92,13 +93,13 @@ impl file::Store {
         )
     }
 
-    fn find_inner<'p>(
+    fn find_inner(
         &self,
         inbetween: &str,
         relative_path: &Path,
-        packed: Option<&'p packed::Buffer>,
+        packed: Option<&packed::Buffer>,
         transform: Transform,
-    ) -> Result<Option<file::Reference<'p>>, Error> {
+    ) -> Result<Option<file::Reference>, Error> {
         let (base, is_definitely_absolute) = match transform {
             Transform::EnforceRefsPrefix => (
                 if relative_path.starts_with("refs") {
@@ -119,7 +120,7 @@ impl file::Store {
                         let full_name = path_to_name(relative_path);
                         let full_name = PartialName((*full_name).as_bstr());
                         if let Some(packed_ref) = packed.find(full_name)? {
-                            return Ok(Some(file::Reference::Packed(packed_ref)));
+                            return Ok(Some(file::Reference::Packed(packed_ref.to_owned())));
                         };
                     }
                 }
@@ -127,8 +128,16 @@ impl file::Store {
             }
             Some(c) => c,
         };
+        let full_name = path_to_name(&relative_path);
+        if let Some(id) = first_id_of_special_ref(&relative_path, &contents)? {
+            return Ok(id.map(|id| {
+                file::Reference::Loose(loose::Reference {
+                    name: FullName(full_name),
+                    target: Target::Peeled(id),
+                })
+            }));
+        }
         Ok(Some({
-            let full_name = path_to_name(&relative_path);
             loose::Reference::try_from_path(FullName(full_name), &contents)
                 .map(file::Reference::Loose)
                 .map_err(|err| Error::ReferenceCreation { err, relative_path })?
@@ -136,10 +145,24 @@ impl file::Store {
     }
 }
 
+/// Return the first id listed in `contents` of the `FETCH_HEAD` or `MERGE_HEAD` file at `relative_path`, or `None` if
+/// `relative_path` isn't one of these. Like in `git`, `FETCH_HEAD` resolves to its first entry even if it isn't meant to
+/// be merged, and files without entries are treated like missing references.
+fn first_id_of_special_ref(relative_path: &Path, contents: &[u8]) -> Result<Option<Option<ObjectId>>, Error> {
+    Ok(if relative_path == Path::new("FETCH_HEAD") {
+        Some(file::FetchHead::from_bytes(contents)?.entries.first().map(|e| e.id))
+    } else if relative_path == Path::new("MERGE_HEAD") {
+        Some(file::MergeHead::from_bytes(contents)?.ids.first().copied())
+    } else {
+        None
+    })
+}
+
 impl file::Store {
     /// Implements the logic required to transform a fully qualified refname into a filesystem path
     pub(crate) fn reference_path(&self, name: &Path) -> PathBuf {
-        self.base.join(name)
+        let (base, relative_path) = self.to_base_dir_and_relative_name(name);
+        base.join(relative_path)
     }
 
     /// Read the file contents with a verified full reference path and return it in the given vector if possible.
@@ -178,12 +201,31 @@ pub mod existing {
     };
 
     impl file::Store {
-        /// Similar to [`file::Store::find_existing()`] but a non-existing ref is treated as error.
-        pub fn find_existing<'a, 'p, 's, Name, E>(
-            &'s self,
+        /// Similar to [`file::Store::find()`] but a non-existing ref is treated as error.
+        pub fn find_existing<'a, Name, E>(&self, partial: Name) -> Result<file::Reference, Error>
+        where
+            Name: TryInto<PartialName<'a>, Error = E>,
+            crate::name::Error: From<E>,
+        {
+            let packed = self.packed_buffer().map_err(|err| Error::Find(err.into()))?;
+            self.find_existing_inner(partial, packed.as_deref())
+        }
+
+        /// Similar to [`file::Store::find_existing()`] but won't handle packed-refs.
+        pub fn loose_find_existing<'a, Name, E>(&self, partial: Name) -> Result<loose::Reference, Error>
+        where
+            Name: TryInto<PartialName<'a>, Error = E>,
+            crate::name::Error: From<E>,
+        {
+            self.find_existing_inner(partial, None)
+                .map(|r| r.try_into().expect("always loose without packed"))
+        }
+
+        fn find_existing_inner<'a, Name, E>(
+            &self,
             partial: Name,
-            packed: Option<&'p packed::Buffer>,
-        ) -> Result<file::Reference<'p>, Error>
+            packed: Option<&packed::Buffer>,
+        ) -> Result<file::Reference, Error>
         where
             Name: TryInto<PartialName<'a>, Error = E>,
             crate::name::Error: From<E>,
@@ -197,16 +239,6 @@ pub mod existing {
                 Err(err) => Err(err.into()),
             }
         }
-
-        /// Similar to [`file::Store::find()`] won't handle packed-refs.
-        pub fn loose_find_existing<'a, Name, E>(&self, partial: Name) -> Result<loose::Reference, Error>
-        where
-            Name: TryInto<PartialName<'a>, Error = E>,
-            crate::name::Error: From<E>,
-        {
-            self.find_existing(partial, None)
-                .map(|r| r.try_into().expect("always loose without packed"))
-        }
     }
 
     mod error {
@@ -260,11 +292,26 @@ mod error {
                 display("The reference at '{}' could not be instantiated", relative_path.display())
                 source(err)
             }
+            FetchHeadDecode(err: file::fetch_head::decode::Error) {
+                display("The FETCH_HEAD file could not be parsed")
+                from()
+                source(err)
+            }
+            MergeHeadDecode(err: file::merge_head::decode::Error) {
+                display("The MERGE_HEAD file could not be parsed")
+                from()
+                source(err)
+            }
             PackedRef(err: packed::find::Error) {
                 display("A packed ref lookup failed")
                 from()
                 source(err)
             }
+            PackedOpen(err: packed::buffer::open::Error) {
+                display("The packed-refs file could not be opened")
+                from()
+                source(err)
+            }
         }
     }
 
@@ -1,6 +1,6 @@
 use bstr::BStr;
 
-pub use super::loose::reflog::{create_or_update, Error};
+pub use super::loose::reflog::{create_or_update, expire, Error};
 
 /// A parsed ref log line.
 #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
@@ -1,27 +1,41 @@
 use crate::{
     mutable::FullName,
-    store::file::{self, loose::Reference},
+    store::file::{self, loose::Reference, worktree::is_per_worktree},
 };
 use bstr::ByteSlice;
 use git_features::fs::walkdir::DirEntryIter;
 use os_str_bytes::OsStrBytes;
 use std::{
     io::Read,
+    iter::Peekable,
     path::{Path, PathBuf},
 };
 
+/// Determines which references a [`SortedLoosePaths`] iterator yields.
+#[derive(Clone, Copy)]
+pub(in crate::store::file) enum Refs {
+    /// Yield all references.
+    All,
+    /// Only yield references private to a worktree.
+    PerWorktree,
+    /// Only yield references shared by all worktrees.
+    Shared,
+}
+
 /// An iterator over all valid loose reference paths as seen from a particular base directory.
 pub(in crate::store::file) struct SortedLoosePaths {
     pub(crate) base: PathBuf,
     file_walk: DirEntryIter,
+    refs: Refs,
 }
 
 impl SortedLoosePaths {
-    pub fn at_root_with_names(path: impl AsRef<Path>, base: impl Into<PathBuf>) -> Self {
+    pub fn at_root_with_names(path: impl AsRef<Path>, base: impl Into<PathBuf>, refs: Refs) -> Self {
         let file_walk = git_features::fs::walkdir_sorted_new(path).into_iter();
         SortedLoosePaths {
             base: base.into(),
             file_walk,
+            refs,
         }
     }
 }
@@ -44,7 +58,12 @@ impl Iterator for SortedLoosePaths {
                     #[cfg(windows)]
                     let full_name: Vec<u8> = full_name.into_owned().replace(b"\\", b"/");
 
-                    if git_validate::reference::name_partial(full_name.as_bstr()).is_ok() {
+                    let is_included = match self.refs {
+                        Refs::All => true,
+                        Refs::PerWorktree => is_per_worktree(full_path.strip_prefix(&self.base).expect("within base")),
+                        Refs::Shared => !is_per_worktree(full_path.strip_prefix(&self.base).expect("within base")),
+                    };
+                    if is_included && git_validate::reference::name_partial(full_name.as_bstr()).is_ok() {
                         #[cfg(not(windows))]
                         let name = FullName(full_name.into_owned().into());
                         #[cfg(windows)]
@@ -61,9 +80,57 @@ impl Iterator for SortedLoosePaths {
     }
 }
 
+/// An iterator over the valid loose reference paths of a store sorted by name, which merges the shared references in the
+/// common directory of a linked worktree with the ones private to it.
+pub(in crate::store::file) struct LoosePaths {
+    shared: Peekable<SortedLoosePaths>,
+    worktree: Option<Peekable<SortedLoosePaths>>,
+    bases: Vec<PathBuf>,
+}
+
+impl LoosePaths {
+    fn at_root_with_names(root: impl AsRef<Path>, base: impl Into<PathBuf>) -> Self {
+        let base = base.into();
+        LoosePaths {
+            shared: SortedLoosePaths::at_root_with_names(root, base.clone(), Refs::All).peekable(),
+            worktree: None,
+            bases: vec![base],
+        }
+    }
+
+    /// Return `path` relative to the directory containing it.
+    pub(in crate::store::file) fn relative_path<'a>(&self, path: &'a Path) -> &'a Path {
+        self.bases
+            .iter()
+            .find_map(|base| path.strip_prefix(base).ok())
+            .expect("root contains path")
+    }
+}
+
+impl Iterator for LoosePaths {
+    type Item = std::io::Result<(PathBuf, FullName)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let worktree = match &mut self.worktree {
+            Some(worktree) => worktree,
+            None => return self.shared.next(),
+        };
+        let worktree_first = match (self.shared.peek(), worktree.peek()) {
+            (None, _) | (Some(Ok(_)), Some(Err(_))) => true,
+            (Some(Ok((_, shared))), Some(Ok((_, private)))) => private.as_bstr() < shared.as_bstr(),
+            (Some(_), _) => false,
+        };
+        if worktree_first {
+            worktree.next()
+        } else {
+            self.shared.next()
+        }
+    }
+}
+
 /// An iterator over all loose references as seen from a particular base directory.
 pub struct Loose {
-    ref_paths: SortedLoosePaths,
+    ref_paths: LoosePaths,
     buf: Vec<u8>,
 }
 
@@ -72,7 +139,7 @@ impl Loose {
     /// path to which resulting reference names should be relative to.
     pub fn at_root(root: impl AsRef<Path>, base: impl Into<PathBuf>) -> Self {
         Loose {
-            ref_paths: SortedLoosePaths::at_root_with_names(root, base),
+            ref_paths: LoosePaths::at_root_with_names(root, base),
             buf: Vec::new(),
         }
     }
@@ -91,9 +158,7 @@ impl Iterator for Loose {
                     })
                     .map_err(loose::Error::ReadFileContents)
                     .and_then(|_| {
-                        let relative_path = validated_path
-                            .strip_prefix(&self.ref_paths.base)
-                            .expect("root contains path");
+                        let relative_path = self.ref_paths.relative_path(&validated_path);
                         Reference::try_from_path(name, &self.buf).map_err(|err| loose::Error::ReferenceCreation {
                             err,
                             relative_path: relative_path.into(),
@@ -117,7 +182,10 @@ impl file::Store {
         if !refs.is_dir() {
             return Err(std::io::ErrorKind::NotFound.into());
         }
-        Ok(Loose::at_root(refs, self.base.clone()))
+        Ok(Loose {
+            ref_paths: self.loose_paths(Path::new("refs")),
+            buf: Vec::new(),
+        })
     }
 
     /// Return an iterator over all loose references that start with the given `prefix`.
@@ -125,11 +193,30 @@ impl file::Store {
     /// Otherwise it's similar to [`loose_iter()`][file::Store::loose_iter()].
     pub fn loose_iter_prefixed(&self, prefix: impl AsRef<Path>) -> std::io::Result<Loose> {
         let prefix = self.validate_prefix(prefix.as_ref())?;
-        Ok(Loose::at_root(self.base.join(prefix), self.base.clone()))
+        Ok(Loose {
+            ref_paths: self.loose_paths(prefix),
+            buf: Vec::new(),
+        })
+    }
+
+    /// Return the paths of all loose references whose name starts with `prefix`, taking linked worktrees into account.
+    pub(in crate::store::file) fn loose_paths(&self, prefix: &Path) -> LoosePaths {
+        match &self.common_dir {
+            None => LoosePaths::at_root_with_names(self.base.join(prefix), self.base.clone()),
+            Some(common_dir) => LoosePaths {
+                shared: SortedLoosePaths::at_root_with_names(common_dir.join(prefix), common_dir.clone(), Refs::Shared)
+                    .peekable(),
+                worktree: Some(
+                    SortedLoosePaths::at_root_with_names(self.base.join(prefix), self.base.clone(), Refs::PerWorktree)
+                        .peekable(),
+                ),
+                bases: vec![self.base.clone(), common_dir.clone()],
+            },
+        }
     }
 
     pub(in crate::store::file) fn refs_dir(&self) -> PathBuf {
-        self.base.join("refs")
+        self.common_dir().join("refs")
     }
     pub(in crate::store::file) fn validate_prefix<'a>(&self, prefix: &'a Path) -> std::io::Result<&'a Path> {
         if prefix.is_absolute() {
@@ -35,7 +35,24 @@ mod init {
         pub fn at(git_dir: impl Into<PathBuf>, write_reflog: crate::file::WriteReflog) -> Self {
             file::Store {
                 base: git_dir.into(),
+                common_dir: None,
                 write_reflog,
+                packed: Default::default(),
+            }
+        }
+
+        /// Create a new instance for the linked worktree whose private directory is `git_dir`, typically
+        /// `.git/worktrees/<id>`, with `common_dir` being the directory shared by all worktrees, as read from its `commondir` file.
+        pub fn for_linked_worktree(
+            git_dir: impl Into<PathBuf>,
+            common_dir: impl Into<PathBuf>,
+            write_reflog: crate::file::WriteReflog,
+        ) -> Self {
+            file::Store {
+                base: git_dir.into(),
+                common_dir: Some(common_dir.into()),
+                write_reflog,
+                packed: Default::default(),
             }
         }
     }
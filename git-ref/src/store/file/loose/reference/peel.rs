@@ -3,10 +3,7 @@ use quick_error::quick_error;
 use crate::{
     file::{self},
     mutable::Target,
-    store::{
-        file::{find, loose},
-        packed,
-    },
+    store::file::{find, loose},
 };
 
 quick_error! {
@@ -26,19 +23,19 @@ quick_error! {
 }
 
 impl loose::Reference {
-    /// Follow this symbolic reference one level and return the ref it refers to, possibly providing access to `packed` references for lookup.
+    /// Follow this symbolic reference one level and return the ref it refers to, which may be a packed reference.
     ///
     /// Returns `None` if this is not a symbolic reference, hence the leaf of the chain.
-    pub fn peel_one_level<'p>(
-        &self,
-        store: &file::Store,
-        packed: Option<&'p packed::Buffer>,
-    ) -> Option<Result<file::Reference<'p>, Error>> {
+    pub fn peel_one_level(&self, store: &file::Store) -> Option<Result<file::Reference, Error>> {
         match &self.target {
             Target::Peeled(_) => None,
             Target::Symbolic(full_name) => {
                 let path = full_name.to_path();
-                match store.find_one_with_verified_input(path.as_ref(), packed) {
+                let packed = match store.packed_buffer() {
+                    Ok(packed) => packed,
+                    Err(err) => return Some(Err(Error::FindExisting(find::existing::Error::Find(err.into())))),
+                };
+                match store.find_one_with_verified_input(path.as_ref(), packed.as_deref()) {
                     Ok(Some(next)) => Some(Ok(next)),
                     Ok(None) => Some(Err(Error::FindExisting(find::existing::Error::NotFound(
                         path.into_owned(),
@@ -57,8 +54,8 @@ pub mod to_id {
     use std::{collections::BTreeSet, path::PathBuf};
 
     use crate::{
-        mutable::{FullName, Target},
-        store::{file, file::loose, packed},
+        mutable::Target,
+        store::{file, file::loose},
     };
 
     quick_error! {
@@ -82,26 +79,22 @@ pub mod to_id {
 
     impl loose::Reference {
         /// Peel this symbolic reference until the end of the chain is reached and an object ID is available,
-        /// possibly providing access to `packed` references for lookup.
+        /// following packed references as well.
         ///
         /// If an error occurs this reference remains unchanged.
-        pub fn peel_to_id_in_place(
-            &mut self,
-            store: &file::Store,
-            packed: Option<&packed::Buffer>,
-        ) -> Result<&oid, Error> {
+        pub fn peel_to_id_in_place(&mut self, store: &file::Store) -> Result<&oid, Error> {
             let mut count = 0;
             let mut seen = BTreeSet::new();
             let mut storage;
             let mut cursor = &mut *self;
-            while let Some(next) = cursor.peel_one_level(store, packed) {
+            while let Some(next) = cursor.peel_one_level(store) {
                 let next_ref = next?;
                 if let crate::Kind::Peeled = next_ref.kind() {
                     match next_ref {
                         file::Reference::Loose(r) => *self = r,
                         file::Reference::Packed(p) => {
                             self.target = Target::Peeled(p.object());
-                            self.name = FullName(p.name.0.to_owned());
+                            self.name = p.name;
                         }
                     };
                     return Ok(self.target.as_id().expect("it to be present"));
@@ -179,25 +179,21 @@ pub mod create_or_update {
         }
 
         fn reflock_resource_full_name(&self, reflock: &git_lock::Marker) -> PathBuf {
-            reflock
-                .resource_path()
-                .strip_prefix(&self.base)
-                .expect("lock must be held within this store")
+            self.to_base_dir_and_relative_path(&reflock.resource_path())
+                .1
                 .to_owned()
         }
 
         fn reflock_resource_to_log_path(&self, reflock: &git_lock::Marker) -> PathBuf {
-            self.reflog_path_inner(
-                reflock
-                    .resource_path()
-                    .strip_prefix(&self.base)
-                    .expect("lock must be held within this store"),
-            )
+            let resource_path = reflock.resource_path();
+            let (base, relative_path) = self.to_base_dir_and_relative_path(&resource_path);
+            base.join("logs").join(relative_path)
         }
 
         /// Returns the base and a full path (including the base) to the reflog for a ref of the given `full_name`
         pub(in crate::store::file::loose::reflog) fn reflog_path_inner(&self, full_name: &Path) -> PathBuf {
-            self.base.join("logs").join(full_name)
+            let (base, relative_path) = self.to_base_dir_and_relative_name(full_name);
+            base.join("logs").join(relative_path)
         }
     }
 
@@ -230,6 +226,9 @@ pub mod create_or_update {
     pub use error::Error;
 }
 
+///
+pub mod expire;
+
 mod error {
     use quick_error::quick_error;
     use std::io;
@@ -0,0 +1,236 @@
+use std::{
+    convert::TryInto,
+    io::{BufWriter, Write},
+};
+
+use git_hash::{oid, ObjectId};
+
+pub use error::Error;
+
+use crate::{
+    mutable::Target,
+    store::{
+        file,
+        file::{log, loose},
+    },
+    FullName,
+};
+
+/// Options to control which reflog entries to [expire][file::Store::reflog_expire()] and how to adjust the log and its
+/// reference afterwards, similar to `git reflog expire`.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Copy, Default)]
+pub struct Options {
+    /// Remove entries older than this time in seconds since the unix epoch, typically derived from `gc.reflogExpire`.
+    /// If `None`, entries are not removed due to their age alone.
+    pub expire_total: Option<u32>,
+    /// Remove entries older than this time in seconds since the unix epoch if their previous or new object isn't reachable
+    /// anymore, typically derived from `gc.reflogExpireUnreachable`.
+    /// If `None`, reachability doesn't matter.
+    pub expire_unreachable: Option<u32>,
+    /// If true, the previous id of each remaining entry is set to the new id of the remaining entry before it, or to the
+    /// null id for the first entry, to keep the log consistent, like `--rewrite` does.
+    pub rewrite: bool,
+    /// If true and the most recent entry was removed, the reference is set to the new id of the last remaining entry unless
+    /// it is symbolic, like `--updateref` does.
+    pub update_ref: bool,
+}
+
+impl file::Store {
+    /// Remove all entries from the reflog of the reference `name` that expired according to `options`, and return the
+    /// amount of removed entries.
+    ///
+    /// `is_reachable(id)` is called for the object ids of entries which are old enough to be expired if unreachable, and
+    /// shall return true if the object can be reached from the tip of the reference. Null ids are never passed.
+    ///
+    /// The reference is locked while its reflog is rewritten, and `lock_mode` determines how to deal with existing locks.
+    /// If no reflog exists, nothing is done.
+    pub fn reflog_expire<'a, Name, E>(
+        &self,
+        name: Name,
+        options: Options,
+        mut is_reachable: impl FnMut(&oid) -> bool,
+        lock_mode: git_lock::acquire::Fail,
+    ) -> Result<usize, Error>
+    where
+        Name: TryInto<FullName<'a>, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        let name = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        Ok(self
+            .rewrite_reflog(name, options, lock_mode, |_index, line| {
+                let time = line.signature.time.time;
+                if options.expire_total.map_or(false, |expire| time < expire) {
+                    return true;
+                }
+                options.expire_unreachable.map_or(false, |expire| time < expire)
+                    && [&line.previous_oid, &line.new_oid]
+                        .iter()
+                        .any(|id| !id.is_null() && !is_reachable(id))
+            })?
+            .map_or(0, |(removed, _len)| removed))
+    }
+
+    /// Remove the reflog entry at `index` of the reference `name`, with `0` being the most recent entry, similar to
+    /// `git reflog delete <name>@{<index>}`.
+    ///
+    /// Only [`rewrite`][Options::rewrite] and [`update_ref`][Options::update_ref] of `options` are used to adjust the log
+    /// and the reference afterwards.
+    /// The reference is locked while its reflog is rewritten, and `lock_mode` determines how to deal with existing locks.
+    pub fn reflog_delete_entry<'a, Name, E>(
+        &self,
+        name: Name,
+        index: usize,
+        options: Options,
+        lock_mode: git_lock::acquire::Fail,
+    ) -> Result<(), Error>
+    where
+        Name: TryInto<FullName<'a>, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        let name = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        match self.rewrite_reflog(name, options, lock_mode, |entry_index, _line| entry_index == index)? {
+            Some((1, _)) => Ok(()),
+            Some((_, len)) => Err(Error::EntryOutOfRange { index, len }),
+            None => Err(Error::EntryOutOfRange { index, len: 0 }),
+        }
+    }
+
+    /// Rewrite the reflog of `name` without all entries for which `should_remove(index, entry)` returns true, with index `0`
+    /// being the most recent entry, leaving it untouched if no entry is removed. Return the amount of removed entries and
+    /// the amount of entries before the rewrite, or `None` if there is no reflog.
+    fn rewrite_reflog(
+        &self,
+        name: FullName<'_>,
+        options: Options,
+        lock_mode: git_lock::acquire::Fail,
+        mut should_remove: impl FnMut(usize, &log::mutable::Line) -> bool,
+    ) -> Result<Option<(usize, usize)>, Error> {
+        let relative_path = name.to_path();
+        let mut ref_lock = git_lock::File::acquire_to_update_resource(
+            self.reference_path(&relative_path),
+            lock_mode,
+            Some(self.to_base_dir_and_relative_name(&relative_path).0.into_owned()),
+        )
+        .map_err(|err| Error::LockAcquire {
+            err,
+            full_name: name.as_bstr().into(),
+        })?;
+
+        let mut buf = Vec::new();
+        let lines = match self.reflog_iter(name, &mut buf)? {
+            Some(lines) => lines
+                .map(|line| line.map(log::mutable::Line::from))
+                .collect::<Result<Vec<_>, _>>()?,
+            None => return Ok(None),
+        };
+        let len = lines.len();
+        let mut newest_removed = false;
+        let kept = lines
+            .into_iter()
+            .enumerate()
+            .filter_map(|(pos, line)| {
+                let index = len - pos - 1;
+                if should_remove(index, &line) {
+                    newest_removed |= index == 0;
+                    None
+                } else {
+                    Some(line)
+                }
+            })
+            .collect::<Vec<_>>();
+        let removed = len - kept.len();
+        if removed == 0 {
+            return Ok(Some((0, len)));
+        }
+
+        let mut log_lock = git_lock::File::acquire_to_update_resource(self.reflog_path(name), lock_mode, None)
+            .map_err(|err| Error::LockAcquire {
+                err,
+                full_name: name.as_bstr().into(),
+            })?;
+        let mut last_kept = None;
+        log_lock.with_mut(|out| {
+            let mut out = BufWriter::new(out);
+            for mut line in kept {
+                if options.rewrite {
+                    line.previous_oid = last_kept.unwrap_or_else(|| ObjectId::null_sha(line.new_oid.kind()));
+                }
+                line.write_to(&mut out)?;
+                last_kept = Some(line.new_oid);
+            }
+            out.flush()
+        })?;
+        log_lock.commit().map_err(|err| Error::Commit {
+            err: err.error,
+            full_name: name.as_bstr().into(),
+        })?;
+
+        // The reference only needs to change if the entry it was taken from is gone.
+        if let Some(last_kept) = last_kept.filter(|id| options.update_ref && newest_removed && !id.is_null()) {
+            let is_symbolic = match self.ref_contents(&relative_path)? {
+                Some(contents) => matches!(
+                    loose::Reference::try_from_path(crate::mutable::FullName(name.as_bstr().into()), &contents),
+                    Ok(loose::Reference {
+                        target: Target::Symbolic(_),
+                        ..
+                    })
+                ),
+                None => false,
+            };
+            if !is_symbolic {
+                ref_lock.with_mut(|file| write!(file, "{}", last_kept))?;
+                ref_lock.commit().map_err(|err| Error::Commit {
+                    err: err.error,
+                    full_name: name.as_bstr().into(),
+                })?;
+            }
+        }
+        Ok(Some((removed, len)))
+    }
+}
+
+mod error {
+    use bstr::BString;
+    use quick_error::quick_error;
+
+    use crate::store::file::log;
+
+    quick_error! {
+        /// The error returned by [`file::Store::reflog_expire()`][crate::file::Store::reflog_expire()] and
+        /// [`file::Store::reflog_delete_entry()`][crate::file::Store::reflog_delete_entry()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            RefnameValidation(err: crate::name::Error) {
+                display("The reflog name or path is not a valid ref name")
+                source(err)
+            }
+            LockAcquire { err: git_lock::acquire::Error, full_name: BString } {
+                display("A lock could not be obtained for the reference or reflog of '{}'", full_name)
+                source(err)
+            }
+            Read(err: log::Error) {
+                display("The reflog could not be read")
+                from()
+                source(err)
+            }
+            Decode(err: log::iter::decode::Error) {
+                display("A reflog entry could not be parsed")
+                from()
+                source(err)
+            }
+            Io(err: std::io::Error) {
+                display("The reflog or reference could not be written")
+                from()
+                source(err)
+            }
+            Commit { err: std::io::Error, full_name: BString } {
+                display("The changed reflog or reference of '{}' could not be committed", full_name)
+                source(err)
+            }
+            EntryOutOfRange { index: usize, len: usize } {
+                display("There is no reflog entry at index {} as there are only {} entries", index, len)
+            }
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use std::{
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
+use bstr::ByteSlice;
+use git_hash::ObjectId;
+
+use crate::store::file::{self, find};
+
+/// The contents of the `MERGE_HEAD` file, listing the ids of all commits that are merged into `HEAD` by the merge
+/// in progress.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone, Default)]
+pub struct MergeHead {
+    /// The ids of the commits to merge, in the order they appear in the file.
+    pub ids: Vec<ObjectId>,
+}
+
+impl MergeHead {
+    /// Parse the contents of a `MERGE_HEAD` file from `data`, which is one id per line.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, decode::Error> {
+        let ids = data
+            .lines()
+            .enumerate()
+            .map(|(line_number, line)| {
+                ObjectId::from_hex(line).map_err(|_| decode::Error::Parse {
+                    line_number: line_number + 1,
+                    line: line.into(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(MergeHead { ids })
+    }
+
+    /// Serialize all ids to `out` in the format used by `git`.
+    pub fn write_to(&self, mut out: impl Write) -> io::Result<()> {
+        for id in &self.ids {
+            writeln!(out, "{}", id)?;
+        }
+        Ok(())
+    }
+}
+
+impl file::Store {
+    /// Read and parse the `MERGE_HEAD` file of this worktree, or return `None` if it doesn't exist.
+    pub fn merge_head(&self) -> Result<Option<MergeHead>, find::Error> {
+        Ok(match self.ref_contents(Path::new("MERGE_HEAD"))? {
+            Some(contents) => Some(MergeHead::from_bytes(&contents)?),
+            None => None,
+        })
+    }
+
+    /// Replace the `MERGE_HEAD` file of this worktree with `merge_head`, using `lock_mode` to deal with existing locks.
+    pub fn write_merge_head(&self, merge_head: &MergeHead, lock_mode: git_lock::acquire::Fail) -> io::Result<()> {
+        self.write_special_ref(Path::new("MERGE_HEAD"), lock_mode, |out| {
+            let mut out = BufWriter::new(out);
+            merge_head.write_to(&mut out)?;
+            out.flush()
+        })
+    }
+}
+
+///
+pub mod decode {
+    use bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`MergeHead::from_bytes()`][super::MergeHead::from_bytes()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Parse { line_number: usize, line: BString } {
+                display("MERGE_HEAD line {} could not be parsed: {:?}", line_number, line)
+            }
+        }
+    }
+}
@@ -25,8 +25,16 @@ pub struct Store {
     ///
     /// Typical base paths are `.git` repository folders.
     pub base: PathBuf,
+    /// The directory shared by all worktrees of a repository if `base` is the private directory of a linked worktree,
+    /// or `None` if the store belongs to the main worktree or to a bare repository.
+    ///
+    /// References private to a worktree, like `HEAD` or those in `refs/bisect/`, are kept in `base` while all others
+    /// are kept in the common directory.
+    pub common_dir: Option<PathBuf>,
     /// The way to handle reflog edits
     pub write_reflog: WriteReflog,
+    /// The `packed-refs` buffer as loaded most recently, shared among clones of this store.
+    pub(in crate::store::file) packed: crate::store::cache::Shared<crate::packed::Buffer>,
 }
 
 pub(in crate::store::file) fn path_to_name(path: impl Into<PathBuf>) -> bstr::BString {
@@ -67,26 +75,17 @@ mod reference;
 pub use reference::Reference;
 
 ///
-pub mod transaction;
+pub mod fetch_head;
+pub use fetch_head::FetchHead;
 
-mod packed {
-    use std::path::PathBuf;
+///
+pub mod merge_head;
+pub use merge_head::MergeHead;
 
-    use crate::store::{file, packed};
+///
+pub mod transaction;
 
-    impl file::Store {
-        /// Return a buffer for the packed file
-        pub fn packed(&self) -> Result<Option<packed::Buffer>, packed::buffer::open::Error> {
-            match packed::Buffer::open(self.packed_refs_path(), 32 * 1024) {
-                Ok(buf) => Ok(Some(buf)),
-                Err(packed::buffer::open::Error::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
-                Err(err) => Err(err),
-            }
-        }
+mod worktree;
 
-        /// Return the path at which packed-refs would usually be stored
-        pub fn packed_refs_path(&self) -> PathBuf {
-            self.base.join("packed-refs")
-        }
-    }
-}
+///
+pub mod packed;
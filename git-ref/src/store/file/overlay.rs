@@ -15,9 +15,9 @@ use std::{
 ///
 /// All errors will be returned verbatim, while packed errors are depleted first if loose refs also error.
 pub struct LooseThenPacked<'p, 's> {
-    base: &'s Path,
+    store: &'s file::Store,
     packed: Peekable<packed::Iter<'p>>,
-    loose: Peekable<loose::iter::SortedLoosePaths>,
+    loose: Peekable<loose::iter::LoosePaths>,
     buf: Vec<u8>,
 }
 
@@ -25,20 +25,22 @@ impl<'p, 's> LooseThenPacked<'p, 's> {
     fn convert_packed(
         &mut self,
         packed: Result<packed::Reference<'p>, packed::iter::Error>,
-    ) -> Result<Reference<'p>, Error> {
-        packed.map(Reference::Packed).map_err(|err| match err {
-            packed::iter::Error::Reference {
-                invalid_line,
-                line_number,
-            } => Error::PackedReference {
-                invalid_line,
-                line_number,
-            },
-            packed::iter::Error::Header { .. } => unreachable!("this one only happens on iteration creation"),
-        })
+    ) -> Result<Reference, Error> {
+        packed
+            .map(|p| Reference::Packed(p.to_owned()))
+            .map_err(|err| match err {
+                packed::iter::Error::Reference {
+                    invalid_line,
+                    line_number,
+                } => Error::PackedReference {
+                    invalid_line,
+                    line_number,
+                },
+                packed::iter::Error::Header { .. } => unreachable!("this one only happens on iteration creation"),
+            })
     }
 
-    fn convert_loose(&mut self, res: std::io::Result<(PathBuf, FullName)>) -> Result<Reference<'p>, Error> {
+    fn convert_loose(&mut self, res: std::io::Result<(PathBuf, FullName)>) -> Result<Reference, Error> {
         let (refpath, name) = res.map_err(Error::Traversal)?;
         std::fs::File::open(&refpath)
             .and_then(|mut f| {
@@ -49,14 +51,14 @@ impl<'p, 's> LooseThenPacked<'p, 's> {
         loose::Reference::try_from_path(name, &self.buf)
             .map_err(|err| Error::ReferenceCreation {
                 err,
-                relative_path: refpath.strip_prefix(&self.base).expect("base contains path").into(),
+                relative_path: self.store.to_base_dir_and_relative_path(&refpath).1.into(),
             })
             .map(Reference::Loose)
     }
 }
 
 impl<'p, 's> Iterator for LooseThenPacked<'p, 's> {
-    type Item = Result<Reference<'p>, Error>;
+    type Item = Result<Reference, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match (self.loose.peek(), self.packed.peek()) {
@@ -94,19 +96,20 @@ impl<'p, 's> Iterator for LooseThenPacked<'p, 's> {
 impl file::Store {
     /// Return an iterator over all references, loose or `packed`, sorted by their name.
     ///
-    /// Note that the caller is responsible for the freshness of the `packed` references buffer.
+    /// Note that the caller is responsible for the freshness of the `packed` references buffer, which is typically obtained
+    /// with [`packed_buffer()`][file::Store::packed_buffer()].
     /// If a reference cannot be parsed or read, the error will be visible to the caller and the iteration
     /// continues.
     ///
     /// Errors are returned similarly to what would happen when loose and packed refs where iterated by themeselves.
     pub fn iter<'p, 's>(&'s self, packed: &'p packed::Buffer) -> std::io::Result<LooseThenPacked<'p, 's>> {
         Ok(LooseThenPacked {
-            base: &self.base,
+            store: self,
             packed: packed
                 .iter()
                 .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?
                 .peekable(),
-            loose: loose::iter::SortedLoosePaths::at_root_with_names(self.refs_dir(), self.base.to_owned()).peekable(),
+            loose: self.loose_paths(Path::new("refs")).peekable(),
             buf: Vec::new(),
         })
     }
@@ -121,13 +124,12 @@ impl file::Store {
     ) -> std::io::Result<LooseThenPacked<'p, 's>> {
         let packed_prefix = path_to_name(self.validate_prefix(prefix.as_ref())?);
         Ok(LooseThenPacked {
-            base: &self.base,
+            store: self,
             packed: packed
                 .iter_prefixed(packed_prefix)
                 .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?
                 .peekable(),
-            loose: loose::iter::SortedLoosePaths::at_root_with_names(self.base.join(prefix), self.base.to_owned())
-                .peekable(),
+            loose: self.loose_paths(prefix.as_ref()).peekable(),
             buf: Vec::new(),
         })
     }
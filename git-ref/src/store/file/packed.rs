@@ -0,0 +1,167 @@
+use std::path::PathBuf;
+
+use crate::store::{file, packed};
+
+impl file::Store {
+    /// Return a freshly opened buffer for the packed file, bypassing the cache used by [`packed_buffer()`][file::Store::packed_buffer()].
+    pub fn packed(&self) -> Result<Option<packed::Buffer>, packed::buffer::open::Error> {
+        match packed::Buffer::open(self.packed_refs_path(), 32 * 1024) {
+            Ok(buf) => Ok(Some(buf)),
+            Err(packed::buffer::open::Error::Io(err)) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Return the buffer for the packed file, or `None` if there is no such file.
+    ///
+    /// The buffer is cached and only reloaded if the modification time, size or inode of the file changed since it was last loaded.
+    pub fn packed_buffer(&self) -> Result<Option<packed::SharedBuffer>, packed::buffer::open::Error> {
+        self.packed.get_or_load(&self.packed_refs_path(), || self.packed())
+    }
+
+    /// Forget the cached packed buffer, forcing it to be reloaded the next time it's needed.
+    pub(in crate::store::file) fn invalidate_packed_buffer(&self) {
+        self.packed.clear();
+    }
+
+    /// Return the path at which packed-refs would usually be stored
+    pub fn packed_refs_path(&self) -> PathBuf {
+        self.common_dir().join("packed-refs")
+    }
+}
+
+///
+pub mod pack_refs {
+    use std::{collections::BTreeMap, io::Write, path::Path};
+
+    use bstr::BString;
+    use git_hash::{oid, ObjectId};
+
+    use crate::{
+        mutable::Target,
+        store::{
+            file::{self, loose, worktree::is_per_worktree},
+            packed,
+        },
+    };
+
+    impl file::Store {
+        /// Move all loose references into the `packed-refs` file and delete them afterwards, similar to
+        /// `git pack-refs --all --prune`, failing according to `lock_mode` if a lock cannot be obtained.
+        ///
+        /// Symbolic references, references private to a worktree and references that can't be parsed remain loose.
+        /// `peel` is called with the target of each reference that isn't peeled yet and returns the object it ultimately
+        /// points to if it is an annotated tag, or `None` otherwise, so that the `packed-refs` file is fully peeled like
+        /// the one written by git.
+        /// Loose references are only deleted if they didn't change while they were packed, along with the directories
+        /// they leave empty below `refs/<category>`.
+        ///
+        /// Returns the amount of loose references that were packed.
+        pub fn pack_refs(
+            &self,
+            lock_mode: git_lock::acquire::Fail,
+            mut peel: impl FnMut(&oid) -> Option<ObjectId>,
+        ) -> Result<usize, Error> {
+            let mut lock = git_lock::File::acquire_to_update_resource(self.packed_refs_path(), lock_mode, None)?;
+
+            let mut refs = BTreeMap::<BString, packed::mutable::Reference>::new();
+            if let Some(packed) = self.packed()? {
+                for reference in packed.iter()? {
+                    let reference = reference?.to_owned();
+                    refs.insert(reference.name.0.clone(), reference);
+                }
+            }
+
+            let mut loose_refs = Vec::new();
+            for entry in self.loose_paths(Path::new("refs")) {
+                let (path, name) = entry?;
+                if is_per_worktree(name.to_path().as_ref()) {
+                    continue;
+                }
+                let contents = std::fs::read(&path)?;
+                let id = match loose::Reference::try_from_path(name.clone(), &contents) {
+                    Ok(loose::Reference {
+                        target: Target::Peeled(id),
+                        ..
+                    }) => id,
+                    Ok(_) | Err(_) => continue,
+                };
+                refs.insert(
+                    name.0.clone(),
+                    packed::mutable::Reference {
+                        name: name.clone(),
+                        target: id,
+                        object: None,
+                    },
+                );
+                loose_refs.push((name, contents));
+            }
+            for reference in refs.values_mut().filter(|r| r.object.is_none()) {
+                reference.object = peel(&reference.target);
+            }
+
+            lock.with_mut(|out| {
+                let mut out = std::io::BufWriter::new(out);
+                out.write_all(b"# pack-refs with: peeled fully-peeled sorted \n")?;
+                for reference in refs.values() {
+                    reference.write_to(&mut out)?;
+                }
+                out.flush()
+            })?;
+            let res = lock.commit().map_err(|err| err.error);
+            self.invalidate_packed_buffer();
+            res?;
+
+            for (name, contents) in &loose_refs {
+                let relative_path = name.to_path();
+                let ref_path = self.reference_path(&relative_path);
+                let (base, relative_name) = self.to_base_dir_and_relative_name(&relative_path);
+                // Like git, remove the directories left empty by the deleted ref, but keep `refs/<category>`.
+                let boundary = match relative_name.iter().nth(1) {
+                    Some(category) if relative_name.iter().count() > 2 => base.join("refs").join(category),
+                    _ => base.join("refs"),
+                };
+                let _lock = git_lock::Marker::acquire_to_hold_resource(&ref_path, lock_mode, Some(boundary))?;
+                if self.ref_contents(&relative_path)?.as_ref() == Some(contents) {
+                    std::fs::remove_file(&ref_path)?;
+                }
+            }
+            Ok(loose_refs.len())
+        }
+    }
+
+    mod error {
+        use quick_error::quick_error;
+
+        use crate::store::packed;
+
+        quick_error! {
+            /// The error returned by [`file::Store::pack_refs()`][crate::file::Store::pack_refs()].
+            #[derive(Debug)]
+            #[allow(missing_docs)]
+            pub enum Error {
+                LockAcquire(err: git_lock::acquire::Error) {
+                    display("A lock could not be obtained")
+                    from()
+                    source(err)
+                }
+                Io(err: std::io::Error) {
+                    display("An IO error occurred while packing references")
+                    from()
+                    source(err)
+                }
+                PackedOpen(err: packed::buffer::open::Error) {
+                    display("The existing packed-refs file could not be opened")
+                    from()
+                    source(err)
+                }
+                PackedIter(err: packed::iter::Error) {
+                    display("The existing packed-refs file could not be parsed")
+                    from()
+                    source(err)
+                }
+            }
+        }
+    }
+    pub use error::Error;
+}
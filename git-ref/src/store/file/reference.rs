@@ -12,18 +12,18 @@ use git_hash::ObjectId;
 use std::convert::TryFrom;
 
 /// Either a loose or packed reference, depending on where it was found.
-#[derive(Debug)]
-pub enum Reference<'p> {
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub enum Reference {
     /// A reference originating in a pack
-    Packed(packed::Reference<'p>),
+    Packed(packed::mutable::Reference),
     /// A reference from the filesystem
     Loose(loose::Reference),
 }
 
-impl<'p> TryFrom<Reference<'p>> for loose::Reference {
+impl TryFrom<Reference> for loose::Reference {
     type Error = ();
 
-    fn try_from(value: Reference<'p>) -> Result<Self, Self::Error> {
+    fn try_from(value: Reference) -> Result<Self, Self::Error> {
         match value {
             Reference::Loose(l) => Ok(l),
             Reference::Packed(_) => Err(()),
@@ -31,10 +31,10 @@ impl<'p> TryFrom<Reference<'p>> for loose::Reference {
     }
 }
 
-impl<'p> TryFrom<Reference<'p>> for packed::Reference<'p> {
+impl TryFrom<Reference> for packed::mutable::Reference {
     type Error = ();
 
-    fn try_from(value: Reference<'p>) -> Result<Self, Self::Error> {
+    fn try_from(value: Reference) -> Result<Self, Self::Error> {
         match value {
             Reference::Loose(_) => Err(()),
             Reference::Packed(p) => Ok(p),
@@ -42,12 +42,14 @@ impl<'p> TryFrom<Reference<'p>> for packed::Reference<'p> {
     }
 }
 
-impl<'p> Reference<'p> {
+impl Reference {
     /// For details, see [loose::Reference::log_exists()].
     pub fn log_exists(&self, store: &file::Store) -> bool {
         match self {
             Reference::Loose(r) => r.log_exists(store),
-            Reference::Packed(p) => store.reflog_exists(p.name).expect("infallible name conversion"),
+            Reference::Packed(p) => store
+                .reflog_exists(p.name.borrow())
+                .expect("infallible name conversion"),
         }
     }
 
@@ -55,39 +57,32 @@ impl<'p> Reference<'p> {
     pub fn peel_to_id_in_place(
         &mut self,
         store: &file::Store,
-        packed: Option<&packed::Buffer>,
     ) -> Result<ObjectId, crate::store::file::loose::reference::peel::to_id::Error> {
         match self {
-            Reference::Loose(r) => r.peel_to_id_in_place(store, packed).map(ToOwned::to_owned),
+            Reference::Loose(r) => r.peel_to_id_in_place(store).map(ToOwned::to_owned),
             Reference::Packed(p) => {
-                if let Some(object) = p.object {
+                if let Some(object) = p.object.take() {
                     p.target = object;
                 }
-                p.object = None;
-                Ok(p.target())
+                Ok(p.target)
             }
         }
     }
 
     /// For details, see [crate::file::loose::Reference::peel_one_level].
-    pub fn peel_one_level<'p2>(
+    pub fn peel_one_level(
         &self,
         store: &file::Store,
-        packed: Option<&'p2 packed::Buffer>,
-    ) -> Option<Result<Reference<'p2>, crate::store::file::loose::reference::peel::Error>> {
+    ) -> Option<Result<Reference, crate::store::file::loose::reference::peel::Error>> {
         match self {
-            Reference::Loose(r) => r.peel_one_level(store, packed),
-            Reference::Packed(p) => packed
-                .and_then(|packed| packed.find(p.name).ok().flatten()) // needed to get data with 'p2 lifetime
-                .and_then(|np| {
-                    p.object.and(np.object).map(|peeled| {
-                        Ok(Reference::Packed(packed::Reference {
-                            name: np.name,
-                            target: peeled,
-                            object: None,
-                        }))
-                    })
-                }),
+            Reference::Loose(r) => r.peel_one_level(store),
+            Reference::Packed(p) => p.object.map(|peeled| {
+                Ok(Reference::Packed(packed::mutable::Reference {
+                    name: p.name.clone(),
+                    target: peeled,
+                    object: None,
+                }))
+            }),
         }
     }
 
@@ -99,7 +94,7 @@ impl<'p> Reference<'p> {
     ) -> std::io::Result<Option<log::iter::Reverse<'b, std::fs::File>>> {
         match self {
             Reference::Loose(r) => r.log_iter_rev(store, buf),
-            Reference::Packed(p) => store.reflog_iter_rev(p.name, buf).map_err(must_be_io_err),
+            Reference::Packed(p) => store.reflog_iter_rev(p.name.borrow(), buf).map_err(must_be_io_err),
         }
     }
 
@@ -111,7 +106,7 @@ impl<'p> Reference<'p> {
     ) -> std::io::Result<Option<impl Iterator<Item = Result<log::Line<'b>, log::iter::decode::Error>> + 'a>> {
         match self {
             Reference::Loose(r) => store.reflog_iter(r.name.borrow(), buf).map_err(must_be_io_err),
-            Reference::Packed(p) => store.reflog_iter(p.name, buf).map_err(must_be_io_err),
+            Reference::Packed(p) => store.reflog_iter(p.name.borrow(), buf).map_err(must_be_io_err),
         }
     }
 
@@ -139,10 +134,10 @@ impl<'p> Reference<'p> {
         }
     }
 
-    /// Return the full validated name of the reference. Please note that if the reference is packed, validation can fail here.
+    /// Return the full validated name of the reference.
     pub fn name(&self) -> FullName<'_> {
         match self {
-            Reference::Packed(p) => p.name,
+            Reference::Packed(p) => p.name.borrow(),
             Reference::Loose(l) => l.name.borrow(),
         }
     }
@@ -150,7 +145,7 @@ impl<'p> Reference<'p> {
     /// Return the target to which the reference points to.
     pub fn target(&self) -> mutable::Target {
         match self {
-            Reference::Packed(p) => mutable::Target::Peeled(p.target()),
+            Reference::Packed(p) => mutable::Target::Peeled(p.target),
             Reference::Loose(l) => l.target.clone(),
         }
     }
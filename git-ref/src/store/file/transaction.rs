@@ -1,11 +1,12 @@
 use crate::{
-    mutable::Target,
-    store::{file, file::loose},
+    mutable::{FullName, Target},
+    store::{file, file::loose, packed},
     transaction::{Change, Create, RefEdit, RefEditsExt, RefLog},
+    PartialName,
 };
-use bstr::BString;
+use bstr::{BString, ByteSlice};
 use git_hash::ObjectId;
-use std::io::Write;
+use std::{convert::TryInto, io::Write, path::Path};
 
 #[derive(Debug)]
 struct Edit {
@@ -14,6 +15,9 @@ struct Edit {
     /// Set if this update is coming from a symbolic reference and used to make it appear like it is the one that is handled,
     /// instead of the referent reference.
     parent_index: Option<usize>,
+    /// Set if this update was added for the rename or copy at the given index, either to create the reference with the
+    /// new name or to update a symbolic reference pointing to the renamed one.
+    source_index: Option<usize>,
     /// For symbolic refs, this is the previous OID to put into the reflog instead of our own previous value. It's the
     /// peeled value of the leaf referent.
     leaf_referent_previous_oid: Option<ObjectId>,
@@ -25,6 +29,51 @@ impl Edit {
     }
 }
 
+/// The reference renamed or copied to the name of an [`Edit`], along with the id it points to.
+struct Relocation {
+    source: FullName,
+    oid: ObjectId,
+    is_rename: bool,
+}
+
+impl Relocation {
+    /// Return the relocation if the edit at `index` creates the new reference of a rename or copy.
+    fn of(updates: &[Edit], index: usize) -> Option<Relocation> {
+        let edit = &updates[index];
+        let source = &updates[edit.source_index?];
+        let (new_name, previous, is_rename) = match &source.update.change {
+            Change::Rename { new_name, previous, .. } => (new_name, previous, true),
+            Change::Copy { new_name, previous, .. } => (new_name, previous, false),
+            Change::Update { .. } | Change::Delete { .. } => return None,
+        };
+        match previous {
+            Some(Target::Peeled(oid)) if *new_name == edit.update.name => Some(Relocation {
+                source: source.update.name.clone(),
+                oid: *oid,
+                is_rename,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Return true if the new name can only be created after the source was removed as one is a directory of the other.
+    fn is_nested(&self, new_name: &FullName) -> bool {
+        let (source, new_name) = (self.source.as_bstr(), new_name.as_bstr());
+        is_parent_of(source, new_name) || is_parent_of(new_name, source)
+    }
+
+    /// Return true if the new name can only be locked after the source was removed, as it is in a directory of the
+    /// same name as the loose source reference.
+    fn blocks_lock_of(&self, store: &file::Store, new_name: &FullName) -> bool {
+        is_parent_of(self.source.as_bstr(), new_name.as_bstr())
+            && store.reference_path(self.source.to_path().as_ref()).is_file()
+    }
+}
+
+fn is_parent_of(parent: &bstr::BStr, child: &bstr::BStr) -> bool {
+    child.len() > parent.len() && child.starts_with(parent) && child[parent.len()] == b'/'
+}
+
 impl std::borrow::Borrow<RefEdit> for Edit {
     fn borrow(&self) -> &RefEdit {
         &self.update
@@ -42,12 +91,15 @@ pub struct Transaction<'a> {
     updates: Vec<Edit>,
     state: State,
     lock_fail_mode: git_lock::acquire::Fail,
+    /// The lock on the `packed-refs` file holding its new content, if deletions have to remove references from it.
+    packed_refs: Option<git_lock::File>,
 }
 
 impl<'a> Transaction<'a> {
     fn lock_ref_and_apply_change(
         store: &file::Store,
         lock_fail_mode: git_lock::acquire::Fail,
+        packed: Option<&packed::Buffer>,
         change: &mut Edit,
     ) -> Result<(), Error> {
         assert!(
@@ -66,13 +118,22 @@ impl<'a> Transaction<'a> {
             .or_else(|err| match err {
                 Error::ReferenceDecode(_) => Ok(None),
                 other => Err(other),
+            })
+            .and_then(|maybe_loose| match (maybe_loose, packed) {
+                (None, Some(packed)) if change.update.name.0.starts_with_str("refs/") => Ok(packed
+                    .find(change.update.name.borrow())?
+                    .map(|packed_ref| loose::Reference {
+                        name: change.update.name.clone(),
+                        target: Target::Peeled(packed_ref.target()),
+                    })),
+                (maybe_loose, _) => Ok(maybe_loose),
             });
         let lock = match &mut change.update.change {
             Change::Delete { previous, .. } => {
                 let lock = git_lock::Marker::acquire_to_hold_resource(
                     store.reference_path(&relative_path),
                     lock_fail_mode,
-                    Some(store.base.to_owned()),
+                    Some(store.to_base_dir_and_relative_name(&relative_path).0.into_owned()),
                 )
                 .map_err(|err| Error::LockAcquire {
                     err,
@@ -105,13 +166,49 @@ impl<'a> Transaction<'a> {
 
                 lock
             }
+            Change::Rename { previous, .. } | Change::Copy { previous, .. } => {
+                let lock = git_lock::Marker::acquire_to_hold_resource(
+                    store.reference_path(&relative_path),
+                    lock_fail_mode,
+                    Some(store.to_base_dir_and_relative_name(&relative_path).0.into_owned()),
+                )
+                .map_err(|err| Error::LockAcquire {
+                    err,
+                    full_name: "borrowchk wont allow change.name()".into(),
+                })?;
+                let existing = match existing_ref? {
+                    Some(existing) => existing,
+                    None => {
+                        return Err(Error::SourceMustExist {
+                            full_name: change.name(),
+                        })
+                    }
+                };
+                if let Target::Symbolic(_) = existing.target {
+                    return Err(Error::SymbolicSource {
+                        full_name: change.name(),
+                    });
+                }
+                if let Some(previous) = previous {
+                    if !previous.is_null() && *previous != existing.target {
+                        let expected = previous.clone();
+                        return Err(Error::ReferenceOutOfDate {
+                            full_name: change.name(),
+                            expected,
+                            actual: existing.target,
+                        });
+                    }
+                }
+                *previous = Some(existing.target);
+                lock
+            }
             Change::Update {
                 mode: previous, new, ..
             } => {
                 let mut lock = git_lock::File::acquire_to_update_resource(
                     store.reference_path(&relative_path),
                     lock_fail_mode,
-                    Some(store.base.to_owned()),
+                    Some(store.to_base_dir_and_relative_name(&relative_path).0.into_owned()),
                 )
                 .map_err(|err| Error::LockAcquire {
                     err,
@@ -196,23 +293,57 @@ impl<'a> Transaction<'a> {
             State::Prepared => self,
             State::Open => {
                 let store = self.store;
+                self.updates
+                    .extend_with_renames_and_copies(
+                        |name| store.find_existing(name).map(|r| r.into_target()).ok(),
+                        |name| store.symbolic_referrers(name),
+                        |idx, update| Edit {
+                            update,
+                            lock: None,
+                            parent_index: None,
+                            source_index: Some(idx),
+                            leaf_referent_previous_oid: None,
+                        },
+                    )
+                    .map_err(Error::PreprocessingFailed)?;
                 self.updates
                     .pre_process(
-                        // TODO: figure out how to best pass packed::Buffer here.
-                        // I think we should have our own as it might need changes and needs a lock, too.
-                        |name| store.find_existing(name, None).map(|r| r.into_target()).ok(),
+                        |name| store.find_existing(name).map(|r| r.into_target()).ok(),
                         |idx, update| Edit {
                             update,
                             lock: None,
                             parent_index: Some(idx),
+                            source_index: None,
                             leaf_referent_previous_oid: None,
                         },
                     )
                     .map_err(Error::PreprocessingFailed)?;
 
+                let mut packed = store.packed_buffer()?;
                 for cid in 0..self.updates.len() {
+                    let relocation = Relocation::of(&self.updates, cid);
+                    if let Some(relocation) = &relocation {
+                        let new_name = &self.updates[cid].update.name;
+                        let ignore = if relocation.is_rename {
+                            Some(&relocation.source)
+                        } else {
+                            None
+                        };
+                        if let Some(existing) = self.store.name_conflict(packed.as_deref(), new_name, ignore)? {
+                            return Err(Error::NameConflict {
+                                full_name: new_name.as_bstr().to_owned(),
+                                existing,
+                            });
+                        }
+                        if relocation.is_rename && relocation.blocks_lock_of(self.store, new_name) {
+                            // The new reference is locked when committing, right after the source was deleted.
+                            continue;
+                        }
+                    }
                     let change = &mut self.updates[cid];
-                    if let Err(err) = Self::lock_ref_and_apply_change(self.store, self.lock_fail_mode, change) {
+                    if let Err(err) =
+                        Self::lock_ref_and_apply_change(self.store, self.lock_fail_mode, packed.as_deref(), change)
+                    {
                         let err = match err {
                             Error::LockAcquire { err, full_name: _bogus } => Error::LockAcquire {
                                 err,
@@ -234,6 +365,22 @@ impl<'a> Transaction<'a> {
                         };
                         return Err(err);
                     };
+                    if let (
+                        Change::Update {
+                            mode: Create::OrUpdate { previous: Some(actual) },
+                            new,
+                            ..
+                        },
+                        Some(_),
+                    ) = (&change.update.change, &relocation)
+                    {
+                        // Unlike other updates, renames and copies don't tolerate references that already have the new value.
+                        return Err(Error::MustNotExist {
+                            full_name: change.name(),
+                            actual: actual.clone(),
+                            new: new.clone(),
+                        });
+                    }
 
                     // traverse parent chain from leaf/peeled ref and set the leaf previous oid accordingly
                     // to help with their reflog entries
@@ -248,6 +395,60 @@ impl<'a> Transaction<'a> {
                         }
                     }
                 }
+
+                // Like git, lock packed-refs only after all references are locked so that all writers acquire locks in the same order.
+                let updates = &self.updates;
+                let find_packed_refs_to_delete = |packed: Option<&packed::Buffer>| match packed {
+                    Some(buffer) => updates
+                        .iter()
+                        .filter_map(|edit| match edit.update.change {
+                            Change::Delete {
+                                log: RefLog::AndReference,
+                                ..
+                            }
+                            | Change::Rename { .. }
+                                if edit.update.name.0.starts_with_str("refs/") =>
+                            {
+                                Some(
+                                    buffer
+                                        .find(edit.update.name.borrow())
+                                        .map(|packed_ref| packed_ref.map(|_| edit.update.name.clone())),
+                                )
+                            }
+                            _ => None,
+                        })
+                        .filter_map(Result::transpose)
+                        .collect::<Result<Vec<_>, _>>(),
+                    None => Ok(Vec::new()),
+                };
+                let mut packed_refs_to_delete = find_packed_refs_to_delete(packed.as_deref())?;
+                if !packed_refs_to_delete.is_empty() {
+                    let lock =
+                        git_lock::File::acquire_to_update_resource(store.packed_refs_path(), self.lock_fail_mode, None)
+                            .map_err(Error::PackedRefsLockAcquire)?;
+                    // Read the file again now that it's locked to be sure nobody changed it in the meantime.
+                    packed = store.packed()?.map(std::sync::Arc::new);
+                    packed_refs_to_delete = find_packed_refs_to_delete(packed.as_deref())?;
+                    self.packed_refs = Some(lock);
+                }
+
+                if let (Some(lock), Some(buffer)) = (self.packed_refs.as_mut(), packed.as_deref()) {
+                    lock.with_mut(|out| {
+                        let mut out = std::io::BufWriter::new(out);
+                        out.write_all(buffer.header())?;
+                        for packed_ref in buffer.iter().map_err(to_io_err)? {
+                            let packed_ref = packed_ref.map_err(to_io_err)?;
+                            if packed_refs_to_delete
+                                .iter()
+                                .any(|name| name.borrow() == packed_ref.name)
+                            {
+                                continue;
+                            }
+                            packed_ref.to_owned().write_to(&mut out)?;
+                        }
+                        out.flush()
+                    })?;
+                }
                 self.state = State::Prepared;
                 self
             }
@@ -264,106 +465,123 @@ impl<'a> Transaction<'a> {
     ///
     /// In this stage, we perform the following operations:
     ///
-    /// * write the ref log
+    /// * write the ref log, after copying the one of renamed or copied refs
     /// * move updated refs into place
+    /// * remove deleted or renamed refs from `packed-refs`
     /// * delete reflogs
     /// * delete their corresponding reference (if applicable)
     ///   along with empty parent directories
+    /// * create renamed refs whose new name is a directory of the old one, or vice versa, and restore the old one if
+    ///   that fails
     ///
     /// Note that transactions will be prepared automatically as needed.
     pub fn commit(mut self, committer: &git_actor::Signature) -> Result<Vec<RefEdit>, Error> {
         match self.state {
             State::Open => self.prepare()?.commit(committer),
             State::Prepared => {
+                // Read the logs of renamed or copied references while they are still present and locked.
+                let mut relocated_logs = Vec::with_capacity(self.updates.len());
+                for cid in 0..self.updates.len() {
+                    relocated_logs.push(match Relocation::of(&self.updates, cid) {
+                        Some(relocation) => {
+                            let log = match std::fs::read(self.store.reflog_path(relocation.source.borrow())) {
+                                Ok(log) => Some(log),
+                                Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                                Err(err) => {
+                                    return Err(Error::CopyReflog {
+                                        err,
+                                        full_name: relocation.source.into_inner(),
+                                    })
+                                }
+                            };
+                            let is_deferred =
+                                relocation.is_rename && relocation.is_nested(&self.updates[cid].update.name);
+                            Some((relocation.oid, log, is_deferred))
+                        }
+                        None => None,
+                    });
+                }
+
                 // Perform updates first so live commits remain referenced
-                for change in self.updates.iter_mut() {
+                for (change, relocated_log) in self.updates.iter_mut().zip(relocated_logs.iter()) {
                     assert!(!change.update.deref, "Deref mode is turned into splits and turned off");
                     match &change.update.change {
-                        // reflog first, then reference
-                        Change::Update { log, new, mode } => {
-                            let lock = change.lock.take().expect("each ref is locked");
-                            let (update_ref, update_reflog) = match log.mode {
-                                RefLog::Only => (false, true),
-                                RefLog::AndReference => (true, true),
-                            };
-                            if update_reflog {
-                                match new {
-                                    Target::Symbolic(_) => {} // no reflog for symref changes
-                                    Target::Peeled(oid) => {
-                                        self.store.reflog_create_or_append(
-                                            &lock,
-                                            mode.previous_oid().or(change.leaf_referent_previous_oid),
-                                            oid,
-                                            committer,
-                                            log.message.as_ref(),
-                                            log.force_create_reflog,
-                                        )?;
-                                    }
-                                }
-                            }
-                            if update_ref {
-                                if let Err(err) = lock.commit() {
-                                    #[cfg(not(target_os = "windows"))]
-                                    let special_kind = std::io::ErrorKind::Other;
-                                    #[cfg(target_os = "windows")]
-                                    let special_kind = std::io::ErrorKind::PermissionDenied;
-                                    let err = if err.error.kind() == special_kind {
-                                        git_tempfile::remove_dir::empty_depth_first(err.instance.resource_path())
-                                            .map_err(|io_err| std::io::Error::new(std::io::ErrorKind::Other, io_err))
-                                            .and_then(|_| err.instance.commit().map_err(|err| err.error))
-                                            .err()
-                                    } else {
-                                        Some(err.error)
-                                    };
-
-                                    if let Some(err) = err {
-                                        return Err(Error::LockCommit {
-                                            err,
-                                            full_name: change.name(),
-                                        });
-                                    }
-                                };
-                            }
-                        }
-                        Change::Delete { .. } => {}
+                        // The new name of a nested rename is created after the source was deleted.
+                        Change::Update { .. } if matches!(relocated_log, Some((_, _, true))) => {}
+                        Change::Update { .. } => Self::commit_update(self.store, change, relocated_log, committer)?,
+                        Change::Delete { .. } | Change::Rename { .. } | Change::Copy { .. } => {}
                     }
                 }
 
+                if let Some(lock) = self.packed_refs.take() {
+                    let res = lock.commit().map_err(|err| Error::PackedRefsCommit(err.error));
+                    self.store.invalidate_packed_buffer();
+                    res?;
+                }
+
                 for change in self.updates.iter_mut() {
-                    match &change.update.change {
-                        Change::Update { .. } => {}
-                        Change::Delete { log: mode, .. } => {
-                            let lock = change.lock.take().expect("each ref is locked, even deletions");
-                            let (rm_reflog, rm_ref) = match mode {
-                                RefLog::AndReference => (true, true),
-                                RefLog::Only => (true, false),
-                            };
+                    let (rm_reflog, rm_ref) = match &change.update.change {
+                        Change::Update { .. } => continue,
+                        Change::Delete { log: mode, .. } => match mode {
+                            RefLog::AndReference => (true, true),
+                            RefLog::Only => (true, false),
+                        },
+                        Change::Rename { .. } => (true, true),
+                        Change::Copy { .. } => (false, false),
+                    };
+                    let lock = change.lock.take().expect("each ref is locked, even deletions");
 
-                            // Reflog deletion happens first in case it fails a ref without log is less terrible than
-                            // a log without a reference.
-                            if rm_reflog {
-                                let reflog_path = self.store.reflog_path(change.update.name.borrow());
-                                if let Err(err) = std::fs::remove_file(reflog_path) {
-                                    if err.kind() != std::io::ErrorKind::NotFound {
-                                        return Err(Error::DeleteReflog {
-                                            err,
-                                            full_name: change.name(),
-                                        });
-                                    }
-                                }
+                    // Reflog deletion happens first in case it fails a ref without log is less terrible than
+                    // a log without a reference.
+                    if rm_reflog {
+                        let reflog_path = self.store.reflog_path(change.update.name.borrow());
+                        if let Err(err) = std::fs::remove_file(reflog_path) {
+                            if err.kind() != std::io::ErrorKind::NotFound {
+                                return Err(Error::DeleteReflog {
+                                    err,
+                                    full_name: change.name(),
+                                });
                             }
-                            if rm_ref {
-                                let reference_path = self.store.reference_path(change.update.name.to_path().as_ref());
-                                if let Err(err) = std::fs::remove_file(reference_path) {
-                                    if err.kind() != std::io::ErrorKind::NotFound {
-                                        return Err(Error::DeleteReference {
-                                            err,
-                                            full_name: change.name(),
-                                        });
-                                    }
-                                }
+                        }
+                    }
+                    if rm_ref {
+                        let reference_path = self.store.reference_path(change.update.name.to_path().as_ref());
+                        if let Err(err) = std::fs::remove_file(reference_path) {
+                            if err.kind() != std::io::ErrorKind::NotFound {
+                                return Err(Error::DeleteReference {
+                                    err,
+                                    full_name: change.name(),
+                                });
                             }
-                            drop(lock); // allow deletion of empty leading directories
+                        }
+                    }
+                    drop(lock); // allow deletion of empty leading directories
+                }
+
+                for (cid, relocated_log) in relocated_logs.iter().enumerate() {
+                    if let Some((oid, log, true)) = relocated_log {
+                        let store = self.store;
+                        let change = &mut self.updates[cid];
+                        let res = match change.lock {
+                            Some(_) => Ok(()),
+                            None => Self::lock_ref_and_apply_change(store, self.lock_fail_mode, None, change),
+                        }
+                        .and_then(|_| Self::commit_update(store, change, relocated_log, committer));
+                        if let Err(err) = res {
+                            // Like git, try to put the source back to not lose it. If that fails as well, all we
+                            // can do is to report the original error.
+                            let source =
+                                &self.updates[self.updates[cid].source_index.expect("relocations have a source")];
+                            store
+                                .restore_rename_source(
+                                    &source.update.name,
+                                    &self.updates[cid].update.name,
+                                    oid,
+                                    log.as_deref(),
+                                    self.lock_fail_mode,
+                                )
+                                .ok();
+                            return Err(err);
                         }
                     }
                 }
@@ -371,6 +589,204 @@ impl<'a> Transaction<'a> {
             }
         }
     }
+
+    /// Write the reflog of the locked `change`, which is an update, and move the updated reference into place.
+    ///
+    /// If `relocated_log` is set, the change creates the new reference of a rename or copy of a reference pointing to its
+    /// id, whose reflog is written first, along with a flag telling if the reference could only be created after the
+    /// source was deleted.
+    fn commit_update(
+        store: &file::Store,
+        change: &mut Edit,
+        relocated_log: &Option<(ObjectId, Option<Vec<u8>>, bool)>,
+        committer: &git_actor::Signature,
+    ) -> Result<(), Error> {
+        let (log, new, mode) = match &change.update.change {
+            Change::Update { log, new, mode } => (log, new, mode),
+            Change::Delete { .. } | Change::Rename { .. } | Change::Copy { .. } => {
+                unreachable!("only called for updates")
+            }
+        };
+        // reflog first, then reference
+        let lock = change.lock.take().expect("each ref is locked");
+        let (update_ref, update_reflog) = match log.mode {
+            RefLog::Only => (false, true),
+            RefLog::AndReference => (true, true),
+        };
+        let (previous_oid, force_create_reflog) = match relocated_log {
+            Some((oid, relocated_log, _is_deferred)) => {
+                if let Some(content) = relocated_log {
+                    store
+                        .write_reflog_copy(change.update.name.borrow(), content)
+                        .map_err(|err| Error::CopyReflog {
+                            err,
+                            full_name: change.name(),
+                        })?;
+                }
+                (Some(*oid), log.force_create_reflog || relocated_log.is_some())
+            }
+            None => (
+                mode.previous_oid().or(change.leaf_referent_previous_oid),
+                log.force_create_reflog,
+            ),
+        };
+        if update_reflog {
+            match new {
+                Target::Symbolic(_) => {} // no reflog for symref changes
+                Target::Peeled(oid) => {
+                    store.reflog_create_or_append(
+                        &lock,
+                        previous_oid,
+                        oid,
+                        committer,
+                        log.message.as_ref(),
+                        force_create_reflog,
+                    )?;
+                }
+            }
+        }
+        if update_ref {
+            if let Err(err) = lock.commit() {
+                #[cfg(not(target_os = "windows"))]
+                let special_kind = std::io::ErrorKind::Other;
+                #[cfg(target_os = "windows")]
+                let special_kind = std::io::ErrorKind::PermissionDenied;
+                let err = if err.error.kind() == special_kind {
+                    git_tempfile::remove_dir::empty_depth_first(err.instance.resource_path())
+                        .map_err(|io_err| std::io::Error::new(std::io::ErrorKind::Other, io_err))
+                        .and_then(|_| err.instance.commit().map_err(|err| err.error))
+                        .err()
+                } else {
+                    Some(err.error)
+                };
+
+                if let Some(err) = err {
+                    return Err(Error::LockCommit {
+                        err,
+                        full_name: change.name(),
+                    });
+                }
+            };
+        }
+        Ok(())
+    }
+}
+
+impl file::Store {
+    /// Return the names of all loose symbolic references, including `HEAD`, which point to `name`.
+    fn symbolic_referrers(&self, name: &FullName) -> std::io::Result<Vec<FullName>> {
+        let head = self
+            .ref_contents(Path::new("HEAD"))?
+            .and_then(|buf| loose::Reference::try_from_path("HEAD".try_into().expect("valid name"), &buf).ok());
+        let refs = match self.loose_iter() {
+            Ok(refs) => Some(refs),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        Ok(head
+            .into_iter()
+            .chain(refs.into_iter().flatten().filter_map(Result::ok))
+            .filter(|r| matches!(&r.target, Target::Symbolic(target) if target == name))
+            .map(|r| r.name)
+            .collect())
+    }
+
+    /// Return the name of a reference which prevents a reference named `name` from being created as one would be the
+    /// directory of the other, ignoring the reference named `ignore`.
+    fn name_conflict(
+        &self,
+        packed: Option<&packed::Buffer>,
+        name: &FullName,
+        ignore: Option<&FullName>,
+    ) -> Result<Option<BString>, Error> {
+        let name = name.as_bstr();
+        let is_ignored = |candidate: &bstr::BStr| ignore.map_or(false, |ignore| ignore.as_bstr() == candidate);
+        let first_component_end = name.find_byte(b'/').unwrap_or(name.len());
+        for pos in (first_component_end + 1..name.len()).filter(|pos| name[*pos] == b'/') {
+            let parent = &name[..pos];
+            if is_ignored(parent.as_bstr()) {
+                continue;
+            }
+            let exists_loose = self.ref_contents(Path::new(parent.to_str_lossy().as_ref()))?.is_some();
+            let exists_packed = match packed {
+                Some(packed) => packed.find(PartialName(parent.as_bstr()))?.is_some(),
+                None => false,
+            };
+            if exists_loose || exists_packed {
+                return Ok(Some(parent.into()));
+            }
+        }
+
+        let children = self.reference_path(Path::new(name.to_str_lossy().as_ref()));
+        if children.is_dir() {
+            if let Some(child) = self
+                .loose_iter_prefixed(Path::new(name.to_str_lossy().as_ref()))?
+                .filter_map(Result::ok)
+                .find(|r| !is_ignored(r.name.as_bstr()))
+            {
+                return Ok(Some(child.name.into_inner()));
+            }
+        }
+        if let Some(packed) = packed {
+            let mut prefix = name.to_owned();
+            prefix.push(b'/');
+            for r in packed.iter_prefixed(prefix).map_err(to_io_err)? {
+                let r = r.map_err(to_io_err)?;
+                if !is_ignored(r.name.as_bstr()) {
+                    return Ok(Some(r.name.as_bstr().to_owned()));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Write `oid` and its reflog `log` back to `source` after renaming it to `new_name` failed once it was deleted,
+    /// and remove the reflog that may have been written for `new_name` already.
+    fn restore_rename_source(
+        &self,
+        source: &FullName,
+        new_name: &FullName,
+        oid: &ObjectId,
+        log: Option<&[u8]>,
+        lock_fail_mode: git_lock::acquire::Fail,
+    ) -> std::io::Result<()> {
+        if let Err(err) = std::fs::remove_file(self.reflog_path(new_name.borrow())) {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                return Err(err);
+            }
+        }
+        let relative_path = source.to_path();
+        let path = self.reference_path(&relative_path);
+        if path.is_dir() {
+            git_tempfile::remove_dir::empty_depth_first(&path)?;
+        }
+        let mut lock = git_lock::File::acquire_to_update_resource(
+            path,
+            lock_fail_mode,
+            Some(self.to_base_dir_and_relative_name(&relative_path).0.into_owned()),
+        )
+        .map_err(to_io_err)?;
+        lock.with_mut(|file| write!(file, "{}", oid))?;
+        if let Some(log) = log {
+            self.write_reflog_copy(source.borrow(), log)?;
+        }
+        lock.commit().map_err(|err| err.error)?;
+        Ok(())
+    }
+
+    /// Write `content` as reflog of the reference `name`, replacing empty directories that may be in the way.
+    fn write_reflog_copy(&self, name: crate::FullName<'_>, content: &[u8]) -> std::io::Result<()> {
+        let path = self.reflog_path(name);
+        if path.is_dir() {
+            git_tempfile::remove_dir::empty_depth_first(&path)?;
+        }
+        std::fs::create_dir_all(path.parent().expect("always with parent directory"))?;
+        std::fs::write(path, content)
+    }
+}
+
+fn to_io_err(err: impl std::error::Error + Send + Sync + 'static) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::Other, err)
 }
 
 /// The state of a [`Transaction`]
@@ -397,17 +813,22 @@ impl file::Store {
                     update,
                     lock: None,
                     parent_index: None,
+                    source_index: None,
                     leaf_referent_previous_oid: None,
                 })
                 .collect(),
             state: State::Open,
             lock_fail_mode: lock,
+            packed_refs: None,
         }
     }
 }
 
 mod error {
-    use crate::{mutable::Target, store::file};
+    use crate::{
+        mutable::Target,
+        store::{file, packed},
+    };
     use bstr::BString;
     use quick_error::quick_error;
 
@@ -463,6 +884,37 @@ mod error {
                 from()
                 source(err)
             }
+            PackedOpen(err: packed::buffer::open::Error) {
+                display("The packed-refs file could not be opened")
+                from()
+                source(err)
+            }
+            PackedFind(err: packed::find::Error) {
+                display("A reference could not be looked up in the packed-refs file")
+                from()
+                source(err)
+            }
+            PackedRefsLockAcquire(err: git_lock::acquire::Error) {
+                display("A lock for the packed-refs file could not be obtained")
+                source(err)
+            }
+            PackedRefsCommit(err: std::io::Error) {
+                display("The packed-refs file could not be updated")
+                source(err)
+            }
+            SourceMustExist { full_name: BString } {
+                display("The reference '{}' to rename or copy did not exist or could not be parsed", full_name)
+            }
+            SymbolicSource { full_name: BString } {
+                display("The reference '{}' is symbolic and can't be renamed or copied", full_name)
+            }
+            NameConflict { full_name: BString, existing: BString } {
+                display("'{}' exists; cannot create '{}'", existing, full_name)
+            }
+            CopyReflog { full_name: BString, err: std::io::Error } {
+                display("The reflog of reference '{}' could not be copied", full_name)
+                source(err)
+            }
         }
     }
 }
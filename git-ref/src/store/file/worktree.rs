@@ -0,0 +1,79 @@
+use std::{
+    borrow::Cow,
+    path::{Component, Path},
+};
+
+use crate::store::file;
+
+impl file::Store {
+    /// Return the directory shared by all worktrees, which is our [base][file::Store::base] unless this store belongs to a
+    /// linked worktree.
+    pub fn common_dir(&self) -> &Path {
+        self.common_dir.as_deref().unwrap_or(&self.base)
+    }
+
+    /// Return the directory containing the reference of the given full `name` along with its path relative to that directory.
+    ///
+    /// Besides the references private to our worktree, `main-worktree/<name>` refers to the private references of the main
+    /// worktree and `worktrees/<id>/<name>` to the ones of the linked worktree with the given id, like in `git`.
+    pub(in crate::store::file) fn to_base_dir_and_relative_name<'a>(
+        &self,
+        name: &'a Path,
+    ) -> (Cow<'_, Path>, &'a Path) {
+        let mut components = name.components();
+        match components.next() {
+            Some(Component::Normal(first)) if first == "main-worktree" && is_per_worktree(components.as_path()) => {
+                return (self.common_dir().into(), components.as_path())
+            }
+            Some(Component::Normal(first)) if first == "worktrees" => {
+                if let Some(Component::Normal(id)) = components.next() {
+                    if is_per_worktree(components.as_path()) {
+                        let dir = self.common_dir().join("worktrees").join(id);
+                        return (dir.into(), components.as_path());
+                    }
+                }
+            }
+            _ => {}
+        }
+        if is_per_worktree(name) {
+            (self.base.as_path().into(), name)
+        } else {
+            (self.common_dir().into(), name)
+        }
+    }
+
+    /// Return the directory containing the reference whose file or lock is at `path` along with the path relative to it.
+    pub(in crate::store::file) fn to_base_dir_and_relative_path<'a>(
+        &self,
+        path: &'a Path,
+    ) -> (Cow<'_, Path>, &'a Path) {
+        let (base, relative_path) = match path.strip_prefix(&self.base) {
+            Ok(relative_path) => (&self.base, relative_path),
+            Err(_) => (
+                self.common_dir.as_ref().expect("path is within the common directory"),
+                path.strip_prefix(self.common_dir())
+                    .expect("lock must be held within this store"),
+            ),
+        };
+        let mut components = relative_path.components();
+        match (components.next(), components.next()) {
+            (Some(Component::Normal(first)), Some(Component::Normal(id))) if first == "worktrees" => {
+                (base.join("worktrees").join(id).into(), components.as_path())
+            }
+            _ => (base.as_path().into(), relative_path),
+        }
+    }
+}
+
+/// Return true if the reference with the given full `name` is private to a worktree, which is the case for `HEAD`
+/// and other references outside of `refs/`, as well as for references in `refs/worktree/`, `refs/bisect/` and `refs/rewritten/`.
+pub(in crate::store::file) fn is_per_worktree(name: &Path) -> bool {
+    let mut components = name.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(first)), None) => first != "refs" && !first.is_empty(),
+        (Some(Component::Normal(first)), Some(Component::Normal(second))) if first == "refs" => {
+            second == "worktree" || second == "bisect" || second == "rewritten"
+        }
+        _ => false,
+    }
+}
@@ -1,5 +1,10 @@
+mod cache;
+
 ///
 pub mod file;
 
 ///
 pub mod packed;
+
+///
+pub mod reftable;
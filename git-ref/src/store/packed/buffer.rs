@@ -18,6 +18,13 @@ impl AsRef<[u8]> for packed::Backing {
     }
 }
 
+impl packed::Buffer {
+    /// Return the header line of the packed-refs file including its trailing newline, or an empty slice if there is none.
+    pub(crate) fn header(&self) -> &[u8] {
+        &self.data.as_ref()[..self.offset]
+    }
+}
+
 ///
 pub mod open {
     use crate::store::packed;
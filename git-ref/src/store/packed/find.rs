@@ -71,7 +71,7 @@ impl packed::Buffer {
                 .rfind(b"\n")
                 .and_then(|pos| {
                     let candidate = pos + 1;
-                    if a[candidate] == b'^' {
+                    if a.get(candidate) == Some(&b'^') {
                         a[..pos].rfind(b"\n").map(|pos| pos + 1)
                     } else {
                         Some(candidate)
@@ -2,6 +2,7 @@ use crate::FullName;
 use bstr::{BStr, BString};
 use filebuffer::FileBuffer;
 use git_hash::ObjectId;
+use std::sync::Arc;
 
 enum Backing {
     /// The buffer is loaded entirely in memory, along with the `offset` to the first record past the header.
@@ -19,6 +20,9 @@ pub struct Buffer {
     offset: usize,
 }
 
+/// A buffer which can be shared among threads and is typically cached by a [file store][crate::file::Store].
+pub type SharedBuffer = Arc<Buffer>;
+
 /// A reference as parsed from the `packed-refs` file
 #[derive(Debug, PartialEq, Eq)]
 pub struct Reference<'a> {
@@ -45,6 +49,49 @@ impl<'a> Reference<'a> {
             |id| ObjectId::from_hex(id).expect("parser validation"),
         )
     }
+
+    /// Convert this instance into an owned version which doesn't borrow from the buffer it was read from.
+    pub fn to_owned(&self) -> mutable::Reference {
+        mutable::Reference {
+            name: crate::mutable::FullName(self.name.0.to_owned()),
+            target: self.target(),
+            object: self.object.map(|id| ObjectId::from_hex(id).expect("parser validation")),
+        }
+    }
+}
+
+///
+pub mod mutable {
+    use git_hash::ObjectId;
+    use std::io;
+
+    /// An owned reference as parsed from the `packed-refs` file, see [`Reference`][super::Reference].
+    #[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+    pub struct Reference {
+        /// The validated full name of the reference.
+        pub name: crate::mutable::FullName,
+        /// The target object id of the reference.
+        pub target: ObjectId,
+        /// The fully peeled object id that the ref is ultimately pointing to, if known.
+        pub object: Option<ObjectId>,
+    }
+
+    impl Reference {
+        /// Return the object this reference is ultimately pointing to, which is the `target` unless it is a peelable
+        /// reference like an annotated tag.
+        pub fn object(&self) -> ObjectId {
+            self.object.unwrap_or(self.target)
+        }
+
+        /// Serialize this instance as lines of a `packed-refs` file to `out`.
+        pub fn write_to(&self, mut out: impl io::Write) -> io::Result<()> {
+            writeln!(out, "{} {}", self.target, self.name.0)?;
+            if let Some(object) = self.object {
+                writeln!(out, "^{}", object)?;
+            }
+            Ok(())
+        }
+    }
 }
 
 /// An iterator over references in a packed refs file
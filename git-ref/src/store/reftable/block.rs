@@ -0,0 +1,259 @@
+//! Blocks hold prefix-compressed records along with restart points to allow binary searches.
+use std::{borrow::Cow, io::Write};
+
+use crate::store::reftable::varint;
+
+/// The types of blocks, stored in their first byte.
+pub(crate) mod typ {
+    pub const REF: u8 = b'r';
+    pub const OBJ: u8 = b'o';
+    pub const INDEX: u8 = b'i';
+    pub const LOG: u8 = b'g';
+}
+
+fn is_known_type(typ: u8) -> bool {
+    matches!(typ, typ::REF | typ::OBJ | typ::INDEX | typ::LOG)
+}
+
+fn be24(bytes: &[u8]) -> usize {
+    (usize::from(bytes[0]) << 16) | (usize::from(bytes[1]) << 8) | usize::from(bytes[2])
+}
+
+pub(crate) fn put_be24(value: usize, out: &mut [u8]) {
+    out[0] = (value >> 16) as u8;
+    out[1] = (value >> 8) as u8;
+    out[2] = value as u8;
+}
+
+/// The largest block size that can be stored in 24 bits.
+pub(crate) const MAX_SIZE: usize = (1 << 24) - 1;
+
+/// Builds a single block in memory.
+pub(crate) struct Writer {
+    typ: u8,
+    header_len: usize,
+    buf: Vec<u8>,
+    restarts: Vec<usize>,
+    last_key: Vec<u8>,
+    entries: usize,
+    block_size: usize,
+    restart_interval: usize,
+}
+
+impl Writer {
+    /// Create a new writer for a block of type `typ` which leaves `header_len` bytes for the file header at its beginning.
+    pub fn new(typ: u8, header_len: usize, block_size: usize, restart_interval: usize) -> Self {
+        let mut buf = vec![0; header_len + 4];
+        buf[header_len] = typ;
+        Writer {
+            typ,
+            header_len,
+            buf,
+            restarts: Vec::new(),
+            last_key: Vec::new(),
+            entries: 0,
+            block_size,
+            restart_interval: restart_interval.max(1),
+        }
+    }
+
+    /// Add a record with `key`, the 3 bits of `extra` information and its encoded `value`.
+    ///
+    /// Returns `false` if the record doesn't fit into the block anymore, which never happens for the first record.
+    pub fn add(&mut self, key: &[u8], extra: u8, value: &[u8]) -> bool {
+        let is_restart = self.entries % self.restart_interval == 0;
+        let prefix_len = if is_restart {
+            0
+        } else {
+            self.last_key.iter().zip(key).take_while(|(a, b)| a == b).count()
+        };
+        let mut record = Vec::with_capacity(key.len() + value.len() + 8);
+        varint::encode(prefix_len as u64, &mut record);
+        varint::encode((((key.len() - prefix_len) as u64) << 3) | u64::from(extra), &mut record);
+        record.extend_from_slice(&key[prefix_len..]);
+        record.extend_from_slice(value);
+
+        let restart_count = self.restarts.len() + usize::from(is_restart);
+        if self.entries > 0 && self.buf.len() + record.len() + 3 * restart_count + 2 > self.block_size {
+            return false;
+        }
+        if is_restart {
+            self.restarts.push(self.buf.len());
+        }
+        self.buf.extend_from_slice(&record);
+        self.last_key.clear();
+        self.last_key.extend_from_slice(key);
+        self.entries += 1;
+        true
+    }
+
+    pub fn typ(&self) -> u8 {
+        self.typ
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries == 0
+    }
+
+    pub fn last_key(&self) -> &[u8] {
+        &self.last_key
+    }
+
+    /// Return the finished block, with the space for the file header left empty.
+    ///
+    /// Log blocks are compressed, their size in the block header is the size before compression.
+    pub fn finish(mut self) -> std::io::Result<Vec<u8>> {
+        for restart in &self.restarts {
+            let mut bytes = [0; 3];
+            put_be24(*restart, &mut bytes);
+            self.buf.extend_from_slice(&bytes);
+        }
+        self.buf.extend_from_slice(&(self.restarts.len() as u16).to_be_bytes());
+        let block_len = self.buf.len();
+        if block_len > MAX_SIZE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "A block exceeded the maximum block size",
+            ));
+        }
+        put_be24(block_len, &mut self.buf[self.header_len + 1..]);
+        if self.typ == typ::LOG {
+            let records_start = self.header_len + 4;
+            let mut out = git_features::zlib::stream::deflate::Write::new(self.buf[..records_start].to_vec());
+            out.write_all(&self.buf[records_start..])?;
+            out.flush()?;
+            self.buf = out.into_inner();
+        }
+        Ok(self.buf)
+    }
+}
+
+/// A block as read from a table.
+pub(crate) struct Block<'a> {
+    pub typ: u8,
+    /// The block data beginning at the position of the block in the file, with log records already decompressed.
+    data: Cow<'a, [u8]>,
+    records_start: usize,
+    restarts_start: usize,
+    restart_count: usize,
+    /// The amount of bytes the block occupies in the file, including padding.
+    pub full_len: usize,
+}
+
+impl<'a> Block<'a> {
+    /// Read the block at `pos` of `file`, which ends where the footer begins, or return `Ok(None)` if there is none.
+    ///
+    /// `header_len` is the size of the file header if the block is the first one in the file, and `block_size` is the one
+    /// stored in the file header.
+    /// An error is returned if the block is corrupt.
+    pub fn at(file: &'a [u8], pos: usize, header_len: usize, block_size: usize) -> Result<Option<Self>, ()> {
+        if pos >= file.len() {
+            return Ok(None);
+        }
+        let records_start = header_len + 4;
+        let header = file.get(pos + header_len..pos + records_start).ok_or(())?;
+        let typ = header[0];
+        if !is_known_type(typ) {
+            return Err(());
+        }
+        let block_len = be24(&header[1..]);
+        if block_len < records_start + 2 {
+            return Err(());
+        }
+        let (data, full_len) = if typ == typ::LOG {
+            let mut data = vec![0; block_len];
+            data[..records_start].copy_from_slice(&file[pos..pos + records_start]);
+            let consumed = inflate(&file[pos + records_start..], &mut data[records_start..])?;
+            (Cow::Owned(data), records_start + consumed)
+        } else {
+            let data = file.get(pos..pos + block_len).ok_or(())?;
+            let is_padded = block_size > block_len && file.get(pos + block_len).map_or(true, |b| *b == 0);
+            (Cow::Borrowed(data), if is_padded { block_size } else { block_len })
+        };
+        let restart_count = usize::from(u16::from_be_bytes([data[block_len - 2], data[block_len - 1]]));
+        let restarts_start = (block_len - 2).checked_sub(3 * restart_count).ok_or(())?;
+        if restarts_start < records_start {
+            return Err(());
+        }
+        Ok(Some(Block {
+            typ,
+            data,
+            records_start,
+            restarts_start,
+            restart_count,
+            full_len,
+        }))
+    }
+
+    fn restart(&self, idx: usize) -> usize {
+        be24(&self.data[self.restarts_start + 3 * idx..])
+    }
+
+    /// Decode all records, starting at the last restart point whose key is not greater than `seek` if set,
+    /// or at the beginning of the block otherwise.
+    ///
+    /// `decode(key, extra, input)` decodes the value of the record and returns it along with the amount of consumed bytes.
+    #[allow(clippy::type_complexity)]
+    pub fn records<T>(
+        &self,
+        seek: Option<&[u8]>,
+        decode: impl Fn(Vec<u8>, u8, &[u8]) -> Option<(T, usize)>,
+    ) -> Result<Vec<(Vec<u8>, T)>, ()> {
+        let mut pos = self.records_start;
+        if let Some(seek) = seek {
+            let (mut low, mut high) = (0, self.restart_count);
+            while low < high {
+                let mid = (low + high) / 2;
+                let (key, _, _) = self.key_at(self.restart(mid), &[])?;
+                if key.as_slice() <= seek {
+                    low = mid + 1;
+                } else {
+                    high = mid;
+                }
+            }
+            if low > 0 {
+                pos = self.restart(low - 1);
+            }
+        }
+
+        let mut out = Vec::new();
+        let mut last_key = Vec::new();
+        while pos < self.restarts_start {
+            let (key, extra, consumed) = self.key_at(pos, &last_key)?;
+            pos += consumed;
+            let (value, consumed) =
+                decode(key.clone(), extra, self.data.get(pos..self.restarts_start).ok_or(())?).ok_or(())?;
+            pos += consumed;
+            last_key.clone_from(&key);
+            out.push((key, value));
+        }
+        Ok(out)
+    }
+
+    fn key_at(&self, pos: usize, last_key: &[u8]) -> Result<(Vec<u8>, u8, usize), ()> {
+        let input = self.data.get(pos..self.restarts_start).ok_or(())?;
+        let (prefix_len, mut consumed) = varint::decode(input).ok_or(())?;
+        let (suffix_and_extra, len) = varint::decode(&input[consumed..]).ok_or(())?;
+        consumed += len;
+        let (prefix_len, suffix_len) = (prefix_len as usize, (suffix_and_extra >> 3) as usize);
+        let mut key = last_key.get(..prefix_len).ok_or(())?.to_vec();
+        key.extend_from_slice(input.get(consumed..consumed + suffix_len).ok_or(())?);
+        Ok((key, (suffix_and_extra & 7) as u8, consumed + suffix_len))
+    }
+}
+
+/// Inflate `input` into `out` and return the amount of consumed bytes, which must exactly fill `out`.
+fn inflate(input: &[u8], out: &mut [u8]) -> Result<usize, ()> {
+    let mut inflate = git_features::zlib::Inflate::default();
+    let (mut consumed, mut written) = (0, 0);
+    loop {
+        let (status, read, wrote) = inflate.once(&input[consumed..], &mut out[written..]).map_err(|_| ())?;
+        consumed += read;
+        written += wrote;
+        match status {
+            git_features::zlib::Status::StreamEnd if written == out.len() => return Ok(consumed),
+            git_features::zlib::Status::Ok if read + wrote > 0 => continue,
+            _ => return Err(()),
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::convert::TryInto;
+
+use bstr::{BStr, BString, ByteSlice};
+
+pub use error::Error;
+
+use crate::{
+    mutable,
+    store::reftable::{
+        self,
+        record::{self, RefValue},
+        stack, Table,
+    },
+    PartialName,
+};
+
+impl reftable::Store {
+    /// Find a single reference by the given `partial` name, following the same lookup rules as
+    /// [`file::Store::find()`][crate::file::Store::find()].
+    ///
+    /// Returns `Ok(None)` if no such ref exists.
+    pub fn find<'a, Name, E>(&self, partial: Name) -> Result<Option<reftable::Reference>, Error>
+    where
+        Name: TryInto<PartialName<'a>, Error = E>,
+        Error: From<E>,
+    {
+        let partial = partial.try_into()?;
+        let tables = self.shared_tables()?;
+        for name in candidates(partial.as_bstr()) {
+            if let Some(r) = find_in(&tables, name.as_ref())? {
+                return Ok(Some(r));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Return all fully qualified names `partial` could refer to, in the order in which they should be tried.
+fn candidates(partial: &BStr) -> Vec<BString> {
+    let mut names = Vec::new();
+    if partial.find_byte(b'/').is_none() && partial.iter().all(|b| b.is_ascii_uppercase() || *b == b'_') {
+        names.push(partial.to_owned());
+    }
+    if partial.starts_with_str("refs/") {
+        names.push(partial.to_owned());
+    }
+    for prefix in &["refs/", "refs/tags/", "refs/heads/", "refs/remotes/"] {
+        let mut name = BString::from(*prefix);
+        name.extend_from_slice(partial);
+        names.push(name);
+    }
+    let mut remote_head = BString::from("refs/remotes/");
+    remote_head.extend_from_slice(partial);
+    remote_head.extend_from_slice(b"/HEAD");
+    names.push(remote_head);
+    names
+}
+
+/// Find the reference with the fully qualified `name` in `tables`, ordered from oldest to newest.
+pub(crate) fn find_in(tables: &[Table], name: &BStr) -> Result<Option<reftable::Reference>, stack::Error> {
+    for table in tables.iter().rev() {
+        if let Some(r) = table.find_ref(name)? {
+            return Ok(reftable::Reference::from_record(r));
+        }
+    }
+    Ok(None)
+}
+
+impl reftable::Reference {
+    /// Convert `r` into a reference, or return `None` if it is a deletion.
+    pub(crate) fn from_record(r: record::Ref) -> Option<Self> {
+        let (target, peeled) = match r.value {
+            RefValue::Deletion => return None,
+            RefValue::Peeled(id) => (mutable::Target::Peeled(id), None),
+            RefValue::PeeledWithObject { target, peeled } => (mutable::Target::Peeled(target), Some(peeled)),
+            RefValue::Symbolic(name) => (mutable::Target::Symbolic(mutable::FullName(name)), None),
+        };
+        Some(reftable::Reference {
+            name: mutable::FullName(r.name),
+            target,
+            peeled,
+            update_index: r.update_index,
+        })
+    }
+}
+
+mod error {
+    use std::convert::Infallible;
+
+    use quick_error::quick_error;
+
+    use crate::store::reftable::stack;
+
+    quick_error! {
+        /// The error returned by [`Store::find()`][crate::reftable::Store::find()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            RefnameValidation(err: crate::name::Error) {
+                display("The ref name or path is not a valid ref name")
+                from()
+                source(err)
+            }
+            Stack(err: stack::Error) {
+                display("The reftables could not be read")
+                from()
+                source(err)
+            }
+        }
+    }
+
+    impl From<Infallible> for Error {
+        fn from(_: Infallible) -> Self {
+            unreachable!("this impl is needed to allow passing a known valid partial path as parameter")
+        }
+    }
+}
+
+///
+pub mod existing {
+    use std::convert::TryInto;
+
+    pub use error::Error;
+
+    use crate::{
+        store::{reftable, reftable::find},
+        PartialName,
+    };
+
+    impl reftable::Store {
+        /// Similar to [`reftable::Store::find()`] but a non-existing ref is treated as error.
+        pub fn find_existing<'a, Name, E>(&self, partial: Name) -> Result<reftable::Reference, Error>
+        where
+            Name: TryInto<PartialName<'a>, Error = E>,
+            find::Error: From<E>,
+        {
+            match self.find(partial) {
+                Ok(Some(r)) => Ok(r),
+                Ok(None) => Err(Error::NotFound),
+                Err(err) => Err(err.into()),
+            }
+        }
+    }
+
+    mod error {
+        use quick_error::quick_error;
+
+        use crate::store::reftable::find;
+
+        quick_error! {
+            /// The error returned by [`Store::find_existing()`][crate::reftable::Store::find_existing()].
+            #[derive(Debug)]
+            #[allow(missing_docs)]
+            pub enum Error {
+                Find(err: find::Error) {
+                    display("An error occurred while trying to find a reference")
+                    from()
+                    source(err)
+                }
+                NotFound {
+                    display("The reference did not exist even though that was expected")
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use bstr::{BStr, ByteSlice};
+
+use crate::store::{
+    reftable,
+    reftable::{record::RefValue, stack},
+};
+
+/// An iterator over all references of a [reftable store][reftable::Store], in the order of their names.
+pub struct Iter {
+    inner: std::vec::IntoIter<reftable::Reference>,
+}
+
+impl Iterator for Iter {
+    type Item = reftable::Reference;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+impl reftable::Store {
+    /// Return an iterator over all references in the store, as seen at the time of the call.
+    pub fn iter(&self) -> Result<Iter, stack::Error> {
+        self.iter_prefixed(b"".as_bstr())
+    }
+
+    /// Return an iterator over all references whose fully qualified name starts with `prefix`, like `refs/heads/`.
+    pub fn iter_prefixed(&self, prefix: &BStr) -> Result<Iter, stack::Error> {
+        let tables = self.shared_tables()?;
+        let refs = stack::merged_refs(&tables)?
+            .into_iter()
+            .filter(|(name, r)| name.starts_with(prefix) && r.value != RefValue::Deletion)
+            .filter_map(|(_, r)| reftable::Reference::from_record(r))
+            .collect::<Vec<_>>();
+        Ok(Iter {
+            inner: refs.into_iter(),
+        })
+    }
+}
@@ -0,0 +1,105 @@
+use std::{collections::BTreeMap, convert::TryInto};
+
+use bstr::BStr;
+
+pub use error::Error;
+
+use crate::{
+    store::{
+        file::log,
+        reftable::{self, record::LogValue, stack, Table},
+    },
+    FullName,
+};
+
+impl reftable::Store {
+    /// Returns true if a reflog with at least one entry exists for the given reference `name`.
+    pub fn reflog_exists<'a, Name, E>(&self, name: Name) -> Result<bool, Error>
+    where
+        Name: TryInto<FullName<'a>, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        let name = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        Ok(!entries(&self.shared_tables()?, name.as_bstr())?.is_empty())
+    }
+
+    /// Return a reflog reverse iterator for the given fully qualified `name`, traversing log entries from most recent to oldest.
+    ///
+    /// Return `Ok(None)` if no reflog exists.
+    pub fn reflog_iter_rev<'a, Name, E>(
+        &self,
+        name: Name,
+    ) -> Result<Option<std::vec::IntoIter<log::mutable::Line>>, Error>
+    where
+        Name: TryInto<FullName<'a>, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        let name = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        let entries = entries(&self.shared_tables()?, name.as_bstr())?;
+        Ok((!entries.is_empty()).then(|| {
+            entries
+                .into_iter()
+                .map(|(_, line)| line)
+                .collect::<Vec<_>>()
+                .into_iter()
+        }))
+    }
+
+    /// Return a reflog forward iterator for the given fully qualified `name`, traversing log entries from oldest to newest.
+    ///
+    /// Return `Ok(None)` if no reflog exists.
+    pub fn reflog_iter<'a, Name, E>(&self, name: Name) -> Result<Option<std::vec::IntoIter<log::mutable::Line>>, Error>
+    where
+        Name: TryInto<FullName<'a>, Error = E>,
+        crate::name::Error: From<E>,
+    {
+        Ok(self.reflog_iter_rev(name)?.map(|lines| {
+            let mut lines: Vec<_> = lines.collect();
+            lines.reverse();
+            lines.into_iter()
+        }))
+    }
+}
+
+/// Return all log entries of the reference `name` in `tables`, which are ordered from oldest to newest, along with their
+/// update index and starting with the most recent entry.
+pub(crate) fn entries(tables: &[Table], name: &BStr) -> Result<Vec<(u64, log::mutable::Line)>, stack::Error> {
+    let mut entries = BTreeMap::new();
+    for table in tables {
+        for entry in table.logs_of(name)? {
+            entries.insert(entry.update_index, entry.value);
+        }
+    }
+    Ok(entries
+        .into_iter()
+        .rev()
+        .filter_map(|(update_index, value)| match value {
+            LogValue::Update(line) => Some((update_index, line)),
+            LogValue::Deletion => None,
+        })
+        .collect())
+}
+
+mod error {
+    use quick_error::quick_error;
+
+    use crate::store::reftable::stack;
+
+    quick_error! {
+        /// The error returned by [`Store::reflog_iter()`][crate::reftable::Store::reflog_iter()] and related methods.
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            RefnameValidation(err: crate::name::Error) {
+                display("The reflog name or path is not a valid ref name")
+                from()
+                source(err)
+            }
+            Stack(err: stack::Error) {
+                display("The reftables could not be read")
+                from()
+                source(err)
+            }
+        }
+    }
+}
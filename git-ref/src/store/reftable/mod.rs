@@ -0,0 +1,88 @@
+//! A store for references in the [reftable format][reftable-docs], which keeps all references and their logs in a stack of
+//! binary tables in the `reftable` directory.
+//!
+//! Each transaction adds a new table to the stack, and tables are merged automatically to keep their amount low.
+//!
+//! [reftable-docs]: https://git-scm.com/docs/reftable
+use std::path::PathBuf;
+
+use git_hash::ObjectId;
+
+use crate::{mutable, store::file::WriteReflog};
+
+mod block;
+mod varint;
+
+///
+pub mod record;
+
+///
+pub mod table;
+pub use table::Table;
+
+///
+pub mod stack;
+
+///
+pub mod find;
+
+///
+pub mod iter;
+
+///
+pub mod log;
+
+///
+pub mod transaction;
+
+mod reference;
+pub use reference::peel;
+
+/// A store for references which keeps them in a stack of reftables.
+#[derive(Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone)]
+pub struct Store {
+    /// The repository directory whose `reftable` directory contains the tables, typically the `.git` directory.
+    pub base: PathBuf,
+    /// The way to handle reflog edits
+    pub write_reflog: WriteReflog,
+    /// The options to use when writing new tables.
+    pub write_options: table::WriteOptions,
+    /// If true, tables will be merged after each transaction to keep their sizes in a geometric sequence, which
+    /// keeps the amount of tables logarithmic in the amount of transactions.
+    pub auto_compact: bool,
+    /// The tables as loaded most recently, shared among clones of this store.
+    pub(in crate::store::reftable) tables: crate::store::cache::Shared<Vec<Table>>,
+}
+
+/// A reference as found in a [reftable store][Store].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Reference {
+    /// The fully qualified name of the reference.
+    pub name: mutable::FullName,
+    /// The target of the reference.
+    pub target: mutable::Target,
+    /// The object the target ultimately points to if it is an annotated tag and its peeled value was stored.
+    pub peeled: Option<ObjectId>,
+    /// The update index of the transaction which last changed the reference.
+    pub update_index: u64,
+}
+
+impl Store {
+    /// Create a new instance for the repository at `git_dir` with the given way of handling reflogs.
+    ///
+    /// The tables are expected in `git_dir/reftable`, which is created once the first transaction is committed.
+    pub fn at(git_dir: impl Into<PathBuf>, write_reflog: WriteReflog) -> Self {
+        Store {
+            base: git_dir.into(),
+            write_reflog,
+            write_options: Default::default(),
+            auto_compact: true,
+            tables: Default::default(),
+        }
+    }
+
+    /// The directory containing the tables and their list.
+    pub fn reftable_dir(&self) -> PathBuf {
+        self.base.join("reftable")
+    }
+}
@@ -0,0 +1,328 @@
+//! The records stored in the blocks of a reftable, along with their encoding.
+use std::convert::TryFrom;
+
+use bstr::{BString, ByteSlice};
+use git_hash::ObjectId;
+
+use crate::store::reftable::varint;
+
+const HASH_LEN: usize = 20;
+
+/// The value of a [reference record][Ref].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub enum RefValue {
+    /// The reference was deleted, hiding all of its values in older tables.
+    Deletion,
+    /// The reference points to an object.
+    Peeled(ObjectId),
+    /// The reference points to an annotated tag `target` which was peeled to the object `peeled`.
+    PeeledWithObject {
+        /// The object the reference points to.
+        target: ObjectId,
+        /// The object that `target` ultimately points to.
+        peeled: ObjectId,
+    },
+    /// The reference points to another reference by name.
+    Symbolic(BString),
+}
+
+impl RefValue {
+    fn value_type(&self) -> u8 {
+        match self {
+            RefValue::Deletion => 0,
+            RefValue::Peeled(_) => 1,
+            RefValue::PeeledWithObject { .. } => 2,
+            RefValue::Symbolic(_) => 3,
+        }
+    }
+}
+
+/// A reference as stored in a reftable.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Ref {
+    /// The fully qualified name of the reference.
+    pub name: BString,
+    /// The update index of the transaction that last changed the reference.
+    pub update_index: u64,
+    /// The value of the reference.
+    pub value: RefValue,
+}
+
+/// The value of a [log record][Log].
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub enum LogValue {
+    /// The log entry was deleted, hiding the entry with the same update index in older tables.
+    Deletion,
+    /// A change of the reference.
+    Update(crate::store::file::log::mutable::Line),
+}
+
+/// A reflog entry as stored in a reftable.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Log {
+    /// The fully qualified name of the reference the entry belongs to.
+    pub name: BString,
+    /// The update index of the transaction that produced the entry.
+    pub update_index: u64,
+    /// The entry itself.
+    pub value: LogValue,
+}
+
+impl Ref {
+    pub(crate) fn key(&self) -> Vec<u8> {
+        self.name.to_vec()
+    }
+
+    /// Encode the value and return its type, with the update index stored relative to `min_update_index`.
+    pub(crate) fn encode_value(&self, min_update_index: u64, out: &mut Vec<u8>) -> u8 {
+        varint::encode(self.update_index - min_update_index, out);
+        match &self.value {
+            RefValue::Deletion => {}
+            RefValue::Peeled(id) => out.extend_from_slice(id.as_slice()),
+            RefValue::PeeledWithObject { target, peeled } => {
+                out.extend_from_slice(target.as_slice());
+                out.extend_from_slice(peeled.as_slice());
+            }
+            RefValue::Symbolic(name) => encode_string(name, out),
+        }
+        self.value.value_type()
+    }
+
+    pub(crate) fn decode(key: Vec<u8>, value_type: u8, input: &[u8], min_update_index: u64) -> Option<(Self, usize)> {
+        let (delta, mut consumed) = varint::decode(input)?;
+        let input = &input[consumed..];
+        let value = match value_type {
+            0 => RefValue::Deletion,
+            1 => {
+                consumed += HASH_LEN;
+                RefValue::Peeled(decode_id(input)?)
+            }
+            2 => {
+                consumed += 2 * HASH_LEN;
+                RefValue::PeeledWithObject {
+                    target: decode_id(input)?,
+                    peeled: decode_id(input.get(HASH_LEN..)?)?,
+                }
+            }
+            3 => {
+                let (name, len) = decode_string(input)?;
+                consumed += len;
+                RefValue::Symbolic(name)
+            }
+            _ => return None,
+        };
+        Some((
+            Ref {
+                name: key.into(),
+                update_index: min_update_index.checked_add(delta)?,
+                value,
+            },
+            consumed,
+        ))
+    }
+
+    /// Return all object ids the reference points to, for use in the object index.
+    pub(crate) fn ids(&self) -> impl Iterator<Item = &ObjectId> {
+        let (first, second) = match &self.value {
+            RefValue::Peeled(id) => (Some(id), None),
+            RefValue::PeeledWithObject { target, peeled } => (Some(target), Some(peeled)),
+            RefValue::Deletion | RefValue::Symbolic(_) => (None, None),
+        };
+        first.into_iter().chain(second)
+    }
+}
+
+impl Log {
+    /// The key sorts entries by name and from newest to oldest.
+    pub(crate) fn key(&self) -> Vec<u8> {
+        log_key(self.name.as_ref(), self.update_index)
+    }
+
+    /// Encode the value and return its type.
+    ///
+    /// The message is stored with a trailing newline, just like git does.
+    pub(crate) fn encode_value(&self, out: &mut Vec<u8>) -> u8 {
+        match &self.value {
+            LogValue::Deletion => 0,
+            LogValue::Update(line) => {
+                out.extend_from_slice(line.previous_oid.as_slice());
+                out.extend_from_slice(line.new_oid.as_slice());
+                encode_string(&line.signature.name, out);
+                encode_string(&line.signature.email, out);
+                varint::encode(u64::from(line.signature.time.time), out);
+                // Like git, store the offset as the decimal number that would be printed as `±hhmm`.
+                let offset_minutes = line.signature.time.offset.abs() / 60;
+                let mut tz = (offset_minutes / 60 * 100 + offset_minutes % 60) as i16;
+                if line.signature.time.offset < 0 {
+                    tz = -tz;
+                }
+                out.extend_from_slice(&tz.to_be_bytes());
+                let mut message = line.message.clone();
+                message.push(b'\n');
+                encode_string(&message, out);
+                1
+            }
+        }
+    }
+
+    pub(crate) fn decode(key: Vec<u8>, log_type: u8, input: &[u8]) -> Option<(Self, usize)> {
+        let (name, update_index) = split_log_key(&key)?;
+        let (value, consumed) = match log_type {
+            0 => (LogValue::Deletion, 0),
+            1 => {
+                let previous_oid = decode_id(input)?;
+                let new_oid = decode_id(input.get(HASH_LEN..)?)?;
+                let mut consumed = 2 * HASH_LEN;
+                let (name, len) = decode_string(input.get(consumed..)?)?;
+                consumed += len;
+                let (email, len) = decode_string(input.get(consumed..)?)?;
+                consumed += len;
+                let (time, len) = varint::decode(input.get(consumed..)?)?;
+                consumed += len;
+                let tz = i32::from(i16::from_be_bytes([*input.get(consumed)?, *input.get(consumed + 1)?]));
+                consumed += 2;
+                let (mut message, len) = decode_string(input.get(consumed..)?)?;
+                consumed += len;
+                if message.last() == Some(&b'\n') {
+                    message.pop();
+                }
+                let offset = tz.signum() * (tz.abs() / 100 * 3600 + tz.abs() % 100 * 60);
+                (
+                    LogValue::Update(crate::store::file::log::mutable::Line {
+                        previous_oid,
+                        new_oid,
+                        signature: git_actor::Signature {
+                            name,
+                            email,
+                            time: git_actor::Time {
+                                time: u32::try_from(time).ok()?,
+                                offset,
+                                sign: if offset < 0 {
+                                    git_actor::Sign::Minus
+                                } else {
+                                    git_actor::Sign::Plus
+                                },
+                            },
+                        },
+                        message,
+                    }),
+                    consumed,
+                )
+            }
+            _ => return None,
+        };
+        Some((
+            Log {
+                name: name.into(),
+                update_index,
+                value,
+            },
+            consumed,
+        ))
+    }
+}
+
+/// An entry of the object index, mapping a unique prefix of an object id to the positions of all ref blocks
+/// containing references to it.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub(crate) struct Obj {
+    pub prefix: Vec<u8>,
+    pub positions: Vec<u64>,
+}
+
+impl Obj {
+    pub(crate) fn encode_value(&self, out: &mut Vec<u8>) -> u8 {
+        let count = self.positions.len();
+        if count == 0 || count >= 8 {
+            varint::encode(count as u64, out);
+        }
+        let mut last = 0;
+        for (idx, position) in self.positions.iter().enumerate() {
+            varint::encode(if idx == 0 { *position } else { position - last }, out);
+            last = *position;
+        }
+        if count > 0 && count < 8 {
+            count as u8
+        } else {
+            0
+        }
+    }
+
+    pub(crate) fn decode(key: Vec<u8>, count: u8, input: &[u8]) -> Option<(Self, usize)> {
+        let (count, mut consumed) = match count {
+            0 => {
+                let (count, len) = varint::decode(input)?;
+                (count, len)
+            }
+            count => (u64::from(count), 0),
+        };
+        let mut positions = Vec::new();
+        let mut last = 0u64;
+        for idx in 0..count {
+            let (value, len) = varint::decode(input.get(consumed..)?)?;
+            consumed += len;
+            last = if idx == 0 { value } else { last.checked_add(value)? };
+            positions.push(last);
+        }
+        Some((Obj { prefix: key, positions }, consumed))
+    }
+}
+
+/// An entry of an index, pointing to the block at `position` whose last key is `last_key`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub(crate) struct Index {
+    pub last_key: Vec<u8>,
+    pub position: u64,
+}
+
+impl Index {
+    pub(crate) fn encode_value(&self, out: &mut Vec<u8>) -> u8 {
+        varint::encode(self.position, out);
+        0
+    }
+
+    pub(crate) fn decode(key: Vec<u8>, _extra: u8, input: &[u8]) -> Option<(Self, usize)> {
+        let (position, consumed) = varint::decode(input)?;
+        Some((
+            Index {
+                last_key: key,
+                position,
+            },
+            consumed,
+        ))
+    }
+}
+
+/// Return the key for the log entry of `name` at `update_index`.
+pub(crate) fn log_key(name: &[u8], update_index: u64) -> Vec<u8> {
+    let mut key = Vec::with_capacity(name.len() + 9);
+    key.extend_from_slice(name);
+    key.push(0);
+    key.extend_from_slice(&(u64::MAX - update_index).to_be_bytes());
+    key
+}
+
+fn split_log_key(key: &[u8]) -> Option<(&[u8], u64)> {
+    if key.len() < 9 || key[key.len() - 9] != 0 {
+        return None;
+    }
+    let (name, reversed_index) = key.split_at(key.len() - 8);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(reversed_index);
+    Some((&name[..name.len() - 1], u64::MAX - u64::from_be_bytes(bytes)))
+}
+
+fn encode_string(value: &[u8], out: &mut Vec<u8>) {
+    varint::encode(value.len() as u64, out);
+    out.extend_from_slice(value);
+}
+
+fn decode_string(input: &[u8]) -> Option<(BString, usize)> {
+    let (len, consumed) = varint::decode(input)?;
+    let end = consumed.checked_add(len as usize)?;
+    Some((input.get(consumed..end)?.as_bstr().to_owned(), end))
+}
+
+fn decode_id(input: &[u8]) -> Option<ObjectId> {
+    input.get(..HASH_LEN).map(ObjectId::from_20_bytes)
+}
@@ -0,0 +1,70 @@
+use std::collections::BTreeSet;
+
+use git_hash::oid;
+
+use crate::{
+    mutable::Target,
+    store::{reftable, reftable::find::find_in},
+};
+
+impl reftable::Reference {
+    /// Follow all symbolic targets this reference might point to and peel the underlying object id to its final value.
+    ///
+    /// This is a no-op for peeled references, and `self` is replaced with the reference it ultimately points to otherwise.
+    pub fn peel_to_id_in_place(&mut self, store: &reftable::Store) -> Result<&oid, peel::Error> {
+        let tables = store.shared_tables()?;
+        let mut seen = BTreeSet::new();
+        while let Target::Symbolic(name) = &self.target {
+            if !seen.insert(self.name.clone()) {
+                return Err(peel::Error::Cycle {
+                    start_absolute: self.name.as_bstr().to_owned(),
+                });
+            }
+            if seen.len() > peel::MAX_DEPTH {
+                return Err(peel::Error::DepthLimitExceeded {
+                    max_depth: peel::MAX_DEPTH,
+                });
+            }
+            *self = find_in(&tables, name.as_bstr())?.ok_or_else(|| peel::Error::NotFound {
+                name: name.as_bstr().to_owned(),
+            })?;
+        }
+        match &self.target {
+            Target::Peeled(id) => Ok(id),
+            Target::Symbolic(_) => unreachable!("the loop above continues until the target is peeled"),
+        }
+    }
+}
+
+///
+pub mod peel {
+    use bstr::BString;
+    use quick_error::quick_error;
+
+    use crate::store::reftable::stack;
+
+    /// The maximum amount of symbolic references to follow, like git does.
+    pub(crate) const MAX_DEPTH: usize = 5;
+
+    quick_error! {
+        /// The error returned by [`Reference::peel_to_id_in_place()`][crate::reftable::Reference::peel_to_id_in_place()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Stack(err: stack::Error) {
+                display("The reftables could not be read")
+                from()
+                source(err)
+            }
+            NotFound { name: BString } {
+                display("The symbolic reference target '{}' could not be found", name)
+            }
+            Cycle { start_absolute: BString } {
+                display("A reference cycle was detected starting at '{}'", start_absolute)
+            }
+            DepthLimitExceeded { max_depth: usize } {
+                display("Refusing to follow more than {} levels of indirection", max_depth)
+            }
+        }
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    collections::{hash_map::RandomState, BTreeMap},
+    hash::{BuildHasher, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use bstr::BString;
+
+use crate::store::{
+    reftable,
+    reftable::{
+        record::{Log, Ref},
+        table, Table,
+    },
+};
+
+/// The name of the file listing all tables of the stack, from oldest to newest.
+pub const TABLES_LIST: &str = "tables.list";
+
+/// All tables of a stack, from oldest to newest, as shared by all clones of a store.
+pub type SharedTables = Arc<Vec<Table>>;
+
+impl reftable::Store {
+    /// Load all tables of the stack, from oldest to newest, bypassing the cache used by
+    /// [`shared_tables()`][reftable::Store::shared_tables()].
+    pub fn tables(&self) -> Result<Vec<Table>, Error> {
+        let dir = self.reftable_dir();
+        let mut attempts = 0;
+        loop {
+            let tables = read_list(&dir)?
+                .into_iter()
+                .map(|name| Table::at(dir.join(&name)).map_err(|err| (name, err)))
+                .collect::<Result<Vec<_>, _>>();
+            match tables {
+                Ok(tables) => return Ok(tables),
+                // The list may have been changed by a concurrent compaction which removed the tables we were about to read.
+                Err((_, table::decode::Error::Io(err)))
+                    if err.kind() == std::io::ErrorKind::NotFound && attempts < 3 =>
+                {
+                    attempts += 1;
+                }
+                Err((name, err)) => {
+                    return Err(Error::Decode {
+                        err,
+                        path: dir.join(name),
+                    })
+                }
+            }
+        }
+    }
+
+    /// Return all tables of the stack, from oldest to newest.
+    ///
+    /// The tables are cached and only reloaded if the modification time, size or inode of the list of tables changed
+    /// since they were last loaded.
+    pub fn shared_tables(&self) -> Result<SharedTables, Error> {
+        Ok(self
+            .tables
+            .get_or_load(&self.tables_list_path(), || self.tables().map(Some))?
+            .unwrap_or_default())
+    }
+
+    /// Return the path to the file listing all tables of the stack.
+    pub fn tables_list_path(&self) -> PathBuf {
+        self.reftable_dir().join(TABLES_LIST)
+    }
+}
+
+/// Read the names of all tables in the list file in `dir`, which is empty if there is no list yet.
+pub(crate) fn read_list(dir: &Path) -> std::io::Result<Vec<String>> {
+    match std::fs::read_to_string(dir.join(TABLES_LIST)) {
+        Ok(list) => Ok(list.lines().filter(|l| !l.is_empty()).map(ToOwned::to_owned).collect()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err),
+    }
+}
+
+/// Write the given `names` into the locked list file.
+pub(crate) fn write_list(lock: &mut git_lock::File, names: &[String]) -> std::io::Result<()> {
+    lock.with_mut(|out| {
+        for name in names {
+            writeln!(out, "{}", name)?;
+        }
+        Ok(())
+    })
+}
+
+/// Write `data` as new table with the given update index range into `dir` and return its file name.
+pub(crate) fn write_table(
+    dir: &Path,
+    data: &[u8],
+    min_update_index: u64,
+    max_update_index: u64,
+) -> std::io::Result<String> {
+    let suffix = RandomState::new().build_hasher().finish() as u32;
+    let name = format!(
+        "0x{:012x}-0x{:012x}-{:08x}.ref",
+        min_update_index, max_update_index, suffix
+    );
+    let mut lock =
+        git_lock::File::acquire_to_update_resource(dir.join(&name), git_lock::acquire::Fail::Immediately, None)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+    lock.with_mut(|out| out.write_all(data))?;
+    lock.commit().map_err(|err| err.error)?;
+    Ok(name)
+}
+
+/// Return the merged view of all references in `tables`, ordered from oldest to newest, including deletions.
+pub(crate) fn merged_refs(tables: &[Table]) -> Result<BTreeMap<BString, Ref>, table::decode::Error> {
+    let mut refs = BTreeMap::new();
+    for table in tables {
+        for r in table.refs() {
+            let r = r?;
+            refs.insert(r.name.clone(), r);
+        }
+    }
+    Ok(refs)
+}
+
+/// Return the merged view of all log entries in `tables`, ordered from oldest to newest, by their key and including deletions.
+pub(crate) fn merged_logs(tables: &[Table]) -> Result<BTreeMap<Vec<u8>, Log>, table::decode::Error> {
+    let mut logs = BTreeMap::new();
+    for table in tables {
+        for log in table.logs() {
+            let log = log?;
+            logs.insert(log.key(), log);
+        }
+    }
+    Ok(logs)
+}
+
+/// Return the range of tables with the given `sizes` to merge to restore a geometric sequence of table sizes, with each
+/// table being at least twice as large as the next newer one, like git does.
+fn suggest_compaction(sizes: &[u64]) -> Option<std::ops::RangeInclusive<usize>> {
+    const FACTOR: u64 = 2;
+    let mut idx = sizes.len().checked_sub(1)?;
+    let (end, mut bytes) = loop {
+        if idx == 0 {
+            return None;
+        }
+        if sizes[idx - 1] < sizes[idx] * FACTOR {
+            break (idx, sizes[idx]);
+        }
+        idx -= 1;
+    };
+    let mut start = None;
+    while idx > 0 {
+        let current = bytes;
+        bytes += sizes[idx - 1];
+        if sizes[idx - 1] < current * FACTOR {
+            start = Some(idx - 1);
+        }
+        idx -= 1;
+    }
+    start.map(|start| start..=end)
+}
+
+///
+pub mod compact {
+    use std::ops::RangeInclusive;
+
+    use super::{merged_logs, merged_refs, read_list, suggest_compaction, write_list, write_table};
+    use crate::store::{
+        reftable,
+        reftable::{
+            record::{LogValue, RefValue},
+            table, Table,
+        },
+    };
+
+    impl reftable::Store {
+        /// Merge all tables of the stack into a single one, dropping deleted references and log entries, and fail
+        /// according to `lock_mode` if the stack can't be locked.
+        ///
+        /// Returns the amount of tables that were merged.
+        pub fn compact(&self, lock_mode: git_lock::acquire::Fail) -> Result<usize, Error> {
+            self.compact_with(lock_mode, |sizes| (sizes.len() > 1).then(|| 0..=sizes.len() - 1))
+        }
+
+        /// Merge tables of the stack as needed so that each table is at least twice as large as the next newer one,
+        /// which keeps the amount of tables logarithmic, and fail according to `lock_mode` if the stack can't be locked.
+        ///
+        /// Returns the amount of tables that were merged.
+        pub fn auto_compact(&self, lock_mode: git_lock::acquire::Fail) -> Result<usize, Error> {
+            self.compact_with(lock_mode, suggest_compaction)
+        }
+
+        fn compact_with(
+            &self,
+            lock_mode: git_lock::acquire::Fail,
+            choose: impl FnOnce(&[u64]) -> Option<RangeInclusive<usize>>,
+        ) -> Result<usize, Error> {
+            let dir = self.reftable_dir();
+            let mut lock = git_lock::File::acquire_to_update_resource(self.tables_list_path(), lock_mode, None)?;
+            let mut names = read_list(&dir)?;
+            let tables = names
+                .iter()
+                .map(|name| {
+                    Table::at(dir.join(name)).map_err(|err| Error::Decode {
+                        err,
+                        path: dir.join(name),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let sizes: Vec<_> = tables.iter().map(|t| t.size() as u64).collect();
+            let range = match choose(&sizes) {
+                Some(range) if range.end() > range.start() => range,
+                _ => return Ok(0),
+            };
+
+            // Deletions only need to be kept if there are older tables whose records they hide.
+            let keep_deletions = *range.start() > 0;
+            let merged = &tables[range.clone()];
+            let refs: Vec<_> = merged_refs(merged)
+                .map_err(|err| Error::Decode { err, path: dir.clone() })?
+                .into_iter()
+                .map(|(_, r)| r)
+                .filter(|r| keep_deletions || r.value != RefValue::Deletion)
+                .collect();
+            let logs: Vec<_> = merged_logs(merged)
+                .map_err(|err| Error::Decode { err, path: dir.clone() })?
+                .into_iter()
+                .map(|(_, log)| log)
+                .filter(|log| keep_deletions || log.value != LogValue::Deletion)
+                .collect();
+            let (min_update_index, max_update_index) = (
+                merged[0].min_update_index(),
+                merged[merged.len() - 1].max_update_index(),
+            );
+            let data = table::write(&refs, &logs, min_update_index, max_update_index, self.write_options)?;
+            let name = write_table(&dir, &data, min_update_index, max_update_index)?;
+
+            let obsolete: Vec<_> = names.splice(range.clone(), std::iter::once(name)).collect();
+            write_list(&mut lock, &names)?;
+            lock.commit().map_err(|err| err.error)?;
+            for name in obsolete {
+                // Readers that still have the list open will retry, and leftover files are harmless.
+                std::fs::remove_file(dir.join(name)).ok();
+            }
+            Ok(range.end() - range.start() + 1)
+        }
+    }
+
+    mod error {
+        use std::path::PathBuf;
+
+        use quick_error::quick_error;
+
+        use crate::store::reftable::table;
+
+        quick_error! {
+            /// The error returned by [`Store::compact()`][crate::reftable::Store::compact()] and
+            /// [`Store::auto_compact()`][crate::reftable::Store::auto_compact()].
+            #[derive(Debug)]
+            #[allow(missing_docs)]
+            pub enum Error {
+                LockAcquire(err: git_lock::acquire::Error) {
+                    display("The list of tables could not be locked")
+                    from()
+                    source(err)
+                }
+                Io(err: std::io::Error) {
+                    display("An IO error occurred while compacting tables")
+                    from()
+                    source(err)
+                }
+                Decode { err: table::decode::Error, path: PathBuf } {
+                    display("The table at '{}' could not be read", path.display())
+                    source(err)
+                }
+                Write(err: table::write::Error) {
+                    display("The compacted table could not be written")
+                    from()
+                    source(err)
+                }
+            }
+        }
+    }
+    pub use error::Error;
+}
+
+mod error {
+    use std::path::PathBuf;
+
+    use quick_error::quick_error;
+
+    use crate::store::reftable::table;
+
+    quick_error! {
+        /// The error returned when loading the tables of a [`Store`][crate::reftable::Store].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io(err: std::io::Error) {
+                display("The list of tables could not be read")
+                from()
+                source(err)
+            }
+            Decode { err: table::decode::Error, path: PathBuf } {
+                display("The table at '{}' could not be read", path.display())
+                source(err)
+            }
+            Corrupt(err: table::decode::Error) {
+                display("The records of a table could not be decoded")
+                from()
+                source(err)
+            }
+        }
+    }
+}
+pub use error::Error;
+
+#[cfg(test)]
+mod tests {
+    use super::suggest_compaction;
+
+    #[test]
+    fn geometric_sequences_need_no_compaction() {
+        assert_eq!(suggest_compaction(&[]), None);
+        assert_eq!(suggest_compaction(&[5]), None);
+        assert_eq!(suggest_compaction(&[64, 32, 16, 8, 4, 2]), None);
+    }
+
+    #[test]
+    fn the_smallest_segment_restoring_the_sequence_is_suggested() {
+        assert_eq!(
+            suggest_compaction(&[512, 64, 17, 16, 9, 9, 9, 16, 2, 16]),
+            Some(1..=9),
+            "the same result as git produces"
+        );
+        assert_eq!(suggest_compaction(&[128, 32, 16, 8, 4, 3, 1]), Some(1..=5));
+        assert_eq!(suggest_compaction(&[1, 1]), Some(0..=1));
+        assert_eq!(suggest_compaction(&[10, 1, 1]), Some(1..=2));
+    }
+}
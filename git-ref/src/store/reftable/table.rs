@@ -0,0 +1,612 @@
+use std::{collections::BTreeMap, path::Path};
+
+use bstr::{BStr, ByteSlice};
+use git_hash::{oid, ObjectId};
+
+use crate::store::reftable::{
+    block::{self, Block},
+    record::{self, Log, Ref},
+};
+
+pub(crate) const HEADER_LEN: usize = 24;
+pub(crate) const FOOTER_LEN: usize = 68;
+const MAGIC: &[u8] = b"REFT";
+const VERSION: u8 = 1;
+
+/// If a section has more blocks than this, an index is written for it.
+const INDEX_THRESHOLD: usize = 3;
+
+/// The options used when writing tables.
+#[derive(Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
+pub struct WriteOptions {
+    /// The size of all blocks but log blocks, which is also the maximum size of uncompressed log blocks.
+    pub block_size: u32,
+    /// The amount of records after which a record is stored with its full key to allow binary searches.
+    pub restart_interval: u16,
+    /// If true, write an index from object ids to the ref blocks referring to them, to quickly find all references pointing
+    /// to a given object.
+    pub index_objects: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            block_size: 4096,
+            restart_interval: 16,
+            index_objects: true,
+        }
+    }
+}
+
+/// A single reftable, held in memory.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Table {
+    data: Vec<u8>,
+    block_size: usize,
+    min_update_index: u64,
+    max_update_index: u64,
+    ref_index_position: u64,
+    obj_position: u64,
+    obj_id_len: usize,
+    obj_index_position: u64,
+    log_position: u64,
+    log_index_position: u64,
+}
+
+fn be64(bytes: &[u8]) -> u64 {
+    let mut buf = [0; 8];
+    buf.copy_from_slice(&bytes[..8]);
+    u64::from_be_bytes(buf)
+}
+
+/// Reading
+impl Table {
+    /// Read the table at `path`.
+    pub fn at(path: impl AsRef<Path>) -> Result<Self, decode::Error> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Decode the table in `data` after verifying its header and footer.
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, decode::Error> {
+        if data.len() < HEADER_LEN + FOOTER_LEN || &data[..4] != MAGIC {
+            return Err(decode::Error::Header);
+        }
+        if data[4] != VERSION {
+            return Err(decode::Error::UnsupportedVersion(data[4]));
+        }
+        let footer = &data[data.len() - FOOTER_LEN..];
+        if footer[..HEADER_LEN] != data[..HEADER_LEN] {
+            return Err(decode::Error::Footer);
+        }
+        let crc = u32::from_be_bytes([footer[64], footer[65], footer[66], footer[67]]);
+        if git_features::hash::crc32(&footer[..64]) != crc {
+            return Err(decode::Error::Checksum);
+        }
+        let obj = be64(&footer[32..]);
+        Ok(Table {
+            block_size: (usize::from(data[5]) << 16) | (usize::from(data[6]) << 8) | usize::from(data[7]),
+            min_update_index: be64(&data[8..]),
+            max_update_index: be64(&data[16..]),
+            ref_index_position: be64(&footer[24..]),
+            obj_position: obj >> 5,
+            obj_id_len: (obj & 31) as usize,
+            obj_index_position: be64(&footer[40..]),
+            log_position: be64(&footer[48..]),
+            log_index_position: be64(&footer[56..]),
+            data,
+        })
+    }
+
+    /// The smallest update index of all records in this table.
+    pub fn min_update_index(&self) -> u64 {
+        self.min_update_index
+    }
+
+    /// The greatest update index of all records in this table.
+    pub fn max_update_index(&self) -> u64 {
+        self.max_update_index
+    }
+
+    /// The amount of bytes used by the records of the table, without header and footer.
+    pub(crate) fn size(&self) -> usize {
+        self.data.len() - HEADER_LEN - FOOTER_LEN
+    }
+
+    /// Return an iterator over all references in the table in the order of their names, including deletions.
+    pub fn refs(&self) -> Iter<'_, Ref> {
+        self.iter(block::typ::REF, self.ref_section(), None, decode_ref)
+    }
+
+    /// Return the reference with the fully qualified `name`, which may be a deletion, or `None` if it isn't in the table.
+    pub fn find_ref(&self, name: &BStr) -> Result<Option<Ref>, decode::Error> {
+        let start = self.seek_position(self.ref_section(), self.ref_index_position, name)?;
+        match self.iter(block::typ::REF, start, Some(name), decode_ref).next() {
+            Some(Ok(r)) if r.name == name => Ok(Some(r)),
+            Some(Err(err)) => Err(err),
+            Some(Ok(_)) | None => Ok(None),
+        }
+    }
+
+    /// Return all references pointing to `id` either directly or once peeled.
+    pub fn refs_pointing_to(&self, id: &oid) -> Result<Vec<Ref>, decode::Error> {
+        let points_to_id = |r: &Ref| r.ids().any(|candidate| candidate.as_ref() == id);
+        if self.obj_position == 0 || self.obj_id_len > id.as_bytes().len() {
+            return self.refs().filter(|r| r.as_ref().map_or(true, points_to_id)).collect();
+        }
+        let prefix = &id.as_bytes()[..self.obj_id_len];
+        let start = self.seek_position(
+            Some(self.obj_position as usize),
+            self.obj_index_position,
+            prefix.as_bstr(),
+        )?;
+        let obj = match self
+            .iter(block::typ::OBJ, start, Some(prefix.as_bstr()), decode_obj)
+            .next()
+        {
+            Some(Ok(obj)) if obj.prefix == prefix => obj,
+            Some(Err(err)) => return Err(err),
+            Some(Ok(_)) | None => return Ok(Vec::new()),
+        };
+        if obj.positions.is_empty() {
+            return self.refs().filter(|r| r.as_ref().map_or(true, points_to_id)).collect();
+        }
+        let mut out = Vec::new();
+        for position in obj.positions {
+            let block = self
+                .block_at(position as usize)?
+                .ok_or(decode::Error::Corrupt { position })?;
+            let min_update_index = self.min_update_index;
+            let refs = block
+                .records(None, |key, extra, input| {
+                    Ref::decode(key, extra, input, min_update_index)
+                })
+                .map_err(|_| decode::Error::Corrupt { position })?;
+            out.extend(refs.into_iter().map(|(_, r)| r).filter(points_to_id));
+        }
+        Ok(out)
+    }
+
+    /// Return an iterator over all log entries in the table, ordered by reference name and from newest to oldest.
+    pub fn logs(&self) -> Iter<'_, Log> {
+        self.iter(block::typ::LOG, self.log_section(), None, decode_log)
+    }
+
+    /// Return all log entries of the reference with the fully qualified `name`, from newest to oldest.
+    pub fn logs_of(&self, name: &BStr) -> Result<Vec<Log>, decode::Error> {
+        let seek = record::log_key(name, u64::MAX);
+        let start = self.seek_position(self.log_section(), self.log_index_position, seek.as_bstr())?;
+        let mut out = Vec::new();
+        for log in self.iter(block::typ::LOG, start, Some(seek.as_bstr()), decode_log) {
+            let log = log?;
+            if log.name != name {
+                break;
+            }
+            out.push(log);
+        }
+        Ok(out)
+    }
+
+    fn first_block_type(&self) -> Option<u8> {
+        if self.data.len() > HEADER_LEN + FOOTER_LEN {
+            Some(self.data[HEADER_LEN])
+        } else {
+            None
+        }
+    }
+
+    fn ref_section(&self) -> Option<usize> {
+        (self.first_block_type() == Some(block::typ::REF)).then(|| 0)
+    }
+
+    fn log_section(&self) -> Option<usize> {
+        if self.first_block_type() == Some(block::typ::LOG) {
+            Some(0)
+        } else if self.log_position > 0 {
+            Some(self.log_position as usize)
+        } else {
+            None
+        }
+    }
+
+    fn block_at(&self, pos: usize) -> Result<Option<Block<'_>>, decode::Error> {
+        let header_len = if pos == 0 { HEADER_LEN } else { 0 };
+        Block::at(
+            &self.data[..self.data.len() - FOOTER_LEN],
+            pos,
+            header_len,
+            self.block_size,
+        )
+        .map_err(|_| decode::Error::Corrupt { position: pos as u64 })
+    }
+
+    /// Use the index at `index_position` to find the position of the block that may contain `key`, or return `section`
+    /// if there is no index.
+    fn seek_position(
+        &self,
+        section: Option<usize>,
+        index_position: u64,
+        key: &BStr,
+    ) -> Result<Option<usize>, decode::Error> {
+        if index_position == 0 || section.is_none() {
+            return Ok(section);
+        }
+        let mut pos = index_position as usize;
+        loop {
+            let target = loop {
+                let block = match self.block_at(pos)? {
+                    Some(block) if block.typ == block::typ::INDEX => block,
+                    _ => return Ok(None),
+                };
+                let records = block
+                    .records(Some(key), record::Index::decode)
+                    .map_err(|_| decode::Error::Corrupt { position: pos as u64 })?;
+                match records
+                    .into_iter()
+                    .find(|(last_key, _)| last_key.as_slice() >= key.as_bytes())
+                {
+                    Some((_, index)) => break index.position as usize,
+                    None => pos += block.full_len,
+                }
+            };
+            match self.block_at(target)? {
+                Some(block) if block.typ == block::typ::INDEX => pos = target,
+                Some(_) => return Ok(Some(target)),
+                None => {
+                    return Err(decode::Error::Corrupt {
+                        position: target as u64,
+                    })
+                }
+            }
+        }
+    }
+
+    fn iter<'a, T>(&'a self, typ: u8, start: Option<usize>, seek: Option<&BStr>, decode: Decode<T>) -> Iter<'a, T> {
+        Iter {
+            table: self,
+            typ,
+            next_block: start,
+            seek: seek.map(|key| key.to_vec()),
+            records: Vec::new().into_iter(),
+            decode,
+        }
+    }
+}
+
+type Decode<T> = fn(Vec<u8>, u8, &[u8], u64) -> Option<(T, usize)>;
+
+fn decode_ref(key: Vec<u8>, extra: u8, input: &[u8], min_update_index: u64) -> Option<(Ref, usize)> {
+    Ref::decode(key, extra, input, min_update_index)
+}
+
+fn decode_log(key: Vec<u8>, extra: u8, input: &[u8], _min_update_index: u64) -> Option<(Log, usize)> {
+    Log::decode(key, extra, input)
+}
+
+fn decode_obj(key: Vec<u8>, extra: u8, input: &[u8], _min_update_index: u64) -> Option<(record::Obj, usize)> {
+    record::Obj::decode(key, extra, input)
+}
+
+/// An iterator over the records of one section of a [`Table`], decoding one block at a time.
+pub struct Iter<'a, T> {
+    table: &'a Table,
+    typ: u8,
+    next_block: Option<usize>,
+    seek: Option<Vec<u8>>,
+    records: std::vec::IntoIter<(Vec<u8>, T)>,
+    decode: Decode<T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = Result<T, decode::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((_, record)) = self.records.next() {
+                return Some(Ok(record));
+            }
+            let pos = self.next_block.take()?;
+            let block = match self.table.block_at(pos) {
+                Ok(Some(block)) if block.typ == self.typ => block,
+                Ok(_) => return None,
+                Err(err) => return Some(Err(err)),
+            };
+            let (decode, min_update_index) = (self.decode, self.table.min_update_index);
+            let seek = self.seek.take();
+            let mut records = match block.records(seek.as_deref(), |key, extra, input| {
+                decode(key, extra, input, min_update_index)
+            }) {
+                Ok(records) => records,
+                Err(_) => return Some(Err(decode::Error::Corrupt { position: pos as u64 })),
+            };
+            if let Some(seek) = seek {
+                records.retain(|(key, _)| key.as_slice() >= seek.as_slice());
+            }
+            self.next_block = Some(pos + block.full_len);
+            self.records = records.into_iter();
+        }
+    }
+}
+
+/// Write a table with the given `refs` and `logs`, each sorted by their key, and return its bytes.
+///
+/// The update indices of all `refs` must be within `min_update_index` and `max_update_index`.
+pub fn write(
+    refs: &[Ref],
+    logs: &[Log],
+    min_update_index: u64,
+    max_update_index: u64,
+    options: WriteOptions,
+) -> Result<Vec<u8>, write::Error> {
+    let block_size = options.block_size as usize;
+    if !(256..=block::MAX_SIZE).contains(&block_size) {
+        return Err(write::Error::BlockSize(options.block_size));
+    }
+    let mut header = [0u8; HEADER_LEN];
+    header[..4].copy_from_slice(MAGIC);
+    header[4] = VERSION;
+    block::put_be24(block_size, &mut header[5..]);
+    header[8..16].copy_from_slice(&min_update_index.to_be_bytes());
+    header[16..24].copy_from_slice(&max_update_index.to_be_bytes());
+
+    let mut w = Writer {
+        out: Vec::new(),
+        next: 0,
+        pending_padding: 0,
+        header,
+        options,
+        index: Vec::new(),
+        block: None,
+    };
+
+    let mut objects = BTreeMap::<ObjectId, Vec<u64>>::new();
+    let mut last_key = None::<Vec<u8>>;
+    for r in refs {
+        if r.update_index < min_update_index || r.update_index > max_update_index {
+            return Err(write::Error::UpdateIndexOutOfRange {
+                name: r.name.clone(),
+                update_index: r.update_index,
+            });
+        }
+        let key = r.key();
+        check_order(&mut last_key, &key)?;
+        let mut value = Vec::new();
+        let value_type = r.encode_value(min_update_index, &mut value);
+        w.add(block::typ::REF, &key, value_type, &value)?;
+        if options.index_objects {
+            for id in r.ids() {
+                let positions = objects.entry(*id).or_default();
+                if positions.last() != Some(&w.next) {
+                    positions.push(w.next);
+                }
+            }
+        }
+    }
+    let ref_index_position = w.finish_section()?;
+
+    let (mut obj_position, mut obj_id_len, mut obj_index_position) = (0, 0, 0);
+    if ref_index_position > 0 && !objects.is_empty() {
+        let ids: Vec<_> = objects.keys().collect();
+        obj_id_len = ids
+            .windows(2)
+            .map(|ids| {
+                ids[0]
+                    .as_slice()
+                    .iter()
+                    .zip(ids[1].as_slice())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            })
+            .max()
+            .unwrap_or(0)
+            .max(1)
+            + 1;
+        obj_position = w.next;
+        for (id, positions) in &objects {
+            let obj = record::Obj {
+                prefix: id.as_slice()[..obj_id_len].to_vec(),
+                positions: positions.clone(),
+            };
+            let mut value = Vec::new();
+            let count = obj.encode_value(&mut value);
+            w.add(block::typ::OBJ, &obj.prefix, count, &value)?;
+        }
+        obj_index_position = w.finish_section()?;
+    }
+
+    let (mut log_position, mut log_index_position) = (0, 0);
+    if !logs.is_empty() {
+        // Log blocks aren't aligned, so they follow the last block without padding.
+        w.next -= w.pending_padding as u64;
+        w.pending_padding = 0;
+        log_position = w.next;
+        let mut last_key = None;
+        for log in logs {
+            let key = log.key();
+            check_order(&mut last_key, &key)?;
+            let mut value = Vec::new();
+            let log_type = log.encode_value(&mut value);
+            w.add(block::typ::LOG, &key, log_type, &value)?;
+        }
+        log_index_position = w.finish_section()?;
+    }
+
+    let mut out = w.out;
+    if out.is_empty() {
+        out.extend_from_slice(&header);
+    }
+    let footer_start = out.len();
+    out.extend_from_slice(&header);
+    for value in &[
+        ref_index_position,
+        (obj_position << 5) | obj_id_len as u64,
+        obj_index_position,
+        log_position,
+        log_index_position,
+    ] {
+        out.extend_from_slice(&value.to_be_bytes());
+    }
+    let crc = git_features::hash::crc32(&out[footer_start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+    Ok(out)
+}
+
+fn check_order(last_key: &mut Option<Vec<u8>>, key: &[u8]) -> Result<(), write::Error> {
+    if let Some(last_key) = last_key.as_ref() {
+        if last_key.as_slice() >= key {
+            return Err(write::Error::Unsorted { key: key.into() });
+        }
+    }
+    *last_key = Some(key.to_vec());
+    Ok(())
+}
+
+struct Writer {
+    out: Vec<u8>,
+    /// The position at which the next block will be written, which includes the padding of the previous block.
+    next: u64,
+    /// Padding is only written if another block follows.
+    pending_padding: usize,
+    header: [u8; HEADER_LEN],
+    options: WriteOptions,
+    /// Index records for all blocks written in the current section.
+    index: Vec<record::Index>,
+    block: Option<block::Writer>,
+}
+
+impl Writer {
+    fn new_block(&self, typ: u8) -> block::Writer {
+        block::Writer::new(
+            typ,
+            if self.next == 0 { HEADER_LEN } else { 0 },
+            self.options.block_size as usize,
+            self.options.restart_interval as usize,
+        )
+    }
+
+    fn add(&mut self, typ: u8, key: &[u8], extra: u8, value: &[u8]) -> Result<(), write::Error> {
+        if self.block.is_none() {
+            self.block = Some(self.new_block(typ));
+        }
+        if !self.block.as_mut().expect("just set").add(key, extra, value) {
+            self.flush_block()?;
+            let mut block = self.new_block(typ);
+            assert!(block.add(key, extra, value), "the first record always fits");
+            self.block = Some(block);
+        }
+        Ok(())
+    }
+
+    fn flush_block(&mut self) -> Result<(), write::Error> {
+        let block = match self.block.take() {
+            Some(block) if !block.is_empty() => block,
+            _ => return Ok(()),
+        };
+        let last_key = block.last_key().to_vec();
+        let typ = block.typ();
+        let mut raw = block.finish()?;
+        if self.next == 0 {
+            raw[..HEADER_LEN].copy_from_slice(&self.header);
+        }
+        let block_size = self.options.block_size as usize;
+        let padding = if typ == block::typ::LOG {
+            0
+        } else {
+            block_size
+                .checked_sub(raw.len())
+                .ok_or_else(|| write::Error::RecordTooLarge {
+                    key: last_key.clone().into(),
+                })?
+        };
+        self.out.resize(self.out.len() + self.pending_padding, 0);
+        self.pending_padding = padding;
+        self.index.push(record::Index {
+            last_key,
+            position: self.next,
+        });
+        self.out.extend_from_slice(&raw);
+        self.next += (raw.len() + padding) as u64;
+        Ok(())
+    }
+
+    /// Flush the current block and write a possibly multi-level index if there are enough blocks in the section.
+    /// Return the position of the highest level of the index or 0 if there is none.
+    fn finish_section(&mut self) -> Result<u64, write::Error> {
+        self.flush_block()?;
+        let mut index_position = 0;
+        while self.index.len() > INDEX_THRESHOLD {
+            index_position = self.next;
+            for index in std::mem::take(&mut self.index) {
+                let mut value = Vec::new();
+                let extra = index.encode_value(&mut value);
+                self.add(block::typ::INDEX, &index.last_key, extra, &value)?;
+            }
+            self.flush_block()?;
+        }
+        self.index.clear();
+        Ok(index_position)
+    }
+}
+
+///
+pub mod decode {
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned when reading a [`Table`][super::Table].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io(err: std::io::Error) {
+                display("The table could not be read")
+                from()
+                source(err)
+            }
+            Header {
+                display("The table header is invalid")
+            }
+            UnsupportedVersion(version: u8) {
+                display("Reftable version {} is not supported", version)
+            }
+            Footer {
+                display("The table footer does not match its header")
+            }
+            Checksum {
+                display("The checksum of the table footer did not match")
+            }
+            Corrupt { position: u64 } {
+                display("The block at position {} could not be decoded", position)
+            }
+        }
+    }
+}
+
+///
+pub mod write {
+    use bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`write()`][super::write()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io(err: std::io::Error) {
+                display("A block could not be encoded")
+                from()
+                source(err)
+            }
+            BlockSize(size: u32) {
+                display("The block size {} is not between 256 bytes and 16MB", size)
+            }
+            Unsorted { key: BString } {
+                display("The record with key '{}' isn't sorted after its predecessor", key)
+            }
+            UpdateIndexOutOfRange { name: BString, update_index: u64 } {
+                display("The update index {} of reference '{}' is out of range for the table", update_index, name)
+            }
+            RecordTooLarge { key: BString } {
+                display("The record with key '{}' does not fit into a single block", key)
+            }
+        }
+    }
+}
@@ -0,0 +1,575 @@
+use std::collections::BTreeMap;
+
+use bstr::{BStr, BString, ByteSlice};
+use git_hash::ObjectId;
+
+use crate::{
+    mutable::{FullName, Target},
+    store::{
+        file::{log, WriteReflog},
+        reftable::{
+            self,
+            find::find_in,
+            record::{self, LogValue, RefValue},
+            stack, table, Table,
+        },
+    },
+    transaction::{Change, Create, RefEdit, RefEditsExt, RefLog},
+};
+
+#[derive(Debug)]
+struct Edit {
+    update: RefEdit,
+    /// Set if this update is coming from a symbolic reference and used to make it appear like it is the one that is handled,
+    /// instead of the referent reference.
+    parent_index: Option<usize>,
+    /// Set if this update was added for the rename or copy at the given index, either to create the reference with the
+    /// new name or to update a symbolic reference pointing to the renamed one.
+    source_index: Option<usize>,
+    /// For symbolic refs, this is the previous OID to put into the reflog instead of our own previous value. It's the
+    /// peeled value of the leaf referent.
+    leaf_referent_previous_oid: Option<ObjectId>,
+}
+
+impl Edit {
+    fn name(&self) -> BString {
+        self.update.name.0.clone()
+    }
+}
+
+/// Return the name of the reference renamed or copied to the name of the edit at `index`, along with the id it points to,
+/// if the edit creates the new reference of a rename or copy.
+fn relocation_source(updates: &[Edit], index: usize) -> Option<(&FullName, ObjectId, bool)> {
+    let edit = &updates[index];
+    let source = &updates[edit.source_index?];
+    let (new_name, previous, is_rename) = match &source.update.change {
+        Change::Rename { new_name, previous, .. } => (new_name, previous, true),
+        Change::Copy { new_name, previous, .. } => (new_name, previous, false),
+        Change::Update { .. } | Change::Delete { .. } => return None,
+    };
+    match previous {
+        Some(Target::Peeled(oid)) if *new_name == edit.update.name => Some((&source.update.name, *oid, is_rename)),
+        _ => None,
+    }
+}
+
+/// Return the name of a reference in `refs` which prevents a reference named `name` from being created as one would be the
+/// directory of the other, ignoring the reference named `ignore`.
+fn name_conflict(refs: &BTreeMap<BString, record::Ref>, name: &BStr, ignore: Option<&BStr>) -> Option<BString> {
+    let is_ignored = |candidate: &BStr| ignore.map_or(false, |ignore| ignore == candidate);
+    let exists = |candidate: &BStr| {
+        refs.get(candidate)
+            .map_or(false, |r| r.value != RefValue::Deletion && !is_ignored(candidate))
+    };
+    let first_component_end = name.find_byte(b'/').unwrap_or(name.len());
+    if let Some(parent) = (first_component_end + 1..name.len())
+        .filter(|pos| name[*pos] == b'/')
+        .map(|pos| name[..pos].as_bstr())
+        .find(|parent| exists(parent))
+    {
+        return Some(parent.to_owned());
+    }
+    let mut prefix = name.to_owned();
+    prefix.push(b'/');
+    refs.range(prefix.clone()..)
+        .take_while(|(child, _)| child.starts_with(&prefix))
+        .find(|(child, _)| exists(child.as_bstr()))
+        .map(|(child, _)| child.clone())
+}
+
+impl std::borrow::Borrow<RefEdit> for Edit {
+    fn borrow(&self) -> &RefEdit {
+        &self.update
+    }
+}
+
+impl std::borrow::BorrowMut<RefEdit> for Edit {
+    fn borrow_mut(&mut self) -> &mut RefEdit {
+        &mut self.update
+    }
+}
+
+/// A transaction on a reftable store, which adds a single table to its stack when committed.
+pub struct Transaction<'a> {
+    store: &'a reftable::Store,
+    updates: Vec<Edit>,
+    state: State,
+    lock_fail_mode: git_lock::acquire::Fail,
+    /// The lock on the list of tables, held from preparation until the commit.
+    lock: Option<git_lock::File>,
+    /// The tables as seen while holding the lock.
+    tables: Vec<Table>,
+}
+
+impl<'a> Transaction<'a> {
+    fn apply_change(tables: &[Table], change: &mut Edit) -> Result<(), Error> {
+        let existing_ref = find_in(tables, change.update.name.as_bstr())?;
+        match &mut change.update.change {
+            Change::Delete { previous, .. } => {
+                match (&previous, &existing_ref) {
+                    (None, None | Some(_)) => {}
+                    (Some(_previous), None) => {
+                        return Err(Error::DeleteReferenceMustExist {
+                            full_name: change.name(),
+                        })
+                    }
+                    (Some(previous), Some(existing)) => {
+                        if !previous.is_null() && *previous != existing.target {
+                            let expected = previous.clone();
+                            return Err(Error::ReferenceOutOfDate {
+                                full_name: change.name(),
+                                expected,
+                                actual: existing.target.clone(),
+                            });
+                        }
+                    }
+                }
+
+                // Keep the previous value for the caller and ourselves. Maybe they want to keep a log of sorts.
+                if let Some(existing) = existing_ref {
+                    *previous = Some(existing.target);
+                }
+            }
+            Change::Rename { previous, .. } | Change::Copy { previous, .. } => {
+                let existing = match existing_ref {
+                    Some(existing) => existing,
+                    None => {
+                        return Err(Error::SourceMustExist {
+                            full_name: change.name(),
+                        })
+                    }
+                };
+                if let Target::Symbolic(_) = existing.target {
+                    return Err(Error::SymbolicSource {
+                        full_name: change.name(),
+                    });
+                }
+                if let Some(previous) = previous {
+                    if !previous.is_null() && *previous != existing.target {
+                        let expected = previous.clone();
+                        return Err(Error::ReferenceOutOfDate {
+                            full_name: change.name(),
+                            expected,
+                            actual: existing.target,
+                        });
+                    }
+                }
+                *previous = Some(existing.target);
+            }
+            Change::Update {
+                mode: previous, new, ..
+            } => {
+                match (&previous, &existing_ref) {
+                    (Create::Only, Some(existing)) if existing.target != *new => {
+                        let new = new.clone();
+                        return Err(Error::MustNotExist {
+                            full_name: change.name(),
+                            actual: existing.target.clone(),
+                            new,
+                        });
+                    }
+                    (
+                        Create::OrUpdate {
+                            previous: Some(previous),
+                        },
+                        Some(existing),
+                    ) => match previous {
+                        Target::Peeled(oid) if oid.is_null() => {}
+                        any_target if *any_target == existing.target => {}
+                        _target_mismatch => {
+                            let actual = existing.target.clone();
+                            let expected = previous.to_owned();
+                            let full_name = change.name();
+                            return Err(Error::ReferenceOutOfDate {
+                                full_name,
+                                actual,
+                                expected,
+                            });
+                        }
+                    },
+                    (
+                        Create::OrUpdate {
+                            previous: Some(previous),
+                        },
+                        None,
+                    ) => {
+                        let expected = previous.to_owned();
+                        let full_name = change.name();
+                        return Err(Error::MustExist { full_name, expected });
+                    }
+                    (Create::Only | Create::OrUpdate { previous: None }, None | Some(_)) => {}
+                };
+
+                *previous = match existing_ref {
+                    None => Create::Only,
+                    Some(existing) => Create::OrUpdate {
+                        previous: Some(existing.target),
+                    },
+                };
+            }
+        };
+        Ok(())
+    }
+}
+
+impl<'a> Transaction<'a> {
+    /// Discard the transaction and re-obtain the initial edits
+    pub fn into_edits(self) -> Vec<RefEdit> {
+        self.updates.into_iter().map(|e| e.update).collect()
+    }
+
+    /// Prepare for calling [`commit(…)`][Transaction::commit()] by locking the stack of tables and validating all edits
+    /// against its current state.
+    ///
+    /// If the operation succeeds, the transaction can be committed or dropped to cause a rollback automatically.
+    /// This method is idempotent.
+    pub fn prepare(mut self) -> Result<Self, Error> {
+        Ok(match self.state {
+            State::Prepared => self,
+            State::Open => {
+                let store = self.store;
+                self.updates
+                    .extend_with_renames_and_copies(
+                        |name| store.find_existing(name).map(|r| r.target).ok(),
+                        |name| {
+                            Ok(store
+                                .iter()
+                                .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?
+                                .filter(|r| matches!(&r.target, Target::Symbolic(target) if target == name))
+                                .map(|r| r.name)
+                                .collect())
+                        },
+                        |idx, update| Edit {
+                            update,
+                            parent_index: None,
+                            source_index: Some(idx),
+                            leaf_referent_previous_oid: None,
+                        },
+                    )
+                    .map_err(Error::PreprocessingFailed)?;
+                self.updates
+                    .pre_process(
+                        |name| store.find_existing(name).map(|r| r.target).ok(),
+                        |idx, update| Edit {
+                            update,
+                            parent_index: Some(idx),
+                            source_index: None,
+                            leaf_referent_previous_oid: None,
+                        },
+                    )
+                    .map_err(Error::PreprocessingFailed)?;
+
+                std::fs::create_dir_all(store.reftable_dir())?;
+                self.lock = Some(
+                    git_lock::File::acquire_to_update_resource(store.tables_list_path(), self.lock_fail_mode, None)
+                        .map_err(Error::LockAcquire)?,
+                );
+                // Read the tables again now that they are locked to be sure nobody changed them in the meantime.
+                self.tables = store.tables()?;
+
+                let refs = if self.updates.iter().any(|edit| edit.source_index.is_some()) {
+                    stack::merged_refs(&self.tables).map_err(stack::Error::from)?
+                } else {
+                    BTreeMap::new()
+                };
+                for cid in 0..self.updates.len() {
+                    let relocation = relocation_source(&self.updates, cid);
+                    let is_relocation = relocation.is_some();
+                    if let Some((source, _, is_rename)) = relocation {
+                        let new_name = self.updates[cid].update.name.as_bstr();
+                        let ignore = if is_rename { Some(source.as_bstr()) } else { None };
+                        if let Some(existing) = name_conflict(&refs, new_name, ignore) {
+                            return Err(Error::NameConflict {
+                                full_name: new_name.to_owned(),
+                                existing,
+                            });
+                        }
+                    }
+                    let change = &mut self.updates[cid];
+                    Self::apply_change(&self.tables, change)?;
+                    if let (
+                        Change::Update {
+                            mode: Create::OrUpdate { previous: Some(actual) },
+                            new,
+                            ..
+                        },
+                        true,
+                    ) = (&change.update.change, is_relocation)
+                    {
+                        // Unlike other updates, renames and copies don't tolerate references that already have the new value.
+                        return Err(Error::MustNotExist {
+                            full_name: change.name(),
+                            actual: actual.clone(),
+                            new: new.clone(),
+                        });
+                    }
+
+                    // traverse parent chain from leaf/peeled ref and set the leaf previous oid accordingly
+                    // to help with their reflog entries
+                    if let (Some(crate::Target::Peeled(oid)), Some(parent_idx)) =
+                        (change.update.change.previous_value(), change.parent_index)
+                    {
+                        let oid = oid.to_owned();
+                        let mut parent_idx_cursor = Some(parent_idx);
+                        while let Some(parent) = parent_idx_cursor.take().map(|idx| &mut self.updates[idx]) {
+                            parent_idx_cursor = parent.parent_index;
+                            parent.leaf_referent_previous_oid = Some(oid);
+                        }
+                    }
+                }
+                self.state = State::Prepared;
+                self
+            }
+        })
+    }
+
+    /// Make all [prepared][Transaction::prepare()] permanent and return the performed edits which represent the current
+    /// state of the affected refs in the ref store in that instant. Please note that the obtained edits may have been
+    /// adjusted to contain more dependent edits or additional information.
+    /// `committer` is used in the reflog.
+    ///
+    /// All edits are written into a single new table, which makes the transaction atomic.
+    /// Deleting a reflog hides all of its entries, and afterwards the stack is compacted if
+    /// [`auto_compact`][reftable::Store::auto_compact] is set, which never causes the transaction to fail if the stack
+    /// is locked by someone else.
+    ///
+    /// Note that transactions will be prepared automatically as needed.
+    pub fn commit(mut self, committer: &git_actor::Signature) -> Result<Vec<RefEdit>, Error> {
+        match self.state {
+            State::Open => self.prepare()?.commit(committer),
+            State::Prepared => {
+                let update_index = self.tables.last().map_or(1, |t| t.max_update_index() + 1);
+                let mut refs = BTreeMap::new();
+                let mut logs = BTreeMap::new();
+                for (cid, change) in self.updates.iter().enumerate() {
+                    assert!(!change.update.deref, "Deref mode is turned into splits and turned off");
+                    let name = change.update.name.as_bstr();
+                    match &change.update.change {
+                        Change::Update { log, new, mode } => {
+                            let mut previous_oid = mode.previous_oid().or(change.leaf_referent_previous_oid);
+                            let mut force_create_reflog = log.force_create_reflog;
+                            if let Some((source, oid, _)) = relocation_source(&self.updates, cid) {
+                                for (entry_update_index, line) in
+                                    reftable::log::entries(&self.tables, source.as_bstr())?
+                                {
+                                    let entry = record::Log {
+                                        name: name.to_owned(),
+                                        update_index: entry_update_index,
+                                        value: LogValue::Update(line),
+                                    };
+                                    logs.insert(entry.key(), entry);
+                                    force_create_reflog = true;
+                                }
+                                previous_oid = Some(oid);
+                            }
+                            if log.mode == RefLog::AndReference {
+                                let value = match new {
+                                    Target::Peeled(oid) => RefValue::Peeled(*oid),
+                                    Target::Symbolic(name) => RefValue::Symbolic(name.as_bstr().to_owned()),
+                                };
+                                refs.insert(
+                                    name.to_owned(),
+                                    record::Ref {
+                                        name: name.to_owned(),
+                                        update_index,
+                                        value,
+                                    },
+                                );
+                            }
+                            match new {
+                                Target::Symbolic(_) => {} // no reflog for symref changes
+                                Target::Peeled(oid) => {
+                                    if self.store.write_reflog == WriteReflog::Disable
+                                        || !(force_create_reflog
+                                            || should_autocreate_reflog(name)
+                                            || !reftable::log::entries(&self.tables, name)?.is_empty())
+                                    {
+                                        continue;
+                                    }
+                                    if log.message.find_byte(b'\n').is_some() {
+                                        return Err(Error::ReflogMessage { full_name: name.into() });
+                                    }
+                                    let entry = record::Log {
+                                        name: name.to_owned(),
+                                        update_index,
+                                        value: LogValue::Update(log::mutable::Line {
+                                            previous_oid: previous_oid
+                                                .unwrap_or_else(|| ObjectId::null_sha(oid.kind())),
+                                            new_oid: *oid,
+                                            signature: committer.clone(),
+                                            message: log.message.clone(),
+                                        }),
+                                    };
+                                    logs.insert(entry.key(), entry);
+                                }
+                            }
+                        }
+                        Change::Copy { .. } => {}
+                        Change::Delete { .. } | Change::Rename { .. } => {
+                            if let Change::Delete {
+                                log: RefLog::AndReference,
+                                ..
+                            }
+                            | Change::Rename { .. } = change.update.change
+                            {
+                                refs.insert(
+                                    name.to_owned(),
+                                    record::Ref {
+                                        name: name.to_owned(),
+                                        update_index,
+                                        value: RefValue::Deletion,
+                                    },
+                                );
+                            }
+                            for (entry_update_index, _) in reftable::log::entries(&self.tables, name)? {
+                                let entry = record::Log {
+                                    name: name.to_owned(),
+                                    update_index: entry_update_index,
+                                    value: LogValue::Deletion,
+                                };
+                                logs.insert(entry.key(), entry);
+                            }
+                        }
+                    }
+                }
+
+                let mut lock = self.lock.take().expect("prepared transactions hold the lock");
+                if !(refs.is_empty() && logs.is_empty()) {
+                    let refs: Vec<_> = refs.into_iter().map(|(_, r)| r).collect();
+                    let logs: Vec<_> = logs.into_iter().map(|(_, log)| log).collect();
+                    let data = table::write(&refs, &logs, update_index, update_index, self.store.write_options)?;
+                    let dir = self.store.reftable_dir();
+                    let name = stack::write_table(&dir, &data, update_index, update_index)?;
+                    let mut names = stack::read_list(&dir)?;
+                    names.push(name);
+                    stack::write_list(&mut lock, &names)?;
+                    lock.commit().map_err(|err| Error::Commit(err.error))?;
+
+                    if self.store.auto_compact {
+                        match self.store.auto_compact(self.lock_fail_mode) {
+                            Ok(_) | Err(stack::compact::Error::LockAcquire(_)) => {}
+                            Err(err) => return Err(Error::Compact(err)),
+                        }
+                    }
+                }
+                Ok(self.updates.into_iter().map(|edit| edit.update).collect())
+            }
+        }
+    }
+}
+
+fn should_autocreate_reflog(full_name: &BStr) -> bool {
+    full_name.starts_with_str("refs/heads/")
+        || full_name.starts_with_str("refs/remotes/")
+        || full_name.starts_with_str("refs/notes/")
+        || full_name == "HEAD"
+}
+
+/// The state of a [`Transaction`]
+enum State {
+    /// The transaction was just created but isn't prepared yet.
+    Open,
+    /// The transaction is ready to be committed.
+    Prepared,
+}
+
+/// Edits
+impl reftable::Store {
+    /// Open a transaction with the given `edits`, and determine how to fail if a `lock` cannot be obtained.
+    pub fn transaction(
+        &self,
+        edits: impl IntoIterator<Item = RefEdit>,
+        lock: git_lock::acquire::Fail,
+    ) -> Transaction<'_> {
+        Transaction {
+            store: self,
+            updates: edits
+                .into_iter()
+                .map(|update| Edit {
+                    update,
+                    parent_index: None,
+                    source_index: None,
+                    leaf_referent_previous_oid: None,
+                })
+                .collect(),
+            state: State::Open,
+            lock_fail_mode: lock,
+            lock: None,
+            tables: Vec::new(),
+        }
+    }
+}
+
+mod error {
+    use bstr::BString;
+    use quick_error::quick_error;
+
+    use crate::{
+        mutable::Target,
+        store::reftable::{stack, table},
+    };
+
+    quick_error! {
+        /// The error returned by various [`Transaction`][super::Transaction] methods.
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            PreprocessingFailed(err: std::io::Error) {
+                display("Edit preprocessing failed with error: {}", err.to_string())
+                source(err)
+            }
+            LockAcquire(err: git_lock::acquire::Error) {
+                display("A lock for the list of tables could not be obtained")
+                source(err)
+            }
+            Io(err: std::io::Error) {
+                display("An IO error occurred while applying an edit")
+                from()
+                source(err)
+            }
+            Stack(err: stack::Error) {
+                display("The reftables could not be read")
+                from()
+                source(err)
+            }
+            WriteTable(err: table::write::Error) {
+                display("The table with all edits could not be written")
+                from()
+                source(err)
+            }
+            Commit(err: std::io::Error) {
+                display("The list of tables could not be updated")
+                source(err)
+            }
+            Compact(err: stack::compact::Error) {
+                display("The tables could not be compacted after committing the transaction")
+                source(err)
+            }
+            DeleteReferenceMustExist { full_name: BString } {
+                display("The reference '{}' for deletion did not exist", full_name)
+            }
+            MustNotExist { full_name: BString, actual: Target, new: Target } {
+                display("Reference '{}' was not supposed to exist when writing it with value {}, but actual content was {}", full_name, new, actual)
+            }
+            MustExist { full_name: BString, expected: Target } {
+                display("Reference '{}' was supposed to exist with value {}, but didn't.", full_name, expected)
+            }
+            ReferenceOutOfDate { full_name: BString, expected: Target, actual: Target } {
+                display("The reference '{}' should have content {}, actual content was {}", full_name, expected, actual)
+            }
+            SourceMustExist { full_name: BString } {
+                display("The reference '{}' to rename or copy did not exist", full_name)
+            }
+            SymbolicSource { full_name: BString } {
+                display("The reference '{}' is symbolic and can't be renamed or copied", full_name)
+            }
+            NameConflict { full_name: BString, existing: BString } {
+                display("'{}' exists; cannot create '{}'", existing, full_name)
+            }
+            ReflogMessage { full_name: BString } {
+                display("The reflog message for reference '{}' must not contain newlines", full_name)
+            }
+        }
+    }
+}
+pub use error::Error;
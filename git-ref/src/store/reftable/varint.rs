@@ -0,0 +1,53 @@
+//! The variable length integer encoding used by reftables, which is the same as the one used for offsets in packs.
+
+/// Append `value` to `out` in its variable length encoding.
+pub fn encode(mut value: u64, out: &mut Vec<u8>) {
+    let mut buf = [0u8; 10];
+    let mut pos = buf.len() - 1;
+    buf[pos] = (value & 127) as u8;
+    value >>= 7;
+    while value != 0 {
+        value -= 1;
+        pos -= 1;
+        buf[pos] = 128 | (value & 127) as u8;
+        value >>= 7;
+    }
+    out.extend_from_slice(&buf[pos..]);
+}
+
+/// Decode a variable length integer from the beginning of `input` and return it along with the amount of bytes consumed,
+/// or `None` if `input` ended prematurely or the value doesn't fit into 64 bits.
+pub fn decode(input: &[u8]) -> Option<(u64, usize)> {
+    let mut bytes = input.iter();
+    let mut c = *bytes.next()?;
+    let mut value = u64::from(c & 127);
+    let mut consumed = 1;
+    while c & 128 != 0 {
+        c = *bytes.next()?;
+        consumed += 1;
+        value = value.checked_add(1)?.checked_mul(128).map(|v| v | u64::from(c & 127))?;
+    }
+    Some((value, consumed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+
+    #[test]
+    fn round_trip() {
+        for value in &[0, 1, 127, 128, 129, 16511, 16512, 1 << 32, u64::MAX - 1, u64::MAX] {
+            let mut buf = Vec::new();
+            encode(*value, &mut buf);
+            assert_eq!(decode(&buf), Some((*value, buf.len())), "{}", value);
+        }
+    }
+
+    #[test]
+    fn known_encodings() {
+        let mut buf = Vec::new();
+        encode(128, &mut buf);
+        assert_eq!(buf, [0x80, 0x00], "the offset makes this encoding unique");
+        assert_eq!(decode(&[0x80]), None, "truncated input");
+    }
+}
@@ -5,6 +5,7 @@
 //!
 //! * create or update reference
 //! * delete references
+//! * rename or copy references along with their reflog
 //!
 //! The following guarantees are made:
 //!
@@ -96,6 +97,34 @@ pub enum Change {
         /// How to thread the reference log during deletion.
         log: RefLog,
     },
+    /// Rename a reference to `new_name` like `git branch -m` does, moving its reflog along with it and adding an entry
+    /// with `message`. Symbolic references pointing to the renamed reference, like `HEAD`, will point to `new_name` afterwards.
+    ///
+    /// The reference must exist and must not be symbolic, and no reference named `new_name` must exist either.
+    Rename {
+        /// The name of the reference after the rename.
+        new_name: FullName,
+        /// The previous state of the reference, which is expected to match if set.
+        ///
+        /// It will be filled in automatically and can be accessed if the transaction was committed successfully.
+        previous: Option<Target>,
+        /// The message to put into the reflog of the renamed reference, which must be a single line.
+        message: BString,
+    },
+    /// Copy a reference to `new_name` like `git branch -c` does, copying its reflog along with it and adding an entry
+    /// with `message`.
+    ///
+    /// The same rules as for [renames][Change::Rename] apply.
+    Copy {
+        /// The name of the copy.
+        new_name: FullName,
+        /// The previous state of the reference, which is expected to match if set.
+        ///
+        /// It will be filled in automatically and can be accessed if the transaction was committed successfully.
+        previous: Option<Target>,
+        /// The message to put into the reflog of the copy, which must be a single line.
+        message: BString,
+    },
 }
 
 impl Change {
@@ -107,7 +136,9 @@ impl Change {
                 mode: Create::OrUpdate { previous },
                 ..
             }
-            | Change::Delete { previous, .. } => previous.as_ref().map(|t| t.borrow()),
+            | Change::Delete { previous, .. }
+            | Change::Rename { previous, .. }
+            | Change::Copy { previous, .. } => previous.as_ref().map(|t| t.borrow()),
         }
     }
 }
@@ -135,7 +166,8 @@ pub enum RefLog {
 
 mod ext {
     use crate::{
-        transaction::{Change, LogChange, RefEdit, RefLog, Target},
+        mutable::FullName,
+        transaction::{Change, Create, LogChange, RefEdit, RefLog, Target},
         PartialName,
     };
     use bstr::BString;
@@ -157,6 +189,19 @@ mod ext {
             make_entry: impl FnMut(usize, RefEdit) -> T,
         ) -> Result<(), std::io::Error>;
 
+        /// Add an edit creating the new reference for each rename or copy, and for renames, edits to let all symbolic
+        /// references returned by `referrers` point to the new name.
+        ///
+        /// The previous value of renamed or copied references is set to their current value as returned by `find` unless
+        /// it is already set, which allows stores to assure the value doesn't change until the edits are applied.
+        /// References which can't be found or are symbolic are left to stores to reject.
+        fn extend_with_renames_and_copies(
+            &mut self,
+            find: impl FnMut(PartialName<'_>) -> Option<Target>,
+            referrers: impl FnMut(&FullName) -> std::io::Result<Vec<FullName>>,
+            make_entry: impl FnMut(usize, RefEdit) -> T,
+        ) -> Result<(), std::io::Error>;
+
         /// All processing steps in one and in the correct order.
         ///
         /// Users call this to assure derefs are honored and duplicate checks are done.
@@ -188,6 +233,80 @@ mod ext {
             }
         }
 
+        fn extend_with_renames_and_copies(
+            &mut self,
+            mut find: impl FnMut(PartialName<'_>) -> Option<Target>,
+            mut referrers: impl FnMut(&FullName) -> std::io::Result<Vec<FullName>>,
+            mut make_entry: impl FnMut(usize, RefEdit) -> E,
+        ) -> Result<(), std::io::Error> {
+            let mut new_edits = Vec::new();
+            for (eid, edit) in self.iter_mut().enumerate() {
+                let RefEdit { change, name, deref } = edit.borrow_mut();
+                let (new_name, previous, message, is_rename) = match change {
+                    Change::Rename {
+                        new_name,
+                        previous,
+                        message,
+                    } => (new_name, previous, message, true),
+                    Change::Copy {
+                        new_name,
+                        previous,
+                        message,
+                    } => (new_name, previous, message, false),
+                    Change::Update { .. } | Change::Delete { .. } => continue,
+                };
+                // Symbolic references can't be renamed, hence there is nothing to dereference.
+                *deref = false;
+                let oid = match find(name.to_partial()) {
+                    Some(Target::Peeled(oid)) => oid,
+                    Some(Target::Symbolic(_)) | None => continue,
+                };
+                if previous.is_none() {
+                    *previous = Some(Target::Peeled(oid));
+                }
+                new_edits.push(make_entry(
+                    eid,
+                    RefEdit {
+                        change: Change::Update {
+                            log: LogChange {
+                                mode: RefLog::AndReference,
+                                force_create_reflog: false,
+                                message: message.clone(),
+                            },
+                            mode: Create::Only,
+                            new: Target::Peeled(oid),
+                        },
+                        name: new_name.clone(),
+                        deref: false,
+                    },
+                ));
+                if is_rename {
+                    for referrer in referrers(name)? {
+                        new_edits.push(make_entry(
+                            eid,
+                            RefEdit {
+                                change: Change::Update {
+                                    log: LogChange {
+                                        mode: RefLog::AndReference,
+                                        force_create_reflog: false,
+                                        message: message.clone(),
+                                    },
+                                    mode: Create::OrUpdate {
+                                        previous: Some(Target::Symbolic(name.clone())),
+                                    },
+                                    new: Target::Symbolic(new_name.clone()),
+                                },
+                                name: referrer,
+                                deref: false,
+                            },
+                        ));
+                    }
+                }
+            }
+            self.extend(new_edits);
+            Ok(())
+        }
+
         fn extend_with_splits_of_symbolic_refs(
             &mut self,
             mut find: impl FnMut(PartialName<'_>) -> Option<Target>,
@@ -207,6 +326,9 @@ mod ext {
                     // In any case, we don't want the following algorithms to try dereffing it and assume they deal with
                     // broken refs gracefully.
                     edit.deref = false;
+                    if let Change::Rename { .. } | Change::Copy { .. } = edit.change {
+                        continue;
+                    }
                     if let Some(Target::Symbolic(referent)) = find(edit.name.to_partial()) {
                         new_edits.push(make_entry(
                             eid,
@@ -246,6 +368,9 @@ mod ext {
                                         deref: true,
                                     }
                                 }
+                                Change::Rename { .. } | Change::Copy { .. } => {
+                                    unreachable!("renames and copies are never dereferenced")
+                                }
                             },
                         ));
                     }
@@ -5,8 +5,7 @@ mod reflog {
         #[test]
         fn iter() -> crate::Result {
             let store = file::store_with_packed_refs()?;
-            let packed = store.packed()?;
-            let r = store.find_existing("main", packed.as_ref())?;
+            let r = store.find_existing("main")?;
             let mut buf = Vec::new();
             assert_eq!(r.log_iter(&store, &mut buf)?.expect("log exists").count(), 1);
             assert!(r.log_exists(&store), "it exists if its readable");
@@ -16,8 +15,7 @@ mod reflog {
         #[test]
         fn iter_rev() -> crate::Result {
             let store = file::store_with_packed_refs()?;
-            let packed = store.packed()?;
-            let r = store.find_existing("main", packed.as_ref())?;
+            let r = store.find_existing("main")?;
             let mut buf = [0u8; 256];
             assert_eq!(r.log_iter_rev(&store, &mut buf)?.expect("log exists").count(), 1);
             Ok(())
@@ -59,15 +57,14 @@ mod peel {
         let r = store.loose_find_existing("HEAD")?;
         assert_eq!(r.kind(), git_ref::Kind::Symbolic, "there is something to peel");
 
-        let nr = git_ref::file::loose::Reference::try_from(
-            r.peel_one_level(&store, None).expect("exists").expect("no failure"),
-        )
-        .expect("loose ref");
+        let nr =
+            git_ref::file::loose::Reference::try_from(r.peel_one_level(&store).expect("exists").expect("no failure"))
+                .expect("loose ref");
         assert!(
             matches!(nr.target.borrow(), git_ref::Target::Peeled(_)),
             "iteration peels a single level"
         );
-        assert!(nr.peel_one_level(&store, None).is_none(), "end of iteration");
+        assert!(nr.peel_one_level(&store).is_none(), "end of iteration");
         assert_eq!(
             nr.target.borrow(),
             git_ref::Target::Peeled(&hex_to_id("134385f6d781b7e97062102c6a483440bfda2a03")),
@@ -80,13 +77,12 @@ mod peel {
     fn peel_with_packed_involvement() -> crate::Result {
         let store = store_with_packed_refs()?;
         let mut head = store.loose_find_existing("HEAD")?;
-        let packed = store.packed()?;
         let expected = hex_to_id("134385f6d781b7e97062102c6a483440bfda2a03");
-        assert_eq!(head.peel_to_id_in_place(&store, packed.as_ref())?, expected);
+        assert_eq!(head.peel_to_id_in_place(&store)?, expected);
         assert_eq!(head.target.as_id().map(ToOwned::to_owned), Some(expected));
 
-        let mut head = store.find_existing("dt1", packed.as_ref())?;
-        assert_eq!(head.peel_to_id_in_place(&store, packed.as_ref())?, expected);
+        let mut head = store.find_existing("dt1")?;
+        assert_eq!(head.peel_to_id_in_place(&store)?, expected);
         assert_eq!(head.target().as_id().map(ToOwned::to_owned), Some(expected));
         Ok(())
     }
@@ -94,9 +90,8 @@ mod peel {
     #[test]
     fn peel_one_level_with_pack() -> crate::Result {
         let store = store_with_packed_refs()?;
-        let packed = store.packed()?;
 
-        let head = store.find_existing("dt1", packed.as_ref())?;
+        let head = store.find_existing("dt1")?;
         assert!(head.is_packed());
         assert_eq!(
             head.target().as_id().map(ToOwned::to_owned),
@@ -108,16 +103,14 @@ mod peel {
             "its peeled, but does have another step to peel to"
         );
 
-        let peeled = head
-            .peel_one_level(&store, packed.as_ref())
-            .expect("a peeled ref for the object")?;
+        let peeled = head.peel_one_level(&store).expect("a peeled ref for the object")?;
         assert_eq!(
             peeled.target().as_id().map(ToOwned::to_owned),
             Some(hex_to_id("134385f6d781b7e97062102c6a483440bfda2a03")),
             "packed refs are always peeled (at least the ones we choose to read)"
         );
         assert_eq!(peeled.kind(), git_ref::Kind::Peeled, "it's terminally peeled now");
-        assert!(peeled.peel_one_level(&store, packed.as_ref()).is_none());
+        assert!(peeled.peel_one_level(&store).is_none());
         Ok(())
     }
 
@@ -128,7 +121,7 @@ mod peel {
         assert_eq!(r.kind(), git_ref::Kind::Symbolic, "there is something to peel");
 
         assert_eq!(
-            r.peel_to_id_in_place(&store, None)?,
+            r.peel_to_id_in_place(&store)?,
             hex_to_id("134385f6d781b7e97062102c6a483440bfda2a03")
         );
         assert_eq!(r.name.as_bstr(), "refs/remotes/origin/multi-link-target3");
@@ -144,7 +137,7 @@ mod peel {
         assert_eq!(r.name.as_bstr(), "refs/loop-a");
 
         assert!(matches!(
-            r.peel_to_id_in_place(&store, None).unwrap_err(),
+            r.peel_to_id_in_place(&store).unwrap_err(),
             git_ref::file::loose::reference::peel::to_id::Error::Cycle { .. }
         ));
         assert_eq!(r.name.as_bstr(), "refs/loop-a", "the ref is not changed on error");
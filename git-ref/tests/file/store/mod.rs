@@ -1,3 +1,5 @@
 mod find;
 mod iter;
+mod packed;
 mod reflog;
+mod special;
@@ -0,0 +1,118 @@
+use crate::file::store_writable;
+use git_lock::acquire::Fail;
+use git_ref::mutable::Target;
+use std::sync::Arc;
+
+#[test]
+fn packed_buffer_is_cached_until_the_file_changes() -> crate::Result {
+    let (_keep, store) = store_writable("make_packed_ref_repository_for_overlay.sh")?;
+    let first = store.packed_buffer()?.expect("packed refs exist");
+    let second = store.packed_buffer()?.expect("packed refs exist");
+    assert!(Arc::ptr_eq(&first, &second), "unchanged files are not reloaded");
+    assert_eq!(first.iter()?.count(), 4);
+
+    std::fs::write(
+        store.packed_refs_path(),
+        "# pack-refs with: peeled fully-peeled sorted \n134385f6d781b7e97062102c6a483440bfda2a03 refs/heads/main\n",
+    )?;
+    let third = store.packed_buffer()?.expect("packed refs exist");
+    assert!(!Arc::ptr_eq(&first, &third), "the changed file was reloaded");
+    assert_eq!(third.iter()?.count(), 1);
+    assert!(store.find("tag-object")?.is_none(), "lookups see the new file as well");
+
+    #[cfg(unix)]
+    {
+        let replacement = store.packed_refs_path().with_extension("new");
+        std::fs::write(
+            &replacement,
+            "# pack-refs with: peeled fully-peeled sorted \n134385f6d781b7e97062102c6a483440bfda2a03 refs/heads/dev_\n",
+        )?;
+        let modified = std::fs::metadata(store.packed_refs_path())?.modified()?;
+        std::fs::File::options()
+            .write(true)
+            .open(&replacement)?
+            .set_modified(modified)?;
+        std::fs::rename(&replacement, store.packed_refs_path())?;
+        let fourth = store.packed_buffer()?.expect("packed refs exist");
+        assert!(
+            !Arc::ptr_eq(&third, &fourth),
+            "files replaced with one of the same size and modification time are noticed by their inode"
+        );
+        assert!(store.find("dev_")?.is_some());
+    }
+
+    std::fs::remove_file(store.packed_refs_path())?;
+    assert!(store.packed_buffer()?.is_none(), "removed files are noticed");
+    Ok(())
+}
+
+#[test]
+fn pack_refs_moves_loose_refs_into_packed_refs_and_prunes_them() -> crate::Result {
+    let (_keep, store) = store_writable("make_packed_ref_repository_for_overlay.sh")?;
+    let newer = store.loose_find_existing("newer-as-loose")?.target;
+    let packed_tag = store.find_existing("tag-object")?;
+    let (tag_id, commit_id) = match &packed_tag {
+        git_ref::file::Reference::Packed(r) => (r.target, r.object()),
+        git_ref::file::Reference::Loose(_) => unreachable!("the tag is packed"),
+    };
+    let nested_dir = store.base.join("refs/tags/nested/deeper");
+    std::fs::create_dir_all(&nested_dir)?;
+    std::fs::write(nested_dir.join("tag"), format!("{}\n", tag_id))?;
+
+    let mut peeled = Vec::new();
+    assert_eq!(
+        store.pack_refs(Fail::Immediately, |id| {
+            peeled.push(id.to_owned());
+            if id == tag_id {
+                Some(commit_id)
+            } else {
+                None
+            }
+        })?,
+        2,
+        "only two loose refs are not symbolic"
+    );
+    assert_eq!(
+        peeled.len(),
+        4,
+        "all refs but the previously packed and peeled tag-object are peeled"
+    );
+
+    assert!(
+        store.loose_find("newer-as-loose")?.is_none(),
+        "the loose ref was removed"
+    );
+    let packed = store.find_existing("newer-as-loose")?;
+    assert!(packed.is_packed());
+    assert_eq!(packed.target(), newer, "the loose value replaced the packed one");
+    assert_eq!(
+        store.find_existing("tag-object")?,
+        packed_tag,
+        "previously packed refs are kept along with their peeled object"
+    );
+    match store.find_existing("refs/tags/nested/deeper/tag")? {
+        git_ref::file::Reference::Packed(r) => {
+            assert_eq!(r.target, tag_id);
+            assert_eq!(r.object(), commit_id, "annotated tags are peeled");
+        }
+        git_ref::file::Reference::Loose(_) => unreachable!("the tag was packed"),
+    }
+    assert!(
+        !store.base.join("refs/tags/nested").exists(),
+        "directories left empty are removed"
+    );
+    assert!(
+        store.base.join("refs/tags").is_dir() && store.base.join("refs/heads").is_dir(),
+        "but not the category directories, even if empty"
+    );
+    assert!(matches!(
+        store.loose_find_existing("origin")?.target,
+        Target::Symbolic(_)
+    ));
+    assert_eq!(store.packed_buffer()?.expect("exists").iter()?.count(), 5);
+    assert!(
+        std::fs::read(store.packed_refs_path())?.starts_with(b"# pack-refs with: peeled fully-peeled sorted \n"),
+        "the header is the one git writes"
+    );
+    Ok(())
+}
@@ -64,3 +64,197 @@ mod iter_rev {
         Ok(())
     }
 }
+
+mod expire {
+    use git_hash::ObjectId;
+    use git_lock::acquire::Fail;
+    use git_ref::file::{log, log::expire, Store};
+
+    use crate::file::store_writable;
+
+    fn lines(store: &Store, name: &str) -> crate::Result<Vec<log::mutable::Line>> {
+        let mut buf = Vec::new();
+        let lines = store
+            .reflog_iter(name, &mut buf)?
+            .expect("exists")
+            .map(|line| line.map(Into::into))
+            .collect::<Result<_, _>>()?;
+        Ok(lines)
+    }
+
+    fn ref_id(store: &Store, name: &str) -> crate::Result<ObjectId> {
+        Ok(store
+            .loose_find_existing(name)?
+            .target
+            .as_id()
+            .expect("peeled")
+            .to_owned())
+    }
+
+    /// The time at which all entries of the fixture were written.
+    const TIME: u32 = 946771200;
+
+    #[test]
+    fn entries_older_than_the_cutoff_are_removed() -> crate::Result {
+        let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+        let main = ref_id(&store, "refs/heads/main")?;
+        let options = |expire_total| expire::Options {
+            expire_total: Some(expire_total),
+            update_ref: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            store.reflog_expire("refs/heads/main", options(TIME), |_| false, Fail::Immediately)?,
+            0,
+            "entries at the cutoff time are kept"
+        );
+        assert_eq!(lines(&store, "refs/heads/main")?.len(), 5);
+
+        assert_eq!(
+            store.reflog_expire("refs/heads/main", options(TIME + 1), |_| true, Fail::Immediately)?,
+            5
+        );
+        assert!(lines(&store, "refs/heads/main")?.is_empty());
+        assert_eq!(
+            ref_id(&store, "refs/heads/main")?,
+            main,
+            "the reference is left alone if no entry remains"
+        );
+
+        assert_eq!(
+            store.reflog_expire(
+                "refs/heads/does-not-exist",
+                options(TIME + 1),
+                |_| true,
+                Fail::Immediately
+            )?,
+            0,
+            "missing logs are ignored"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unreachable_entries_are_removed_if_old_enough() -> crate::Result {
+        let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+        let before = lines(&store, "refs/heads/main")?;
+        let amended = before[3].new_oid;
+        let options = |expire_unreachable| expire::Options {
+            expire_unreachable: Some(expire_unreachable),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            store.reflog_expire("refs/heads/main", options(TIME), |id| id != amended, Fail::Immediately)?,
+            0,
+            "entries aren't old enough"
+        );
+        assert_eq!(
+            store.reflog_expire(
+                "refs/heads/main",
+                options(TIME + 1),
+                |id| id != amended,
+                Fail::Immediately
+            )?,
+            2,
+            "the entries creating and replacing the amended commit are removed"
+        );
+        assert_eq!(lines(&store, "refs/heads/main")?, before[..3].to_vec());
+        Ok(())
+    }
+
+    #[test]
+    fn single_entries_can_be_deleted_with_the_log_rewritten_and_the_ref_updated() -> crate::Result {
+        let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+        let before = lines(&store, "refs/heads/main")?;
+        let options = expire::Options {
+            rewrite: true,
+            update_ref: true,
+            ..Default::default()
+        };
+
+        store.reflog_delete_entry("refs/heads/main", 1, options, Fail::Immediately)?;
+        let after = lines(&store, "refs/heads/main")?;
+        assert_eq!(after.len(), 4);
+        assert_eq!(after[..3], before[..3]);
+        assert_eq!(
+            after[3].previous_oid, before[2].new_oid,
+            "the previous id follows the entry before it"
+        );
+        assert_eq!(after[3].new_oid, before[4].new_oid);
+        assert_eq!(ref_id(&store, "refs/heads/main")?, before[4].new_oid);
+
+        store.reflog_delete_entry("refs/heads/main", 0, options, Fail::Immediately)?;
+        assert_eq!(lines(&store, "refs/heads/main")?, before[..3].to_vec());
+        assert_eq!(
+            ref_id(&store, "refs/heads/main")?,
+            before[2].new_oid,
+            "the reference is set to the most recent remaining entry"
+        );
+
+        assert!(matches!(
+            store.reflog_delete_entry("refs/heads/main", 3, options, Fail::Immediately),
+            Err(expire::Error::EntryOutOfRange { index: 3, len: 3 })
+        ));
+        assert_eq!(lines(&store, "refs/heads/main")?.len(), 3);
+
+        let log_path = store.base.join("logs/refs/heads/main");
+        let log = std::fs::read(&log_path)?;
+        std::fs::write(log_path.with_extension("lock"), "")?;
+        assert!(
+            matches!(
+                store.reflog_delete_entry("refs/heads/main", 3, options, Fail::Immediately),
+                Err(expire::Error::EntryOutOfRange { index: 3, len: 3 })
+            ),
+            "the index is checked before the log is locked"
+        );
+        assert_eq!(std::fs::read(&log_path)?, log, "the log is untouched");
+        Ok(())
+    }
+
+    #[test]
+    fn the_ref_is_only_updated_if_the_most_recent_entry_was_removed() -> crate::Result {
+        let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+        let before = lines(&store, "refs/heads/main")?;
+        let other = before[0].new_oid;
+        assert_ne!(other, before[4].new_oid);
+        std::fs::write(store.base.join("refs/heads/main"), format!("{}\n", other))?;
+        let options = expire::Options {
+            expire_total: Some(TIME),
+            update_ref: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            store.reflog_expire("refs/heads/main", options, |_| false, Fail::Immediately)?,
+            0
+        );
+        assert_eq!(
+            ref_id(&store, "refs/heads/main")?,
+            other,
+            "nothing was pruned, so the reference keeps its value"
+        );
+
+        store.reflog_delete_entry("refs/heads/main", 1, options, Fail::Immediately)?;
+        assert_eq!(
+            ref_id(&store, "refs/heads/main")?,
+            other,
+            "the most recent entry is still present"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn symbolic_refs_are_not_updated() -> crate::Result {
+        let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+        let options = expire::Options {
+            update_ref: true,
+            ..Default::default()
+        };
+        store.reflog_delete_entry("HEAD", 0, options, Fail::Immediately)?;
+        assert_eq!(lines(&store, "HEAD")?.len(), 4);
+        assert!(store.loose_find_existing("HEAD")?.target.as_name().is_some());
+        Ok(())
+    }
+}
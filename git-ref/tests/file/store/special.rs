@@ -0,0 +1,112 @@
+use git_lock::acquire::Fail;
+use git_ref::file::{fetch_head, FetchHead, MergeHead};
+use git_testtools::hex_to_id;
+
+use crate::file::store_writable;
+
+const FETCH_HEAD: &[u8] = b"4e8d713a70019a1920d4827d11b76d47fcc7b681\tnot-for-merge\tbranch 'other' of ../a
+134385f6d781b7e97062102c6a483440bfda2a03\t\tbranch 'main' of https://example.com/a
+";
+
+#[test]
+fn fetch_head_round_trips_and_marks_entries_not_for_merge() -> crate::Result {
+    let fetch_head = FetchHead::from_bytes(FETCH_HEAD)?;
+    assert_eq!(
+        fetch_head.entries,
+        vec![
+            fetch_head::Entry {
+                id: hex_to_id("4e8d713a70019a1920d4827d11b76d47fcc7b681"),
+                not_for_merge: true,
+                description: "branch 'other' of ../a".into()
+            },
+            fetch_head::Entry {
+                id: hex_to_id("134385f6d781b7e97062102c6a483440bfda2a03"),
+                not_for_merge: false,
+                description: "branch 'main' of https://example.com/a".into()
+            }
+        ]
+    );
+    assert_eq!(fetch_head.for_merge().count(), 1);
+
+    let mut buf = Vec::new();
+    fetch_head.write_to(&mut buf)?;
+    assert_eq!(buf, FETCH_HEAD);
+    Ok(())
+}
+
+#[test]
+fn invalid_lines_are_rejected() {
+    for (input, expected) in &[
+        (
+            &b"4e8d713a70019a1920d4827d11b76d47fcc7b681\t\tok\nfoo\t\tbar\n"[..],
+            "FETCH_HEAD line 2 could not be parsed: \"foo\\t\\tbar\"",
+        ),
+        (
+            b"4e8d713a70019a1920d4827d11b76d47fcc7b681\tmaybe\tdescription",
+            "FETCH_HEAD line 1 could not be parsed: \"4e8d713a70019a1920d4827d11b76d47fcc7b681\\tmaybe\\tdescription\"",
+        ),
+        (
+            b"4e8d713a70019a1920d4827d11b76d47fcc7b681",
+            "FETCH_HEAD line 1 could not be parsed: \"4e8d713a70019a1920d4827d11b76d47fcc7b681\"",
+        ),
+    ] {
+        assert_eq!(FetchHead::from_bytes(input).unwrap_err().to_string(), *expected);
+    }
+    assert_eq!(
+        MergeHead::from_bytes(b"4e8d713a70019a1920d4827d11b76d47fcc7b681\nnot-an-id\n")
+            .unwrap_err()
+            .to_string(),
+        "MERGE_HEAD line 2 could not be parsed: \"not-an-id\""
+    );
+}
+
+#[test]
+fn merge_head_round_trips() -> crate::Result {
+    let input = b"4e8d713a70019a1920d4827d11b76d47fcc7b681\n134385f6d781b7e97062102c6a483440bfda2a03\n";
+    let merge_head = MergeHead::from_bytes(input)?;
+    assert_eq!(
+        merge_head.ids,
+        vec![
+            hex_to_id("4e8d713a70019a1920d4827d11b76d47fcc7b681"),
+            hex_to_id("134385f6d781b7e97062102c6a483440bfda2a03")
+        ]
+    );
+    let mut buf = Vec::new();
+    merge_head.write_to(&mut buf)?;
+    assert_eq!(buf, input);
+    Ok(())
+}
+
+#[test]
+fn store_reads_writes_and_finds_fetch_head_and_merge_head() -> crate::Result {
+    let (_keep, store) = store_writable("make_ref_repository.sh")?;
+    assert_eq!(store.fetch_head()?, None);
+    assert_eq!(store.merge_head()?, None);
+    assert!(store.find("FETCH_HEAD")?.is_none());
+
+    let fetch_head = FetchHead::from_bytes(FETCH_HEAD)?;
+    store.write_fetch_head(&fetch_head, Fail::Immediately)?;
+    assert_eq!(store.fetch_head()?.as_ref(), Some(&fetch_head));
+    assert_eq!(
+        store.find_existing("FETCH_HEAD")?.target().as_id(),
+        Some(fetch_head.entries[0].id.as_ref()),
+        "like git, the first entry is used even if it is not for merge"
+    );
+
+    let merge_head = MergeHead {
+        ids: fetch_head.entries.iter().rev().map(|e| e.id).collect(),
+    };
+    store.write_merge_head(&merge_head, Fail::Immediately)?;
+    assert_eq!(store.merge_head()?.as_ref(), Some(&merge_head));
+    assert_eq!(
+        store.loose_find_existing("MERGE_HEAD")?.target.as_id(),
+        Some(merge_head.ids[0].as_ref())
+    );
+
+    store.write_fetch_head(&FetchHead::default(), Fail::Immediately)?;
+    assert!(
+        store.find("FETCH_HEAD")?.is_none(),
+        "an empty FETCH_HEAD, as left by fetches without updates, doesn't resolve"
+    );
+    Ok(())
+}
@@ -152,7 +152,7 @@ fn delete_reflog_only_of_symbolic_no_deref() -> crate::Result {
     assert!(main.log_exists(&store), "log is untouched, too");
     assert_eq!(
         main.target,
-        head.peel_one_level(&store, None).expect("a symref")?.target(),
+        head.peel_one_level(&store).expect("a symref")?.target(),
         "head points to main"
     );
     Ok(())
@@ -185,7 +185,7 @@ fn delete_reflog_only_of_symbolic_with_deref() -> crate::Result {
     assert!(!main.log_exists(&store), "log is removed");
     assert_eq!(
         main.target,
-        head.peel_one_level(&store, None).expect("a symref")?.target(),
+        head.peel_one_level(&store).expect("a symref")?.target(),
         "head points to main"
     );
     Ok(())
@@ -282,3 +282,58 @@ fn store_write_mode_has_no_effect_and_reflogs_are_always_deleted() -> crate::Res
     }
     Ok(())
 }
+
+#[test]
+fn delete_packed_ref_removes_it_from_packed_refs() -> crate::Result {
+    let (_keep, store) = store_writable("make_packed_ref_repository_for_overlay.sh")?;
+    let main = store.find_existing("main")?;
+    assert!(main.is_packed(), "the ref is only packed");
+
+    let edits = store
+        .transaction(
+            Some(RefEdit {
+                change: Change::Delete {
+                    previous: Some(main.target()),
+                    log: RefLog::AndReference,
+                },
+                name: "refs/heads/main".try_into()?,
+                deref: false,
+            }),
+            Fail::Immediately,
+        )
+        .commit(&committer())?;
+    assert_eq!(edits.len(), 1);
+    assert!(store.find("main")?.is_none(), "the ref is gone from packed-refs");
+    assert_eq!(
+        store.packed_buffer()?.expect("still exists").iter()?.count(),
+        3,
+        "other refs were kept"
+    );
+    assert!(store.find("tag-object")?.is_some());
+    Ok(())
+}
+
+#[test]
+fn delete_ref_which_is_loose_and_packed_removes_both() -> crate::Result {
+    let (_keep, store) = store_writable("make_packed_ref_repository_for_overlay.sh")?;
+    assert!(store.loose_find("newer-as-loose")?.is_some());
+
+    store
+        .transaction(
+            Some(RefEdit {
+                change: Change::Delete {
+                    previous: Some(Target::must_exist()),
+                    log: RefLog::AndReference,
+                },
+                name: "refs/heads/newer-as-loose".try_into()?,
+                deref: false,
+            }),
+            Fail::Immediately,
+        )
+        .commit(&committer())?;
+    assert!(
+        store.find("newer-as-loose")?.is_none(),
+        "the packed ref doesn't reappear after the loose one was deleted"
+    );
+    Ok(())
+}
@@ -0,0 +1,275 @@
+use crate::file::{
+    store_writable,
+    transaction::prepare_and_commit::{committer, log_line, reflog_lines},
+};
+use git_lock::acquire::Fail;
+use git_ref::{
+    mutable::Target,
+    transaction::{Change, Create, LogChange, RefEdit, RefLog},
+};
+use std::convert::TryInto;
+
+fn rename(name: &str, new_name: &str) -> crate::Result<RefEdit> {
+    Ok(RefEdit {
+        change: Change::Rename {
+            new_name: new_name.try_into()?,
+            previous: None,
+            message: format!("Branch: renamed {} to {}", name, new_name).into(),
+        },
+        name: name.try_into()?,
+        deref: false,
+    })
+}
+
+fn copy(name: &str, new_name: &str) -> crate::Result<RefEdit> {
+    Ok(RefEdit {
+        change: Change::Copy {
+            new_name: new_name.try_into()?,
+            previous: None,
+            message: format!("Branch: copied {} to {}", name, new_name).into(),
+        },
+        name: name.try_into()?,
+        deref: false,
+    })
+}
+
+#[test]
+fn rename_moves_the_reflog_and_updates_symbolic_refs_pointing_to_it() -> crate::Result {
+    let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+    let main = store.loose_find_existing("main")?;
+    let head_log = reflog_lines(&store, "HEAD")?;
+    let mut expected_log = reflog_lines(&store, "refs/heads/main")?;
+
+    let edits = store
+        .transaction(Some(rename("refs/heads/main", "refs/heads/new")?), Fail::Immediately)
+        .commit(&committer())?;
+
+    let message = "Branch: renamed refs/heads/main to refs/heads/new";
+    assert_eq!(
+        edits,
+        vec![
+            RefEdit {
+                change: Change::Rename {
+                    new_name: "refs/heads/new".try_into()?,
+                    previous: Some(main.target.clone()),
+                    message: message.into(),
+                },
+                name: "refs/heads/main".try_into()?,
+                deref: false,
+            },
+            RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: message.into(),
+                    },
+                    mode: Create::Only,
+                    new: main.target.clone(),
+                },
+                name: "refs/heads/new".try_into()?,
+                deref: false,
+            },
+            RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: message.into(),
+                    },
+                    mode: Create::OrUpdate {
+                        previous: Some(Target::Symbolic("refs/heads/main".try_into()?)),
+                    },
+                    new: Target::Symbolic("refs/heads/new".try_into()?),
+                },
+                name: "HEAD".try_into()?,
+                deref: false,
+            }
+        ],
+        "the new reference is created and HEAD is updated"
+    );
+
+    assert!(store.loose_find("main")?.is_none(), "the old reference is gone…");
+    assert!(!store.reflog_exists("refs/heads/main")?, "…along with its reflog");
+    assert_eq!(store.loose_find_existing("new")?.target, main.target);
+    let id = main.target.as_id().expect("peeled").to_owned();
+    expected_log.push(log_line(id, id, message));
+    assert_eq!(
+        reflog_lines(&store, "refs/heads/new")?,
+        expected_log,
+        "the reflog was moved and an entry was added"
+    );
+
+    assert_eq!(
+        store.loose_find_existing("HEAD")?.target,
+        Target::Symbolic("refs/heads/new".try_into()?)
+    );
+    assert_eq!(
+        reflog_lines(&store, "HEAD")?,
+        head_log,
+        "changes to symbolic refs are not logged"
+    );
+    Ok(())
+}
+
+#[test]
+fn copy_keeps_the_original_and_its_reflog() -> crate::Result {
+    let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+    let main = store.loose_find_existing("main")?;
+    let mut expected_log = reflog_lines(&store, "refs/heads/main")?;
+
+    let edits = store
+        .transaction(Some(copy("refs/heads/main", "refs/heads/copy")?), Fail::Immediately)
+        .commit(&committer())?;
+    assert_eq!(edits.len(), 2, "symbolic refs don't change for copies");
+
+    assert_eq!(store.loose_find_existing("main")?.target, main.target);
+    assert_eq!(store.loose_find_existing("copy")?.target, main.target);
+    assert_eq!(
+        store.loose_find_existing("HEAD")?.target,
+        Target::Symbolic("refs/heads/main".try_into()?)
+    );
+    assert_eq!(reflog_lines(&store, "refs/heads/main")?, expected_log);
+    let id = main.target.as_id().expect("peeled").to_owned();
+    expected_log.push(log_line(id, id, "Branch: copied refs/heads/main to refs/heads/copy"));
+    assert_eq!(reflog_lines(&store, "refs/heads/copy")?, expected_log);
+    Ok(())
+}
+
+#[test]
+fn rename_into_a_directory_of_the_same_name_and_back() -> crate::Result {
+    let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+    let main = store.loose_find_existing("main")?;
+    let log_len = reflog_lines(&store, "refs/heads/main")?.len();
+
+    store
+        .transaction(
+            Some(rename("refs/heads/main", "refs/heads/main/nested")?),
+            Fail::Immediately,
+        )
+        .commit(&committer())?;
+    assert_eq!(store.loose_find_existing("main/nested")?.target, main.target);
+    assert_eq!(reflog_lines(&store, "refs/heads/main/nested")?.len(), log_len + 1);
+
+    store
+        .transaction(
+            Some(rename("refs/heads/main/nested", "refs/heads/main")?),
+            Fail::Immediately,
+        )
+        .commit(&committer())?;
+    assert_eq!(store.loose_find_existing("main")?.target, main.target);
+    assert!(
+        store.base.join("refs/heads/main").is_file(),
+        "the directory for the nested reference is gone"
+    );
+    assert_eq!(reflog_lines(&store, "refs/heads/main")?.len(), log_len + 2);
+    assert_eq!(
+        store.loose_find_existing("HEAD")?.target,
+        Target::Symbolic("refs/heads/main".try_into()?),
+        "HEAD followed both renames"
+    );
+    Ok(())
+}
+
+#[test]
+fn nested_renames_keep_the_source_if_the_new_name_is_locked() -> crate::Result {
+    let (_keep, store) = store_writable("make_packed_ref_repository.sh")?;
+    let d1 = store.find_existing("refs/heads/d1")?;
+    assert!(d1.is_packed());
+    std::fs::create_dir(store.base.join("refs/heads/d1"))?;
+    std::fs::write(store.base.join("refs/heads/d1/nested.lock"), "")?;
+    store
+        .transaction(
+            Some(rename("refs/heads/d1", "refs/heads/d1/nested")?),
+            Fail::Immediately,
+        )
+        .commit(&committer())
+        .expect_err("the new name is locked");
+    assert_eq!(
+        store.find_existing("refs/heads/d1")?,
+        d1,
+        "the packed source is still present"
+    );
+    assert!(store.find("refs/heads/d1/nested")?.is_none());
+
+    let (_keep, store) = store_writable("make_repo_for_reflog.sh")?;
+    let main = store.loose_find_existing("main")?;
+    store
+        .transaction(
+            Some(rename("refs/heads/main", "refs/heads/main/nested")?),
+            Fail::Immediately,
+        )
+        .commit(&committer())?;
+    let log = reflog_lines(&store, "refs/heads/main/nested")?;
+    std::fs::write(store.base.join("refs/heads/main.lock"), "")?;
+    store
+        .transaction(
+            Some(rename("refs/heads/main/nested", "refs/heads/main")?),
+            Fail::Immediately,
+        )
+        .commit(&committer())
+        .expect_err("the new name is locked");
+    assert_eq!(
+        store.loose_find_existing("main/nested")?.target,
+        main.target,
+        "the loose source is still present"
+    );
+    assert_eq!(
+        reflog_lines(&store, "refs/heads/main/nested")?,
+        log,
+        "along with its reflog"
+    );
+    Ok(())
+}
+
+#[test]
+fn rename_of_packed_refs_removes_them_from_packed_refs() -> crate::Result {
+    let (_keep, store) = store_writable("make_packed_ref_repository.sh")?;
+    let d1 = store.find_existing("refs/heads/d1")?;
+    assert!(d1.is_packed());
+
+    store
+        .transaction(Some(rename("refs/heads/d1", "refs/heads/d2")?), Fail::Immediately)
+        .commit(&committer())?;
+    assert!(store.find("refs/heads/d1")?.is_none());
+    assert_eq!(store.find_existing("refs/heads/d2")?.target(), d1.target());
+    Ok(())
+}
+
+#[test]
+fn invalid_renames_and_copies_fail_without_changing_anything() -> crate::Result {
+    let (_keep, store) = store_writable("make_packed_ref_repository.sh")?;
+    for (edit, expected) in vec![
+        (
+            copy("refs/heads/main", "refs/heads/main/copy")?,
+            "'refs/heads/main' exists; cannot create 'refs/heads/main/copy'",
+        ),
+        (
+            rename("refs/heads/dt1", "refs/remotes/origin")?,
+            "'refs/remotes/origin/HEAD' exists; cannot create 'refs/remotes/origin'",
+        ),
+        (
+            rename("refs/heads/d1", "refs/heads/dt1")?,
+            "Reference 'refs/heads/dt1' was not supposed to exist when writing it with value 134385f6d781b7e97062102c6a483440bfda2a03, but actual content was 134385f6d781b7e97062102c6a483440bfda2a03",
+        ),
+        (
+            rename("refs/heads/does-not-exist", "refs/heads/new")?,
+            "The reference 'refs/heads/does-not-exist' to rename or copy did not exist or could not be parsed",
+        ),
+        (
+            rename("refs/remotes/origin/HEAD", "refs/remotes/origin/new")?,
+            "The reference 'refs/remotes/origin/HEAD' is symbolic and can't be renamed or copied",
+        ),
+    ] {
+        let err = store
+            .transaction(Some(edit), Fail::Immediately)
+            .commit(&committer())
+            .expect_err("invalid");
+        assert_eq!(err.to_string(), expected);
+    }
+    assert!(store.find("refs/heads/d1")?.is_some());
+    assert!(store.find("refs/heads/dt1")?.is_some());
+    assert!(store.find("refs/heads/new")?.is_none());
+    assert!(store.find("refs/heads/main/copy")?.is_none());
+    Ok(())
+}
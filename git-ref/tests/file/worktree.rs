@@ -0,0 +1,120 @@
+use std::{convert::TryInto, path::Path};
+
+use git_lock::acquire::Fail;
+use git_ref::{
+    file,
+    mutable::Target,
+    transaction::{Change, Create, LogChange, RefEdit},
+};
+
+fn stores(root: &Path) -> (file::Store, file::Store) {
+    let common_dir = root.join("main/.git");
+    let main = file::Store::at(&common_dir, Default::default());
+    let linked = file::Store::for_linked_worktree(common_dir.join("worktrees/w1"), &common_dir, Default::default());
+    (main, linked)
+}
+
+fn target(store: &file::Store, name: &str) -> crate::Result<String> {
+    Ok(store.loose_find_existing(name)?.target.to_string())
+}
+
+#[test]
+fn per_worktree_refs_are_found_in_the_private_directory_of_their_worktree() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_worktree_repo.sh")?;
+    let (main, linked) = stores(&root);
+
+    assert_eq!(target(&main, "HEAD")?, "ref: refs/heads/main");
+    assert_eq!(target(&linked, "HEAD")?, "ref: refs/heads/w1");
+    assert_eq!(target(&linked, "main-worktree/HEAD")?, "ref: refs/heads/main");
+    assert_eq!(target(&main, "worktrees/w1/HEAD")?, "ref: refs/heads/w1");
+    assert_eq!(target(&linked, "worktrees/w1/HEAD")?, "ref: refs/heads/w1");
+
+    assert_eq!(
+        target(&linked, "w1")?,
+        target(&linked, "refs/bisect/good")?,
+        "shared refs are found in the common directory"
+    );
+    assert!(linked.loose_find("refs/bisect/main-good")?.is_none());
+    assert!(main.loose_find("refs/bisect/good")?.is_none());
+    assert!(linked.reflog_exists("HEAD")?);
+    assert_eq!(linked.packed_refs_path(), root.join("main/.git/packed-refs"));
+    Ok(())
+}
+
+#[test]
+fn iteration_merges_shared_and_per_worktree_refs() -> crate::Result {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_worktree_repo.sh")?;
+    let (main, linked) = stores(&root);
+    let names = |store: &file::Store| -> crate::Result<Vec<String>> {
+        Ok(store
+            .loose_iter()?
+            .map(|r| r.map(|r| r.name.as_bstr().to_string()))
+            .collect::<Result<_, _>>()?)
+    };
+    assert_eq!(
+        names(&main)?,
+        vec!["refs/bisect/main-good", "refs/heads/main", "refs/heads/w1"]
+    );
+    assert_eq!(
+        names(&linked)?,
+        vec![
+            "refs/bisect/good",
+            "refs/heads/main",
+            "refs/heads/w1",
+            "refs/worktree/private"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn transactions_write_per_worktree_refs_and_their_logs_into_the_private_directory() -> crate::Result {
+    let dir = git_testtools::scripted_fixture_repo_writable("make_worktree_repo.sh")?;
+    let (_main, linked) = stores(dir.path());
+    let id = linked
+        .loose_find_existing("HEAD")?
+        .peel_to_id_in_place(&linked)?
+        .to_owned();
+    let edits = ["refs/worktree/new", "refs/heads/new", "main-worktree/refs/bisect/bad"]
+        .iter()
+        .map(|name| {
+            Ok(RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        force_create_reflog: true,
+                        ..Default::default()
+                    },
+                    mode: Create::Only,
+                    new: Target::Peeled(id),
+                },
+                name: (*name).try_into()?,
+                deref: false,
+            })
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    let committer = git_actor::Signature {
+        name: "committer".into(),
+        email: "committer@example.com".into(),
+        time: git_actor::Time {
+            time: 1234,
+            offset: 0,
+            sign: git_actor::Sign::Plus,
+        },
+    };
+    linked.transaction(edits, Fail::Immediately).commit(&committer)?;
+
+    let common_dir = dir.path().join("main/.git");
+    let private_dir = common_dir.join("worktrees/w1");
+    for path in &[
+        private_dir.join("refs/worktree/new"),
+        private_dir.join("logs/refs/worktree/new"),
+        common_dir.join("refs/heads/new"),
+        common_dir.join("logs/refs/heads/new"),
+        common_dir.join("refs/bisect/bad"),
+        common_dir.join("logs/refs/bisect/bad"),
+    ] {
+        assert!(path.is_file(), "{} exists", path.display());
+    }
+    assert!(!common_dir.join("refs/worktree").exists());
+    Ok(())
+}
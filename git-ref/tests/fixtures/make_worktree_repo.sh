@@ -0,0 +1,15 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q main && cd main
+git config commit.gpgsign false
+
+git checkout -q -b main
+git commit -q --allow-empty -m c1
+git update-ref refs/bisect/main-good HEAD
+
+git worktree add -q ../w1 -b w1
+cd ../w1
+git commit -q --allow-empty -m c2
+git update-ref refs/bisect/good HEAD
+git update-ref refs/worktree/private HEAD
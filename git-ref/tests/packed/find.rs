@@ -179,3 +179,15 @@ fn find_speed() -> crate::Result {
     );
     Ok(())
 }
+
+#[test]
+fn names_sorting_after_the_last_record_are_not_found() -> crate::Result {
+    let packed_refs =
+        b"# pack-refs with: peeled fully-peeled sorted \n134385f6d781b7e97062102c6a483440bfda2a03 refs/heads/main\n";
+    let (_keep, path) = write_packed_refs_with(packed_refs)?;
+
+    let buf = packed::Buffer::open(path, 1024)?;
+    assert!(buf.find("refs/tags/v1")?.is_none());
+    assert!(buf.find("v1")?.is_none());
+    Ok(())
+}
@@ -0,0 +1,29 @@
+use git_actor::{Sign, Time};
+use git_hash::ObjectId;
+use git_ref::reftable;
+
+fn hex_to_id(hex: &str) -> ObjectId {
+    ObjectId::from_hex(hex.as_bytes()).expect("40 bytes hex")
+}
+
+fn empty_store() -> crate::Result<(tempfile::TempDir, reftable::Store)> {
+    let dir = tempfile::TempDir::new()?;
+    let store = reftable::Store::at(dir.path(), Default::default());
+    Ok((dir, store))
+}
+
+fn committer() -> git_actor::Signature {
+    git_actor::Signature {
+        name: "committer".into(),
+        email: "committer@example.com".into(),
+        time: Time {
+            time: 1234,
+            offset: 1800,
+            sign: Sign::Plus,
+        },
+    }
+}
+
+mod table;
+
+mod store;
@@ -0,0 +1,368 @@
+use std::convert::TryInto;
+
+use bstr::ByteSlice;
+use git_lock::acquire::Fail;
+use git_ref::{
+    mutable::Target,
+    reftable,
+    transaction::{Change, Create, LogChange, RefEdit, RefLog},
+};
+
+use crate::reftable::{committer, empty_store, hex_to_id};
+
+fn update(name: &str, new: Target, message: &str) -> RefEdit {
+    RefEdit {
+        change: Change::Update {
+            log: LogChange {
+                message: message.into(),
+                ..Default::default()
+            },
+            mode: Create::OrUpdate { previous: None },
+            new,
+        },
+        name: name.try_into().expect("valid"),
+        deref: false,
+    }
+}
+
+fn peeled(hex: &str) -> Target {
+    Target::Peeled(hex_to_id(hex))
+}
+
+fn symbolic(name: &str) -> Target {
+    Target::Symbolic(name.try_into().expect("valid"))
+}
+
+const A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+const B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+
+fn commit(store: &reftable::Store, edits: impl IntoIterator<Item = RefEdit>) -> crate::Result<Vec<RefEdit>> {
+    Ok(store.transaction(edits, Fail::Immediately).commit(&committer())?)
+}
+
+#[test]
+fn committed_edits_can_be_found_iterated_and_peeled() -> crate::Result {
+    let (_dir, store) = empty_store()?;
+    assert!(store.find("main")?.is_none(), "nothing there yet");
+    assert_eq!(store.iter()?.count(), 0);
+
+    commit(
+        &store,
+        vec![
+            update("refs/heads/main", peeled(A), "initial"),
+            update("HEAD", symbolic("refs/heads/main"), ""),
+        ],
+    )?;
+    commit(&store, Some(update("refs/tags/v1", peeled(B), "")))?;
+    assert!(store.tables_list_path().is_file());
+
+    let main = store.find_existing("main")?;
+    assert_eq!(main.name.as_bstr(), "refs/heads/main");
+    assert_eq!(main.target, peeled(A));
+    assert_eq!(main.update_index, 1);
+    assert_eq!(store.find_existing("v1")?.update_index, 2);
+
+    let mut head = store.find_existing("HEAD")?;
+    assert_eq!(head.target, symbolic("refs/heads/main"));
+    assert_eq!(head.peel_to_id_in_place(&store)?, hex_to_id(A));
+    assert_eq!(head.name.as_bstr(), "refs/heads/main", "peeling replaces the reference");
+
+    assert_eq!(
+        store.iter()?.map(|r| r.name.into_inner()).collect::<Vec<_>>(),
+        vec!["HEAD", "refs/heads/main", "refs/tags/v1"]
+    );
+    assert_eq!(
+        store
+            .iter_prefixed(b"refs/heads/".as_bstr())?
+            .map(|r| r.name.into_inner())
+            .collect::<Vec<_>>(),
+        vec!["refs/heads/main"]
+    );
+
+    let log: Vec<_> = store.reflog_iter("refs/heads/main")?.expect("reflog exists").collect();
+    assert_eq!(log.len(), 1);
+    assert!(log[0].previous_oid.is_null());
+    assert_eq!(log[0].new_oid, hex_to_id(A));
+    assert_eq!(log[0].message, "initial");
+    assert_eq!(log[0].signature, committer());
+    assert!(
+        store.reflog_iter("refs/tags/v1")?.is_none(),
+        "tags don't get a reflog by default"
+    );
+    Ok(())
+}
+
+#[test]
+fn tables_are_cached_until_their_list_changes() -> crate::Result {
+    let (dir, store) = empty_store()?;
+    assert!(store.shared_tables()?.is_empty(), "there is no list yet");
+    commit(&store, Some(update("refs/heads/main", peeled(A), "")))?;
+
+    let first = store.shared_tables()?;
+    let second = store.clone().shared_tables()?;
+    assert!(
+        std::sync::Arc::ptr_eq(&first, &second),
+        "unchanged lists are not reloaded"
+    );
+    assert_eq!(first.len(), 1);
+
+    let mut other = reftable::Store::at(dir.path(), Default::default());
+    other.auto_compact = false;
+    commit(&other, Some(update("refs/heads/dev", peeled(B), "")))?;
+    let third = store.shared_tables()?;
+    assert!(!std::sync::Arc::ptr_eq(&first, &third), "the changed list was reloaded");
+    assert_eq!(third.len(), 2);
+    assert!(store.find("dev")?.is_some(), "lookups see the new table as well");
+    Ok(())
+}
+
+#[test]
+fn updates_are_checked_against_the_current_state() -> crate::Result {
+    let (_dir, store) = empty_store()?;
+    commit(&store, Some(update("refs/heads/main", peeled(A), "")))?;
+
+    let mut edit = update("refs/heads/main", peeled(B), "");
+    if let Change::Update { mode, .. } = &mut edit.change {
+        *mode = Create::Only;
+    }
+    assert!(matches!(
+        store.transaction(Some(edit), Fail::Immediately).commit(&committer()),
+        Err(reftable::transaction::Error::MustNotExist { .. })
+    ));
+
+    let mut edit = update("refs/heads/main", peeled(B), "");
+    if let Change::Update { mode, .. } = &mut edit.change {
+        *mode = Create::OrUpdate {
+            previous: Some(peeled(B)),
+        };
+    }
+    assert!(matches!(
+        store.transaction(Some(edit), Fail::Immediately).commit(&committer()),
+        Err(reftable::transaction::Error::ReferenceOutOfDate { .. })
+    ));
+
+    let edits = commit(&store, Some(update("refs/heads/main", peeled(B), "second")))?;
+    assert_eq!(
+        edits[0].change.previous_value(),
+        Some(git_ref::Target::Peeled(&hex_to_id(A))),
+        "the previous value is filled in"
+    );
+    let log: Vec<_> = store.reflog_iter_rev("refs/heads/main")?.expect("exists").collect();
+    assert_eq!(log.len(), 2);
+    assert_eq!(log[0].previous_oid, hex_to_id(A), "most recent entry first");
+    assert_eq!(log[0].message, "second");
+    Ok(())
+}
+
+#[test]
+fn updates_through_symbolic_refs_are_logged_for_both() -> crate::Result {
+    let (_dir, store) = empty_store()?;
+    commit(
+        &store,
+        vec![
+            update("refs/heads/main", peeled(A), "initial"),
+            update("HEAD", symbolic("refs/heads/main"), ""),
+        ],
+    )?;
+    let mut edit = update("HEAD", peeled(B), "commit");
+    edit.deref = true;
+    commit(&store, Some(edit))?;
+
+    assert_eq!(store.find_existing("HEAD")?.target, symbolic("refs/heads/main"));
+    assert_eq!(store.find_existing("main")?.target, peeled(B));
+    for name in &["HEAD", "refs/heads/main"] {
+        let log: Vec<_> = store.reflog_iter_rev(*name)?.expect("exists").collect();
+        assert_eq!(log[0].previous_oid, hex_to_id(A), "{}", name);
+        assert_eq!(log[0].new_oid, hex_to_id(B), "{}", name);
+    }
+    Ok(())
+}
+
+#[test]
+fn deletions_hide_references_and_their_reflog() -> crate::Result {
+    let (_dir, store) = empty_store()?;
+    commit(
+        &store,
+        vec![
+            update("refs/heads/main", peeled(A), "initial"),
+            update("refs/heads/other", peeled(B), "initial"),
+        ],
+    )?;
+    let edits = commit(
+        &store,
+        Some(RefEdit {
+            change: Change::Delete {
+                previous: Some(Target::must_exist()),
+                log: RefLog::AndReference,
+            },
+            name: "refs/heads/main".try_into()?,
+            deref: false,
+        }),
+    )?;
+    assert_eq!(
+        edits[0].change.previous_value(),
+        Some(git_ref::Target::Peeled(&hex_to_id(A)))
+    );
+    assert!(store.find("main")?.is_none());
+    assert!(!store.reflog_exists("refs/heads/main")?);
+    assert!(store.reflog_exists("refs/heads/other")?);
+    assert_eq!(store.iter()?.count(), 1);
+
+    assert!(matches!(
+        store
+            .transaction(
+                Some(RefEdit {
+                    change: Change::Delete {
+                        previous: Some(Target::must_exist()),
+                        log: RefLog::AndReference,
+                    },
+                    name: "refs/heads/main".try_into()?,
+                    deref: false,
+                }),
+                Fail::Immediately
+            )
+            .commit(&committer()),
+        Err(reftable::transaction::Error::DeleteReferenceMustExist { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn a_locked_stack_fails_the_transaction() -> crate::Result {
+    let (_dir, store) = empty_store()?;
+    commit(&store, Some(update("refs/heads/main", peeled(A), "")))?;
+    let _lock = git_lock::Marker::acquire_to_hold_resource(store.tables_list_path(), Fail::Immediately, None)?;
+    assert!(matches!(
+        store
+            .transaction(Some(update("refs/heads/main", peeled(B), "")), Fail::Immediately)
+            .prepare(),
+        Err(reftable::transaction::Error::LockAcquire(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn tables_are_compacted_automatically_and_on_demand() -> crate::Result {
+    let (_dir, mut store) = empty_store()?;
+    for n in 0..32 {
+        commit(&store, Some(update(&format!("refs/heads/b{}", n), peeled(A), "")))?;
+    }
+    let tables = store.tables()?;
+    assert!(tables.len() < 8, "the stack is kept small, got {}", tables.len());
+    assert_eq!(tables.last().expect("present").max_update_index(), 32);
+    assert_eq!(store.iter()?.count(), 32);
+
+    store.auto_compact = false;
+    commit(
+        &store,
+        Some(RefEdit {
+            change: Change::Delete {
+                previous: None,
+                log: RefLog::AndReference,
+            },
+            name: "refs/heads/b0".try_into()?,
+            deref: false,
+        }),
+    )?;
+    assert!(store.compact(Fail::Immediately)? > 1);
+    let tables = store.tables()?;
+    assert_eq!(tables.len(), 1);
+    assert_eq!(
+        tables[0].refs().count(),
+        31,
+        "the deletion isn't needed anymore after merging all tables"
+    );
+    assert_eq!(
+        std::fs::read_dir(store.reftable_dir())?.count(),
+        2,
+        "the table and the list, without obsolete tables"
+    );
+    assert!(store.reflog_exists("refs/heads/b1")?);
+    Ok(())
+}
+
+#[test]
+fn renames_and_copies_carry_their_reflog_and_update_symbolic_refs() -> crate::Result {
+    let (_dir, store) = empty_store()?;
+    commit(
+        &store,
+        vec![
+            update("refs/heads/main", peeled(A), "initial"),
+            update("refs/heads/other", peeled(B), "initial"),
+            update("HEAD", symbolic("refs/heads/main"), ""),
+        ],
+    )?;
+    let relocate = |name: &str, new_name: &str, is_rename: bool| -> crate::Result<RefEdit> {
+        let (new_name, previous, message) = (new_name.try_into()?, None, "relocated".into());
+        Ok(RefEdit {
+            change: if is_rename {
+                Change::Rename {
+                    new_name,
+                    previous,
+                    message,
+                }
+            } else {
+                Change::Copy {
+                    new_name,
+                    previous,
+                    message,
+                }
+            },
+            name: name.try_into()?,
+            deref: false,
+        })
+    };
+
+    let edits = commit(
+        &store,
+        Some(relocate("refs/heads/main", "refs/heads/main/nested", true)?),
+    )?;
+    assert_eq!(edits.len(), 3, "the new reference and HEAD are updated as well");
+    assert!(store.find("refs/heads/main")?.is_none());
+    assert!(!store.reflog_exists("refs/heads/main")?);
+    assert_eq!(store.find_existing("HEAD")?.target, symbolic("refs/heads/main/nested"));
+    let log: Vec<_> = store
+        .reflog_iter("refs/heads/main/nested")?
+        .expect("moved")
+        .map(|line| (line.previous_oid, line.new_oid, line.message))
+        .collect();
+    assert_eq!(
+        log,
+        vec![
+            (hex_to_id(&"0".repeat(40)), hex_to_id(A), "initial".into()),
+            (hex_to_id(A), hex_to_id(A), "relocated".into())
+        ]
+    );
+
+    commit(&store, Some(relocate("refs/heads/other", "refs/heads/copy", false)?))?;
+    assert_eq!(store.find_existing("refs/heads/other")?.target, peeled(B));
+    assert_eq!(store.find_existing("refs/heads/copy")?.target, peeled(B));
+    assert_eq!(store.reflog_iter("refs/heads/other")?.expect("kept").count(), 1);
+    assert_eq!(store.reflog_iter("refs/heads/copy")?.expect("copied").count(), 2);
+
+    assert!(matches!(
+        store
+            .transaction(
+                Some(relocate("refs/heads/other", "refs/heads/main", false)?),
+                Fail::Immediately
+            )
+            .commit(&committer()),
+        Err(reftable::transaction::Error::NameConflict { .. })
+    ));
+    assert!(matches!(
+        store
+            .transaction(
+                Some(relocate("refs/heads/other", "refs/heads/copy", true)?),
+                Fail::Immediately
+            )
+            .commit(&committer()),
+        Err(reftable::transaction::Error::MustNotExist { .. })
+    ));
+    assert!(matches!(
+        store
+            .transaction(Some(relocate("HEAD", "refs/heads/head", true)?), Fail::Immediately)
+            .commit(&committer()),
+        Err(reftable::transaction::Error::SymbolicSource { .. })
+    ));
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use bstr::ByteSlice;
+use git_ref::{
+    file::log,
+    reftable::{
+        record::{Log, LogValue, Ref, RefValue},
+        table, Table,
+    },
+};
+
+use crate::reftable::{committer, hex_to_id};
+
+/// A table encoded by hand following the format documentation, with `HEAD` pointing to `refs/heads/main`, which has
+/// a single reflog entry.
+#[rustfmt::skip]
+const HANDCRAFTED: &[u8] = &[
+    // header: magic, version 1, block size 4096, update indices 1 to 2
+    0x52, 0x45, 0x46, 0x54, 0x01, 0x00, 0x10, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
+    // unpadded ref block of 94 bytes including the header,
+    // with the symbolic ref HEAD at update index 2 and refs/heads/main at update index 1
+    0x72, 0x00, 0x00, 0x5e,
+    0x00, 0x23, 0x48, 0x45, 0x41, 0x44, 0x01, 0x0f, 0x72, 0x65, 0x66, 0x73, 0x2f, 0x68, 0x65, 0x61, 0x64, 0x73, 0x2f,
+    0x6d, 0x61, 0x69, 0x6e,
+    0x00, 0x79, 0x72, 0x65, 0x66, 0x73, 0x2f, 0x68, 0x65, 0x61, 0x64, 0x73, 0x2f, 0x6d, 0x61, 0x69, 0x6e, 0x00,
+    0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa, 0xaa,
+    // a single restart point at offset 28
+    0x00, 0x00, 0x1c, 0x00, 0x01,
+    // log block of 114 bytes once inflated, including its 4 byte header, followed by 78 bytes of deflated records
+    0x67, 0x00, 0x00, 0x72,
+    0x78, 0x9c, 0x63, 0x68, 0x70, 0x2c, 0x4a, 0x4d, 0x2b, 0xd6, 0xcf, 0x48, 0x4d, 0x4c, 0x29, 0xd6, 0xcf, 0x4d, 0xcc,
+    0xcc, 0x63, 0xf8, 0x0f, 0x01, 0xff, 0x18, 0xb0, 0x80, 0x55, 0x58, 0x00, 0x8b, 0x5f, 0x62, 0x6e, 0xaa, 0x40, 0x1e,
+    0x90, 0x70, 0x48, 0xad, 0x48, 0xcc, 0x2d, 0xc8, 0x49, 0xd5, 0x4b, 0xce, 0xcf, 0x6d, 0x5d, 0x79, 0xee, 0x21, 0xc3,
+    0xff, 0x3a, 0x8e, 0xcc, 0xbc, 0xcc, 0x92, 0xcc, 0xc4, 0x1c, 0x2e, 0x06, 0x06, 0x16, 0x06, 0x46, 0x00, 0x72, 0x9f,
+    0x2a, 0xe3,
+    // footer: the header, followed by positions of which only the log position 94 is set, and the CRC32
+    0x52, 0x45, 0x46, 0x54, 0x01, 0x00, 0x10, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x5e,
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xef, 0x66, 0xda, 0xcc,
+];
+
+fn id(byte: u8) -> git_hash::ObjectId {
+    git_hash::ObjectId::from_20_bytes(&[byte; 20])
+}
+
+#[test]
+fn handcrafted_table_can_be_read() -> crate::Result {
+    let table = Table::from_bytes(HANDCRAFTED.to_vec())?;
+    assert_eq!(table.min_update_index(), 1);
+    assert_eq!(table.max_update_index(), 2);
+
+    let refs = table.refs().collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        refs,
+        vec![
+            Ref {
+                name: "HEAD".into(),
+                update_index: 2,
+                value: RefValue::Symbolic("refs/heads/main".into())
+            },
+            Ref {
+                name: "refs/heads/main".into(),
+                update_index: 1,
+                value: RefValue::Peeled(hex_to_id("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"))
+            }
+        ]
+    );
+    assert_eq!(table.find_ref(b"refs/heads/main".as_bstr())?.as_ref(), refs.get(1));
+    assert_eq!(table.find_ref(b"refs/heads/other".as_bstr())?, None);
+
+    let logs = table.logs_of(b"refs/heads/main".as_bstr())?;
+    assert_eq!(logs.len(), 1);
+    match &logs[0].value {
+        LogValue::Update(line) => {
+            assert!(line.previous_oid.is_null());
+            assert_eq!(line.new_oid, hex_to_id("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"));
+            assert_eq!(line.signature.email, "name@example.com");
+            assert_eq!(line.signature.time.time, 1700000000);
+            assert_eq!(line.signature.time.offset, -5400, "-0130 is stored as decimal number");
+            assert_eq!(line.message, "initial", "the trailing newline is removed");
+        }
+        LogValue::Deletion => unreachable!("it's an update"),
+    }
+    assert!(table.logs_of(b"HEAD".as_bstr())?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn corrupt_footers_are_detected() {
+    let mut data = HANDCRAFTED.to_vec();
+    let len = data.len();
+    data[len - 1] ^= 1;
+    assert!(matches!(Table::from_bytes(data), Err(table::decode::Error::Checksum)));
+    assert!(matches!(
+        Table::from_bytes(HANDCRAFTED[..50].to_vec()),
+        Err(table::decode::Error::Header)
+    ));
+}
+
+#[test]
+fn writing_the_handcrafted_table_yields_the_same_records() -> crate::Result {
+    let original = Table::from_bytes(HANDCRAFTED.to_vec())?;
+    let refs = original.refs().collect::<Result<Vec<_>, _>>()?;
+    let logs = original.logs().collect::<Result<Vec<_>, _>>()?;
+    let written = table::write(&refs, &logs, 1, 2, Default::default())?;
+    assert_eq!(
+        &written[..94],
+        &HANDCRAFTED[..94],
+        "a single unpadded ref block is followed by the log block, whose compression may differ"
+    );
+
+    let table = Table::from_bytes(written)?;
+    assert_eq!(table.refs().collect::<Result<Vec<_>, _>>()?, refs);
+    assert_eq!(table.logs().collect::<Result<Vec<_>, _>>()?, logs);
+    Ok(())
+}
+
+#[test]
+fn many_refs_and_logs_round_trip_through_multi_level_indices() -> crate::Result {
+    let refs: Vec<_> = (0..3000u32)
+        .map(|n| Ref {
+            name: format!("refs/heads/branch-{:05}", n).into(),
+            update_index: 1 + u64::from(n % 5),
+            value: match n % 4 {
+                0 => RefValue::Peeled(id((n % 251) as u8)),
+                1 => RefValue::PeeledWithObject {
+                    target: id((n % 251) as u8),
+                    peeled: id(((n + 1) % 251) as u8),
+                },
+                2 => RefValue::Symbolic("refs/heads/main".into()),
+                _ => RefValue::Deletion,
+            },
+        })
+        .collect();
+    let logs: Vec<_> = (0..500u32)
+        .flat_map(|n| {
+            (1..=3u64).rev().map(move |update_index| Log {
+                name: format!("refs/heads/branch-{:05}", n).into(),
+                update_index,
+                value: if update_index == 2 {
+                    LogValue::Deletion
+                } else {
+                    LogValue::Update(log::mutable::Line {
+                        previous_oid: id(update_index as u8),
+                        new_oid: id(update_index as u8 + 1),
+                        signature: committer(),
+                        message: format!("update {}", update_index).into(),
+                    })
+                },
+            })
+        })
+        .collect();
+    let options = table::WriteOptions {
+        block_size: 256,
+        ..Default::default()
+    };
+    let table = Table::from_bytes(table::write(&refs, &logs, 1, 5, options)?)?;
+
+    assert_eq!(table.refs().collect::<Result<Vec<_>, _>>()?, refs);
+    assert_eq!(table.logs().collect::<Result<Vec<_>, _>>()?, logs);
+    for r in refs.iter().step_by(7) {
+        assert_eq!(table.find_ref(r.name.as_ref())?.as_ref(), Some(r), "via the index");
+    }
+    assert_eq!(table.find_ref(b"refs/heads/zzz".as_bstr())?, None);
+    assert_eq!(table.find_ref(b"refs/heads/a".as_bstr())?, None);
+    assert_eq!(
+        table.logs_of(b"refs/heads/branch-00042".as_bstr())?,
+        logs[42 * 3..43 * 3].to_vec()
+    );
+
+    let pointing_to = table.refs_pointing_to(&id(7))?;
+    let expected: Vec<_> = refs
+        .iter()
+        .filter(|r| match &r.value {
+            RefValue::Peeled(target) => *target == id(7),
+            RefValue::PeeledWithObject { target, peeled } => *target == id(7) || *peeled == id(7),
+            _ => false,
+        })
+        .cloned()
+        .collect();
+    assert!(!expected.is_empty());
+    assert_eq!(pointing_to, expected, "found via the object index");
+    Ok(())
+}
+
+#[test]
+fn empty_tables_only_have_a_header_and_footer() -> crate::Result {
+    let data = table::write(&[], &[], 1, 1, Default::default())?;
+    assert_eq!(data.len(), 24 + 68);
+    let table = Table::from_bytes(data)?;
+    assert_eq!(table.refs().count(), 0);
+    assert_eq!(table.logs().count(), 0);
+    Ok(())
+}
+
+#[test]
+fn unsorted_records_are_rejected() {
+    let r = |name: &str| Ref {
+        name: name.into(),
+        update_index: 1,
+        value: RefValue::Deletion,
+    };
+    assert!(matches!(
+        table::write(&[r("refs/b"), r("refs/a")], &[], 1, 1, Default::default()),
+        Err(table::write::Error::Unsorted { .. })
+    ));
+    assert!(matches!(
+        table::write(&[r("refs/a")], &[], 2, 2, Default::default()),
+        Err(table::write::Error::UpdateIndexOutOfRange { .. })
+    ));
+}
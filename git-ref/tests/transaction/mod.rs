@@ -95,6 +95,103 @@ mod refedit_ext {
         );
     }
 
+    #[test]
+    fn renames_and_copies_add_edits_for_the_new_name_and_symbolic_referrers() -> crate::Result {
+        use git_hash::ObjectId;
+        use git_ref::transaction::{Create, LogChange};
+
+        let store = MockStore::with(vec![
+            ("refs/heads/main", Target::Peeled(ObjectId::null_sha1())),
+            ("refs/heads/other", Target::Peeled(ObjectId::null_sha1())),
+        ]);
+        let relocate = |name: &str, new_name: &str, is_rename: bool| -> crate::Result<RefEdit> {
+            let (new_name, previous, message) = (new_name.try_into()?, None, "message".into());
+            Ok(RefEdit {
+                change: if is_rename {
+                    Change::Rename {
+                        new_name,
+                        previous,
+                        message,
+                    }
+                } else {
+                    Change::Copy {
+                        new_name,
+                        previous,
+                        message,
+                    }
+                },
+                name: name.try_into()?,
+                deref: true,
+            })
+        };
+        let mut edits = vec![
+            relocate("refs/heads/main", "refs/heads/new", true)?,
+            relocate("refs/heads/other", "refs/heads/copy", false)?,
+        ];
+        edits.extend_with_renames_and_copies(
+            |n| store.find_existing(n),
+            |name| {
+                Ok(if name.as_bstr() == "refs/heads/main" {
+                    vec!["HEAD".try_into().expect("valid")]
+                } else {
+                    Vec::new()
+                })
+            },
+            |_, e| e,
+        )?;
+        store.assert_empty();
+
+        let create = |name: &str| -> crate::Result<RefEdit> {
+            Ok(RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: "message".into(),
+                    },
+                    mode: Create::Only,
+                    new: Target::Peeled(ObjectId::null_sha1()),
+                },
+                name: name.try_into()?,
+                deref: false,
+            })
+        };
+        let mut main = relocate("refs/heads/main", "refs/heads/new", true)?;
+        let mut other = relocate("refs/heads/other", "refs/heads/copy", false)?;
+        for edit in [&mut main, &mut other].iter_mut() {
+            edit.deref = false;
+            if let Change::Rename { previous, .. } | Change::Copy { previous, .. } = &mut edit.change {
+                *previous = Some(Target::Peeled(ObjectId::null_sha1()));
+            }
+        }
+        assert_eq!(
+            edits,
+            vec![
+                main,
+                other,
+                create("refs/heads/new")?,
+                RefEdit {
+                    change: Change::Update {
+                        log: LogChange {
+                            mode: RefLog::AndReference,
+                            force_create_reflog: false,
+                            message: "message".into(),
+                        },
+                        mode: Create::OrUpdate {
+                            previous: Some(Target::Symbolic("refs/heads/main".try_into()?)),
+                        },
+                        new: Target::Symbolic("refs/heads/new".try_into()?),
+                    },
+                    name: "HEAD".try_into()?,
+                    deref: false,
+                },
+                create("refs/heads/copy")?,
+            ],
+            "the previous value is set, symbolic refs are only adjusted for renames, and nothing is dereferenced"
+        );
+        Ok(())
+    }
+
     mod splitting {
         use crate::transaction::refedit_ext::MockStore;
         use git_hash::ObjectId;
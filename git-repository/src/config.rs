@@ -0,0 +1,134 @@
+//! The configuration of a repository, cascading from the system and global levels over the repository and worktree levels
+//! to values passed on the command-line.
+use std::path::Path;
+
+use git_config::{
+    fs::{Config, Origin, Source},
+    values::Boolean,
+};
+use git_object::bstr::{BString, ByteSlice};
+
+use crate::Repository;
+
+/// Load the configuration for the repository whose git directory is `git_dir` and whose shared directory is `common_dir`,
+/// with the levels to load controlled by `options`.
+pub(crate) fn load(
+    git_dir: &Path,
+    common_dir: &Path,
+    options: &crate::open::Options,
+) -> Result<Config, crate::open::Error> {
+    let mut config = Config::new();
+    let include_options = git_config::file::include::Options {
+        git_dir: Some(git_dir.to_owned()),
+        branch_name: head_branch_name(git_dir),
+        ..Default::default()
+    };
+    let push_file = |config: &mut Config, source: Source, path: std::path::PathBuf| {
+        config.push_file(source, path, &include_options)
+    };
+    if options.system_config {
+        for path in Source::System.default_paths() {
+            push_file(&mut config, Source::System, path)?;
+        }
+    }
+    if options.global_config {
+        for path in Source::Global.default_paths() {
+            push_file(&mut config, Source::Global, path)?;
+        }
+    }
+    push_file(&mut config, Source::Repository, common_dir.join("config"))?;
+    if is_true(&config, "extensions", "worktreeConfig") == Some(true) {
+        push_file(&mut config, Source::Worktree, git_dir.join("config.worktree"))?;
+    }
+    if !options.config_overrides.is_empty() {
+        config.push_cli_values(options.config_overrides.iter().map(String::as_str))?;
+        config.resolve_hasconfig_includes()?;
+    }
+    Ok(config)
+}
+
+/// Return the name of the branch `HEAD` points to, if it is a symbolic reference.
+fn head_branch_name(git_dir: &Path) -> Option<String> {
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    head.strip_prefix("ref: ").map(|name| name.trim_end().to_owned())
+}
+
+pub(crate) fn is_true(config: &Config, section: &str, key: &str) -> Option<bool> {
+    config.value::<Boolean<'_>>(section, None, key).ok().map(Into::into)
+}
+
+/// Return how to write reference logs according to `core.logAllRefUpdates`, which defaults to writing them unless the
+/// repository `is_bare`.
+pub(crate) fn write_reflog(config: &Config, is_bare: bool) -> git_ref::file::WriteReflog {
+    let is_always = config
+        .get_raw_value("core", None, "logAllRefUpdates")
+        .map_or(false, |value| value.eq_ignore_ascii_case(b"always"));
+    match is_true(config, "core", "logAllRefUpdates") {
+        Some(true) => git_ref::file::WriteReflog::Normal,
+        Some(false) => git_ref::file::WriteReflog::Disable,
+        None if is_always || !is_bare => git_ref::file::WriteReflog::Normal,
+        None => git_ref::file::WriteReflog::Disable,
+    }
+}
+
+/// Return the path of the file with exclude patterns for all repositories as configured in `core.excludesFile`, which
+/// defaults to `$XDG_CONFIG_HOME/git/ignore`, or `None` if it can't be determined.
+pub(crate) fn excludes_file(config: &Config) -> Option<std::path::PathBuf> {
+    match config.value::<git_config::values::Path<'_>>("core", None, "excludesFile") {
+        Ok(path) => path.interpolate(None, None).ok().map(|path| path.into_owned()),
+        Err(_) => std::env::var_os("XDG_CONFIG_HOME")
+            .map(std::path::PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| std::path::PathBuf::from(home).join(".config")))
+            .map(|config| config.join("git").join("ignore")),
+    }
+}
+
+/// Return the values of `safe.directory` from the levels `git` reads them from, which excludes the repository and worktree
+/// levels as these can't be trusted.
+pub(crate) fn safe_directories(config: &Config) -> Vec<std::path::PathBuf> {
+    config
+        .files()
+        .filter(|(origin, _)| matches!(origin.source, Source::System | Source::Global | Source::Cli))
+        .filter_map(|(_, file)| file.get_raw_multi_value("safe", None, "directory").ok())
+        .flatten()
+        .map(|value| git_config::values::normalize_cow(value).to_path_lossy().into_owned())
+        .collect()
+}
+
+/// Configuration values driving the behaviour of a repository.
+impl Repository {
+    /// Return the amount of hex characters to abbreviate object ids to as configured in `core.abbrev`, which defaults to 7.
+    ///
+    /// A value of `no` disables abbreviation, while invalid values fall back to the default. Use
+    /// [`git_config::typed::Core`] to validate them instead.
+    pub fn abbrev_len(&self) -> usize {
+        let full = git_hash::Kind::Sha1.len_in_hex();
+        git_config::typed::Core::from_config(&self.config)
+            .map(|core| core.abbrev_len(full))
+            .unwrap_or(7)
+    }
+
+    /// Return the value of `user.name`, if set.
+    pub fn user_name(&self) -> Option<BString> {
+        git_config::typed::User::from_config(&self.config).name
+    }
+
+    /// Return the value of `user.email`, if set.
+    pub fn user_email(&self) -> Option<BString> {
+        git_config::typed::User::from_config(&self.config).email
+    }
+
+    /// Return the configuration of the remote with the given `name` with its urls rewritten by `url.<base>.insteadOf`,
+    /// or `None` if there is no such remote.
+    pub fn remote(&self, name: &str) -> Result<Option<git_config::typed::Remote>, git_config::typed::Error> {
+        git_config::typed::Remote::from_config(&self.config, name)
+    }
+
+    /// Return the origin of the value of `key` in `section`, if it is set.
+    pub fn config_origin(&self, section: &str, subsection: Option<&str>, key: &str) -> Option<&Origin> {
+        self.config
+            .get_raw_value_with_origin(section, subsection, key)
+            .ok()
+            .map(|(_, origin)| origin)
+    }
+}
@@ -85,10 +85,23 @@ pub use path::Path;
 
 pub mod repository;
 
+pub mod open;
+
+pub mod config;
+
+pub mod worktree;
+pub use worktree::Worktree;
+
+pub mod submodule;
+pub use submodule::Submodule;
+
+mod user;
+
 pub struct Repository {
     pub refs: git_ref::file::Store,
     pub working_tree: Option<PathBuf>,
     pub odb: git_odb::linked::Store,
+    pub config: git_config::fs::Config,
 }
 
 impl Repository {
@@ -102,6 +115,11 @@ impl Repository {
     pub fn git_dir(&self) -> &std::path::Path {
         &self.refs.base
     }
+    /// Return the directory shared by all worktrees, which is the [git directory][Repository::git_dir()] unless this is
+    /// a linked worktree.
+    pub fn common_dir(&self) -> &std::path::Path {
+        self.refs.common_dir()
+    }
     pub fn objects_dir(&self) -> &std::path::Path {
         &self.odb.dbs[0].loose.path
     }
@@ -0,0 +1,409 @@
+//! Open repositories with [`Repository::open_opts()`] or [`Repository::discover_opts()`] while controlling which environment
+//! variables are honoured, where discovery stops and which repositories are trusted.
+use std::path::{Path, PathBuf};
+
+use quick_error::quick_error;
+
+use crate::Repository;
+
+/// Options for opening or discovering a repository, with the defaults ignoring all environment variables.
+///
+/// Values read from the environment override the ones set explicitly, as environment variables do in `git`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub(crate) git_dir: Option<PathBuf>,
+    pub(crate) work_tree: Option<PathBuf>,
+    pub(crate) object_dir: Option<PathBuf>,
+    pub(crate) alternate_object_dirs: Vec<PathBuf>,
+    pub(crate) ceiling_dirs: Vec<PathBuf>,
+    pub(crate) cross_fs: bool,
+    pub(crate) check_ownership: bool,
+    pub(crate) safe_directories: Vec<PathBuf>,
+    pub(crate) system_config: bool,
+    pub(crate) global_config: bool,
+    pub(crate) config_overrides: Vec<String>,
+    pub(crate) git_dir_from_env: bool,
+    pub(crate) object_dirs_from_env: bool,
+    pub(crate) ceiling_dirs_from_env: bool,
+    pub(crate) cross_fs_from_env: bool,
+    pub(crate) config_from_env: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            git_dir: None,
+            work_tree: None,
+            object_dir: None,
+            alternate_object_dirs: Vec::new(),
+            ceiling_dirs: Vec::new(),
+            cross_fs: false,
+            check_ownership: true,
+            safe_directories: Vec::new(),
+            system_config: true,
+            global_config: true,
+            config_overrides: Vec::new(),
+            git_dir_from_env: false,
+            object_dirs_from_env: false,
+            ceiling_dirs_from_env: false,
+            cross_fs_from_env: false,
+            config_from_env: false,
+        }
+    }
+}
+
+/// Builder methods
+impl Options {
+    /// Return options which honour all environment variables, like `git` does.
+    pub fn from_env() -> Self {
+        Options::default()
+            .git_dir_from_env(true)
+            .object_dirs_from_env(true)
+            .ceiling_dirs_from_env(true)
+            .cross_fs_from_env(true)
+            .config_from_env(true)
+    }
+
+    /// Use `git_dir` as git directory instead of opening or discovering one, like `GIT_DIR` does.
+    pub fn git_dir(mut self, git_dir: impl Into<PathBuf>) -> Self {
+        self.git_dir = Some(git_dir.into());
+        self
+    }
+
+    /// Use `work_tree` as working tree instead of the one belonging to the git directory, like `GIT_WORK_TREE` does.
+    pub fn work_tree(mut self, work_tree: impl Into<PathBuf>) -> Self {
+        self.work_tree = Some(work_tree.into());
+        self
+    }
+
+    /// Read objects from `object_dir` instead of the `objects` directory of the repository, like `GIT_OBJECT_DIRECTORY` does.
+    pub fn object_dir(mut self, object_dir: impl Into<PathBuf>) -> Self {
+        self.object_dir = Some(object_dir.into());
+        self
+    }
+
+    /// Read objects from `dirs` as well, in addition to the alternates of the repository, like `GIT_ALTERNATE_OBJECT_DIRECTORIES` does.
+    pub fn alternate_object_dirs(mut self, dirs: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        self.alternate_object_dirs = dirs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Don't look for repositories in the given `dirs` when discovering them, like `GIT_CEILING_DIRECTORIES` does.
+    ///
+    /// The directory discovery starts in is always searched, even if it's a ceiling directory. Relative paths are ignored.
+    pub fn ceiling_dirs(mut self, dirs: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        self.ceiling_dirs = dirs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// If `toggle` is true, continue discovery in parent directories on other file systems, like
+    /// `GIT_DISCOVERY_ACROSS_FILESYSTEM` does. Defaults to false.
+    pub fn cross_fs(mut self, toggle: bool) -> Self {
+        self.cross_fs = toggle;
+        self
+    }
+
+    /// If `toggle` is true, refuse to open repositories owned by someone else than the current user unless they are
+    /// listed as [safe directories][Options::safe_directories()]. Defaults to true.
+    ///
+    /// Ownership is only checked on unix, other platforms don't have this protection yet.
+    pub fn check_ownership(mut self, toggle: bool) -> Self {
+        self.check_ownership = toggle;
+        self
+    }
+
+    /// Trust the repositories whose working tree, or git directory if bare, is one of `dirs` even if they are owned by
+    /// someone else, in addition to the ones listed in `safe.directory` of the system and global configuration.
+    ///
+    /// A directory of `*` trusts all repositories.
+    pub fn safe_directories(mut self, dirs: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        self.safe_directories = dirs.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// If `toggle` is true, load the system configuration at `/etc/gitconfig`. Defaults to true.
+    pub fn system_config(mut self, toggle: bool) -> Self {
+        self.system_config = toggle;
+        self
+    }
+
+    /// If `toggle` is true, load the configuration of the current user at `$XDG_CONFIG_HOME/git/config` and `~/.gitconfig`.
+    /// Defaults to true.
+    pub fn global_config(mut self, toggle: bool) -> Self {
+        self.global_config = toggle;
+        self
+    }
+
+    /// Override configuration values with `values` of the form `section[.subsection].key[=value]`, like `git -c` does.
+    pub fn config_overrides(mut self, values: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.config_overrides = values.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// If `toggle` is true, read the git directory and working tree from `GIT_DIR` and `GIT_WORK_TREE`.
+    pub fn git_dir_from_env(mut self, toggle: bool) -> Self {
+        self.git_dir_from_env = toggle;
+        self
+    }
+
+    /// If `toggle` is true, read object directories from `GIT_OBJECT_DIRECTORY` and `GIT_ALTERNATE_OBJECT_DIRECTORIES`.
+    pub fn object_dirs_from_env(mut self, toggle: bool) -> Self {
+        self.object_dirs_from_env = toggle;
+        self
+    }
+
+    /// If `toggle` is true, read additional ceiling directories from `GIT_CEILING_DIRECTORIES`.
+    pub fn ceiling_dirs_from_env(mut self, toggle: bool) -> Self {
+        self.ceiling_dirs_from_env = toggle;
+        self
+    }
+
+    /// If `toggle` is true, read whether to cross file systems during discovery from `GIT_DISCOVERY_ACROSS_FILESYSTEM`.
+    pub fn cross_fs_from_env(mut self, toggle: bool) -> Self {
+        self.cross_fs_from_env = toggle;
+        self
+    }
+
+    /// If `toggle` is true, don't load the system configuration if `GIT_CONFIG_NOSYSTEM` is set.
+    pub fn config_from_env(mut self, toggle: bool) -> Self {
+        self.config_from_env = toggle;
+        self
+    }
+}
+
+impl Options {
+    /// Apply the values of all enabled environment variables.
+    pub(crate) fn apply_environment(mut self) -> Self {
+        let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty());
+        if self.git_dir_from_env {
+            if let Some(git_dir) = var("GIT_DIR") {
+                self.git_dir = Some(git_dir.into());
+            }
+            if let Some(work_tree) = var("GIT_WORK_TREE") {
+                self.work_tree = Some(work_tree.into());
+            }
+        }
+        if self.object_dirs_from_env {
+            if let Some(object_dir) = var("GIT_OBJECT_DIRECTORY") {
+                self.object_dir = Some(object_dir.into());
+            }
+            if let Some(dirs) = var("GIT_ALTERNATE_OBJECT_DIRECTORIES") {
+                self.alternate_object_dirs = std::env::split_paths(&dirs).collect();
+            }
+        }
+        if self.ceiling_dirs_from_env {
+            if let Some(dirs) = var("GIT_CEILING_DIRECTORIES") {
+                self.ceiling_dirs.extend(std::env::split_paths(&dirs));
+            }
+        }
+        if self.cross_fs_from_env {
+            if let Some(value) = var("GIT_DISCOVERY_ACROSS_FILESYSTEM") {
+                self.cross_fs = is_true(&value);
+            }
+        }
+        if self.config_from_env {
+            if let Some(value) = var("GIT_CONFIG_NOSYSTEM") {
+                self.system_config &= !is_true(&value);
+            }
+        }
+        self
+    }
+
+    fn is_safe_directory(&self, dir: &Path, config: &git_config::fs::Config) -> bool {
+        let dir = absolute(dir);
+        self.safe_directories
+            .iter()
+            .chain(crate::config::safe_directories(config).iter())
+            .any(|safe| safe.as_os_str() == "*" || absolute(safe) == dir)
+    }
+}
+
+fn is_true(value: &std::ffi::OsStr) -> bool {
+    let value = value.to_string_lossy().to_ascii_lowercase();
+    matches!(value.as_str(), "true" | "yes" | "on" | "1")
+}
+
+quick_error! {
+    /// The error returned by [`Repository::open_opts()`] and when opening repositories in general.
+    #[derive(Debug)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        NotARepository { source: crate::path::is_git::Error, path: PathBuf } {
+            display("'{}' is not a git repository", path.display())
+            source(source)
+        }
+        Io { source: std::io::Error, path: PathBuf } {
+            display("Could not read '{}'", path.display())
+            source(source)
+        }
+        UnsafeRepository(path: PathBuf) {
+            display("The repository at '{}' is owned by someone else, add it to the safe directories to trust it", path.display())
+        }
+        Config(err: git_config::file::include::Error) {
+            display("Could not load the configuration")
+            from()
+            source(err)
+        }
+        ConfigOverride(err: git_config::fs::InvalidCliValue) {
+            display("Could not apply a configuration override")
+            from()
+            source(err)
+        }
+        CommonDir(err: std::io::Error) {
+            display("Could not read the commondir file")
+            from()
+            source(err)
+        }
+        ObjectStoreInitialization(err: git_odb::linked::init::Error) {
+            display("Could not initialize the object database")
+            from()
+            source(err)
+        }
+    }
+}
+
+impl Repository {
+    /// Open the repository at `path`, which is either a working tree or a git directory, without searching parent directories
+    /// and without honouring any environment variables.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Repository::open_opts(path, Options::default())
+    }
+
+    /// Open the repository at `path`, which is either a working tree or a git directory, without searching parent directories.
+    ///
+    /// If the git directory is set in `options`, `path` is the working tree unless it's a bare repository.
+    pub fn open_opts(path: impl AsRef<Path>, options: Options) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let options = options.apply_environment();
+        let location = match &options.git_dir {
+            Some(git_dir) => with_git_dir(git_dir, path, options.object_dir.as_deref())?,
+            None => {
+                let dot_git = path.join(".git");
+                if dot_git.is_file() {
+                    let git_dir = crate::path::from_gitdir_file(&dot_git).map_err(|err| Error::Io {
+                        source: err,
+                        path: dot_git,
+                    })?;
+                    is_git(&git_dir, options.object_dir.as_deref())?;
+                    crate::Path::LinkedWorkTree {
+                        work_dir: path.to_owned(),
+                        git_dir,
+                    }
+                } else if dot_git.is_dir() {
+                    crate::Path::from_dot_git_dir(&dot_git, is_git(&dot_git, options.object_dir.as_deref())?)
+                } else {
+                    crate::Path::from_dot_git_dir(path, is_git(path, options.object_dir.as_deref())?)
+                }
+            }
+        };
+        Repository::from_location(location, options)
+    }
+
+    /// Open the repository at the given `location`, applying all `options` which don't affect discovery.
+    pub(crate) fn from_location(location: crate::Path, options: Options) -> Result<Self, Error> {
+        let (git_dir, working_tree) = match location {
+            crate::Path::WorkingTree(working_tree) => (working_tree.join(".git"), Some(working_tree)),
+            crate::Path::LinkedWorkTree { work_dir, git_dir } => (git_dir, Some(work_dir)),
+            crate::Path::Repository(repository) => (repository, None),
+        };
+        Repository::from_paths(git_dir, working_tree, &options)
+    }
+
+    /// Open the repository at `git_dir` with `working_tree` being its working tree unless it's bare, applying all `options`
+    /// which don't affect discovery.
+    ///
+    /// If `git_dir` belongs to a linked worktree, objects and shared references are read from the directory
+    /// named in its `commondir` file. Unless overridden in `options`, `core.bare` decides if there is a working tree.
+    pub(crate) fn from_paths(
+        git_dir: impl Into<PathBuf>,
+        working_tree: Option<PathBuf>,
+        options: &Options,
+    ) -> Result<Self, Error> {
+        let git_dir = git_dir.into();
+        let common_dir = crate::path::common_dir(&git_dir)?;
+        let config = crate::config::load(&git_dir, common_dir.as_deref().unwrap_or(&git_dir), options)?;
+        let working_tree = match (&options.work_tree, crate::config::is_true(&config, "core", "bare")) {
+            (Some(work_tree), _) => Some(work_tree.to_owned()),
+            (None, Some(true)) => None,
+            (None, Some(false)) if working_tree.is_none() && git_dir.file_name() == Some(".git".as_ref()) => {
+                git_dir.parent().map(ToOwned::to_owned)
+            }
+            (None, _) => working_tree,
+        };
+        if options.check_ownership {
+            let dir = working_tree.as_deref().unwrap_or(&git_dir);
+            if !is_owned_by_current_user(dir).map_err(|err| Error::Io {
+                source: err,
+                path: dir.to_owned(),
+            })? && !options.is_safe_directory(dir, &config)
+            {
+                return Err(Error::UnsafeRepository(dir.to_owned()));
+            }
+        }
+
+        let write_reflog = crate::config::write_reflog(&config, working_tree.is_none());
+        let refs = match common_dir {
+            Some(common_dir) => git_ref::file::Store::for_linked_worktree(git_dir, common_dir, write_reflog),
+            None => git_ref::file::Store::at(git_dir, write_reflog),
+        };
+        let mut odb = git_odb::linked::Store::at(
+            options
+                .object_dir
+                .clone()
+                .unwrap_or_else(|| refs.common_dir().join("objects")),
+        )?;
+        for dir in &options.alternate_object_dirs {
+            odb.dbs.extend(git_odb::linked::Store::at(dir)?.dbs);
+        }
+        Ok(Repository {
+            odb,
+            refs,
+            working_tree,
+            config,
+        })
+    }
+}
+
+/// Return the location of the repository whose git directory is `git_dir`, with `path` being its working tree unless it's bare.
+pub(crate) fn with_git_dir(git_dir: &Path, path: &Path, object_dir: Option<&Path>) -> Result<crate::Path, Error> {
+    Ok(match is_git(git_dir, object_dir)? {
+        crate::Kind::Bare => crate::Path::Repository(git_dir.to_owned()),
+        crate::Kind::WorkingTree => crate::Path::LinkedWorkTree {
+            work_dir: path.to_owned(),
+            git_dir: git_dir.to_owned(),
+        },
+    })
+}
+
+fn is_git(git_dir: &Path, object_dir: Option<&Path>) -> Result<crate::Kind, Error> {
+    crate::path::is_git::is_git_with_object_dir(git_dir, object_dir).map_err(|err| Error::NotARepository {
+        source: err,
+        path: git_dir.to_owned(),
+    })
+}
+
+/// Return `path` as absolute path, without resolving symlinks.
+pub(crate) fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        path.to_owned()
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(path))
+            .unwrap_or_else(|_| path.to_owned())
+    }
+}
+
+/// Return true if `path` is owned by the effective user of this process, like `git` checks it.
+#[cfg(unix)]
+fn is_owned_by_current_user(path: &Path) -> std::io::Result<bool> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(std::fs::metadata(path)?.uid() == crate::user::effective_uid())
+}
+
+/// Ownership isn't checked on this platform, so all repositories are considered to be owned by the current user and
+/// there is no protection through `safe.directory`.
+#[cfg(not(unix))]
+fn is_owned_by_current_user(_path: &Path) -> std::io::Result<bool> {
+    // TODO: check the owner's SID on windows
+    Ok(true)
+}
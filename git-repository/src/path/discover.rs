@@ -14,29 +14,103 @@ pub mod existing {
             NoGitRepository(path: PathBuf) {
                 display("Could find a git repository in '{}' or in any of its parents", path.display())
             }
+            NoGitRepositoryWithinCeiling { path: PathBuf, ceiling: PathBuf } {
+                display("Could find a git repository in '{}' or in any of its parents up to the ceiling directory '{}'", path.display(), ceiling.display())
+            }
+            NoGitRepositoryWithinFs { path: PathBuf, limit: PathBuf } {
+                display("Could find a git repository in '{}' or in any of its parents up to the file system boundary at '{}'", path.display(), limit.display())
+            }
+            Open(err: crate::open::Error) {
+                display("Could not use the git directory set in the options")
+                from()
+                source(err)
+            }
         }
     }
 }
 
 /// Returns the working tree if possible and the found repository is not bare or the git repository itself.
 pub fn existing(directory: impl AsRef<Path>) -> Result<crate::Path, existing::Error> {
-    let directory = directory.as_ref();
+    existing_opts(directory, &crate::open::Options::default())
+}
+
+/// Like [`existing()`], but stops at the ceiling directories and file system boundaries configured in `options`, or
+/// returns the git directory set in `options` without searching for it.
+pub fn existing_opts(
+    directory: impl AsRef<Path>,
+    options: &crate::open::Options,
+) -> Result<crate::Path, existing::Error> {
+    existing_with_applied_environment(directory.as_ref(), &options.clone().apply_environment())
+}
+
+pub(crate) fn existing_with_applied_environment(
+    directory: &Path,
+    options: &crate::open::Options,
+) -> Result<crate::Path, existing::Error> {
     if !directory.is_dir() {
         return Err(existing::Error::InaccessibleDirectory(directory.into()));
     }
+    let object_dir = options.object_dir.as_deref();
+    if let Some(git_dir) = &options.git_dir {
+        return Ok(crate::open::with_git_dir(git_dir, directory, object_dir)?);
+    }
+
+    let ceiling_dirs: Vec<_> = options
+        .ceiling_dirs
+        .iter()
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| crate::open::absolute(dir))
+        .collect();
+    let device = device_id(directory);
 
     let mut cursor = directory;
     loop {
-        if let Ok(kind) = path::is_git(cursor) {
+        if let Ok(kind) = path::is_git::is_git_with_object_dir(cursor, object_dir) {
             break Ok(crate::Path::from_dot_git_dir(cursor, kind));
         }
         let git_dir = cursor.join(".git");
-        if let Ok(kind) = path::is_git(&git_dir) {
+        if git_dir.is_file() {
+            if let Ok(linked_git_dir) = path::from_gitdir_file(&git_dir) {
+                if path::is_git::is_git_with_object_dir(&linked_git_dir, object_dir).is_ok() {
+                    break Ok(crate::Path::LinkedWorkTree {
+                        work_dir: cursor.to_owned(),
+                        git_dir: linked_git_dir,
+                    });
+                }
+            }
+        } else if let Ok(kind) = path::is_git::is_git_with_object_dir(&git_dir, object_dir) {
             break Ok(crate::Path::from_dot_git_dir(git_dir, kind));
         }
-        match cursor.parent() {
-            Some(parent) => cursor = parent,
+        let parent = match cursor.parent() {
+            Some(parent) => parent,
             None => break Err(existing::Error::NoGitRepository(directory.to_owned())),
+        };
+        let absolute_parent = crate::open::absolute(parent);
+        if let Some(ceiling) = ceiling_dirs.iter().find(|dir| **dir == absolute_parent) {
+            break Err(existing::Error::NoGitRepositoryWithinCeiling {
+                path: directory.to_owned(),
+                ceiling: ceiling.to_owned(),
+            });
         }
+        if !options.cross_fs && device.is_some() && device_id(parent) != device {
+            break Err(existing::Error::NoGitRepositoryWithinFs {
+                path: directory.to_owned(),
+                limit: cursor.to_owned(),
+            });
+        }
+        cursor = parent;
     }
 }
+
+#[cfg(unix)]
+fn device_id(directory: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+    std::fs::metadata(crate::open::absolute(directory))
+        .ok()
+        .map(|meta| meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device_id(_directory: &Path) -> Option<u64> {
+    None
+}
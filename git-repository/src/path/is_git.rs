@@ -18,6 +18,11 @@ quick_error! {
         MissingRefsDirectory(missing: PathBuf) {
             display("Expected a refs directory at '{}'", missing.display())
         }
+        CommonDir(err: std::io::Error) {
+            display("Could not read the commondir file")
+            from()
+            source(err)
+        }
     }
 }
 
@@ -31,38 +36,43 @@ pub fn is_bare(git_dir: impl AsRef<Path>) -> bool {
 /// What constitutes a valid git repository, and what's yet to be implemented.
 ///
 /// * [x] a valid head
-/// * [ ] git common directory
+/// * [x] git common directory
 ///   * [ ] respect GIT_COMMON_DIR
 /// * [x] an objects directory
-///   * [x] respect GIT_OBJECT_DIRECTORY
+///   * [x] respect GIT_OBJECT_DIRECTORY, see [`open::Options::object_dirs_from_env()`][crate::open::Options::object_dirs_from_env()]
 /// * [x] a refs directory
 pub fn is_git(git_dir: impl AsRef<Path>) -> Result<crate::Kind, Error> {
-    let dot_git = git_dir.as_ref();
+    is_git_with_object_dir(git_dir.as_ref(), None)
+}
 
+/// Like [`is_git()`], but expects the objects directory at `object_dir` if set.
+pub(crate) fn is_git_with_object_dir(dot_git: &Path, object_dir: Option<&Path>) -> Result<crate::Kind, Error> {
     {
-        let refs = git_ref::file::Store::at(&dot_git, Default::default());
+        let refs = git_ref::file::Store::at(dot_git, Default::default());
         let head = refs.loose_find_existing("HEAD")?;
         if head.name.as_bstr() != "HEAD" {
             return Err(Error::MisplacedHead(head.name.into_inner()));
         }
     }
 
+    let common_dir = crate::path::common_dir(dot_git)?;
+    let common_dir = common_dir.as_deref().unwrap_or(dot_git);
     {
-        let objects_path = std::env::var("GIT_OBJECT_DIRECTORY")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| dot_git.join("objects"));
+        let objects_path = object_dir
+            .map(ToOwned::to_owned)
+            .unwrap_or_else(|| common_dir.join("objects"));
         if !objects_path.is_dir() {
             return Err(Error::MissingObjectsDirectory(objects_path));
         }
     }
     {
-        let refs_path = dot_git.join("refs");
+        let refs_path = common_dir.join("refs");
         if !refs_path.is_dir() {
             return Err(Error::MissingRefsDirectory(refs_path));
         }
     }
 
-    Ok(if is_bare(git_dir) {
+    Ok(if is_bare(dot_git) {
         crate::Kind::Bare
     } else {
         crate::Kind::WorkingTree
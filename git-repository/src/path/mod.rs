@@ -8,28 +8,42 @@ pub use is_git::{is_bare, is_git};
 #[derive(Debug, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub enum Path {
     WorkingTree(PathBuf),
+    /// A working tree whose `.git` file points to its git directory, as is the case for linked worktrees and submodules.
+    LinkedWorkTree {
+        work_dir: PathBuf,
+        git_dir: PathBuf,
+    },
     Repository(PathBuf),
 }
 
 impl AsRef<std::path::Path> for Path {
     fn as_ref(&self) -> &std::path::Path {
         match self {
-            Path::WorkingTree(path) | Path::Repository(path) => path,
+            Path::WorkingTree(path) | Path::LinkedWorkTree { work_dir: path, .. } | Path::Repository(path) => path,
         }
     }
 }
 
 impl Path {
+    /// Instantiate a new path from the git directory `dir` of the given `kind`.
+    ///
+    /// If `dir` is the private git directory of a linked worktree, the working tree is read from its `gitdir` file.
     pub fn from_dot_git_dir(dir: impl Into<PathBuf>, kind: Kind) -> Self {
         let dir = dir.into();
         match kind {
-            Kind::WorkingTree => Path::WorkingTree(dir.parent().expect("this is a sub-directory").to_owned()),
+            Kind::WorkingTree => match read_gitdir_file(dir.join("gitdir")) {
+                Ok(dot_git) if dir.join("commondir").is_file() => Path::LinkedWorkTree {
+                    work_dir: dot_git.parent().expect("the .git file is in a directory").to_owned(),
+                    git_dir: dir,
+                },
+                _ => Path::WorkingTree(dir.parent().expect("this is a sub-directory").to_owned()),
+            },
             Kind::Bare => Path::Repository(dir),
         }
     }
     pub fn kind(&self) -> Kind {
         match self {
-            Path::WorkingTree(_) => Kind::WorkingTree,
+            Path::WorkingTree(_) | Path::LinkedWorkTree { .. } => Kind::WorkingTree,
             Path::Repository(_) => Kind::Bare,
         }
     }
@@ -37,7 +51,73 @@ impl Path {
     pub fn into_repository_directory(self) -> PathBuf {
         match self {
             Path::WorkingTree(path) => path.join(".git"),
+            Path::LinkedWorkTree { git_dir, .. } => git_dir,
             Path::Repository(path) => path,
         }
     }
 }
+
+/// Read the path of a git directory from the `.git` file at `path`, which contains a line like `gitdir: <path>`.
+///
+/// Relative paths are relative to the directory containing the file.
+pub fn from_gitdir_file(path: impl AsRef<std::path::Path>) -> std::io::Result<PathBuf> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)?;
+    let git_dir = content
+        .strip_prefix("gitdir: ")
+        .map(|git_dir| git_dir.trim_end_matches(&['\n', '\r'][..]))
+        .filter(|git_dir| !git_dir.is_empty())
+        .ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("'{}' does not start with 'gitdir: '", path.display()),
+            )
+        })?;
+    Ok(join_lexically(
+        path.parent().expect("files are in a directory"),
+        git_dir,
+    ))
+}
+
+/// Read the path of the `.git` file of a linked worktree from the `gitdir` file at `path` in its private git directory.
+pub(crate) fn read_gitdir_file(path: PathBuf) -> std::io::Result<PathBuf> {
+    let content = std::fs::read_to_string(&path)?;
+    Ok(join_lexically(
+        path.parent().expect("files are in a directory"),
+        content.trim_end_matches(&['\n', '\r'][..]),
+    ))
+}
+
+/// Return the directory shared by all worktrees as read from the `commondir` file in `git_dir`, or `None` if there is no such file
+/// as `git_dir` doesn't belong to a linked worktree.
+pub fn common_dir(git_dir: impl AsRef<std::path::Path>) -> std::io::Result<Option<PathBuf>> {
+    let git_dir = git_dir.as_ref();
+    match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(content) => Ok(Some(join_lexically(
+            git_dir,
+            content.trim_end_matches(&['\n', '\r'][..]),
+        ))),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Join `path` to `base` and resolve `.` and `..` components without accessing the file system, unless `path` is absolute.
+fn join_lexically(base: &std::path::Path, path: impl AsRef<std::path::Path>) -> PathBuf {
+    use std::path::Component;
+    let path = path.as_ref();
+    if path.is_absolute() {
+        return path.to_owned();
+    }
+    let mut out = base.to_owned();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if matches!(out.components().next_back(), Some(Component::Normal(_))) => {
+                out.pop();
+            }
+            component => out.push(component),
+        }
+    }
+    out
+}
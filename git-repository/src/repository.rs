@@ -7,7 +7,7 @@ mod init {
         pub fn create_and_init(directory: impl AsRef<Path>) -> Result<Self, crate::init::Error> {
             // TODO: proper error
             crate::init::repository(directory.as_ref())?;
-            Ok(Repository::discover(directory).unwrap()) // TODO: a specialized method without discovery
+            Ok(Repository::open(directory).unwrap())
         }
     }
 }
@@ -25,8 +25,8 @@ pub mod discover {
                 from()
                 source(err)
             }
-            ObjectStoreInitialization(err: git_odb::linked::init::Error) {
-                display("Could not initialize the object database")
+            Open(err: crate::open::Error) {
+                display("Could not open the repository")
                 from()
                 source(err)
             }
@@ -35,23 +35,173 @@ pub mod discover {
 
     impl Repository {
         pub fn discover(directory: impl AsRef<Path>) -> Result<Self, Error> {
-            let path = discover::existing(directory)?;
-            let (git_dir, working_tree) = match path {
-                crate::Path::WorkingTree(working_tree) => (working_tree.join(".git"), Some(working_tree)),
-                crate::Path::Repository(repository) => (repository, None),
+            Repository::discover_opts(directory, Default::default())
+        }
+
+        /// Find the repository in `directory` or any of its parents and open it, honouring the environment variables,
+        /// ceiling directories and file system boundaries configured in `options`.
+        pub fn discover_opts(directory: impl AsRef<Path>, options: crate::open::Options) -> Result<Self, Error> {
+            let options = options.apply_environment();
+            let path = discover::existing_with_applied_environment(directory.as_ref(), &options)?;
+            Ok(Repository::from_location(path, options)?)
+        }
+    }
+}
+
+pub mod status {
+    use crate::Repository;
+    use git_hash::ObjectId;
+    use git_odb::{pack, FindExt};
+    use quick_error::quick_error;
+    use std::path::PathBuf;
+
+    quick_error! {
+        #[derive(Debug)]
+        pub enum Error {
+            Io { source: std::io::Error, path: PathBuf } {
+                display("Could not read '{}'", path.display())
+                source(source)
+            }
+            Index(err: git_index::file::init::Error) {
+                display("Could not read the index")
+                from()
+                source(err)
+            }
+            PackedRefs(err: git_ref::packed::buffer::open::Error) {
+                display("Could not open the packed-refs file")
+                from()
+                source(err)
+            }
+            FindHead(err: git_ref::file::find::existing::Error) {
+                display("Could not find HEAD")
+                from()
+                source(err)
+            }
+            FindHeadReferent(err: git_ref::file::find::Error) {
+                display("Could not find the reference HEAD points to")
+                from()
+                source(err)
+            }
+            PeelHead(err: git_ref::file::loose::reference::peel::to_id::Error) {
+                display("Could not peel HEAD to a commit")
+                from()
+                source(err)
+            }
+            FindCommit(err: git_odb::pack::find::existing_object::Error<git_odb::compound::find::Error>) {
+                display("Could not find a commit")
+                from()
+                source(err)
+            }
+            ReadTree(err: git_index::read_tree::Error) {
+                display("Could not read the tree of a commit")
+                from()
+                source(err)
+            }
+            Status(err: git_worktree::status::Error) {
+                display("Could not check the working tree for changes")
+                from()
+                source(err)
+            }
+        }
+    }
+
+    impl Repository {
+        /// Return the id of the commit `HEAD` points to.
+        pub fn head_id(&self) -> Result<ObjectId, Error> {
+            Ok(self.refs.find_existing("HEAD")?.peel_to_id_in_place(&self.refs)?)
+        }
+
+        /// Return the id of the commit `HEAD` points to, or `None` if `HEAD` refers to a branch without commits yet.
+        pub(crate) fn head_id_if_born(&self) -> Result<Option<ObjectId>, Error> {
+            if let git_ref::mutable::Target::Symbolic(name) = self.refs.loose_find_existing("HEAD")?.target {
+                if self.refs.find(name.to_partial())?.is_none() {
+                    return Ok(None);
+                }
+            }
+            self.head_id().map(Some)
+        }
+
+        /// Read the index of the working tree, or return `None` if there is none yet.
+        pub(crate) fn index(&self) -> Result<Option<git_index::File>, git_index::file::init::Error> {
+            match git_index::File::at(self.git_dir().join("index")) {
+                Ok(index) => Ok(Some(index)),
+                Err(git_index::file::init::Error::Io { source, .. })
+                    if source.kind() == std::io::ErrorKind::NotFound =>
+                {
+                    Ok(None)
+                }
+                Err(err) => Err(err),
+            }
+        }
+
+        /// Return true if the working tree has modified, staged or untracked files, ignoring the changes within submodules
+        /// as well as ignored files, or false if the repository is bare.
+        ///
+        /// A `HEAD` without commits is treated like an empty tree, and a missing index like an empty one.
+        pub fn is_dirty(&self) -> Result<bool, Error> {
+            use git_worktree::status::{index_worktree, tree_index, untracked, Untracked};
+
+            let working_tree = match &self.working_tree {
+                Some(working_tree) => working_tree,
+                None => return Ok(false),
+            };
+            let index = self.index()?;
+            let empty = git_index::State::new(git_index::Version::V2);
+            let head = match self.head_id_if_born()? {
+                Some(id) => self.index_from_commit::<Error>(id)?,
+                None => git_index::State::new(git_index::Version::V2),
             };
-            Ok(Repository {
-                odb: git_odb::linked::Store::at(git_dir.join("objects"))?,
-                refs: git_ref::file::Store::at(
-                    git_dir,
-                    if working_tree.is_none() {
-                        git_ref::file::WriteReflog::Disable
-                    } else {
-                        git_ref::file::WriteReflog::Normal
-                    },
-                ),
+            if !tree_index::changes(&head, index.as_deref().unwrap_or(&empty), false).is_empty() {
+                return Ok(true);
+            }
+            if let Some(index) = &index {
+                if !index_worktree::changes(index, working_tree, None, &Default::default())?.is_empty() {
+                    return Ok(true);
+                }
+            }
+            let mut ignore = self.ignore_stack(working_tree)?;
+            let untracked = untracked::collect(
+                index.as_deref().unwrap_or(&empty),
                 working_tree,
-            })
+                Untracked::Normal,
+                |path, is_dir| ignore.is_ignored(path, is_dir),
+            )?;
+            Ok(!untracked.untracked.is_empty())
+        }
+
+        /// Return the stack of exclude patterns for `working_tree`, with the global lists read from the file configured
+        /// in `core.excludesFile` and from `info/exclude`.
+        fn ignore_stack(&self, working_tree: &std::path::Path) -> Result<git_worktree::ignore::Stack, Error> {
+            let read = |path: PathBuf| {
+                git_worktree::ignore::List::from_file(&path, Default::default())
+                    .map_err(|err| Error::Io { source: err, path })
+            };
+            let mut globals = Vec::new();
+            if let Some(path) = crate::config::excludes_file(&self.config) {
+                globals.extend(read(path)?);
+            }
+            globals.extend(read(self.common_dir().join("info").join("exclude"))?);
+            Ok(git_worktree::ignore::Stack::new(
+                working_tree,
+                globals,
+                git_glob::pattern::Case::Sensitive,
+            ))
+        }
+
+        /// Return an index with the entries of the tree of the commit with the given `id`.
+        pub(crate) fn index_from_commit<E>(&self, id: ObjectId) -> Result<git_index::State, E>
+        where
+            E: From<git_odb::pack::find::existing_object::Error<git_odb::compound::find::Error>>
+                + From<git_index::read_tree::Error>,
+        {
+            let mut buf = Vec::new();
+            let tree = self
+                .odb
+                .find_existing_commit(id, &mut buf, &mut pack::cache::Never)?
+                .tree();
+            Ok(git_index::State::from_tree(&tree, |oid, buf| {
+                self.odb.find_existing_tree_iter(oid, buf, &mut pack::cache::Never).ok()
+            })?)
         }
     }
 }
@@ -0,0 +1,282 @@
+//! Submodules as declared in `.gitmodules` and recorded as gitlinks in the index, similar to `git submodule`.
+use std::{convert::TryFrom, path::PathBuf};
+
+use git_hash::ObjectId;
+use git_object::bstr::{BStr, BString, ByteSlice, ByteVec};
+
+use crate::Repository;
+
+/// The declaration of a submodule in a `.gitmodules` file.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Config {
+    /// The name of the submodule, which is the subsection name of its `submodule` section.
+    pub name: BString,
+    /// The path of the submodule relative to the working tree of its superproject.
+    pub path: BString,
+    /// The url to clone the submodule from, if set.
+    pub url: Option<BString>,
+    /// The branch to track when updating the submodule remotely, if set.
+    pub branch: Option<BString>,
+}
+
+/// A submodule of a repository along with the location of its git directory and working tree.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Submodule {
+    /// The declaration of the submodule in `.gitmodules`.
+    pub config: Config,
+    /// The commit recorded for the submodule in the index of its superproject, or `None` if the gitlink is conflicted.
+    pub commit: Option<ObjectId>,
+    /// The working tree of the submodule, which is a directory within the one of its superproject.
+    pub work_dir: PathBuf,
+    /// The git directory of the submodule, which is typically `$GIT_COMMON_DIR/modules/<name>` of its superproject.
+    ///
+    /// It doesn't exist if the submodule was never initialized.
+    pub git_dir: PathBuf,
+}
+
+/// The state of a submodule as compared to the commit recorded in its superproject.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub struct Status {
+    /// The commit checked out in the submodule, or `None` if it isn't checked out.
+    pub checked_out: Option<ObjectId>,
+    /// True if the working tree of the submodule has modified, staged or untracked files.
+    pub is_dirty: bool,
+}
+
+impl Status {
+    /// Return the character prefixed to the submodule by `git submodule status`, given the `submodule` this status belongs to.
+    ///
+    /// It's `U` if its gitlink is conflicted, `-` if it's not checked out, `+` if another commit than the recorded one is
+    /// checked out and a space otherwise. Note that being dirty isn't indicated.
+    pub fn as_char(&self, submodule: &Submodule) -> char {
+        match (self.checked_out, submodule.commit) {
+            (_, None) => 'U',
+            (None, Some(_)) => '-',
+            (Some(checked_out), Some(recorded)) if checked_out != recorded => '+',
+            (Some(_), Some(_)) => ' ',
+        }
+    }
+}
+
+///
+pub mod from_bytes {
+    use git_object::bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`from_bytes()`][super::from_bytes()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Parse(err: git_config::parser::Error<'static>) {
+                display("Could not parse the submodule configuration")
+                from()
+                source(err)
+            }
+            InvalidName(name: BString) {
+                display("The submodule name '{}' isn't allowed as it could escape the modules directory", name)
+            }
+        }
+    }
+}
+
+/// Parse the submodule declarations in the `.gitmodules` file contained in `bytes`, in the order they appear.
+///
+/// Sections without a `path` are skipped, like `git` does. Names which could be used to escape the modules directory, like the ones
+/// with `..` components, are rejected.
+pub fn from_bytes(bytes: &[u8]) -> Result<Vec<Config>, from_bytes::Error> {
+    let config = git_config::file::GitConfig::try_from(bytes).map_err(|err| err.to_owned())?;
+    let value = |body: &git_config::file::SectionBody<'_>, key: &str| {
+        body.value(&key.into())
+            .map(|value| BString::from(git_config::values::normalize_cow(value).into_owned()))
+    };
+    let mut out = Vec::new();
+    for (header, body) in config.sections_by_name_with_header("submodule") {
+        let name = match header.subsection_name.as_deref() {
+            Some(name) => BString::from(name),
+            None => continue,
+        };
+        if !is_valid_name(name.as_ref()) {
+            return Err(from_bytes::Error::InvalidName(name));
+        }
+        let path = match value(body, "path").filter(|path| !path.is_empty()) {
+            Some(path) => path,
+            None => continue,
+        };
+        out.push(Config {
+            name,
+            path,
+            url: value(body, "url"),
+            branch: value(body, "branch"),
+        });
+    }
+    Ok(out)
+}
+
+fn is_valid_name(name: &BStr) -> bool {
+    !name.is_empty()
+        && !name
+            .split(|b| *b == b'/' || *b == b'\\')
+            .any(|component| component == b"..")
+}
+
+///
+pub mod list {
+    use std::path::PathBuf;
+
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`Repository::submodules()`][crate::Repository::submodules()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io { source: std::io::Error, path: PathBuf } {
+                display("Could not read '{}'", path.display())
+                source(source)
+            }
+            Gitmodules(err: super::from_bytes::Error) {
+                display("Could not read the .gitmodules file")
+                from()
+                source(err)
+            }
+            Index(err: git_index::file::init::Error) {
+                display("Could not read the index")
+                from()
+                source(err)
+            }
+            Open(err: crate::open::Error) {
+                display("Could not open a submodule")
+                from()
+                source(err)
+            }
+        }
+    }
+}
+
+///
+pub mod status {
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`Submodule::status()`][super::Submodule::status()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Open(err: crate::open::Error) {
+                display("Could not open the submodule")
+                from()
+                source(err)
+            }
+            Status(err: crate::repository::status::Error) {
+                display("Could not obtain the state of the submodule")
+                from()
+                source(err)
+            }
+        }
+    }
+}
+
+impl Submodule {
+    /// Open the repository of the submodule, or return `None` if it isn't checked out.
+    pub fn open(&self) -> Result<Option<Repository>, crate::open::Error> {
+        if !self.work_dir.join(".git").exists() || !self.git_dir.is_dir() {
+            return Ok(None);
+        }
+        Repository::from_paths(&self.git_dir, Some(self.work_dir.clone()), &Default::default()).map(Some)
+    }
+
+    /// Compare the checked out commit with the one recorded in the superproject and check the working tree for changes.
+    pub fn status(&self) -> Result<Status, status::Error> {
+        Ok(match self.open()? {
+            Some(repo) => Status {
+                checked_out: Some(repo.head_id()?),
+                is_dirty: repo.is_dirty()?,
+            },
+            None => Status {
+                checked_out: None,
+                is_dirty: false,
+            },
+        })
+    }
+}
+
+impl Repository {
+    /// Return all submodules declared in the `.gitmodules` file of the working tree which have a gitlink in the index,
+    /// sorted by path like `git submodule status` does.
+    ///
+    /// Bare repositories and repositories without `.gitmodules` file or index have no submodules.
+    pub fn submodules(&self) -> Result<Vec<Submodule>, list::Error> {
+        let working_tree = match &self.working_tree {
+            Some(working_tree) => working_tree,
+            None => return Ok(Vec::new()),
+        };
+        let path = working_tree.join(".gitmodules");
+        let configs = match std::fs::read(&path) {
+            Ok(bytes) => from_bytes(&bytes)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(list::Error::Io { source: err, path }),
+        };
+        let index = match self.index()? {
+            Some(index) => index,
+            None => return Ok(Vec::new()),
+        };
+        let mut out = Vec::new();
+        for config in configs {
+            let mut gitlinks = index
+                .entries()
+                .iter()
+                .filter(|entry| entry.path == config.path && entry.mode.is_commit());
+            let commit = match gitlinks.next() {
+                Some(entry) if entry.stage() == 0 => Some(entry.id),
+                Some(_) => None,
+                None => continue,
+            };
+            let work_dir = working_tree.join(config.path.to_path_lossy());
+            let dot_git = work_dir.join(".git");
+            let git_dir = if dot_git.is_file() {
+                crate::path::from_gitdir_file(&dot_git).map_err(|err| list::Error::Io {
+                    source: err,
+                    path: dot_git,
+                })?
+            } else if dot_git.is_dir() {
+                dot_git
+            } else {
+                self.common_dir().join("modules").join(config.name.to_path_lossy())
+            };
+            out.push(Submodule {
+                config,
+                commit,
+                work_dir,
+                git_dir,
+            });
+        }
+        out.sort_by(|a, b| a.config.path.cmp(&b.config.path));
+        Ok(out)
+    }
+
+    /// Return all submodules like [`submodules()`][Repository::submodules()] does, along with the ones of all checked out
+    /// submodules, recursively.
+    ///
+    /// Submodules are listed depth-first, with the ones of a submodule following it, and their path is adjusted to be relative
+    /// to the working tree of this repository.
+    pub fn submodules_recursive(&self) -> Result<Vec<Submodule>, list::Error> {
+        let mut out = Vec::new();
+        for submodule in self.submodules()? {
+            let nested = match submodule.open()? {
+                Some(repo) => repo.submodules_recursive()?,
+                None => Vec::new(),
+            };
+            let prefix = submodule.config.path.clone();
+            out.push(submodule);
+            out.extend(nested.into_iter().map(|mut nested| {
+                let mut path = prefix.clone();
+                path.push_byte(b'/');
+                path.push_str(&nested.config.path);
+                nested.config.path = path;
+                nested
+            }));
+        }
+        Ok(out)
+    }
+}
@@ -0,0 +1,9 @@
+//! Information about the user running this process, which is only available through the C library.
+#![allow(unsafe_code)]
+
+/// Return the effective user id of this process, which is the owner of the files it creates.
+#[cfg(unix)]
+pub(crate) fn effective_uid() -> u32 {
+    // SAFETY: `geteuid()` has no preconditions, never fails and only returns a value of the process.
+    unsafe { libc::geteuid() }
+}
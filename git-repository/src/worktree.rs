@@ -0,0 +1,483 @@
+//! Linked worktrees, which share objects and references with the repository while having their own `HEAD` and index,
+//! similar to `git worktree`.
+use std::{
+    convert::TryFrom,
+    io,
+    path::{Path, PathBuf},
+};
+
+use git_hash::ObjectId;
+use git_object::bstr::{BString, ByteSlice};
+use git_ref::{
+    mutable::{FullName, Target},
+    transaction::{Change, Create, LogChange, RefEdit, RefLog},
+};
+
+use crate::Repository;
+
+/// A linked worktree as listed in the `worktrees` directory of the repository.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Worktree {
+    /// The name of the private git directory of the worktree within `$GIT_COMMON_DIR/worktrees`.
+    pub id: BString,
+    /// The private git directory of the worktree, containing its `HEAD`, index and per-worktree references.
+    pub git_dir: PathBuf,
+    /// The directory containing the files of the worktree as read from the `gitdir` file, or `None` if it couldn't be read.
+    pub path: Option<PathBuf>,
+    /// The reason for locking the worktree, which may be empty, or `None` if it isn't locked.
+    pub lock_reason: Option<BString>,
+}
+
+impl Worktree {
+    /// Read the worktree whose private git directory is `git_dir`.
+    pub fn at(git_dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let git_dir = git_dir.into();
+        let lock_reason = match std::fs::read(git_dir.join("locked")) {
+            Ok(reason) => Some(reason.trim_end_with(|c| c == '\n').into()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        Ok(Worktree {
+            id: git_dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned().into())
+                .unwrap_or_default(),
+            path: crate::path::read_gitdir_file(git_dir.join("gitdir"))
+                .ok()
+                .and_then(|dot_git| dot_git.parent().map(ToOwned::to_owned)),
+            git_dir,
+            lock_reason,
+        })
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.lock_reason.is_some()
+    }
+
+    /// Return true if the directory of the worktree is gone and it isn't locked, which is when
+    /// [`Repository::prune_worktrees()`] removes it.
+    pub fn is_prunable(&self) -> bool {
+        !self.is_locked() && self.path.as_ref().map_or(true, |path| !path.is_dir())
+    }
+
+    /// Lock the worktree for the given `reason`, which may be empty, to prevent it from being pruned or removed.
+    pub fn lock(&mut self, reason: impl Into<BString>) -> io::Result<()> {
+        let reason = reason.into();
+        let mut content = reason.clone();
+        if !content.is_empty() {
+            content.push(b'\n');
+        }
+        std::fs::write(self.git_dir.join("locked"), content)?;
+        self.lock_reason = Some(reason);
+        Ok(())
+    }
+
+    pub fn unlock(&mut self) -> io::Result<()> {
+        match std::fs::remove_file(self.git_dir.join("locked")) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        self.lock_reason = None;
+        Ok(())
+    }
+
+    /// Return the reference store of this worktree, which sees its own per-worktree references along with the shared ones
+    /// in the directory named in its `commondir` file, and writes reference logs according to `core.logAllRefUpdates` in `config`.
+    ///
+    /// Like in `git`, a worktree without `commondir` file is treated as if it had no shared directory.
+    pub fn refs(&self, config: &git_config::fs::Config) -> io::Result<git_ref::file::Store> {
+        let write_reflog = crate::config::write_reflog(config, false);
+        Ok(match crate::path::common_dir(&self.git_dir)? {
+            Some(common_dir) => git_ref::file::Store::for_linked_worktree(&self.git_dir, common_dir, write_reflog),
+            None => git_ref::file::Store::at(&self.git_dir, write_reflog),
+        })
+    }
+}
+
+/// What to check out in a new worktree with [`Repository::add_worktree()`].
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub enum Head {
+    /// Create the branch with the given short `name` at the commit `id` and check it out.
+    NewBranch { name: BString, id: ObjectId },
+    /// Check out the existing branch with the given short name, which must not be checked out in any other worktree.
+    Branch(BString),
+    /// Check out the commit with the given id with a detached `HEAD`.
+    Detached(ObjectId),
+}
+
+///
+pub mod add {
+    use std::path::PathBuf;
+
+    use git_object::bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`Repository::add_worktree()`][crate::Repository::add_worktree()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io { source: std::io::Error, path: PathBuf } {
+                display("Could not write '{}'", path.display())
+                source(source)
+            }
+            Exists(path: PathBuf) {
+                display("'{}' already exists and is not an empty directory", path.display())
+            }
+            BranchCheckedOut { name: BString, path: PathBuf } {
+                display("The branch '{}' is already checked out at '{}'", name, path.display())
+            }
+            InvalidBranchName(err: git_validate::refname::Error) {
+                display("The branch name is invalid")
+                from()
+                source(err)
+            }
+            PackedRefs(err: git_ref::packed::buffer::open::Error) {
+                display("Could not open the packed-refs file")
+                from()
+                source(err)
+            }
+            FindBranch(err: git_ref::file::find::existing::Error) {
+                display("Could not find the branch to check out")
+                from()
+                source(err)
+            }
+            PeelBranch(err: git_ref::file::loose::reference::peel::to_id::Error) {
+                display("Could not peel the branch to check out to a commit")
+                from()
+                source(err)
+            }
+            ReferenceEdit(err: git_ref::file::transaction::Error) {
+                display("Could not create the branch or HEAD of the worktree")
+                from()
+                source(err)
+            }
+            FindCommit(err: git_odb::pack::find::existing_object::Error<git_odb::compound::find::Error>) {
+                display("Could not find the commit to check out")
+                from()
+                source(err)
+            }
+            ReadTree(err: git_index::read_tree::Error) {
+                display("Could not read the tree of the commit to check out")
+                from()
+                source(err)
+            }
+            Checkout(err: git_worktree::checkout::Error) {
+                display("Could not check out the files of the worktree")
+                from()
+                source(err)
+            }
+            WriteIndex(err: git_index::file::write::Error) {
+                display("Could not write the index of the worktree")
+                from()
+                source(err)
+            }
+        }
+    }
+}
+
+///
+pub mod remove {
+    use std::path::PathBuf;
+
+    use git_object::bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`Repository::remove_worktree()`][crate::Repository::remove_worktree()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io { source: std::io::Error, path: PathBuf } {
+                display("Could not remove '{}'", path.display())
+                source(source)
+            }
+            Locked(id: BString) {
+                display("The worktree '{}' is locked", id)
+            }
+            Dirty(path: PathBuf) {
+                display("The worktree at '{}' contains modified or untracked files", path.display())
+            }
+            Open(err: crate::open::Error) {
+                display("Could not open the worktree")
+                from()
+                source(err)
+            }
+            Status(err: crate::repository::status::Error) {
+                display("Could not check the worktree for changes")
+                from()
+                source(err)
+            }
+        }
+    }
+}
+
+impl Repository {
+    /// Return all linked worktrees of the repository, sorted by id, which doesn't include the main worktree.
+    pub fn worktrees(&self) -> io::Result<Vec<Worktree>> {
+        let entries = match std::fs::read_dir(self.common_dir().join("worktrees")) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        let mut out = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                out.push(Worktree::at(entry.path())?);
+            }
+        }
+        out.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(out)
+    }
+
+    /// Create a new worktree at `path`, which must not exist or be an empty directory, and check out `head` in it, using
+    /// `committer` for the reference logs.
+    ///
+    /// The id of the worktree is the last component of `path`, with a number appended if it's already taken.
+    /// If anything goes wrong, the private git directory of the worktree is removed again, as is `path` if it was created
+    /// and the branch of [`Head::NewBranch`].
+    pub fn add_worktree(
+        &self,
+        path: impl AsRef<Path>,
+        head: Head,
+        committer: &git_actor::Signature,
+    ) -> Result<Worktree, add::Error> {
+        let path = path.as_ref();
+        let path_existed = path.exists();
+        if path_existed
+            && std::fs::read_dir(path)
+                .map_err(|err| add::Error::Io {
+                    source: err,
+                    path: path.to_owned(),
+                })?
+                .next()
+                .is_some()
+        {
+            return Err(add::Error::Exists(path.to_owned()));
+        }
+
+        let branch = match &head {
+            Head::NewBranch { name, .. } | Head::Branch(name) => {
+                Some(FullName::try_from(format!("refs/heads/{}", name).as_str())?)
+            }
+            Head::Detached(_) => None,
+        };
+        let id = match (&head, &branch) {
+            (Head::NewBranch { id, .. }, _) | (Head::Detached(id), _) => *id,
+            (Head::Branch(_), Some(branch)) => {
+                let name = branch.as_bstr();
+                if let Some(checked_out_at) = self.checked_out_at(name) {
+                    return Err(add::Error::BranchCheckedOut {
+                        name: name.into(),
+                        path: checked_out_at,
+                    });
+                }
+                self.refs.find_existing(name)?.peel_to_id_in_place(&self.refs)?
+            }
+            (Head::Branch(_), None) => unreachable!("branches have a name"),
+        };
+
+        let worktrees_dir = self.common_dir().join("worktrees");
+        let git_dir = create_unique_dir(&worktrees_dir, path.file_name().unwrap_or_default().to_string_lossy())
+            .map_err(|err| add::Error::Io {
+                source: err,
+                path: worktrees_dir,
+            })?;
+        let new_branch = match (&head, &branch) {
+            (Head::NewBranch { .. }, Some(branch)) => Some(branch),
+            _ => None,
+        };
+        if let Some(branch) = new_branch {
+            let res = self
+                .refs
+                .transaction(
+                    Some(RefEdit {
+                        change: Change::Update {
+                            log: LogChange {
+                                message: format!("branch: Created from {}", id).into(),
+                                ..Default::default()
+                            },
+                            mode: Create::Only,
+                            new: Target::Peeled(id),
+                        },
+                        name: branch.clone(),
+                        deref: false,
+                    }),
+                    git_lock::acquire::Fail::Immediately,
+                )
+                .commit(committer);
+            if let Err(err) = res {
+                std::fs::remove_dir_all(&git_dir).ok();
+                return Err(err.into());
+            }
+        }
+        let res = self.initialize_worktree(path, &git_dir, branch.clone(), id, committer);
+        if res.is_err() {
+            std::fs::remove_dir_all(&git_dir).ok();
+            if !path_existed {
+                std::fs::remove_dir_all(path).ok();
+            }
+            if let Some(branch) = new_branch {
+                self.refs
+                    .transaction(
+                        Some(RefEdit {
+                            change: Change::Delete {
+                                previous: Some(Target::Peeled(id)),
+                                log: RefLog::AndReference,
+                            },
+                            name: branch.clone(),
+                            deref: false,
+                        }),
+                        git_lock::acquire::Fail::Immediately,
+                    )
+                    .commit(committer)
+                    .ok();
+            }
+        }
+        res
+    }
+
+    fn initialize_worktree(
+        &self,
+        path: &Path,
+        git_dir: &Path,
+        branch: Option<FullName>,
+        id: ObjectId,
+        committer: &git_actor::Signature,
+    ) -> Result<Worktree, add::Error> {
+        let write = |path: PathBuf, content: &[u8]| {
+            std::fs::write(&path, content).map_err(|err| add::Error::Io { source: err, path })
+        };
+        write(git_dir.join("locked"), b"initializing\n")?;
+        std::fs::create_dir_all(path).map_err(|err| add::Error::Io {
+            source: err,
+            path: path.to_owned(),
+        })?;
+        let absolute = |path: &Path| {
+            std::fs::canonicalize(path).map_err(|err| add::Error::Io {
+                source: err,
+                path: path.to_owned(),
+            })
+        };
+        let (path, git_dir) = (absolute(path)?, absolute(git_dir)?);
+        let dot_git = path.join(".git");
+        write(git_dir.join("commondir"), b"../..\n")?;
+        write(git_dir.join("gitdir"), format!("{}\n", dot_git.display()).as_bytes())?;
+        write(dot_git, format!("gitdir: {}\n", git_dir.display()).as_bytes())?;
+
+        let mut worktree = Worktree::at(&git_dir).map_err(|err| add::Error::Io {
+            source: err,
+            path: git_dir.clone(),
+        })?;
+        worktree
+            .refs(&self.config)
+            .map_err(|err| add::Error::Io {
+                source: err,
+                path: git_dir.join("commondir"),
+            })?
+            .transaction(
+                Some(RefEdit {
+                    change: Change::Update {
+                        log: Default::default(),
+                        mode: Create::Only,
+                        new: branch.map_or(Target::Peeled(id), Target::Symbolic),
+                    },
+                    name: FullName::try_from("HEAD").expect("valid"),
+                    deref: false,
+                }),
+                git_lock::acquire::Fail::Immediately,
+            )
+            .commit(committer)?;
+
+        let mut index = self.index_from_commit::<add::Error>(id)?;
+        git_worktree::checkout::checkout(&mut index, &path, &self.odb, None, Default::default())?;
+        git_index::File::from_state(index, git_dir.join("index")).write(git_lock::acquire::Fail::Immediately)?;
+        worktree.unlock().map_err(|err| add::Error::Io {
+            source: err,
+            path: git_dir,
+        })?;
+        Ok(worktree)
+    }
+
+    /// Remove the administrative files of all [prunable][Worktree::is_prunable()] worktrees and return them.
+    pub fn prune_worktrees(&self) -> io::Result<Vec<Worktree>> {
+        let mut pruned = Vec::new();
+        for worktree in self.worktrees()? {
+            if worktree.is_prunable() {
+                std::fs::remove_dir_all(&worktree.git_dir)?;
+                pruned.push(worktree);
+            }
+        }
+        Ok(pruned)
+    }
+
+    /// Remove the files of `worktree` along with its private git directory.
+    ///
+    /// Locked worktrees are never removed, and unless `force` is true, neither are worktrees with modified, staged
+    /// or untracked files. Ignored files don't prevent the removal.
+    pub fn remove_worktree(&self, worktree: Worktree, force: bool) -> Result<(), remove::Error> {
+        if worktree.is_locked() {
+            return Err(remove::Error::Locked(worktree.id));
+        }
+        if let Some(path) = worktree.path.as_ref().filter(|path| path.is_dir()) {
+            if !force
+                && Repository::from_paths(&worktree.git_dir, Some(path.to_owned()), &Default::default())?.is_dirty()?
+            {
+                return Err(remove::Error::Dirty(path.to_owned()));
+            }
+            std::fs::remove_dir_all(path).map_err(|err| remove::Error::Io {
+                source: err,
+                path: path.to_owned(),
+            })?;
+        }
+        std::fs::remove_dir_all(&worktree.git_dir).map_err(|err| remove::Error::Io {
+            source: err,
+            path: worktree.git_dir,
+        })
+    }
+
+    /// Return the directory of the worktree which has the branch with the full `name` checked out, if any.
+    fn checked_out_at(&self, name: &git_object::bstr::BStr) -> Option<PathBuf> {
+        let is_checked_out = |refs: &git_ref::file::Store| {
+            refs.loose_find("HEAD").ok().flatten().map_or(
+                false,
+                |head| matches!(head.target, Target::Symbolic(target) if target.as_bstr() == name),
+            )
+        };
+        let main = git_ref::file::Store::at(self.common_dir(), Default::default());
+        if !crate::path::is_bare(self.common_dir()) && is_checked_out(&main) {
+            return Some(
+                self.common_dir()
+                    .parent()
+                    .map(ToOwned::to_owned)
+                    .unwrap_or_else(|| self.common_dir().to_owned()),
+            );
+        }
+        self.worktrees()
+            .ok()?
+            .into_iter()
+            .find(|worktree| worktree.refs(&self.config).map_or(false, |refs| is_checked_out(&refs)))
+            .map(|worktree| worktree.path.unwrap_or(worktree.git_dir))
+    }
+}
+
+/// Create a new directory in `parent` named `name`, appending the smallest number needed to make it unique.
+fn create_unique_dir(parent: &Path, name: impl AsRef<str>) -> io::Result<PathBuf> {
+    let name = name.as_ref();
+    std::fs::create_dir_all(parent)?;
+    let mut counter = 0;
+    loop {
+        let dir = if counter == 0 {
+            parent.join(name)
+        } else {
+            parent.join(format!("{}{}", name, counter))
+        };
+        match std::fs::create_dir(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => counter += 1,
+            Err(err) => return Err(err),
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use git_config::fs::Source;
+use git_repository::{open, Kind, Repository};
+use std::path::PathBuf;
+
+fn repo_path() -> crate::Result<PathBuf> {
+    git_testtools::scripted_fixture_repo_read_only("make_config_repo.sh")
+}
+
+fn isolated() -> open::Options {
+    open::Options::default().system_config(false).global_config(false)
+}
+
+#[test]
+fn values_cascade_from_repository_over_worktree_to_overrides() -> crate::Result {
+    let dir = repo_path()?.join("layered");
+    let repo = Repository::open_opts(&dir, isolated())?;
+    assert_eq!(repo.user_name().as_ref().map(|n| n.as_slice()), Some(&b"repo-user"[..]));
+    assert_eq!(
+        repo.user_email().as_ref().map(|n| n.as_slice()),
+        Some(&b"worktree@example.com"[..]),
+        "the worktree configuration overrides the one of the repository"
+    );
+    let origin = repo.config_origin("user", None, "name").expect("set");
+    assert_eq!(origin.source, Source::Repository);
+    assert_eq!(origin.path.as_deref(), Some(dir.join(".git/config").as_path()));
+    assert_eq!(
+        repo.config_origin("user", None, "email").map(|o| o.source),
+        Some(Source::Worktree)
+    );
+    assert_eq!(repo.abbrev_len(), 12);
+
+    let repo = Repository::open_opts(
+        &dir,
+        isolated().config_overrides(vec!["user.name=cli", "core.abbrev=no"]),
+    )?;
+    assert_eq!(repo.user_name().as_ref().map(|n| n.as_slice()), Some(&b"cli"[..]));
+    assert_eq!(
+        repo.config_origin("user", None, "name").map(|o| o.source),
+        Some(Source::Cli)
+    );
+    assert_eq!(repo.abbrev_len(), 40);
+    assert_eq!(
+        repo.config.get_raw_multi_value("user", None, "name")?.len(),
+        2,
+        "multi-values contain the values of all levels"
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_overrides_fail_to_open() -> crate::Result {
+    let dir = repo_path()?.join("layered");
+    assert!(matches!(
+        Repository::open_opts(&dir, isolated().config_overrides(Some("no-key"))),
+        Err(open::Error::ConfigOverride(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn core_bare_decides_about_the_working_tree() -> crate::Result {
+    let dir = repo_path()?.join("empty");
+    let repo = Repository::open_opts(dir.join(".git"), isolated())?;
+    assert_eq!(
+        repo.kind(),
+        Kind::WorkingTree,
+        "without index the repository looks bare, but core.bare says otherwise"
+    );
+    assert_eq!(repo.working_tree.as_deref(), Some(dir.as_path()));
+    assert_eq!(repo.abbrev_len(), 7, "the default is used if core.abbrev is unset");
+
+    let repo = Repository::open_opts(&dir, isolated().config_overrides(Some("core.bare")))?;
+    assert_eq!(repo.kind(), Kind::Bare);
+    Ok(())
+}
+
+#[test]
+fn remotes_have_their_urls_rewritten() -> crate::Result {
+    let dir = repo_path()?.join("empty");
+    let repo = Repository::open_opts(
+        &dir,
+        isolated().config_overrides(vec![
+            "url.https://github.com/.insteadOf=gh:",
+            "remote.origin.url=gh:byron/gitoxide",
+        ]),
+    )?;
+    let remote = repo.remote("origin")?.expect("configured");
+    assert_eq!(
+        remote.url.map(|url| url.to_string()),
+        Some("https://github.com/byron/gitoxide".into())
+    );
+    assert!(repo.remote("upstream")?.is_none());
+    Ok(())
+}
@@ -0,0 +1,13 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q -b main layered
+(cd layered
+  git config user.name repo-user
+  git config user.email repo@example.com
+  git config core.abbrev 12
+  git config extensions.worktreeConfig true
+  git config --worktree user.email worktree@example.com
+)
+
+git init -q -b main empty
@@ -0,0 +1,45 @@
+#!/bin/bash
+set -eu -o pipefail
+
+function commit() {
+  git add -A
+  git commit -q -m "$1"
+}
+
+git init -q -b main deep
+(cd deep
+  echo deep > file
+  commit deep
+)
+
+git init -q -b main sub
+(cd sub
+  echo sub > file
+  git -c protocol.file.allow=always submodule add -q ../deep deep
+  commit sub
+)
+
+git init -q -b main other
+(cd other
+  echo one > file
+  commit one
+  echo two > file
+  commit two
+)
+
+git init -q -b main super
+(cd super
+  echo top > file
+  git -c protocol.file.allow=always submodule add -q ../sub sub
+  git -c protocol.file.allow=always submodule add -q --name renamed -b main ../other dir/other
+  commit super
+  git -c protocol.file.allow=always submodule update -q --init --recursive
+)
+
+git clone -q super uninitialized
+
+(cd super
+  (cd dir/other && git checkout -q HEAD~1)
+  echo changed > sub/file
+  git submodule status --recursive | sed -E 's/ \(.*\)$//' > ../status.expected
+)
@@ -0,0 +1,9 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q unborn
+
+(git init -q unborn-with-index && cd unborn-with-index
+  echo a > a
+  git add a
+)
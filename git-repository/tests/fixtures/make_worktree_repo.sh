@@ -0,0 +1,27 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q main
+(cd main
+  git config commit.gpgsign false
+  git checkout -q -b main
+  echo content > file
+  mkdir dir && echo nested > dir/file
+  git add .
+  git commit -q -m c1
+  git branch other
+
+  git worktree add -q ../linked -b linked
+  mkdir -p ../linked/some/dir
+  git worktree add -q --lock --reason "on a usb stick" ../locked -b locked
+  git worktree add -q ../gone -b gone
+)
+rm -rf gone
+
+# use relative paths so that copies of the fixture refer to themselves
+for id in linked locked gone; do
+  echo "../../../../$id/.git" > main/.git/worktrees/$id/gitdir
+  if [ -d $id ]; then
+    echo "gitdir: ../main/.git/worktrees/$id" > $id/.git
+  fi
+done
@@ -0,0 +1,140 @@
+use git_repository::{open, path::discover, Kind, Repository};
+use std::{io::Write, path::PathBuf};
+
+fn repo_path() -> crate::Result<PathBuf> {
+    git_testtools::scripted_fixture_repo_read_only("make_basic_repo.sh")
+}
+
+#[test]
+fn open_does_not_discover() -> crate::Result {
+    let dir = repo_path()?;
+    assert_eq!(Repository::open(&dir)?.kind(), Kind::WorkingTree);
+    assert_eq!(Repository::open(dir.join(".git"))?.kind(), Kind::WorkingTree);
+    assert_eq!(Repository::open(dir.join("bare.git"))?.kind(), Kind::Bare);
+    assert!(
+        matches!(
+            Repository::open(dir.join("some")),
+            Err(open::Error::NotARepository { .. })
+        ),
+        "parent directories are not searched"
+    );
+    Ok(())
+}
+
+#[test]
+fn ceiling_dirs_stop_discovery() -> crate::Result {
+    let dir = repo_path()?;
+    let cwd = std::env::current_dir()?;
+    let nested = dir.join("some/very/deeply");
+
+    let options = open::Options::default().ceiling_dirs(Some(cwd.join(&dir).join("some")));
+    assert!(matches!(
+        discover::existing_opts(&nested, &options),
+        Err(discover::existing::Error::NoGitRepositoryWithinCeiling { .. })
+    ));
+    assert_eq!(
+        discover::existing_opts(dir.join("some"), &options)?.as_ref(),
+        dir,
+        "the ceiling directory itself may be the start of the search"
+    );
+
+    let options = open::Options::default().ceiling_dirs(Some(dir.join("some")));
+    assert_eq!(
+        discover::existing_opts(&nested, &options)?.as_ref(),
+        dir,
+        "relative ceiling directories are ignored"
+    );
+    Ok(())
+}
+
+#[test]
+fn git_dir_and_work_tree_from_options() -> crate::Result {
+    let dir = repo_path()?;
+    let nested = dir.join("some/very");
+
+    let repo = Repository::discover_opts(&nested, open::Options::default().git_dir(dir.join("bare.git")))?;
+    assert_eq!(repo.kind(), Kind::Bare, "the git dir is used as is without discovery");
+
+    let repo = Repository::open_opts(&nested, open::Options::default().git_dir(dir.join(".git")))?;
+    assert_eq!(repo.git_dir(), dir.join(".git"));
+    assert_eq!(
+        repo.working_tree.as_deref(),
+        Some(nested.as_path()),
+        "the given path is the working tree"
+    );
+
+    let repo = Repository::open_opts(&dir, open::Options::default().work_tree(&nested))?;
+    assert_eq!(repo.working_tree.as_deref(), Some(nested.as_path()));
+    Ok(())
+}
+
+#[test]
+fn object_dirs_from_options() -> crate::Result {
+    let dir = repo_path()?;
+    let objects = dir.join("bare.git/objects");
+
+    let repo = Repository::open_opts(&dir, open::Options::default().object_dir(&objects))?;
+    assert_eq!(repo.objects_dir(), objects);
+    assert!(Repository::open_opts(&dir, open::Options::default().object_dir(dir.join("missing"))).is_err());
+
+    let repo = Repository::open_opts(&dir, open::Options::default().alternate_object_dirs(Some(&objects)))?;
+    assert_eq!(repo.odb.dbs.len(), 2, "alternates are appended to the object database");
+    Ok(())
+}
+
+#[test]
+fn ownership_of_own_repositories_is_trusted() -> crate::Result {
+    let dir = repo_path()?;
+    Repository::open_opts(&dir, open::Options::default().check_ownership(true))?;
+    Repository::open_opts(&dir, open::Options::default().safe_directories(Some("*")))?;
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn repositories_owned_by_someone_else_are_only_trusted_if_they_are_safe_directories() -> crate::Result {
+    let tmp = git_testtools::scripted_fixture_repo_writable("make_basic_repo.sh")?;
+    let dir = tmp.path();
+    if std::os::unix::fs::chown(dir, Some(12345), None).is_err() {
+        // only a privileged user can give away files
+        return Ok(());
+    }
+    let options = || open::Options::default().global_config(false).system_config(false);
+    assert!(matches!(
+        Repository::open_opts(dir, options()),
+        Err(open::Error::UnsafeRepository(path)) if path == dir
+    ));
+    Repository::open_opts(dir, options().check_ownership(false))?;
+    Repository::open_opts(dir, options().safe_directories(Some(dir)))?;
+    Repository::open_opts(
+        dir,
+        options().config_overrides(Some(format!("safe.directory={}", dir.display()))),
+    )?;
+    Repository::open_opts(dir, options().config_overrides(Some("safe.directory=*")))?;
+
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(dir.join(".git/config"))?
+        .write_all(format!("[safe]\n\tdirectory = {}\n", dir.display()).as_bytes())?;
+    assert!(
+        matches!(
+            Repository::open_opts(dir, options()),
+            Err(open::Error::UnsafeRepository(_))
+        ),
+        "the configuration of the repository itself can't make it safe"
+    );
+    Ok(())
+}
+
+#[test]
+fn environment_is_only_used_if_enabled() -> crate::Result {
+    let dir = repo_path()?;
+    std::env::set_var("GIT_DIR", dir.join("bare.git"));
+    let from_env = Repository::discover_opts(&dir, open::Options::from_env()).map(|repo| repo.kind());
+    let isolated = Repository::discover_opts(&dir, open::Options::default()).map(|repo| repo.kind());
+    std::env::remove_var("GIT_DIR");
+
+    assert_eq!(from_env?, Kind::Bare);
+    assert_eq!(isolated?, Kind::WorkingTree);
+    Ok(())
+}
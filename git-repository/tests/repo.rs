@@ -1,3 +1,8 @@
 type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
 
+mod config;
 mod discover;
+mod open;
+mod status;
+mod submodule;
+mod worktree;
@@ -0,0 +1,34 @@
+use git_repository::{open, Repository};
+
+fn open(path: &std::path::Path, excludes_file: &std::path::Path) -> crate::Result<Repository> {
+    Ok(Repository::open_opts(
+        path,
+        open::Options::default().config_overrides(Some(format!("core.excludesFile={}", excludes_file.display()))),
+    )?)
+}
+
+#[test]
+fn unborn_heads_and_missing_indices_are_empty_and_global_excludes_are_respected() -> crate::Result {
+    let dir = git_testtools::scripted_fixture_repo_writable("make_unborn_repo.sh")?;
+    let excludes_file = dir.path().join("global-excludes");
+    std::fs::write(&excludes_file, "ignored\n")?;
+
+    let unborn = dir.path().join("unborn");
+    assert!(!unborn.join(".git/index").exists());
+    assert!(!open(&unborn, &excludes_file)?.is_dirty()?);
+    assert!(open(&unborn, &excludes_file)?.submodules()?.is_empty());
+
+    std::fs::write(unborn.join("ignored"), "")?;
+    assert!(
+        !open(&unborn, &excludes_file)?.is_dirty()?,
+        "files ignored by core.excludesFile don't count"
+    );
+    std::fs::write(unborn.join("untracked"), "")?;
+    assert!(open(&unborn, &excludes_file)?.is_dirty()?);
+
+    assert!(
+        open(&dir.path().join("unborn-with-index"), &excludes_file)?.is_dirty()?,
+        "staged files are changes compared to the empty tree"
+    );
+    Ok(())
+}
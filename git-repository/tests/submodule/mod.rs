@@ -0,0 +1,119 @@
+use git_repository::{submodule, Repository};
+
+fn fixture() -> crate::Result<std::path::PathBuf> {
+    git_testtools::scripted_fixture_repo_read_only("make_submodule_repo.sh")
+}
+
+#[test]
+fn gitmodules_are_parsed_in_order_and_sections_without_path_are_skipped() -> crate::Result {
+    let configs = submodule::from_bytes(
+        b"[submodule \"b\"]\n\tpath = \"with space\"\n\turl = ../b\n[submodule \"no-path\"]\n\turl = x\n[submodule \"a\"]\n\tpath = a\n\tbranch = main\n",
+    )?;
+    assert_eq!(
+        configs,
+        vec![
+            submodule::Config {
+                name: "b".into(),
+                path: "with space".into(),
+                url: Some("../b".into()),
+                branch: None,
+            },
+            submodule::Config {
+                name: "a".into(),
+                path: "a".into(),
+                url: None,
+                branch: Some("main".into()),
+            },
+        ]
+    );
+
+    assert!(matches!(
+        submodule::from_bytes(b"[submodule \"../../escape\"]\n\tpath = a\n"),
+        Err(submodule::from_bytes::Error::InvalidName(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn status_of_all_submodules_recursively_matches_git() -> crate::Result {
+    let root = fixture()?;
+    let repo = Repository::discover(root.join("super"))?;
+    let submodules = repo.submodules_recursive()?;
+    assert_eq!(
+        submodules
+            .iter()
+            .map(|s| (s.config.name.to_string(), s.git_dir.clone()))
+            .collect::<Vec<_>>(),
+        vec![
+            ("renamed".into(), root.join("super/.git/modules/renamed")),
+            ("sub".into(), root.join("super/.git/modules/sub")),
+            ("deep".into(), root.join("super/.git/modules/sub/modules/deep")),
+        ],
+        "git directories are named after the submodule and nested ones are in their superproject's git dir"
+    );
+
+    let mut lines = String::new();
+    let mut dirty = Vec::new();
+    for submodule in &submodules {
+        let status = submodule.status()?;
+        let id = match status.as_char(submodule) {
+            '+' => status.checked_out,
+            _ => submodule.commit,
+        }
+        .expect("no conflicts");
+        lines.push_str(&format!(
+            "{}{} {}\n",
+            status.as_char(submodule),
+            id,
+            submodule.config.path
+        ));
+        if status.is_dirty {
+            dirty.push(submodule.config.path.to_string());
+        }
+    }
+    assert_eq!(lines, std::fs::read_to_string(root.join("status.expected"))?);
+    assert_eq!(dirty, vec!["sub"]);
+    Ok(())
+}
+
+#[test]
+fn uninitialized_submodules_are_listed_without_being_opened() -> crate::Result {
+    let root = fixture()?;
+    let repo = Repository::discover(root.join("uninitialized"))?;
+    let submodules = repo.submodules_recursive()?;
+    assert_eq!(
+        submodules.iter().map(|s| s.config.path.to_string()).collect::<Vec<_>>(),
+        vec!["dir/other", "sub"],
+        "nested submodules of submodules which aren't checked out are unknown"
+    );
+    for submodule in &submodules {
+        assert!(submodule.open()?.is_none());
+        assert_eq!(submodule.status()?.as_char(submodule), '-');
+        assert!(
+            submodule.work_dir.is_dir(),
+            "gitlinks are checked out as empty directories"
+        );
+    }
+    assert_eq!(submodules[0].git_dir, root.join("uninitialized/.git/modules/renamed"));
+    assert!(
+        Repository::discover(root.join("super"))?
+            .submodules()?
+            .iter()
+            .all(|s| s.commit.is_some()),
+        "gitlinks are recorded in the index"
+    );
+    Ok(())
+}
+
+#[test]
+fn conflicts_are_indicated_even_if_the_submodule_is_not_checked_out() -> crate::Result {
+    let root = fixture()?;
+    let repo = Repository::discover(root.join("uninitialized"))?;
+    let mut submodule = repo.submodules()?.into_iter().next().expect("at least one submodule");
+    let status = submodule.status()?;
+    assert_eq!(status.as_char(&submodule), '-');
+
+    submodule.commit = None;
+    assert_eq!(status.as_char(&submodule), 'U', "conflicts take precedence");
+    Ok(())
+}
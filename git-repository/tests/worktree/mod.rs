@@ -0,0 +1,244 @@
+use std::{path::Path, process::Command};
+
+use git_repository::{
+    hash::ObjectId,
+    worktree::{self, Head},
+    Kind, Repository,
+};
+
+fn fixture() -> crate::Result<std::path::PathBuf> {
+    git_testtools::scripted_fixture_repo_read_only("make_worktree_repo.sh")
+}
+
+fn committer() -> git_repository::actor::Signature {
+    git_repository::actor::Signature {
+        name: "committer".into(),
+        email: "committer@example.com".into(),
+        time: git_repository::actor::Time {
+            time: 1234,
+            offset: 0,
+            sign: git_repository::actor::Sign::Plus,
+        },
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> crate::Result<String> {
+    let out = Command::new("git").args(args).current_dir(dir).output()?;
+    assert!(out.status.success(), "git {:?} failed: {:?}", args, out);
+    Ok(String::from_utf8(out.stdout)?)
+}
+
+fn ids(worktrees: &[git_repository::Worktree]) -> Vec<String> {
+    worktrees.iter().map(|w| w.id.to_string()).collect()
+}
+
+#[test]
+fn linked_worktrees_are_discovered_through_their_dot_git_file() -> crate::Result {
+    let root = fixture()?;
+    let common_dir = root.join("main/.git");
+    for dir in &[root.join("linked"), root.join("linked/some/dir")] {
+        let path = git_repository::path::discover::existing(dir)?;
+        assert_eq!(path.kind(), Kind::WorkingTree);
+        assert_eq!(path.as_ref(), root.join("linked"));
+        assert_eq!(path.into_repository_directory(), common_dir.join("worktrees/linked"));
+    }
+
+    let path = git_repository::path::discover::existing(common_dir.join("worktrees/linked"))?;
+    assert_eq!(
+        path,
+        git_repository::Path::LinkedWorkTree {
+            work_dir: root.join("linked"),
+            git_dir: common_dir.join("worktrees/linked")
+        },
+        "the worktree is found from its private git directory as well"
+    );
+
+    let repo = Repository::discover(root.join("linked/some"))?;
+    assert_eq!(repo.git_dir(), common_dir.join("worktrees/linked"));
+    assert_eq!(repo.common_dir(), common_dir);
+    assert_eq!(repo.working_tree.as_deref(), Some(root.join("linked").as_path()));
+    assert_eq!(repo.objects_dir(), common_dir.join("objects"));
+    assert_eq!(
+        repo.refs.loose_find_existing("HEAD")?.target.to_string(),
+        "ref: refs/heads/linked"
+    );
+    assert_eq!(
+        ids(&repo.worktrees()?),
+        ids(&Repository::discover(root.join("main"))?.worktrees()?),
+        "all worktrees see the same list of linked worktrees"
+    );
+    Ok(())
+}
+
+#[test]
+fn worktrees_are_listed_with_their_lock_state_and_can_be_pruned() -> crate::Result {
+    let dir = git_testtools::scripted_fixture_repo_writable("make_worktree_repo.sh")?;
+    let repo = Repository::discover(dir.path().join("main"))?;
+    let worktrees = repo.worktrees()?;
+    assert_eq!(ids(&worktrees), vec!["gone", "linked", "locked"]);
+    assert_eq!(worktrees[1].path.as_deref(), Some(dir.path().join("linked").as_path()));
+    assert_eq!(
+        worktrees[2].lock_reason.as_ref().map(|r| r.to_string()).as_deref(),
+        Some("on a usb stick")
+    );
+    assert_eq!(
+        worktrees.iter().map(|w| w.is_prunable()).collect::<Vec<_>>(),
+        vec![true, false, false]
+    );
+
+    let mut locked = worktrees[2].clone();
+    locked.unlock()?;
+    assert!(!worktree::Worktree::at(&locked.git_dir)?.is_locked());
+    let mut linked = worktrees[1].clone();
+    linked.lock("")?;
+    assert_eq!(
+        worktree::Worktree::at(&linked.git_dir)?.lock_reason,
+        Some("".into()),
+        "locks without reason are empty"
+    );
+
+    assert_eq!(ids(&repo.prune_worktrees()?), vec!["gone"]);
+    assert_eq!(ids(&repo.worktrees()?), vec!["linked", "locked"]);
+    assert!(
+        git(&dir.path().join("main"), &["worktree", "list", "--porcelain"])?.contains("locked\n"),
+        "git sees our lock"
+    );
+    Ok(())
+}
+
+#[test]
+fn added_worktrees_are_clean_in_the_eyes_of_git_and_can_be_removed() -> crate::Result {
+    let dir = git_testtools::scripted_fixture_repo_writable("make_worktree_repo.sh")?;
+    let repo = Repository::discover(dir.path().join("main"))?;
+    let head = repo
+        .refs
+        .loose_find_existing("refs/heads/main")?
+        .target
+        .as_id()
+        .map(ObjectId::from)
+        .expect("peeled");
+
+    let path = dir.path().join("linked");
+    assert!(matches!(
+        repo.add_worktree(&path, Head::Detached(head), &committer()),
+        Err(worktree::add::Error::Exists(_))
+    ));
+    assert!(matches!(
+        repo.add_worktree(dir.path().join("new"), Head::Branch("linked".into()), &committer()),
+        Err(worktree::add::Error::BranchCheckedOut { .. })
+    ));
+    assert!(!dir.path().join("new").exists(), "nothing is left behind on error");
+
+    let new = repo.add_worktree(
+        dir.path().join("new"),
+        Head::NewBranch {
+            name: "new".into(),
+            id: head,
+        },
+        &committer(),
+    )?;
+    let detached = repo.add_worktree(dir.path().join("other/gone"), Head::Detached(head), &committer())?;
+    let branch = repo.add_worktree(
+        dir.path().join("other/branch"),
+        Head::Branch("other".into()),
+        &committer(),
+    )?;
+    assert_eq!(detached.id, "gone1", "ids are made unique");
+    assert!(!new.is_locked() && !detached.is_locked() && !branch.is_locked());
+
+    for (worktree, expected_head) in &[(&new, "ref: refs/heads/new"), (&detached, "")] {
+        let path = worktree.path.as_ref().expect("path is set");
+        assert_eq!(
+            git(path, &["status", "--porcelain"])?,
+            "",
+            "the index matches the files"
+        );
+        assert_eq!(std::fs::read(path.join("dir/file"))?, b"nested\n");
+        let head_target = worktree
+            .refs(&repo.config)?
+            .loose_find_existing("HEAD")?
+            .target
+            .to_string();
+        if !expected_head.is_empty() {
+            assert_eq!(&head_target, expected_head);
+        }
+    }
+    assert_eq!(
+        git(&dir.path().join("new"), &["rev-parse", "--git-common-dir"])?.trim(),
+        std::fs::canonicalize(dir.path().join("main/.git"))?
+            .display()
+            .to_string()
+    );
+    assert_eq!(
+        git(&dir.path().join("other/branch"), &["rev-parse", "--abbrev-ref", "HEAD"])?,
+        "other\n"
+    );
+
+    let new_path = new.path.clone().expect("set");
+    std::fs::write(new_path.join("untracked"), "")?;
+    assert!(matches!(
+        repo.remove_worktree(new.clone(), false),
+        Err(worktree::remove::Error::Dirty(_))
+    ));
+    repo.remove_worktree(new, true)?;
+    assert!(!new_path.exists());
+
+    std::fs::write(detached.path.as_ref().expect("set").join("file"), "modified")?;
+    assert!(matches!(
+        repo.remove_worktree(detached.clone(), false),
+        Err(worktree::remove::Error::Dirty(_))
+    ));
+
+    let mut branch = branch;
+    std::fs::write(dir.path().join("main/.git/info/exclude"), "*.ignored\n")?;
+    std::fs::write(branch.path.as_ref().expect("set").join("file.ignored"), "")?;
+    branch.lock("reason")?;
+    assert!(matches!(
+        repo.remove_worktree(branch.clone(), true),
+        Err(worktree::remove::Error::Locked(_))
+    ));
+    branch.unlock()?;
+    repo.remove_worktree(branch, false)?;
+    assert_eq!(ids(&repo.worktrees()?), vec!["gone", "gone1", "linked", "locked"]);
+    Ok(())
+}
+
+#[test]
+fn branches_created_for_failed_worktrees_are_removed_and_reflogs_follow_the_configuration() -> crate::Result {
+    let dir = git_testtools::scripted_fixture_repo_writable("make_worktree_repo.sh")?;
+    let main = dir.path().join("main");
+    let blob = ObjectId::from_hex(git(&main, &["rev-parse", "main:file"])?.trim().as_bytes())?;
+    let repo = Repository::discover(&main)?;
+    assert!(
+        repo.add_worktree(
+            dir.path().join("new"),
+            Head::NewBranch {
+                name: "new".into(),
+                id: blob,
+            },
+            &committer(),
+        )
+        .is_err(),
+        "blobs can't be checked out"
+    );
+    assert!(
+        repo.refs.loose_find("refs/heads/new")?.is_none(),
+        "the branch created for the worktree is removed"
+    );
+    assert!(!main.join(".git/logs/refs/heads/new").exists(), "as is its log");
+
+    git(&main, &["config", "core.logAllRefUpdates", "false"])?;
+    let repo = Repository::discover(&main)?;
+    let head = ObjectId::from_hex(git(&main, &["rev-parse", "main"])?.trim().as_bytes())?;
+    let new = repo.add_worktree(
+        dir.path().join("new"),
+        Head::NewBranch {
+            name: "new".into(),
+            id: head,
+        },
+        &committer(),
+    )?;
+    assert!(!main.join(".git/logs/refs/heads/new").exists());
+    assert!(!new.git_dir.join("logs/HEAD").exists());
+    Ok(())
+}
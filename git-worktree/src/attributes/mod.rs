@@ -0,0 +1,156 @@
+//! Determine the attributes of paths in a worktree from `.gitattributes` files, `.git/info/attributes` and `core.attributesFile`,
+//! similar to `git check-attr`.
+use std::{collections::BTreeMap, path::PathBuf};
+
+use git_glob::Pattern;
+use git_object::bstr::{BStr, BString, ByteSlice};
+
+mod parse;
+
+mod stack;
+pub use stack::Stack;
+
+/// The state of an attribute of a path.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub enum State {
+    /// The attribute is set, as in `attr`.
+    Set,
+    /// The attribute is unset, as in `-attr`.
+    Unset,
+    /// The attribute is set to the given value, as in `attr=value`.
+    Value(BString),
+    /// The attribute is unspecified, either because no pattern mentions it or because it was reset with `!attr`.
+    Unspecified,
+}
+
+impl State {
+    /// Return the value of this state if it is a [`Value`][State::Value].
+    pub fn as_value(&self) -> Option<&BStr> {
+        match self {
+            State::Value(value) => Some(value.as_bstr()),
+            _ => None,
+        }
+    }
+
+    /// Return the state like `git check-attr` would display it, i.e. `set`, `unset`, `unspecified` or the value itself.
+    pub fn to_bstring(&self) -> BString {
+        match self {
+            State::Set => "set".into(),
+            State::Unset => "unset".into(),
+            State::Value(value) => value.clone(),
+            State::Unspecified => "unspecified".into(),
+        }
+    }
+}
+
+/// The assignment of a state to the attribute with the given name.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Assignment {
+    /// The name of the attribute.
+    pub name: BString,
+    /// The state of the attribute.
+    pub state: State,
+}
+
+/// What a line of an attributes file applies to.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub enum Kind {
+    /// The assignments apply to paths matching the pattern.
+    Pattern(Pattern),
+    /// The assignments are made whenever the attribute with the given name is set, as in `[attr]name`.
+    Macro(BString),
+}
+
+/// A line of an attributes file with all of its assignments.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Line {
+    /// The pattern or macro the assignments apply to.
+    pub kind: Kind,
+    /// The assignments in the order they appear on the line.
+    pub assignments: Vec<Assignment>,
+    /// The line number in its file, starting at 1.
+    pub sequence_number: usize,
+}
+
+/// All lines read from a single attributes file, along with the directory they apply to.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct List {
+    /// The lines in the order they appear in the file.
+    pub lines: Vec<Line>,
+    /// The file the lines were read from, if any.
+    pub source: Option<PathBuf>,
+    /// The directory relative to the worktree the patterns apply to, with a trailing slash, or empty if they apply
+    /// to the entire worktree.
+    pub base: BString,
+}
+
+impl List {
+    /// Parse the contents of an attributes file in `bytes`, which were read from `source`, for patterns applying to paths in `base`,
+    /// a directory relative to the worktree with trailing slash.
+    ///
+    /// Macros are only accepted if `allow_macros` is true, which is the case for all files but the ones in sub-directories of
+    /// the worktree. Like `git`, invalid lines and negative patterns are ignored.
+    pub fn from_bytes(bytes: &[u8], source: Option<PathBuf>, base: BString, allow_macros: bool) -> Self {
+        let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+        let lines = bytes
+            .lines_with_terminator()
+            .enumerate()
+            .filter_map(|(idx, line)| {
+                parse::line(line)
+                    .filter(|(kind, _)| allow_macros || !matches!(kind, Kind::Macro(_)))
+                    .map(|(kind, assignments)| Line {
+                        kind,
+                        assignments,
+                        sequence_number: idx + 1,
+                    })
+            })
+            .collect();
+        List { lines, source, base }
+    }
+
+    /// Read the lines from the file at `path` for use with paths in `base`, or return `None` if it doesn't exist.
+    pub fn from_file(path: impl Into<PathBuf>, base: BString, allow_macros: bool) -> std::io::Result<Option<Self>> {
+        let path = path.into();
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(Some(List::from_bytes(&bytes, Some(path), base, allow_macros))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// The macros `git` defines without them appearing in any file, which is `binary`.
+    pub fn builtin_macros() -> Self {
+        List::from_bytes(b"[attr]binary -diff -merge -text", None, BString::default(), true)
+    }
+}
+
+/// The attributes of a path as returned by [`Stack::attributes()`].
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Outcome {
+    /// All attributes which are not unspecified, by name.
+    pub attributes: BTreeMap<BString, State>,
+}
+
+impl Outcome {
+    /// Return the state of the attribute with `name`, which is [`Unspecified`][State::Unspecified] if it wasn't mentioned.
+    pub fn state(&self, name: &str) -> &State {
+        self.attributes
+            .get(name.as_bytes().as_bstr())
+            .unwrap_or(&State::Unspecified)
+    }
+
+    /// Return true if the attribute with `name` is [set][State::Set].
+    pub fn is_set(&self, name: &str) -> bool {
+        *self.state(name) == State::Set
+    }
+
+    /// Return true if the attribute with `name` is [unset][State::Unset].
+    pub fn is_unset(&self, name: &str) -> bool {
+        *self.state(name) == State::Unset
+    }
+
+    /// Return the value of the attribute with `name` if it is set to a value.
+    pub fn value(&self, name: &str) -> Option<&BStr> {
+        self.state(name).as_value()
+    }
+}
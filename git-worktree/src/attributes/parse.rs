@@ -0,0 +1,125 @@
+use git_glob::Pattern;
+use git_object::bstr::BString;
+
+use crate::attributes::{Assignment, Kind, State};
+
+const BLANK: &[u8] = b" \t\r\n";
+
+fn is_blank(b: &u8) -> bool {
+    BLANK.contains(b)
+}
+
+/// Return `bytes` without leading blanks.
+fn skip_blanks(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| !is_blank(b)).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+/// Split `bytes` at the first blank.
+fn split_at_blank(bytes: &[u8]) -> (&[u8], &[u8]) {
+    bytes.split_at(bytes.iter().position(is_blank).unwrap_or(bytes.len()))
+}
+
+fn is_valid_attribute_name(name: &[u8]) -> bool {
+    name.first().map_or(false, |b| *b != b'-')
+        && name
+            .iter()
+            .all(|b| matches!(b, b'-' | b'.' | b'_' | b'0'..=b'9' | b'a'..=b'z' | b'A'..=b'Z'))
+}
+
+/// Parse a single `line` of an attributes file, returning `None` if it is empty, a comment or invalid.
+pub(crate) fn line(line: &[u8]) -> Option<(Kind, Vec<Assignment>)> {
+    let line = skip_blanks(line);
+    if line.is_empty() || line[0] == b'#' {
+        return None;
+    }
+    let (name, states) = match unquote(line) {
+        Some((name, rest)) => (name, rest),
+        None => {
+            let (name, rest) = split_at_blank(line);
+            (name.into(), rest)
+        }
+    };
+
+    const MACRO_PREFIX: &[u8] = b"[attr]";
+    let kind = if name.len() > MACRO_PREFIX.len() && name.starts_with(MACRO_PREFIX) {
+        let (name, _) = split_at_blank(skip_blanks(&name[MACRO_PREFIX.len()..]));
+        if !is_valid_attribute_name(name) {
+            return None;
+        }
+        Kind::Macro(name.into())
+    } else {
+        let pattern = Pattern::from_bytes(&name)?;
+        if pattern.is_negative() {
+            return None;
+        }
+        Kind::Pattern(pattern)
+    };
+
+    let mut assignments = Vec::new();
+    let mut states = skip_blanks(states);
+    while !states.is_empty() {
+        let (token, rest) = split_at_blank(states);
+        assignments.push(assignment(token)?);
+        states = skip_blanks(rest);
+    }
+    Some((kind, assignments))
+}
+
+/// Parse a single `token` like `attr`, `-attr`, `!attr` or `attr=value`.
+fn assignment(token: &[u8]) -> Option<Assignment> {
+    let (name, value) = match token.iter().position(|b| *b == b'=') {
+        Some(pos) => (&token[..pos], Some(&token[pos + 1..])),
+        None => (token, None),
+    };
+    let (name, state) = match name.first() {
+        Some(b'-') => (&name[1..], State::Unset),
+        Some(b'!') => (&name[1..], State::Unspecified),
+        _ => (name, value.map_or(State::Set, |value| State::Value(value.into()))),
+    };
+    is_valid_attribute_name(name).then(|| Assignment {
+        name: name.into(),
+        state,
+    })
+}
+
+/// Unquote the C-style quoted string at the beginning of `input`, returning it along with the remaining input, or
+/// `None` if `input` isn't quoted or the quoting is invalid.
+fn unquote(input: &[u8]) -> Option<(BString, &[u8])> {
+    if input.first() != Some(&b'"') {
+        return None;
+    }
+    let mut out = BString::default();
+    let mut pos = 1;
+    loop {
+        let b = *input.get(pos)?;
+        pos += 1;
+        match b {
+            b'"' => return Some((out, &input[pos..])),
+            b'\\' => {
+                let escaped = *input.get(pos)?;
+                pos += 1;
+                out.push(match escaped {
+                    b'a' => 0x07,
+                    b'b' => 0x08,
+                    b'f' => 0x0c,
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    b'v' => 0x0b,
+                    b'"' | b'\\' => escaped,
+                    b'0'..=b'3' => {
+                        let digits = input.get(pos..pos + 2)?;
+                        if !digits.iter().all(|b| (b'0'..=b'7').contains(b)) {
+                            return None;
+                        }
+                        pos += 2;
+                        ((escaped - b'0') << 6) | ((digits[0] - b'0') << 3) | (digits[1] - b'0')
+                    }
+                    _ => return None,
+                });
+            }
+            _ => out.push(b),
+        }
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use git_glob::pattern::Case;
+use git_object::bstr::{BStr, BString, ByteSlice};
+
+use crate::attributes::{Assignment, Kind, List, Outcome, State};
+
+#[derive(Clone)]
+struct Directory {
+    /// The path of the directory relative to the worktree, with trailing slash unless it's the root.
+    path: BString,
+    /// The lines of the `.gitattributes` file in this directory.
+    list: Option<List>,
+}
+
+/// A stack of attribute lists of the directories leading to a path, which is adjusted as paths in different directories
+/// are queried, making it efficient to use while traversing the worktree.
+///
+/// For each attribute, the state assigned by the list with the highest precedence wins, which is `.git/info/attributes`
+/// followed by `.gitattributes` files from the deepest directory up to the root of the worktree, followed by
+/// [global lists][Stack::new()]. Within a list, the last matching line wins.
+#[derive(Clone)]
+pub struct Stack {
+    worktree: PathBuf,
+    case: Case,
+    globals: Vec<List>,
+    info: Option<List>,
+    dirs: Vec<Directory>,
+    macros: HashMap<BString, Vec<Assignment>>,
+}
+
+impl Stack {
+    /// Create a new stack for the `worktree`, using `globals` for paths in all directories, with lists at the end taking precedence,
+    /// and `info` as list with the highest precedence.
+    ///
+    /// Typically the globals are the [builtin macros][List::builtin_macros()] followed by the list read from `core.attributesFile`,
+    /// whereas `info` is read from `.git/info/attributes`.
+    /// `case` determines if patterns match regardless of case, like `core.ignoreCase` does.
+    pub fn new(worktree: impl Into<PathBuf>, globals: Vec<List>, info: Option<List>, case: Case) -> Self {
+        let worktree = worktree.into();
+        let root = Directory {
+            list: read_gitattributes(&worktree, BString::default()),
+            path: BString::default(),
+        };
+        let mut macros = HashMap::new();
+        let lists = info.iter().chain(root.list.iter()).chain(globals.iter().rev());
+        for line in lists.flat_map(|list| list.lines.iter().rev()) {
+            if let Kind::Macro(name) = &line.kind {
+                macros.entry(name.clone()).or_insert_with(|| line.assignments.clone());
+            }
+        }
+        Stack {
+            worktree,
+            case,
+            globals,
+            info,
+            dirs: vec![root],
+            macros,
+        }
+    }
+
+    /// Create a new stack for the `worktree` of the repository at `git_dir`, reading the global lists from `attributes_file`,
+    /// the value of `core.attributesFile`, and `.git/info/attributes`.
+    pub fn from_git_dir(
+        worktree: impl Into<PathBuf>,
+        git_dir: &Path,
+        attributes_file: Option<&Path>,
+        case: Case,
+    ) -> std::io::Result<Self> {
+        let mut globals = vec![List::builtin_macros()];
+        if let Some(attributes_file) = attributes_file {
+            globals.extend(List::from_file(attributes_file, BString::default(), true)?);
+        }
+        let info = List::from_file(git_dir.join("info").join("attributes"), BString::default(), true)?;
+        Ok(Stack::new(worktree, globals, info, case))
+    }
+
+    /// Return all attributes of `path`, relative to the worktree, with `is_dir` indicating if it is a directory.
+    ///
+    /// Note that patterns ending with a slash only match directories, which is why they don't apply to the files within them.
+    pub fn attributes(&mut self, path: &BStr, is_dir: bool) -> Outcome {
+        let dir_len = path.rfind_byte(b'/').map_or(0, |pos| pos + 1);
+        self.set_directory(&path[..dir_len]);
+
+        let mut assigned = HashMap::new();
+        let lists = self
+            .info
+            .iter()
+            .chain(self.dirs.iter().rev().filter_map(|dir| dir.list.as_ref()))
+            .chain(self.globals.iter().rev());
+        for list in lists {
+            let relative_path = &path[list.base.len()..];
+            let basename_start_pos = relative_path.rfind_byte(b'/').map(|pos| pos + 1);
+            for line in list.lines.iter().rev() {
+                let matches = match &line.kind {
+                    Kind::Pattern(pattern) => pattern.matches_repo_relative_path(
+                        relative_path.as_bstr(),
+                        basename_start_pos,
+                        is_dir,
+                        self.case,
+                    ),
+                    Kind::Macro(_) => false,
+                };
+                if matches {
+                    assign(&line.assignments, &self.macros, &mut assigned);
+                }
+            }
+        }
+        Outcome {
+            attributes: assigned
+                .into_iter()
+                .filter(|(_, state)| **state != State::Unspecified)
+                .map(|(name, state)| (name.clone(), state.clone()))
+                .collect(),
+        }
+    }
+
+    /// Adjust the stack so that it contains all directories leading to `dir`, which is empty or has a trailing slash.
+    fn set_directory(&mut self, dir: &[u8]) {
+        while self.dirs.len() > 1 && !dir.starts_with(&self.dirs.last().expect("non-empty").path) {
+            self.dirs.pop();
+        }
+        while self.dirs.last().expect("root is always present").path.len() < dir.len() {
+            let parent_len = self.dirs.last().expect("root is always present").path.len();
+            let end = dir[parent_len..]
+                .find_byte(b'/')
+                .map(|pos| parent_len + pos)
+                .expect("directories end with a slash");
+            let path: BString = dir[..=end].into();
+            self.dirs.push(Directory {
+                list: read_gitattributes(&self.worktree, path.clone()),
+                path,
+            });
+        }
+    }
+}
+
+/// Assign all `assignments` in reverse order to attributes which don't have a state yet, expanding macros that are set.
+fn assign<'a>(
+    assignments: &'a [Assignment],
+    macros: &'a HashMap<BString, Vec<Assignment>>,
+    assigned: &mut HashMap<&'a BString, &'a State>,
+) {
+    for assignment in assignments.iter().rev() {
+        if assigned.contains_key(&assignment.name) {
+            continue;
+        }
+        assigned.insert(&assignment.name, &assignment.state);
+        if assignment.state == State::Set {
+            if let Some(macro_assignments) = macros.get(&assignment.name) {
+                assign(macro_assignments, macros, assigned);
+            }
+        }
+    }
+}
+
+/// Read the `.gitattributes` file in the directory `base` relative to the `worktree`, ignoring it if it can't be read or isn't
+/// a regular file. Only the file at the root of the worktree may define macros.
+fn read_gitattributes(worktree: &Path, base: BString) -> Option<List> {
+    let mut path = worktree.join(base.to_path_lossy());
+    path.push(".gitattributes");
+    let is_file = std::fs::symlink_metadata(&path).map_or(false, |m| m.is_file());
+    if !is_file {
+        return None;
+    }
+    let allow_macros = base.is_empty();
+    List::from_file(path, base, allow_macros).ok().flatten()
+}
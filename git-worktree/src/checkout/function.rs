@@ -0,0 +1,444 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    path::Path,
+};
+
+use git_features::parallel::{self, in_parallel, Reduce};
+use git_index::{entry, State};
+use git_object::{
+    bstr::{BStr, BString, ByteSlice},
+    tree::EntryMode,
+};
+use git_odb::{linked, pack, Find};
+
+use crate::{
+    checkout::{Collision, Error, Options, Outcome},
+    filter, fs,
+};
+
+/// Write all entries of the `index` into the `worktree` directory, reading blobs from `odb`, and update the stat information
+/// of the written entries.
+///
+/// Files in the worktree which already have the content of their entry are left alone. All other files and directories that
+/// are in the way of an entry are considered modified or untracked, and unless [`Options::overwrite_existing`] is set,
+/// nothing is written if there is any such file and [`Error::WouldOverwrite`] is returned.
+///
+/// Entries which are conflicted, skipped in the worktree or added with the intent to add them aren't written,
+/// and submodules are written as empty directories.
+/// Before anything is written, all paths are validated like `git` does and [`Error::InvalidPath`] is returned for paths
+/// which could point outside of the `worktree` or into the `.git` directory.
+/// If `filters` are given, the content of files is converted with them before it is written, and before existing files are
+/// compared to their entries.
+/// Otherwise, large loose objects are streamed into their files instead of being decompressed into memory.
+/// Files are written in parallel using up to `options.thread_limit` threads.
+pub fn checkout(
+    index: &mut State,
+    worktree: &Path,
+    odb: &linked::Store,
+    filters: Option<&filter::Pipeline>,
+    options: Options,
+) -> Result<Outcome, Error> {
+    let mut outcome = Outcome::default();
+    let selected = select_entries(index.entries(), options.ignore_case, &mut outcome.collisions);
+
+    let entries = index.entries();
+    if let Some(idx) = selected
+        .iter()
+        .find(|idx| !is_valid_path(entries[**idx].path.as_bstr()))
+    {
+        return Err(Error::InvalidPath {
+            path: entries[*idx].path.clone(),
+        });
+    }
+    let checks = if options.overwrite_existing {
+        selected.iter().map(|idx| (*idx, Check::Write)).collect()
+    } else {
+        for_each_entry(&selected, options.thread_limit, filters, |idx, state| {
+            Ok((idx, check(&entries[idx], worktree, odb, options, state)?))
+        })?
+    };
+
+    let refused: Vec<_> = checks
+        .iter()
+        .filter(|(_, check)| *check == Check::Refused)
+        .map(|(idx, _)| entries[*idx].path.clone())
+        .collect();
+    if !refused.is_empty() {
+        return Err(Error::WouldOverwrite { paths: refused });
+    }
+
+    let mut to_write = Vec::new();
+    let mut stats = Vec::new();
+    for (idx, check) in checks {
+        match check {
+            Check::UpToDate(stat) => {
+                outcome.files_up_to_date += 1;
+                stats.extend(stat.map(|stat| (idx, stat)));
+            }
+            Check::Write => to_write.push(idx),
+            Check::Refused => unreachable!("refusals were handled"),
+        }
+    }
+
+    for (idx, stat, bytes_written) in for_each_entry(&to_write, options.thread_limit, filters, |idx, state| {
+        let (stat, bytes_written) = write(&entries[idx], worktree, odb, options, state)?;
+        Ok((idx, stat, bytes_written))
+    })? {
+        outcome.files_written += 1;
+        outcome.bytes_written += bytes_written;
+        stats.extend(stat.map(|stat| (idx, stat)));
+    }
+
+    let entries = index.entries_mut();
+    for (idx, stat) in stats {
+        entries[idx].stat = stat;
+    }
+    Ok(outcome)
+}
+
+/// Return the indices of all entries which should be written, recording all entries whose path collides with another one
+/// if `ignore_case` is true.
+fn select_entries(entries: &[git_index::Entry], ignore_case: bool, collisions: &mut Vec<Collision>) -> Vec<usize> {
+    let mut files = HashMap::<BString, usize>::new();
+    let mut dirs = HashMap::<BString, usize>::new();
+    let mut out = Vec::new();
+    for (idx, entry) in entries.iter().enumerate() {
+        if entry.stage() != 0
+            || entry.mode.is_tree()
+            || entry
+                .flags
+                .intersects(entry::Flags::SKIP_WORKTREE | entry::Flags::INTENT_TO_ADD)
+        {
+            continue;
+        }
+        if ignore_case {
+            let folded = entry.path.to_ascii_lowercase();
+            let leading_dirs = folded
+                .iter()
+                .enumerate()
+                .filter(|(_, b)| **b == b'/')
+                .map(|(pos, _)| &folded[..pos]);
+            let colliding = files
+                .get(folded.as_bstr())
+                .or_else(|| dirs.get(folded.as_bstr()))
+                .copied()
+                .or_else(|| leading_dirs.clone().find_map(|dir| files.get(dir.as_bstr()).copied()));
+            if let Some(other) = colliding {
+                collisions.push(Collision {
+                    path: entry.path.clone(),
+                    with: entries[other].path.clone(),
+                });
+                continue;
+            }
+            for dir in leading_dirs {
+                dirs.entry(dir.into()).or_insert(idx);
+            }
+            files.insert(folded.into(), idx);
+        }
+        out.push(idx);
+    }
+    out
+}
+
+/// Return true if `path` is a relative path whose components are all valid, similar to `verify_path()` in `git`.
+///
+/// Components may not be empty, `.` or `..`, and may not be named `.git` in any case, which also holds for the short name
+/// `git~1` and names with trailing dots or spaces which are equivalent to it on NTFS. Backslashes are treated as path separators
+/// as they are one on Windows.
+fn is_valid_path(path: &BStr) -> bool {
+    path.split(|b| *b == b'/' || *b == b'\\').all(|component| {
+        let trimmed = component.trim_end_with(|c| c == '.' || c == ' ');
+        !(component.is_empty()
+            || component == b"."
+            || component == b".."
+            || trimmed.eq_ignore_ascii_case(b".git")
+            || trimmed.eq_ignore_ascii_case(b"git~1"))
+    })
+}
+
+/// The state of a thread writing entries.
+struct ThreadState {
+    /// Scratch space for reading objects.
+    buf: Vec<u8>,
+    /// The filters to apply to files, cloned for use by this thread.
+    filters: Option<filter::Pipeline>,
+}
+
+/// Call `f` with each of the `indices` and the state of the calling thread, possibly in parallel, and collect the results.
+fn for_each_entry<O: Send>(
+    indices: &[usize],
+    thread_limit: Option<usize>,
+    filters: Option<&filter::Pipeline>,
+    f: impl Fn(usize, &mut ThreadState) -> Result<O, Error> + Send + Sync,
+) -> Result<Vec<O>, Error> {
+    let (chunk_size, thread_limit, _) =
+        parallel::optimize_chunk_size_and_thread_limit(50, Some(indices.len()), thread_limit, None);
+    in_parallel(
+        indices.chunks(chunk_size),
+        thread_limit,
+        |_| ThreadState {
+            buf: Vec::new(),
+            filters: filters.cloned(),
+        },
+        |chunk, state| chunk.iter().map(|idx| f(*idx, state)).collect::<Result<Vec<_>, _>>(),
+        Collect(Vec::new()),
+    )
+}
+
+struct Collect<O>(Vec<O>);
+
+impl<O> Reduce for Collect<O> {
+    type Input = Result<Vec<O>, Error>;
+    type FeedProduce = ();
+    type Output = Vec<O>;
+    type Error = Error;
+
+    fn feed(&mut self, item: Self::Input) -> Result<Self::FeedProduce, Self::Error> {
+        self.0.extend(item?);
+        Ok(())
+    }
+
+    fn finalize(self) -> Result<Self::Output, Self::Error> {
+        Ok(self.0)
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+enum Check {
+    /// The file already has the desired content and only needs the given stat information to be recorded, if any.
+    UpToDate(Option<entry::Stat>),
+    /// Nothing is in the way of the entry.
+    Write,
+    /// A modified or untracked file or directory is in the way of the entry.
+    Refused,
+}
+
+/// Determine if `entry` can be written without overwriting anything that would be lost.
+fn check(
+    entry: &git_index::Entry,
+    worktree: &Path,
+    odb: &linked::Store,
+    options: Options,
+    state: &mut ThreadState,
+) -> Result<Check, Error> {
+    let path = worktree.join(entry.path.to_path_lossy());
+    let io_err = |err| Error::Io {
+        source: err,
+        path: path.clone(),
+    };
+    let mut leading_dir = worktree.to_owned();
+    let mut components = entry.path.split_str("/").peekable();
+    while let Some(component) = components.next() {
+        if components.peek().is_none() {
+            break;
+        }
+        leading_dir.push(component.to_path_lossy());
+        match std::fs::symlink_metadata(&leading_dir) {
+            Ok(metadata) if metadata.is_dir() => continue,
+            Ok(_) => return Ok(Check::Refused),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Check::Write),
+            Err(err) => return Err(io_err(err)),
+        }
+    }
+
+    let metadata = match std::fs::symlink_metadata(&path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Check::Write),
+        Err(err) => return Err(io_err(err)),
+    };
+    if metadata.is_dir() {
+        return Ok(if entry.mode == EntryMode::Commit {
+            Check::UpToDate(None)
+        } else if is_empty_dir(&path) {
+            Check::Write
+        } else {
+            Check::Refused
+        });
+    }
+    let mode = fs::mode(&metadata, options.executable_bit);
+    if entry.mode == EntryMode::Commit || mode != Some(worktree_mode(entry.mode, options)) {
+        return Ok(Check::Refused);
+    }
+    let id = match (&mut state.filters, mode.expect("checked")) {
+        (Some(filters), mode) if mode != EntryMode::Link => {
+            let buf = &mut state.buf;
+            filters.blob_id(&path, entry.path.as_bstr(), || {
+                find_blob(entry, odb, buf).map_or(false, filter::has_crlf)
+            })?
+        }
+        (_, mode) => fs::blob_id(&path, mode).map_err(io_err)?,
+    };
+    Ok(if id == entry.id {
+        Check::UpToDate(Some(fs::stat(&metadata)))
+    } else {
+        Check::Refused
+    })
+}
+
+/// Return the mode `entry_mode` will have once written to the worktree.
+fn worktree_mode(entry_mode: EntryMode, options: Options) -> EntryMode {
+    match entry_mode {
+        EntryMode::Link if !options.symlinks => EntryMode::Blob,
+        EntryMode::BlobExecutable if !options.executable_bit => EntryMode::Blob,
+        mode => mode,
+    }
+}
+
+fn is_empty_dir(path: &Path) -> bool {
+    std::fs::read_dir(path).map_or(false, |mut entries| entries.next().is_none())
+}
+
+/// Write `entry` into the `worktree`, replacing everything in its way, and return its stat information along with
+/// the amount of bytes written.
+fn write(
+    entry: &git_index::Entry,
+    worktree: &Path,
+    odb: &linked::Store,
+    options: Options,
+    state: &mut ThreadState,
+) -> Result<(Option<entry::Stat>, u64), Error> {
+    let buf = &mut state.buf;
+    let path = worktree.join(entry.path.to_path_lossy());
+    let io_err = |path: &Path| {
+        let path = path.to_owned();
+        move |err| Error::Io { source: err, path }
+    };
+    create_leading_dirs(worktree, &path).map_err(io_err(&path))?;
+    remove(&path).map_err(io_err(&path))?;
+
+    let mut bytes_written = 0;
+    match worktree_mode(entry.mode, options) {
+        EntryMode::Commit => {
+            std::fs::create_dir(&path).map_err(io_err(&path))?;
+            return Ok((None, 0));
+        }
+        EntryMode::Link => {
+            let target = find_blob(entry, odb, buf)?;
+            symlink(target.to_path_lossy().as_ref(), &path).map_err(io_err(&path))?;
+        }
+        mode => {
+            let mut file = create_file(&path, mode == EntryMode::BlobExecutable).map_err(io_err(&path))?;
+            let stream = match state.filters {
+                Some(_) => None,
+                None => odb.stream(entry.id)?,
+            };
+            bytes_written = match stream {
+                Some(mut stream) => {
+                    if stream.kind != git_object::Kind::Blob {
+                        return Err(Error::ObjectKind {
+                            oid: entry.id,
+                            path: entry.path.clone(),
+                            actual: stream.kind,
+                        });
+                    }
+                    let expected = stream.size;
+                    let actual = io::copy(&mut stream, &mut file).map_err(io_err(&path))?;
+                    if actual != expected {
+                        return Err(Error::Truncated {
+                            oid: entry.id,
+                            path: entry.path.clone(),
+                            expected,
+                            actual,
+                        });
+                    }
+                    actual
+                }
+                None => {
+                    let data = find_blob(entry, odb, buf)?;
+                    let data = match &mut state.filters {
+                        Some(filters) => filters.convert_to_worktree(data, entry.path.as_bstr(), &entry.id)?,
+                        None => data.into(),
+                    };
+                    file.write_all(&data).map_err(io_err(&path))?;
+                    data.len() as u64
+                }
+            };
+        }
+    }
+    let metadata = std::fs::symlink_metadata(&path).map_err(io_err(&path))?;
+    Ok((Some(fs::stat(&metadata)), bytes_written))
+}
+
+fn find_blob<'a>(entry: &git_index::Entry, odb: &linked::Store, buf: &'a mut Vec<u8>) -> Result<&'a [u8], Error> {
+    let object = odb
+        .find(entry.id, buf, &mut pack::cache::Never)?
+        .ok_or_else(|| Error::NotFound {
+            oid: entry.id,
+            path: entry.path.clone(),
+        })?;
+    if object.kind != git_object::Kind::Blob {
+        return Err(Error::ObjectKind {
+            oid: entry.id,
+            path: entry.path.clone(),
+            actual: object.kind,
+        });
+    }
+    Ok(object.data)
+}
+
+/// Create all directories leading to `path` within `worktree`, replacing files and symlinks that are in the way.
+fn create_leading_dirs(worktree: &Path, path: &Path) -> io::Result<()> {
+    let relative = path.strip_prefix(worktree).expect("path is within worktree");
+    let mut dir = worktree.to_owned();
+    let mut components = relative.components().peekable();
+    while let Some(component) = components.next() {
+        if components.peek().is_none() {
+            break;
+        }
+        dir.push(component);
+        match std::fs::symlink_metadata(&dir) {
+            Ok(metadata) if metadata.is_dir() => continue,
+            Ok(_) => std::fs::remove_file(&dir)?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err),
+        }
+        match std::fs::create_dir(&dir) {
+            // Another thread may have created the same directory in the meantime.
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists && dir.is_dir() => {}
+            res => res?,
+        }
+    }
+    Ok(())
+}
+
+/// Remove the file, symlink or directory at `path` if there is one.
+fn remove(path: &Path) -> io::Result<()> {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => std::fs::remove_dir_all(path),
+        Ok(_) => std::fs::remove_file(path),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(err) => Err(err),
+    }
+}
+
+fn create_file(path: &Path, executable: bool) -> io::Result<std::fs::File> {
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(if executable { 0o777 } else { 0o666 });
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+    options.open(path)
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, path: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, path: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(target, path)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn symlink(_target: &Path, _path: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "symlinks aren't supported on this platform",
+    ))
+}
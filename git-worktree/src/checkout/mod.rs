@@ -0,0 +1,111 @@
+//! Write the entries of an index into a worktree, similar to `git checkout-index`.
+use git_object::bstr::BString;
+
+mod function;
+pub use function::checkout;
+
+/// Options for use in [`checkout()`].
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub struct Options {
+    /// The amount of threads to use when writing files, or `None` to use all logical cores.
+    ///
+    /// Note that threads are only used if the `parallel` feature of `git-features` is enabled.
+    pub thread_limit: Option<usize>,
+    /// If true, executable files are written with the executable bit set, like `core.fileMode` does.
+    pub executable_bit: bool,
+    /// If true, symlinks are created as such, otherwise they are written as files containing the link target,
+    /// like `core.symlinks` does.
+    pub symlinks: bool,
+    /// If true, the file system is assumed to be case-insensitive, so paths which only differ in case are considered
+    /// the same path, like `core.ignoreCase` does.
+    ///
+    /// Only the first of the colliding entries is written, and all others are listed as [collisions][Outcome::collisions].
+    pub ignore_case: bool,
+    /// If true, files and directories which are in the way of an entry are replaced even if their content differs from it.
+    pub overwrite_existing: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            thread_limit: None,
+            executable_bit: cfg!(unix),
+            symlinks: cfg!(unix),
+            ignore_case: false,
+            overwrite_existing: false,
+        }
+    }
+}
+
+/// An entry which wasn't written as its path collides with the one of another entry on a case-insensitive file system.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Collision {
+    /// The path of the entry which wasn't written.
+    pub path: BString,
+    /// The path of the entry which was written in its place, or of the directory containing it.
+    pub with: BString,
+}
+
+/// The outcome of a [`checkout()`].
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct Outcome {
+    /// The amount of files, symlinks and submodule directories that were written.
+    pub files_written: usize,
+    /// The amount of bytes written into files, without counting symlinks.
+    pub bytes_written: u64,
+    /// The amount of entries whose file in the worktree already had the desired content, which were left untouched.
+    pub files_up_to_date: usize,
+    /// All entries which weren't written as their path collides with another entry.
+    pub collisions: Vec<Collision>,
+}
+
+mod error {
+    use std::path::PathBuf;
+
+    use git_hash::ObjectId;
+    use git_object::bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`checkout()`][super::checkout()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io { source: std::io::Error, path: PathBuf } {
+                display("Could not write '{}' in the worktree", path.display())
+                source(source)
+            }
+            Filter(err: crate::filter::Error) {
+                display("Could not convert the content of a file")
+                from()
+                source(err)
+            }
+            Find(err: git_odb::compound::find::Error) {
+                display("Could not read an object from the object database")
+                from()
+                source(err)
+            }
+            Stream(err: git_odb::loose::find::Error) {
+                display("Could not stream an object from the object database")
+                from()
+                source(err)
+            }
+            NotFound { oid: ObjectId, path: BString } {
+                display("The object {} for '{}' could not be found", oid, path)
+            }
+            ObjectKind { oid: ObjectId, path: BString, actual: git_object::Kind } {
+                display("The object {} for '{}' is a {} but should be a blob", oid, path, actual)
+            }
+            Truncated { oid: ObjectId, path: BString, expected: u64, actual: u64 } {
+                display("The object {} for '{}' ended after {} of {} bytes", oid, path, actual, expected)
+            }
+            InvalidPath { path: BString } {
+                display("Refusing to check out the invalid path '{}'", path)
+            }
+            WouldOverwrite { paths: Vec<BString> } {
+                display("Refusing to overwrite {} modified or untracked file(s) in the worktree, the first being '{}'", paths.len(), paths[0])
+            }
+        }
+    }
+}
+pub use error::Error;
@@ -0,0 +1,229 @@
+use std::{
+    collections::HashSet,
+    io::{self, Read, Write},
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use git_object::bstr::{BStr, BString, ByteSlice, ByteVec};
+use git_packetline::PacketLine;
+
+/// The direction of a conversion performed by a driver.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub(crate) enum Operation {
+    /// Convert content of the worktree for storage in the object database.
+    Clean,
+    /// Convert content of the object database for use in the worktree.
+    Smudge,
+}
+
+impl Operation {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Operation::Clean => "clean",
+            Operation::Smudge => "smudge",
+        }
+    }
+}
+
+/// Quote `path` for use in a shell like `git` does, and substitute it for `%f` in `command`.
+fn substitute_path(command: &BStr, path: &BStr) -> BString {
+    let mut quoted = BString::from("'");
+    for b in path.iter() {
+        match b {
+            b'\'' | b'!' => {
+                quoted.push_str("'\\");
+                quoted.push(*b);
+                quoted.push(b'\'');
+            }
+            b => quoted.push(*b),
+        }
+    }
+    quoted.push(b'\'');
+    command.replace("%f", quoted.as_slice()).into()
+}
+
+fn shell(command: &BStr) -> Command {
+    let mut cmd = Command::new(if cfg!(windows) { "sh.exe" } else { "sh" });
+    cmd.arg("-c").arg(command.to_os_str_lossy());
+    cmd
+}
+
+/// Run the single-file filter `command` with `data` as its input, returning its output if it succeeded.
+pub(crate) fn run(command: &BStr, path: &BStr, data: &[u8]) -> io::Result<Vec<u8>> {
+    let mut child = shell(substitute_path(command, path).as_bstr())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().expect("configured");
+    let data = data.to_vec();
+    // Write on another thread so filters producing output before consuming all input can't block us.
+    let writer = std::thread::spawn(move || {
+        // Filters are allowed to not read their input, so errors are ignored.
+        stdin.write_all(&data).ok();
+    });
+    let mut output = Vec::new();
+    child.stdout.take().expect("configured").read_to_end(&mut output)?;
+    writer.join().expect("no panic while writing");
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("filter exited with {}", status),
+        ));
+    }
+    Ok(output)
+}
+
+/// A long-running filter process speaking the `git` filter protocol, converting any amount of files.
+pub(crate) struct Process {
+    child: Child,
+    /// The input of the process, which is only taken to close it when dropping the process.
+    input: Option<ChildStdin>,
+    output: git_packetline::StreamingPeekableIter<ChildStdout>,
+    capabilities: HashSet<BString>,
+    /// Operations the process asked us to not use anymore.
+    aborted: HashSet<Operation>,
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        // Closing the input signals the process to shut down, and like `git` we wait for it to do so.
+        drop(self.input.take());
+        self.child.wait().ok();
+    }
+}
+
+/// The result of converting data with a [`Process`].
+pub(crate) enum Outcome {
+    /// The process produced the given output.
+    Converted(Vec<u8>),
+    /// The process doesn't support the operation, or asked to not be used for it anymore, so the data wasn't converted.
+    Unsupported,
+    /// The process couldn't convert the data and responded with the given status.
+    Failed(BString),
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+impl Process {
+    /// Start the process by running `command` and perform the handshake, negotiating the `clean` and `smudge` capabilities.
+    pub(crate) fn start(command: &BStr) -> io::Result<Self> {
+        let mut child = shell(command).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()?;
+        let input = child.stdin.take().expect("configured");
+        let output = child.stdout.take().expect("configured");
+        let mut process = Process {
+            child,
+            input: Some(input),
+            output: git_packetline::StreamingPeekableIter::new(output, &[PacketLine::Flush]),
+            capabilities: HashSet::new(),
+            aborted: HashSet::new(),
+        };
+
+        process.write_text_lines(&["git-filter-client", "version=2"])?;
+        let lines = process.read_text_lines()?;
+        if lines.first().map(|line| line.as_slice()) != Some(b"git-filter-server")
+            || !lines.iter().any(|line| line == "version=2")
+        {
+            return Err(invalid_data(
+                "filter process didn't respond with version 2 of the protocol",
+            ));
+        }
+        process.write_text_lines(&["capability=clean", "capability=smudge"])?;
+        process.capabilities = process
+            .read_text_lines()?
+            .into_iter()
+            .filter_map(|line| line.strip_prefix(b"capability=").map(Into::into))
+            .collect();
+        Ok(process)
+    }
+
+    /// Convert `data` of the file at `path` using `operation`.
+    ///
+    /// Errors indicate that the process can't be used anymore.
+    pub(crate) fn apply(&mut self, operation: Operation, path: &BStr, data: &[u8]) -> io::Result<Outcome> {
+        if !self.capabilities.contains(operation.as_str().as_bytes().as_bstr()) || self.aborted.contains(&operation) {
+            return Ok(Outcome::Unsupported);
+        }
+        let mut pathname = BString::from("pathname=");
+        pathname.push_str(path);
+        self.write_text_lines(&[
+            format!("command={}", operation.as_str()).as_bytes(),
+            pathname.as_slice(),
+        ])?;
+        {
+            let mut writer = git_packetline::Writer::new(self.input()).binary_mode();
+            for chunk in data.chunks(8192) {
+                writer.write_all(chunk)?;
+            }
+        }
+        git_packetline::encode::flush_to_write(self.input())?;
+        self.input().flush()?;
+
+        let status = self.read_status(None)?;
+        if status != "success" {
+            return Ok(self.failed(operation, status));
+        }
+        let mut out = Vec::new();
+        self.read_packets(|data| out.extend_from_slice(data))?;
+        let status = self.read_status(Some(status))?;
+        if status != "success" {
+            return Ok(self.failed(operation, status));
+        }
+        Ok(Outcome::Converted(out))
+    }
+
+    fn input(&mut self) -> &mut ChildStdin {
+        self.input.as_mut().expect("only taken when dropped")
+    }
+
+    /// Read the status sent by the process, keeping `previous` if none is sent.
+    fn read_status(&mut self, previous: Option<BString>) -> io::Result<BString> {
+        let lines = self.read_text_lines()?;
+        lines
+            .into_iter()
+            .rev()
+            .find_map(|line| line.strip_prefix(b"status=").map(Into::into))
+            .or(previous)
+            .ok_or_else(|| invalid_data("filter process didn't send a status"))
+    }
+
+    fn failed(&mut self, operation: Operation, status: BString) -> Outcome {
+        if status == "abort" {
+            self.aborted.insert(operation);
+        }
+        Outcome::Failed(status)
+    }
+
+    fn write_text_lines(&mut self, lines: &[impl AsRef<[u8]>]) -> io::Result<()> {
+        for line in lines {
+            git_packetline::encode::text_to_write(line.as_ref(), self.input())?;
+        }
+        git_packetline::encode::flush_to_write(self.input())?;
+        self.input().flush()
+    }
+
+    /// Read all text lines up to the next flush packet, without their trailing newline.
+    fn read_text_lines(&mut self) -> io::Result<Vec<BString>> {
+        let mut out = Vec::new();
+        self.read_packets(|line| out.push(line.strip_suffix(b"\n").unwrap_or(line).into()))?;
+        Ok(out)
+    }
+
+    /// Call `f` with the data of all packets up to the next flush packet.
+    fn read_packets(&mut self, mut f: impl FnMut(&[u8])) -> io::Result<()> {
+        while let Some(line) = self.output.read_line() {
+            let line = line?.map_err(|err| invalid_data(err.to_string()))?;
+            f(line.as_slice().unwrap_or_default());
+        }
+        if self.output.stopped_at().is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "filter process closed its output",
+            ));
+        }
+        self.output.reset();
+        Ok(())
+    }
+}
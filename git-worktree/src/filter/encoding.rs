@@ -0,0 +1,177 @@
+use std::{borrow::Cow, convert::TryFrom};
+
+use git_object::bstr::BStr;
+
+/// An encoding files in the worktree can have, as set by the `working-tree-encoding` attribute.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub(crate) enum Encoding {
+    Utf16 { big_endian: Option<bool>, bom: bool },
+    Utf32 { big_endian: Option<bool> },
+    Latin1,
+}
+
+impl Encoding {
+    /// Return the encoding with `name`, `None` if it is UTF-8 and thus needs no conversion, or `Err` if it isn't supported.
+    pub(crate) fn from_name(name: &BStr) -> Result<Option<Self>, ()> {
+        let name = name.to_ascii_uppercase();
+        Ok(Some(match name.as_slice() {
+            b"UTF-8" | b"UTF8" => return Ok(None),
+            b"UTF-16" | b"UTF16" => Encoding::Utf16 {
+                big_endian: None,
+                bom: true,
+            },
+            b"UTF-16LE" | b"UTF16LE" => Encoding::Utf16 {
+                big_endian: Some(false),
+                bom: false,
+            },
+            b"UTF-16BE" | b"UTF16BE" => Encoding::Utf16 {
+                big_endian: Some(true),
+                bom: false,
+            },
+            b"UTF-16LE-BOM" => Encoding::Utf16 {
+                big_endian: Some(false),
+                bom: true,
+            },
+            b"UTF-32" | b"UTF32" => Encoding::Utf32 { big_endian: None },
+            b"UTF-32LE" | b"UTF32LE" => Encoding::Utf32 {
+                big_endian: Some(false),
+            },
+            b"UTF-32BE" | b"UTF32BE" => Encoding::Utf32 { big_endian: Some(true) },
+            b"ISO-8859-1" | b"ISO8859-1" | b"LATIN1" | b"LATIN-1" => Encoding::Latin1,
+            _ => return Err(()),
+        }))
+    }
+
+    /// Decode `data` in this encoding into UTF-8, or return a message explaining why it couldn't be decoded.
+    pub(crate) fn decode<'a>(self, data: Cow<'a, [u8]>) -> Result<Cow<'a, [u8]>, &'static str> {
+        if data.is_empty() {
+            return Ok(data);
+        }
+        let chars: Vec<char> = match self {
+            Encoding::Latin1 => {
+                if data.is_ascii() {
+                    return Ok(data);
+                }
+                data.iter().map(|b| char::from(*b)).collect()
+            }
+            Encoding::Utf16 { big_endian, bom } => {
+                let (big_endian, data) = with_bom(&data, big_endian, bom, &[0xfe, 0xff], &[0xff, 0xfe])?;
+                if data.len() % 2 != 0 {
+                    return Err("the data has an odd number of bytes");
+                }
+                let units = data.chunks(2).map(|unit| {
+                    let unit = [unit[0], unit[1]];
+                    if big_endian {
+                        u16::from_be_bytes(unit)
+                    } else {
+                        u16::from_le_bytes(unit)
+                    }
+                });
+                std::char::decode_utf16(units)
+                    .collect::<Result<_, _>>()
+                    .map_err(|_| "the data contains invalid surrogates")?
+            }
+            Encoding::Utf32 { big_endian } => {
+                let (big_endian, data) = with_bom(
+                    &data,
+                    big_endian,
+                    big_endian.is_none(),
+                    &[0, 0, 0xfe, 0xff],
+                    &[0xff, 0xfe, 0, 0],
+                )?;
+                if data.len() % 4 != 0 {
+                    return Err("the data has a number of bytes not divisible by four");
+                }
+                data.chunks(4)
+                    .map(|unit| {
+                        let unit = [unit[0], unit[1], unit[2], unit[3]];
+                        let code_point = if big_endian {
+                            u32::from_be_bytes(unit)
+                        } else {
+                            u32::from_le_bytes(unit)
+                        };
+                        std::char::from_u32(code_point)
+                    })
+                    .collect::<Option<_>>()
+                    .ok_or("the data contains invalid code points")?
+            }
+        };
+        Ok(chars.into_iter().collect::<String>().into_bytes().into())
+    }
+
+    /// Encode the UTF-8 `data` in this encoding, or return a message explaining why it couldn't be encoded.
+    ///
+    /// Like `git` on little-endian platforms, a byte order mark is written along with little-endian data for the encodings
+    /// not specifying the byte order.
+    pub(crate) fn encode<'a>(self, data: Cow<'a, [u8]>) -> Result<Cow<'a, [u8]>, &'static str> {
+        if data.is_empty() {
+            return Ok(data);
+        }
+        let text = std::str::from_utf8(&data).map_err(|_| "the data isn't valid UTF-8")?;
+        let mut out = Vec::with_capacity(data.len() * 2);
+        match self {
+            Encoding::Latin1 => {
+                if data.is_ascii() {
+                    return Ok(data);
+                }
+                for c in text.chars() {
+                    out.push(u8::try_from(u32::from(c)).map_err(|_| "the data can't be represented in ISO-8859-1")?);
+                }
+            }
+            Encoding::Utf16 { big_endian, bom } => {
+                let big_endian = big_endian.unwrap_or(false);
+                let mut push = |unit: u16| {
+                    out.extend_from_slice(&if big_endian {
+                        unit.to_be_bytes()
+                    } else {
+                        unit.to_le_bytes()
+                    })
+                };
+                if bom {
+                    push(0xfeff);
+                }
+                text.encode_utf16().for_each(push);
+            }
+            Encoding::Utf32 { big_endian } => {
+                let mut push = |unit: u32| {
+                    out.extend_from_slice(&if big_endian.unwrap_or(false) {
+                        unit.to_be_bytes()
+                    } else {
+                        unit.to_le_bytes()
+                    })
+                };
+                if big_endian.is_none() {
+                    push(0xfeff);
+                }
+                text.chars().map(u32::from).for_each(push);
+            }
+        }
+        Ok(out.into())
+    }
+}
+
+/// Strip the byte order mark from `data` if `bom` is true and determine the byte order, which is `big_endian` if it is known.
+///
+/// Like `git`, a byte order mark is required if the byte order isn't known, and prohibited if it is known unless `bom` is set.
+fn with_bom<'a>(
+    data: &'a [u8],
+    big_endian: Option<bool>,
+    bom: bool,
+    be_bom: &[u8],
+    le_bom: &[u8],
+) -> Result<(bool, &'a [u8]), &'static str> {
+    let found = if data.starts_with(be_bom) {
+        Some(true)
+    } else if data.starts_with(le_bom) {
+        Some(false)
+    } else {
+        None
+    };
+    match (big_endian, found) {
+        (None, None) => Err("a byte order mark is required"),
+        (None, Some(big_endian)) => Ok((big_endian, &data[be_bom.len()..])),
+        (Some(_), Some(_)) if !bom => Err("a byte order mark is prohibited"),
+        (Some(big_endian), Some(found)) if big_endian == found => Ok((big_endian, &data[be_bom.len()..])),
+        (Some(big_endian), _) => Ok((big_endian, data)),
+    }
+}
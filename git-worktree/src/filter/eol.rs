@@ -0,0 +1,189 @@
+use std::borrow::Cow;
+
+use crate::filter::{AutoCrlf, Eol};
+
+/// Statistics about the content of a file, used to determine if it is binary and how its lines are terminated.
+#[derive(Default)]
+struct Stats {
+    nul: usize,
+    lone_cr: usize,
+    lone_lf: usize,
+    crlf: usize,
+    printable: usize,
+    non_printable: usize,
+}
+
+impl Stats {
+    fn from_bytes(data: &[u8]) -> Self {
+        let mut stats = Stats::default();
+        let mut bytes = data.iter().peekable();
+        while let Some(b) = bytes.next() {
+            match b {
+                b'\r' => {
+                    if bytes.peek() == Some(&&b'\n') {
+                        bytes.next();
+                        stats.crlf += 1;
+                    } else {
+                        stats.lone_cr += 1;
+                    }
+                }
+                b'\n' => stats.lone_lf += 1,
+                127 => stats.non_printable += 1,
+                b'\x08' | b'\t' | b'\x1b' | b'\x0c' => stats.printable += 1,
+                0 => {
+                    stats.nul += 1;
+                    stats.non_printable += 1;
+                }
+                b if *b < 32 => stats.non_printable += 1,
+                _ => stats.printable += 1,
+            }
+        }
+        // A trailing end-of-file marker doesn't count as non-printable character.
+        if data.last() == Some(&b'\x1a') {
+            stats.non_printable -= 1;
+        }
+        stats
+    }
+
+    /// Return true if the content is considered binary, in which case automatic conversions are disabled.
+    fn is_binary(&self) -> bool {
+        self.lone_cr > 0 || self.nul > 0 || (self.printable >> 7) < self.non_printable
+    }
+}
+
+/// How to convert line endings, as determined by attributes and configuration.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub(crate) enum Action {
+    /// Never convert line endings.
+    Binary,
+    /// Convert line endings of text files to LF in the object database, and to `core.eol` in the worktree.
+    Text,
+    /// Convert line endings of text files to LF in the object database, and leave them alone in the worktree.
+    TextInput,
+    /// Convert line endings of text files to LF in the object database, and to CRLF in the worktree.
+    TextCrlf,
+    /// Like `Text`, but only for files detected to be text.
+    Auto,
+    /// Like `TextInput`, but only for files detected to be text.
+    AutoInput,
+    /// Like `TextCrlf`, but only for files detected to be text.
+    AutoCrlf,
+}
+
+impl Action {
+    /// Determine the action from the values of the `text` and `crlf` attributes as `text`, the `eol` attribute and
+    /// the respective configuration values, similar to how `git` does it.
+    pub(crate) fn new(text: Option<Action>, eol: Option<Eol>, auto_crlf: AutoCrlf, core_eol: Option<Eol>) -> Action {
+        let action = match (text, eol) {
+            (Some(Action::Binary), _) => Some(Action::Binary),
+            (Some(Action::Auto), Some(Eol::Lf)) => Some(Action::AutoInput),
+            (Some(Action::Auto), Some(Eol::Crlf)) => Some(Action::AutoCrlf),
+            (_, Some(Eol::Lf)) => Some(Action::TextInput),
+            (_, Some(Eol::Crlf)) => Some(Action::TextCrlf),
+            (text, None) => text,
+        };
+        match action {
+            Some(Action::Text) => {
+                if text_eol_is_crlf(auto_crlf, core_eol) {
+                    Action::TextCrlf
+                } else {
+                    Action::TextInput
+                }
+            }
+            Some(action) => action,
+            None => match auto_crlf {
+                AutoCrlf::False => Action::Binary,
+                AutoCrlf::True => Action::AutoCrlf,
+                AutoCrlf::Input => Action::AutoInput,
+            },
+        }
+    }
+
+    fn is_auto(&self) -> bool {
+        matches!(self, Action::Auto | Action::AutoInput | Action::AutoCrlf)
+    }
+
+    fn output_is_crlf(&self, auto_crlf: AutoCrlf, core_eol: Option<Eol>) -> bool {
+        match self {
+            Action::Binary | Action::TextInput | Action::AutoInput => false,
+            Action::TextCrlf | Action::AutoCrlf => true,
+            Action::Text | Action::Auto => text_eol_is_crlf(auto_crlf, core_eol),
+        }
+    }
+}
+
+fn text_eol_is_crlf(auto_crlf: AutoCrlf, core_eol: Option<Eol>) -> bool {
+    match auto_crlf {
+        AutoCrlf::True => true,
+        AutoCrlf::Input => false,
+        AutoCrlf::False => core_eol.unwrap_or(Eol::NATIVE) == Eol::Crlf,
+    }
+}
+
+/// Convert CRLF line endings in `data` to LF according to `action`.
+///
+/// `index_has_crlf()` is called to learn if the version of the file in the index contains CRLF, which prevents automatic
+/// conversions as they would make the file appear modified.
+pub(crate) fn to_git<'a>(data: Cow<'a, [u8]>, action: Action, index_has_crlf: impl FnOnce() -> bool) -> Cow<'a, [u8]> {
+    if action == Action::Binary || data.is_empty() {
+        return data;
+    }
+    let stats = Stats::from_bytes(&data);
+    if stats.crlf == 0 {
+        return data;
+    }
+    if action.is_auto() {
+        if stats.is_binary() {
+            return data;
+        }
+        if index_has_crlf() {
+            return data;
+        }
+        // Files with lone CRs are binary, so all of them are part of a CRLF.
+        return data.iter().copied().filter(|b| *b != b'\r').collect::<Vec<_>>().into();
+    }
+    let mut out = Vec::with_capacity(data.len() - stats.crlf);
+    let mut bytes = data.iter().copied().peekable();
+    while let Some(b) = bytes.next() {
+        if b == b'\r' && bytes.peek() == Some(&b'\n') {
+            continue;
+        }
+        out.push(b);
+    }
+    out.into()
+}
+
+/// Convert LF line endings in `data` to CRLF according to `action`, `auto_crlf` and `core_eol`.
+pub(crate) fn to_worktree<'a>(
+    data: Cow<'a, [u8]>,
+    action: Action,
+    auto_crlf: AutoCrlf,
+    core_eol: Option<Eol>,
+) -> Cow<'a, [u8]> {
+    if !action.output_is_crlf(auto_crlf, core_eol) {
+        return data;
+    }
+    let stats = Stats::from_bytes(&data);
+    if stats.lone_lf == 0 {
+        return data;
+    }
+    if action.is_auto() && (stats.lone_cr > 0 || stats.crlf > 0 || stats.is_binary()) {
+        return data;
+    }
+    let mut out = Vec::with_capacity(data.len() + stats.lone_lf);
+    let mut last = None;
+    for b in data.iter().copied() {
+        if b == b'\n' && last != Some(b'\r') {
+            out.push(b'\r');
+        }
+        out.push(b);
+        last = Some(b);
+    }
+    out.into()
+}
+
+/// Return true if `data` is text containing a CRLF line ending.
+pub(crate) fn has_crlf(data: &[u8]) -> bool {
+    let stats = Stats::from_bytes(data);
+    stats.crlf > 0 && !stats.is_binary()
+}
@@ -0,0 +1,74 @@
+use std::borrow::Cow;
+
+use git_hash::oid;
+use git_object::bstr::ByteSlice;
+
+/// Replace all `$Id: <anything>$` keywords in `data` with `$Id$`.
+pub(crate) fn to_git(data: Cow<'_, [u8]>) -> Cow<'_, [u8]> {
+    if data.find(b"$Id").is_none() {
+        return data;
+    }
+    let mut out = Vec::with_capacity(data.len());
+    let mut rest = &data[..];
+    while let Some(dollar) = rest.find_byte(b'$') {
+        out.extend_from_slice(&rest[..=dollar]);
+        rest = &rest[dollar + 1..];
+        if rest.len() > 3 && rest.starts_with(b"Id:") {
+            let end = match rest[3..].find_byte(b'$') {
+                Some(pos) => pos + 3,
+                None => break,
+            };
+            if rest[3..end].contains(&b'\n') {
+                continue;
+            }
+            out.extend_from_slice(b"Id$");
+            rest = &rest[end + 1..];
+        }
+    }
+    out.extend_from_slice(rest);
+    out.into()
+}
+
+/// Replace all `$Id$` and `$Id: <anything>$` keywords in `data` with `$Id: <id> $`.
+///
+/// Keywords with spaces in unexpected places are left alone as they are probably from another version control system.
+pub(crate) fn to_worktree<'a>(data: Cow<'a, [u8]>, id: &oid) -> Cow<'a, [u8]> {
+    if data.find(b"$Id").is_none() {
+        return data;
+    }
+    let mut out = Vec::with_capacity(data.len() + 60);
+    let mut rest = &data[..];
+    while let Some(dollar) = rest.find_byte(b'$') {
+        out.extend_from_slice(&rest[..=dollar]);
+        rest = &rest[dollar + 1..];
+        if rest.len() < 3 || !rest.starts_with(b"Id") {
+            continue;
+        }
+        match rest[2] {
+            b':' => {
+                let end = match rest[3..].find_byte(b'$') {
+                    Some(pos) => pos + 3,
+                    None => break,
+                };
+                if rest[3..end].contains(&b'\n') {
+                    continue;
+                }
+                let has_unexpected_space = rest
+                    .get(4..end)
+                    .and_then(|value| value.find_byte(b' '))
+                    .map_or(false, |pos| pos + 4 < end - 1);
+                if has_unexpected_space {
+                    continue;
+                }
+                rest = &rest[end + 1..];
+            }
+            b'$' => rest = &rest[3..],
+            _ => continue,
+        }
+        out.extend_from_slice(b"Id: ");
+        out.extend_from_slice(&id.to_sha1_hex());
+        out.extend_from_slice(b" $");
+    }
+    out.extend_from_slice(rest);
+    out.into()
+}
@@ -0,0 +1,341 @@
+//! Convert file contents between their form in the object database and in the worktree, as configured by attributes
+//! like `text`, `eol`, `ident`, `working-tree-encoding` and `filter`.
+use std::{borrow::Cow, collections::HashMap, path::Path};
+
+use git_hash::{oid, ObjectId};
+use git_object::bstr::{BStr, BString, ByteSlice};
+
+use crate::attributes::{self, State};
+
+mod driver;
+mod encoding;
+mod eol;
+mod ident;
+
+/// The value of `core.autocrlf`.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub enum AutoCrlf {
+    /// Don't convert line endings of files without `text` attribute.
+    False,
+    /// Convert line endings of files detected to be text to LF in the object database, and to CRLF in the worktree.
+    True,
+    /// Convert line endings of files detected to be text to LF in the object database only.
+    Input,
+}
+
+impl Default for AutoCrlf {
+    fn default() -> Self {
+        AutoCrlf::False
+    }
+}
+
+/// A line ending, as used by `core.eol` or the `eol` attribute.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub enum Eol {
+    /// A line feed, `\n`.
+    Lf,
+    /// A carriage return followed by a line feed, `\r\n`.
+    Crlf,
+}
+
+impl Eol {
+    /// The line ending native to the platform we are compiled for.
+    pub const NATIVE: Eol = if cfg!(windows) { Eol::Crlf } else { Eol::Lf };
+}
+
+/// A filter driver as configured in the `filter.<name>` section, for use with files having the `filter=<name>` attribute.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct Driver {
+    /// The name of the driver.
+    pub name: BString,
+    /// The command to convert files of the worktree for storage in the object database, with `%f` being substituted
+    /// with the path of the file.
+    pub clean: Option<BString>,
+    /// The command to convert files of the object database for use in the worktree, with `%f` being substituted
+    /// with the path of the file.
+    pub smudge: Option<BString>,
+    /// The command to start a long-running process speaking the filter protocol, used instead of `clean` and `smudge`.
+    pub process: Option<BString>,
+    /// If true, failing to apply the filter is an error, otherwise the content remains unconverted.
+    pub required: bool,
+}
+
+/// Options for use in a [`Pipeline`], typically obtained from the configuration.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct Options {
+    /// The value of `core.autocrlf`.
+    pub auto_crlf: AutoCrlf,
+    /// The value of `core.eol`, or `None` to use the [native line ending][Eol::NATIVE].
+    pub eol: Option<Eol>,
+    /// All configured filter drivers.
+    pub drivers: Vec<Driver>,
+}
+
+mod error {
+    use std::path::PathBuf;
+
+    use git_object::bstr::BString;
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`Pipeline`][super::Pipeline] methods.
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io { source: std::io::Error, path: PathBuf } {
+                display("Could not read '{}'", path.display())
+                source(source)
+            }
+            UnsupportedEncoding { encoding: BString, path: BString } {
+                display("The working-tree-encoding '{}' of '{}' is not supported", encoding, path)
+            }
+            Encoding { encoding: BString, path: BString, message: &'static str } {
+                display("Could not convert '{}' between UTF-8 and '{}': {}", path, encoding, message)
+            }
+            Driver { name: BString, operation: &'static str, path: BString, source: std::io::Error } {
+                display("The required {} filter '{}' failed on '{}'", operation, name, path)
+                source(source)
+            }
+            MissingCommand { name: BString, operation: &'static str, path: BString } {
+                display("The required filter '{}' has no {} command to apply to '{}'", name, operation, path)
+            }
+        }
+    }
+}
+pub use error::Error;
+
+/// The conversions to apply to a single file as determined by its attributes.
+struct Conversion<'a> {
+    eol: eol::Action,
+    ident: bool,
+    encoding: Option<(encoding::Encoding, BString)>,
+    driver: Option<&'a Driver>,
+}
+
+/// Converts file contents when they are written into the worktree or read from it, according to their attributes.
+///
+/// Conversions are applied in the same order as `git` applies them. When reading files from the worktree these are
+/// the `filter` driver, the `working-tree-encoding`, line ending conversions and finally `ident` expansions, and
+/// when writing files into the worktree it's the other way around.
+///
+/// Long-running filter processes are started on first use and kept alive until the pipeline is dropped. Clones of
+/// the pipeline, for instance for use in other threads, start their own processes.
+pub struct Pipeline {
+    attributes: attributes::Stack,
+    options: Options,
+    processes: HashMap<BString, driver::Process>,
+}
+
+impl Clone for Pipeline {
+    fn clone(&self) -> Self {
+        Pipeline {
+            attributes: self.attributes.clone(),
+            options: self.options.clone(),
+            processes: HashMap::new(),
+        }
+    }
+}
+
+impl Pipeline {
+    /// Create a new pipeline which obtains the attributes of files from `attributes` and applies them using `options`.
+    pub fn new(attributes: attributes::Stack, options: Options) -> Self {
+        Pipeline {
+            attributes,
+            options,
+            processes: HashMap::new(),
+        }
+    }
+
+    /// Convert `data` of the file at `rela_path`, relative to the worktree, for storage in the object database.
+    ///
+    /// `index_has_crlf()` is called to learn if the text of the file in the index has CRLF line endings, which prevents
+    /// automatic line ending conversions as they would make the file appear modified.
+    pub fn convert_to_git<'a>(
+        &mut self,
+        data: &'a [u8],
+        rela_path: &BStr,
+        index_has_crlf: impl FnOnce() -> bool,
+    ) -> Result<Cow<'a, [u8]>, Error> {
+        let Pipeline {
+            attributes,
+            options,
+            processes,
+        } = self;
+        let conversion = conversion(attributes, options, rela_path)?;
+        let mut data = Cow::Borrowed(data);
+        if let Some(driver) = conversion.driver {
+            data = apply_driver(processes, driver, driver::Operation::Clean, rela_path, data)?;
+        }
+        if let Some((encoding, name)) = &conversion.encoding {
+            data = encoding.decode(data).map_err(|message| Error::Encoding {
+                encoding: name.clone(),
+                path: rela_path.into(),
+                message,
+            })?;
+        }
+        data = eol::to_git(data, conversion.eol, index_has_crlf);
+        if conversion.ident {
+            data = ident::to_git(data);
+        }
+        Ok(data)
+    }
+
+    /// Convert `data` of the blob with `id` for use as file at `rela_path` in the worktree.
+    pub fn convert_to_worktree<'a>(
+        &mut self,
+        data: &'a [u8],
+        rela_path: &BStr,
+        id: &oid,
+    ) -> Result<Cow<'a, [u8]>, Error> {
+        let Pipeline {
+            attributes,
+            options,
+            processes,
+        } = self;
+        let conversion = conversion(attributes, options, rela_path)?;
+        let mut data = Cow::Borrowed(data);
+        if conversion.ident {
+            data = ident::to_worktree(data, id);
+        }
+        data = eol::to_worktree(data, conversion.eol, options.auto_crlf, options.eol);
+        if let Some((encoding, name)) = &conversion.encoding {
+            data = encoding.encode(data).map_err(|message| Error::Encoding {
+                encoding: name.clone(),
+                path: rela_path.into(),
+                message,
+            })?;
+        }
+        if let Some(driver) = conversion.driver {
+            data = apply_driver(processes, driver, driver::Operation::Smudge, rela_path, data)?;
+        }
+        Ok(data)
+    }
+
+    /// Return the id of the file at `path` in the worktree once it is [converted][Pipeline::convert_to_git()], with `rela_path`
+    /// being its path relative to the worktree.
+    pub fn blob_id(
+        &mut self,
+        path: &Path,
+        rela_path: &BStr,
+        index_has_crlf: impl FnOnce() -> bool,
+    ) -> Result<ObjectId, Error> {
+        use git_odb::Write;
+        let io_err = |err| Error::Io {
+            source: err,
+            path: path.to_owned(),
+        };
+        let data = std::fs::read(path).map_err(io_err)?;
+        let data = self.convert_to_git(&data, rela_path, index_has_crlf)?;
+        git_odb::sink()
+            .write_buf(git_object::Kind::Blob, &data, git_hash::Kind::Sha1)
+            .map_err(io_err)
+    }
+}
+
+/// Return true if `data`, typically a blob in the index, is text with CRLF line endings.
+///
+/// This is useful to implement the `index_has_crlf()` callback of [`Pipeline::convert_to_git()`].
+pub fn has_crlf(data: &[u8]) -> bool {
+    eol::has_crlf(data)
+}
+
+fn conversion<'a>(
+    attributes: &mut attributes::Stack,
+    options: &'a Options,
+    rela_path: &BStr,
+) -> Result<Conversion<'a>, Error> {
+    let attrs = attributes.attributes(rela_path, false);
+    let text = |name| match attrs.state(name) {
+        State::Set => Some(eol::Action::Text),
+        State::Unset => Some(eol::Action::Binary),
+        State::Value(value) if value == "auto" => Some(eol::Action::Auto),
+        State::Value(value) if value == "input" => Some(eol::Action::TextInput),
+        _ => None,
+    };
+    let eol = match attrs.value("eol") {
+        Some(value) if value == "lf" => Some(Eol::Lf),
+        Some(value) if value == "crlf" => Some(Eol::Crlf),
+        _ => None,
+    };
+    let encoding = match attrs.value("working-tree-encoding") {
+        Some(name) => encoding::Encoding::from_name(name)
+            .map_err(|_| Error::UnsupportedEncoding {
+                encoding: name.into(),
+                path: rela_path.into(),
+            })?
+            .map(|encoding| (encoding, BString::from(name))),
+        None => None,
+    };
+    let driver = attrs
+        .value("filter")
+        .and_then(|name| options.drivers.iter().find(|driver| driver.name == name));
+    Ok(Conversion {
+        eol: eol::Action::new(
+            text("text").or_else(|| text("crlf")),
+            eol,
+            options.auto_crlf,
+            options.eol,
+        ),
+        ident: attrs.is_set("ident"),
+        encoding,
+        driver,
+    })
+}
+
+/// Apply `driver` for `operation` to `data` of the file at `rela_path`, using and maintaining long-running `processes`.
+///
+/// Failures are only errors if the driver is required, otherwise `data` is returned unchanged.
+fn apply_driver<'a>(
+    processes: &mut HashMap<BString, driver::Process>,
+    driver: &Driver,
+    operation: driver::Operation,
+    rela_path: &BStr,
+    data: Cow<'a, [u8]>,
+) -> Result<Cow<'a, [u8]>, Error> {
+    let result = match &driver.process {
+        Some(command) => {
+            let process = match processes.remove(&driver.name) {
+                Some(process) => Ok(process),
+                None => driver::Process::start(command.as_bstr()),
+            };
+            process.and_then(|mut process| {
+                // Processes which failed to communicate are dropped, and restarted on next use.
+                let outcome = process.apply(operation, rela_path, &data)?;
+                processes.insert(driver.name.clone(), process);
+                match outcome {
+                    driver::Outcome::Converted(out) => Ok(Some(out)),
+                    driver::Outcome::Unsupported => Ok(None),
+                    driver::Outcome::Failed(status) => Err(std::io::Error::new(
+                        std::io::ErrorKind::Other,
+                        format!("filter process failed with status '{}'", status),
+                    )),
+                }
+            })
+        }
+        None => {
+            let command = match operation {
+                driver::Operation::Clean => &driver.clean,
+                driver::Operation::Smudge => &driver.smudge,
+            };
+            match command {
+                Some(command) => driver::run(command.as_bstr(), rela_path, &data).map(Some),
+                None => Ok(None),
+            }
+        }
+    };
+    match result {
+        Ok(Some(out)) => Ok(out.into()),
+        Ok(None) if driver.required => Err(Error::MissingCommand {
+            name: driver.name.clone(),
+            operation: operation.as_str(),
+            path: rela_path.into(),
+        }),
+        Err(err) if driver.required => Err(Error::Driver {
+            name: driver.name.clone(),
+            operation: operation.as_str(),
+            path: rela_path.into(),
+            source: err,
+        }),
+        Ok(None) | Err(_) => Ok(data),
+    }
+}
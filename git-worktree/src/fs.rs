@@ -0,0 +1,118 @@
+//! Utilities to obtain information about files in the worktree in the way git needs it.
+use std::path::Path;
+
+use git_hash::ObjectId;
+use git_index::entry;
+use git_object::tree::EntryMode;
+
+/// Return the stat information of a file as stored in the index, obtained from its `metadata`.
+///
+/// On platforms other than unix, only the modification time and the size are available.
+pub fn stat(metadata: &std::fs::Metadata) -> entry::Stat {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        entry::Stat {
+            ctime: entry::Time {
+                secs: metadata.ctime() as u32,
+                nsecs: metadata.ctime_nsec() as u32,
+            },
+            mtime: entry::Time {
+                secs: metadata.mtime() as u32,
+                nsecs: metadata.mtime_nsec() as u32,
+            },
+            dev: metadata.dev() as u32,
+            ino: metadata.ino() as u32,
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+            size: metadata.size() as u32,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let mtime = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|duration| entry::Time {
+                secs: duration.as_secs() as u32,
+                nsecs: duration.subsec_nanos(),
+            })
+            .unwrap_or_default();
+        entry::Stat {
+            mtime,
+            size: metadata.len() as u32,
+            ..Default::default()
+        }
+    }
+}
+
+/// Return true if `lhs` and `rhs` are considered equal by git, which ignores the device the file resides on.
+pub fn stat_matches(lhs: &entry::Stat, rhs: &entry::Stat) -> bool {
+    lhs.mtime == rhs.mtime
+        && lhs.ctime == rhs.ctime
+        && lhs.ino == rhs.ino
+        && lhs.uid == rhs.uid
+        && lhs.gid == rhs.gid
+        && lhs.size == rhs.size
+}
+
+/// Return the mode of the file with `metadata` as it would be stored in the index, or `None` if it's neither a file,
+/// a symlink nor a directory.
+///
+/// Directories are submodules, and if `check_executable_bit` is false, files are never considered executable.
+pub fn mode(metadata: &std::fs::Metadata, check_executable_bit: bool) -> Option<EntryMode> {
+    let file_type = metadata.file_type();
+    Some(if file_type.is_symlink() {
+        EntryMode::Link
+    } else if file_type.is_dir() {
+        EntryMode::Commit
+    } else if file_type.is_file() {
+        if check_executable_bit && is_executable(metadata) {
+            EntryMode::BlobExecutable
+        } else {
+            EntryMode::Blob
+        }
+    } else {
+        return None;
+    })
+}
+
+#[cfg(unix)]
+fn is_executable(metadata: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    metadata.permissions().mode() & 0o100 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_metadata: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// Return the target of the symlink at `path` as bytes, which is the content of its blob.
+pub fn symlink_target(path: &Path) -> std::io::Result<Vec<u8>> {
+    let target = std::fs::read_link(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        Ok(target.into_os_string().into_vec())
+    }
+    #[cfg(not(unix))]
+    {
+        Ok(target.to_string_lossy().replace('\\', "/").into_bytes())
+    }
+}
+
+/// Compute the id of the blob for the file at `path` with the given `mode`, which is either a symlink or a file.
+pub fn blob_id(path: &Path, mode: EntryMode) -> std::io::Result<ObjectId> {
+    use git_odb::Write;
+    let sink = git_odb::sink();
+    if mode == EntryMode::Link {
+        let target = symlink_target(path)?;
+        sink.write_buf(git_object::Kind::Blob, &target, git_hash::Kind::Sha1)
+    } else {
+        let file = std::fs::File::open(path)?;
+        let len = file.metadata()?.len();
+        sink.write_stream(git_object::Kind::Blob, len, file, git_hash::Kind::Sha1)
+    }
+}
@@ -0,0 +1,287 @@
+//! Determine if paths in a worktree are ignored by `.gitignore` files, `.git/info/exclude` or `core.excludesFile`, similar to
+//! `git check-ignore`.
+use std::path::{Path, PathBuf};
+
+use git_glob::{pattern::Case, Pattern};
+use git_object::bstr::{BStr, BString, ByteSlice, ByteVec};
+
+/// A pattern along with its position in the file it was read from.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub struct Mapping {
+    /// The pattern itself.
+    pub pattern: Pattern,
+    /// The line number of the pattern in its file, starting at 1.
+    pub sequence_number: usize,
+}
+
+impl Mapping {
+    /// Return the pattern as it was written in its file, apart from trailing whitespace.
+    pub fn to_bstring(&self) -> BString {
+        let mut out = BString::default();
+        if self.pattern.is_negative() {
+            out.push(b'!');
+        }
+        if self.pattern.mode.contains(git_glob::pattern::Mode::ABSOLUTE) {
+            out.push(b'/');
+        }
+        out.push_str(&self.pattern.text);
+        if self.pattern.mode.contains(git_glob::pattern::Mode::MUST_BE_DIR) {
+            out.push(b'/');
+        }
+        out
+    }
+}
+
+/// All patterns read from a single file, along with the directory they apply to.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct List {
+    /// The patterns in the order they appear in the file.
+    pub patterns: Vec<Mapping>,
+    /// The file the patterns were read from, if any.
+    pub source: Option<PathBuf>,
+    /// The directory relative to the worktree the patterns apply to, with a trailing slash, or empty if they apply
+    /// to the entire worktree.
+    pub base: BString,
+}
+
+impl List {
+    /// Parse the contents of an exclude file in `bytes`, which were read from `source`, for patterns applying to paths in `base`,
+    /// a directory relative to the worktree with trailing slash.
+    ///
+    /// Empty lines and comments starting with `#` are skipped, and trailing spaces are removed unless they are escaped with
+    /// a backslash.
+    pub fn from_bytes(bytes: &[u8], source: Option<PathBuf>, base: BString) -> Self {
+        let bytes = bytes.strip_prefix(b"\xef\xbb\xbf").unwrap_or(bytes);
+        let patterns = bytes
+            .lines_with_terminator()
+            .enumerate()
+            .filter_map(|(idx, line)| {
+                let line = line.strip_suffix(b"\n").unwrap_or(line);
+                if line.first() == Some(&b'#') {
+                    return None;
+                }
+                Pattern::from_bytes(trim_trailing_spaces(line)).map(|pattern| Mapping {
+                    pattern,
+                    sequence_number: idx + 1,
+                })
+            })
+            .collect();
+        List { patterns, source, base }
+    }
+
+    /// Read the patterns from the file at `path` for use with paths in `base`, or return `None` if it doesn't exist.
+    pub fn from_file(path: impl Into<PathBuf>, base: BString) -> std::io::Result<Option<Self>> {
+        let path = path.into();
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(Some(List::from_bytes(&bytes, Some(path), base))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Return the index of the last pattern matching `path`, which is relative to the worktree and within our `base` directory.
+    pub(crate) fn pattern_matching_relative_path(&self, path: &BStr, is_dir: bool, case: Case) -> Option<usize> {
+        let path = &path[self.base.len()..];
+        let basename_start_pos = path.rfind_byte(b'/').map(|pos| pos + 1);
+        self.patterns.iter().rposition(|m| {
+            m.pattern
+                .matches_repo_relative_path(path.as_bstr(), basename_start_pos, is_dir, case)
+        })
+    }
+}
+
+/// Remove trailing spaces from `line` unless they are escaped.
+fn trim_trailing_spaces(line: &[u8]) -> &[u8] {
+    let mut last_space = None;
+    let mut pos = 0;
+    while pos < line.len() {
+        match line[pos] {
+            b' ' => {
+                last_space.get_or_insert(pos);
+            }
+            b'\\' => {
+                pos += 1;
+                last_space = None;
+            }
+            _ => last_space = None,
+        }
+        pos += 1;
+    }
+    &line[..last_space.unwrap_or(line.len())]
+}
+
+/// The pattern which decided whether a path is ignored, as returned by [`Stack::pattern_matching()`].
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub struct Match<'a> {
+    /// The matching pattern, which excludes the path unless it is [negative][Pattern::is_negative()].
+    pub pattern: &'a Pattern,
+    /// The file the pattern was read from, if any.
+    pub source: Option<&'a Path>,
+    /// The line number of the pattern in its file, starting at 1.
+    pub sequence_number: usize,
+}
+
+impl<'a> Match<'a> {
+    fn new(list: &'a List, mapping: &'a Mapping) -> Self {
+        Match {
+            pattern: &mapping.pattern,
+            source: list.source.as_deref(),
+            sequence_number: mapping.sequence_number,
+        }
+    }
+}
+
+/// Where to find a list of patterns in a [`Stack`].
+#[derive(Clone, Copy)]
+enum Location {
+    Directory(usize),
+    Global(usize),
+}
+
+struct Directory {
+    /// The path of the directory relative to the worktree, with trailing slash unless it's the root.
+    path: BString,
+    /// The patterns of the `.gitignore` file in this directory.
+    list: Option<List>,
+    /// The pattern by which this directory or one of its parents is excluded, if any.
+    excluded_by: Option<(Location, usize)>,
+}
+
+/// A stack of pattern lists of the directories leading to a path, which is adjusted as paths in different directories
+/// are queried, making it efficient to use while traversing the worktree.
+///
+/// Patterns of `.gitignore` files in deeper directories take precedence over the ones in their parent directories, which in turn
+/// take precedence over [global lists][Stack::new()]. Within a list, the last matching pattern wins.
+/// If a directory is excluded, everything within it is excluded as well and `.gitignore` files within it aren't read.
+pub struct Stack {
+    worktree: PathBuf,
+    case: Case,
+    globals: Vec<List>,
+    dirs: Vec<Directory>,
+}
+
+impl Stack {
+    /// Create a new stack for the `worktree`, using `globals` for paths in all directories, with lists at the end taking precedence.
+    ///
+    /// Typically these are the lists read from `core.excludesFile` followed by `.git/info/exclude`.
+    /// `case` determines if patterns match regardless of case, like `core.ignoreCase` does.
+    pub fn new(worktree: impl Into<PathBuf>, globals: Vec<List>, case: Case) -> Self {
+        let worktree = worktree.into();
+        let root = Directory {
+            list: read_gitignore(&worktree, BString::default()),
+            path: BString::default(),
+            excluded_by: None,
+        };
+        Stack {
+            worktree,
+            case,
+            globals,
+            dirs: vec![root],
+        }
+    }
+
+    /// Create a new stack for the `worktree` of the repository at `git_dir`, reading the global lists from `excludes_file`,
+    /// the value of `core.excludesFile`, and `.git/info/exclude`.
+    pub fn from_git_dir(
+        worktree: impl Into<PathBuf>,
+        git_dir: &Path,
+        excludes_file: Option<&Path>,
+        case: Case,
+    ) -> std::io::Result<Self> {
+        let mut globals = Vec::new();
+        if let Some(excludes_file) = excludes_file {
+            globals.extend(List::from_file(excludes_file, BString::default())?);
+        }
+        globals.extend(List::from_file(
+            git_dir.join("info").join("exclude"),
+            BString::default(),
+        )?);
+        Ok(Stack::new(worktree, globals, case))
+    }
+
+    /// Return true if `path`, relative to the worktree, is excluded, with `is_dir` indicating if it is a directory.
+    pub fn is_ignored(&mut self, path: &BStr, is_dir: bool) -> bool {
+        self.pattern_matching(path, is_dir)
+            .map_or(false, |m| !m.pattern.is_negative())
+    }
+
+    /// Return the pattern which decides whether `path`, relative to the worktree, is excluded, with `is_dir` indicating if it is
+    /// a directory, or `None` if no pattern matches it.
+    ///
+    /// The path is excluded unless the pattern is negative.
+    pub fn pattern_matching(&mut self, path: &BStr, is_dir: bool) -> Option<Match<'_>> {
+        let dir_len = path.rfind_byte(b'/').map_or(0, |pos| pos + 1);
+        self.set_directory(&path[..dir_len]);
+        let location = match self.dirs.last().expect("root is always present").excluded_by {
+            Some(location) => Some(location),
+            None => self.find(path, is_dir),
+        };
+        let this = &*self;
+        location.map(move |location| this.match_at(location))
+    }
+
+    fn match_at(&self, (location, idx): (Location, usize)) -> Match<'_> {
+        let list = match location {
+            Location::Directory(dir) => self.dirs[dir].list.as_ref().expect("matches have a list"),
+            Location::Global(global) => &self.globals[global],
+        };
+        Match::new(list, &list.patterns[idx])
+    }
+
+    /// Find the pattern deciding about `path` in all lists currently on the stack.
+    fn find(&self, path: &BStr, is_dir: bool) -> Option<(Location, usize)> {
+        for (idx, dir) in self.dirs.iter().enumerate().rev() {
+            if let Some(list) = &dir.list {
+                if let Some(pos) = list.pattern_matching_relative_path(path, is_dir, self.case) {
+                    return Some((Location::Directory(idx), pos));
+                }
+            }
+        }
+        for (idx, list) in self.globals.iter().enumerate().rev() {
+            if let Some(pos) = list.pattern_matching_relative_path(path, is_dir, self.case) {
+                return Some((Location::Global(idx), pos));
+            }
+        }
+        None
+    }
+
+    /// Adjust the stack so that it contains all directories leading to `dir`, which is empty or has a trailing slash.
+    fn set_directory(&mut self, dir: &[u8]) {
+        while self.dirs.len() > 1 && !dir.starts_with(&self.dirs.last().expect("non-empty").path) {
+            self.dirs.pop();
+        }
+        while self.dirs.last().expect("root is always present").path.len() < dir.len() {
+            let parent = self.dirs.last().expect("root is always present");
+            let end = dir[parent.path.len()..]
+                .find_byte(b'/')
+                .map(|pos| parent.path.len() + pos)
+                .expect("directories end with a slash");
+            let path: BString = dir[..=end].into();
+            let excluded_by = parent.excluded_by.or_else(|| {
+                self.find(path[..end].as_bstr(), true)
+                    .filter(|location| !self.match_at(*location).pattern.is_negative())
+            });
+            let list = match excluded_by {
+                Some(_) => None,
+                None => read_gitignore(&self.worktree, path.clone()),
+            };
+            self.dirs.push(Directory {
+                path,
+                list,
+                excluded_by,
+            });
+        }
+    }
+}
+
+/// Read the `.gitignore` file in the directory `base` relative to the `worktree`, ignoring it if it can't be read or isn't
+/// a regular file.
+fn read_gitignore(worktree: &Path, base: BString) -> Option<List> {
+    let mut path = worktree.join(base.to_path_lossy());
+    path.push(".gitignore");
+    let is_file = std::fs::symlink_metadata(&path).map_or(false, |m| m.is_file());
+    if !is_file {
+        return None;
+    }
+    List::from_file(path, base).ok().flatten()
+}
@@ -0,0 +1,31 @@
+//! A crate for working with the files of a worktree, comparing them to the index and to trees.
+//!
+//! The [`status`][status::status()] of a worktree compares the tree of `HEAD` with the index and the index with the files on disk,
+//! similar to `git status`, and a [`checkout`][checkout::checkout()] writes the entries of an index into a worktree.
+//! An [ignore stack][ignore::Stack] determines which of the files in a worktree are excluded, and an
+//! [attribute stack][attributes::Stack] determines their attributes, which drive the [filter pipeline][filter::Pipeline]
+//! converting their content.
+//! [Sparse checkouts][sparse::apply()] restrict the files in a worktree to the entries matching a set of patterns.
+#![forbid(unsafe_code)]
+#![deny(missing_docs, rust_2018_idioms)]
+
+///
+pub mod attributes;
+
+///
+pub mod checkout;
+
+///
+pub mod filter;
+
+///
+pub mod fs;
+
+///
+pub mod ignore;
+
+///
+pub mod sparse;
+
+///
+pub mod status;
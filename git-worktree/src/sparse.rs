@@ -0,0 +1,372 @@
+//! Restrict the files in a worktree to the entries of the index matching the patterns of `.git/info/sparse-checkout`,
+//! similar to `git sparse-checkout`.
+use std::{
+    collections::BTreeSet,
+    path::{Path, PathBuf},
+};
+
+use git_glob::pattern::{Case, Mode};
+use git_index::{entry, State};
+use git_object::{
+    bstr::{BStr, BString, ByteSlice, ByteVec},
+    tree::EntryMode,
+};
+use git_odb::linked;
+
+use crate::{checkout, filter, ignore, status};
+
+/// The patterns deciding which entries of the index are included in the worktree.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub enum Patterns {
+    /// Patterns in the format of `.gitignore` files, with entries being included if the last pattern matching them or one of
+    /// their leading directories is positive.
+    Full(ignore::List),
+    /// Patterns restricted to directories, which are matched much faster.
+    Cone(Cone),
+}
+
+impl Patterns {
+    /// Parse the contents of a sparse-checkout file in `bytes`, interpreting them as [cone patterns][Cone] if `cone` is true,
+    /// like `core.sparseCheckoutCone` does.
+    ///
+    /// Like `git`, the patterns are used in full if they aren't valid cone patterns.
+    pub fn from_bytes(bytes: &[u8], cone: bool) -> Self {
+        let list = ignore::List::from_bytes(bytes, None, BString::default());
+        match cone.then(|| Cone::from_list(&list)).flatten() {
+            Some(cone) => Patterns::Cone(cone),
+            None => Patterns::Full(list),
+        }
+    }
+
+    /// Read the patterns from the file at `path`, or return `None` if it doesn't exist.
+    pub fn from_file(path: impl Into<PathBuf>, cone: bool) -> std::io::Result<Option<Self>> {
+        let path = path.into();
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(Some(match Patterns::from_bytes(&bytes, cone) {
+                Patterns::Full(list) => Patterns::Full(ignore::List {
+                    source: Some(path),
+                    ..list
+                }),
+                cone => cone,
+            })),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Read the patterns from `.git/info/sparse-checkout` of the repository at `git_dir`, or return `None` if there is no such file.
+    pub fn from_git_dir(git_dir: &Path, cone: bool) -> std::io::Result<Option<Self>> {
+        Patterns::from_file(git_dir.join("info").join("sparse-checkout"), cone)
+    }
+
+    /// Return true if the file at `path`, relative to the worktree, is included in the worktree.
+    pub fn is_included(&self, path: &BStr, case: Case) -> bool {
+        match self {
+            Patterns::Full(list) => {
+                let leading_dirs = path
+                    .rfind_iter("/")
+                    .map(|pos| (path[..pos].as_bstr(), true))
+                    .collect::<Vec<_>>();
+                std::iter::once((path, false))
+                    .chain(leading_dirs)
+                    .find_map(|(path, is_dir)| {
+                        list.pattern_matching_relative_path(path, is_dir, case)
+                            .map(|idx| !list.patterns[idx].pattern.is_negative())
+                    })
+                    .unwrap_or(false)
+            }
+            Patterns::Cone(cone) => cone.is_included(path, case),
+        }
+    }
+}
+
+/// Patterns of a sparse-checkout file in cone mode, which include all files in the root of the worktree along with
+/// all files in and below [recursive directories][Cone::recursive], and the files directly within [parent directories][Cone::parents].
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct Cone {
+    /// The directories which are included along with everything in them, without leading or trailing slash.
+    pub recursive: BTreeSet<BString>,
+    /// The directories whose files are included, but not the directories within them, without leading or trailing slash.
+    pub parents: BTreeSet<BString>,
+}
+
+impl Cone {
+    /// Create patterns including `dirs` recursively, similar to `git sparse-checkout set --cone <dirs>…`.
+    pub fn from_dirs(dirs: impl IntoIterator<Item = impl Into<BString>>) -> Self {
+        let mut cone = Cone::default();
+        for dir in dirs {
+            let dir = dir.into();
+            let dir = dir.trim_start_with(|c| c == '/').trim_end_with(|c| c == '/');
+            if !dir.is_empty() {
+                cone.insert_recursive(dir.into());
+            }
+        }
+        cone
+    }
+
+    /// Return the patterns as they are written into a sparse-checkout file, in the same order as `git` writes them.
+    pub fn to_bstring(&self) -> BString {
+        let mut out = BString::from("/*\n!/*/\n");
+        for dir in self
+            .parents
+            .iter()
+            .filter(|dir| !self.recursive.contains(*dir) && !self.is_below_recursive(dir.as_bstr()))
+        {
+            out.push_str("/");
+            out.push_str(escape(dir));
+            out.push_str("/\n!/");
+            out.push_str(escape(dir));
+            out.push_str("/*/\n");
+        }
+        for dir in self
+            .recursive
+            .iter()
+            .filter(|dir| !self.is_below_recursive(dir.as_bstr()))
+        {
+            out.push_str("/");
+            out.push_str(escape(dir));
+            out.push_str("/\n");
+        }
+        out
+    }
+
+    /// Return true if the file at `path`, relative to the worktree, is included.
+    pub fn is_included(&self, path: &BStr, case: Case) -> bool {
+        let parent = match path.rfind_byte(b'/') {
+            Some(pos) => path[..pos].as_bstr(),
+            None => return true,
+        };
+        contains(&self.parents, parent, case)
+            || std::iter::once(parent.len())
+                .chain(parent.find_iter("/"))
+                .any(|pos| contains(&self.recursive, parent[..pos].as_bstr(), case))
+    }
+
+    /// Interpret the patterns of `list` as cone patterns, or return `None` if they aren't.
+    fn from_list(list: &ignore::List) -> Option<Self> {
+        let mut cone = Cone::default();
+        for mapping in &list.patterns {
+            let pattern = &mapping.pattern;
+            if pattern.text == "*" && pattern.mode.contains(Mode::ABSOLUTE) {
+                continue;
+            }
+            if !pattern.mode.contains(Mode::ABSOLUTE | Mode::MUST_BE_DIR) {
+                return None;
+            }
+            let text = pattern.text.as_slice();
+            match text.strip_suffix(b"/*") {
+                Some(dir) if pattern.is_negative() => {
+                    let dir = unescape(dir)?;
+                    cone.recursive.remove(&dir);
+                    cone.parents.insert(dir);
+                }
+                _ if pattern.is_negative() => return None,
+                _ => cone.insert_recursive(unescape(text)?),
+            }
+        }
+        Some(cone)
+    }
+
+    fn insert_recursive(&mut self, dir: BString) {
+        self.parents
+            .extend(dir.find_iter("/").map(|pos| BString::from(&dir[..pos])));
+        self.recursive.insert(dir);
+    }
+
+    fn is_below_recursive(&self, dir: &BStr) -> bool {
+        dir.find_iter("/")
+            .any(|pos| self.recursive.contains(dir[..pos].as_bstr()))
+    }
+}
+
+fn contains(dirs: &BTreeSet<BString>, dir: &BStr, case: Case) -> bool {
+    match case {
+        Case::Sensitive => dirs.contains(dir),
+        Case::Fold => dirs.iter().any(|candidate| candidate.eq_ignore_ascii_case(dir)),
+    }
+}
+
+fn is_glob_special(b: u8) -> bool {
+    matches!(b, b'*' | b'?' | b'[' | b'\\')
+}
+
+/// Remove the backslashes escaping special characters in the literal `text`, or return `None` if it contains
+/// unescaped special characters.
+fn unescape(text: &[u8]) -> Option<BString> {
+    let mut out = BString::default();
+    let mut bytes = text.iter().copied();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'\\' => out.push(bytes.next()?),
+            b if is_glob_special(b) => return None,
+            b => out.push(b),
+        }
+    }
+    Some(out)
+}
+
+fn escape(dir: &[u8]) -> BString {
+    let mut out = BString::default();
+    for b in dir {
+        if is_glob_special(*b) {
+            out.push(b'\\');
+        }
+        out.push(*b);
+    }
+    out
+}
+
+/// The outcome of [`apply()`], with all paths sorted.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct Outcome {
+    /// The entries which are now skipped in the worktree, and whose files were removed.
+    pub excluded: Vec<BString>,
+    /// The entries which are now included in the worktree, and which were checked out.
+    pub included: Vec<BString>,
+    /// The entries which should be excluded, but whose files were modified, so they are kept in the worktree.
+    pub kept: Vec<BString>,
+    /// The outcome of checking out the included entries.
+    pub checkout: checkout::Outcome,
+}
+
+mod error {
+    use std::path::PathBuf;
+
+    use quick_error::quick_error;
+
+    quick_error! {
+        /// The error returned by [`apply()`][super::apply()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io { source: std::io::Error, path: PathBuf } {
+                display("Could not remove '{}' from the worktree", path.display())
+                source(source)
+            }
+            Status(err: crate::status::Error) {
+                display("Could not determine if a file was modified")
+                from()
+                source(err)
+            }
+            Checkout(err: crate::checkout::Error) {
+                display("Could not check out the included entries")
+                from()
+                source(err)
+            }
+        }
+    }
+}
+pub use error::Error;
+
+/// Mark the entries of the `index` which aren't included by `patterns` as skipped in the `worktree` and remove their files,
+/// and check out the previously skipped entries which are now included, similar to `git sparse-checkout reapply`.
+///
+/// Files of excluded entries which were modified are kept along with their entry, and conflicted entries as well as entries
+/// added with the intent to add them are always kept.
+/// Included entries are checked out with [`checkout()`][checkout::checkout()] using `odb`, `filters` and `options`, which are
+/// also used to determine if files were modified. If the checkout fails, the included entries remain skipped.
+pub fn apply(
+    index: &mut State,
+    worktree: &Path,
+    patterns: &Patterns,
+    odb: &linked::Store,
+    filters: Option<&filter::Pipeline>,
+    options: checkout::Options,
+) -> Result<Outcome, Error> {
+    let case = if options.ignore_case {
+        Case::Fold
+    } else {
+        Case::Sensitive
+    };
+    let mut out = Outcome::default();
+    let mut pipeline = filters.cloned();
+    let mut excluded = Vec::new();
+    let mut included = Vec::new();
+    for (idx, entry) in index.entries().iter().enumerate() {
+        if entry.stage() != 0 || entry.mode.is_tree() || entry.flags.contains(entry::Flags::INTENT_TO_ADD) {
+            continue;
+        }
+        match (
+            patterns.is_included(entry.path.as_bstr(), case),
+            entry.flags.contains(entry::Flags::SKIP_WORKTREE),
+        ) {
+            (true, true) => included.push(idx),
+            (false, false) => {
+                let filters = pipeline.as_mut().map(|pipeline| (pipeline, odb));
+                if remove_unmodified(entry, worktree, options, filters)? {
+                    excluded.push(idx);
+                } else {
+                    out.kept.push(entry.path.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let entries = index.entries_mut();
+    for idx in &excluded {
+        entries[*idx].flags.insert(entry::Flags::SKIP_WORKTREE);
+        out.excluded.push(entries[*idx].path.clone());
+    }
+    remove_empty_leading_dirs(worktree, &out.excluded);
+
+    // Only check out the included entries by temporarily skipping all others.
+    let flags: Vec<_> = entries.iter().map(|entry| entry.flags).collect();
+    let mut is_included = vec![false; entries.len()];
+    for idx in &included {
+        is_included[*idx] = true;
+    }
+    for (entry, is_included) in entries.iter_mut().zip(is_included) {
+        entry.flags.set(entry::Flags::SKIP_WORKTREE, !is_included);
+    }
+    let checkout = checkout::checkout(index, worktree, odb, filters, options);
+    let entries = index.entries_mut();
+    for (entry, flags) in entries.iter_mut().zip(flags) {
+        entry.flags = flags;
+    }
+    out.checkout = checkout?;
+    for idx in included {
+        entries[idx].flags.remove(entry::Flags::SKIP_WORKTREE);
+        out.included.push(entries[idx].path.clone());
+    }
+    Ok(out)
+}
+
+/// Remove the file of `entry` from the `worktree` unless it was modified, and return true if it doesn't exist anymore.
+fn remove_unmodified(
+    entry: &git_index::Entry,
+    worktree: &Path,
+    options: checkout::Options,
+    filters: Option<(&mut filter::Pipeline, &linked::Store)>,
+) -> Result<bool, Error> {
+    match status::index_worktree::change(entry, worktree, None, options.executable_bit, filters)? {
+        Some(status::index_worktree::Change::Deleted { .. }) => Ok(true),
+        Some(_) => Ok(false),
+        None => {
+            let path = worktree.join(entry.path.to_path_lossy());
+            let res = if entry.mode == EntryMode::Commit {
+                match std::fs::read_dir(&path).map(|mut entries| entries.next().is_none()) {
+                    Ok(true) => std::fs::remove_dir(&path),
+                    Ok(false) => return Ok(false),
+                    Err(err) => Err(err),
+                }
+            } else {
+                std::fs::remove_file(&path)
+            };
+            res.map_err(|err| Error::Io { source: err, path })?;
+            Ok(true)
+        }
+    }
+}
+
+/// Remove the directories leading to the removed files at `paths` if they are empty now.
+fn remove_empty_leading_dirs(worktree: &Path, paths: &[BString]) {
+    let dirs: BTreeSet<_> = paths
+        .iter()
+        .flat_map(|path| path.find_iter("/").map(move |pos| path[..pos].as_bstr()))
+        .collect();
+    for dir in dirs.into_iter().rev() {
+        // Directories which aren't empty fail to be removed, which is expected.
+        std::fs::remove_dir(worktree.join(dir.to_path_lossy())).ok();
+    }
+}
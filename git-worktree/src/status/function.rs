@@ -0,0 +1,141 @@
+use std::{collections::BTreeMap, path::Path};
+
+use git_index::State;
+use git_object::bstr::{BStr, BString, ByteSlice};
+
+use crate::status::{index_worktree, tree_index, untracked, Change, Entry, Error, Filters, Item, Options};
+
+/// Compute the status of the `worktree` by comparing `head` with the `index` and the `index` with the files in the `worktree`,
+/// similar to `git status --porcelain=v2`.
+///
+/// `head` is typically [created from the tree][State::from_tree()] of the `HEAD` commit, or empty if there is no commit yet.
+/// `filters` are applied to files of the worktree before hashing them, see [`index_worktree::changes()`].
+/// `is_ignored(path, is_dir)` is called for untracked files and directories relative to the `worktree` to determine if they are
+/// ignored.
+///
+/// The returned entries are ordered like `git status` does, with changed entries first, followed by conflicts, untracked and
+/// ignored files, each sorted by path.
+pub fn status(
+    head: &State,
+    index: &git_index::File,
+    worktree: &Path,
+    filters: Option<Filters<'_>>,
+    options: Options,
+    is_ignored: impl FnMut(&BStr, bool) -> bool,
+) -> Result<Vec<Entry>, Error> {
+    let mut changed = BTreeMap::<BString, Entry>::new();
+    let mut conflicted = BTreeMap::<BString, Entry>::new();
+
+    for change in tree_index::changes(head, index, options.track_renames) {
+        let (path, source, staged, head, index) = match change {
+            tree_index::Change::Added { path, index } => (path, None, Change::Added, None, Some(index)),
+            tree_index::Change::Deleted { path, head } => (path, None, Change::Deleted, Some(head), None),
+            tree_index::Change::Modified { path, head, index } => {
+                let staged = if kind(head) == kind(index) {
+                    Change::Modified
+                } else {
+                    Change::TypeChanged
+                };
+                (path, None, staged, Some(head), Some(index))
+            }
+            tree_index::Change::Renamed {
+                source,
+                path,
+                head,
+                index,
+            } => (path, Some(source), Change::Renamed, Some(head), Some(index)),
+            tree_index::Change::Conflicted { path, stages } => {
+                conflicted.insert(
+                    path.clone(),
+                    Entry::Conflicted {
+                        path,
+                        stages,
+                        worktree_mode: None,
+                    },
+                );
+                continue;
+            }
+        };
+        let worktree_mode = index.map(|index| index.mode);
+        changed.insert(
+            path.clone(),
+            Entry::Changed {
+                path,
+                source,
+                staged,
+                unstaged: Change::Unmodified,
+                head,
+                index,
+                worktree_mode,
+            },
+        );
+    }
+
+    for change in index_worktree::changes(index, worktree, filters, &options)? {
+        let (path, unstaged, worktree_mode) = match change {
+            index_worktree::Change::Conflicted { path, worktree_mode } => {
+                if let Some(Entry::Conflicted {
+                    worktree_mode: mode, ..
+                }) = conflicted.get_mut(&path)
+                {
+                    *mode = worktree_mode;
+                }
+                continue;
+            }
+            index_worktree::Change::IntentToAdd { path, worktree_mode } => {
+                changed.insert(
+                    path.clone(),
+                    Entry::Changed {
+                        head: head.entry_by_path_and_stage(path.as_bstr(), 0).map(Into::into),
+                        path,
+                        source: None,
+                        staged: Change::Unmodified,
+                        unstaged: Change::Added,
+                        index: None,
+                        worktree_mode,
+                    },
+                );
+                continue;
+            }
+            index_worktree::Change::Modified { path, worktree_mode } => (path, Change::Modified, Some(worktree_mode)),
+            index_worktree::Change::TypeChanged { path, worktree_mode } => {
+                (path, Change::TypeChanged, Some(worktree_mode))
+            }
+            index_worktree::Change::Deleted { path } => (path, Change::Deleted, None),
+        };
+        let entry = changed.entry(path.clone()).or_insert_with(|| Entry::Changed {
+            head: head.entry_by_path_and_stage(path.as_bstr(), 0).map(Into::into),
+            index: index.entry_by_path_and_stage(path.as_bstr(), 0).map(Into::into),
+            path,
+            source: None,
+            staged: Change::Unmodified,
+            unstaged: Change::Unmodified,
+            worktree_mode: None,
+        });
+        if let Entry::Changed {
+            unstaged: entry_unstaged,
+            worktree_mode: entry_worktree_mode,
+            ..
+        } = entry
+        {
+            *entry_unstaged = unstaged;
+            *entry_worktree_mode = worktree_mode;
+        }
+    }
+
+    let untracked = untracked::collect(index, worktree, options.untracked, is_ignored)?;
+    let mut out: Vec<_> = changed.into_iter().chain(conflicted).map(|(_, entry)| entry).collect();
+    out.extend(untracked.untracked.into_iter().map(|path| Entry::Untracked { path }));
+    if options.ignored {
+        out.extend(untracked.ignored.into_iter().map(|path| Entry::Ignored { path }));
+    }
+    Ok(out)
+}
+
+/// Return the mode of `item` with executable files being treated like any other file.
+fn kind(item: Item) -> git_object::tree::EntryMode {
+    match item.mode {
+        git_object::tree::EntryMode::BlobExecutable => git_object::tree::EntryMode::Blob,
+        mode => mode,
+    }
+}
@@ -0,0 +1,225 @@
+use std::path::Path;
+
+use git_features::parallel::{self, in_parallel, Reduce};
+use git_index::entry;
+use git_object::{
+    bstr::{BString, ByteSlice},
+    tree::EntryMode,
+};
+use git_odb::{pack, Find};
+
+use crate::{
+    filter, fs,
+    status::{Error, Filters, Options},
+};
+
+/// A change of a file in the worktree compared to its entry in the index.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub enum Change {
+    /// The content or the executable bit of the file changed.
+    Modified {
+        /// The path of the entry.
+        path: BString,
+        /// The mode of the file in the worktree.
+        worktree_mode: EntryMode,
+    },
+    /// The kind of the file changed, for instance from a file to a symlink.
+    TypeChanged {
+        /// The path of the entry.
+        path: BString,
+        /// The mode of the file in the worktree.
+        worktree_mode: EntryMode,
+    },
+    /// The file was deleted.
+    Deleted {
+        /// The path of the entry.
+        path: BString,
+    },
+    /// The entry was added with the intent to add it, so the file is about to be added.
+    IntentToAdd {
+        /// The path of the entry.
+        path: BString,
+        /// The mode of the file in the worktree, or `None` if it doesn't exist.
+        worktree_mode: Option<EntryMode>,
+    },
+    /// The entry has a merge conflict.
+    Conflicted {
+        /// The path of the entry.
+        path: BString,
+        /// The mode of the file in the worktree, or `None` if it doesn't exist.
+        worktree_mode: Option<EntryMode>,
+    },
+}
+
+impl Change {
+    /// Return the path of the entry.
+    pub fn path(&self) -> &BString {
+        match self {
+            Change::Modified { path, .. }
+            | Change::TypeChanged { path, .. }
+            | Change::Deleted { path }
+            | Change::IntentToAdd { path, .. }
+            | Change::Conflicted { path, .. } => path,
+        }
+    }
+}
+
+/// Compare all entries of the `index` with the files in the `worktree` directory and return the changes sorted by path.
+///
+/// Files whose stat information matches the one of their entry are considered unchanged unless their modification time
+/// isn't older than the index itself, in which case they could have changed within the time resolution of the file system.
+/// Files are only hashed if their stat information doesn't suffice to tell whether they changed.
+/// Entries which are skipped in the worktree are ignored, and submodules are considered unchanged as long as their directory exists.
+///
+/// If `filters` are given, files are converted with them before being hashed, which is required if they were converted when
+/// checking them out.
+/// The files are checked in parallel using up to `options.thread_limit` threads.
+pub fn changes(
+    index: &git_index::File,
+    worktree: &Path,
+    filters: Option<Filters<'_>>,
+    options: &Options,
+) -> Result<Vec<Change>, Error> {
+    let index_mtime = std::fs::metadata(index.path()).ok().map(|m| fs::stat(&m).mtime);
+    let entries = index.entries();
+    let (chunk_size, thread_limit, _) =
+        parallel::optimize_chunk_size_and_thread_limit(100, Some(entries.len()), options.thread_limit, None);
+    let num_chunks = (entries.len() + chunk_size - 1) / chunk_size;
+    let check_executable_bit = options.check_executable_bit;
+
+    let mut out = in_parallel(
+        (0..num_chunks).map(|chunk| chunk * chunk_size..((chunk + 1) * chunk_size).min(entries.len())),
+        thread_limit,
+        |_| filters.map(|filters| (filters.pipeline.clone(), filters.odb)),
+        |range, filters| {
+            let mut changes = Vec::new();
+            for idx in range {
+                let entry = &entries[idx];
+                if entry.stage() != 0 && idx > 0 && entries[idx - 1].path == entry.path {
+                    continue;
+                }
+                let filters = filters.as_mut().map(|(pipeline, odb)| (pipeline, *odb));
+                if let Some(change) = change(entry, worktree, index_mtime, check_executable_bit, filters)? {
+                    changes.push(change);
+                }
+            }
+            Ok(changes)
+        },
+        Changes::default(),
+    )?;
+    out.sort_by(|a, b| a.path().cmp(b.path()));
+    Ok(out)
+}
+
+#[derive(Default)]
+struct Changes(Vec<Change>);
+
+impl Reduce for Changes {
+    type Input = Result<Vec<Change>, Error>;
+    type FeedProduce = ();
+    type Output = Vec<Change>;
+    type Error = Error;
+
+    fn feed(&mut self, item: Self::Input) -> Result<Self::FeedProduce, Self::Error> {
+        self.0.extend(item?);
+        Ok(())
+    }
+
+    fn finalize(self) -> Result<Self::Output, Self::Error> {
+        Ok(self.0)
+    }
+}
+
+pub(crate) fn change(
+    entry: &git_index::Entry,
+    worktree: &Path,
+    index_mtime: Option<entry::Time>,
+    check_executable_bit: bool,
+    filters: Option<(&mut filter::Pipeline, &git_odb::linked::Store)>,
+) -> Result<Option<Change>, Error> {
+    if entry.flags.contains(entry::Flags::SKIP_WORKTREE) || entry.mode.is_tree() {
+        return Ok(None);
+    }
+    let path = worktree.join(entry.path.to_path_lossy());
+    let io_err = |err| Error::Io {
+        source: err,
+        path: path.clone(),
+    };
+    let metadata = match std::fs::symlink_metadata(&path) {
+        Ok(metadata) => Some(metadata),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(io_err(err)),
+    };
+    let worktree_mode = metadata
+        .as_ref()
+        .and_then(|m| fs::mode(m, check_executable_bit))
+        .map(|mode| match mode {
+            EntryMode::Blob if !check_executable_bit && entry.mode == EntryMode::BlobExecutable => entry.mode,
+            mode => mode,
+        });
+
+    if entry.stage() != 0 {
+        return Ok(Some(Change::Conflicted {
+            path: entry.path.clone(),
+            worktree_mode: worktree_mode.filter(|mode| *mode != EntryMode::Commit),
+        }));
+    }
+    if entry.flags.contains(entry::Flags::INTENT_TO_ADD) {
+        return Ok(Some(Change::IntentToAdd {
+            path: entry.path.clone(),
+            worktree_mode: worktree_mode.filter(|mode| *mode != EntryMode::Commit),
+        }));
+    }
+    let (metadata, worktree_mode) = match (metadata, worktree_mode) {
+        (Some(metadata), Some(mode)) if mode != EntryMode::Commit || entry.mode == EntryMode::Commit => {
+            (metadata, mode)
+        }
+        _ => {
+            return Ok(Some(Change::Deleted {
+                path: entry.path.clone(),
+            }))
+        }
+    };
+    if entry.mode == EntryMode::Commit && worktree_mode == EntryMode::Commit {
+        return Ok(None);
+    }
+    let kind = |mode: EntryMode| match mode {
+        EntryMode::BlobExecutable => EntryMode::Blob,
+        mode => mode,
+    };
+    if kind(worktree_mode) != kind(entry.mode) {
+        return Ok(Some(Change::TypeChanged {
+            path: entry.path.clone(),
+            worktree_mode,
+        }));
+    }
+    let modified = Change::Modified {
+        path: entry.path.clone(),
+        worktree_mode,
+    };
+    if worktree_mode != entry.mode {
+        return Ok(Some(modified));
+    }
+
+    let stat = fs::stat(&metadata);
+    let is_racy = index_mtime.map_or(true, |index_mtime| entry.stat.mtime >= index_mtime);
+    if fs::stat_matches(&stat, &entry.stat) && !is_racy {
+        return Ok(None);
+    }
+    if entry.stat.size != 0 && entry.stat.size != stat.size {
+        return Ok(Some(modified));
+    }
+    let id = match filters {
+        Some((pipeline, odb)) if worktree_mode != EntryMode::Link => {
+            pipeline.blob_id(&path, entry.path.as_bstr(), || {
+                let mut buf = Vec::new();
+                odb.find(entry.id, &mut buf, &mut pack::cache::Never)
+                    .ok()
+                    .flatten()
+                    .map_or(false, |blob| filter::has_crlf(blob.data))
+            })?
+        }
+        _ => fs::blob_id(&path, worktree_mode).map_err(io_err)?,
+    };
+    Ok(if id == entry.id { None } else { Some(modified) })
+}
@@ -0,0 +1,181 @@
+//! Compare the tree of `HEAD` with the index and the index with the worktree, similar to `git status`.
+use git_hash::ObjectId;
+use git_object::{bstr::BString, tree::EntryMode};
+
+///
+pub mod tree_index;
+
+///
+pub mod index_worktree;
+
+///
+pub mod untracked;
+
+mod function;
+pub use function::status;
+
+mod porcelain;
+
+/// The mode and id of an entry in a tree or in the index.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub struct Item {
+    /// The kind of the entry.
+    pub mode: EntryMode,
+    /// The id of the object the entry points to.
+    pub id: ObjectId,
+}
+
+impl From<&git_index::Entry> for Item {
+    fn from(entry: &git_index::Entry) -> Self {
+        Item {
+            mode: entry.mode,
+            id: entry.id,
+        }
+    }
+}
+
+/// Determine how untracked files are listed.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub enum Untracked {
+    /// Don't list untracked files at all.
+    No,
+    /// List untracked files, but collapse directories without tracked files into a single entry with a trailing slash.
+    Normal,
+    /// List all untracked files individually.
+    All,
+}
+
+/// The filters to apply to files of the worktree before hashing them, for use in [`status()`].
+#[derive(Clone, Copy)]
+pub struct Filters<'a> {
+    /// The pipeline to convert files with.
+    pub pipeline: &'a crate::filter::Pipeline,
+    /// The object database to read blobs of index entries from, which is required by some conversions.
+    pub odb: &'a git_odb::linked::Store,
+}
+
+/// Options for use in [`status()`].
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub struct Options {
+    /// The amount of threads to use when checking files of the worktree for changes, or `None` to use all logical cores.
+    ///
+    /// Note that threads are only used if the `parallel` feature of `git-features` is enabled.
+    pub thread_limit: Option<usize>,
+    /// If true, the executable bit of files is compared to the one of entries, like `core.fileMode` does.
+    pub check_executable_bit: bool,
+    /// How to list untracked files.
+    pub untracked: Untracked,
+    /// If true, ignored files are listed as well, in the same way as untracked files.
+    pub ignored: bool,
+    /// If true, files which were deleted and added with the same content between `HEAD` and the index are shown as renames.
+    ///
+    /// Only exact renames are detected, which is why all renames have a similarity of 100%.
+    pub track_renames: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            thread_limit: None,
+            check_executable_bit: cfg!(unix),
+            untracked: Untracked::Normal,
+            ignored: false,
+            track_renames: true,
+        }
+    }
+}
+
+/// The way an entry changed compared to its previous version.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Copy)]
+pub enum Change {
+    /// There was no change.
+    Unmodified,
+    /// The content or executable bit changed.
+    Modified,
+    /// The kind of the entry changed, for instance from a file to a symlink.
+    TypeChanged,
+    /// The entry was added.
+    Added,
+    /// The entry was deleted.
+    Deleted,
+    /// The entry was renamed.
+    Renamed,
+}
+
+impl Change {
+    /// Return the letter used for this change in the short and porcelain formats of `git status`.
+    pub fn as_char(&self) -> char {
+        match self {
+            Change::Unmodified => '.',
+            Change::Modified => 'M',
+            Change::TypeChanged => 'T',
+            Change::Added => 'A',
+            Change::Deleted => 'D',
+            Change::Renamed => 'R',
+        }
+    }
+}
+
+/// An entry of the [status][status()] with all information needed to display it.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub enum Entry {
+    /// A tracked path which changed in the index compared to `HEAD`, in the worktree compared to the index, or both.
+    Changed {
+        /// The path relative to the root of the worktree.
+        path: BString,
+        /// The path this entry was renamed from if `staged` is [`Change::Renamed`].
+        source: Option<BString>,
+        /// How the entry in the index changed compared to `HEAD`.
+        staged: Change,
+        /// How the file in the worktree changed compared to the index.
+        unstaged: Change,
+        /// The entry in `HEAD`, or `None` if it was added.
+        head: Option<Item>,
+        /// The entry in the index, or `None` if it was deleted or only added with the intent to add it.
+        index: Option<Item>,
+        /// The mode of the file in the worktree, or `None` if it doesn't exist.
+        worktree_mode: Option<EntryMode>,
+    },
+    /// A path with a merge conflict.
+    Conflicted {
+        /// The path relative to the root of the worktree.
+        path: BString,
+        /// The merge base, our and their version in stages `1` to `3` respectively, if present.
+        stages: [Option<Item>; 3],
+        /// The mode of the file in the worktree, or `None` if it doesn't exist.
+        worktree_mode: Option<EntryMode>,
+    },
+    /// A file or directory which isn't tracked, with directories having a trailing slash.
+    Untracked {
+        /// The path relative to the root of the worktree.
+        path: BString,
+    },
+    /// A file or directory which is ignored, with directories having a trailing slash.
+    Ignored {
+        /// The path relative to the root of the worktree.
+        path: BString,
+    },
+}
+
+mod error {
+    use quick_error::quick_error;
+    use std::path::PathBuf;
+
+    quick_error! {
+        /// The error returned by [`status()`][super::status()] and [`index_worktree::changes()`][super::index_worktree::changes()].
+        #[derive(Debug)]
+        #[allow(missing_docs)]
+        pub enum Error {
+            Io { source: std::io::Error, path: PathBuf } {
+                display("Could not access '{}' in the worktree", path.display())
+                source(source)
+            }
+            Filter(err: crate::filter::Error) {
+                display("Could not convert a file of the worktree before hashing it")
+                from()
+                source(err)
+            }
+        }
+    }
+}
+pub use error::Error;
@@ -0,0 +1,104 @@
+use std::io;
+
+use git_hash::ObjectId;
+use git_object::tree::EntryMode;
+
+use crate::status::{Change, Entry, Item};
+
+impl Entry {
+    /// Write this entry as a single line in the format of `git status --porcelain=v2` to `out`.
+    ///
+    /// Note that as submodules aren't inspected, their state is always written as `N...`.
+    pub fn write_porcelain_v2(&self, mut out: impl io::Write) -> io::Result<()> {
+        match self {
+            Entry::Changed {
+                path,
+                source,
+                staged,
+                unstaged,
+                head,
+                index,
+                worktree_mode,
+            } => {
+                write!(
+                    out,
+                    "{} {}{} N... {} {} {} {} {}",
+                    if source.is_some() { '2' } else { '1' },
+                    staged.as_char(),
+                    unstaged.as_char(),
+                    Mode(head.map(|item| item.mode)),
+                    Mode(index.map(|item| item.mode)),
+                    Mode(*worktree_mode),
+                    id(head),
+                    id(index),
+                )?;
+                match source {
+                    Some(source) => {
+                        debug_assert_eq!(*staged, Change::Renamed);
+                        out.write_all(b" R100 ")?;
+                        out.write_all(path)?;
+                        out.write_all(b"\t")?;
+                        out.write_all(source)?;
+                    }
+                    None => {
+                        out.write_all(b" ")?;
+                        out.write_all(path)?;
+                    }
+                }
+            }
+            Entry::Conflicted {
+                path,
+                stages,
+                worktree_mode,
+            } => {
+                write!(
+                    out,
+                    "u {} N... {} {} {} {} {} {} {} ",
+                    conflict_status(stages),
+                    Mode(stages[0].map(|item| item.mode)),
+                    Mode(stages[1].map(|item| item.mode)),
+                    Mode(stages[2].map(|item| item.mode)),
+                    Mode(*worktree_mode),
+                    id(&stages[0]),
+                    id(&stages[1]),
+                    id(&stages[2]),
+                )?;
+                out.write_all(path)?;
+            }
+            Entry::Untracked { path } => {
+                out.write_all(b"? ")?;
+                out.write_all(path)?;
+            }
+            Entry::Ignored { path } => {
+                out.write_all(b"! ")?;
+                out.write_all(path)?;
+            }
+        }
+        out.write_all(b"\n")
+    }
+}
+
+struct Mode(Option<EntryMode>);
+
+impl std::fmt::Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:06o}", self.0.map_or(0, |mode| mode as u16))
+    }
+}
+
+fn id(item: &Option<Item>) -> ObjectId {
+    item.map_or_else(ObjectId::null_sha1, |item| item.id)
+}
+
+/// Return the two letters describing which side added or deleted a conflicting entry, depending on the stages present.
+fn conflict_status(stages: &[Option<Item>; 3]) -> &'static str {
+    match (stages[0].is_some(), stages[1].is_some(), stages[2].is_some()) {
+        (true, false, false) => "DD",
+        (false, true, false) => "AU",
+        (true, true, false) => "UD",
+        (false, false, true) => "UA",
+        (true, false, true) => "DU",
+        (false, true, true) => "AA",
+        (true, true, true) | (false, false, false) => "UU",
+    }
+}
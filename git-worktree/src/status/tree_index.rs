@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use git_index::{entry, State};
+use git_object::bstr::BString;
+
+use crate::status::Item;
+
+/// A change between the tree of `HEAD` and the index.
+#[derive(PartialEq, Eq, Debug, Hash, Clone)]
+pub enum Change {
+    /// An entry was added to the index.
+    Added {
+        /// The path of the entry.
+        path: BString,
+        /// The entry in the index.
+        index: Item,
+    },
+    /// An entry was removed from the index.
+    Deleted {
+        /// The path of the entry.
+        path: BString,
+        /// The entry in `HEAD`.
+        head: Item,
+    },
+    /// The mode or id of an entry changed.
+    Modified {
+        /// The path of the entry.
+        path: BString,
+        /// The entry in `HEAD`.
+        head: Item,
+        /// The entry in the index.
+        index: Item,
+    },
+    /// An entry was moved to another path without changing its content.
+    Renamed {
+        /// The path of the entry in `HEAD`.
+        source: BString,
+        /// The path of the entry in the index.
+        path: BString,
+        /// The entry in `HEAD`.
+        head: Item,
+        /// The entry in the index.
+        index: Item,
+    },
+    /// The index has a merge conflict at `path`.
+    Conflicted {
+        /// The path of the entry.
+        path: BString,
+        /// The merge base, our and their version in stages `1` to `3` respectively, if present.
+        stages: [Option<Item>; 3],
+    },
+}
+
+impl Change {
+    /// Return the path of the entry in the index.
+    pub fn path(&self) -> &BString {
+        match self {
+            Change::Added { path, .. }
+            | Change::Deleted { path, .. }
+            | Change::Modified { path, .. }
+            | Change::Renamed { path, .. }
+            | Change::Conflicted { path, .. } => path,
+        }
+    }
+}
+
+/// Compare the entries of `head`, typically [created from the tree][State::from_tree()] of `HEAD`, with the ones in `index`
+/// and return all changes sorted by path.
+///
+/// Entries added with the intent to add them are ignored as they are only considered to be changes of the worktree.
+/// If `track_renames` is true, deleted entries whose content was added at another path are turned into renames.
+pub fn changes(head: &State, index: &State, track_renames: bool) -> Vec<Change> {
+    let mut out = Vec::new();
+    let mut head_entries = head.entries().iter().filter(|e| e.stage() == 0).peekable();
+    let mut index_entries = index
+        .entries()
+        .iter()
+        .filter(|e| !e.flags.contains(entry::Flags::INTENT_TO_ADD))
+        .peekable();
+
+    loop {
+        let ordering = match (head_entries.peek(), index_entries.peek()) {
+            (None, None) => break,
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (Some(head), Some(index)) => head.path.cmp(&index.path),
+        };
+        match ordering {
+            std::cmp::Ordering::Less => {
+                let head = head_entries.next().expect("peeked");
+                out.push(Change::Deleted {
+                    path: head.path.clone(),
+                    head: head.into(),
+                });
+            }
+            std::cmp::Ordering::Greater => {
+                let index = index_entries.next().expect("peeked");
+                if index.stage() == 0 {
+                    out.push(Change::Added {
+                        path: index.path.clone(),
+                        index: index.into(),
+                    });
+                } else {
+                    out.push(conflict(index, &mut index_entries));
+                }
+            }
+            std::cmp::Ordering::Equal => {
+                let head = head_entries.next().expect("peeked");
+                let index = index_entries.next().expect("peeked");
+                if index.stage() != 0 {
+                    out.push(conflict(index, &mut index_entries));
+                } else if head.mode != index.mode || head.id != index.id {
+                    out.push(Change::Modified {
+                        path: index.path.clone(),
+                        head: head.into(),
+                        index: index.into(),
+                    });
+                }
+            }
+        }
+    }
+
+    if track_renames {
+        detect_renames(&mut out);
+    }
+    out
+}
+
+/// Consume all stages of the conflict starting at `first`.
+fn conflict<'a>(
+    first: &'a git_index::Entry,
+    entries: &mut std::iter::Peekable<impl Iterator<Item = &'a git_index::Entry>>,
+) -> Change {
+    let mut stages = [None, None, None];
+    stages[first.stage() as usize - 1] = Some(first.into());
+    while let Some(entry) = entries.next_if(|e| e.path == first.path) {
+        if entry.stage() != 0 {
+            stages[entry.stage() as usize - 1] = Some(entry.into());
+        }
+    }
+    Change::Conflicted {
+        path: first.path.clone(),
+        stages,
+    }
+}
+
+/// Turn pairs of deletions and additions of the same object into renames, keeping the changes sorted by the path in the index.
+fn detect_renames(changes: &mut Vec<Change>) {
+    let mut deleted_by_id: HashMap<_, Vec<usize>> = HashMap::new();
+    for (idx, change) in changes.iter().enumerate().rev() {
+        if let Change::Deleted { head, .. } = change {
+            deleted_by_id.entry(head.id).or_default().push(idx);
+        }
+    }
+    let mut renamed_sources = Vec::new();
+    for idx in 0..changes.len() {
+        let (path, index) = match &changes[idx] {
+            Change::Added { path, index } => (path.clone(), *index),
+            _ => continue,
+        };
+        let source_idx = match deleted_by_id.get_mut(&index.id).and_then(|candidates| candidates.pop()) {
+            Some(source_idx) => source_idx,
+            None => continue,
+        };
+        let (source, head) = match &changes[source_idx] {
+            Change::Deleted { path, head } => (path.clone(), *head),
+            _ => unreachable!("only deletions are candidates"),
+        };
+        changes[idx] = Change::Renamed {
+            source,
+            path,
+            head,
+            index,
+        };
+        renamed_sources.push(source_idx);
+    }
+    renamed_sources.sort_unstable();
+    for idx in renamed_sources.into_iter().rev() {
+        changes.remove(idx);
+    }
+}
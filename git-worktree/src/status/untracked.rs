@@ -0,0 +1,161 @@
+use std::path::Path;
+
+use git_index::State;
+use git_object::{
+    bstr::{BStr, BString, ByteSlice, ByteVec},
+    tree::EntryMode,
+};
+
+use crate::status::{Error, Untracked};
+
+/// The files and directories found by [`collect()`], each sorted by path.
+#[derive(PartialEq, Eq, Debug, Hash, Clone, Default)]
+pub struct Outcome {
+    /// All untracked files and directories, with directories having a trailing slash.
+    pub untracked: Vec<BString>,
+    /// All ignored files and directories, with directories having a trailing slash.
+    pub ignored: Vec<BString>,
+}
+
+/// Find all files in the `worktree` directory which aren't tracked by the `index`, listing them according to `mode`.
+///
+/// `is_ignored(path, is_dir)` is called with the path of each untracked file or directory relative to the `worktree` to determine
+/// if it's ignored, in which case it is listed as ignored instead of untracked.
+/// With [`Untracked::Normal`], untracked directories are listed as a whole, and so are ignored directories or directories which
+/// contain nothing but ignored files. Ignored files within untracked directories are still listed individually.
+/// The `.git` directory and submodules are always skipped, and empty directories aren't listed at all.
+pub fn collect(
+    index: &State,
+    worktree: &Path,
+    mode: Untracked,
+    mut is_ignored: impl FnMut(&BStr, bool) -> bool,
+) -> Result<Outcome, Error> {
+    let mut out = Outcome::default();
+    if mode == Untracked::No {
+        return Ok(out);
+    }
+    let mut path = BString::default();
+    Walk {
+        index,
+        worktree,
+        mode,
+        is_ignored: &mut is_ignored,
+    }
+    .dir(&mut path, Location::Tracked, &mut out)?;
+    out.untracked.sort();
+    out.ignored.sort();
+    Ok(out)
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Location {
+    /// The directory contains tracked files.
+    Tracked,
+    /// The directory doesn't contain tracked files.
+    Untracked,
+    /// The directory is ignored, and so is everything in it.
+    Ignored,
+}
+
+struct Walk<'a> {
+    index: &'a State,
+    worktree: &'a Path,
+    mode: Untracked,
+    is_ignored: &'a mut dyn FnMut(&BStr, bool) -> bool,
+}
+
+impl<'a> Walk<'a> {
+    /// Collect all untracked and ignored files in the directory at `path` into `out`.
+    fn dir(&mut self, path: &mut BString, location: Location, out: &mut Outcome) -> Result<(), Error> {
+        let dir = self.worktree.join(path.to_path_lossy());
+        let io_err = |err| Error::Io {
+            source: err,
+            path: dir.clone(),
+        };
+        for entry in std::fs::read_dir(&dir).map_err(io_err)? {
+            let entry = entry.map_err(io_err)?;
+            let name = entry.file_name();
+            if name == ".git" {
+                continue;
+            }
+            let file_type = entry.file_type().map_err(io_err)?;
+            let prefix_len = path.len();
+            if !path.is_empty() {
+                path.push(b'/');
+            }
+            path.push_str(
+                Vec::from_os_string(name).unwrap_or_else(|name| name.to_string_lossy().into_owned().into_bytes()),
+            );
+
+            if file_type.is_dir() {
+                self.subdir(path, location, out)?;
+            } else if location != Location::Tracked || !is_tracked_file(self.index, path.as_ref()) {
+                if location == Location::Ignored || (self.is_ignored)(path.as_ref(), false) {
+                    out.ignored.push(path.clone());
+                } else {
+                    out.untracked.push(path.clone());
+                }
+            }
+            path.truncate(prefix_len);
+        }
+        Ok(())
+    }
+
+    fn subdir(&mut self, path: &mut BString, parent: Location, out: &mut Outcome) -> Result<(), Error> {
+        let location = match parent {
+            Location::Tracked => match tracked_directory(self.index, path.as_ref()) {
+                Some(true) => return Ok(()),
+                Some(false) => Location::Tracked,
+                None if (self.is_ignored)(path.as_ref(), true) => Location::Ignored,
+                None => Location::Untracked,
+            },
+            Location::Untracked if (self.is_ignored)(path.as_ref(), true) => Location::Ignored,
+            location => location,
+        };
+        // Directories are only collapsed if they are the outermost untracked or ignored directory.
+        if self.mode == Untracked::All || location == Location::Tracked || parent != Location::Tracked {
+            return self.dir(path, location, out);
+        }
+
+        let mut contents = Outcome::default();
+        self.dir(path, location, &mut contents)?;
+        if !contents.untracked.is_empty() {
+            out.untracked.push(directory(path.as_ref()));
+            out.ignored.extend(contents.ignored);
+        } else if !contents.ignored.is_empty() {
+            out.ignored.push(directory(path.as_ref()));
+        }
+        Ok(())
+    }
+}
+
+fn directory(path: &BStr) -> BString {
+    let mut dir = path.to_owned();
+    dir.push(b'/');
+    dir
+}
+
+fn is_tracked_file(index: &State, path: &BStr) -> bool {
+    index.entries().binary_search_by(|e| e.path.as_bstr().cmp(path)).is_ok()
+}
+
+/// Return `Some(true)` if the directory at `path` is a submodule, `Some(false)` if it contains tracked files and `None` otherwise.
+fn tracked_directory(index: &State, path: &BStr) -> Option<bool> {
+    let dir = directory(path);
+    let entries = index.entries();
+    match entries.binary_search_by(|e| e.path.as_bstr().cmp(path)) {
+        Ok(pos) if entries[pos].mode == EntryMode::Commit => Some(true),
+        Ok(_) => None,
+        Err(pos) => {
+            let has_tracked_files = entries[pos..]
+                .iter()
+                .take_while(|e| e.path.starts_with(path))
+                .any(|e| e.path.starts_with(&dir));
+            if has_tracked_files {
+                Some(false)
+            } else {
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use std::path::{Path, PathBuf};
+
+use git_glob::pattern::Case;
+use git_object::bstr::{BString, ByteSlice};
+use git_worktree::attributes::{Assignment, Kind, List, Stack, State};
+
+fn fixture() -> crate::Result<PathBuf> {
+    git_testtools::scripted_fixture_repo_read_only("make_attributes_repo.sh")
+}
+
+fn stack(root: &Path, case: Case) -> crate::Result<Stack> {
+    let repo = root.join("repo");
+    let attributes_file = PathBuf::from(std::fs::read_to_string(root.join("attributes-file"))?.trim_end());
+    Ok(Stack::from_git_dir(
+        &repo,
+        &repo.join(".git"),
+        Some(&attributes_file),
+        case,
+    )?)
+}
+
+#[test]
+fn stack_matches_like_git_check_attr() -> crate::Result {
+    let root = fixture()?;
+    let mut stack = stack(&root, Case::Sensitive)?;
+    let names = std::fs::read_to_string(root.join("attributes"))?;
+    let names: Vec<_> = names.split_whitespace().collect();
+    let baseline = std::fs::read(root.join("git-check-attr.baseline"))?;
+    let mut lines = baseline.lines();
+
+    let paths = std::fs::read(root.join("paths"))?;
+    for path in paths.lines() {
+        let (path_without_slash, is_dir) = match path.strip_suffix(b"/") {
+            Some(path) => (path, true),
+            None => (path, false),
+        };
+        let outcome = stack.attributes(path_without_slash.as_bstr(), is_dir);
+        for name in &names {
+            let actual = format!("{}: {}: {}", path.as_bstr(), name, outcome.state(name).to_bstring());
+            let expected = lines.next().expect("one line per path and attribute");
+            assert_eq!(actual, expected.as_bstr());
+        }
+    }
+    assert!(lines.next().is_none(), "all lines of the baseline were checked");
+    Ok(())
+}
+
+#[test]
+fn case_folding() -> crate::Result {
+    let root = fixture()?;
+    let outcome = stack(&root, Case::Fold)?.attributes("X.UPPER".as_bytes().as_bstr(), false);
+    assert_eq!(outcome.value("custom"), Some("case".as_bytes().as_bstr()));
+    Ok(())
+}
+
+#[test]
+fn outcome_accessors() -> crate::Result {
+    let root = fixture()?;
+    let outcome = stack(&root, Case::Sensitive)?.attributes("b.bin".as_bytes().as_bstr(), false);
+    assert!(outcome.is_set("binary"));
+    assert!(outcome.is_unset("text"));
+    assert_eq!(outcome.value("custom"), Some("root".as_bytes().as_bstr()));
+    assert_eq!(*outcome.state("eol"), State::Unspecified);
+    assert!(
+        !outcome.attributes.contains_key("eol".as_bytes().as_bstr()),
+        "unspecified attributes aren't listed"
+    );
+    Ok(())
+}
+
+#[test]
+fn list_parsing() {
+    let list = List::from_bytes(
+        b"# comment\n\n  *.txt text -diff !merge eol=lf\r\n[attr]m a b=c\n!neg a\n*.bad -\n\"a\\tb\\040c\" x\n",
+        None,
+        BString::default(),
+        false,
+    );
+    let lines: Vec<_> = list
+        .lines
+        .iter()
+        .map(|line| {
+            let pattern = match &line.kind {
+                Kind::Pattern(pattern) => pattern.text.clone(),
+                Kind::Macro(name) => name.clone(),
+            };
+            (pattern, line.assignments.clone(), line.sequence_number)
+        })
+        .collect();
+    let assignment = |name: &str, state: State| Assignment {
+        name: name.into(),
+        state,
+    };
+    assert_eq!(
+        lines,
+        vec![
+            (
+                "*.txt".into(),
+                vec![
+                    assignment("text", State::Set),
+                    assignment("diff", State::Unset),
+                    assignment("merge", State::Unspecified),
+                    assignment("eol", State::Value("lf".into()))
+                ],
+                3
+            ),
+            ("a\tb c".into(), vec![assignment("x", State::Set)], 7)
+        ],
+        "macros aren't allowed, negative patterns and invalid assignments discard the line"
+    );
+}
@@ -0,0 +1,245 @@
+use std::path::{Path, PathBuf};
+
+use git_index::State;
+use git_object::bstr::ByteSlice;
+use git_odb::linked;
+use git_testtools::tempfile::TempDir;
+use git_worktree::{
+    checkout::{self, checkout, Collision, Options},
+    status::index_worktree,
+};
+
+fn fixture() -> crate::Result<(PathBuf, State, linked::Store)> {
+    let repo = git_testtools::scripted_fixture_repo_read_only("make_checkout_repo.sh")?.join("repo");
+    let index = git_index::File::at(repo.join(".git/index"))?.into_state();
+    let odb = linked::Store::at(repo.join(".git/objects"))?;
+    Ok((repo, index, odb))
+}
+
+/// Write `index` into `dir` and return it as file for use with the status.
+fn index_file(index: State, dir: &Path) -> crate::Result<git_index::File> {
+    std::fs::create_dir_all(dir)?;
+    let mut file = git_index::File::from_state(index, dir.join("index"));
+    file.write(git_lock::acquire::Fail::Immediately)?;
+    Ok(file)
+}
+
+fn case_sensitive() -> Options {
+    Options {
+        thread_limit: Some(2),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn all_entries_are_written_into_an_empty_directory() -> crate::Result {
+    let (repo, mut index, odb) = fixture()?;
+    let tmp = TempDir::new()?;
+    let outcome = checkout(&mut index, tmp.path(), &odb, None, case_sensitive())?;
+    assert_eq!(outcome.files_written, 12, "submodules count as well");
+    assert_eq!(outcome.files_up_to_date, 0);
+    assert_eq!(outcome.collisions, vec![]);
+
+    for path in &[
+        "packed", "a/file", "a/b/c", "exe", "Readme", "README", "D", "d/e", "large",
+    ] {
+        assert_eq!(
+            std::fs::read(tmp.path().join(path))?,
+            std::fs::read(repo.join(path))?,
+            "{} has the same content",
+            path
+        );
+    }
+    assert_eq!(std::fs::read_link(tmp.path().join("link"))?, Path::new("a/file"));
+    assert_eq!(
+        std::fs::read_link(tmp.path().join("dangling"))?,
+        Path::new("does-not-exist")
+    );
+    assert!(tmp.path().join("sub").is_dir(), "submodules are empty directories");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_ne!(
+            std::fs::metadata(tmp.path().join("exe"))?.permissions().mode() & 0o100,
+            0
+        );
+        assert_eq!(
+            std::fs::metadata(tmp.path().join("packed"))?.permissions().mode() & 0o100,
+            0
+        );
+    }
+    assert_eq!(outcome.bytes_written, 200_000 + 33);
+
+    let index = index_file(index, &tmp.path().join(".git"))?;
+    assert_eq!(
+        index_worktree::changes(&index, tmp.path(), None, &Default::default())?,
+        vec![],
+        "the index knows the stat information of all written files"
+    );
+    Ok(())
+}
+
+#[test]
+fn files_with_the_desired_content_are_not_written_again() -> crate::Result {
+    let (_repo, mut index, odb) = fixture()?;
+    let tmp = TempDir::new()?;
+    checkout(&mut index, tmp.path(), &odb, None, case_sensitive())?;
+    std::fs::remove_file(tmp.path().join("exe"))?;
+
+    let outcome = checkout(&mut index, tmp.path(), &odb, None, case_sensitive())?;
+    assert_eq!(outcome.files_written, 1);
+    assert_eq!(outcome.files_up_to_date, 11);
+    Ok(())
+}
+
+#[test]
+fn modified_files_are_only_overwritten_if_forced() -> crate::Result {
+    let (_repo, mut index, odb) = fixture()?;
+    let tmp = TempDir::new()?;
+    std::fs::write(tmp.path().join("a"), "a file in place of a directory")?;
+    std::fs::create_dir(tmp.path().join("exe"))?;
+    std::fs::write(tmp.path().join("exe/untracked"), "")?;
+    std::fs::write(tmp.path().join("packed"), "modified")?;
+
+    let err = checkout(&mut index, tmp.path(), &odb, None, case_sensitive()).unwrap_err();
+    match err {
+        checkout::Error::WouldOverwrite { paths } => {
+            assert_eq!(paths, vec!["a/b/c", "a/file", "exe", "packed"]);
+        }
+        err => panic!("unexpected error: {}", err),
+    }
+    assert_eq!(std::fs::read(tmp.path().join("packed"))?, b"modified");
+    assert!(!tmp.path().join("large").exists(), "nothing was written");
+
+    let outcome = checkout(
+        &mut index,
+        tmp.path(),
+        &odb,
+        None,
+        Options {
+            overwrite_existing: true,
+            ..case_sensitive()
+        },
+    )?;
+    assert_eq!(outcome.files_written, 12);
+    assert_eq!(std::fs::read(tmp.path().join("packed"))?, b"packed\n");
+    assert_eq!(std::fs::read(tmp.path().join("exe"))?, b"exe\n");
+    assert_eq!(std::fs::read(tmp.path().join("a/file"))?, b"a\n");
+    Ok(())
+}
+
+#[test]
+fn paths_differing_in_case_collide_if_case_is_ignored() -> crate::Result {
+    let (_repo, mut index, odb) = fixture()?;
+    let tmp = TempDir::new()?;
+    let outcome = checkout(
+        &mut index,
+        tmp.path(),
+        &odb,
+        None,
+        Options {
+            ignore_case: true,
+            ..case_sensitive()
+        },
+    )?;
+    assert_eq!(
+        outcome.collisions,
+        vec![
+            Collision {
+                path: "Readme".into(),
+                with: "README".into()
+            },
+            Collision {
+                path: "d/e".into(),
+                with: "D".into()
+            },
+        ]
+    );
+    assert_eq!(outcome.files_written, 10);
+    assert!(!tmp.path().join("d").exists());
+    Ok(())
+}
+
+#[test]
+fn symlinks_and_executable_bits_can_be_disabled() -> crate::Result {
+    let (_repo, mut index, odb) = fixture()?;
+    let tmp = TempDir::new()?;
+    let options = Options {
+        symlinks: false,
+        executable_bit: false,
+        ..case_sensitive()
+    };
+    checkout(&mut index, tmp.path(), &odb, None, options)?;
+    assert_eq!(std::fs::read(tmp.path().join("link"))?, b"a/file");
+    assert!(std::fs::symlink_metadata(tmp.path().join("link"))?.is_file());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(
+            std::fs::metadata(tmp.path().join("exe"))?.permissions().mode() & 0o100,
+            0
+        );
+    }
+
+    let outcome = checkout(&mut index, tmp.path(), &odb, None, options)?;
+    assert_eq!(outcome.files_up_to_date, 12, "files are checked with the same options");
+    Ok(())
+}
+
+#[test]
+fn paths_leading_outside_of_the_worktree_or_into_the_git_dir_are_rejected() -> crate::Result {
+    for invalid_path in &["../x", ".GIT/config", "a/../../x", "/x", "a//b", "a/.git./hooks/x"] {
+        for overwrite_existing in &[false, true] {
+            let (_repo, mut index, odb) = fixture()?;
+            let tmp = TempDir::new()?;
+            let worktree = tmp.path().join("worktree");
+            std::fs::create_dir(&worktree)?;
+            index.entries_mut()[0].path = (*invalid_path).into();
+
+            let options = Options {
+                overwrite_existing: *overwrite_existing,
+                ..case_sensitive()
+            };
+            let err = checkout(&mut index, &worktree, &odb, None, options).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("Refusing to check out the invalid path '{}'", invalid_path)
+            );
+            assert_eq!(
+                std::fs::read_dir(tmp.path())?.count(),
+                1,
+                "nothing was written next to the worktree"
+            );
+            assert_eq!(std::fs::read_dir(&worktree)?.count(), 0, "nothing was written at all");
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn objects_which_are_not_blobs_are_rejected() -> crate::Result {
+    let (repo, index, odb) = fixture()?;
+    let head = git_hash::ObjectId::from_hex(std::fs::read(repo.join(".git/refs/heads/main"))?.trim_end())?;
+    let packed_commit = index.entries().iter().find(|e| e.path == "sub").expect("present").id;
+    for (path, id) in &[("exe", head), ("packed", packed_commit)] {
+        let mut index = index.clone();
+        let entry = index
+            .entries_mut()
+            .iter_mut()
+            .find(|e| e.path == *path)
+            .expect("present");
+        entry.id = *id;
+        let tmp = TempDir::new()?;
+        assert!(
+            matches!(
+                checkout(&mut index, tmp.path(), &odb, None, case_sensitive()),
+                Err(checkout::Error::ObjectKind {
+                    actual: git_object::Kind::Commit,
+                    ..
+                })
+            ),
+            "loose objects are streamed and packed ones are read, and both are checked"
+        );
+    }
+    Ok(())
+}
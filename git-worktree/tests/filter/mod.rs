@@ -0,0 +1,213 @@
+use std::path::{Path, PathBuf};
+
+use git_glob::pattern::Case;
+use git_hash::ObjectId;
+use git_object::bstr::ByteSlice;
+use git_odb::{linked, pack, FindExt};
+use git_testtools::tempfile::TempDir;
+use git_worktree::{
+    attributes,
+    checkout::{self, checkout},
+    filter::{self, AutoCrlf, Driver, Pipeline},
+    status::{index_worktree, Filters},
+};
+
+fn fixture() -> crate::Result<PathBuf> {
+    git_testtools::scripted_fixture_repo_read_only("make_filter_repo.sh")
+}
+
+/// The drivers configured in the fixture, with the process driver being `required` or not.
+fn drivers(root: &Path, required: bool) -> Vec<Driver> {
+    let rot13 = "tr 'A-Za-z' 'N-ZA-Mn-za-m'";
+    vec![
+        Driver {
+            name: "rot13".into(),
+            clean: Some(rot13.into()),
+            smudge: Some(rot13.into()),
+            ..Default::default()
+        },
+        Driver {
+            name: "rot13-process".into(),
+            process: Some(format!("perl {}", root.join("rot13-process.pl").display()).into()),
+            required,
+            ..Default::default()
+        },
+    ]
+}
+
+fn new_pipeline(root: &Path, drivers: Vec<Driver>) -> crate::Result<Pipeline> {
+    let repo = root.join("repo");
+    let attributes = attributes::Stack::from_git_dir(&repo, &repo.join(".git"), None, Case::Sensitive)?;
+    Ok(Pipeline::new(
+        attributes,
+        filter::Options {
+            auto_crlf: AutoCrlf::True,
+            eol: None,
+            drivers,
+        },
+    ))
+}
+
+#[test]
+fn checkout_converts_files_like_git() -> crate::Result {
+    let root = fixture()?;
+    let repo = root.join("repo");
+    let mut index = git_index::File::at(repo.join(".git/index"))?.into_state();
+    let odb = linked::Store::at(repo.join(".git/objects"))?;
+    let pipeline = new_pipeline(&root, drivers(&root, true))?;
+
+    let tmp = TempDir::new()?;
+    let options = checkout::Options {
+        thread_limit: Some(2),
+        ..Default::default()
+    };
+    let outcome = checkout(&mut index, tmp.path(), &odb, Some(&pipeline), options)?;
+    assert_eq!(outcome.files_written, index.entries().len());
+    // git kept the `.gitattributes` file from before `core.autocrlf` was set.
+    for entry in index.entries().iter().filter(|e| e.path != ".gitattributes") {
+        let path = entry.path.to_path_lossy();
+        assert_eq!(
+            std::fs::read(tmp.path().join(&path))?.as_bstr(),
+            std::fs::read(repo.join(&path))?.as_bstr(),
+            "{}",
+            entry.path
+        );
+    }
+
+    let outcome = checkout(&mut index, tmp.path(), &odb, Some(&pipeline), options)?;
+    assert_eq!(
+        outcome.files_up_to_date,
+        index.entries().len(),
+        "converted files are recognized as unchanged"
+    );
+
+    let index_dir = TempDir::new()?;
+    let mut index = git_index::File::from_state(index, index_dir.path().join("index"));
+    index.write(git_lock::acquire::Fail::Immediately)?;
+    let filters = Filters {
+        pipeline: &pipeline,
+        odb: &odb,
+    };
+    assert_eq!(
+        index_worktree::changes(&index, tmp.path(), Some(filters), &Default::default())?,
+        vec![],
+        "the files are converted before they are hashed"
+    );
+    Ok(())
+}
+
+#[test]
+fn files_checked_out_by_git_hash_to_the_ids_of_their_entries() -> crate::Result {
+    let root = fixture()?;
+    let repo = root.join("repo");
+    let index = git_index::File::at(repo.join(".git/index"))?;
+    let odb = linked::Store::at(repo.join(".git/objects"))?;
+    let mut pipeline = new_pipeline(&root, drivers(&root, true))?;
+    let mut buf = Vec::new();
+    for entry in index.entries() {
+        let id = pipeline.blob_id(&repo.join(entry.path.to_path_lossy()), entry.path.as_bstr(), || {
+            let blob = odb
+                .find_existing_blob(entry.id, &mut buf, &mut pack::cache::Never)
+                .expect("blob exists");
+            filter::has_crlf(blob.data)
+        })?;
+        assert_eq!(id, entry.id, "{}", entry.path);
+    }
+    Ok(())
+}
+
+#[test]
+fn crlf_blobs_in_the_index_are_kept_for_text_auto() -> crate::Result {
+    let root = fixture()?;
+    assert_eq!(
+        std::fs::read(root.join("l.auto.eol"))?
+            .as_bstr()
+            .split_str("\t")
+            .next()
+            .expect("info")
+            .split_str(" ")
+            .filter(|field| !field.is_empty())
+            .map(|field| field.as_bstr())
+            .collect::<Vec<_>>(),
+        vec!["i/crlf", "w/crlf", "attr/text=auto"]
+    );
+    assert_eq!(
+        std::fs::read(root.join("status"))?,
+        b"",
+        "git considers the file unchanged"
+    );
+
+    let mut pipeline = new_pipeline(&root, Vec::new())?;
+    let path = "l.auto".as_bytes().as_bstr();
+    assert_eq!(
+        pipeline.convert_to_git(b"r\r\ns\r\n", path, || true)?.as_bstr(),
+        "r\r\ns\r\n",
+        "CRLF in the index prevents the conversion"
+    );
+    assert_eq!(
+        pipeline.convert_to_git(b"r\r\ns\r\n", path, || false)?.as_bstr(),
+        "r\ns\n"
+    );
+    Ok(())
+}
+
+#[test]
+fn failing_drivers_leave_content_unconverted_unless_they_are_required() -> crate::Result {
+    let root = fixture()?;
+    let id = ObjectId::null_sha1();
+    let mut pipeline = new_pipeline(&root, drivers(&root, false))?;
+    assert_eq!(
+        pipeline
+            .convert_to_worktree(b"Content\n", "error.proc".as_bytes().as_bstr(), &id)?
+            .as_bstr(),
+        "Content\r\n"
+    );
+
+    let mut pipeline = new_pipeline(&root, drivers(&root, true))?;
+    assert!(matches!(
+        pipeline.convert_to_worktree(b"Content\n", "error.proc".as_bytes().as_bstr(), &id),
+        Err(filter::Error::Driver { .. })
+    ));
+    assert_eq!(
+        pipeline
+            .convert_to_git(b"Pbagrag\r\n", "other.proc".as_bytes().as_bstr(), || false)?
+            .as_bstr(),
+        "Content\n",
+        "the process continues to be used after it failed to convert a file"
+    );
+
+    let mut drivers = drivers(&root, true);
+    drivers[0].smudge = Some("exit 1".into());
+    drivers[0].clean = None;
+    drivers[0].required = true;
+    let mut pipeline = new_pipeline(&root, drivers)?;
+    assert!(matches!(
+        pipeline.convert_to_worktree(b"data", "a.rot".as_bytes().as_bstr(), &id),
+        Err(filter::Error::Driver { .. })
+    ));
+    assert!(matches!(
+        pipeline.convert_to_git(b"data", "a.rot".as_bytes().as_bstr(), || false),
+        Err(filter::Error::MissingCommand { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn working_tree_encodings_validate_byte_order_marks() -> crate::Result {
+    let root = fixture()?;
+    let mut pipeline = new_pipeline(&root, Vec::new())?;
+    assert!(
+        matches!(
+            pipeline.convert_to_git(b"h\0i\0", "no-bom.u16".as_bytes().as_bstr(), || false),
+            Err(filter::Error::Encoding { .. })
+        ),
+        "UTF-16 without byte order mark is ambiguous"
+    );
+    assert_eq!(
+        pipeline
+            .convert_to_git(b"\xfe\xff\0h\0i", "big-endian.u16".as_bytes().as_bstr(), || false)?
+            .as_bstr(),
+        "hi"
+    );
+    Ok(())
+}
@@ -0,0 +1,78 @@
+#!/bin/bash
+set -eu -o pipefail
+
+cat <<'END' > user-attributes
+# a comment
+*.user custom=user
+*.txt text eol=crlf
+[attr]user-macro -text custom=from-user-macro
+*.um user-macro
+END
+
+git init -q repo && cd repo
+git config core.attributesFile "$(cd .. && pwd)/user-attributes"
+git config core.attributesFile > ../attributes-file
+
+cat <<'END' > .git/info/attributes
+*.info custom=info
+*.txt eol=lf
+sub/override.txt -text
+END
+
+cat <<'END' > .gitattributes
+[attr]my-macro text diff=my-diff -merge
+[attr]nested-macro my-macro filter=nested
+* custom=root
+*.txt text
+*.bin binary
+*.jpg -text -diff
+*.sh eol=lf
+*.bat text eol=crlf
+*.md diff=markdown merge=union
+*.lfs filter=lfs diff=lfs merge=lfs -text
+export/** export-ignore
+docs/ export-ignore
+*.mac my-macro
+*.nest nested-macro
+*.nest-override nested-macro text=forced
+*.reset !custom
+"quoted name.txt" custom=quoted
+\!bang custom=bang
+!negated custom=negated
+/anchored custom=anchored
+invalid -bad= custom=invalid -
+   *.indented    custom=indented
+*.upper custom=case
+a/**/z custom=deep
+END
+
+mkdir -p sub/deeper export/x docs a/b/c
+cat <<'END' > sub/.gitattributes
+[attr]sub-macro custom=sub-macro
+*.txt -text
+*.log custom=sub
+*.um -custom
+!*.md
+/local custom=sub-local
+*.sub sub-macro
+deeper/*.c custom=sub-deeper
+END
+cat <<'END' > sub/deeper/.gitattributes
+*.txt !text eol
+*.c diff=cpp
+END
+
+for f in a.txt b.bin c.jpg d.sh e.bat f.md g.lfs h.mac i.nest j.nest-override k.reset l.user m.um n.info \
+         "quoted name.txt" '!bang' negated anchored sub/anchored local sub/local x.indented X.UPPER x.upper \
+         export/x/f docs/f sub/a.txt sub/a.log sub/a.md sub/a.um sub/override.txt sub/a.sub sub/deeper/a.txt \
+         sub/deeper/a.c sub/deeper/b.sh a/z a/b/c/z invalid; do
+  echo "$f" > "$f"
+done
+
+attrs="text eol diff merge filter binary export-ignore custom my-macro nested-macro user-macro sub-macro"
+{
+  find . -path ./.git -prune -o -type f -print | sed -e 's|^\./||' | sort
+  printf '%s\n' docs/ export/ sub/deeper/
+} > ../paths
+git check-attr --stdin $attrs < ../paths > ../git-check-attr.baseline
+echo "$attrs" > ../attributes
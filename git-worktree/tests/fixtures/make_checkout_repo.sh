@@ -0,0 +1,23 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q repo && cd repo
+git checkout -q -b main
+
+mkdir -p a/b
+echo packed > packed
+echo a > a/file
+git add . && git commit -qm "packed" && git repack -qad
+
+echo c > a/b/c
+echo exe > exe && chmod +x exe
+ln -s a/file link
+ln -s does-not-exist dangling
+echo Readme > Readme
+echo README > README
+echo D > D
+mkdir d && echo e > d/e
+head -c 200000 /dev/zero | tr '\0' 'x' > large
+git add .
+git update-index --add --cacheinfo 160000,$(git rev-parse HEAD),sub
+git commit -qm "loose"
@@ -0,0 +1,117 @@
+#!/bin/bash
+set -eu -o pipefail
+
+# A long-running filter process applying rot13, which fails on files with 'error' in their path.
+cat <<'END' > rot13-process.pl
+use strict;
+use warnings;
+binmode STDIN;
+binmode STDOUT;
+$| = 1;
+
+sub packet_read {
+    my $buffer;
+    my $bytes_read = read STDIN, $buffer, 4;
+    exit 0 if $bytes_read == 0;
+    my $len = hex($buffer);
+    return (1, "") if $len == 0;
+    read STDIN, $buffer, $len - 4;
+    return (0, $buffer);
+}
+
+sub packet_write {
+    my ($data) = @_;
+    print STDOUT sprintf("%04x", length($data) + 4) . $data;
+}
+
+sub read_lines {
+    my @lines;
+    while (1) {
+        my ($flush, $line) = packet_read();
+        last if $flush;
+        chomp $line;
+        push @lines, $line;
+    }
+    return @lines;
+}
+
+my @hello = read_lines();
+die "unexpected greeting" unless $hello[0] eq "git-filter-client";
+packet_write("git-filter-server\n");
+packet_write("version=2\n");
+print STDOUT "0000";
+read_lines();
+packet_write("capability=clean\n");
+packet_write("capability=smudge\n");
+print STDOUT "0000";
+
+while (1) {
+    my ($pathname) = map { /^pathname=(.*)$/ ? $1 : () } read_lines();
+    my $content = "";
+    while (1) {
+        my ($flush, $data) = packet_read();
+        last if $flush;
+        $content .= $data;
+    }
+    if ($pathname =~ /error/) {
+        packet_write("status=error\n");
+        print STDOUT "0000";
+        next;
+    }
+    $content =~ tr/A-Za-z/N-ZA-Mn-za-m/;
+    packet_write("status=success\n");
+    print STDOUT "0000";
+    while (length $content) {
+        packet_write(substr($content, 0, 65516, ""));
+    }
+    print STDOUT "0000";
+    print STDOUT "0000";
+}
+END
+
+git init -q repo && cd repo
+git config filter.rot13.clean "tr 'A-Za-z' 'N-ZA-Mn-za-m'"
+git config filter.rot13.smudge "tr 'A-Za-z' 'N-ZA-Mn-za-m'"
+git config filter.rot13-process.process "perl $(cd .. && pwd)/rot13-process.pl"
+
+cat <<'END' > .gitattributes
+*.txt text
+*.crlf text eol=crlf
+*.lf text eol=lf
+*.auto text=auto
+*.bin binary
+*.id ident
+*.rot filter=rot13
+*.proc filter=rot13-process
+*.u16 working-tree-encoding=UTF-16
+*.l1 working-tree-encoding=ISO-8859-1
+*.combined text eol=crlf ident filter=rot13
+END
+
+printf 'a\r\nb\n' > a.txt
+printf 'one\ntwo\n' > b.crlf
+printf 'x\r\ny\r\n' > c.lf
+printf 'p\r\nq\n' > d.auto
+printf 'bin\r\n\0ary\n' > e.bin
+printf 'ident $Id$ and $Id: old $\n' > f.id
+printf 'Hello\n' > g.rot
+printf 'World\n' > h.proc
+printf '\xff\xfeh\x00\xe4\x00\n\x00' > i.u16
+printf 'caf\xe9\n' > j.l1
+printf 'Some $Id$\nText\n' > k.combined
+printf 'already\r\ncrlf\r\n' > with-crlf
+printf 'plain\nlf\n' > with-lf
+mkdir dir
+printf 'nested\n' > dir/nested.txt
+git add .
+# CRLF blobs committed before being covered by `text=auto` are kept as they are.
+printf 'r\r\ns\r\n' > l.auto
+git update-index --add --cacheinfo "100644,$(git hash-object -w --no-filters l.auto),l.auto"
+git commit -qm "init"
+
+git config core.autocrlf true
+git ls-files | grep -v gitattributes | xargs rm
+git checkout -- .
+
+git ls-files --eol l.auto > ../l.auto.eol
+git status --porcelain > ../status
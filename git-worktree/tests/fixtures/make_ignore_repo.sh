@@ -0,0 +1,64 @@
+#!/bin/bash
+set -eu -o pipefail
+
+cat <<'END' > user-excludes
+# a comment
+*.user
+user-dir/
+!important.user
+END
+
+git init -q repo && cd repo
+git config core.excludesFile "$(cd .. && pwd)/user-excludes"
+git config core.excludesFile > ../excludes-file
+
+cat <<'END' > .git/info/exclude
+*.info
+/anchored
+from-info
+END
+
+cat <<'END' > .gitignore
+*.log
+!keep.log
+build/
+/top-only
+from-info
+!*.user
+trailing-space  
+escaped-space\ 
+\#hash
+\!bang
+**/deep/**/file
+docs/*.html
+ignored-dir/
+ignored-dir-file
+[ab]?c.txt
+END
+
+mkdir -p sub/nested ignored-dir/sub build/out docs/api a/deep/x/y user-dir sub/build anchored sub/anchored
+cat <<'END' > sub/.gitignore
+!*.log
+*.tmp
+nested/*.md
+/local
+END
+cat <<'END' > sub/nested/.gitignore
+*.md
+!readme.md
+END
+cat <<'END' > ignored-dir/.gitignore
+!*
+END
+
+for f in a.log keep.log sub/a.log sub/b.tmp sub/nested/x.md sub/nested/readme.md sub/nested/a.tmp sub/local local \
+         top-only sub/top-only x.info sub/x.info anchored/f sub/anchored/f from-info sub/from-info \
+         a.user important.user sub/b.user user-dir/f sub/build/f build/out/f ignored-dir/f ignored-dir/sub/f ignored-dir-file \
+         "trailing-space" "escaped-space " "#hash" "!bang" a/deep/x/y/file a/deep/file docs/index.html docs/api/index.html \
+         abc.txt bxc.txt cbc.txt Abc.txt; do
+  echo "$f" > "$f"
+done
+
+find . -path ./.git -prune -o -print | sed -e 's|^\./||' | grep -v '^\.$' | sort > ../paths
+printf '%s\n' does-not-exist build sub/does-not-exist.tmp >> ../paths
+git check-ignore --no-index -v -n --stdin < ../paths > ../git-check-ignore.baseline || true
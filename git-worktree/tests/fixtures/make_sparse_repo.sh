@@ -0,0 +1,24 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q repo && cd repo
+git checkout -q -b main
+
+mkdir -p a/b/c a/d e/f g
+for path in root a/file a/b/file a/b/c/file a/d/file e/file e/f/file g/file g/keep.txt; do
+  echo "$path" > "$path"
+done
+git add . && git commit -qm "initial"
+git ls-files > ../all.expected
+
+git sparse-checkout set --cone a/b e/f
+cp .git/info/sparse-checkout ../cone-patterns
+git ls-files -t > ../cone.expected
+find . -path ./.git -prune -o -type f -print | sort > ../cone-worktree.expected
+
+git sparse-checkout set --no-cone '/*' '!/a/' '/a/b/' '!*.txt' 'keep.txt' '!/e/f/'
+cp .git/info/sparse-checkout ../full-patterns
+git ls-files -t > ../full.expected
+find . -path ./.git -prune -o -type f -print | sort > ../full-worktree.expected
+
+git sparse-checkout disable
@@ -0,0 +1,50 @@
+#!/bin/bash
+set -eu -o pipefail
+
+git init -q repo && cd repo
+git checkout -q -b main
+mkdir -p d/e
+echo a > a
+echo b > d/b
+echo c > d/e/c
+echo x > x && chmod +x x
+ln -s a link
+echo renamed > renamed-from
+echo deleted > deleted
+echo deleted > staged-delete
+echo modified > modified-staged
+echo type-change > type-change
+echo conflict > conflict
+printf '*.log\nignored-dir/\n' > .gitignore
+git add . && git commit -qm "init"
+
+git checkout -q -b other
+echo other > conflict && git commit -qam "other"
+git checkout -q main
+echo ours > conflict && git commit -qam "ours"
+git merge other >/dev/null || :
+
+echo staged > modified-staged && git add modified-staged
+echo added > added && git add added
+git mv renamed-from renamed-to
+git rm -q --cached staged-delete
+echo intent > intent && git add --intent-to-add intent
+
+echo changed >> a
+rm deleted
+chmod +x d/b
+rm type-change && ln -s a type-change
+echo "staged and changed" > modified-staged
+echo "conflict resolution" > conflict
+
+echo untracked > untracked
+mkdir -p u/v && echo u > u/v/w
+echo log > i.log
+echo log > d/e/i.log
+mkdir ignored-dir && echo f > ignored-dir/f
+mkdir empty-dir
+
+git rev-parse HEAD^{tree} > ../head-tree
+git status --porcelain=v2 > ../status.expected
+git status --porcelain=v2 --ignored > ../status-ignored.expected
+git status --porcelain=v2 --untracked-files=all > ../status-all.expected
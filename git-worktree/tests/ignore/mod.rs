@@ -0,0 +1,107 @@
+use std::path::{Path, PathBuf};
+
+use git_glob::pattern::Case;
+use git_object::bstr::{BString, ByteSlice};
+use git_worktree::ignore::{List, Mapping, Stack};
+
+fn fixture() -> crate::Result<PathBuf> {
+    git_testtools::scripted_fixture_repo_read_only("make_ignore_repo.sh")
+}
+
+fn stack(root: &Path) -> crate::Result<Stack> {
+    let repo = root.join("repo");
+    let excludes_file = PathBuf::from(std::fs::read_to_string(root.join("excludes-file"))?.trim_end());
+    Ok(Stack::from_git_dir(
+        &repo,
+        &repo.join(".git"),
+        Some(&excludes_file),
+        Case::Sensitive,
+    )?)
+}
+
+#[test]
+fn stack_matches_like_git_check_ignore() -> crate::Result {
+    let root = fixture()?;
+    let repo = root.join("repo");
+    let mut stack = stack(&root)?;
+    let baseline = std::fs::read(root.join("git-check-ignore.baseline"))?;
+    let mut count = 0;
+    for line in baseline.lines() {
+        let tab = line.find_byte(b'\t').expect("tab-separated output");
+        let (expected, path) = (&line[..tab], &line[tab + 1..]);
+        let is_dir = repo.join(path.to_path_lossy()).is_dir();
+        let actual = match stack.pattern_matching(path.as_bstr(), is_dir) {
+            Some(m) => {
+                let source = m.source.expect("patterns are read from files");
+                let source = source.strip_prefix(&repo).unwrap_or(source);
+                let pattern = Mapping {
+                    pattern: m.pattern.clone(),
+                    sequence_number: m.sequence_number,
+                };
+                format!("{}:{}:{}", source.display(), m.sequence_number, pattern.to_bstring())
+            }
+            None => "::".into(),
+        };
+        assert_eq!(actual, expected.to_str_lossy(), "{}", path.as_bstr());
+        count += 1;
+    }
+    assert_eq!(count, 61, "all paths were checked");
+    Ok(())
+}
+
+#[test]
+fn is_ignored_respects_negation_and_excluded_parent_directories() -> crate::Result {
+    let root = fixture()?;
+    let mut stack = stack(&root)?;
+    assert!(stack.is_ignored("a.log".as_bytes().as_bstr(), false));
+    assert!(!stack.is_ignored("keep.log".as_bytes().as_bstr(), false), "negated");
+    assert!(
+        !stack.is_ignored("sub/a.log".as_bytes().as_bstr(), false),
+        "negated in sub-directory"
+    );
+    assert!(
+        stack.is_ignored("ignored-dir/f".as_bytes().as_bstr(), false),
+        "negations within excluded directories are ineffective"
+    );
+    assert!(
+        !stack.is_ignored("build".as_bytes().as_bstr(), false),
+        "only directories"
+    );
+    assert!(stack.is_ignored("build".as_bytes().as_bstr(), true));
+    Ok(())
+}
+
+#[test]
+fn list_parsing() {
+    let list = List::from_bytes(
+        b"\xef\xbb\xbf# comment\n\n  \nfoo  \nbar\\ \r\n\\#baz\n!/qux/\n",
+        None,
+        BString::default(),
+    );
+    let patterns: Vec<_> = list
+        .patterns
+        .iter()
+        .map(|m| (m.to_bstring(), m.sequence_number))
+        .collect();
+    assert_eq!(
+        patterns,
+        vec![
+            ("foo".into(), 4),
+            ("bar\\ \r".into(), 5),
+            ("\\#baz".into(), 6),
+            ("!/qux/".into(), 7)
+        ]
+    );
+}
+
+#[test]
+fn case_folding() -> crate::Result {
+    let root = fixture()?;
+    let repo = root.join("repo");
+    let mut stack = Stack::new(&repo, Vec::new(), Case::Fold);
+    assert!(stack.is_ignored("A.LOG".as_bytes().as_bstr(), false));
+    assert!(stack.is_ignored("Abc.txt".as_bytes().as_bstr(), false));
+    let mut stack = Stack::new(&repo, Vec::new(), Case::Sensitive);
+    assert!(!stack.is_ignored("Abc.txt".as_bytes().as_bstr(), false));
+    Ok(())
+}
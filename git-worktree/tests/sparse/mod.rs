@@ -0,0 +1,167 @@
+use std::path::{Path, PathBuf};
+
+use git_glob::pattern::Case;
+use git_index::{entry, State};
+use git_object::bstr::{BString, ByteSlice};
+use git_odb::linked;
+use git_testtools::tempfile::TempDir;
+use git_worktree::{
+    checkout::{self, checkout},
+    sparse::{self, Cone, Patterns},
+};
+
+fn fixture() -> crate::Result<(PathBuf, State, linked::Store)> {
+    let root = git_testtools::scripted_fixture_repo_read_only("make_sparse_repo.sh")?;
+    let index = git_index::File::at(root.join("repo/.git/index"))?.into_state();
+    let odb = linked::Store::at(root.join("repo/.git/objects"))?;
+    Ok((root, index, odb))
+}
+
+fn options() -> checkout::Options {
+    checkout::Options {
+        thread_limit: Some(2),
+        ..Default::default()
+    }
+}
+
+fn patterns(root: &Path, name: &str, cone: bool) -> crate::Result<Patterns> {
+    Ok(Patterns::from_file(root.join(name), cone)?.expect("present"))
+}
+
+/// Return the index in the format of `git ls-files -t`.
+fn ls_files(index: &State) -> String {
+    index
+        .entries()
+        .iter()
+        .map(|entry| {
+            let tag = if entry.flags.contains(entry::Flags::SKIP_WORKTREE) {
+                'S'
+            } else {
+                'H'
+            };
+            format!("{} {}\n", tag, entry.path)
+        })
+        .collect()
+}
+
+/// Return the files in `dir` in the format of `find . -type f | sort`.
+fn files(root: &Path) -> crate::Result<String> {
+    let mut out = Vec::new();
+    let mut dirs = vec![root.to_owned()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                out.push(format!("./{}\n", path.strip_prefix(root)?.display()));
+            }
+        }
+    }
+    out.sort();
+    Ok(out.concat())
+}
+
+fn expected(root: &Path, name: &str) -> crate::Result<String> {
+    Ok(std::fs::read_to_string(root.join(name))?)
+}
+
+#[test]
+fn cone_and_full_patterns_are_applied_like_git() -> crate::Result {
+    let (root, mut index, odb) = fixture()?;
+    let tmp = TempDir::new()?;
+    checkout(&mut index, tmp.path(), &odb, None, options())?;
+
+    let cone = patterns(&root, "cone-patterns", true)?;
+    assert!(matches!(cone, Patterns::Cone(_)));
+    let outcome = sparse::apply(&mut index, tmp.path(), &cone, &odb, None, options())?;
+    assert_eq!(outcome.excluded, vec!["a/d/file", "g/file", "g/keep.txt"]);
+    assert_eq!(outcome.included, Vec::<BString>::new());
+    assert_eq!(ls_files(&index), expected(&root, "cone.expected")?);
+    assert_eq!(files(tmp.path())?, expected(&root, "cone-worktree.expected")?);
+
+    let full = patterns(&root, "full-patterns", false)?;
+    let outcome = sparse::apply(&mut index, tmp.path(), &full, &odb, None, options())?;
+    assert_eq!(outcome.excluded, vec!["a/file", "e/f/file"]);
+    assert_eq!(outcome.included, vec!["g/file", "g/keep.txt"]);
+    assert_eq!(outcome.checkout.files_written, 2);
+    assert_eq!(ls_files(&index), expected(&root, "full.expected")?);
+    assert_eq!(files(tmp.path())?, expected(&root, "full-worktree.expected")?);
+
+    let outcome = sparse::apply(&mut index, tmp.path(), &full, &odb, None, options())?;
+    assert_eq!(
+        outcome,
+        sparse::Outcome::default(),
+        "applying the same patterns again does nothing"
+    );
+    Ok(())
+}
+
+#[test]
+fn modified_files_are_kept() -> crate::Result {
+    let (root, mut index, odb) = fixture()?;
+    let tmp = TempDir::new()?;
+    checkout(&mut index, tmp.path(), &odb, None, options())?;
+    std::fs::write(tmp.path().join("g/file"), "modified")?;
+
+    let outcome = sparse::apply(
+        &mut index,
+        tmp.path(),
+        &patterns(&root, "cone-patterns", true)?,
+        &odb,
+        None,
+        options(),
+    )?;
+    assert_eq!(outcome.excluded, vec!["a/d/file", "g/keep.txt"]);
+    assert_eq!(outcome.kept, vec!["g/file"]);
+    assert_eq!(std::fs::read(tmp.path().join("g/file"))?, b"modified");
+    assert!(!tmp.path().join("a/d").exists(), "empty directories are removed");
+    Ok(())
+}
+
+#[test]
+fn cone_patterns_are_written_like_git() -> crate::Result {
+    let (root, _, _) = fixture()?;
+    let cone = Cone::from_dirs(vec!["e/f/", "a/b", "a/b/c"]);
+    assert_eq!(cone.to_bstring(), std::fs::read(root.join("cone-patterns"))?.as_bstr());
+    assert_eq!(
+        patterns(&root, "cone-patterns", true)?,
+        Patterns::Cone(Cone::from_dirs(vec!["a/b", "e/f"]))
+    );
+
+    let cone = Cone::from_dirs(vec!["with [special] chars*"]);
+    assert_eq!(cone.to_bstring(), "/*\n!/*/\n/with \\[special] chars\\*/\n");
+    assert_eq!(Patterns::from_bytes(&cone.to_bstring(), true), Patterns::Cone(cone));
+    Ok(())
+}
+
+#[test]
+fn invalid_cone_patterns_are_used_in_full() -> crate::Result {
+    let (root, _, _) = fixture()?;
+    assert!(matches!(patterns(&root, "full-patterns", true)?, Patterns::Full(_)));
+    for invalid in &["/a/*.txt/", "a/", "/a", "!/a/", "/a/**/"] {
+        assert!(
+            matches!(Patterns::from_bytes(invalid.as_bytes(), true), Patterns::Full(_)),
+            "{}",
+            invalid
+        );
+    }
+    let cone = Patterns::from_bytes(b"/*\n!/*/\n/a/\n!/a/*/\n/a/b/\n", true);
+    for (path, is_included) in &[
+        ("root", true),
+        ("a/file", true),
+        ("a/c/file", false),
+        ("a/b/file", true),
+        ("a/b/c/file", true),
+        ("A/B/file", false),
+    ] {
+        assert_eq!(
+            cone.is_included(path.as_bytes().as_bstr(), Case::Sensitive),
+            *is_included,
+            "{}",
+            path
+        );
+    }
+    assert!(cone.is_included("A/B/file".as_bytes().as_bstr(), Case::Fold));
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use std::path::{Path, PathBuf};
+
+use git_glob::pattern::Case;
+use git_hash::ObjectId;
+use git_index::State;
+use git_object::bstr::ByteSlice;
+use git_odb::{linked, pack, FindExt};
+use git_worktree::{
+    ignore,
+    status::{self, index_worktree, tree_index, untracked, Options, Untracked},
+};
+
+fn fixture() -> crate::Result<PathBuf> {
+    git_testtools::scripted_fixture_repo_read_only("make_status_repo.sh")
+}
+
+fn head_state(root: &Path) -> crate::Result<State> {
+    let repo = root.join("repo");
+    let db = linked::Store::at(repo.join(".git/objects"))?;
+    let tree = ObjectId::from_hex(std::fs::read(root.join("head-tree"))?.trim())?;
+    Ok(State::from_tree(&tree, |oid, buf| {
+        db.find_existing_tree_iter(oid, buf, &mut pack::cache::Never).ok()
+    })?)
+}
+
+fn porcelain_v2(root: &Path, options: Options) -> crate::Result<String> {
+    let repo = root.join("repo");
+    let index = git_index::File::at(repo.join(".git/index"))?;
+    let mut stack = ignore::Stack::from_git_dir(&repo, &repo.join(".git"), None, Case::Sensitive)?;
+    let mut out = Vec::new();
+    for entry in status::status(&head_state(root)?, &index, &repo, None, options, |path, is_dir| {
+        stack.is_ignored(path, is_dir)
+    })? {
+        entry.write_porcelain_v2(&mut out)?;
+    }
+    Ok(String::from_utf8(out)?)
+}
+
+fn expected(root: &Path, name: &str) -> crate::Result<String> {
+    Ok(std::fs::read_to_string(root.join(name))?)
+}
+
+#[test]
+fn porcelain_v2_matches_git() -> crate::Result {
+    let root = fixture()?;
+    assert_eq!(
+        porcelain_v2(&root, Options::default())?,
+        expected(&root, "status.expected")?
+    );
+    Ok(())
+}
+
+#[test]
+fn porcelain_v2_with_ignored_files_matches_git() -> crate::Result {
+    let root = fixture()?;
+    let options = Options {
+        ignored: true,
+        ..Default::default()
+    };
+    assert_eq!(
+        porcelain_v2(&root, options)?,
+        expected(&root, "status-ignored.expected")?
+    );
+    Ok(())
+}
+
+#[test]
+fn porcelain_v2_with_all_untracked_files_matches_git() -> crate::Result {
+    let root = fixture()?;
+    let options = Options {
+        untracked: Untracked::All,
+        thread_limit: Some(1),
+        ..Default::default()
+    };
+    assert_eq!(porcelain_v2(&root, options)?, expected(&root, "status-all.expected")?);
+    Ok(())
+}
+
+#[test]
+fn renames_are_additions_and_deletions_without_tracking() -> crate::Result {
+    let root = fixture()?;
+    let index = git_index::File::at(root.join("repo/.git/index"))?;
+    let changes = tree_index::changes(&head_state(&root)?, &index, false);
+    let paths: Vec<_> = changes
+        .iter()
+        .map(|change| {
+            let kind = match change {
+                tree_index::Change::Added { .. } => "A",
+                tree_index::Change::Deleted { .. } => "D",
+                tree_index::Change::Modified { .. } => "M",
+                tree_index::Change::Renamed { .. } => "R",
+                tree_index::Change::Conflicted { .. } => "U",
+            };
+            format!("{} {}", kind, change.path())
+        })
+        .collect();
+    assert_eq!(
+        paths,
+        vec![
+            "A added",
+            "U conflict",
+            "M modified-staged",
+            "D renamed-from",
+            "A renamed-to",
+            "D staged-delete"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn index_worktree_changes_without_executable_bit() -> crate::Result {
+    let root = fixture()?;
+    let repo = root.join("repo");
+    let index = git_index::File::at(repo.join(".git/index"))?;
+    let options = Options {
+        check_executable_bit: false,
+        ..Default::default()
+    };
+    let changes = index_worktree::changes(&index, &repo, None, &options)?;
+    let paths: Vec<_> = changes.iter().map(|change| change.path().to_string()).collect();
+    assert_eq!(
+        paths,
+        vec!["a", "conflict", "deleted", "intent", "modified-staged", "type-change"],
+        "the executable bit of 'd/b' isn't considered"
+    );
+    Ok(())
+}
+
+#[test]
+fn untracked_files_are_not_collected_if_disabled() -> crate::Result {
+    let root = fixture()?;
+    let index = git_index::File::at(root.join("repo/.git/index"))?;
+    let outcome = untracked::collect(&index, &root.join("repo"), Untracked::No, |_, _| {
+        unreachable!("no untracked files are checked")
+    })?;
+    assert_eq!(outcome, untracked::Outcome::default());
+    Ok(())
+}
@@ -0,0 +1,8 @@
+pub type Result<T = ()> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+mod attributes;
+mod checkout;
+mod filter;
+mod ignore;
+mod sparse;
+mod status;
@@ -0,0 +1,172 @@
+use anyhow::{anyhow, bail, Context as AnyhowContext, Result};
+use git_config::{
+    file::{persist, GitConfig},
+    parser::{Key, SectionHeaderName},
+};
+use std::{borrow::Cow, io, path::Path};
+
+/// What to do with the configuration file, similar to the options of `git config --file`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Print the value of `key`.
+    Get { key: String },
+    /// Set the value of `key` to `value`, or add it if it doesn't exist yet. `key` must not have multiple values.
+    Set { key: String, value: String },
+    /// Add `value` to `key`, even if `key` already has values.
+    Add { key: String, value: String },
+    /// Remove the value of `key`, which must not have multiple values.
+    Unset { key: String },
+    /// Remove all values of `key`.
+    UnsetAll { key: String },
+    /// Rename all sections named `old` to `new`, both of the form `section[.subsection]`.
+    RenameSection { old: String, new: String },
+}
+
+impl Action {
+    /// Derive the action from the flags and arguments of the `config` subcommand, which are used like the ones of
+    /// `git config`. At most one flag may be set.
+    pub fn from_args(
+        add: bool,
+        unset: bool,
+        unset_all: bool,
+        rename_section: bool,
+        name: String,
+        value: Option<String>,
+    ) -> Result<Self> {
+        if [add, unset, unset_all, rename_section]
+            .iter()
+            .filter(|flag| **flag)
+            .count()
+            > 1
+        {
+            bail!("Only one of --add, --unset, --unset-all and --rename-section may be given");
+        }
+        Ok(match value {
+            Some(value) if add => Action::Add { key: name, value },
+            Some(new) if rename_section => Action::RenameSection { old: name, new },
+            Some(_) if unset || unset_all => bail!("--unset and --unset-all don't take a value"),
+            Some(value) => Action::Set { key: name, value },
+            None if add || rename_section => bail!("--add and --rename-section require a value"),
+            None if unset => Action::Unset { key: name },
+            None if unset_all => Action::UnsetAll { key: name },
+            None => Action::Get { key: name },
+        })
+    }
+}
+
+/// Apply `action` to the configuration file at `path`, printing values to `out`.
+///
+/// Changes are written back to the file while holding its lock, preserving comments and whitespace.
+pub fn edit(path: impl AsRef<Path>, action: Action, mut out: impl io::Write) -> Result<()> {
+    let path = path.as_ref();
+    let mut config = if path.is_file() || matches!(action, Action::Get { .. }) {
+        GitConfig::open(path)?
+    } else {
+        GitConfig::new()
+    };
+    match action {
+        Action::Get { key } => {
+            let (section, subsection, name) = parse_key(&key)?;
+            let value = config
+                .get_raw_value(section, subsection, name)
+                .map_err(|_| anyhow!("The key '{}' is not set", key))?;
+            out.write_all(&value)?;
+            writeln!(out)?;
+            return Ok(());
+        }
+        Action::Set { key: name, value } => {
+            let (section, subsection, key) = parse_key(&name)?;
+            if config
+                .get_raw_multi_value(section, subsection, key)
+                .map_or(false, |values| values.len() > 1)
+            {
+                bail!("Cannot overwrite the multiple values of '{}' with a single value", name);
+            }
+            if config
+                .set_raw_value(section, subsection, key, value.clone().into_bytes())
+                .is_err()
+            {
+                add(&mut config, section, subsection, key, value);
+            }
+        }
+        Action::Add { key, value } => {
+            let (section, subsection, key) = parse_key(&key)?;
+            add(&mut config, section, subsection, key, value);
+        }
+        Action::Unset { key } => {
+            let (section, subsection, name) = parse_key(&key)?;
+            let mut values = config
+                .get_raw_multi_value_mut(section, subsection, name)
+                .map_err(|_| anyhow!("The key '{}' is not set", key))?;
+            if values.len() > 1 {
+                bail!("The key '{}' has multiple values", key);
+            }
+            values.delete_all();
+        }
+        Action::UnsetAll { key } => {
+            let (section, subsection, name) = parse_key(&key)?;
+            config
+                .get_raw_multi_value_mut(section, subsection, name)
+                .map_err(|_| anyhow!("The key '{}' is not set", key))?
+                .delete_all();
+        }
+        Action::RenameSection { old, new } => {
+            let (section, subsection) = parse_section(&old)?;
+            let (new_section, new_subsection) = parse_section(&new)?;
+            let count = config
+                .sections_by_name_with_header(section)
+                .iter()
+                .filter(|(header, _)| header.subsection_name.as_deref() == subsection)
+                .count();
+            if count == 0 {
+                bail!("There is no section named '{}'", old);
+            }
+            for _ in 0..count {
+                config
+                    .rename_section(
+                        section,
+                        subsection,
+                        SectionHeaderName(Cow::Owned(new_section.to_owned())),
+                        new_subsection.map(|s| Cow::Owned(s.to_owned())),
+                    )
+                    .expect("as many sections as counted");
+            }
+        }
+    }
+    config
+        .write_to_path(path, persist::Fail::Immediately)
+        .with_context(|| format!("Could not write configuration to '{}'", path.display()))
+}
+
+fn add(config: &mut GitConfig<'_>, section: &str, subsection: Option<&str>, key: &str, value: String) {
+    let key = Key(Cow::Owned(key.to_owned()));
+    let value = Cow::Owned(value.into_bytes());
+    match config.section_mut(section, subsection) {
+        Ok(mut existing) => existing.push(key, value),
+        Err(_) => config
+            .new_section(section.to_owned(), subsection.map(|s| Cow::Owned(s.to_owned())))
+            .push(key, value),
+    }
+}
+
+/// Split `name` of the form `section[.subsection].key` into its parts.
+fn parse_key(name: &str) -> Result<(&str, Option<&str>, &str)> {
+    match (name.find('.'), name.rfind('.')) {
+        (Some(first), Some(last)) if first > 0 && last + 1 < name.len() => Ok((
+            &name[..first],
+            (first != last).then(|| &name[first + 1..last]),
+            &name[last + 1..],
+        )),
+        _ => bail!("'{}' is not of the form 'section[.subsection].key'", name),
+    }
+}
+
+/// Split `name` of the form `section[.subsection]` into its parts.
+fn parse_section(name: &str) -> Result<(&str, Option<&str>)> {
+    match name.find('.') {
+        Some(0) => bail!("'{}' is not of the form 'section[.subsection]'", name),
+        Some(pos) => Ok((&name[..pos], Some(&name[pos + 1..]))),
+        None if !name.is_empty() => Ok((name, None)),
+        None => bail!("'{}' is not of the form 'section[.subsection]'", name),
+    }
+}
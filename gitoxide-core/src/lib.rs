@@ -38,6 +38,7 @@ impl FromStr for OutputFormat {
 pub mod net;
 
 pub mod commitgraph;
+pub mod config;
 #[cfg(feature = "estimate-hours")]
 pub mod hours;
 #[cfg(feature = "organize")]
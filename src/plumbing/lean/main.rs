@@ -212,5 +212,18 @@ pub fn main() -> Result<()> {
             )
             .map(|_| ())
         }
+        SubCommands::Config(options::Config {
+            file,
+            add,
+            unset,
+            unset_all,
+            rename_section,
+            name,
+            value,
+        }) => core::config::edit(
+            file,
+            core::config::Action::from_args(add, unset, unset_all, rename_section, name, value)?,
+            stdout(),
+        ),
     }
 }
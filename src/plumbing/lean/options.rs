@@ -37,6 +37,7 @@ pub enum SubCommands {
     #[cfg(any(feature = "gitoxide-core-async-client", feature = "gitoxide-core-blocking-client"))]
     PackReceive(PackReceive),
     CommitGraphVerify(CommitGraphVerify),
+    Config(Config),
 }
 
 /// Create an index from a packfile.
@@ -246,3 +247,39 @@ pub struct CommitGraphVerify {
     #[argh(switch, short = 's')]
     pub statistics: bool,
 }
+
+/// Query and edit a configuration file, preserving its comments and whitespace.
+///
+/// This is the plumbing equivalent of `git config --file`. Without flags, the value of the given key is printed,
+/// or set if a value is given.
+#[derive(FromArgs, PartialEq, Debug)]
+#[argh(subcommand, name = "config")]
+pub struct Config {
+    /// the configuration file to read and edit.
+    #[argh(option)]
+    pub file: PathBuf,
+
+    /// add the value to the key, even if it already has values.
+    #[argh(switch)]
+    pub add: bool,
+
+    /// remove the value of the key, which fails if it has multiple values.
+    #[argh(switch)]
+    pub unset: bool,
+
+    /// remove all values of the key.
+    #[argh(switch)]
+    pub unset_all: bool,
+
+    /// rename the section given by name to the one given by value, both of the form 'section[.subsection]'.
+    #[argh(switch)]
+    pub rename_section: bool,
+
+    /// the key of the form 'section[.subsection].key', or the section to rename.
+    #[argh(positional)]
+    pub name: String,
+
+    /// the value to set or add, or the new name of the section to rename.
+    #[argh(positional)]
+    pub value: Option<String>,
+}
@@ -242,6 +242,28 @@ pub fn main() -> Result<()> {
             },
         )
         .map(|_| ()),
+        Subcommands::Config {
+            file,
+            add,
+            unset,
+            unset_all,
+            rename_section,
+            name,
+            value,
+        } => prepare_and_run(
+            "config",
+            verbose,
+            progress,
+            progress_keep_open,
+            None,
+            move |_progress, out, _err| {
+                core::config::edit(
+                    file,
+                    core::config::Action::from_args(add, unset, unset_all, rename_section, name, value)?,
+                    out,
+                )
+            },
+        ),
     }?;
     Ok(())
 }
@@ -235,4 +235,37 @@ pub enum Subcommands {
         #[clap(long, short = 's')]
         statistics: bool,
     },
+    /// Query and edit a configuration file, preserving its comments and whitespace.
+    ///
+    /// This is the plumbing equivalent of `git config --file`. Without flags, the value of the given key is printed,
+    /// or set if a value is given.
+    #[clap(setting = AppSettings::ColoredHelp)]
+    #[clap(setting = AppSettings::DisableVersion)]
+    Config {
+        /// The configuration file to read and edit.
+        #[clap(long, parse(from_os_str))]
+        file: PathBuf,
+
+        /// Add the value to the key, even if it already has values.
+        #[clap(long, conflicts_with_all(&["unset", "unset-all", "rename-section"]))]
+        add: bool,
+
+        /// Remove the value of the key, which fails if it has multiple values.
+        #[clap(long, conflicts_with_all(&["unset-all", "rename-section"]))]
+        unset: bool,
+
+        /// Remove all values of the key.
+        #[clap(long, conflicts_with("rename-section"))]
+        unset_all: bool,
+
+        /// Rename the section given by name to the one given by value, both of the form 'section[.subsection]'.
+        #[clap(long)]
+        rename_section: bool,
+
+        /// The key of the form 'section[.subsection].key', or the section to rename.
+        name: String,
+
+        /// The value to set or add, or the new name of the section to rename.
+        value: Option<String>,
+    },
 }
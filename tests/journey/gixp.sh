@@ -494,3 +494,53 @@ title "gixp commit-graph-verify"
     )
   )
 )
+title "gixp config"
+(when "running 'config'"
+  snapshot="$snapshot/config"
+  (sandbox
+    (with "a configuration file with comments"
+      printf '# a comment\n[core]\n\tbare = false ; keep this\n\n[remote "origin"]\n\turl = https://example.com/repo\n' > config
+      it "prints the value of a key" && {
+        WITH_SNAPSHOT="$snapshot/get-success" \
+        expect_run $SUCCESSFULLY "$exe_plumbing" config --file config remote.origin.url
+      }
+      it "fails to print the value of a key that isn't set" && {
+        WITH_SNAPSHOT="$snapshot/get-failure" \
+        expect_run $WITH_FAILURE "$exe_plumbing" config --file config core.missing
+      }
+      (with "values being added and set, and a section being renamed"
+        "$exe_plumbing" config --file config --add remote.origin.push refs/heads/main
+        "$exe_plumbing" config --file config --add remote.origin.push refs/heads/next
+        "$exe_plumbing" config --file config --rename-section remote.origin remote.upstream
+        "$exe_plumbing" config --file config user.name name
+        it "changes only the affected lines" && {
+          WITH_SNAPSHOT="$snapshot/edited-config" \
+          expect_run $SUCCESSFULLY cat config
+        }
+        it "refuses to set a key with multiple values" && {
+          WITH_SNAPSHOT="$snapshot/set-failure" \
+          expect_run $WITH_FAILURE "$exe_plumbing" config --file config remote.upstream.push refs/heads/other
+        }
+        it "refuses to unset a key with multiple values" && {
+          WITH_SNAPSHOT="$snapshot/unset-failure" \
+          expect_run $WITH_FAILURE "$exe_plumbing" config --file config --unset remote.upstream.push
+        }
+        it "unsets all values of a key" && {
+          expect_run $SUCCESSFULLY "$exe_plumbing" config --file config --unset-all remote.upstream.push
+        }
+        it "can be read by git" && {
+          WITH_SNAPSHOT="$snapshot/git-config-list" \
+          expect_run $SUCCESSFULLY git config --file config --list
+        }
+      )
+    )
+    (with "a section appearing multiple times"
+      printf '[a]\n\tx = 1\n[b]\n\ty = 2\n[a]\n\tx = 3\n[a "sub"]\n\tz = 4\n' > config
+      "$exe_plumbing" config --file config --rename-section a c
+      it "renames all of them" && {
+        WITH_SNAPSHOT="$snapshot/renamed-sections" \
+        expect_run $SUCCESSFULLY cat config
+      }
+    )
+  )
+)